@@ -0,0 +1,76 @@
+#![no_main]
+
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+use mrvn_back_ytdl::{
+    classify_term, Error, LocalLibrary, MediaServerConfig, MediaServerKind, PlayConfig,
+    TermSource, LIBRARY_URL_PREFIX, LOCAL_SEARCH_PREFIX,
+};
+use std::time::Duration;
+
+#[derive(Debug, Arbitrary)]
+struct Input<'a> {
+    term: &'a str,
+    host_blocklist: Vec<String>,
+    media_server_url: Option<&'a str>,
+    is_jellyfin: bool,
+    has_library: bool,
+}
+
+fuzz_target!(|input: Input| {
+    let library = LocalLibrary::new("library", false);
+    let config = PlayConfig {
+        search_prefix: "ytsearch",
+        music_mode_search_prefix: None,
+        host_blocklist: &input.host_blocklist,
+        ytdl_name: "youtube-dl",
+        ytdl_args: &[],
+        ffmpeg_name: "ffmpeg",
+        ffmpeg_args: &[],
+        normalization: None,
+        tts: None,
+        duck_volume: 1.,
+        duck_fade: Duration::ZERO,
+        media_server: input.media_server_url.map(|url| MediaServerConfig {
+            kind: if input.is_jellyfin {
+                MediaServerKind::Jellyfin
+            } else {
+                MediaServerKind::Subsonic
+            },
+            url,
+            username: "",
+            password: "",
+            api_key: "",
+            search_by_default: false,
+        }),
+        library: input.has_library.then_some(&library),
+    };
+
+    match classify_term(input.term, &config) {
+        Ok(TermSource::LibrarySong(relative_path)) => {
+            assert!(input.has_library);
+            assert_eq!(input.term.strip_prefix(LIBRARY_URL_PREFIX), Some(relative_path));
+        }
+        Ok(TermSource::MediaServerSong(id)) => {
+            assert!(input.media_server_url.is_some());
+            assert!(!id.is_empty() && input.term.contains(id));
+        }
+        Ok(TermSource::Search {
+            query,
+            is_local_search: true,
+        }) => {
+            assert!(input.term.starts_with(LOCAL_SEARCH_PREFIX));
+            assert_eq!(query, query.trim());
+        }
+        Ok(TermSource::Search {
+            query,
+            is_local_search: false,
+        }) => {
+            assert_eq!(query, input.term);
+            assert!(url::Url::parse(input.term).is_err());
+        }
+        Ok(TermSource::Url) => assert!(url::Url::parse(input.term).is_ok()),
+        Err(Error::UnsupportedUrl) => assert!(!input.host_blocklist.is_empty()),
+        Err(why) => panic!("Unexpected error classifying {:?}: {}", input.term, why),
+    }
+});
@@ -0,0 +1,20 @@
+#![no_main]
+
+// The frontend is a binary, so the template code is included straight from its source.
+#[path = "../../mrvn-front-discord/src/template.rs"]
+mod template;
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (&str, Vec<(&str, &str)>)| {
+    let (message_template, substitutions) = input;
+    let message = template::fill_template(message_template, &substitutions);
+
+    for placeholder in template::template_placeholders(message_template) {
+        assert!(message_template.contains(&format!("{{{}}}", placeholder)));
+    }
+    // Templates without placeholders come out as they went in.
+    if !message_template.contains('{') {
+        assert_eq!(message, message_template);
+    }
+});
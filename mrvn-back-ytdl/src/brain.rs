@@ -1,19 +1,76 @@
-use crate::{GuildSpeakerHandle, GuildSpeakerRef, SongMetadata, Speaker};
-use futures::prelude::*;
+use crate::{GuildSpeakerHandle, GuildSpeakerRef, SongMetadata, Speaker, SpeakerEvent};
+use futures::future::BoxFuture;
+use futures::Stream;
 use serenity::model::prelude::*;
 use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+// Subscribers that fall further behind than this will miss events.
+const SPEAKER_EVENT_BUFFER_SIZE: usize = 64;
+
+type ChannelFilter = dyn Fn(GuildId, ChannelId) -> bool + Send + Sync;
+type SpeakerFilter = dyn Fn(GuildId, usize) -> BoxFuture<'static, bool> + Send + Sync;
 
 pub struct Brain {
     pub speakers: Vec<Arc<Speaker>>,
+    pub(crate) events: broadcast::Sender<SpeakerEvent>,
+    channel_filter: Option<Arc<ChannelFilter>>,
+    speaker_filter: Option<Arc<SpeakerFilter>>,
 }
 
 impl Brain {
     pub fn new() -> Self {
+        let (events, _) = broadcast::channel(SPEAKER_EVENT_BUFFER_SIZE);
         Brain {
             speakers: Vec::new(),
+            events,
+            channel_filter: None,
+            speaker_filter: None,
         }
     }
 
+    pub fn subscribe_events(&self) -> broadcast::Receiver<SpeakerEvent> {
+        self.events.subscribe()
+    }
+
+    /// Every event from every speaker from now on. Events are skipped if the stream isn't read
+    /// quickly enough, which is logged with `name` to say what fell behind.
+    pub fn event_stream(&self, name: &'static str) -> impl Stream<Item = SpeakerEvent> {
+        futures::stream::unfold(self.subscribe_events(), move |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("{} fell behind, skipped {} events", name, skipped);
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
+    /// Limits which voice channels speakers can be found to play in, e.g. to keep them out of
+    /// channels a guild has marked as off-limits. The filter returns `false` for channels that
+    /// aren't allowed.
+    pub fn set_channel_filter(
+        &mut self,
+        filter: impl Fn(GuildId, ChannelId) -> bool + Send + Sync + 'static,
+    ) {
+        self.channel_filter = Some(Arc::new(filter));
+    }
+
+    /// Limits which speakers can be picked to start playing in a guild, e.g. when another process
+    /// could be using the same bot there. The filter is given the speaker's index in `speakers`,
+    /// and is only asked about a speaker once it's been picked, so it can claim it. The filter runs
+    /// while the guild's speakers are locked, so it should give up quickly if it has to wait.
+    pub fn set_speaker_filter(
+        &mut self,
+        filter: impl Fn(GuildId, usize) -> BoxFuture<'static, bool> + Send + Sync + 'static,
+    ) {
+        self.speaker_filter = Some(Arc::new(filter));
+    }
+
     pub fn guild_speakers(&self, guild_id: GuildId) -> BrainSpeakersHandle {
         let guild_speaker_handles: Vec<_> = self
             .speakers
@@ -22,7 +79,10 @@ impl Brain {
             .collect();
 
         BrainSpeakersHandle {
+            guild_id,
             guild_speaker_handles,
+            channel_filter: self.channel_filter.clone(),
+            speaker_filter: self.speaker_filter.clone(),
         }
     }
 }
@@ -34,23 +94,44 @@ impl Default for Brain {
 }
 
 pub struct BrainSpeakersHandle {
+    guild_id: GuildId,
     guild_speaker_handles: Vec<GuildSpeakerHandle>,
+    channel_filter: Option<Arc<ChannelFilter>>,
+    speaker_filter: Option<Arc<SpeakerFilter>>,
 }
 
 impl BrainSpeakersHandle {
     pub async fn lock(&self) -> BrainSpeakersRef<'_> {
-        let guild_speaker_refs = future::join_all(
-            self.guild_speaker_handles
-                .iter()
-                .map(|handle| handle.lock()),
-        )
-        .await;
-        BrainSpeakersRef { guild_speaker_refs }
+        // Locking one at a time in the same order everywhere means two tasks can't each end up
+        // holding a speaker the other is waiting on, which matters once events come in from many
+        // shards at once.
+        let mut guild_speaker_refs = Vec::with_capacity(self.guild_speaker_handles.len());
+        for handle in &self.guild_speaker_handles {
+            guild_speaker_refs.push(handle.lock().await);
+        }
+        BrainSpeakersRef {
+            guild_id: self.guild_id,
+            guild_speaker_refs,
+            channel_filter: self.channel_filter.as_deref(),
+            speaker_filter: self.speaker_filter.as_deref(),
+        }
     }
 }
 
+/// Why a speaker couldn't be found to play in a channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FindSpeakerError {
+    /// The channel has been marked as off-limits.
+    ChannelNotAllowed,
+    /// Every speaker is busy in another channel.
+    NoneAvailable,
+}
+
 pub struct BrainSpeakersRef<'handle> {
+    guild_id: GuildId,
     guild_speaker_refs: Vec<GuildSpeakerRef<'handle>>,
+    channel_filter: Option<&'handle ChannelFilter>,
+    speaker_filter: Option<&'handle SpeakerFilter>,
 }
 
 impl<'handle> BrainSpeakersRef<'handle> {
@@ -71,39 +152,81 @@ impl<'handle> BrainSpeakersRef<'handle> {
         None
     }
 
-    pub fn find_to_play_in_channel(
+    pub fn find_in_channel(
         &mut self,
         channel_id: ChannelId,
     ) -> Option<&mut GuildSpeakerRef<'handle>> {
+        self.guild_speaker_refs
+            .iter_mut()
+            .find(|guild_speaker| guild_speaker.current_channel() == Some(channel_id))
+    }
+
+    pub async fn find_to_play_in_channel(
+        &mut self,
+        channel_id: ChannelId,
+    ) -> Result<&mut GuildSpeakerRef<'handle>, FindSpeakerError> {
+        if let Some(channel_filter) = self.channel_filter {
+            if !channel_filter(self.guild_id, channel_id) {
+                return Err(FindSpeakerError::ChannelNotAllowed);
+            }
+        }
+
         // Look for a speaker already in the channel
         // The weird way of doing this is a workaround for
         // https://users.rust-lang.org/t/solved-borrow-doesnt-drop-returning-this-value-requires-that/24182
         let already_in_channel_index = self
-            .guild_speaker_refs
-            .iter()
-            .position(|guild_speaker| guild_speaker.current_channel() == Some(channel_id));
+            .position_allowed(|guild_speaker| guild_speaker.current_channel() == Some(channel_id))
+            .await;
         if let Some(index) = already_in_channel_index {
-            return Some(&mut self.guild_speaker_refs[index]);
+            return Ok(&mut self.guild_speaker_refs[index]);
+        }
+
+        // Look for the speaker that last played in the channel, if it's free, so the same bot
+        // keeps being used and any permissions given to it in the channel still apply
+        let last_in_channel_index = self
+            .position_allowed(|guild_speaker| {
+                guild_speaker.last_channel() == Some(channel_id)
+                    && (guild_speaker.current_channel().is_none() || !guild_speaker.is_active())
+            })
+            .await;
+        if let Some(index) = last_in_channel_index {
+            return Ok(&mut self.guild_speaker_refs[index]);
         }
 
         // Look for a speaker not in any channel
         let not_in_channel_index = self
-            .guild_speaker_refs
-            .iter()
-            .position(|guild_speaker| guild_speaker.current_channel().is_none());
+            .position_allowed(|guild_speaker| guild_speaker.current_channel().is_none())
+            .await;
         if let Some(index) = not_in_channel_index {
-            return Some(&mut self.guild_speaker_refs[index]);
+            return Ok(&mut self.guild_speaker_refs[index]);
         }
 
         // Look for a speaker in a different channel but not active
         let not_active_index = self
-            .guild_speaker_refs
-            .iter()
-            .position(|guild_speaker| !guild_speaker.is_active());
+            .position_allowed(|guild_speaker| !guild_speaker.is_active())
+            .await;
         if let Some(index) = not_active_index {
-            return Some(&mut self.guild_speaker_refs[index]);
+            return Ok(&mut self.guild_speaker_refs[index]);
         }
 
+        Err(FindSpeakerError::NoneAvailable)
+    }
+
+    /// Finds the first speaker matching `predicate` that the speaker filter allows. The filter is
+    /// only asked about speakers that match, so only the one that's picked is asked about.
+    async fn position_allowed(
+        &self,
+        predicate: impl Fn(&GuildSpeakerRef<'handle>) -> bool,
+    ) -> Option<usize> {
+        for (index, guild_speaker) in self.guild_speaker_refs.iter().enumerate() {
+            if !predicate(guild_speaker) {
+                continue;
+            }
+            match self.speaker_filter {
+                Some(speaker_filter) if !speaker_filter(self.guild_id, index).await => {}
+                _ => return Some(index),
+            }
+        }
         None
     }
 }
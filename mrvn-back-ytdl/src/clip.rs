@@ -0,0 +1,69 @@
+use crate::song::DEFAULT_FFMPEG_ARGS;
+use crate::{Error, PlayConfig, TtsConfig};
+use std::process::{Command, Stdio};
+
+// Anything longer than this is cut off, so a long file can't hold up the channel.
+const MAX_CLIP_SECS: &str = "30";
+
+/// Opens a short audio clip, from either a local file or a direct link to an audio file. Unlike
+/// songs these aren't resolved with youtube-dl, FFmpeg reads them directly.
+pub(crate) fn clip_input(
+    source: &str,
+    config: &PlayConfig<'_>,
+) -> Result<songbird::input::Input, Error> {
+    let ffmpeg = Command::new(config.ffmpeg_name)
+        .args(config.ffmpeg_args)
+        .args(&["-t", MAX_CLIP_SECS, "-i", source])
+        .args(DEFAULT_FFMPEG_ARGS)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(Error::Io)?;
+    Ok(songbird::input::Input::new(
+        true,
+        vec![ffmpeg].into(),
+        songbird::input::Codec::FloatPcm,
+        songbird::input::Container::Raw,
+        None,
+    ))
+}
+
+/// Reads text out with the text-to-speech command, decoding whatever format it writes with FFmpeg.
+pub(crate) fn tts_input(
+    text: &str,
+    tts_config: &TtsConfig<'_>,
+    config: &PlayConfig<'_>,
+) -> Result<songbird::input::Input, Error> {
+    // Song titles come from anywhere, make sure they can't be taken as an option.
+    let text = text.trim_start_matches('-');
+
+    let mut tts = Command::new(tts_config.name)
+        .args(
+            tts_config
+                .args
+                .iter()
+                .map(|arg| arg.replace("{text}", text)),
+        )
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(Error::Io)?;
+    let ffmpeg = Command::new(config.ffmpeg_name)
+        .args(config.ffmpeg_args)
+        .args(&["-i", "pipe:0"])
+        .args(DEFAULT_FFMPEG_ARGS)
+        .stdin(tts.stdout.take().unwrap())
+        .stderr(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(Error::Io)?;
+    Ok(songbird::input::Input::new(
+        true,
+        vec![tts, ffmpeg].into(),
+        songbird::input::Codec::FloatPcm,
+        songbird::input::Container::Raw,
+        None,
+    ))
+}
@@ -1,3 +1,13 @@
+/// What youtube-dl printed when it failed and how it exited, for working out why songs aren't
+/// loading.
+#[derive(Debug)]
+pub struct ExtractorFailure {
+    /// The classified reason, which is never another `Error::Extractor`.
+    pub cause: Error,
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
 #[derive(Debug)]
 pub enum Error {
     Io(std::io::Error),
@@ -8,6 +18,26 @@ pub enum Error {
     SongbirdTrack(songbird::error::TrackError),
     UnsupportedUrl,
     NoDataProvided,
+    NetworkTimeout,
+    VideoUnavailable,
+    AgeRestricted,
+    GeoBlocked,
+    PrivateVideo,
+    LiveNotSupported,
+    ExtractorOutdated,
+    Extractor(Box<ExtractorFailure>),
+    /// What a media server said went wrong.
+    MediaServer(String),
+}
+
+impl Error {
+    /// Looks past the extractor output attached to youtube-dl failures to why they happened.
+    pub fn classification(&self) -> &Error {
+        match self {
+            Error::Extractor(failure) => &failure.cause,
+            err => err,
+        }
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -21,6 +51,18 @@ impl std::fmt::Display for Error {
             Error::SongbirdTrack(err) => err.fmt(f),
             Error::UnsupportedUrl => write!(f, "Unsupported URL"),
             Error::NoDataProvided => write!(f, "No data provided"),
+            Error::NetworkTimeout => write!(f, "Network timed out"),
+            Error::VideoUnavailable => write!(f, "Video unavailable"),
+            Error::AgeRestricted => write!(f, "Video is age restricted"),
+            Error::GeoBlocked => write!(f, "Video is blocked in this country"),
+            Error::PrivateVideo => write!(f, "Video is private"),
+            Error::LiveNotSupported => write!(f, "Live stream has not started"),
+            Error::ExtractorOutdated => write!(f, "Extractor is outdated"),
+            Error::MediaServer(message) => write!(f, "Media server error: {}", message),
+            Error::Extractor(failure) => match failure.exit_code {
+                Some(exit_code) => write!(f, "{} (exit code {})", failure.cause, exit_code),
+                None => failure.cause.fmt(f),
+            },
         }
     }
 }
@@ -1,9 +1,17 @@
 mod brain;
+mod clip;
 mod error;
+mod library;
+mod media_server;
+mod music_mode;
 mod song;
 mod speaker;
+mod voice_stats;
 
 pub use self::brain::*;
 pub use self::error::*;
+pub use self::library::*;
+pub use self::media_server::*;
 pub use self::song::*;
 pub use self::speaker::*;
+pub use self::voice_stats::VoiceStats;
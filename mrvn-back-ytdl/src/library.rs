@@ -0,0 +1,275 @@
+use crate::{Error, Song, SongMetadata};
+use lofty::{Accessor, AudioFile, ItemKey, TaggedFileExt};
+use serenity::model::prelude::UserId;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{Duration, SystemTime};
+
+/// Songs in the library link to their path in it with this in front, so they can be found again.
+pub const LIBRARY_URL_PREFIX: &str = "library:";
+
+// Files with any other extension are skipped without being opened.
+const AUDIO_EXTENSIONS: &[&str] = &[
+    "aac", "aif", "aiff", "ape", "flac", "m4a", "mp3", "mp4", "mpc", "oga", "ogg", "opus", "spx",
+    "wav", "wv",
+];
+
+#[derive(Debug, Clone)]
+struct LibraryTrack {
+    /// From the library's directory, with `/` between directories on every platform.
+    relative_path: String,
+    title: String,
+    artist: Option<String>,
+    album: Option<String>,
+    duration: Option<Duration>,
+    track_gain: Option<f32>,
+    // The title, artist, album and path in lowercase, for matching searches against.
+    search_text: String,
+}
+
+/// Music files in a local directory, indexed by their tags so they can be searched and played
+/// without going to the internet.
+pub struct LocalLibrary {
+    directory: PathBuf,
+    search_by_default: bool,
+    tracks: RwLock<Vec<LibraryTrack>>,
+    // Only one scan runs at a time, so a slow scan can't overwrite a newer one.
+    scan_lock: tokio::sync::Mutex<()>,
+}
+
+impl LocalLibrary {
+    /// The library is empty until it's scanned.
+    pub fn new(directory: impl Into<PathBuf>, search_by_default: bool) -> Self {
+        LocalLibrary {
+            directory: directory.into(),
+            search_by_default,
+            tracks: RwLock::new(Vec::new()),
+            scan_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    /// Whether every term that isn't a link should be searched for in the library first.
+    pub fn search_by_default(&self) -> bool {
+        self.search_by_default
+    }
+
+    pub fn len(&self) -> usize {
+        self.tracks.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Scans the library now, then again every `rescan_interval` if there is one, to pick up
+    /// files that were added, changed or removed.
+    pub async fn run(&self, rescan_interval: Option<Duration>) {
+        let mut maybe_interval = rescan_interval.map(tokio::time::interval);
+        loop {
+            if let Some(interval) = &mut maybe_interval {
+                interval.tick().await;
+            }
+            match self.rescan().await {
+                Ok(count) => log::info!("Indexed {} songs in the local library", count),
+                Err(why) => log::error!("Error while indexing the local library: {}", why),
+            }
+            if maybe_interval.is_none() {
+                break;
+            }
+        }
+    }
+
+    /// Reads the tags of every music file in the directory, replacing the index once they've all
+    /// been read. Returns how many songs were found.
+    pub async fn rescan(&self) -> Result<usize, Error> {
+        let _scan_guard = self.scan_lock.lock().await;
+        let directory = self.directory.clone();
+        let tracks = tokio::task::spawn_blocking(move || scan_directory(&directory))
+            .await
+            .map_err(Error::Runtime)?
+            .map_err(Error::Io)?;
+        let count = tracks.len();
+        *self.tracks.write().unwrap() = tracks;
+        Ok(count)
+    }
+
+    /// The path of the song in the library, if the link is to one.
+    pub fn song_path_from_url<'u>(&self, url: &'u str) -> Option<&'u str> {
+        url.strip_prefix(LIBRARY_URL_PREFIX)
+    }
+
+    /// Finds the song that best matches the query, by its title, artist, album or path. Returns
+    /// no songs if nothing matches.
+    pub fn search(&self, query: &str, user_id: UserId) -> Vec<Song> {
+        let query = query.to_lowercase();
+        let words: Vec<_> = query.split_whitespace().collect();
+        if words.is_empty() {
+            return Vec::new();
+        }
+
+        let tracks = self.tracks.read().unwrap();
+        // Songs with more of the query in their title are better matches, and an exact title is
+        // best of all. Reversed so ties go to whichever comes first by path.
+        let best_track = tracks
+            .iter()
+            .filter(|track| words.iter().all(|word| track.search_text.contains(word)))
+            .rev()
+            .max_by_key(|track| {
+                let title = track.title.to_lowercase();
+                let title_words = words.iter().filter(|word| title.contains(*word)).count();
+                (title == query, title_words)
+            });
+        best_track
+            .map(|track| self.track_song(track, user_id))
+            .into_iter()
+            .collect()
+    }
+
+    /// Looks up a song by its path in the library.
+    pub fn fetch(&self, relative_path: &str, user_id: UserId) -> Result<Song, Error> {
+        self.tracks
+            .read()
+            .unwrap()
+            .iter()
+            .find(|track| track.relative_path == relative_path)
+            .map(|track| self.track_song(track, user_id))
+            .ok_or(Error::VideoUnavailable)
+    }
+
+    fn track_song(&self, track: &LibraryTrack, user_id: UserId) -> Song {
+        let path = self.directory.join(&track.relative_path);
+        // File URLs have to be absolute, even if the library's directory isn't.
+        let download_url = std::fs::canonicalize(&path)
+            .ok()
+            .and_then(|path| url::Url::from_file_path(path).ok())
+            .map(String::from)
+            .unwrap_or_default();
+        Song::from_stream_url(
+            SongMetadata {
+                id: track.relative_path.clone(),
+                title: track.title.clone(),
+                artist: track.artist.clone(),
+                uploader: track.artist.clone(),
+                url: format!("{}{}", LIBRARY_URL_PREFIX, track.relative_path),
+                duration: track.duration,
+                site: Some("Library".to_string()),
+                view_count: None,
+                upload_date: None,
+                thumbnail: None,
+                collection: track.album.clone(),
+                track_gain: track.track_gain,
+                user_id,
+                queued_at: SystemTime::now(),
+            },
+            download_url,
+            Vec::new(),
+        )
+    }
+}
+
+fn scan_directory(directory: &Path) -> std::io::Result<Vec<LibraryTrack>> {
+    let mut tracks = Vec::new();
+    let mut pending_directories = vec![directory.to_path_buf()];
+    while let Some(current_directory) = pending_directories.pop() {
+        for entry in std::fs::read_dir(&current_directory)? {
+            let entry = entry?;
+            // Symlinked directories are skipped, since they could loop back on themselves.
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            if file_type.is_dir() {
+                pending_directories.push(path);
+            } else if is_audio_file(&path) {
+                if let Some(track) = read_track(directory, &path) {
+                    tracks.push(track);
+                }
+            }
+        }
+    }
+    tracks.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(tracks)
+}
+
+fn is_audio_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| AUDIO_EXTENSIONS.contains(&extension.to_lowercase().as_str()))
+}
+
+// Files without tags are named after the file instead, and files that can't be read at all are
+// left out.
+fn read_track(directory: &Path, path: &Path) -> Option<LibraryTrack> {
+    let relative_path = path
+        .strip_prefix(directory)
+        .ok()?
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let tagged_file = match lofty::read_from_path(path) {
+        Ok(tagged_file) => tagged_file,
+        Err(why) => {
+            log::warn!(
+                "Unable to read {} for the local library: {}",
+                relative_path,
+                why
+            );
+            return None;
+        }
+    };
+
+    let tag = tagged_file
+        .primary_tag()
+        .or_else(|| tagged_file.first_tag());
+    let title = tag
+        .and_then(|tag| tag.title())
+        .map(|title| title.into_owned())
+        .unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| relative_path.clone())
+        });
+    let artist = tag
+        .and_then(|tag| tag.artist())
+        .map(|artist| artist.into_owned());
+    let album = tag
+        .and_then(|tag| tag.album())
+        .map(|album| album.into_owned());
+    let duration = Some(tagged_file.properties().duration()).filter(|duration| !duration.is_zero());
+    let track_gain = tag
+        .and_then(|tag| tag.get_string(&ItemKey::ReplayGainTrackGain))
+        .and_then(parse_gain);
+
+    let search_text = [
+        Some(title.as_str()),
+        artist.as_deref(),
+        album.as_deref(),
+        Some(relative_path.as_str()),
+    ]
+    .iter()
+    .flatten()
+    .map(|text| text.to_lowercase())
+    .collect::<Vec<_>>()
+    .join(" ");
+    Some(LibraryTrack {
+        relative_path,
+        title,
+        artist,
+        album,
+        duration,
+        track_gain,
+        search_text,
+    })
+}
+
+// ReplayGain tags are written like "-6.48 dB".
+fn parse_gain(gain: &str) -> Option<f32> {
+    let gain = gain.trim();
+    let gain = gain
+        .strip_suffix("dB")
+        .or_else(|| gain.strip_suffix("db"))
+        .unwrap_or(gain);
+    gain.trim()
+        .parse()
+        .ok()
+        .filter(|gain: &f32| gain.is_finite())
+}
@@ -0,0 +1,329 @@
+use crate::{Error, Song, SongMetadata};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serenity::model::prelude::UserId;
+use std::time::{Duration, SystemTime};
+
+const SUBSONIC_API_VERSION: &str = "1.16.1";
+const SUBSONIC_CLIENT_NAME: &str = "mrvn";
+// Subsonic's error code for something that doesn't exist.
+const SUBSONIC_NOT_FOUND_CODE: i32 = 70;
+
+// Jellyfin measures durations in ticks of 100 nanoseconds.
+const JELLYFIN_NANOS_PER_TICK: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MediaServerKind {
+    /// Any server with the Subsonic API, like Navidrome or Airsonic.
+    Subsonic,
+    Jellyfin,
+}
+
+/// A personal media server to search and stream from, bypassing youtube-dl.
+pub struct MediaServerConfig<'s> {
+    pub kind: MediaServerKind,
+    /// Where the server is, e.g. `https://music.example.com`.
+    pub url: &'s str,
+    /// Used to sign in to Subsonic servers.
+    pub username: &'s str,
+    pub password: &'s str,
+    /// Used to sign in to Jellyfin servers.
+    pub api_key: &'s str,
+    /// Search the server for every term that isn't a link, and only fall back to youtube-dl if
+    /// nothing is found there.
+    pub search_by_default: bool,
+}
+
+impl MediaServerConfig<'_> {
+    fn base_url(&self) -> &str {
+        self.url.trim_end_matches('/')
+    }
+
+    // Songs need a link to identify them by, so they can be found again to refresh the stream or
+    // play them from a playlist.
+    fn song_url_prefix(&self) -> String {
+        match self.kind {
+            MediaServerKind::Subsonic => format!("{}/rest/getSong?id=", self.base_url()),
+            MediaServerKind::Jellyfin => {
+                format!("{}/web/index.html#!/details?id=", self.base_url())
+            }
+        }
+    }
+
+    fn song_url(&self, id: &str) -> String {
+        format!("{}{}", self.song_url_prefix(), id)
+    }
+
+    /// The ID of the song on the server, if the link is to a song on it.
+    pub fn song_id_from_url<'u>(&self, url: &'u str) -> Option<&'u str> {
+        let id = url.strip_prefix(&self.song_url_prefix())?;
+        let id = id.split('&').next().unwrap_or(id);
+        if id.is_empty() {
+            None
+        } else {
+            Some(id)
+        }
+    }
+
+    /// Finds the best match for the query in the server's library. Returns no songs if nothing
+    /// matches.
+    pub async fn search(&self, query: &str, user_id: UserId) -> Result<Vec<Song>, Error> {
+        match self.kind {
+            MediaServerKind::Subsonic => {
+                let response = self
+                    .subsonic_request(
+                        "search3",
+                        &[
+                            ("query", query),
+                            ("songCount", "1"),
+                            ("artistCount", "0"),
+                            ("albumCount", "0"),
+                        ],
+                    )
+                    .await?;
+                Ok(response
+                    .search_result3
+                    .map(|result| result.song)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|song| self.subsonic_song(song, user_id))
+                    .collect())
+            }
+            MediaServerKind::Jellyfin => Ok(self
+                .jellyfin_items(&[
+                    ("searchTerm", query),
+                    ("IncludeItemTypes", "Audio"),
+                    ("Recursive", "true"),
+                    ("Limit", "1"),
+                ])
+                .await?
+                .into_iter()
+                .map(|item| self.jellyfin_song(item, user_id))
+                .collect()),
+        }
+    }
+
+    /// Looks up a song on the server by its ID, with a fresh stream URL.
+    pub async fn fetch(&self, id: &str, user_id: UserId) -> Result<Song, Error> {
+        match self.kind {
+            MediaServerKind::Subsonic => {
+                let response = self.subsonic_request("getSong", &[("id", id)]).await?;
+                let song = response.song.ok_or(Error::VideoUnavailable)?;
+                Ok(self.subsonic_song(song, user_id))
+            }
+            MediaServerKind::Jellyfin => {
+                let item = self
+                    .jellyfin_items(&[("Ids", id)])
+                    .await?
+                    .into_iter()
+                    .next()
+                    .ok_or(Error::VideoUnavailable)?;
+                Ok(self.jellyfin_song(item, user_id))
+            }
+        }
+    }
+
+    // Subsonic signs each request with a hash of the password and a random salt, so the password
+    // itself is never sent.
+    fn subsonic_url(&self, endpoint: &str, params: &[(&str, &str)]) -> Result<url::Url, Error> {
+        let salt: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(12)
+            .map(char::from)
+            .collect();
+        let token = format!("{:x}", md5::compute(format!("{}{}", self.password, salt)));
+
+        let mut url = url::Url::parse(&format!("{}/rest/{}", self.base_url(), endpoint))
+            .map_err(|_| Error::UnsupportedUrl)?;
+        url.query_pairs_mut()
+            .append_pair("u", self.username)
+            .append_pair("t", &token)
+            .append_pair("s", &salt)
+            .append_pair("v", SUBSONIC_API_VERSION)
+            .append_pair("c", SUBSONIC_CLIENT_NAME)
+            .append_pair("f", "json")
+            .extend_pairs(params);
+        Ok(url)
+    }
+
+    async fn subsonic_request(
+        &self,
+        endpoint: &str,
+        params: &[(&str, &str)],
+    ) -> Result<SubsonicResponse, Error> {
+        let url = self.subsonic_url(endpoint, params)?;
+        let body = http_client()
+            .get(url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(Error::Http)?
+            .bytes()
+            .await
+            .map_err(Error::Http)?;
+        let envelope: SubsonicEnvelope = serde_json::from_slice(&body)
+            .map_err(|err| Error::Parse(err, String::from_utf8_lossy(&body).into_owned()))?;
+
+        let mut response = envelope.response;
+        match response.error.take() {
+            Some(error) if error.code == SUBSONIC_NOT_FOUND_CODE => Err(Error::VideoUnavailable),
+            Some(error) => Err(Error::MediaServer(
+                error
+                    .message
+                    .unwrap_or_else(|| format!("Error code {}", error.code)),
+            )),
+            None => Ok(response),
+        }
+    }
+
+    fn subsonic_song(&self, song: SubsonicSong, user_id: UserId) -> Song {
+        // The stream URL is signed like any other request, so it's only valid for this server.
+        let download_url = self
+            .subsonic_url("stream", &[("id", &song.id)])
+            .map(String::from)
+            .unwrap_or_default();
+        Song::from_stream_url(
+            SongMetadata {
+                url: self.song_url(&song.id),
+                id: song.id,
+                title: song.title,
+                artist: song.artist.clone(),
+                uploader: song.artist,
+                duration: song.duration.map(Duration::from_secs),
+                site: Some("Subsonic".to_string()),
+                view_count: None,
+                upload_date: None,
+                // Cover art needs signing in to fetch, and thumbnails are shown to everyone.
+                thumbnail: None,
+                collection: song.album_id,
+                track_gain: song
+                    .replay_gain
+                    .and_then(|replay_gain| replay_gain.track_gain),
+                user_id,
+                queued_at: SystemTime::now(),
+            },
+            download_url,
+            Vec::new(),
+        )
+    }
+
+    async fn jellyfin_items(&self, params: &[(&str, &str)]) -> Result<Vec<JellyfinItem>, Error> {
+        let body = http_client()
+            .get(format!("{}/Items", self.base_url()))
+            .query(params)
+            .header("X-Emby-Token", self.api_key)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(Error::Http)?
+            .bytes()
+            .await
+            .map_err(Error::Http)?;
+        let items: JellyfinItems = serde_json::from_slice(&body)
+            .map_err(|err| Error::Parse(err, String::from_utf8_lossy(&body).into_owned()))?;
+        Ok(items.items)
+    }
+
+    fn jellyfin_song(&self, item: JellyfinItem, user_id: UserId) -> Song {
+        let artist = item.artists.into_iter().next().or(item.album_artist);
+        Song::from_stream_url(
+            SongMetadata {
+                url: self.song_url(&item.id),
+                title: item.name,
+                artist: artist.clone(),
+                uploader: artist,
+                duration: item.run_time_ticks.map(|ticks| {
+                    Duration::from_nanos(ticks.saturating_mul(JELLYFIN_NANOS_PER_TICK))
+                }),
+                site: Some("Jellyfin".to_string()),
+                view_count: None,
+                upload_date: None,
+                thumbnail: Some(format!(
+                    "{}/Items/{}/Images/Primary",
+                    self.base_url(),
+                    item.id
+                )),
+                id: item.id.clone(),
+                collection: item.album_id,
+                track_gain: item.normalization_gain,
+                user_id,
+                queued_at: SystemTime::now(),
+            },
+            format!("{}/Audio/{}/stream?static=true", self.base_url(), item.id),
+            vec![("X-Emby-Token".to_string(), self.api_key.to_string())],
+        )
+    }
+}
+
+fn http_client() -> &'static reqwest::Client {
+    lazy_static::lazy_static! {
+        static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::builder().build().unwrap();
+    }
+    &HTTP_CLIENT
+}
+
+#[derive(serde::Deserialize)]
+struct SubsonicEnvelope {
+    #[serde(rename = "subsonic-response")]
+    response: SubsonicResponse,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubsonicResponse {
+    error: Option<SubsonicError>,
+    search_result3: Option<SubsonicSearchResult>,
+    song: Option<SubsonicSong>,
+}
+
+#[derive(serde::Deserialize)]
+struct SubsonicError {
+    code: i32,
+    message: Option<String>,
+}
+
+#[derive(serde::Deserialize)]
+struct SubsonicSearchResult {
+    #[serde(default)]
+    song: Vec<SubsonicSong>,
+}
+
+#[derive(serde::Deserialize)]
+struct SubsonicSong {
+    id: String,
+    title: String,
+    artist: Option<String>,
+    #[serde(rename = "albumId")]
+    album_id: Option<String>,
+    /// In seconds.
+    duration: Option<u64>,
+    /// Only servers with the OpenSubsonic extensions have this.
+    #[serde(rename = "replayGain")]
+    replay_gain: Option<SubsonicReplayGain>,
+}
+
+#[derive(serde::Deserialize)]
+struct SubsonicReplayGain {
+    #[serde(rename = "trackGain")]
+    track_gain: Option<f32>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JellyfinItems {
+    items: Vec<JellyfinItem>,
+}
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JellyfinItem {
+    id: String,
+    name: String,
+    #[serde(default)]
+    artists: Vec<String>,
+    album_artist: Option<String>,
+    album_id: Option<String>,
+    run_time_ticks: Option<u64>,
+    normalization_gain: Option<f32>,
+}
@@ -0,0 +1,71 @@
+// Title words that usually mean a result isn't the studio recording, unless they were searched for.
+const ALTERNATE_VERSION_WORDS: &[&str] = &[
+    "lyric",
+    "lyrics",
+    "live",
+    "concert",
+    "performance",
+    "cover",
+    "karaoke",
+    "instrumental",
+    "remix",
+    "reaction",
+    "nightcore",
+    "slowed",
+    "sped up",
+];
+
+// YouTube Music's generated channels for artists are named like "Artist - Topic".
+const TOPIC_CHANNEL_SUFFIX: &str = " - topic";
+
+/// How likely a search result is to be the official audio of a song, higher is more likely. Songs
+/// from YouTube Music come from topic channels and have track details, uploads labelled as
+/// official audio are next best, and lyric videos, live performances and the like are avoided
+/// unless the search asked for them.
+pub(crate) fn music_score(
+    query: &str,
+    title: &str,
+    channel: Option<&str>,
+    categories: &[String],
+    has_track: bool,
+) -> i32 {
+    let query = padded_words(query);
+    let title = padded_words(title);
+    let mut score = 0;
+
+    if channel.is_some_and(|channel| channel.to_lowercase().ends_with(TOPIC_CHANNEL_SUFFIX)) {
+        score += 4;
+    }
+    if has_track {
+        score += 2;
+    }
+    if categories.iter().any(|category| category == "Music") {
+        score += 1;
+    }
+
+    if title.contains(" official audio ") {
+        score += 3;
+    } else if title.contains(" audio ") {
+        score += 1;
+    }
+    for word in ALTERNATE_VERSION_WORDS {
+        let padded_word = format!(" {} ", word);
+        if title.contains(&padded_word) && !query.contains(&padded_word) {
+            score -= 3;
+        }
+    }
+    score
+}
+
+// Lowercase words with a space either side, so whole words can be found with `contains`.
+fn padded_words(text: &str) -> String {
+    let words = text
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>();
+    format!(
+        " {} ",
+        words.split_whitespace().collect::<Vec<_>>().join(" ")
+    )
+}
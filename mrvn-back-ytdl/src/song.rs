@@ -1,14 +1,24 @@
-use crate::Error;
+use crate::music_mode::music_score;
+use crate::{Error, ExtractorFailure, LocalLibrary, MediaServerConfig};
 use futures::future::{AbortHandle, Abortable};
 use serenity::model::prelude::UserId;
 use std::borrow::Cow;
+use std::cmp::Reverse;
 use std::collections::HashMap;
 use std::io::SeekFrom;
 use std::process::{Child, Command, Stdio};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::process::Command as TokioCommand;
+use std::time::{Duration, SystemTime};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::process::{Child as TokioChild, ChildStderr, Command as TokioCommand};
 
-const DEFAULT_FFMPEG_ARGS: &[&str] = &[
+// How much of what youtube-dl prints when it fails is kept with the error.
+const STDERR_SNIPPET_LENGTH: usize = 1000;
+
+/// Terms starting with this are searched for in the local library and on the media server, and
+/// never with youtube-dl.
+pub const LOCAL_SEARCH_PREFIX: &str = "local:";
+
+pub(crate) const DEFAULT_FFMPEG_ARGS: &[&str] = &[
     "-vn",
     "-f",
     "s16le",
@@ -21,43 +31,289 @@ const DEFAULT_FFMPEG_ARGS: &[&str] = &[
     "-",
 ];
 
+/// Songs can be saved and restored, e.g. to keep queues across a restart. Their download URL may
+/// have expired by then, but a new one is fetched when that happens anyway.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
 pub struct Song {
     pub metadata: SongMetadata,
     download_url: String,
     http_headers: Vec<(String, String)>,
+    start_position: Option<Duration>,
+    /// Where in the song playback stops, if it doesn't play to the end.
+    #[serde(default)]
+    end_position: Option<Duration>,
+}
+
+/// Where a term from `/play` should be looked up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TermSource<'t> {
+    /// A song in the local library, by its path in it.
+    LibrarySong(&'t str),
+    /// A song on the media server, by its ID.
+    MediaServerSong(&'t str),
+    /// Anything that isn't a link. It's searched for locally first if `is_local_search` is set or
+    /// the library or media server is searched by default, and with youtube-dl unless
+    /// `is_local_search` is set.
+    Search {
+        query: &'t str,
+        is_local_search: bool,
+    },
+    /// A link for youtube-dl to load.
+    Url,
+}
+
+/// Works out where to look for a term without looking for it yet. Links to hosts in the
+/// blocklist are turned away.
+pub fn classify_term<'t>(term: &'t str, config: &PlayConfig<'_>) -> Result<TermSource<'t>, Error> {
+    if let Some(relative_path) = config
+        .library
+        .and_then(|library| library.song_path_from_url(term))
+    {
+        return Ok(TermSource::LibrarySong(relative_path));
+    }
+    if let Some(id) = config
+        .media_server
+        .as_ref()
+        .and_then(|media_server| media_server.song_id_from_url(term))
+    {
+        return Ok(TermSource::MediaServerSong(id));
+    }
+    if let Some(query) = term.strip_prefix(LOCAL_SEARCH_PREFIX) {
+        return Ok(TermSource::Search {
+            query: query.trim(),
+            is_local_search: true,
+        });
+    }
+
+    let url = match url::Url::parse(term) {
+        Ok(url) => url,
+        Err(_) => {
+            return Ok(TermSource::Search {
+                query: term,
+                is_local_search: false,
+            })
+        }
+    };
+    if let Some(host_str) = url.host_str() {
+        // Ensure the resolved host isn't in the blocklist
+        if config
+            .host_blocklist
+            .iter()
+            .any(|domain| host_str.contains(domain))
+        {
+            return Err(Error::UnsupportedUrl);
+        }
+    }
+    Ok(TermSource::Url)
+}
+
+/// How songs are evened out so they all play at about the same loudness.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Normalization {
+    /// Applies the track gain songs are tagged with, which costs next to nothing. Songs without
+    /// it play as they are.
+    ReplayGain,
+    /// Measures each song as it plays with FFmpeg's loudnorm filter, which works for any song but
+    /// takes more CPU.
+    Loudnorm,
 }
 
 pub struct PlayConfig<'s> {
     pub search_prefix: &'s str,
+    /// Searches with this prefix instead when set, and plays whichever result looks most like
+    /// the song's official audio. The prefix should ask for a few results, like `ytsearch5`.
+    pub music_mode_search_prefix: Option<&'s str>,
     pub host_blocklist: &'s [String],
     pub ytdl_name: &'s str,
     pub ytdl_args: &'s [String],
     pub ffmpeg_name: &'s str,
     pub ffmpeg_args: &'s [String],
+    pub normalization: Option<Normalization>,
+    pub tts: Option<TtsConfig<'s>>,
+    /// How loud songs are while a clip plays over them, relative to their normal volume.
+    pub duck_volume: f32,
+    /// How long songs take to fade down when a clip starts and back up once it ends.
+    pub duck_fade: Duration,
+    pub media_server: Option<MediaServerConfig<'s>>,
+    pub library: Option<&'s LocalLibrary>,
+}
+
+/// A text-to-speech command that writes audio to stdout. `{text}` in its arguments is replaced
+/// with the text to read out.
+pub struct TtsConfig<'s> {
+    pub name: &'s str,
+    pub args: &'s [String],
 }
 
 #[derive(serde::Deserialize)]
 struct YtdlOutput {
+    pub id: String,
     pub title: String,
+    pub artist: Option<String>,
+    pub uploader: Option<String>,
+    pub channel: Option<String>,
+    pub track: Option<String>,
+    pub album: Option<String>,
+    pub playlist_id: Option<String>,
+    pub categories: Option<Vec<String>>,
     pub webpage_url: String,
+    pub duration: Option<f64>,
+    pub extractor_key: Option<String>,
+    pub view_count: Option<u64>,
+    pub upload_date: Option<String>,
+    pub thumbnail: Option<String>,
     pub url: String,
     pub http_headers: HashMap<String, String>,
 }
 
-fn parse_ytdl_line(line: &str, user_id: UserId) -> Result<Song, Error> {
-    let trimmed_line = line.trim();
-    if trimmed_line.starts_with("ERROR:") {
-        return Err(Error::UnsupportedUrl);
+// What youtube-dl prints for each entry when only listing a playlist.
+#[derive(serde::Deserialize)]
+struct FlatYtdlOutput {
+    pub id: String,
+    pub title: Option<String>,
+    pub url: Option<String>,
+    pub webpage_url: Option<String>,
+    pub ie_key: Option<String>,
+}
+
+/// A song in a playlist, found without looking the song itself up.
+#[derive(Debug, Clone)]
+pub struct PlaylistEntry {
+    pub title: String,
+    pub url: String,
+}
+
+/// The songs in a playlist, and what couldn't be read from it.
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistListing {
+    pub entries: Vec<PlaylistEntry>,
+    /// Why each failed entry couldn't be read.
+    pub failures: Vec<String>,
+}
+
+/// youtube-dl only says why it failed in the error message, so the common reasons are picked out
+/// of it. Anything else is treated as a site it doesn't support.
+fn classify_ytdl_error(message: &str) -> Error {
+    let message = message.to_lowercase();
+    let contains_any = |patterns: &[&str]| patterns.iter().any(|pattern| message.contains(pattern));
+
+    if contains_any(&["private video", "video is private"]) {
+        Error::PrivateVideo
+    } else if contains_any(&[
+        "confirm your age",
+        "age-restricted",
+        "age restricted",
+        "inappropriate for some users",
+    ]) {
+        Error::AgeRestricted
+    } else if contains_any(&[
+        "available in your country",
+        "geo restriction",
+        "geo-restricted",
+        "blocked it in your country",
+    ]) {
+        Error::GeoBlocked
+    } else if contains_any(&[
+        "live event will begin",
+        "premieres in",
+        "premiere will begin",
+    ]) {
+        Error::LiveNotSupported
+    } else if contains_any(&[
+        "timed out",
+        "timeout",
+        "temporary failure in name resolution",
+        "connection reset",
+    ]) {
+        Error::NetworkTimeout
+    } else if contains_any(&[
+        "video unavailable",
+        "video is unavailable",
+        "video has been removed",
+        "http error 404",
+    ]) {
+        Error::VideoUnavailable
+    } else if contains_any(&[
+        "unable to extract",
+        "failed to extract",
+        "nsig extraction failed",
+        "please report this issue",
+    ]) {
+        Error::ExtractorOutdated
+    } else {
+        Error::UnsupportedUrl
+    }
+}
+
+fn is_ytdl_error(line: &str) -> bool {
+    line.trim().starts_with("ERROR:")
+}
+
+/// Reads the rest of what youtube-dl printed after an error and waits for it to exit, so the
+/// error carries everything needed to tell why it failed.
+async fn ytdl_failure(
+    mut ytdl: TokioChild,
+    mut lines: Lines<BufReader<ChildStderr>>,
+    error_line: String,
+) -> Error {
+    let cause = classify_ytdl_error(&error_line);
+    let mut stderr = error_line.trim().to_string();
+    while let Ok(Some(line)) = lines.next_line().await {
+        stderr.push('\n');
+        stderr.push_str(line.trim_end());
     }
+    if stderr.chars().count() > STDERR_SNIPPET_LENGTH {
+        stderr = stderr.chars().take(STDERR_SNIPPET_LENGTH).collect();
+        stderr.push('…');
+    }
+    let exit_code = ytdl.wait().await.ok().and_then(|status| status.code());
+
+    log::warn!(
+        "youtube-dl failed: cause=\"{}\" exit_code={:?} stderr={:?}",
+        cause,
+        exit_code,
+        stderr
+    );
+    Error::Extractor(Box::new(ExtractorFailure {
+        cause,
+        exit_code,
+        stderr,
+    }))
+}
 
-    let value: YtdlOutput = serde_json::from_str(trimmed_line)
-        .map_err(|err| Error::Parse(err, trimmed_line.to_string()))?;
+fn read_ytdl_line(line: &str) -> Result<YtdlOutput, Error> {
+    let trimmed_line = line.trim();
+    serde_json::from_str(trimmed_line).map_err(|err| Error::Parse(err, trimmed_line.to_string()))
+}
+
+fn parse_ytdl_line(line: &str, user_id: UserId) -> Result<Song, Error> {
+    Ok(song_from_ytdl_output(read_ytdl_line(line)?, user_id))
+}
 
-    Ok(Song {
+fn song_from_ytdl_output(value: YtdlOutput, user_id: UserId) -> Song {
+    Song {
         metadata: SongMetadata {
+            id: value.id,
             title: value.title.to_string(),
+            // Music tracks often have a proper artist, otherwise the uploader is the next best
+            // thing.
+            artist: value.artist.or_else(|| value.uploader.clone()),
+            uploader: value.uploader,
             url: value.webpage_url.to_string(),
+            duration: value
+                .duration
+                .filter(|secs| secs.is_finite() && *secs >= 0.)
+                .map(Duration::from_secs_f64),
+            site: value.extractor_key,
+            view_count: value.view_count,
+            upload_date: value.upload_date,
+            thumbnail: value.thumbnail,
+            // Songs queued from a playlist belong to it, even if they're from different albums.
+            collection: value.playlist_id.or(value.album),
+            track_gain: None,
             user_id,
+            queued_at: SystemTime::now(),
         },
         download_url: value.url.to_string(),
         http_headers: value
@@ -65,32 +321,97 @@ fn parse_ytdl_line(line: &str, user_id: UserId) -> Result<Song, Error> {
             .iter()
             .map(|(key, value)| (key.to_string(), value.to_string()))
             .collect(),
-    })
+        start_position: None,
+        end_position: None,
+    }
+}
+
+// Keeps only the result that looks most like the song's official audio, or the first of the
+// best if there's a tie, since that's the one the site ranked highest.
+fn pick_music_result(query: &str, mut outputs: Vec<YtdlOutput>) -> Vec<YtdlOutput> {
+    let best_index = outputs
+        .iter()
+        .enumerate()
+        .max_by_key(|(index, output)| {
+            let score = music_score(
+                query,
+                &output.title,
+                output.channel.as_deref().or(output.uploader.as_deref()),
+                output.categories.as_deref().unwrap_or_default(),
+                output.track.is_some(),
+            );
+            (score, Reverse(*index))
+        })
+        .map(|(index, _)| index);
+    match best_index {
+        Some(index) => vec![outputs.swap_remove(index)],
+        None => Vec::new(),
+    }
+}
+
+// Returns why the entry can't be replayed if it can't.
+fn parse_flat_ytdl_line(line: &str) -> Result<PlaylistEntry, String> {
+    let trimmed_line = line.trim();
+    let value: FlatYtdlOutput =
+        serde_json::from_str(trimmed_line).map_err(|err| format!("Unreadable entry: {}", err))?;
+
+    // Flat entries from YouTube only have the video ID as their URL in older versions.
+    let url = match (value.webpage_url, value.url, value.ie_key.as_deref()) {
+        (Some(webpage_url), _, _) => webpage_url,
+        (None, Some(url), _) if url::Url::parse(&url).is_ok() => url,
+        (None, _, Some("Youtube")) => format!("https://www.youtube.com/watch?v={}", value.id),
+        _ => return Err(format!("{}: No link to the song", value.id)),
+    };
+    match value.title {
+        // YouTube keeps removed videos in playlists with a placeholder title.
+        Some(title) if title == "[Deleted video]" || title == "[Private video]" => {
+            Err(format!("{}: {}", value.id, title))
+        }
+        Some(title) => Ok(PlaylistEntry { title, url }),
+        None => Ok(PlaylistEntry {
+            title: url.clone(),
+            url,
+        }),
+    }
 }
 
 impl Song {
+    pub(crate) fn from_stream_url(
+        metadata: SongMetadata,
+        download_url: String,
+        http_headers: Vec<(String, String)>,
+    ) -> Self {
+        Song {
+            metadata,
+            download_url,
+            http_headers,
+            start_position: None,
+            end_position: None,
+        }
+    }
+
     pub async fn load(
         term: &str,
         user_id: UserId,
         config: &PlayConfig<'_>,
     ) -> Result<Vec<Song>, Error> {
-        let ytdl_url = match url::Url::parse(term) {
-            Ok(url) => {
-                if let Some(host_str) = url.host_str() {
-                    // Ensure the resolved host isn't in the blocklist
-                    if config
-                        .host_blocklist
-                        .iter()
-                        .any(|domain| host_str.contains(domain))
-                    {
-                        return Err(Error::UnsupportedUrl);
-                    }
-                }
+        let source = classify_term(term, config)?;
+        if let Some(songs) = Song::load_local(source, user_id, config).await? {
+            return Ok(songs);
+        }
 
-                Cow::Borrowed(term)
-            }
-            Err(_) => Cow::Owned(format!("{}:{}", config.search_prefix, &term)),
+        let ytdl_url = match source {
+            TermSource::Url => Cow::Borrowed(term),
+            _ => Cow::Owned(format!(
+                "{}:{}",
+                config
+                    .music_mode_search_prefix
+                    .unwrap_or(config.search_prefix),
+                &term
+            )),
         };
+        let is_music_search =
+            config.music_mode_search_prefix.is_some() && source != TermSource::Url;
 
         let mut ytdl = TokioCommand::new(config.ytdl_name)
             .args(config.ytdl_args)
@@ -102,6 +423,8 @@ impl Song {
                 "-o",
                 "-",
             ])
+            // One result that can't be played shouldn't stop the others from being compared.
+            .args(is_music_search.then_some("--ignore-errors"))
             .stdin(Stdio::null())
             .stderr(Stdio::piped())
             .stdout(Stdio::null())
@@ -109,12 +432,85 @@ impl Song {
             .map_err(Error::Io)?;
         let mut lines = BufReader::new(ytdl.stderr.take().unwrap()).lines();
 
-        let mut songs = Vec::new();
+        let mut outputs = Vec::new();
+        let mut first_error_line = None;
         while let Some(line) = lines.next_line().await.map_err(Error::Io)? {
-            songs.push(parse_ytdl_line(&line, user_id)?);
+            if is_ytdl_error(&line) {
+                if !is_music_search {
+                    return Err(ytdl_failure(ytdl, lines, line).await);
+                }
+                first_error_line.get_or_insert(line);
+                continue;
+            }
+            outputs.push(read_ytdl_line(&line)?);
+        }
+        if is_music_search {
+            // It's only an error if none of the results could be read.
+            if let Some(error_line) = first_error_line.filter(|_| outputs.is_empty()) {
+                let exit_code = ytdl.wait().await.ok().and_then(|status| status.code());
+                return Err(Error::Extractor(Box::new(ExtractorFailure {
+                    cause: classify_ytdl_error(&error_line),
+                    exit_code,
+                    stderr: error_line.trim().to_string(),
+                })));
+            }
+            outputs = pick_music_result(term, outputs);
+        }
+
+        Ok(outputs
+            .into_iter()
+            .map(|output| song_from_ytdl_output(output, user_id))
+            .collect())
+    }
+
+    // Looks for the term in the local library and on the media server, returning `None` if it
+    // should be looked up with youtube-dl instead.
+    async fn load_local(
+        source: TermSource<'_>,
+        user_id: UserId,
+        config: &PlayConfig<'_>,
+    ) -> Result<Option<Vec<Song>>, Error> {
+        let (query, is_local_search) = match (source, config.library, &config.media_server) {
+            (TermSource::LibrarySong(relative_path), Some(library), _) => {
+                return Ok(Some(vec![library.fetch(relative_path, user_id)?]));
+            }
+            (TermSource::MediaServerSong(id), _, Some(media_server)) => {
+                return Ok(Some(vec![media_server.fetch(id, user_id).await?]));
+            }
+            (
+                TermSource::Search {
+                    query,
+                    is_local_search,
+                },
+                _,
+                _,
+            ) => (query, is_local_search),
+            _ => return Ok(None),
+        };
+        if let Some(library) = config
+            .library
+            .filter(|library| is_local_search || library.search_by_default())
+        {
+            let songs = library.search(query, user_id);
+            if !songs.is_empty() {
+                return Ok(Some(songs));
+            }
+        }
+        if let Some(media_server) = config
+            .media_server
+            .as_ref()
+            .filter(|media_server| is_local_search || media_server.search_by_default)
+        {
+            match media_server.search(query, user_id).await {
+                Ok(songs) if !songs.is_empty() => return Ok(Some(songs)),
+                Ok(_) => {}
+                Err(why) if is_local_search => return Err(why),
+                Err(why) => log::warn!("Unable to search media server for {}: {}", query, why),
+            }
         }
 
-        Ok(songs)
+        // Searches that asked for local songs don't go to the web if nothing was found.
+        Ok(is_local_search.then(Vec::new))
     }
 
     pub async fn fetch_one(
@@ -122,6 +518,17 @@ impl Song {
         user_id: UserId,
         config: &PlayConfig<'_>,
     ) -> Result<Song, Error> {
+        if let Some(library) = config.library {
+            if let Some(relative_path) = library.song_path_from_url(webpage_url) {
+                return library.fetch(relative_path, user_id);
+            }
+        }
+        if let Some(media_server) = &config.media_server {
+            if let Some(id) = media_server.song_id_from_url(webpage_url) {
+                return media_server.fetch(id, user_id).await;
+            }
+        }
+
         let mut ytdl = TokioCommand::new(config.ytdl_name)
             .args(config.ytdl_args)
             .args(&[
@@ -138,16 +545,105 @@ impl Song {
             .stdout(Stdio::null())
             .spawn()
             .map_err(Error::Io)?;
-        let first_line = BufReader::new(ytdl.stderr.take().unwrap())
-            .lines()
+        let mut lines = BufReader::new(ytdl.stderr.take().unwrap()).lines();
+        let first_line = lines
             .next_line()
             .await
             .map_err(Error::Io)?
             .ok_or(Error::UnsupportedUrl)?;
+        if is_ytdl_error(&first_line) {
+            return Err(ytdl_failure(ytdl, lines, first_line).await);
+        }
 
         parse_ytdl_line(&first_line, user_id)
     }
 
+    /// Lists the songs in a playlist without loading them, which is much faster than `load` for
+    /// long playlists. Entries that are deleted, private or unreadable are left out as failures,
+    /// and it's only an error if there's nothing in the playlist that could be read.
+    pub async fn list_playlist(
+        url: &str,
+        config: &PlayConfig<'_>,
+    ) -> Result<PlaylistListing, Error> {
+        let host_str = url::Url::parse(url)
+            .map_err(|_| Error::UnsupportedUrl)?
+            .host_str()
+            .map(str::to_string)
+            .unwrap_or_default();
+        if config
+            .host_blocklist
+            .iter()
+            .any(|domain| host_str.contains(domain))
+        {
+            return Err(Error::UnsupportedUrl);
+        }
+
+        let mut ytdl = TokioCommand::new(config.ytdl_name)
+            .args(config.ytdl_args)
+            .args(&[
+                "--flat-playlist",
+                "--dump-json",
+                "--ignore-errors",
+                "--ignore-config",
+                "--no-warnings",
+                url,
+                "-o",
+                "-",
+            ])
+            .stdin(Stdio::null())
+            .stderr(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(Error::Io)?;
+        let mut lines = BufReader::new(ytdl.stderr.take().unwrap()).lines();
+
+        let mut listing = PlaylistListing::default();
+        let mut first_error_line = None;
+        while let Some(line) = lines.next_line().await.map_err(Error::Io)? {
+            if is_ytdl_error(&line) {
+                let message = line.trim().trim_start_matches("ERROR:").trim().to_string();
+                listing.failures.push(message);
+                first_error_line.get_or_insert(line);
+                continue;
+            }
+            match parse_flat_ytdl_line(&line) {
+                Ok(entry) => listing.entries.push(entry),
+                Err(failure) => listing.failures.push(failure),
+            }
+        }
+
+        match first_error_line {
+            Some(error_line) if listing.entries.is_empty() => {
+                let exit_code = ytdl.wait().await.ok().and_then(|status| status.code());
+                Err(Error::Extractor(Box::new(ExtractorFailure {
+                    cause: classify_ytdl_error(&error_line),
+                    exit_code,
+                    stderr: error_line.trim().to_string(),
+                })))
+            }
+            _ => Ok(listing),
+        }
+    }
+
+    /// Starts playback from a point in the song instead of the beginning.
+    pub fn set_start_position(&mut self, position: Duration) {
+        self.start_position = Some(position);
+    }
+
+    pub fn start_position(&self) -> Duration {
+        self.start_position.unwrap_or_default()
+    }
+
+    /// Stops playback at a point in the song instead of the end. This is measured from the start
+    /// of the song, not the start position.
+    pub fn set_end_position(&mut self, position: Duration) {
+        self.end_position = Some(position);
+    }
+
+    pub fn end_position(&self) -> Option<Duration> {
+        self.end_position
+    }
+
     pub async fn get_input(
         &self,
         config: &PlayConfig<'_>,
@@ -162,30 +658,77 @@ impl Song {
                     &self.metadata.url,
                     why
                 );
-                let refetch_song =
+                let mut refetch_song =
                     Song::fetch_one(&self.metadata.url, self.metadata.user_id, config).await?;
+                refetch_song.start_position = self.start_position;
+                refetch_song.end_position = self.end_position;
                 refetch_song.get_input_no_retry(config).await
             }
         }
     }
 
+    // Gain from the song's details is used when there is some, otherwise FFmpeg looks for
+    // ReplayGain tags in the file itself.
+    fn normalization_args(&self, config: &PlayConfig<'_>) -> Vec<String> {
+        let filter = match (config.normalization, self.metadata.track_gain) {
+            (None, _) => return Vec::new(),
+            (Some(Normalization::ReplayGain), Some(track_gain)) => {
+                format!("volume={}dB", track_gain)
+            }
+            (Some(Normalization::ReplayGain), None) => "volume=replaygain=track".to_string(),
+            (Some(Normalization::Loudnorm), _) => "loudnorm".to_string(),
+        };
+        vec!["-af".to_string(), filter]
+    }
+
     async fn get_input_no_retry(
         &self,
         config: &PlayConfig<'_>,
     ) -> Result<songbird::input::Input, Error> {
-        // If this is a livestream, directly call FFMPEG instead of doing the download step ourself
+        // FFmpeg has to decode up to the start position when reading from a pipe, but that's
+        // still quicker than playing it.
+        let mut trim_args = match self.start_position {
+            Some(position) => vec!["-ss".to_string(), position.as_secs().to_string()],
+            None => Vec::new(),
+        };
+        // FFmpeg's length is from wherever it started reading, so it's cut short by the start.
+        if let Some(end_position) = self.end_position {
+            let start_secs = self.start_position().as_secs();
+            let length_secs = end_position.as_secs().saturating_sub(start_secs);
+            trim_args.extend(["-t".to_string(), length_secs.to_string()]);
+        }
+        let filter_args = self.normalization_args(config);
+
+        // If this is a livestream or a local file, directly call FFMPEG instead of doing the
+        // download step ourself
         let parsed_download_url =
             url::Url::parse(&self.download_url).map_err(|_| Error::UnsupportedUrl)?;
-        if parsed_download_url.path().ends_with(".m3u8") {
+        let maybe_input_args = if parsed_download_url.scheme() == "file" {
+            let path = parsed_download_url
+                .to_file_path()
+                .map_err(|_| Error::UnsupportedUrl)?;
+            Some(vec!["-i".to_string(), path.to_string_lossy().into_owned()])
+        } else if parsed_download_url.path().ends_with(".m3u8") {
             let http_headers: String = self
                 .http_headers
                 .iter()
                 .map(|(key, value)| format!("{}: {}\r\n", key, value))
                 .collect();
-
+            Some(vec![
+                "-headers".to_string(),
+                http_headers,
+                "-i".to_string(),
+                self.download_url.clone(),
+            ])
+        } else {
+            None
+        };
+        if let Some(input_args) = maybe_input_args {
             let ffmpeg = Command::new(config.ffmpeg_name)
                 .args(config.ffmpeg_args)
-                .args(&["-headers", &http_headers, "-i", &self.download_url])
+                .args(&trim_args)
+                .args(&input_args)
+                .args(&filter_args)
                 .args(DEFAULT_FFMPEG_ARGS)
                 .stdin(Stdio::null())
                 .stderr(Stdio::null())
@@ -215,7 +758,8 @@ impl Song {
         }
 
         let request_builder = HTTP_CLIENT.get(&self.download_url).headers(headers);
-        let source = StreamingSource::new(config, request_builder).await?;
+        let source =
+            StreamingSource::new(config, &trim_args, &filter_args, request_builder).await?;
 
         Ok(songbird::input::Input::new(
             true,
@@ -227,11 +771,32 @@ impl Song {
     }
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SongMetadata {
+    /// The ID the site the song is from uses for it, e.g. a YouTube video ID.
+    pub id: String,
     pub title: String,
+    pub artist: Option<String>,
+    pub uploader: Option<String>,
     pub url: String,
+    /// How long the song is, if it isn't a livestream.
+    pub duration: Option<Duration>,
+    /// The name youtube-dl has for the site, e.g. "Youtube".
+    pub site: Option<String>,
+    pub view_count: Option<u64>,
+    /// In the form YYYYMMDD.
+    pub upload_date: Option<String>,
+    pub thumbnail: Option<String>,
+    /// The playlist the song was queued from, or otherwise the album it's on. Consecutive songs
+    /// from the same one can be played without a gap between them.
+    #[serde(default)]
+    pub collection: Option<String>,
+    /// The ReplayGain track gain in dB, if the site or file has one.
+    #[serde(default)]
+    pub track_gain: Option<f32>,
     pub user_id: UserId,
+    /// Songs are loaded as they're queued, so this is also when the song was queued.
+    pub queued_at: SystemTime,
 }
 
 struct StreamingSource {
@@ -244,6 +809,8 @@ struct StreamingSource {
 impl StreamingSource {
     pub async fn new(
         config: &PlayConfig<'_>,
+        trim_args: &[String],
+        filter_args: &[String],
         request_builder: reqwest::RequestBuilder,
     ) -> Result<Self, Error> {
         let initial_response = request_builder
@@ -259,7 +826,9 @@ impl StreamingSource {
 
         let mut ffmpeg = Command::new(config.ffmpeg_name)
             .args(config.ffmpeg_args)
+            .args(trim_args)
             .args(&["-i", "-"])
+            .args(filter_args)
             .args(DEFAULT_FFMPEG_ARGS)
             .stdin(Stdio::piped())
             .stderr(Stdio::null())
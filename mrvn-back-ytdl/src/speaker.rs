@@ -1,27 +1,185 @@
-use crate::{Brain, PlayConfig, Song, SongMetadata};
+use crate::voice_stats::VoiceStatsEventHandler;
+use crate::{Brain, PlayConfig, Song, SongMetadata, VoiceStats};
 use dashmap::DashMap;
 use serenity::client::ClientBuilder;
 use serenity::{model::prelude::*, prelude::*};
 use std::ops::DerefMut;
 use std::sync::Arc;
-use std::time::Instant;
-use tokio::sync::MutexGuard;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::{broadcast, MutexGuard};
+
+// How often the song's volume is stepped while fading, which is smooth enough to not be heard.
+const VOLUME_FADE_STEP: Duration = Duration::from_millis(20);
+// How often `Progress` events are sent while a song plays.
+const PROGRESS_EVENT_INTERVAL: Duration = Duration::from_secs(5);
+// Songs that end sooner than this before they should have are counted as having failed.
+const ENDED_EARLY_MARGIN: Duration = Duration::from_secs(5);
 
 pub struct SpeakerKey;
 
+/// Why the song a speaker is playing has been paused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseReason {
+    /// Someone paused it.
+    Requested,
+    /// The speaker has been server muted or suppressed, so nobody could hear it.
+    Muted,
+}
+
 impl TypeMapKey for SpeakerKey {
     type Value = Arc<Speaker>;
 }
 
+/// Why a song stopped playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EndReason {
+    /// It played to the end, or to where it was trimmed to end.
+    Finished,
+    /// It was stopped, skipped or replaced by another song.
+    Stopped,
+    /// It broke off well before the end, usually because the stream failed.
+    Errored,
+}
+
+/// Sent as speakers play songs. Speakers are identified by their index, which is the order they
+/// were registered with the brain in.
+#[derive(Debug, Clone)]
+pub enum SpeakerEvent {
+    Started {
+        speaker_index: usize,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        metadata: SongMetadata,
+    },
+    /// Sent every few seconds while a song is playing, with how far into the song it is.
+    Progress {
+        speaker_index: usize,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        position: Duration,
+    },
+    Paused {
+        speaker_index: usize,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        reason: PauseReason,
+    },
+    Resumed {
+        speaker_index: usize,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    },
+    /// A song couldn't be started.
+    Errored {
+        speaker_index: usize,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        error: String,
+    },
+    /// A song has stopped playing. `Started` follows if there's another song to play.
+    Ended {
+        speaker_index: usize,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        metadata: SongMetadata,
+        reason: EndReason,
+    },
+    /// The speaker has stopped playing altogether.
+    Stopped {
+        speaker_index: usize,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    },
+}
+
+#[derive(Clone)]
+struct SpeakerEventSender {
+    speaker_index: usize,
+    sender: broadcast::Sender<SpeakerEvent>,
+}
+
+impl SpeakerEventSender {
+    fn started(&self, guild_id: GuildId, channel_id: ChannelId, metadata: SongMetadata) {
+        // Sending only fails when nobody is subscribed, which is fine.
+        let _ = self.sender.send(SpeakerEvent::Started {
+            speaker_index: self.speaker_index,
+            guild_id,
+            channel_id,
+            metadata,
+        });
+    }
+
+    fn progress(&self, guild_id: GuildId, channel_id: ChannelId, position: Duration) {
+        let _ = self.sender.send(SpeakerEvent::Progress {
+            speaker_index: self.speaker_index,
+            guild_id,
+            channel_id,
+            position,
+        });
+    }
+
+    fn paused(&self, guild_id: GuildId, channel_id: ChannelId, reason: PauseReason) {
+        let _ = self.sender.send(SpeakerEvent::Paused {
+            speaker_index: self.speaker_index,
+            guild_id,
+            channel_id,
+            reason,
+        });
+    }
+
+    fn resumed(&self, guild_id: GuildId, channel_id: ChannelId) {
+        let _ = self.sender.send(SpeakerEvent::Resumed {
+            speaker_index: self.speaker_index,
+            guild_id,
+            channel_id,
+        });
+    }
+
+    fn errored(&self, guild_id: GuildId, channel_id: ChannelId, error: &crate::error::Error) {
+        let _ = self.sender.send(SpeakerEvent::Errored {
+            speaker_index: self.speaker_index,
+            guild_id,
+            channel_id,
+            error: error.to_string(),
+        });
+    }
+
+    fn ended(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        metadata: SongMetadata,
+        reason: EndReason,
+    ) {
+        let _ = self.sender.send(SpeakerEvent::Ended {
+            speaker_index: self.speaker_index,
+            guild_id,
+            channel_id,
+            metadata,
+            reason,
+        });
+    }
+
+    fn stopped(&self, guild_id: GuildId, channel_id: ChannelId) {
+        let _ = self.sender.send(SpeakerEvent::Stopped {
+            speaker_index: self.speaker_index,
+            guild_id,
+            channel_id,
+        });
+    }
+}
+
 pub struct Speaker {
     songbird: Arc<songbird::Songbird>,
+    events: SpeakerEventSender,
     guilds: DashMap<GuildId, Arc<Mutex<GuildSpeaker>>>,
 }
 
 impl Speaker {
-    fn new(songbird: Arc<songbird::Songbird>) -> Self {
+    fn new(songbird: Arc<songbird::Songbird>, events: SpeakerEventSender) -> Self {
         Speaker {
             songbird,
+            events,
             guilds: DashMap::new(),
         }
     }
@@ -36,11 +194,37 @@ impl Speaker {
         GuildSpeakerHandle {
             guild_id,
             songbird: self.songbird.clone(),
+            events: self.events.clone(),
             guild_speaker,
             current_call,
         }
     }
 
+    /// Connection stats for each guild the speaker has joined a call in.
+    pub async fn voice_stats(&self) -> Vec<(GuildId, VoiceStats)> {
+        // Clone the handles out first so the map isn't locked while waiting on guild speakers.
+        let guild_speakers: Vec<_> = self
+            .guilds
+            .iter()
+            .map(|guild| (*guild.key(), guild.value().clone()))
+            .collect();
+
+        let mut voice_stats = Vec::new();
+        for (guild_id, guild_speaker) in guild_speakers {
+            let stats = guild_speaker
+                .lock()
+                .await
+                .voice_stats
+                .lock()
+                .unwrap()
+                .clone();
+            if stats.connects > 0 {
+                voice_stats.push((guild_id, stats));
+            }
+        }
+        voice_stats
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = GuildSpeakerHandle> + '_ {
         self.guilds.iter().map(move |guild| {
             let guild_id = *guild.key();
@@ -49,6 +233,7 @@ impl Speaker {
             GuildSpeakerHandle {
                 guild_id,
                 songbird: self.songbird.clone(),
+                events: self.events.clone(),
                 guild_speaker,
                 current_call,
             }
@@ -63,7 +248,11 @@ pub trait SpeakerInit {
 impl SpeakerInit for ClientBuilder<'_> {
     fn register_speaker(self, brain: &mut Brain) -> Self {
         let songbird = songbird::Songbird::serenity();
-        let speaker = Arc::new(Speaker::new(songbird.clone()));
+        let events = SpeakerEventSender {
+            speaker_index: brain.speakers.len(),
+            sender: brain.events.clone(),
+        };
+        let speaker = Arc::new(Speaker::new(songbird.clone(), events));
         brain.speakers.push(speaker.clone());
 
         self.voice_manager_arc(songbird)
@@ -72,14 +261,61 @@ impl SpeakerInit for ClientBuilder<'_> {
 }
 
 struct GuildPlayingState {
-    metadata: SongMetadata,
+    channel_id: ChannelId,
+    // The song's start position is where in the song the track started from.
+    song: Song,
     track: songbird::tracks::TrackHandle,
     is_paused: bool,
 }
 
+/// A song's stream, started before it's due to play so it can follow the song before it
+/// without a gap.
+pub struct PrimedSong {
+    url: String,
+    queued_at: SystemTime,
+    start_position: Duration,
+    // Inputs can't be shared between threads but speakers can, so this is kept in a mutex. It's
+    // never locked, only taken out when the song plays.
+    input: std::sync::Mutex<songbird::input::Input>,
+}
+
+impl PrimedSong {
+    pub async fn new(song: &Song, config: &PlayConfig<'_>) -> Result<Self, crate::error::Error> {
+        Ok(PrimedSong {
+            url: song.metadata.url.clone(),
+            queued_at: song.metadata.queued_at,
+            start_position: song.start_position(),
+            input: std::sync::Mutex::new(song.get_input(config).await?),
+        })
+    }
+
+    fn is_for(&self, song: &Song) -> bool {
+        self.url == song.metadata.url
+            && self.queued_at == song.metadata.queued_at
+            && self.start_position == song.start_position()
+    }
+}
+
 struct GuildSpeaker {
     last_ended_time: Option<Instant>,
     playing_state: Option<GuildPlayingState>,
+    // The next song, if it's been started early. It's only used if it's the song that's played.
+    primed_song: Option<PrimedSong>,
+    playing_clip_count: usize,
+    // How loud the song is while clips play over it, and how long it takes to get there. These
+    // come from the config the last clip was played with.
+    duck_volume: f32,
+    duck_fade: Duration,
+    volume_fade: Option<tokio::task::JoinHandle<()>>,
+    // The announcement the current song is waiting on before it starts.
+    held_for_announcement: Option<u64>,
+    next_announcement_id: u64,
+    // Server muted or suppressed in the channel it's in.
+    is_muted: bool,
+    // The channel it last joined, which it's preferred for next time.
+    last_channel_id: Option<ChannelId>,
+    // Updated from songbird's event handlers, which can't wait on the guild speaker's lock.
+    voice_stats: Arc<std::sync::Mutex<VoiceStats>>,
 }
 
 impl GuildSpeaker {
@@ -87,6 +323,99 @@ impl GuildSpeaker {
         GuildSpeaker {
             last_ended_time: None,
             playing_state: None,
+            primed_song: None,
+            playing_clip_count: 0,
+            duck_volume: 1.,
+            duck_fade: Duration::ZERO,
+            volume_fade: None,
+            held_for_announcement: None,
+            next_announcement_id: 0,
+            is_muted: false,
+            last_channel_id: None,
+            voice_stats: Arc::default(),
+        }
+    }
+
+    /// Starts or pauses the song to match whether anything is holding it up.
+    fn update_song_playing(&self) {
+        if let Some(playing_state) = &self.playing_state {
+            let res =
+                if playing_state.is_paused || self.is_muted || self.held_for_announcement.is_some()
+                {
+                    playing_state.track.pause()
+                } else {
+                    playing_state.track.play()
+                };
+            if let Err(why) = res {
+                log::warn!("Error while updating song playback: {}", why);
+            }
+        }
+    }
+
+    /// Lets the current song start if it was waiting on the announcement, unless it's been
+    /// paused since.
+    fn release_announcement_hold(&mut self, announcement_id: u64) {
+        if self.held_for_announcement != Some(announcement_id) {
+            return;
+        }
+        self.held_for_announcement = None;
+        self.update_song_playing();
+    }
+
+    fn song_volume(&self) -> f32 {
+        if self.playing_clip_count > 0 {
+            self.duck_volume
+        } else {
+            1.
+        }
+    }
+
+    /// Sets the song's volume straight away, e.g. for a song that's only just started.
+    fn update_song_volume(&mut self) {
+        if let Some(volume_fade) = self.volume_fade.take() {
+            volume_fade.abort();
+        }
+        if let Some(playing_state) = &self.playing_state {
+            if let Err(why) = playing_state.track.set_volume(self.song_volume()) {
+                log::warn!("Error while changing song volume: {}", why);
+            }
+        }
+    }
+
+    /// Fades the song to the volume it should be at, starting from wherever it is now, so a fade
+    /// that's cut short by another clip carries on smoothly.
+    fn fade_song_volume(&mut self) {
+        if self.duck_fade.is_zero() {
+            self.update_song_volume();
+            return;
+        }
+        if let Some(volume_fade) = self.volume_fade.take() {
+            volume_fade.abort();
+        }
+        if let Some(playing_state) = &self.playing_state {
+            self.volume_fade = Some(tokio::task::spawn(fade_volume(
+                playing_state.track.clone(),
+                self.song_volume(),
+                self.duck_fade,
+            )));
+        }
+    }
+}
+
+async fn fade_volume(track: songbird::tracks::TrackHandle, target_volume: f32, length: Duration) {
+    let start_volume = match track.get_info().await {
+        Ok(track_state) => track_state.volume,
+        Err(_) => return,
+    };
+    let step_count = (length.as_millis() / VOLUME_FADE_STEP.as_millis()).max(1) as u32;
+    let mut interval = tokio::time::interval(VOLUME_FADE_STEP);
+    for step in 1..=step_count {
+        interval.tick().await;
+        let progress = step as f32 / step_count as f32;
+        let volume = start_volume + (target_volume - start_volume) * progress;
+        // The track has ended, so there's nothing left to fade.
+        if track.set_volume(volume).is_err() {
+            return;
         }
     }
 }
@@ -94,6 +423,7 @@ impl GuildSpeaker {
 pub struct GuildSpeakerHandle {
     guild_id: GuildId,
     songbird: Arc<songbird::Songbird>,
+    events: SpeakerEventSender,
     guild_speaker: Arc<Mutex<GuildSpeaker>>,
     current_call: Option<Arc<Mutex<songbird::Call>>>,
 }
@@ -103,6 +433,7 @@ impl GuildSpeakerHandle {
         GuildSpeakerRef {
             guild_id: self.guild_id,
             songbird: self.songbird.clone(),
+            events: self.events.clone(),
             guild_speaker_ref: self.guild_speaker.clone(),
             guild_speaker: self.guild_speaker.lock().await,
             current_call: match &self.current_call {
@@ -116,6 +447,7 @@ impl GuildSpeakerHandle {
 pub struct GuildSpeakerRef<'handle> {
     guild_id: GuildId,
     songbird: Arc<songbird::Songbird>,
+    events: SpeakerEventSender,
     guild_speaker_ref: Arc<Mutex<GuildSpeaker>>,
     guild_speaker: MutexGuard<'handle, GuildSpeaker>,
     current_call: Option<MutexGuard<'handle, songbird::Call>>,
@@ -136,6 +468,11 @@ impl<'handle> GuildSpeakerRef<'handle> {
             .and_then(|call| call.current_channel().map(|id| ChannelId(id.0)))
     }
 
+    /// The channel the speaker last joined, even if it's left since.
+    pub fn last_channel(&self) -> Option<ChannelId> {
+        self.guild_speaker.last_channel_id
+    }
+
     pub fn is_active(&self) -> bool {
         self.guild_speaker.playing_state.is_some()
     }
@@ -147,45 +484,118 @@ impl<'handle> GuildSpeakerRef<'handle> {
         }
     }
 
+    /// Being muted comes first, since unpausing won't help until the speaker is unmuted.
+    pub fn pause_reason(&self) -> Option<PauseReason> {
+        match &self.guild_speaker.playing_state {
+            Some(_) if self.guild_speaker.is_muted => Some(PauseReason::Muted),
+            Some(state) if state.is_paused => Some(PauseReason::Requested),
+            _ => None,
+        }
+    }
+
+    /// Pauses the song while the speaker is server muted or suppressed, instead of playing
+    /// through songs nobody can hear, and carries on once it's unmuted.
+    pub fn set_muted(&mut self, is_muted: bool) {
+        if self.guild_speaker.is_muted == is_muted {
+            return;
+        }
+        log::debug!(
+            "Speaker has been {}",
+            if is_muted { "muted" } else { "unmuted" }
+        );
+        self.guild_speaker.is_muted = is_muted;
+        self.guild_speaker.update_song_playing();
+
+        // A song that's already paused doesn't change whether it's playing.
+        if let Some(playing_state) = &self.guild_speaker.playing_state {
+            if !playing_state.is_paused {
+                if is_muted {
+                    self.events
+                        .paused(self.guild_id, playing_state.channel_id, PauseReason::Muted);
+                } else {
+                    self.events.resumed(self.guild_id, playing_state.channel_id);
+                }
+            }
+        }
+    }
+
     pub fn active_metadata(&self) -> Option<SongMetadata> {
         self.guild_speaker
             .playing_state
             .as_ref()
-            .map(|state| state.metadata.clone())
+            .map(|state| state.song.metadata.clone())
     }
 
+    /// How far into the active song playback is, counting from the start of the song rather than
+    /// where the track started.
+    pub async fn active_position(&self) -> Option<Duration> {
+        let playing_state = self.guild_speaker.playing_state.as_ref()?;
+        let track_state = playing_state.track.get_info().await.ok()?;
+        Some(playing_state.song.start_position() + track_state.position)
+    }
+
+    /// The active song, starting from where playback has got to, so it can be carried on by
+    /// another speaker.
+    pub async fn resumable_song(&self) -> Option<Song> {
+        let position = self.active_position().await?;
+        let mut song = self.guild_speaker.playing_state.as_ref()?.song.clone();
+        song.set_start_position(position);
+        Some(song)
+    }
+
+    /// Holds on to the stream of the song that's expected to play next, so it starts straight
+    /// away when it does. Replaces any song that was primed before.
+    pub fn prime(&mut self, primed_song: PrimedSong) {
+        self.guild_speaker.primed_song = Some(primed_song);
+    }
+
+    /// Whether the song's stream has already been started with `prime`.
+    pub fn is_primed(&self, song: &Song) -> bool {
+        self.guild_speaker
+            .primed_song
+            .as_ref()
+            .is_some_and(|primed_song| primed_song.is_for(song))
+    }
+
+    /// Plays a song, replacing whatever was playing. If there's an `announcement` and text to
+    /// speech is set up, it's read out before the song starts.
     pub async fn play<Ended: EndedHandler>(
         &mut self,
         channel_id: ChannelId,
         song: Song,
+        announcement: Option<&str>,
         config: &PlayConfig<'_>,
         ended_handler: Ended,
     ) -> Result<(), crate::error::Error> {
-        let input = song.get_input(config).await?;
+        // A primed stream for any other song is dropped, since it won't be needed now.
+        let input = match self.guild_speaker.primed_song.take() {
+            Some(primed_song) if primed_song.is_for(&song) => {
+                log::trace!("Playing \"{}\" from its primed stream", song.metadata.title);
+                primed_song.input.into_inner().unwrap()
+            }
+            _ => match song.get_input(config).await {
+                Ok(input) => input,
+                Err(why) => {
+                    self.events.errored(self.guild_id, channel_id, &why);
+                    return Err(why);
+                }
+            },
+        };
 
         let track_handle = match &mut self.current_call {
             Some(call) if call.current_channel() == Some(channel_id.into()) => {
                 call.play_only_source(input)
             }
             _ => {
-                // Ensure we don't deadlock by having a current_call lock
-                self.current_call = None;
-
-                let (call_handle, join_result) =
-                    self.songbird.join(self.guild_id, channel_id).await;
-                if let Err(why) = join_result {
-                    self.guild_speaker.playing_state = None;
-                    return Err(crate::error::Error::SongbirdJoin(why));
-                }
-
+                let call_handle = match self.join(channel_id).await {
+                    Ok(call_handle) => call_handle,
+                    Err(why) => {
+                        self.guild_speaker.playing_state = None;
+                        self.events.errored(self.guild_id, channel_id, &why);
+                        return Err(why);
+                    }
+                };
                 let mut call = call_handle.lock().await;
-                call.remove_all_global_events();
-                call.add_global_event(
-                    songbird::Event::Core(songbird::CoreEvent::DriverDisconnect),
-                    GuildSpeakerDisconnectedEventHandler {
-                        guild_speaker: self.guild_speaker_ref.clone(),
-                    },
-                );
                 call.play_only_source(input)
             }
         };
@@ -194,28 +604,268 @@ impl<'handle> GuildSpeakerRef<'handle> {
             .add_event(
                 songbird::Event::Track(songbird::TrackEvent::End),
                 GuildSpeakerEndedEventHandler {
+                    channel_id,
+                    metadata: song.metadata.clone(),
+                    expected_length: song
+                        .end_position()
+                        .or(song.metadata.duration)
+                        .map(|end_position| end_position.saturating_sub(song.start_position())),
                     data: Mutex::new(Some((
                         ended_handler,
                         GuildSpeakerEndedBuilder {
                             guild_id: self.guild_id,
                             songbird: self.songbird.clone(),
+                            events: self.events.clone(),
                             guild_speaker: self.guild_speaker_ref.clone(),
+                            track_position: None,
+                            end_reason: EndReason::Finished,
                         },
                     ))),
                 },
             )
             .map_err(crate::error::Error::SongbirdTrack)?;
+        track_handle
+            .add_event(
+                songbird::Event::Periodic(PROGRESS_EVENT_INTERVAL, None),
+                GuildSpeakerProgressEventHandler {
+                    guild_id: self.guild_id,
+                    channel_id,
+                    events: self.events.clone(),
+                    start_position: song.start_position(),
+                },
+            )
+            .map_err(crate::error::Error::SongbirdTrack)?;
+        self.events
+            .started(self.guild_id, channel_id, song.metadata.clone());
         self.guild_speaker.playing_state = Some(GuildPlayingState {
-            metadata: song.metadata,
+            channel_id,
+            song,
             track: track_handle,
             is_paused: false,
         });
+        self.guild_speaker.held_for_announcement = None;
+        self.guild_speaker.update_song_volume();
+
+        // Nobody would hear the announcement, the song waits until the speaker is unmuted.
+        if self.guild_speaker.is_muted {
+            self.guild_speaker.update_song_playing();
+            return Ok(());
+        }
+
+        if let Some(text) = announcement {
+            if let Err(why) = self.announce(channel_id, text, config).await {
+                log::warn!("Error while announcing song: {}", why);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Plays a short clip over whatever song is playing, which is turned down until the clip
+    /// finishes. The speaker joins the channel first if it isn't already in it. `volume` is
+    /// relative to the clip's own volume, so `1.0` plays it unchanged.
+    pub async fn play_clip(
+        &mut self,
+        channel_id: ChannelId,
+        source: &str,
+        volume: f32,
+        config: &PlayConfig<'_>,
+    ) -> Result<(), crate::error::Error> {
+        let input = crate::clip::clip_input(source, config)?;
+        let (mut clip_track, clip_handle) = songbird::create_player(input);
+        clip_track.set_volume(volume);
+        self.play_overlay(channel_id, clip_track, clip_handle, None, config)
+            .await
+    }
+
+    /// Plays part of a song like a clip, from the song's start position until `length` has
+    /// passed. Nothing about the song is kept, so it won't be reported as playing or picked up
+    /// by a handover.
+    pub async fn play_excerpt(
+        &mut self,
+        channel_id: ChannelId,
+        song: &Song,
+        length: Duration,
+        config: &PlayConfig<'_>,
+    ) -> Result<(), crate::error::Error> {
+        let input = song.get_input(config).await?;
+        let (excerpt_track, excerpt_handle) = songbird::create_player(input);
+        excerpt_handle
+            .add_event(songbird::Event::Delayed(length), ExcerptLengthEventHandler)
+            .map_err(crate::error::Error::SongbirdTrack)?;
+        self.play_overlay(channel_id, excerpt_track, excerpt_handle, None, config)
+            .await
+    }
+
+    /// Reads text out with text to speech, holding the current song until it's done.
+    async fn announce(
+        &mut self,
+        channel_id: ChannelId,
+        text: &str,
+        config: &PlayConfig<'_>,
+    ) -> Result<(), crate::error::Error> {
+        let tts_config = match &config.tts {
+            Some(tts_config) => tts_config,
+            None => return Ok(()),
+        };
+        let input = crate::clip::tts_input(text, tts_config, config)?;
+        let (announcement_track, announcement_handle) = songbird::create_player(input);
+
+        let announcement_id = self.guild_speaker.next_announcement_id;
+        self.guild_speaker.next_announcement_id += 1;
+        if let Some(playing_state) = &self.guild_speaker.playing_state {
+            playing_state
+                .track
+                .pause()
+                .map_err(crate::error::Error::SongbirdTrack)?;
+            self.guild_speaker.held_for_announcement = Some(announcement_id);
+        }
+
+        let overlay_res = self
+            .play_overlay(
+                channel_id,
+                announcement_track,
+                announcement_handle,
+                Some(announcement_id),
+                config,
+            )
+            .await;
+        if overlay_res.is_err() {
+            self.guild_speaker
+                .release_announcement_hold(announcement_id);
+        }
+        overlay_res
+    }
+
+    /// Plays a track alongside the current song, without replacing it. The song is faded down
+    /// until the track ends.
+    async fn play_overlay(
+        &mut self,
+        channel_id: ChannelId,
+        track: songbird::tracks::Track,
+        track_handle: songbird::tracks::TrackHandle,
+        announcement_id: Option<u64>,
+        config: &PlayConfig<'_>,
+    ) -> Result<(), crate::error::Error> {
+        match &mut self.current_call {
+            Some(call) if call.current_channel() == Some(channel_id.into()) => call.play(track),
+            _ => {
+                // Ensure we don't deadlock by having a current_call lock
+                self.current_call = None;
+
+                // The call might have just been joined to play a song, in which case we don't
+                // need to join again.
+                let maybe_call_handle = self.songbird.get(self.guild_id);
+                let is_in_channel = match &maybe_call_handle {
+                    Some(call_handle) => {
+                        call_handle.lock().await.current_channel() == Some(channel_id.into())
+                    }
+                    None => false,
+                };
+                let call_handle = match maybe_call_handle {
+                    Some(call_handle) if is_in_channel => call_handle,
+                    _ => self.join(channel_id).await?,
+                };
+                call_handle.lock().await.play(track);
+            }
+        };
+
+        track_handle
+            .add_event(
+                songbird::Event::Track(songbird::TrackEvent::End),
+                GuildSpeakerClipEndedEventHandler {
+                    guild_speaker: self.guild_speaker_ref.clone(),
+                    announcement_id,
+                },
+            )
+            .map_err(crate::error::Error::SongbirdTrack)?;
+        self.guild_speaker.playing_clip_count += 1;
+        self.guild_speaker.duck_volume = config.duck_volume;
+        self.guild_speaker.duck_fade = config.duck_fade;
+        self.guild_speaker.fade_song_volume();
+
+        Ok(())
+    }
+
+    async fn join(
+        &mut self,
+        channel_id: ChannelId,
+    ) -> Result<Arc<Mutex<songbird::Call>>, crate::error::Error> {
+        // Ensure we don't deadlock by having a current_call lock
+        self.current_call = None;
+
+        // Stats handlers go on before joining so they see the connection being made.
+        {
+            let call_handle = self.songbird.get_or_insert(self.guild_id.into());
+            let mut call = call_handle.lock().await;
+            call.remove_all_global_events();
+            for event in [
+                songbird::CoreEvent::DriverConnect,
+                songbird::CoreEvent::DriverReconnect,
+                songbird::CoreEvent::DriverDisconnect,
+                songbird::CoreEvent::RtcpPacket,
+            ] {
+                call.add_global_event(
+                    songbird::Event::Core(event),
+                    VoiceStatsEventHandler {
+                        stats: self.guild_speaker.voice_stats.clone(),
+                    },
+                );
+            }
+        }
+
+        let (call_handle, join_result) = self.songbird.join(self.guild_id, channel_id).await;
+        join_result.map_err(crate::error::Error::SongbirdJoin)?;
+        self.guild_speaker.last_channel_id = Some(channel_id);
 
+        {
+            let mut call = call_handle.lock().await;
+            call.add_global_event(
+                songbird::Event::Core(songbird::CoreEvent::DriverDisconnect),
+                GuildSpeakerDisconnectedEventHandler {
+                    guild_speaker: self.guild_speaker_ref.clone(),
+                },
+            );
+        }
+        Ok(call_handle)
+    }
+
+    /// Moves the speaker to another channel, carrying on with the song it's playing from the same
+    /// point.
+    pub async fn move_to(&mut self, channel_id: ChannelId) -> Result<(), crate::error::Error> {
+        // Leaving the old channel would otherwise stop the song. Joining adds the handler back.
+        if let Some(call) = &mut self.current_call {
+            call.remove_all_global_events();
+        }
+        self.join(channel_id).await?;
+
+        if let Some(playing_state) = &mut self.guild_speaker.playing_state {
+            let old_channel_id = std::mem::replace(&mut playing_state.channel_id, channel_id);
+            self.events.stopped(self.guild_id, old_channel_id);
+            self.events.started(
+                self.guild_id,
+                channel_id,
+                playing_state.song.metadata.clone(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Joins the channel without playing anything. The speaker counts as inactive from now, so
+    /// it's disconnected like any other idle speaker if nothing gets played.
+    pub async fn summon(&mut self, channel_id: ChannelId) -> Result<(), crate::error::Error> {
+        if self.current_channel() != Some(channel_id) {
+            self.join(channel_id).await?;
+        }
+        self.guild_speaker.last_ended_time = Some(Instant::now());
         Ok(())
     }
 
     pub fn unlock(&mut self) {
-        self.guild_speaker.playing_state = None;
+        if let Some(playing_state) = self.guild_speaker.playing_state.take() {
+            self.events.stopped(self.guild_id, playing_state.channel_id);
+        }
+        self.guild_speaker.primed_song = None;
         self.guild_speaker.last_ended_time = Some(Instant::now());
     }
 
@@ -230,22 +880,39 @@ impl<'handle> GuildSpeakerRef<'handle> {
     }
 
     pub fn pause(&mut self) -> Result<(), crate::error::Error> {
+        let is_muted = self.guild_speaker.is_muted;
         if let Some(playing_state) = &mut self.guild_speaker.playing_state {
             playing_state
                 .track
                 .pause()
                 .map_err(crate::error::Error::SongbirdTrack)?;
+            // A muted song was already paused, and it's reported as muted while it still is.
+            if !playing_state.is_paused && !is_muted {
+                self.events.paused(
+                    self.guild_id,
+                    playing_state.channel_id,
+                    PauseReason::Requested,
+                );
+            }
             playing_state.is_paused = true;
         }
         Ok(())
     }
 
     pub fn unpause(&mut self) -> Result<(), crate::error::Error> {
+        self.guild_speaker.held_for_announcement = None;
+        let is_muted = self.guild_speaker.is_muted;
         if let Some(playing_state) = &mut self.guild_speaker.playing_state {
-            playing_state
-                .track
-                .play()
-                .map_err(crate::error::Error::SongbirdTrack)?;
+            // The song stays paused until the speaker is unmuted.
+            if !is_muted {
+                playing_state
+                    .track
+                    .play()
+                    .map_err(crate::error::Error::SongbirdTrack)?;
+                if playing_state.is_paused {
+                    self.events.resumed(self.guild_id, playing_state.channel_id);
+                }
+            }
             playing_state.is_paused = false;
         }
         Ok(())
@@ -281,16 +948,112 @@ impl songbird::events::EventHandler for GuildSpeakerDisconnectedEventHandler {
     }
 }
 
+// Stops an excerpt once it's played for long enough.
+struct ExcerptLengthEventHandler;
+
+#[serenity::async_trait]
+impl songbird::events::EventHandler for ExcerptLengthEventHandler {
+    async fn act(&self, ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
+        if let songbird::EventContext::Track(tracks) = ctx {
+            for (_, track_handle) in tracks.iter() {
+                if let Err(why) = track_handle.stop() {
+                    log::warn!("Error while stopping excerpt: {}", why);
+                }
+            }
+        }
+        Some(songbird::Event::Cancel)
+    }
+}
+
+struct GuildSpeakerClipEndedEventHandler {
+    guild_speaker: Arc<Mutex<GuildSpeaker>>,
+    announcement_id: Option<u64>,
+}
+
+#[serenity::async_trait]
+impl songbird::events::EventHandler for GuildSpeakerClipEndedEventHandler {
+    async fn act(&self, _ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
+        let mut guild_speaker_ref = self.guild_speaker.lock().await;
+        guild_speaker_ref.playing_clip_count =
+            guild_speaker_ref.playing_clip_count.saturating_sub(1);
+        guild_speaker_ref.fade_song_volume();
+        if let Some(announcement_id) = self.announcement_id {
+            guild_speaker_ref.release_announcement_hold(announcement_id);
+        }
+
+        // If the speaker only joined to play the clip, this lets it be disconnected once it's
+        // been inactive for long enough.
+        if guild_speaker_ref.playing_state.is_none() {
+            guild_speaker_ref.last_ended_time = Some(Instant::now());
+        }
+
+        Some(songbird::Event::Cancel)
+    }
+}
+
+struct GuildSpeakerProgressEventHandler {
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    events: SpeakerEventSender,
+    start_position: Duration,
+}
+
+#[serenity::async_trait]
+impl songbird::events::EventHandler for GuildSpeakerProgressEventHandler {
+    async fn act(&self, ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
+        if let songbird::EventContext::Track([(track_state, _), ..]) = ctx {
+            self.events.progress(
+                self.guild_id,
+                self.channel_id,
+                self.start_position + track_state.position,
+            );
+        }
+        None
+    }
+}
+
 struct GuildSpeakerEndedEventHandler<Ended: EndedHandler> {
+    // The song's details are kept here, since another song may have started by the time this
+    // runs.
+    channel_id: ChannelId,
+    metadata: SongMetadata,
+    // How long the track should play for, if the song's length is known.
+    expected_length: Option<Duration>,
     data: Mutex<Option<(Ended, GuildSpeakerEndedBuilder)>>,
 }
 
+impl<Ended: EndedHandler> GuildSpeakerEndedEventHandler<Ended> {
+    fn end_reason(&self, track_state: &songbird::tracks::TrackState) -> EndReason {
+        if track_state.playing == songbird::tracks::PlayMode::Stop {
+            return EndReason::Stopped;
+        }
+        match self.expected_length {
+            Some(expected_length)
+                if track_state.position + ENDED_EARLY_MARGIN < expected_length =>
+            {
+                EndReason::Errored
+            }
+            _ => EndReason::Finished,
+        }
+    }
+}
+
 #[serenity::async_trait]
 impl<Ended: EndedHandler> songbird::events::EventHandler for GuildSpeakerEndedEventHandler<Ended> {
-    async fn act(&self, _ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
+    async fn act(&self, ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
         let mut data_ref = self.data.lock().await;
         let data = std::mem::replace(data_ref.deref_mut(), None);
-        if let Some((ended_handler, builder)) = data {
+        if let Some((ended_handler, mut builder)) = data {
+            if let songbird::EventContext::Track([(track_state, _), ..]) = ctx {
+                builder.track_position = Some(track_state.position);
+                builder.end_reason = self.end_reason(track_state);
+            }
+            builder.events.ended(
+                builder.guild_id,
+                self.channel_id,
+                self.metadata.clone(),
+                builder.end_reason,
+            );
             ended_handler.on_ended(builder.build());
         }
 
@@ -305,7 +1068,11 @@ pub trait EndedHandler: Send + 'static {
 struct GuildSpeakerEndedBuilder {
     guild_id: GuildId,
     songbird: Arc<songbird::Songbird>,
+    events: SpeakerEventSender,
     guild_speaker: Arc<Mutex<GuildSpeaker>>,
+    // How far into the track playback got before it ended.
+    track_position: Option<Duration>,
+    end_reason: EndReason,
 }
 
 impl GuildSpeakerEndedBuilder {
@@ -314,15 +1081,20 @@ impl GuildSpeakerEndedBuilder {
             guild_speaker_handle: GuildSpeakerHandle {
                 guild_id: self.guild_id,
                 songbird: self.songbird.clone(),
+                events: self.events,
                 guild_speaker: self.guild_speaker.clone(),
                 current_call: self.songbird.get(self.guild_id),
             },
+            track_position: self.track_position,
+            end_reason: self.end_reason,
         }
     }
 }
 
 pub struct GuildSpeakerEndedHandle {
     guild_speaker_handle: GuildSpeakerHandle,
+    track_position: Option<Duration>,
+    end_reason: EndReason,
 }
 
 impl GuildSpeakerEndedHandle {
@@ -334,7 +1106,22 @@ impl GuildSpeakerEndedHandle {
         let guild_speaker_ref = self.guild_speaker_handle.lock().await;
         let ended_state = GuildSpeakerEndedState {
             channel_id: guild_speaker_ref.current_channel(),
+            playing_channel_id: guild_speaker_ref
+                .guild_speaker
+                .playing_state
+                .as_ref()
+                .map(|state| state.channel_id),
             ended_metadata: guild_speaker_ref.active_metadata(),
+            ended_position: match (
+                &guild_speaker_ref.guild_speaker.playing_state,
+                self.track_position,
+            ) {
+                (Some(state), Some(track_position)) => {
+                    Some(state.song.start_position() + track_position)
+                }
+                _ => None,
+            },
+            end_reason: self.end_reason,
         };
         (ended_state, GuildSpeakerEndedRef { guild_speaker_ref })
     }
@@ -342,7 +1129,13 @@ impl GuildSpeakerEndedHandle {
 
 pub struct GuildSpeakerEndedState {
     pub channel_id: Option<ChannelId>,
+    /// The channel the song was playing in. This only changes from where it started if the
+    /// speaker was moved with `move_to`.
+    pub playing_channel_id: Option<ChannelId>,
     pub ended_metadata: Option<SongMetadata>,
+    /// How far into the song playback got before it ended.
+    pub ended_position: Option<Duration>,
+    pub end_reason: EndReason,
 }
 
 #[must_use]
@@ -351,16 +1144,21 @@ pub struct GuildSpeakerEndedRef<'handle> {
 }
 
 impl<'handle> GuildSpeakerEndedRef<'handle> {
+    pub fn is_primed(&self, song: &Song) -> bool {
+        self.guild_speaker_ref.is_primed(song)
+    }
+
     pub async fn play<Ended: EndedHandler>(
         mut self,
         song: Song,
+        announcement: Option<&str>,
         config: &PlayConfig<'_>,
         ended_handler: Ended,
     ) -> Result<(), (GuildSpeakerEndedRef<'handle>, crate::error::Error)> {
         match self.guild_speaker_ref.current_channel() {
             Some(channel_id) => self
                 .guild_speaker_ref
-                .play(channel_id, song, config, ended_handler)
+                .play(channel_id, song, announcement, config, ended_handler)
                 .await
                 .map_err(|err| (self, err)),
             None => {
@@ -371,7 +1169,6 @@ impl<'handle> GuildSpeakerEndedRef<'handle> {
     }
 
     pub fn stop(mut self) {
-        self.guild_speaker_ref.guild_speaker.playing_state = None;
-        self.guild_speaker_ref.guild_speaker.last_ended_time = Some(Instant::now());
+        self.guild_speaker_ref.unlock();
     }
 }
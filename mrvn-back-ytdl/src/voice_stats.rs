@@ -0,0 +1,82 @@
+use songbird::packet::rtcp::Rtcp;
+use std::sync::{Arc, Mutex};
+
+// Sender reports have this much about the sender before their report blocks.
+const RTCP_SENDER_INFO_LENGTH: usize = 20;
+const RTCP_REPORT_BLOCK_LENGTH: usize = 24;
+// Jitter is measured in RTP timestamp units, which tick at Discord's 48kHz sample rate.
+const RTP_TICKS_PER_MS: f64 = 48.;
+
+/// How healthy a speaker's voice connection in a guild has been, so choppy audio can be matched up
+/// with what the network was doing.
+#[derive(Debug, Clone, Default)]
+pub struct VoiceStats {
+    pub connects: u64,
+    pub reconnects: u64,
+    pub disconnects: u64,
+    /// The share of the speaker's packets Discord lost since its previous report, from 0 to 1.
+    pub packet_loss: Option<f64>,
+    pub packets_lost_total: Option<u32>,
+    pub jitter_ms: Option<f64>,
+    // Identifies the speaker's own audio in reports, which also cover everyone else in the call.
+    ssrc: Option<u32>,
+}
+
+impl VoiceStats {
+    fn record_rtcp(&mut self, packet: &Rtcp) {
+        let ssrc = match self.ssrc {
+            Some(ssrc) => ssrc,
+            None => return,
+        };
+        let (report_count, blocks) = match packet {
+            Rtcp::ReceiverReport(report) => (report.rx_report_count, &report.payload[..]),
+            Rtcp::SenderReport(report) => (
+                report.rx_report_count,
+                report
+                    .payload
+                    .get(RTCP_SENDER_INFO_LENGTH..)
+                    .unwrap_or_default(),
+            ),
+            _ => return,
+        };
+        let block = blocks
+            .chunks_exact(RTCP_REPORT_BLOCK_LENGTH)
+            .take(report_count as usize)
+            .find(|block| read_u32(&block[0..4]) == ssrc);
+
+        if let Some(block) = block {
+            self.packet_loss = Some(block[4] as f64 / 256.);
+            self.packets_lost_total = Some(read_u32(&[0, block[5], block[6], block[7]]));
+            self.jitter_ms = Some(read_u32(&block[12..16]) as f64 / RTP_TICKS_PER_MS);
+        }
+    }
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+pub(crate) struct VoiceStatsEventHandler {
+    pub(crate) stats: Arc<Mutex<VoiceStats>>,
+}
+
+#[serenity::async_trait]
+impl songbird::events::EventHandler for VoiceStatsEventHandler {
+    async fn act(&self, ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
+        let mut stats = self.stats.lock().unwrap();
+        match ctx {
+            songbird::EventContext::DriverConnect(data) => {
+                stats.connects += 1;
+                stats.ssrc = Some(data.ssrc);
+            }
+            songbird::EventContext::DriverReconnect(data) => {
+                stats.reconnects += 1;
+                stats.ssrc = Some(data.ssrc);
+            }
+            songbird::EventContext::DriverDisconnect(_) => stats.disconnects += 1,
+            songbird::EventContext::RtcpPacket(data) => stats.record_rtcp(data.packet),
+            _ => {}
+        }
+        None
+    }
+}
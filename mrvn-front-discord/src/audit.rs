@@ -0,0 +1,87 @@
+use serenity::http::Http;
+use serenity::model::prelude::*;
+use std::fmt;
+
+/// Something significant a member did, worth keeping a record of for moderators.
+pub enum AuditAction {
+    QueuedSong {
+        song_title: String,
+        song_url: String,
+    },
+    QueuedSongs {
+        count: usize,
+    },
+    VoteSkipped {
+        song_title: String,
+        song_url: String,
+    },
+    ForceSkipped {
+        song_title: String,
+        song_url: String,
+    },
+    QueueCleared {
+        count: usize,
+    },
+    SettingsChanged {
+        command: String,
+    },
+}
+
+/// One line in a guild's audit channel. These are formatted here instead of with the message
+/// templates, since they're meant for moderators rather than everyone using the bot and should
+/// read the same regardless of language.
+pub struct AuditEntry {
+    pub user_id: UserId,
+    pub timestamp: i64,
+    pub action: AuditAction,
+}
+
+impl AuditEntry {
+    pub fn new(user_id: UserId, action: AuditAction) -> Self {
+        AuditEntry {
+            user_id,
+            timestamp: chrono::Utc::now().timestamp(),
+            action,
+        }
+    }
+
+    pub async fn post(&self, http: &Http, channel_id: ChannelId) -> serenity::Result<()> {
+        channel_id
+            .send_message(http, |create_message| {
+                create_message
+                    .content(self)
+                    .allowed_mentions(|mentions| mentions.empty_parse())
+            })
+            .await
+            .map(|_| ())
+    }
+}
+
+impl fmt::Display for AuditEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<t:{}:f> <@{}> ", self.timestamp, self.user_id.0)?;
+        match &self.action {
+            AuditAction::QueuedSong {
+                song_title,
+                song_url,
+            } => write!(f, "queued [{}](<{}>)", song_title, song_url),
+            AuditAction::QueuedSongs { count } => write!(f, "queued {} songs", count),
+            AuditAction::VoteSkipped {
+                song_title,
+                song_url,
+            } => write!(
+                f,
+                "cast the deciding vote to skip [{}](<{}>)",
+                song_title, song_url
+            ),
+            AuditAction::ForceSkipped {
+                song_title,
+                song_url,
+            } => write!(f, "force-skipped [{}](<{}>)", song_title, song_url),
+            AuditAction::QueueCleared { count } => {
+                write!(f, "cleared their queue of {} songs", count)
+            }
+            AuditAction::SettingsChanged { command } => write!(f, "used `/{}`", command),
+        }
+    }
+}
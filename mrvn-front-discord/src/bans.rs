@@ -0,0 +1,45 @@
+use crate::persistence::{GuildStore, Persistence};
+use serenity::model::prelude::*;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Users that guild admins have banned from using music commands.
+pub struct MusicBans {
+    // Keyed by user ID.
+    store: GuildStore<HashSet<u64>>,
+}
+
+impl MusicBans {
+    pub fn new(persistence: Arc<dyn Persistence>) -> Self {
+        MusicBans {
+            store: GuildStore::new("music-bans", persistence),
+        }
+    }
+
+    pub fn is_banned(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> Result<bool, crate::error::Error> {
+        Ok(self
+            .store
+            .get(guild_id)?
+            .read(|banned_users| banned_users.contains(&user_id.0)))
+    }
+
+    /// Returns `false` if the user was already banned.
+    pub fn ban(&self, guild_id: GuildId, user_id: UserId) -> Result<bool, crate::error::Error> {
+        Ok(self
+            .store
+            .get(guild_id)?
+            .update(|banned_users| banned_users.insert(user_id.0)))
+    }
+
+    /// Returns `false` if the user wasn't banned.
+    pub fn unban(&self, guild_id: GuildId, user_id: UserId) -> Result<bool, crate::error::Error> {
+        Ok(self
+            .store
+            .get(guild_id)?
+            .update(|banned_users| banned_users.remove(&user_id.0)))
+    }
+}
@@ -0,0 +1,211 @@
+use crate::persistence::{GuildStore, Persistence};
+use mrvn_back_ytdl::{Song, SongMetadata};
+use serenity::model::prelude::*;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum BlockRule {
+    /// Blocks songs from the domain or any of its subdomains.
+    Domain(String),
+    VideoId(String),
+    /// Blocks songs with the keyword anywhere in their title, ignoring case.
+    Keyword(String),
+}
+
+impl BlockRule {
+    pub fn new(kind: &str, value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+
+        match kind {
+            "domain" => Some(BlockRule::Domain(value.to_lowercase())),
+            "video" => Some(BlockRule::VideoId(value.to_string())),
+            "keyword" => Some(BlockRule::Keyword(value.to_lowercase())),
+            _ => None,
+        }
+    }
+
+    pub fn matches(&self, metadata: &SongMetadata) -> bool {
+        match self {
+            BlockRule::Domain(domain) => {
+                let maybe_host = url::Url::parse(&metadata.url)
+                    .ok()
+                    .and_then(|url| url.host_str().map(str::to_lowercase));
+                match maybe_host {
+                    Some(host) => host == *domain || host.ends_with(&format!(".{}", domain)),
+                    None => false,
+                }
+            }
+            BlockRule::VideoId(id) => metadata.id == *id,
+            BlockRule::Keyword(keyword) => metadata.title.to_lowercase().contains(keyword),
+        }
+    }
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct GuildBlocklist {
+    rules: Vec<BlockRule>,
+}
+
+/// Songs that guild admins have blocked from being queued.
+pub struct Blocklist {
+    store: GuildStore<GuildBlocklist>,
+}
+
+impl Blocklist {
+    pub fn new(persistence: Arc<dyn Persistence>) -> Self {
+        Blocklist {
+            store: GuildStore::new("blocklist", persistence),
+        }
+    }
+
+    pub fn rules(&self, guild_id: GuildId) -> Result<Vec<BlockRule>, crate::error::Error> {
+        Ok(self
+            .store
+            .get(guild_id)?
+            .read(|blocklist| blocklist.rules.clone()))
+    }
+
+    /// Returns `false` if the rule was already in the blocklist.
+    pub fn add(&self, guild_id: GuildId, rule: BlockRule) -> Result<bool, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.update(|blocklist| {
+            if blocklist.rules.contains(&rule) {
+                false
+            } else {
+                blocklist.rules.push(rule.clone());
+                true
+            }
+        }))
+    }
+
+    /// Returns `false` if the rule wasn't in the blocklist.
+    pub fn remove(&self, guild_id: GuildId, rule: &BlockRule) -> Result<bool, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.update(|blocklist| {
+            let old_len = blocklist.rules.len();
+            blocklist
+                .rules
+                .retain(|existing_rule| existing_rule != rule);
+            blocklist.rules.len() != old_len
+        }))
+    }
+
+    /// Removes blocked songs, returning the ones that are left and the rule that blocked the first
+    /// song that was removed.
+    pub fn filter(
+        &self,
+        guild_id: GuildId,
+        songs: Vec<Song>,
+    ) -> Result<(Vec<Song>, Option<BlockRule>), crate::error::Error> {
+        let rules = self.rules(guild_id)?;
+        if rules.is_empty() {
+            return Ok((songs, None));
+        }
+
+        let mut first_matched_rule = None;
+        let allowed_songs = songs
+            .into_iter()
+            .filter(
+                |song| match rules.iter().find(|rule| rule.matches(&song.metadata)) {
+                    Some(rule) => {
+                        log::trace!(
+                            "Blocked {} (\"{}\") with rule {:?}",
+                            song.metadata.url,
+                            song.metadata.title,
+                            rule
+                        );
+                        first_matched_rule.get_or_insert_with(|| rule.clone());
+                        false
+                    }
+                    None => true,
+                },
+            )
+            .collect();
+        Ok((allowed_songs, first_matched_rule))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::SystemTime;
+
+    fn song(id: &str, title: &str, url: &str) -> SongMetadata {
+        SongMetadata {
+            id: id.to_string(),
+            title: title.to_string(),
+            artist: None,
+            uploader: None,
+            url: url.to_string(),
+            duration: None,
+            site: None,
+            view_count: None,
+            upload_date: None,
+            thumbnail: None,
+            collection: None,
+            track_gain: None,
+            user_id: UserId(1),
+            queued_at: SystemTime::UNIX_EPOCH,
+        }
+    }
+
+    fn song_at(url: &str) -> SongMetadata {
+        song("id", "title", url)
+    }
+
+    #[test]
+    fn parses_rules() {
+        assert_eq!(
+            BlockRule::new("domain", " YouTube.com "),
+            Some(BlockRule::Domain("youtube.com".to_string()))
+        );
+        assert_eq!(
+            BlockRule::new("video", "dQw4w9WgXcQ"),
+            Some(BlockRule::VideoId("dQw4w9WgXcQ".to_string()))
+        );
+        assert_eq!(
+            BlockRule::new("keyword", "Nightcore"),
+            Some(BlockRule::Keyword("nightcore".to_string()))
+        );
+        assert_eq!(BlockRule::new("domain", "  "), None);
+        assert_eq!(BlockRule::new("artist", "someone"), None);
+    }
+
+    #[test]
+    fn domains_match_subdomains() {
+        let rule = BlockRule::new("domain", "youtube.com").unwrap();
+        assert!(rule.matches(&song_at("https://youtube.com/watch?v=1")));
+        assert!(rule.matches(&song_at("https://www.youtube.com/watch?v=1")));
+        assert!(rule.matches(&song_at("https://music.YOUTUBE.com/watch?v=1")));
+    }
+
+    #[test]
+    fn domains_only_match_whole_labels() {
+        let rule = BlockRule::new("domain", "youtube.com").unwrap();
+        assert!(!rule.matches(&song_at("https://notyoutube.com/watch?v=1")));
+        assert!(!rule.matches(&song_at("https://youtube.com.example.org/")));
+        assert!(!rule.matches(&song_at("https://example.org/youtube.com")));
+        assert!(!rule.matches(&song_at("not a url")));
+    }
+
+    #[test]
+    fn videos_match_exactly() {
+        let rule = BlockRule::new("video", "abc").unwrap();
+        assert!(rule.matches(&song("abc", "title", "https://example.org/")));
+        assert!(!rule.matches(&song("ABC", "title", "https://example.org/")));
+        assert!(!rule.matches(&song("abcd", "title", "https://example.org/")));
+    }
+
+    #[test]
+    fn keywords_ignore_case() {
+        let rule = BlockRule::new("keyword", "nightcore").unwrap();
+        assert!(rule.matches(&song(
+            "id",
+            "Song (NightCore Remix)",
+            "https://example.org/"
+        )));
+        assert!(!rule.matches(&song("id", "Song", "https://nightcore.example.org/")));
+    }
+}
@@ -74,6 +74,56 @@ async fn check_cleanup(frontend: Arc<Frontend>, cache: Arc<serenity::cache::Cach
     );
 }
 
+// How often to look for speakers that have finished their songs in maintenance mode.
+const PARK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+// Returns whether the speaker is still connected.
+async fn park_speaker(guild_speaker_handle: GuildSpeakerHandle) -> bool {
+    let mut guild_speaker = guild_speaker_handle.lock().await;
+    if guild_speaker.current_channel().is_none() {
+        return false;
+    }
+    if guild_speaker.is_active() {
+        return true;
+    }
+
+    match guild_speaker.disconnect().await {
+        Ok(_) => {
+            log::debug!("Parked speaker for maintenance");
+            false
+        }
+        Err(why) => {
+            log::error!("Error when parking speaker: {}", why);
+            true
+        }
+    }
+}
+
+/// Disconnects each speaker once it's done playing, until they've all left or maintenance mode
+/// is turned off.
+pub async fn park_speakers(frontend: Arc<Frontend>) {
+    let mut interval = tokio::time::interval(PARK_CHECK_INTERVAL);
+    while frontend.is_in_maintenance() {
+        interval.tick().await;
+        let futures = frontend
+            .backend_brain
+            .speakers
+            .iter()
+            .flat_map(|speaker| speaker.iter())
+            .map(park_speaker);
+        let connected_count = future::join_all(futures)
+            .await
+            .into_iter()
+            .filter(|is_connected| *is_connected)
+            .count();
+        if connected_count == 0 {
+            log::info!("Every speaker is parked for maintenance");
+            return;
+        }
+        log::trace!("Waiting on {} speakers to finish playing", connected_count);
+    }
+}
+
 pub async fn cleanup_loop(frontend: Arc<Frontend>, cache: Arc<serenity::cache::Cache>) -> ! {
     let mut interval = tokio::time::interval(Duration::from_secs(
         frontend.config.disconnect_check_interval_secs,
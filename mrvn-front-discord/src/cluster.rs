@@ -0,0 +1,224 @@
+use crate::config::ClusterConfig;
+use futures::Future;
+use mrvn_back_ytdl::Speaker;
+use redis::aio::ConnectionManager;
+use redis::{FromRedisValue, ScriptInvocation};
+use serenity::model::prelude::*;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::OnceCell;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+// Commands are given up on after this long, since some run while a guild's speakers are locked.
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(2);
+const LEASE_KEY_PREFIX: &str = "mrvn:lease";
+
+lazy_static::lazy_static! {
+    // Takes the lease if nobody holds it, or extends it if we already do.
+    static ref ACQUIRE_LEASE_SCRIPT: redis::Script = redis::Script::new(
+        r"
+        local owner = redis.call('GET', KEYS[1])
+        if owner == false or owner == ARGV[1] then
+            redis.call('SET', KEYS[1], ARGV[1], 'PX', ARGV[2])
+            return 1
+        end
+        return 0
+        ",
+    );
+    static ref RELEASE_LEASE_SCRIPT: redis::Script = redis::Script::new(
+        r"
+        if redis.call('GET', KEYS[1]) == ARGV[1] then
+            return redis.call('DEL', KEYS[1])
+        end
+        return 0
+        ",
+    );
+}
+
+/// An async connection to Redis, shared by everything that uses it. It's opened the first time
+/// it's needed, and reconnects by itself if it's dropped after that.
+pub struct RedisConnection {
+    client: redis::Client,
+    connection: OnceCell<ConnectionManager>,
+}
+
+impl RedisConnection {
+    pub fn open(url: &str) -> Result<Self, crate::error::Error> {
+        Ok(RedisConnection {
+            client: redis::Client::open(url).map_err(crate::error::Error::Redis)?,
+            connection: OnceCell::new(),
+        })
+    }
+
+    pub async fn query<T: FromRedisValue>(
+        &self,
+        cmd: &redis::Cmd,
+    ) -> Result<T, crate::error::Error> {
+        let mut connection = self.connection().await?;
+        with_timeout(COMMAND_TIMEOUT, cmd.query_async(&mut connection)).await
+    }
+
+    pub async fn invoke<T: FromRedisValue>(
+        &self,
+        invocation: &ScriptInvocation<'_>,
+    ) -> Result<T, crate::error::Error> {
+        let mut connection = self.connection().await?;
+        with_timeout(COMMAND_TIMEOUT, invocation.invoke_async(&mut connection)).await
+    }
+
+    async fn connection(&self) -> Result<ConnectionManager, crate::error::Error> {
+        let connection = self
+            .connection
+            .get_or_try_init(|| {
+                with_timeout(CONNECT_TIMEOUT, ConnectionManager::new(self.client.clone()))
+            })
+            .await?;
+        Ok(connection.clone())
+    }
+}
+
+async fn with_timeout<T>(
+    duration: Duration,
+    future: impl Future<Output = redis::RedisResult<T>>,
+) -> Result<T, crate::error::Error> {
+    match tokio::time::timeout(duration, future).await {
+        Ok(res) => res.map_err(crate::error::Error::Redis),
+        Err(_) => Err(crate::error::Error::Redis(
+            (redis::ErrorKind::IoError, "Timed out").into(),
+        )),
+    }
+}
+
+/// Coordinates with the other processes running the bot when it's split across several.
+///
+/// Each process connects a slice of every bot's shards, so the guilds on those shards only ever
+/// send it events, and saves its data to Redis so it's shared by all of them. Speakers are also
+/// leased in Redis before they join a channel, so even while processes are being swapped out two
+/// of them can't try to drive the same speaker in a guild.
+pub struct Cluster {
+    config: ClusterConfig,
+    redis: Arc<RedisConnection>,
+    held_leases: Mutex<HashSet<(GuildId, usize)>>,
+}
+
+impl Cluster {
+    pub async fn connect(config: ClusterConfig) -> Result<Self, crate::error::Error> {
+        let redis = RedisConnection::open(&config.redis_url)?;
+        // Fail on startup rather than the first time something's played if Redis can't be
+        // reached.
+        redis.query::<()>(&redis::cmd("PING")).await?;
+        Ok(Cluster {
+            config,
+            redis: Arc::new(redis),
+            held_leases: Mutex::new(HashSet::new()),
+        })
+    }
+
+    pub fn redis(&self) -> Arc<RedisConnection> {
+        self.redis.clone()
+    }
+
+    /// The first and last shard this process connects, out of `shard_count`. Each process gets a
+    /// run of shards next to each other, since that's all Discord's gateway lets a client start.
+    pub fn shard_range(&self, shard_count: u64) -> [u64; 2] {
+        let start = shard_count * self.config.node_index / self.config.node_count;
+        let end = shard_count * (self.config.node_index + 1) / self.config.node_count;
+        [start, end - 1]
+    }
+
+    /// Takes the lease on a speaker in a guild, or extends it if this process already has it.
+    /// Returns `false` if another process holds it.
+    pub async fn acquire_lease(
+        &self,
+        guild_id: GuildId,
+        speaker_index: usize,
+    ) -> Result<bool, crate::error::Error> {
+        let lease_ms = self.config.lease_secs * 1000;
+        let mut invocation = ACQUIRE_LEASE_SCRIPT.prepare_invoke();
+        invocation
+            .key(lease_key(guild_id, speaker_index))
+            .arg(&self.config.node_id)
+            .arg(lease_ms);
+        let is_acquired = self.redis.invoke::<bool>(&invocation).await?;
+
+        let mut held_leases = self.held_leases.lock().unwrap();
+        if is_acquired {
+            held_leases.insert((guild_id, speaker_index));
+        } else {
+            held_leases.remove(&(guild_id, speaker_index));
+        }
+        Ok(is_acquired)
+    }
+
+    pub async fn release_lease(&self, guild_id: GuildId, speaker_index: usize) {
+        if !self
+            .held_leases
+            .lock()
+            .unwrap()
+            .remove(&(guild_id, speaker_index))
+        {
+            return;
+        }
+
+        let mut invocation = RELEASE_LEASE_SCRIPT.prepare_invoke();
+        invocation
+            .key(lease_key(guild_id, speaker_index))
+            .arg(&self.config.node_id);
+        let res = self.redis.invoke::<()>(&invocation).await;
+        if let Err(why) = res {
+            log::error!(
+                "Error while releasing lease on speaker {} in guild {}: {}",
+                speaker_index,
+                guild_id,
+                why
+            );
+        }
+    }
+
+    /// Keeps the leases on speakers that are in a channel from expiring, and gives up the rest.
+    pub async fn run_lease_renewal(&self, speakers: &[Arc<Speaker>]) {
+        // Renew well before leases expire, so one slow round doesn't lose them.
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.lease_secs) / 3);
+        loop {
+            interval.tick().await;
+            for (speaker_index, speaker) in speakers.iter().enumerate() {
+                let guild_speaker_handles: Vec<_> = speaker.iter().collect();
+                for guild_speaker_handle in guild_speaker_handles {
+                    let mut guild_speaker = guild_speaker_handle.lock().await;
+                    let guild_id = guild_speaker.guild_id();
+                    if guild_speaker.current_channel().is_none() {
+                        self.release_lease(guild_id, speaker_index).await;
+                        continue;
+                    }
+
+                    match self.acquire_lease(guild_id, speaker_index).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            log::warn!(
+                                "Speaker {} in guild {} is leased by another process, disconnecting",
+                                speaker_index,
+                                guild_id
+                            );
+                            if let Err(why) = guild_speaker.disconnect().await {
+                                log::error!("Error while disconnecting: {}", why);
+                            }
+                        }
+                        // Keep playing if Redis is down, it's more likely to come back than
+                        // another process is to take over the guild.
+                        Err(why) => log::error!(
+                            "Error while renewing lease on speaker {} in guild {}: {}",
+                            speaker_index,
+                            guild_id,
+                            why
+                        ),
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn lease_key(guild_id: GuildId, speaker_index: usize) -> String {
+    format!("{}:{}:{}", LEASE_KEY_PREFIX, guild_id, speaker_index)
+}
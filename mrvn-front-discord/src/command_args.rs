@@ -0,0 +1,163 @@
+use crate::error::Error;
+use serenity::model::interactions::application_command::{
+    ApplicationCommandInteractionData, ApplicationCommandInteractionDataOption,
+    ApplicationCommandInteractionDataOptionValue, ApplicationCommandOptionType,
+};
+use serenity::model::prelude::*;
+
+/// A command invocation with its subcommand group and subcommand pulled out of the nested options
+/// Discord sends, so handlers can dispatch on them and look up options by name.
+pub struct CommandArgs<'a> {
+    pub name: &'a str,
+    pub group: Option<&'a str>,
+    pub subcommand: Option<&'a str>,
+    options: &'a [ApplicationCommandInteractionDataOption],
+}
+
+impl<'a> CommandArgs<'a> {
+    pub fn parse(data: &'a ApplicationCommandInteractionData) -> Self {
+        let mut args = CommandArgs {
+            name: &data.name,
+            group: None,
+            subcommand: None,
+            options: &data.options,
+        };
+
+        // A group always contains a subcommand, so both are unwrapped in that order.
+        if let [option] = args.options {
+            if matches!(option.kind, ApplicationCommandOptionType::SubCommandGroup) {
+                args.group = Some(&option.name);
+                args.options = &option.options;
+            }
+        }
+        if let [option] = args.options {
+            if matches!(option.kind, ApplicationCommandOptionType::SubCommand) {
+                args.subcommand = Some(&option.name);
+                args.options = &option.options;
+            }
+        }
+
+        args
+    }
+
+    /// The command used without any options, for checking something done on a user's behalf as
+    /// if they'd used it themselves.
+    pub fn named(name: &'a str) -> Self {
+        CommandArgs {
+            name,
+            group: None,
+            subcommand: None,
+            options: &[],
+        }
+    }
+
+    /// The full name of the command as it's typed, like `settings command-channels add`.
+    pub fn full_name(&self) -> String {
+        std::iter::once(self.name)
+            .chain(self.group)
+            .chain(self.subcommand)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn value(&self, name: &str) -> Option<&'a ApplicationCommandInteractionDataOptionValue> {
+        self.options
+            .iter()
+            .find(|option| option.name == name)
+            .and_then(|option| option.resolved.as_ref())
+    }
+
+    /// An option the command can be run without. It's an error if Discord sent a different type
+    /// of value than the command asks for.
+    pub fn optional<T: OptionValue<'a>>(&self, name: &str) -> Result<Option<T>, Error> {
+        match self.value(name) {
+            Some(value) => T::from_value(value)
+                .map(Some)
+                .ok_or_else(|| Error::InvalidOption {
+                    name: name.to_string(),
+                    expected: T::KIND,
+                }),
+            None => Ok(None),
+        }
+    }
+
+    /// An option the command is registered as requiring.
+    pub fn required<T: OptionValue<'a>>(&self, name: &str) -> Result<T, Error> {
+        self.optional(name)?
+            .ok_or_else(|| Error::MissingOption(name.to_string()))
+    }
+}
+
+/// A type an option's value can be read as.
+pub trait OptionValue<'a>: Sized {
+    /// What the value should have been, for errors.
+    const KIND: &'static str;
+
+    fn from_value(value: &'a ApplicationCommandInteractionDataOptionValue) -> Option<Self>;
+}
+
+impl<'a> OptionValue<'a> for &'a str {
+    const KIND: &'static str = "string";
+
+    fn from_value(value: &'a ApplicationCommandInteractionDataOptionValue) -> Option<Self> {
+        match value {
+            ApplicationCommandInteractionDataOptionValue::String(val) => Some(val),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> OptionValue<'a> for i64 {
+    const KIND: &'static str = "integer";
+
+    fn from_value(value: &'a ApplicationCommandInteractionDataOptionValue) -> Option<Self> {
+        match value {
+            ApplicationCommandInteractionDataOptionValue::Integer(val) => Some(*val),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> OptionValue<'a> for bool {
+    const KIND: &'static str = "boolean";
+
+    fn from_value(value: &'a ApplicationCommandInteractionDataOptionValue) -> Option<Self> {
+        match value {
+            ApplicationCommandInteractionDataOptionValue::Boolean(val) => Some(*val),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> OptionValue<'a> for UserId {
+    const KIND: &'static str = "user";
+
+    fn from_value(value: &'a ApplicationCommandInteractionDataOptionValue) -> Option<Self> {
+        match value {
+            ApplicationCommandInteractionDataOptionValue::User(user, _) => Some(user.id),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> OptionValue<'a> for RoleId {
+    const KIND: &'static str = "role";
+
+    fn from_value(value: &'a ApplicationCommandInteractionDataOptionValue) -> Option<Self> {
+        match value {
+            ApplicationCommandInteractionDataOptionValue::Role(role) => Some(role.id),
+            _ => None,
+        }
+    }
+}
+
+impl<'a> OptionValue<'a> for ChannelId {
+    const KIND: &'static str = "channel";
+
+    fn from_value(value: &'a ApplicationCommandInteractionDataOptionValue) -> Option<Self> {
+        match value {
+            ApplicationCommandInteractionDataOptionValue::Channel(channel) => Some(channel.id),
+            _ => None,
+        }
+    }
+}
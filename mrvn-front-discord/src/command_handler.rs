@@ -14,13 +14,71 @@ impl CommandHandler {
 
 #[serenity::async_trait]
 impl EventHandler for CommandHandler {
-    async fn ready(&self, _ctx: Context, ready: Ready) {
-        log::info!("Command client is connected as {}", ready.user.name);
+    async fn ready(&self, ctx: Context, ready: Ready) {
+        log::info!(
+            "Command client is connected as {} on shard {:?}",
+            ready.user.name,
+            ready.shard
+        );
+        if self.frontend.schedules.start_running() {
+            tokio::spawn(self.frontend.clone().run_schedules(ctx.clone()));
+        }
+        if let Some(handover) = &self.frontend.handover {
+            handover.resume(&ctx, &self.frontend).await;
+        }
+        if let Some(recovery) = &self.frontend.recovery {
+            recovery.resume(&ctx, &self.frontend).await;
+        }
+    }
+
+    async fn guild_create(&self, ctx: Context, guild: Guild, is_new: bool) {
+        // Guilds the bot is already in are sent again every time it connects.
+        if !is_new || self.frontend.is_handed_over() {
+            return;
+        }
+        self.frontend.handle_guild_joined(&ctx, &guild).await;
+    }
+
+    async fn message(&self, ctx: Context, new_message: Message) {
+        if self.frontend.is_handed_over() {
+            return;
+        }
+        self.frontend
+            .handle_channel_message(&ctx, &new_message)
+            .await;
+    }
+
+    async fn voice_state_update(
+        &self,
+        ctx: Context,
+        guild_id: Option<GuildId>,
+        old: Option<VoiceState>,
+        new: VoiceState,
+    ) {
+        if self.frontend.is_handed_over() {
+            return;
+        }
+        let guild_id = match guild_id.or(new.guild_id) {
+            Some(guild_id) => guild_id,
+            None => return,
+        };
+        self.frontend
+            .handle_voice_state_update(&ctx, guild_id, old.and_then(|old| old.channel_id), &new)
+            .await;
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::ApplicationCommand(command) = interaction {
-            self.frontend.handle_command(&ctx, &command).await;
+        if self.frontend.is_handed_over() {
+            return;
+        }
+        match interaction {
+            Interaction::ApplicationCommand(command) => {
+                self.frontend.handle_command(&ctx, &command).await;
+            }
+            Interaction::MessageComponent(component) => {
+                self.frontend.handle_component(&ctx, &component).await;
+            }
+            _ => {}
         }
     }
 }
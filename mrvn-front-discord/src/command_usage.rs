@@ -0,0 +1,83 @@
+use serenity::model::prelude::*;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandStats {
+    pub count: u64,
+    /// Uses that failed, or were answered with an error message.
+    pub error_count: u64,
+    pub total_time: Duration,
+    pub max_time: Duration,
+}
+
+impl CommandStats {
+    pub fn average_time(&self) -> Duration {
+        match u32::try_from(self.count) {
+            Ok(count) if count > 0 => self.total_time / count,
+            _ => Duration::ZERO,
+        }
+    }
+}
+
+/// How often each command is used and how long it takes to handle, kept in memory since the bot
+/// started so the bot's owner can see what's worth speeding up with `/usage`.
+#[derive(Default)]
+pub struct CommandUsage {
+    // Keyed by the command's name, without subcommands.
+    commands: Mutex<HashMap<String, CommandStats>>,
+    guilds: Mutex<HashMap<GuildId, u64>>,
+}
+
+impl CommandUsage {
+    /// The time is from when the command was received until its response was ready to send.
+    pub fn record(&self, guild_id: GuildId, name: &str, time: Duration, is_error: bool) {
+        {
+            let mut commands = self.commands.lock().unwrap();
+            let stats = commands.entry(name.to_string()).or_default();
+            stats.count += 1;
+            if is_error {
+                stats.error_count += 1;
+            }
+            stats.total_time += time;
+            stats.max_time = stats.max_time.max(time);
+        }
+        *self.guilds.lock().unwrap().entry(guild_id).or_default() += 1;
+    }
+
+    /// Every command that's been used, by name.
+    pub fn commands(&self) -> Vec<(String, CommandStats)> {
+        let mut commands: Vec<_> = self
+            .commands
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, stats)| (name.clone(), stats.clone()))
+            .collect();
+        commands.sort_by(|(a, _), (b, _)| a.cmp(b));
+        commands
+    }
+
+    /// The commands that take longest on average, slowest first.
+    pub fn slowest_commands(&self, limit: usize) -> Vec<(String, CommandStats)> {
+        let mut commands = self.commands();
+        commands.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.average_time()));
+        commands.truncate(limit);
+        commands
+    }
+
+    /// The guilds that have used the most commands, busiest first.
+    pub fn busiest_guilds(&self, limit: usize) -> Vec<(GuildId, u64)> {
+        let mut guilds: Vec<_> = self
+            .guilds
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(guild_id, count)| (*guild_id, *count))
+            .collect();
+        guilds.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        guilds.truncate(limit);
+        guilds
+    }
+}
@@ -1,19 +1,53 @@
-use futures::prelude::*;
 use serenity::model::prelude::*;
 
-async fn delete_all_global_application_commands(
-    http: impl AsRef<serenity::http::Http>,
-) -> serenity::Result<()> {
-    let http_ref = http.as_ref();
-    let commands = http_ref.get_global_application_commands().await?;
-    log::trace!("Deleting {} global application commands", commands.len());
-    future::try_join_all(
-        commands
-            .iter()
-            .map(|command| http_ref.delete_global_application_command(command.id.0)),
-    )
-    .await?;
-    Ok(())
+/// Who can use a command or subcommand. Commands where only some uses need a permission, like
+/// `/skip force`, are open to everyone and say so in their descriptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CommandPermission {
+    Everyone,
+    /// Needs the Manage Server permission.
+    ManageServer,
+    /// Only the bot's owner, or their team.
+    Owner,
+}
+
+impl CommandPermission {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "everyone" => Some(CommandPermission::Everyone),
+            "manage_server" => Some(CommandPermission::ManageServer),
+            "owner" => Some(CommandPermission::Owner),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CommandPermission::Everyone => "everyone",
+            CommandPermission::ManageServer => "manage_server",
+            CommandPermission::Owner => "owner",
+        }
+    }
+}
+
+/// One way of using a command in `/help`, either a command with its options or one of its
+/// subcommands.
+#[derive(Debug, Clone)]
+pub struct HelpCommand {
+    pub kind: HelpCommandKind,
+    /// Like `/playlist save <name> [guild]`, with optional options in brackets.
+    pub usage: String,
+    pub description: String,
+    pub permission: CommandPermission,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HelpCommandKind {
+    Slash,
+    /// Picked from Apps after right-clicking someone.
+    User,
+    /// Picked from Apps after right-clicking a message.
+    Message,
 }
 
 fn play_command(
@@ -29,6 +63,55 @@ fn play_command(
                 .kind(application_command::ApplicationCommandOptionType::String)
                 .required(true)
         })
+        .create_option(|option| {
+            option
+                .name("resume")
+                .description("Continue from where the song stopped last time.")
+                .kind(application_command::ApplicationCommandOptionType::Boolean)
+        })
+        .create_option(|option| {
+            option
+                .name("start")
+                .description("Where in the song to start playing, like 1:30.")
+                .kind(application_command::ApplicationCommandOptionType::String)
+        })
+        .create_option(|option| {
+            option
+                .name("end")
+                .description("Where in the song to stop playing, like 2:45.")
+                .kind(application_command::ApplicationCommandOptionType::String)
+        })
+}
+
+fn playmany_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("playmany")
+        .description("Add several songs to your queue at once.")
+        .create_option(|option| {
+            option
+                .name("songs")
+                .description("Search terms or links, separated by semicolons.")
+                .kind(application_command::ApplicationCommandOptionType::String)
+                .required(true)
+        })
+}
+
+fn queue_message_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("Queue this")
+        .kind(application_command::ApplicationCommandType::Message)
+}
+
+fn listening_user_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("What are they listening to?")
+        .kind(application_command::ApplicationCommandType::User)
 }
 
 fn resume_command(
@@ -64,6 +147,13 @@ fn skip_command(
     command
         .name("skip")
         .description("Vote to skip the current song.")
+        .create_option(|option| {
+            option
+                .name("force")
+                .description("Skip without a vote. Only server managers can do this.")
+                .kind(application_command::ApplicationCommandOptionType::Boolean)
+                .required(false)
+        })
 }
 
 fn stop_command(
@@ -74,70 +164,1591 @@ fn stop_command(
         .description("Vote to skip the current song and stop playback.")
 }
 
-fn secret_highfive_command(
+fn transfer_command(
     command: &mut serenity::builder::CreateApplicationCommand,
 ) -> &mut serenity::builder::CreateApplicationCommand {
-    command.name("highfive").description("\\^_^")
+    command
+        .name("transfer")
+        .description("Move what's playing in your voice channel to another one.")
+        .create_option(|option| {
+            option
+                .name("channel")
+                .description("The voice channel to move to.")
+                .kind(application_command::ApplicationCommandOptionType::Channel)
+                .required(true)
+        })
 }
 
-fn secret_streak_command(
+fn summon_command(
     command: &mut serenity::builder::CreateApplicationCommand,
 ) -> &mut serenity::builder::CreateApplicationCommand {
     command
-        .name("streak")
-        .description("View your high-five streak")
+        .name("summon")
+        .description("Bring a bot into your voice channel without playing anything.")
 }
 
-pub async fn register_commands(
-    http: impl AsRef<serenity::http::Http>,
-    guild_id: Option<GuildId>,
-    config: &crate::config::Config,
-) -> serenity::Result<()> {
-    let http_ref = http.as_ref();
-    match guild_id {
-        Some(guild_id) => {
-            delete_all_global_application_commands(http_ref).await?;
-            log::trace!("Registering guild application commands");
-            futures::try_join!(
-                guild_id.create_application_command(http_ref, play_command),
-                guild_id.create_application_command(http_ref, resume_command),
-                guild_id.create_application_command(http_ref, replace_command),
-                guild_id.create_application_command(http_ref, pause_command),
-                guild_id.create_application_command(http_ref, skip_command),
-                guild_id.create_application_command(http_ref, stop_command),
-            )?;
-
-            if config.secret_highfive.is_some() {
-                futures::try_join!(
-                    guild_id.create_application_command(http_ref, secret_highfive_command),
-                    guild_id.create_application_command(http_ref, secret_streak_command),
-                )?;
-            }
-        }
-        None => {
-            log::trace!("Registering global application commands");
-            application_command::ApplicationCommand::set_global_application_commands(
-                http_ref,
-                |commands| {
-                    commands
-                        .create_application_command(play_command)
-                        .create_application_command(resume_command)
-                        .create_application_command(replace_command)
-                        .create_application_command(pause_command)
-                        .create_application_command(skip_command)
-                        .create_application_command(stop_command);
-
-                    if config.secret_highfive.is_some() {
-                        commands
-                            .create_application_command(secret_highfive_command)
-                            .create_application_command(secret_streak_command);
-                    }
+fn disconnect_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("disconnect")
+        .description("Make the bot in your voice channel leave, voting to stop if it's playing.")
+}
+
+fn undo_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("undo")
+        .description("Undo the last change you made to your queue.")
+}
+
+fn dedupe_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command.name("dedupe").description(
+        "Remove songs queued more than once in your voice channel, voting to stop if it's playing.",
+    )
+}
+
+fn queue_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("queue")
+        .description("Manage the songs in your queue.")
+        .create_option(|option| {
+            option
+                .name("list")
+                .description("View the songs in your queue.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("channel")
+                .description("View the songs that will play next in a voice channel.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("channel")
+                        .description("The voice channel, if it isn't the one you're in.")
+                        .kind(application_command::ApplicationCommandOptionType::Channel)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("clear")
+                .description("Remove every song from your queue.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("shuffle")
+                .description("Shuffle the songs in your queue.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("remove")
+                .description("Remove a song from your queue.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("position")
+                        .description("The song's position, from /queue list.")
+                        .kind(application_command::ApplicationCommandOptionType::Integer)
+                        .required(true)
+                })
+        })
+}
+
+fn nowplaying_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("nowplaying")
+        .description("See what's playing in a voice channel.")
+        .create_option(|option| {
+            option
+                .name("channel")
+                .description("The voice channel, if it isn't the one you're in.")
+                .kind(application_command::ApplicationCommandOptionType::Channel)
+                .required(false)
+        })
+}
+
+fn songinfo_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("songinfo")
+        .description("See details about what's playing in your voice channel, or a song coming up.")
+        .create_option(|option| {
+            option
+                .name("position")
+                .description("The song's position in /queue channel, if it isn't the current one.")
+                .kind(application_command::ApplicationCommandOptionType::Integer)
+                .required(false)
+        })
+}
+
+fn voteinfo_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("voteinfo")
+        .description("See who has voted to skip or stop in your voice channel.")
+}
+
+fn playlist_name_option(
+    option: &mut serenity::builder::CreateApplicationCommandOption,
+) -> &mut serenity::builder::CreateApplicationCommandOption {
+    option.create_sub_option(|sub_option| {
+        sub_option
+            .name("name")
+            .description("The playlist's name.")
+            .kind(application_command::ApplicationCommandOptionType::String)
+            .required(true)
+    })
+}
+
+fn playlist_guild_option(
+    option: &mut serenity::builder::CreateApplicationCommandOption,
+) -> &mut serenity::builder::CreateApplicationCommandOption {
+    option.create_sub_option(|sub_option| {
+        sub_option
+            .name("guild")
+            .description("Use this server's shared playlists instead of your own.")
+            .kind(application_command::ApplicationCommandOptionType::Boolean)
+            .required(false)
+    })
+}
+
+fn playlist_position_option<'o>(
+    option: &'o mut serenity::builder::CreateApplicationCommandOption,
+    name: &str,
+    description: &str,
+) -> &'o mut serenity::builder::CreateApplicationCommandOption {
+    option.create_sub_option(|sub_option| {
+        sub_option
+            .name(name)
+            .description(description)
+            .kind(application_command::ApplicationCommandOptionType::Integer)
+            .required(true)
+    })
+}
 
-                    commands
-                },
+fn playlist_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("playlist")
+        .description("Save your queue as a playlist, and queue it again later.")
+        .create_option(|option| {
+            playlist_guild_option(playlist_name_option(
+                option
+                    .name("save")
+                    .description("Save the songs in your queue as a playlist.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            ))
+        })
+        .create_option(|option| {
+            playlist_guild_option(playlist_name_option(
+                option
+                    .name("load")
+                    .description("Add the songs in a playlist to your queue.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            ))
+        })
+        .create_option(|option| {
+            playlist_guild_option(
+                option
+                    .name("list")
+                    .description("View your playlists.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            )
+        })
+        .create_option(|option| {
+            playlist_guild_option(playlist_name_option(
+                option
+                    .name("delete")
+                    .description("Delete a playlist.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            ))
+        })
+        .create_option(|option| {
+            playlist_guild_option(
+                playlist_name_option(
+                    option
+                        .name("import")
+                        .description("Save a playlist from YouTube or another site as a playlist.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand),
+                )
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("url")
+                        .description("A link to the playlist.")
+                        .kind(application_command::ApplicationCommandOptionType::String)
+                        .required(true)
+                }),
+            )
+        })
+        .create_option(|option| {
+            playlist_name_option(
+                option
+                    .name("add")
+                    .description("Add songs to the end of a server playlist.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            )
+            .create_sub_option(|sub_option| {
+                sub_option
+                    .name("song")
+                    .description("A link or search term.")
+                    .kind(application_command::ApplicationCommandOptionType::String)
+                    .required(true)
+            })
+        })
+        .create_option(|option| {
+            let option = playlist_name_option(
+                option
+                    .name("move")
+                    .description("Move a song in a server playlist to another position.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            );
+            let option = playlist_position_option(option, "from", "Where the song is now.");
+            playlist_position_option(option, "to", "Where to move it to.")
+        })
+        .create_option(|option| {
+            let option = playlist_name_option(
+                option
+                    .name("remove")
+                    .description("Remove a song from a server playlist.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            );
+            playlist_position_option(option, "position", "Where the song is in the playlist.")
+        })
+        .create_option(|option| {
+            playlist_name_option(
+                option
+                    .name("permission")
+                    .description("Change who can do something to a server playlist.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
             )
-            .await?;
+            .create_sub_option(|sub_option| {
+                sub_option
+                    .name("action")
+                    .description("What to change the permission for.")
+                    .kind(application_command::ApplicationCommandOptionType::String)
+                    .add_string_choice("View", "view")
+                    .add_string_choice("Add songs", "add")
+                    .add_string_choice("Reorder songs", "reorder")
+                    .add_string_choice("Remove songs or delete", "delete")
+                    .required(true)
+            })
+            .create_sub_option(|sub_option| {
+                sub_option
+                    .name("who")
+                    .description("Who can do it.")
+                    .kind(application_command::ApplicationCommandOptionType::String)
+                    .add_string_choice("Everyone", "everyone")
+                    .add_string_choice("Contributors", "contributors")
+                    .add_string_choice("Managers", "managers")
+                    .required(true)
+            })
+        })
+        .create_option(|option| {
+            playlist_name_option(
+                option
+                    .name("log")
+                    .description("See the latest changes to a server playlist.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            )
+        })
+}
+
+fn fav_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("fav")
+        .description("Add the song playing in your voice channel to your favorites.")
+}
+
+fn favs_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("favs")
+        .description("View and play your favorite songs.")
+        .create_option(|option| {
+            option
+                .name("list")
+                .description("View your favorites.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("play")
+                .description("Add all of your favorites to your queue.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("remove")
+                .description("Remove a song from your favorites.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("position")
+                        .description("The song's position, from /favs list.")
+                        .kind(application_command::ApplicationCommandOptionType::Integer)
+                        .required(true)
+                })
+        })
+}
+
+fn stats_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("stats")
+        .description("View listening stats for this server.")
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("Only count songs queued by this user.")
+                .kind(application_command::ApplicationCommandOptionType::User)
+                .required(false)
+        })
+}
+
+fn history_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("history")
+        .description("Work with this server's play history.")
+        .create_option(|option| {
+            option
+                .name("export")
+                .description("Export every play as a file.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("format")
+                        .description("The kind of file to export, CSV by default.")
+                        .kind(application_command::ApplicationCommandOptionType::String)
+                        .add_string_choice("CSV", "csv")
+                        .add_string_choice("JSON", "json")
+                        .required(false)
+                })
+        })
+}
+
+fn achievements_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("achievements")
+        .description("View the achievements you've unlocked in this server.")
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("View another user's achievements.")
+                .kind(application_command::ApplicationCommandOptionType::User)
+                .required(false)
+        })
+}
+
+fn top_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("top")
+        .description("View the most played songs and artists in this server.")
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("Only count songs queued by this user.")
+                .kind(application_command::ApplicationCommandOptionType::User)
+                .required(false)
+        })
+}
+
+fn leaderboard_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("leaderboard")
+        .description("View who has queued the most played songs.")
+        .create_option(|option| {
+            option
+                .name("period")
+                .description("How far back to count.")
+                .kind(application_command::ApplicationCommandOptionType::String)
+                .add_string_choice("This week", "week")
+                .add_string_choice("This month", "month")
+                .add_string_choice("All time", "all_time")
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("page")
+                .description("Which page of the leaderboard to show.")
+                .kind(application_command::ApplicationCommandOptionType::Integer)
+                .required(false)
+        })
+}
+
+fn prefs_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("prefs")
+        .description("View or change your preferences.")
+        .create_option(|option| {
+            option
+                .name("dm_notifications")
+                .description("Get a direct message when your songs start playing.")
+                .kind(application_command::ApplicationCommandOptionType::Boolean)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("mention")
+                .description("Mention you when your songs are announced.")
+                .kind(application_command::ApplicationCommandOptionType::Boolean)
+                .required(false)
+        })
+        .create_option(|option| {
+            option
+                .name("language")
+                .description("Language for responses to your commands, or \"default\".")
+                .kind(application_command::ApplicationCommandOptionType::String)
+                .required(false)
+        })
+}
+
+fn settings_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("settings")
+        .description("View or change settings for this server.")
+        .create_option(|option| {
+            option
+                .name("show")
+                .description("View the settings for this server.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("queue")
+                .description("Change how songs are picked from everyone's queues.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("strategy")
+                        .description("How the next song is picked.")
+                        .kind(application_command::ApplicationCommandOptionType::String)
+                        .add_string_choice("Take turns", "round_robin")
+                        .add_string_choice("Request order", "fifo")
+                        .required(false)
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("drop_absent")
+                        .description("Drop songs queued by people who have left voice.")
+                        .kind(application_command::ApplicationCommandOptionType::Boolean)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("command-channels")
+                .description("Change which text channels music commands can be used in.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommandGroup)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("add")
+                        .description("Allow music commands in a channel.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                        .create_sub_option(|channel_option| {
+                            channel_option
+                                .name("channel")
+                                .description("The text channel.")
+                                .kind(application_command::ApplicationCommandOptionType::Channel)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("remove")
+                        .description("Stop allowing music commands in a channel.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                        .create_sub_option(|channel_option| {
+                            channel_option
+                                .name("channel")
+                                .description("The text channel.")
+                                .kind(application_command::ApplicationCommandOptionType::Channel)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("clear")
+                        .description("Allow music commands in every channel.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("voice-channels")
+                .description("Change which voice channels the bots won't join.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommandGroup)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("block")
+                        .description("Stop the bots from joining a voice channel.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                        .create_sub_option(|channel_option| {
+                            channel_option
+                                .name("channel")
+                                .description("The voice channel.")
+                                .kind(application_command::ApplicationCommandOptionType::Channel)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("unblock")
+                        .description("Let the bots join a voice channel again.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                        .create_sub_option(|channel_option| {
+                            channel_option
+                                .name("channel")
+                                .description("The voice channel.")
+                                .kind(application_command::ApplicationCommandOptionType::Channel)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("clear")
+                        .description("Let the bots join every voice channel.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("announce-channel")
+                .description("Change where songs and other updates are posted.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommandGroup)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("lock")
+                        .description("Always post updates in a text channel.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                        .create_sub_option(|channel_option| {
+                            channel_option
+                                .name("channel")
+                                .description("The text channel.")
+                                .kind(application_command::ApplicationCommandOptionType::Channel)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("unlock")
+                        .description("Post updates wherever commands were last used.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("notify")
+                        .description("Change whether a notice is posted when updates move to another channel.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                        .create_sub_option(|enabled_option| {
+                            enabled_option
+                                .name("enabled")
+                                .description("Post a notice in the old channel.")
+                                .kind(application_command::ApplicationCommandOptionType::Boolean)
+                                .required(true)
+                        })
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("follow")
+                .description("Change whether the bot keeps playing when it's moved to follow someone.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("enabled")
+                        .description("Keep playing if the bot is moved to the channel the last song's requester is in.")
+                        .kind(application_command::ApplicationCommandOptionType::Boolean)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("announcements")
+                .description("Change whether songs are announced in voice before they play.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("enabled")
+                        .description("Read out each song and who queued it.")
+                        .kind(application_command::ApplicationCommandOptionType::Boolean)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("music-mode")
+                .description("Change whether searches prefer official audio.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("enabled")
+                        .description("Pick official audio over lyric videos and live performances.")
+                        .kind(application_command::ApplicationCommandOptionType::Boolean)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("gapless")
+                .description("Change whether songs from the same album play without a gap.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("enabled")
+                        .description("Load the next song from an album or playlist before the last one ends.")
+                        .kind(application_command::ApplicationCommandOptionType::Boolean)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("now-playing-cards")
+                .description("Change whether songs starting to play are shown as an image.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("enabled")
+                        .description("Show the song's art, who queued it and its progress in a generated image.")
+                        .kind(application_command::ApplicationCommandOptionType::Boolean)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("session-threads")
+                .description("Change whether each listening session gets its own thread.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("enabled")
+                        .description("Post updates in a thread that's archived when playback stops.")
+                        .kind(application_command::ApplicationCommandOptionType::Boolean)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("audit-channel")
+                .description("Change where a record of music actions is posted.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommandGroup)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("set")
+                        .description("Post who queued, skipped, cleared and changed settings in a text channel.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                        .create_sub_option(|channel_option| {
+                            channel_option
+                                .name("channel")
+                                .description("The text channel.")
+                                .kind(application_command::ApplicationCommandOptionType::Channel)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("clear")
+                        .description("Stop posting a record of music actions.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("playlist-role")
+                .description("Change who contributes to server playlists.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommandGroup)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("set")
+                        .description("Let members with a role contribute to server playlists.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                        .create_sub_option(|role_option| {
+                            role_option
+                                .name("role")
+                                .description("The role.")
+                                .kind(application_command::ApplicationCommandOptionType::Role)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("clear")
+                        .description("Only let playlist managers contribute to server playlists.")
+                        .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                })
+        })
+}
+
+fn block_rule_options(
+    option: &mut serenity::builder::CreateApplicationCommandOption,
+) -> &mut serenity::builder::CreateApplicationCommandOption {
+    option
+        .create_sub_option(|sub_option| {
+            sub_option
+                .name("kind")
+                .description("What to match songs by.")
+                .kind(application_command::ApplicationCommandOptionType::String)
+                .add_string_choice("Domain", "domain")
+                .add_string_choice("Video ID", "video")
+                .add_string_choice("Title keyword", "keyword")
+                .required(true)
+        })
+        .create_sub_option(|sub_option| {
+            sub_option
+                .name("value")
+                .description("The domain, video ID or keyword.")
+                .kind(application_command::ApplicationCommandOptionType::String)
+                .required(true)
+        })
+}
+
+fn blocklist_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("blocklist")
+        .description("Manage songs that can't be queued in this server.")
+        .create_option(|option| {
+            block_rule_options(
+                option
+                    .name("add")
+                    .description("Block songs from being queued.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            )
+        })
+        .create_option(|option| {
+            block_rule_options(
+                option
+                    .name("remove")
+                    .description("Allow blocked songs to be queued again.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            )
+        })
+        .create_option(|option| {
+            option
+                .name("list")
+                .description("View everything that's blocked.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+}
+
+fn remove_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("remove")
+        .description("Remove songs from anyone's queue. Needs the Manage Server permission.")
+        .create_option(|option| {
+            option
+                .name("range")
+                .description(
+                    "Remove the songs coming up in your voice channel between two positions.",
+                )
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("from")
+                        .description("The first position to remove, from /queue channel.")
+                        .kind(application_command::ApplicationCommandOptionType::Integer)
+                        .required(true)
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("to")
+                        .description("The last position to remove, from /queue channel.")
+                        .kind(application_command::ApplicationCommandOptionType::Integer)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("Remove every song someone has queued.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("member")
+                        .description("The user whose songs to remove.")
+                        .kind(application_command::ApplicationCommandOptionType::User)
+                        .required(true)
+                })
+        })
+}
+
+fn musicban_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("musicban")
+        .description("Stop someone from queueing and controlling music in this server.")
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("The user to ban.")
+                .kind(application_command::ApplicationCommandOptionType::User)
+                .required(true)
+        })
+}
+
+fn musicunban_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("musicunban")
+        .description("Let someone use music commands again.")
+        .create_option(|option| {
+            option
+                .name("user")
+                .description("The user to unban.")
+                .kind(application_command::ApplicationCommandOptionType::User)
+                .required(true)
+        })
+}
+
+fn sync_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("sync")
+        .description("Register commands with Discord again. Only the bot's owner can use this.")
+        .create_option(|option| {
+            option
+                .name("global")
+                .description("Register commands for every server instead of only this one.")
+                .kind(application_command::ApplicationCommandOptionType::Boolean)
+                .required(false)
+        })
+}
+
+fn debug_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("debug")
+        .description("See what's going wrong with the bot. Only the bot's owner can use this.")
+        .create_option(|option| {
+            option
+                .name("status")
+                .description("Show whether maintenance mode is on and how many bots are playing.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("errors")
+                .description("Show the last few times youtube-dl failed and what it said.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("extractor")
+                .description("Show which version of youtube-dl is installed.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+}
+
+fn usage_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command.name("usage").description(
+        "Show the busiest servers and slowest commands. Only the bot's owner can use this.",
+    )
+}
+
+fn maintenance_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("maintenance")
+        .description("Stop starting new songs before a restart. Only the bot's owner can use this.")
+        .create_option(|option| {
+            option
+                .name("on")
+                .description("Let songs that are playing finish, then leave voice.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("off")
+                .description("Start taking play commands again.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+}
+
+fn broadcast_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("broadcast")
+        .description(
+            "Post a message in every server where a song is playing. Only the bot's owner can use this.",
+        )
+        .create_option(|option| {
+            option
+                .name("message")
+                .description("What to say, like when the bot is about to restart.")
+                .kind(application_command::ApplicationCommandOptionType::String)
+                .required(true)
+        })
+}
+
+fn update_extractor_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command.name("update-extractor").description(
+        "Download the latest youtube-dl if there's a newer one. Only the bot's owner can use this.",
+    )
+}
+
+fn library_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("library")
+        .description("Manage the local music library.")
+        .create_option(|option| {
+            option
+                .name("rescan")
+                .description(
+                    "Scan the library for new, changed and removed songs. Only the bot's owner can use this.",
+                )
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+}
+
+fn sfx_name_option(
+    option: &mut serenity::builder::CreateApplicationCommandOption,
+) -> &mut serenity::builder::CreateApplicationCommandOption {
+    option.create_sub_option(|sub_option| {
+        sub_option
+            .name("name")
+            .description("The sound effect's name.")
+            .kind(application_command::ApplicationCommandOptionType::String)
+            .required(true)
+    })
+}
+
+fn sfx_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("sfx")
+        .description("Play sound effects in your voice channel.")
+        .create_option(|option| {
+            sfx_name_option(
+                option
+                    .name("play")
+                    .description("Play a sound effect.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            )
+        })
+        .create_option(|option| {
+            option
+                .name("list")
+                .description("View the sound effects in this server.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            sfx_name_option(
+                option
+                    .name("add")
+                    .description("Add a sound effect to this server.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            )
+            .create_sub_option(|sub_option| {
+                sub_option
+                    .name("url")
+                    .description("A direct link to a short audio file.")
+                    .kind(application_command::ApplicationCommandOptionType::String)
+                    .required(true)
+            })
+            .create_sub_option(|sub_option| {
+                sub_option
+                    .name("volume")
+                    .description("How loud to play it, as a percentage. Defaults to 100.")
+                    .kind(application_command::ApplicationCommandOptionType::Integer)
+                    .required(false)
+            })
+        })
+        .create_option(|option| {
+            sfx_name_option(
+                option
+                    .name("remove")
+                    .description("Remove a sound effect from this server.")
+                    .kind(application_command::ApplicationCommandOptionType::SubCommand),
+            )
+        })
+}
+
+fn trivia_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("trivia")
+        .description("Guess songs from short excerpts.")
+        .create_option(|option| {
+            option
+                .name("start")
+                .description("Start a game in your voice channel, answers are typed here.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("rounds")
+                        .description("How many songs to guess, up to 20.")
+                        .kind(application_command::ApplicationCommandOptionType::Integer)
+                        .required(false)
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("playlist")
+                        .description(
+                            "Use songs from one of your playlists instead of this server's history.",
+                        )
+                        .kind(application_command::ApplicationCommandOptionType::String)
+                        .required(false)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("stop")
+                .description("End the game early.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+}
+
+fn party_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("party")
+        .description("Host a listening party where you control the music without votes.")
+        .create_option(|option| {
+            option
+                .name("start")
+                .description("Start a party in your voice channel, hosted by you.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("end")
+                .description("End the party in your voice channel.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+}
+
+fn schedule_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("schedule")
+        .description("Play music in a voice channel automatically at set times.")
+        .create_option(|option| {
+            option
+                .name("add")
+                .description("Schedule a playlist or link to play. Updates are posted here.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("cron")
+                        .description("When to play, like \"0 18 * * fri\" for Fridays at 18:00.")
+                        .kind(application_command::ApplicationCommandOptionType::String)
+                        .required(true)
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("channel")
+                        .description("The voice channel to play in.")
+                        .kind(application_command::ApplicationCommandOptionType::Channel)
+                        .required(true)
+                })
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("play")
+                        .description("The name of one of your playlists, or a link.")
+                        .kind(application_command::ApplicationCommandOptionType::String)
+                        .required(true)
+                })
+        })
+        .create_option(|option| {
+            option
+                .name("list")
+                .description("See what's scheduled in this server.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("remove")
+                .description("Stop a schedule from playing.")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+                .create_sub_option(|sub_option| {
+                    sub_option
+                        .name("id")
+                        .description("The schedule's number, from /schedule list.")
+                        .kind(application_command::ApplicationCommandOptionType::Integer)
+                        .required(true)
+                })
+        })
+}
+
+fn pet_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("pet")
+        .description("Look for a pet to add to your collection.")
+}
+
+fn pets_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("pets")
+        .description("View the pets you've found.")
+}
+
+fn secret_highfive_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command.name("highfive").description("\\^_^")
+}
+
+fn secret_streak_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("streak")
+        .description("View high-five streaks")
+        .create_option(|option| {
+            option
+                .name("show")
+                .description("View your high-five streak")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+        .create_option(|option| {
+            option
+                .name("leaderboard")
+                .description("View the longest high-five streaks in this server")
+                .kind(application_command::ApplicationCommandOptionType::SubCommand)
+        })
+}
+
+fn greet_command<'c>(
+    command: &'c mut serenity::builder::CreateApplicationCommand,
+    name: &str,
+) -> &'c mut serenity::builder::CreateApplicationCommand {
+    command.name(name).description("Greet everyone.")
+}
+
+fn help_command(
+    command: &mut serenity::builder::CreateApplicationCommand,
+) -> &mut serenity::builder::CreateApplicationCommand {
+    command
+        .name("help")
+        .description("See what commands you can use.")
+        .create_option(|option| {
+            option
+                .name("all")
+                .description("Also show commands that need a permission you don't have.")
+                .kind(application_command::ApplicationCommandOptionType::Boolean)
+                .required(false)
+        })
+}
+
+fn script_command<'c>(
+    command: &'c mut serenity::builder::CreateApplicationCommand,
+    name: &str,
+) -> &'c mut serenity::builder::CreateApplicationCommand {
+    command
+        .name(name)
+        .description("Custom command.")
+        .create_option(|option| {
+            option
+                .name("input")
+                .description("Input passed to the command.")
+                .kind(application_command::ApplicationCommandOptionType::String)
+                .required(false)
+        })
+}
+
+fn build_command(
+    f: impl FnOnce(
+        &mut serenity::builder::CreateApplicationCommand,
+    ) -> &mut serenity::builder::CreateApplicationCommand,
+) -> serde_json::Value {
+    let mut command = serenity::builder::CreateApplicationCommand::default();
+    f(&mut command);
+    serde_json::Value::Object(serenity::utils::hashmap_to_json_map(command.0))
+}
+
+/// Every command the bot should have registered, including script and plugin commands, and who
+/// can use each of them.
+fn available_commands(
+    frontend: &crate::frontend::Frontend,
+) -> Vec<(serde_json::Value, CommandPermission)> {
+    use CommandPermission::{Everyone, ManageServer, Owner};
+
+    let mut commands = vec![
+        (build_command(play_command), Everyone),
+        (build_command(playmany_command), Everyone),
+        (build_command(queue_message_command), Everyone),
+        (build_command(resume_command), Everyone),
+        (build_command(replace_command), Everyone),
+        (build_command(pause_command), Everyone),
+        (build_command(skip_command), Everyone),
+        (build_command(stop_command), Everyone),
+        (build_command(transfer_command), Everyone),
+        (build_command(summon_command), Everyone),
+        (build_command(disconnect_command), Everyone),
+        (build_command(undo_command), Everyone),
+        (build_command(dedupe_command), Everyone),
+        (build_command(queue_command), Everyone),
+        (build_command(nowplaying_command), Everyone),
+        (build_command(songinfo_command), Everyone),
+        (build_command(voteinfo_command), Everyone),
+        (build_command(listening_user_command), Everyone),
+        (build_command(playlist_command), Everyone),
+        (build_command(fav_command), Everyone),
+        (build_command(favs_command), Everyone),
+        (build_command(stats_command), Everyone),
+        (build_command(history_command), ManageServer),
+        (build_command(achievements_command), Everyone),
+        (build_command(top_command), Everyone),
+        (build_command(leaderboard_command), Everyone),
+        (build_command(prefs_command), Everyone),
+        (build_command(settings_command), ManageServer),
+        (build_command(blocklist_command), ManageServer),
+        (build_command(remove_command), ManageServer),
+        (build_command(musicban_command), ManageServer),
+        (build_command(musicunban_command), ManageServer),
+        (build_command(sfx_command), Everyone),
+        (build_command(trivia_command), Everyone),
+        (build_command(schedule_command), Everyone),
+        (build_command(party_command), Everyone),
+        (build_command(sync_command), Owner),
+        (build_command(debug_command), Owner),
+        (build_command(usage_command), Owner),
+        (build_command(maintenance_command), Owner),
+        (build_command(broadcast_command), Owner),
+        (build_command(help_command), Everyone),
+    ];
+
+    if frontend.extractor_updater.is_some() {
+        commands.push((build_command(update_extractor_command), Owner));
+    }
+
+    if frontend.library.is_some() {
+        commands.push((build_command(library_command), Owner));
+    }
+
+    if frontend.config.pets.is_some() {
+        commands.push((build_command(pet_command), Everyone));
+        commands.push((build_command(pets_command), Everyone));
+    }
+
+    if frontend.config.secret_highfive.is_some() {
+        commands.push((build_command(secret_highfive_command), Everyone));
+        commands.push((build_command(secret_streak_command), Everyone));
+    }
+
+    for name in frontend.config.greets.keys() {
+        commands.push((
+            build_command(|command| greet_command(command, name)),
+            Everyone,
+        ));
+    }
+
+    for name in frontend.scripts.command_names() {
+        commands.push((
+            build_command(|command| script_command(command, &name)),
+            Everyone,
+        ));
+    }
+
+    for plugin in frontend.plugins.iter() {
+        commands.push((build_command(|command| plugin.register(command)), Everyone));
+    }
+
+    commands
+}
+
+/// Subcommands that need a different permission from the rest of their command, by their command's
+/// name and their own.
+const SUBCOMMAND_PERMISSIONS: &[(&str, &str, CommandPermission)] = &[
+    ("blocklist", "list", CommandPermission::Everyone),
+    ("schedule", "add", CommandPermission::ManageServer),
+    ("settings", "show", CommandPermission::Everyone),
+    ("sfx", "add", CommandPermission::ManageServer),
+    ("sfx", "remove", CommandPermission::ManageServer),
+];
+
+fn desired_commands(frontend: &crate::frontend::Frontend) -> Vec<serde_json::Value> {
+    available_commands(frontend)
+        .into_iter()
+        .map(|(command, _)| command)
+        .collect()
+}
+
+/// Every way of using the commands up to the given permission, from the same commands that are
+/// registered with Discord, so it can't fall out of date.
+pub fn help_commands(
+    frontend: &crate::frontend::Frontend,
+    max_permission: CommandPermission,
+) -> Vec<HelpCommand> {
+    let mut help_commands = Vec::new();
+    for (command, permission) in available_commands(frontend) {
+        let name = command["name"].as_str().unwrap_or_default();
+        let kind = match command["type"].as_u64() {
+            Some(2) => HelpCommandKind::User,
+            Some(3) => HelpCommandKind::Message,
+            // Slash commands are the default type.
+            _ => HelpCommandKind::Slash,
+        };
+        if kind != HelpCommandKind::Slash {
+            help_commands.push(HelpCommand {
+                kind,
+                usage: name.to_string(),
+                description: String::new(),
+                permission,
+            });
+            continue;
+        }
+        add_help_commands(
+            &mut help_commands,
+            &format!("/{}", name),
+            &command,
+            permission,
+        );
+    }
+    help_commands.retain(|help_command| help_command.permission <= max_permission);
+    help_commands
+}
+
+// Commands with subcommands are listed once for each subcommand, since they're used like separate
+// commands.
+fn add_help_commands(
+    help_commands: &mut Vec<HelpCommand>,
+    usage: &str,
+    command: &serde_json::Value,
+    permission: CommandPermission,
+) {
+    let options = command["options"].as_array().map_or(&[][..], Vec::as_slice);
+    let subcommands: Vec<_> = options
+        .iter()
+        // Subcommands and subcommand groups are options of types 1 and 2.
+        .filter(|option| matches!(option["type"].as_u64(), Some(1 | 2)))
+        .collect();
+    if !subcommands.is_empty() {
+        for subcommand in subcommands {
+            let subcommand_name = subcommand["name"].as_str().unwrap_or_default();
+            let subcommand_permission = SUBCOMMAND_PERMISSIONS
+                .iter()
+                .find(|(command_name, name, _)| {
+                    usage == format!("/{}", command_name) && subcommand_name == *name
+                })
+                .map_or(permission, |(_, _, permission)| *permission);
+            add_help_commands(
+                help_commands,
+                &format!("{} {}", usage, subcommand_name),
+                subcommand,
+                subcommand_permission,
+            );
+        }
+        return;
+    }
+
+    let mut usage = usage.to_string();
+    for option in options {
+        let option_name = option["name"].as_str().unwrap_or_default();
+        if option["required"].as_bool().unwrap_or(false) {
+            usage.push_str(&format!(" <{}>", option_name));
+        } else {
+            usage.push_str(&format!(" [{}]", option_name));
+        }
+    }
+    help_commands.push(HelpCommand {
+        kind: HelpCommandKind::Slash,
+        usage,
+        description: command["description"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string(),
+        permission,
+    });
+}
+
+/// Picks out the parts of a command that we set, filling in the defaults Discord leaves out, so
+/// commands we build can be compared with ones Discord sends back.
+fn normalize_command(command: &serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        // Slash commands are the default type.
+        "type": command["type"].as_u64().unwrap_or(1),
+        "name": command["name"],
+        // Context menu commands don't have a description.
+        "description": command["description"].as_str().unwrap_or_default(),
+        "options": normalize_options(&command["options"]),
+    })
+}
+
+fn normalize_options(options: &serde_json::Value) -> serde_json::Value {
+    let options = match options.as_array() {
+        Some(options) => options,
+        None => return serde_json::json!([]),
+    };
+
+    options
+        .iter()
+        .map(|option| {
+            serde_json::json!({
+                "type": option["type"],
+                "name": option["name"],
+                "description": option["description"],
+                "required": option["required"].as_bool().unwrap_or(false),
+                "choices": option["choices"].as_array().cloned().unwrap_or_default(),
+                "options": normalize_options(&option["options"]),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CommandScope {
+    Global,
+    Guild(GuildId),
+}
+
+/// Brings the commands registered in a scope in line with `desired_commands`, only creating,
+/// editing or deleting the ones that changed.
+async fn sync_commands(
+    http: &serenity::http::Http,
+    scope: CommandScope,
+    desired_commands: &[serde_json::Value],
+) -> serenity::Result<()> {
+    let existing_commands = match scope {
+        CommandScope::Global => http.get_global_application_commands().await?,
+        CommandScope::Guild(guild_id) => http.get_guild_application_commands(guild_id.0).await?,
+    };
+
+    let mut unchanged_count = 0;
+    for desired_command in desired_commands {
+        let name = desired_command["name"].as_str().unwrap_or_default();
+        let maybe_existing_command = existing_commands
+            .iter()
+            .find(|existing_command| existing_command.name == name);
+        match maybe_existing_command {
+            Some(existing_command) => {
+                if normalize_command(&serde_json::to_value(existing_command)?)
+                    == normalize_command(desired_command)
+                {
+                    unchanged_count += 1;
+                    continue;
+                }
+
+                log::trace!("Updating {:?} application command {}", scope, name);
+                match scope {
+                    CommandScope::Global => {
+                        http.edit_global_application_command(existing_command.id.0, desired_command)
+                            .await?
+                    }
+                    CommandScope::Guild(guild_id) => {
+                        http.edit_guild_application_command(
+                            guild_id.0,
+                            existing_command.id.0,
+                            desired_command,
+                        )
+                        .await?
+                    }
+                };
+            }
+            None => {
+                log::trace!("Creating {:?} application command {}", scope, name);
+                match scope {
+                    CommandScope::Global => {
+                        http.create_global_application_command(desired_command)
+                            .await?
+                    }
+                    CommandScope::Guild(guild_id) => {
+                        http.create_guild_application_command(guild_id.0, desired_command)
+                            .await?
+                    }
+                };
+            }
+        }
+    }
+
+    let removed_commands = existing_commands.iter().filter(|existing_command| {
+        !desired_commands
+            .iter()
+            .any(|desired_command| desired_command["name"] == existing_command.name.as_str())
+    });
+    for removed_command in removed_commands {
+        log::trace!(
+            "Deleting {:?} application command {}",
+            scope,
+            removed_command.name
+        );
+        match scope {
+            CommandScope::Global => {
+                http.delete_global_application_command(removed_command.id.0)
+                    .await?
+            }
+            CommandScope::Guild(guild_id) => {
+                http.delete_guild_application_command(guild_id.0, removed_command.id.0)
+                    .await?
+            }
+        };
+    }
+
+    log::trace!(
+        "{} {:?} application commands were already up to date",
+        unchanged_count,
+        scope
+    );
+    Ok(())
+}
+
+pub async fn register_commands(
+    http: impl AsRef<serenity::http::Http>,
+    guild_id: Option<GuildId>,
+    frontend: &crate::frontend::Frontend,
+) -> serenity::Result<()> {
+    let http_ref = http.as_ref();
+    let commands = desired_commands(frontend);
+    match guild_id {
+        // Commands registered in a guild show up alongside global ones, so clear those out to
+        // avoid duplicates.
+        Some(guild_id) => {
+            sync_commands(http_ref, CommandScope::Global, &[]).await?;
+            sync_commands(http_ref, CommandScope::Guild(guild_id), &commands).await?;
         }
+        None => sync_commands(http_ref, CommandScope::Global, &commands).await?,
     };
 
     Ok(())
@@ -1,19 +1,31 @@
-use mrvn_back_ytdl::PlayConfig;
+use crate::entitlements::PremiumFeature;
+use crate::error::Error as FrontendError;
+use crate::message::MESSAGE_KEYS;
+use crate::stats::Achievement;
+use crate::template::{fill_template, template_placeholders};
+use mrvn_back_ytdl::{MediaServerConfig, MediaServerKind, Normalization, PlayConfig, TtsConfig};
 use serde::de::Error;
 use serde::Deserialize;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct CommandBot {
     pub token: String,
     pub application_id: u64,
     pub guild_id: Option<u64>,
+    /// Uses the number of shards Discord recommends if this isn't set.
+    pub shard_count: Option<u64>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct VoiceBot {
     pub token: String,
     pub application_id: u64,
+    /// Uses the number of shards Discord recommends if this isn't set.
+    pub shard_count: Option<u64>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -28,12 +40,286 @@ pub struct FfmpegConfig {
     pub args: Vec<String>,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct TtsCommandConfig {
+    pub name: String,
+    /// `{text}` is replaced with what's being read out.
+    pub args: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MediaServerAccountConfig {
+    pub kind: MediaServerKind,
+    pub url: String,
+    /// Subsonic servers sign in with a username and password.
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    /// Jellyfin servers sign in with an API key.
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default)]
+    pub search_by_default: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LibraryConfig {
+    pub directory: String,
+    /// How often to scan the directory again for changes. Without this, it's only scanned when
+    /// the bot starts and with `/library rescan`.
+    pub rescan_interval_secs: Option<u64>,
+    #[serde(default)]
+    pub search_by_default: bool,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct SecretHighfive {
     pub image_url: String,
     pub timezone: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct PetConfig {
+    pub name: String,
+    pub image_url: String,
+    /// How likely this pet is to be found compared to the others.
+    #[serde(default = "default_pet_weight")]
+    pub weight: u32,
+}
+
+fn default_pet_weight() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ShinyPetConfig {
+    pub name: String,
+    pub image_url: String,
+    /// Each `/pet` has a 1 in this many chance of finding the shiny instead.
+    #[serde(default = "default_shiny_odds")]
+    pub odds: u32,
+}
+
+fn default_shiny_odds() -> u32 {
+    8192
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PetsConfig {
+    pub pets: Vec<PetConfig>,
+    pub shiny: Option<ShinyPetConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebOAuthConfig {
+    pub client_secret: String,
+    pub redirect_uri: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebConfig {
+    pub bind_address: String,
+    /// Where playback events, metrics and health are served when the dashboard is on, since
+    /// `bind_address` has to be reachable by anyone who uses the dashboard then. They stay on
+    /// `bind_address` if this isn't set.
+    pub internal_bind_address: Option<String>,
+    pub oauth: Option<WebOAuthConfig>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookKind {
+    Discord,
+    Http,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebhookConfig {
+    pub url: String,
+    pub kind: WebhookKind,
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct TrackLogConfig {
+    pub webhook_url: Option<String>,
+    pub thread_name: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CooldownConfig {
+    /// Zero isn't allowed, since the command could never be used.
+    pub max_uses: NonZeroUsize,
+    pub per_secs: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct GreetConfig {
+    /// Sent in response to the command.
+    pub message: Option<String>,
+    /// A local file or direct link to an audio file, played in the user's voice channel.
+    pub clip: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LeaderboardConfig {
+    pub timezone: String,
+    pub week_start: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ResumeConfig {
+    /// Only songs at least this long have their position saved.
+    pub min_duration_secs: u64,
+    pub max_age_days: u64,
+}
+
+impl ResumeConfig {
+    pub fn max_age(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.max_age_days * 24 * 60 * 60)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct WorkingMessageConfig {
+    /// How long a command can run before a response is shown while it finishes.
+    pub delay_ms: u64,
+    /// Show the term being searched for instead of Discord's "thinking" state, for commands that
+    /// search.
+    pub show_query: bool,
+}
+
+/// How songs are turned down while greets, sound effects and announcements play over them.
+#[derive(Debug, Deserialize, Clone)]
+pub struct DuckingConfig {
+    /// How loud the song is while ducked, from 0 to 1.
+    pub volume: f32,
+    /// How long the song takes to fade down and back up. Zero changes it straight away.
+    pub fade_ms: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ExtractorUpdateConfig {
+    /// The binary that's replaced by updates. This has to be an absolute path, and `ytdl.name`
+    /// should be the same path so it's the binary that runs.
+    #[serde(deserialize_with = "absolute_path")]
+    pub binary_path: PathBuf,
+    /// A GitHub API URL for the latest release, which has the version as its tag.
+    #[serde(default = "default_extractor_release_url")]
+    pub release_url: String,
+    /// Where the latest release's binary is downloaded from.
+    #[serde(default = "default_extractor_download_url")]
+    pub download_url: String,
+    /// The latest release's SHA2-256SUMS file, which downloads are checked against. It has to list
+    /// the file named at the end of `download_url`.
+    #[serde(default = "default_extractor_checksums_url")]
+    pub checksums_url: String,
+    /// How often to check for a new release. Without this, updates only happen with
+    /// `/update-extractor`.
+    pub check_interval_secs: Option<u64>,
+}
+
+/// Changes how one kind of message looks, keyed by its message string in `embed_styles`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct EmbedStyle {
+    #[serde(default, deserialize_with = "from_hex_option")]
+    pub color: Option<u32>,
+    /// Send the message as plain text instead of an embed. Titles, footers and thumbnails are
+    /// only shown in embeds.
+    #[serde(default)]
+    pub plain_text: bool,
+    pub title: Option<String>,
+    pub footer: Option<String>,
+    /// Show the song's thumbnail, for messages that have one.
+    #[serde(default = "default_show_thumbnail")]
+    pub thumbnail: bool,
+}
+
+fn default_show_thumbnail() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClusterConfig {
+    pub redis_url: String,
+    /// Identifies this process in speaker leases, so it has to be different for each one.
+    pub node_id: String,
+    /// This process' position in the cluster, from 0 up to `node_count - 1`.
+    pub node_index: u64,
+    pub node_count: u64,
+    /// How long a speaker stays leased to a process that's stopped renewing it.
+    #[serde(default = "default_lease_secs")]
+    pub lease_secs: u64,
+}
+
+fn default_lease_secs() -> u64 {
+    30
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct EntitlementsConfig {
+    /// Only these features are limited, everything else can be used in any guild.
+    pub features: Vec<PremiumFeature>,
+    /// Guilds that can always use the features.
+    #[serde(default)]
+    pub guilds: Vec<u64>,
+    /// Guilds with an active entitlement to any of these SKUs can use the features.
+    #[serde(default)]
+    pub sku_ids: Vec<u64>,
+    /// How long a guild's entitlements are remembered before Discord is asked again.
+    #[serde(default = "default_entitlement_cache_secs")]
+    pub cache_secs: u64,
+}
+
+fn default_entitlement_cache_secs() -> u64 {
+    600
+}
+
+fn default_extractor_release_url() -> String {
+    "https://api.github.com/repos/yt-dlp/yt-dlp/releases/latest".to_string()
+}
+
+fn default_music_mode_search_prefix() -> String {
+    "ytsearch5".to_string()
+}
+
+fn default_extractor_download_url() -> String {
+    "https://github.com/yt-dlp/yt-dlp/releases/latest/download/yt-dlp".to_string()
+}
+
+fn default_extractor_checksums_url() -> String {
+    "https://github.com/yt-dlp/yt-dlp/releases/latest/download/SHA2-256SUMS".to_string()
+}
+
+impl Default for WorkingMessageConfig {
+    fn default() -> Self {
+        WorkingMessageConfig {
+            delay_ms: 50,
+            show_query: false,
+        }
+    }
+}
+
+impl Default for DuckingConfig {
+    fn default() -> Self {
+        DuckingConfig {
+            volume: 0.3,
+            fade_ms: 250,
+        }
+    }
+}
+
+impl Default for LeaderboardConfig {
+    fn default() -> Self {
+        LeaderboardConfig {
+            timezone: "UTC".to_string(),
+            week_start: "monday".to_string(),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     #[serde(deserialize_with = "from_hex")]
@@ -42,27 +328,115 @@ pub struct Config {
     pub response_embed_color: u32,
     #[serde(deserialize_with = "from_hex")]
     pub error_embed_color: u32,
+    // Keyed by message string.
+    #[serde(default)]
+    pub embed_styles: HashMap<String, EmbedStyle>,
 
     pub skip_votes_required: usize,
     pub stop_votes_required: usize,
+    pub vote_expiry_secs: Option<u64>,
+    pub undo_window_secs: Option<u64>,
+    /// How many songs each user can have queued at once. There's no limit without this.
+    pub max_user_queue_length: Option<usize>,
+    /// How many songs can be queued in a guild at once, across everyone's queues.
+    pub max_guild_queue_length: Option<usize>,
+    // Keyed by command name.
+    #[serde(default)]
+    pub cooldowns: HashMap<String, CooldownConfig>,
+    // Keyed by role ID.
+    #[serde(default)]
+    pub role_priorities: HashMap<u64, u32>,
 
     pub disconnect_min_inactive_secs: u64,
     pub disconnect_check_interval_secs: u64,
     pub only_disconnect_when_alone: bool,
+    #[serde(default)]
+    pub working_message: WorkingMessageConfig,
 
     pub search_prefix: String,
+    /// Used instead of `search_prefix` in guilds with music mode on. It should ask for a few
+    /// results to pick the official audio from.
+    #[serde(default = "default_music_mode_search_prefix")]
+    pub music_mode_search_prefix: String,
     pub host_blocklist: Vec<String>,
     pub ytdl: YtdlConfig,
+    pub extractor_update: Option<ExtractorUpdateConfig>,
     pub ffmpeg: FfmpegConfig,
+    /// Songs play as they are without this.
+    pub normalization: Option<Normalization>,
+    pub tts: Option<TtsCommandConfig>,
+    #[serde(default)]
+    pub ducking: DuckingConfig,
+    pub media_server: Option<MediaServerAccountConfig>,
+    pub library: Option<LibraryConfig>,
 
     pub command_bot: CommandBot,
     pub voice_bots: Vec<VoiceBot>,
     pub messages: HashMap<String, String>,
+    #[serde(default)]
+    pub languages: HashMap<String, HashMap<String, String>>,
+
+    pub web: Option<WebConfig>,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    #[serde(default)]
+    pub scripts: Vec<String>,
+    // Keyed by command name.
+    #[serde(default)]
+    pub greets: HashMap<String, GreetConfig>,
+    pub data_directory: Option<String>,
+    pub cluster: Option<ClusterConfig>,
+    #[serde(default)]
+    pub persist_queues: bool,
+    pub handover_socket: Option<String>,
+    #[serde(default)]
+    pub recover_playback: bool,
+    #[serde(default)]
+    pub leaderboard: LeaderboardConfig,
+    pub track_log: Option<TrackLogConfig>,
+    pub resume_positions: Option<ResumeConfig>,
+    pub repost_action_message_after: Option<usize>,
+    #[serde(default)]
+    pub voice_channel_status: bool,
+    #[serde(default)]
+    pub show_presence: bool,
+    #[serde(default)]
+    pub welcome_new_guilds: bool,
+    pub entitlements: Option<EntitlementsConfig>,
+
+    pub pets: Option<PetsConfig>,
 
     pub secret_highfive: Option<SecretHighfive>,
 }
 
+const DEFAULT_UNDO_WINDOW_SECS: u64 = 120;
+
 impl Config {
+    pub fn undo_window(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.undo_window_secs.unwrap_or(DEFAULT_UNDO_WINDOW_SECS))
+    }
+
+    /// The config with messages replaced by ones in the given language, where they've been
+    /// translated.
+    pub fn localized(&self, language: Option<&str>) -> Cow<'_, Config> {
+        match language.and_then(|language| self.languages.get(language)) {
+            Some(messages) => {
+                let mut config = self.clone();
+                config.messages.extend(
+                    messages
+                        .iter()
+                        .map(|(key, value)| (key.clone(), value.clone())),
+                );
+                Cow::Owned(config)
+            }
+            None => Cow::Borrowed(self),
+        }
+    }
+
+    pub fn embed_style(&self, message_key: &str) -> Option<&EmbedStyle> {
+        self.embed_styles.get(message_key)
+    }
+
     pub fn get_raw_message<'s>(&'s self, message_key: &'s str) -> &'s str {
         match self.messages.get(message_key) {
             Some(template) => template,
@@ -74,32 +448,95 @@ impl Config {
     }
 
     pub fn get_message(&self, message_key: &str, substitutions: &[(&str, &str)]) -> String {
-        let message_template = self.get_raw_message(message_key);
+        fill_template(self.get_raw_message(message_key), substitutions)
+    }
 
-        lazy_static::lazy_static! {
-            static ref SUBSTITUTE_REGEX: regex::Regex = regex::Regex::new(r"\{(\w+)\}").unwrap();
-        }
+    /// Checks every message, including translations, only uses placeholders the bot fills in for
+    /// it, so a typo is caught at startup rather than showing up as a gap in a message. Messages
+    /// the bot never uses are only warned about.
+    pub fn check_messages(&self) -> Result<(), FrontendError> {
+        let achievement_keys = Achievement::ALL.iter().flat_map(|achievement| {
+            [
+                format!("achievement.{}", achievement.name()),
+                format!("achievement.{}.description", achievement.name()),
+            ]
+        });
+        let known_keys: HashMap<Cow<str>, &[&str]> = MESSAGE_KEYS
+            .iter()
+            .map(|(key, placeholders)| (Cow::Borrowed(*key), *placeholders))
+            .chain(achievement_keys.map(|key| (Cow::Owned(key), &[][..])))
+            .collect();
 
-        SUBSTITUTE_REGEX
-            .replace_all(message_template, |caps: &regex::Captures| {
-                let substitute_name = &caps[1];
-                substitutions
-                    .iter()
-                    .find(|(key, _)| *key == substitute_name)
-                    .map(|(_, value)| *value)
-                    .unwrap_or("")
-            })
-            .into_owned()
+        let languages = std::iter::once((None, &self.messages)).chain(
+            self.languages
+                .iter()
+                .map(|(language, messages)| (Some(language), messages)),
+        );
+        for (language, messages) in languages {
+            let mut unused_keys: Vec<_> = Vec::new();
+            for (message_key, template) in messages {
+                let placeholders = match known_keys.get(message_key.as_str()) {
+                    Some(placeholders) => placeholders,
+                    None => {
+                        unused_keys.push(message_key.as_str());
+                        continue;
+                    }
+                };
+                if let Some(placeholder) = template_placeholders(template)
+                    .find(|placeholder| !placeholders.contains(placeholder))
+                {
+                    return Err(FrontendError::UnknownPlaceholder {
+                        message_key: message_key.clone(),
+                        placeholder: placeholder.to_string(),
+                        language: language.cloned(),
+                    });
+                }
+            }
+
+            unused_keys.sort_unstable();
+            for message_key in unused_keys {
+                match language {
+                    Some(language) => log::warn!(
+                        "Message {} in language {} is never used",
+                        message_key,
+                        language
+                    ),
+                    None => log::warn!("Message {} is never used", message_key),
+                }
+            }
+        }
+        Ok(())
     }
 
-    pub fn get_play_config(&self) -> PlayConfig {
+    pub fn get_play_config(&self) -> PlayConfig<'_> {
         PlayConfig {
             search_prefix: &self.search_prefix,
+            music_mode_search_prefix: None,
             host_blocklist: &self.host_blocklist,
             ytdl_name: &self.ytdl.name,
             ytdl_args: &self.ytdl.args,
             ffmpeg_name: &self.ffmpeg.name,
             ffmpeg_args: &self.ffmpeg.args,
+            normalization: self.normalization,
+            tts: self.tts.as_ref().map(|tts| TtsConfig {
+                name: &tts.name,
+                args: &tts.args,
+            }),
+            duck_volume: self.ducking.volume.clamp(0., 1.),
+            duck_fade: std::time::Duration::from_millis(self.ducking.fade_ms),
+            media_server: self
+                .media_server
+                .as_ref()
+                .map(|media_server| MediaServerConfig {
+                    kind: media_server.kind,
+                    url: &media_server.url,
+                    username: &media_server.username,
+                    password: &media_server.password,
+                    api_key: &media_server.api_key,
+                    search_by_default: media_server.search_by_default,
+                }),
+            // The library is kept by the frontend, since it has to be scanned.
+            library: None,
         }
     }
 }
@@ -111,3 +548,86 @@ where
     let s: String = Deserialize::deserialize(deserializer)?;
     u32::from_str_radix(&s, 16).map_err(D::Error::custom)
 }
+
+fn from_hex_option<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    from_hex(deserializer).map(Some)
+}
+
+fn absolute_path<'de, D>(deserializer: D) -> Result<PathBuf, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let path = PathBuf::deserialize(deserializer)?;
+    if !path.is_absolute() {
+        return Err(D::Error::custom(format!(
+            "{} should be an absolute path",
+            path.display()
+        )));
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example_config() -> Config {
+        serde_json::from_str(include_str!("../../config.example.json")).unwrap()
+    }
+
+    #[test]
+    fn example_messages_are_valid() {
+        example_config().check_messages().unwrap();
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders() {
+        let mut config = example_config();
+        config.messages.insert(
+            "response.play_many_failed.term".to_string(),
+            "{position}. {title}".to_string(),
+        );
+        match config.check_messages() {
+            Err(FrontendError::UnknownPlaceholder {
+                message_key,
+                placeholder,
+                language: None,
+            }) => {
+                assert_eq!(message_key, "response.play_many_failed.term");
+                assert_eq!(placeholder, "title");
+            }
+            res => panic!("Expected an unknown placeholder, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn rejects_unknown_placeholders_in_translations() {
+        let mut config = example_config();
+        config.languages.insert(
+            "fr".to_string(),
+            HashMap::from([(
+                "response.play_many_failed.term".to_string(),
+                "{position}. {terme}".to_string(),
+            )]),
+        );
+        match config.check_messages() {
+            Err(FrontendError::UnknownPlaceholder { language, .. }) => {
+                assert_eq!(language.as_deref(), Some("fr"));
+            }
+            res => panic!("Expected an unknown placeholder, got {:?}", res),
+        }
+    }
+
+    #[test]
+    fn ignores_unused_messages() {
+        let mut config = example_config();
+        config.messages.insert(
+            "response.not_a_message".to_string(),
+            "{anything}".to_string(),
+        );
+        config.check_messages().unwrap();
+    }
+}
@@ -0,0 +1,80 @@
+use crate::config::CooldownConfig;
+use dashmap::DashMap;
+use serenity::model::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// How often uses that have expired are cleared out for everyone, so users who stop using a
+// command don't stay in memory.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Limits how often each user can run commands, so things like `/play` can't be spammed to start
+/// lots of extraction processes.
+pub struct Cooldowns {
+    limits: HashMap<String, CooldownConfig>,
+    // When each user last used each command, oldest first.
+    uses: DashMap<(UserId, String), VecDeque<Instant>>,
+    last_pruned: Mutex<Instant>,
+}
+
+impl Cooldowns {
+    pub fn new(limits: HashMap<String, CooldownConfig>) -> Self {
+        Cooldowns {
+            limits,
+            uses: DashMap::new(),
+            last_pruned: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// Records a use of the command, or returns how long the user has to wait if they've used it
+    /// too many times recently.
+    pub fn check(&self, user_id: UserId, command_name: &str) -> Result<(), Duration> {
+        let limit = match self.limits.get(command_name) {
+            Some(limit) => limit,
+            None => return Ok(()),
+        };
+        let period = Duration::from_secs(limit.per_secs);
+        let now = Instant::now();
+        self.prune_if_due(now);
+
+        let mut uses = self
+            .uses
+            .entry((user_id, command_name.to_string()))
+            .or_default();
+        remove_expired(&mut uses, now, period);
+
+        if uses.len() >= limit.max_uses.get() {
+            let oldest_use = *uses.front().unwrap();
+            return Err(period - now.duration_since(oldest_use));
+        }
+        uses.push_back(now);
+        Ok(())
+    }
+
+    fn prune_if_due(&self, now: Instant) {
+        {
+            let mut last_pruned = self.last_pruned.lock().unwrap();
+            if now.duration_since(*last_pruned) < PRUNE_INTERVAL {
+                return;
+            }
+            *last_pruned = now;
+        }
+
+        self.uses.retain(|(_, command_name), uses| {
+            if let Some(limit) = self.limits.get(command_name) {
+                remove_expired(uses, now, Duration::from_secs(limit.per_secs));
+            }
+            !uses.is_empty()
+        });
+    }
+}
+
+fn remove_expired(uses: &mut VecDeque<Instant>, now: Instant, period: Duration) {
+    while let Some(oldest_use) = uses.front() {
+        if now.duration_since(*oldest_use) < period {
+            break;
+        }
+        uses.pop_front();
+    }
+}
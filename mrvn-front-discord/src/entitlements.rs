@@ -0,0 +1,168 @@
+use crate::command_args::CommandArgs;
+use crate::config::{Config, EntitlementsConfig};
+use dashmap::DashMap;
+use serenity::model::prelude::*;
+use std::time::{Duration, Instant};
+
+const DISCORD_API_URL: &str = "https://discord.com/api/v10";
+
+// How long a failed check lets a guild keep using features before checking again, so an outage
+// doesn't send a request to Discord for every command.
+const FAILURE_CACHE_DURATION: Duration = Duration::from_secs(30);
+
+// How many guilds' checks are remembered at once.
+const MAX_CHECKED_GUILDS: usize = 10_000;
+
+/// Features that cost more to run than the rest, which can be limited to some guilds.
+#[derive(Debug, serde::Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PremiumFeature {
+    Announcements,
+    Gapless,
+    MusicMode,
+    NowPlayingCards,
+    Schedules,
+    Trivia,
+}
+
+impl PremiumFeature {
+    /// The feature a command uses, if it's one that can be limited. Settings only count when
+    /// they're being turned on, so they can always be turned off again.
+    pub fn for_command(args: &CommandArgs<'_>) -> Result<Option<Self>, crate::error::Error> {
+        let feature = match (args.name, args.group, args.subcommand) {
+            ("settings", None, Some(setting)) => {
+                if args.optional::<bool>("enabled")? != Some(true) {
+                    return Ok(None);
+                }
+                match setting {
+                    "announcements" => PremiumFeature::Announcements,
+                    "gapless" => PremiumFeature::Gapless,
+                    "music-mode" => PremiumFeature::MusicMode,
+                    "now-playing-cards" => PremiumFeature::NowPlayingCards,
+                    _ => return Ok(None),
+                }
+            }
+            ("schedule", None, Some("add")) => PremiumFeature::Schedules,
+            ("trivia", None, Some("start")) => PremiumFeature::Trivia,
+            _ => return Ok(None),
+        };
+        Ok(Some(feature))
+    }
+}
+
+/// Decides which guilds can use premium features, from a list in the config or from entitlements
+/// to the bot's SKUs in the Discord store. Without `entitlements` in the config, every guild can
+/// use everything.
+pub struct Entitlements {
+    config: Option<EntitlementsConfig>,
+    token: String,
+    application_id: u64,
+    client: reqwest::Client,
+    // Whether each guild had an entitlement, and when that stops being trusted.
+    checked: DashMap<GuildId, (Instant, bool)>,
+}
+
+impl Entitlements {
+    pub fn new(config: &Config) -> Self {
+        Entitlements {
+            config: config.entitlements.clone(),
+            token: config.command_bot.token.clone(),
+            application_id: config.command_bot.application_id,
+            client: reqwest::Client::new(),
+            checked: DashMap::new(),
+        }
+    }
+
+    pub async fn allows(&self, guild_id: GuildId, feature: PremiumFeature) -> bool {
+        let config = match &self.config {
+            Some(config) => config,
+            None => return true,
+        };
+        if !config.features.contains(&feature) || config.guilds.contains(&guild_id.0) {
+            return true;
+        }
+        if config.sku_ids.is_empty() {
+            return false;
+        }
+
+        if let Some(checked) = self.checked.get(&guild_id) {
+            let (expires_at, is_entitled) = *checked;
+            if Instant::now() < expires_at {
+                return is_entitled;
+            }
+        }
+
+        match self.fetch_is_entitled(config, guild_id).await {
+            Ok(is_entitled) => {
+                self.remember(
+                    guild_id,
+                    Duration::from_secs(config.cache_secs),
+                    is_entitled,
+                );
+                is_entitled
+            }
+            Err(why) => {
+                // Guilds that have paid shouldn't lose features while Discord is having trouble.
+                log::warn!(
+                    "Unable to check entitlements for guild {}, allowing {:?}: {}",
+                    guild_id,
+                    feature,
+                    why
+                );
+                self.remember(guild_id, FAILURE_CACHE_DURATION, true);
+                true
+            }
+        }
+    }
+
+    /// Remembers a check, making room by forgetting expired ones, or any one if none have expired.
+    fn remember(&self, guild_id: GuildId, duration: Duration, is_entitled: bool) {
+        let now = Instant::now();
+        if self.checked.len() >= MAX_CHECKED_GUILDS && !self.checked.contains_key(&guild_id) {
+            self.checked.retain(|_, (expires_at, _)| now < *expires_at);
+            if self.checked.len() >= MAX_CHECKED_GUILDS {
+                let evicted_guild_id = self.checked.iter().next().map(|entry| *entry.key());
+                if let Some(evicted_guild_id) = evicted_guild_id {
+                    self.checked.remove(&evicted_guild_id);
+                }
+            }
+        }
+        self.checked.insert(guild_id, (now + duration, is_entitled));
+    }
+
+    async fn fetch_is_entitled(
+        &self,
+        config: &EntitlementsConfig,
+        guild_id: GuildId,
+    ) -> Result<bool, reqwest::Error> {
+        let sku_ids = config
+            .sku_ids
+            .iter()
+            .map(u64::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+
+        log::trace!("Fetching entitlements for guild {}", guild_id);
+        let entitlements: Vec<serde_json::Value> = self
+            .client
+            .get(format!(
+                "{}/applications/{}/entitlements",
+                DISCORD_API_URL, self.application_id
+            ))
+            .query(&[
+                ("guild_id", guild_id.0.to_string()),
+                ("sku_ids", sku_ids),
+                ("exclude_ended", "true".to_string()),
+            ])
+            .header(
+                reqwest::header::AUTHORIZATION,
+                format!("Bot {}", self.token),
+            )
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(!entitlements.is_empty())
+    }
+}
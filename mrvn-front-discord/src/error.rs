@@ -4,11 +4,29 @@ use serenity::model::prelude::*;
 pub enum Error {
     Serenity(serenity::Error),
     Backend(mrvn_back_ytdl::Error),
+    Script(String),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Request(reqwest::Error),
+    Image(image::ImageError),
+    Redis(redis::RedisError),
 
     UnknownCommand(String),
+    MissingOption(String),
+    InvalidOption {
+        name: String,
+        expected: &'static str,
+    },
     NoGuild,
     UnknownGuild(GuildId),
+    UnknownSpeaker(usize),
     ModelPlayingSpeakerNotDesync,
+    ChecksumMismatch(String),
+    UnknownPlaceholder {
+        message_key: String,
+        placeholder: String,
+        language: Option<String>,
+    },
 }
 
 impl std::fmt::Display for Error {
@@ -16,13 +34,47 @@ impl std::fmt::Display for Error {
         match self {
             Error::Serenity(err) => err.fmt(f),
             Error::Backend(err) => err.fmt(f),
+            Error::Script(why) => write!(f, "Script error: {}", why),
+            Error::Io(err) => err.fmt(f),
+            Error::Json(err) => err.fmt(f),
+            Error::Request(err) => err.fmt(f),
+            Error::Image(err) => err.fmt(f),
+            Error::Redis(err) => err.fmt(f),
             Error::UnknownCommand(command) => write!(f, "Received unknown command {}", command),
+            Error::MissingOption(name) => write!(f, "Command is missing the {} option", name),
+            Error::InvalidOption { name, expected } => {
+                write!(f, "Command option {} should be a {}", name, expected)
+            }
             Error::NoGuild => write!(f, "Command was not invoked from a guild"),
             Error::UnknownGuild(guild_id) => write!(f, "Unknown guild {}", guild_id),
+            Error::UnknownSpeaker(index) => write!(f, "Unknown speaker {}", index),
             Error::ModelPlayingSpeakerNotDesync => write!(
                 f,
                 "Out of sync: model says song is playing, but the speaker disagrees"
             ),
+            Error::ChecksumMismatch(file_name) => write!(
+                f,
+                "Downloaded {} doesn't match its published checksum",
+                file_name
+            ),
+            Error::UnknownPlaceholder {
+                message_key,
+                placeholder,
+                language: Some(language),
+            } => write!(
+                f,
+                "Message {} in language {} uses unknown placeholder {{{}}}",
+                message_key, language, placeholder
+            ),
+            Error::UnknownPlaceholder {
+                message_key,
+                placeholder,
+                language: None,
+            } => write!(
+                f,
+                "Message {} uses unknown placeholder {{{}}}",
+                message_key, placeholder
+            ),
         }
     }
 }
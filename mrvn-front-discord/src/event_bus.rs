@@ -0,0 +1,111 @@
+use serenity::model::prelude::*;
+use tokio::sync::broadcast;
+
+// Subscribers that fall further behind than this will miss events.
+const EVENT_BUFFER_SIZE: usize = 256;
+
+/// Playback events published by the frontend, to be consumed by anything that wants to react to
+/// what the bot is doing (e.g. the event stream served over HTTP).
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    TrackStarted {
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        song_title: String,
+        song_url: String,
+        user_id: UserId,
+    },
+    TrackEnded {
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        song_title: String,
+        song_url: String,
+        user_id: UserId,
+    },
+    QueueChanged {
+        guild_id: GuildId,
+        user_id: UserId,
+        /// Whether a script queued the songs, in which case scripts don't hear about it, so they
+        /// can't keep queueing songs in response to each other.
+        by_script: bool,
+    },
+    VoteProgress {
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        vote_type: &'static str,
+        votes_needed: usize,
+    },
+    VotePassed {
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        vote_type: &'static str,
+    },
+    QueueEmptied {
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    },
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::TrackStarted { .. } => "track_started",
+            Event::TrackEnded { .. } => "track_ended",
+            Event::QueueChanged { .. } => "queue_changed",
+            Event::VoteProgress { .. } => "vote_progress",
+            Event::VotePassed { .. } => "vote_passed",
+            Event::QueueEmptied { .. } => "queue_emptied",
+        }
+    }
+
+    pub fn user_id(&self) -> Option<UserId> {
+        match self {
+            Event::TrackStarted { user_id, .. }
+            | Event::TrackEnded { user_id, .. }
+            | Event::QueueChanged { user_id, .. } => Some(*user_id),
+            Event::VoteProgress { .. } | Event::VotePassed { .. } | Event::QueueEmptied { .. } => {
+                None
+            }
+        }
+    }
+
+    pub fn guild_id(&self) -> GuildId {
+        match self {
+            Event::TrackStarted { guild_id, .. }
+            | Event::TrackEnded { guild_id, .. }
+            | Event::QueueChanged { guild_id, .. }
+            | Event::VoteProgress { guild_id, .. }
+            | Event::VotePassed { guild_id, .. }
+            | Event::QueueEmptied { guild_id, .. } => *guild_id,
+        }
+    }
+}
+
+pub struct EventBus {
+    sender: broadcast::Sender<Event>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_BUFFER_SIZE);
+        EventBus { sender }
+    }
+
+    pub fn publish(&self, event: Event) {
+        log::trace!("Publishing {} event", event.name());
+
+        // Sending only fails when nobody is subscribed, which is fine.
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,64 @@
+use serenity::model::prelude::*;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone)]
+pub struct ExtractionFailure {
+    // Unix timestamp.
+    pub time: i64,
+    pub guild_id: GuildId,
+    pub term: String,
+    pub cause: String,
+    pub exit_code: Option<i32>,
+    pub stderr: String,
+}
+
+/// The last few times youtube-dl failed, kept in memory so the bot's owner can see why songs
+/// aren't loading with `/debug errors`.
+pub struct ExtractionFailures {
+    capacity: usize,
+    // Oldest first.
+    failures: Mutex<VecDeque<ExtractionFailure>>,
+}
+
+impl ExtractionFailures {
+    pub fn new(capacity: usize) -> Self {
+        ExtractionFailures {
+            capacity,
+            failures: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Only errors with youtube-dl's output attached are kept, the rest don't say anything the
+    /// logs don't.
+    pub fn record(&self, guild_id: GuildId, term: &str, why: &mrvn_back_ytdl::Error) {
+        let failure = match why {
+            mrvn_back_ytdl::Error::Extractor(failure) => failure,
+            _ => return,
+        };
+
+        let mut failures = self.failures.lock().unwrap();
+        if failures.len() >= self.capacity {
+            failures.pop_front();
+        }
+        failures.push_back(ExtractionFailure {
+            time: chrono::Utc::now().timestamp(),
+            guild_id,
+            term: term.to_string(),
+            cause: failure.cause.to_string(),
+            exit_code: failure.exit_code,
+            stderr: failure.stderr.clone(),
+        });
+    }
+
+    /// Newest first.
+    pub fn recent(&self) -> Vec<ExtractionFailure> {
+        self.failures
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .cloned()
+            .collect()
+    }
+}
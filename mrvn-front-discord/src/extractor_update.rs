@@ -0,0 +1,176 @@
+use crate::config::ExtractorUpdateConfig;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+#[derive(serde::Deserialize)]
+struct LatestRelease {
+    tag_name: String,
+}
+
+pub enum UpdateStatus {
+    UpToDate {
+        version: String,
+    },
+    Updated {
+        old_version: Option<String>,
+        new_version: String,
+    },
+}
+
+/// Asks youtube-dl for its version, which is `None` if it couldn't be run.
+pub async fn installed_version(binary_path: &str) -> Option<String> {
+    let output = Command::new(binary_path)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    Some(version).filter(|version| !version.is_empty())
+}
+
+/// Keeps the youtube-dl binary up to date by downloading new releases over it, since sites
+/// change often enough that an old one is the usual reason songs stop loading.
+pub struct ExtractorUpdater {
+    config: ExtractorUpdateConfig,
+    client: reqwest::Client,
+    // Only one update runs at a time, so two downloads can't write the binary at once.
+    update_lock: tokio::sync::Mutex<()>,
+}
+
+impl ExtractorUpdater {
+    pub fn new(config: ExtractorUpdateConfig) -> Self {
+        ExtractorUpdater {
+            config,
+            client: reqwest::Client::new(),
+            update_lock: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn check_interval(&self) -> Option<Duration> {
+        self.config.check_interval_secs.map(Duration::from_secs)
+    }
+
+    pub async fn run(&self, check_interval: Duration) {
+        let mut interval = tokio::time::interval(check_interval);
+        loop {
+            interval.tick().await;
+            match self.update().await {
+                Ok(UpdateStatus::UpToDate { version }) => {
+                    log::debug!("youtube-dl is up to date at {}", version)
+                }
+                Ok(UpdateStatus::Updated {
+                    old_version,
+                    new_version,
+                }) => log::info!(
+                    "Updated youtube-dl from {} to {}",
+                    old_version.as_deref().unwrap_or("an unknown version"),
+                    new_version
+                ),
+                Err(why) => log::error!("Error while updating youtube-dl: {}", why),
+            }
+        }
+    }
+
+    pub async fn update(&self) -> Result<UpdateStatus, crate::error::Error> {
+        let _update_guard = self.update_lock.lock().await;
+
+        let old_version = installed_version(&self.config.binary_path.to_string_lossy()).await;
+        let new_version = self.latest_version().await?;
+        if old_version.as_deref() == Some(new_version.as_str()) {
+            return Ok(UpdateStatus::UpToDate {
+                version: new_version,
+            });
+        }
+
+        log::info!("Downloading youtube-dl {}", new_version);
+        let binary = self.download(&self.config.download_url).await?;
+        let checksums = self.download(&self.config.checksums_url).await?;
+        let file_name = self
+            .config
+            .download_url
+            .rsplit('/')
+            .next()
+            .unwrap_or_default();
+        let expected_checksum = find_checksum(&String::from_utf8_lossy(&checksums), file_name);
+        if expected_checksum.as_deref() != Some(sha256_hex(&binary).as_str()) {
+            return Err(crate::error::Error::ChecksumMismatch(file_name.to_string()));
+        }
+        replace_binary(&self.config.binary_path, &binary).map_err(crate::error::Error::Io)?;
+
+        Ok(UpdateStatus::Updated {
+            old_version,
+            new_version,
+        })
+    }
+
+    async fn download(&self, url: &str) -> Result<Vec<u8>, crate::error::Error> {
+        self.client
+            .get(url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(crate::error::Error::Request)?
+            .bytes()
+            .await
+            .map(|bytes| bytes.to_vec())
+            .map_err(crate::error::Error::Request)
+    }
+
+    async fn latest_version(&self) -> Result<String, crate::error::Error> {
+        let release = self
+            .client
+            .get(&self.config.release_url)
+            // GitHub's API turns away requests without a user agent.
+            .header(reqwest::header::USER_AGENT, "mrvn-bot")
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(crate::error::Error::Request)?
+            .json::<LatestRelease>()
+            .await
+            .map_err(crate::error::Error::Request)?;
+        Ok(release.tag_name)
+    }
+}
+
+/// Finds a file's checksum in a SHA2-256SUMS file, where each line is a checksum followed by a
+/// file name. Names can start with `*` when the file was read in binary mode.
+fn find_checksum(checksums: &str, file_name: &str) -> Option<String> {
+    checksums.lines().find_map(|line| {
+        let (checksum, name) = line.trim().split_once(char::is_whitespace)?;
+        let name = name.trim_start();
+        let name = name.strip_prefix('*').unwrap_or(name);
+        (name == file_name).then(|| checksum.to_ascii_lowercase())
+    })
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Writes the new binary next to the old one and moves it into place, so songs loading during the
+/// update never see a half-written file.
+fn replace_binary(path: &Path, binary: &[u8]) -> std::io::Result<()> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".download");
+    let temp_path = PathBuf::from(temp_path);
+
+    std::fs::write(&temp_path, binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&temp_path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    std::fs::rename(&temp_path, path)
+}
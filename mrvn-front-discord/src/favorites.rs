@@ -0,0 +1,47 @@
+use crate::persistence::{Persisted, Persistence};
+use crate::playlists::PlaylistSong;
+use serenity::model::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Songs users have starred with `/fav`, which follow them between guilds.
+pub struct Favorites {
+    // Keyed by user ID, oldest first.
+    users: Persisted<HashMap<u64, Vec<PlaylistSong>>>,
+}
+
+impl Favorites {
+    pub fn load(persistence: Arc<dyn Persistence>) -> Result<Self, crate::error::Error> {
+        Ok(Favorites {
+            users: Persisted::load(persistence, "favorites".to_string())?,
+        })
+    }
+
+    pub fn list(&self, user_id: UserId) -> Vec<PlaylistSong> {
+        self.users
+            .read(|users| users.get(&user_id.0).cloned().unwrap_or_default())
+    }
+
+    /// Returns `false` if the song is already one of the user's favorites.
+    pub fn add(&self, user_id: UserId, song: PlaylistSong) -> bool {
+        self.users.update(|users| {
+            let favorites = users.entry(user_id.0).or_default();
+            if favorites.iter().any(|favorite| favorite.url == song.url) {
+                return false;
+            }
+            favorites.push(song.clone());
+            true
+        })
+    }
+
+    /// Returns the removed song, or `None` if the user doesn't have a favorite at the index.
+    pub fn remove(&self, user_id: UserId, index: usize) -> Option<PlaylistSong> {
+        self.users.update(|users| {
+            let favorites = users.get_mut(&user_id.0)?;
+            if index >= favorites.len() {
+                return None;
+            }
+            Some(favorites.remove(index))
+        })
+    }
+}
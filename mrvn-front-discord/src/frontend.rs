@@ -1,25 +1,136 @@
+use crate::audit::{AuditAction, AuditEntry};
+use crate::bans::MusicBans;
+use crate::blocklist::{BlockRule, Blocklist};
+use crate::command_args::CommandArgs;
+use crate::command_usage::CommandUsage;
+use crate::commands::CommandPermission;
 use crate::config::Config;
+use crate::cooldowns::Cooldowns;
+use crate::entitlements::{Entitlements, PremiumFeature};
+use crate::event_bus::{Event, EventBus};
+use crate::extraction_failures::ExtractionFailures;
+use crate::extractor_update::{ExtractorUpdater, UpdateStatus};
+use crate::favorites::Favorites;
+use crate::handover::Handover;
 use crate::message::{
-    send_messages, ActionMessage, Message, ResponseMessage, SendMessageDestination,
+    send_channel_message, send_messages, truncate_song_id, ActionMessage, ComponentId, Message,
+    Page, PagedList, QueueEta, ResponseMessage, SendMessageDestination, VoteButton,
 };
 use crate::model_delegate::ModelDelegate;
+use crate::now_playing_card::{CardRenderer, NowPlayingCard};
+use crate::pets::Pets;
+use crate::playlists::{
+    GuildPlaylist, GuildPlaylists, PlaylistAccess, PlaylistAction, PlaylistSong, Playlists,
+};
+use crate::plugin::{PluginContext, PluginRegistry};
+use crate::positions::SavedPositions;
+use crate::prefs::Prefs;
+use crate::recovery::PlaybackRecovery;
+use crate::schedules::{Schedule, ScheduleSource, Schedules};
+use crate::scripting::{ScriptAction, ScriptContext, ScriptHost};
+use crate::settings::{QueueStrategy, Settings};
+use crate::soundboard::{SoundEffect, Soundboard};
+use crate::speaker::{self, GuildSpeakers, StartStatus};
+use crate::stats::{Achievement, HistoryFormat, LeaderboardPeriod, Stats};
+use crate::streaks::{StreakStatus, Streaks};
+use crate::trivia::{Trivia, TriviaGame};
+use crate::welcome::Welcomes;
 use futures::prelude::*;
 use mrvn_back_ytdl::{
-    Brain, EndedHandler, GuildSpeakerEndedHandle, GuildSpeakerEndedRef, GuildSpeakerRef, Song,
+    Brain, BrainSpeakersRef, EndReason, EndedHandler, FindSpeakerError, GuildSpeakerEndedHandle,
+    GuildSpeakerEndedRef, GuildSpeakerRef, LocalLibrary, PauseReason, PlayConfig, PrimedSong, Song,
+    SongMetadata,
 };
 use mrvn_model::{
-    AppModel, GuildModel, NextEntry, ReplaceStatus, SecretStreakStatus, VoteStatus, VoteType,
+    AppModel, GuildActionMessage, GuildFeatures, GuildHandle, GuildModel, NextEntry, PlaybackState,
+    ReplaceStatus, VoteStatus, VoteType,
 };
-use serenity::model::id::ChannelId;
+use rand::seq::SliceRandom;
+use serenity::http::Http;
+use serenity::model::id::{ChannelId, RoleId};
 use serenity::{
-    model::prelude::{application_command, interactions, GuildId, UserId},
+    model::prelude::{
+        application_command, interactions, message_component, Guild, GuildId, UserId, VoiceState,
+    },
     prelude::*,
 };
 use std::ops::DerefMut;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+
+const STATS_TOP_COUNT: usize = 3;
+// Positions this close to the start or end of a song aren't worth resuming from.
+const RESUME_POSITION_MARGIN_SECS: u64 = 30;
+const TOP_COUNT: usize = 10;
+const PLAYLIST_LOAD_CONCURRENCY: usize = 4;
+// How many of the latest changes `/playlist log` shows.
+const PLAYLIST_LOG_LENGTH: usize = 10;
+const MAX_SFX_VOLUME: i64 = 200;
+
+// Each term is searched for separately, so this stops one /playmany from starting a huge number of
+// searches.
+const PLAY_MANY_MAX_TERMS: usize = 25;
+// Separates the terms given to /playmany. Search terms and links don't usually have it in them.
+const PLAY_MANY_SEPARATOR: char = ';';
+
+// How many upcoming songs /queue channel shows.
+const CHANNEL_QUEUE_PREVIEW_LENGTH: usize = 10;
+// How many youtube-dl failures `/debug errors` shows.
+const EXTRACTION_FAILURE_HISTORY_LENGTH: usize = 10;
+// How many guilds and commands `/usage` lists.
+const USAGE_LIST_LENGTH: usize = 10;
+// How far ahead in a channel's queue to look for someone's songs.
+const USER_POSITION_LOOKAHEAD: usize = 100;
+const DEFAULT_TRIVIA_ROUNDS: i64 = 5;
+const MAX_TRIVIA_ROUNDS: i64 = 20;
+// How long people have to name each song, from when its excerpt starts.
+const TRIVIA_ROUND_LENGTH: Duration = Duration::from_secs(30);
+// Time to read who won before the next excerpt starts.
+const TRIVIA_ROUND_BREAK: Duration = Duration::from_secs(5);
+// How often to look for due schedules, and so how late into their minute they can start.
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+// How long before a song ends the next one is loaded when playing gaplessly. Long enough for
+// youtube-dl to refresh an expired link, but not so long the stream sits idle.
+const GAPLESS_PRIME_LEAD: Duration = Duration::from_secs(15);
 
-const SEND_WORKING_TIMEOUT_MS: u64 = 50;
+// Commands that users banned with /musicban can't use.
+const MUSIC_COMMANDS: &[&str] = &[
+    "play",
+    "playmany",
+    "Queue this",
+    "resume",
+    "replace",
+    "pause",
+    "skip",
+    "stop",
+    "queue",
+    "playlist",
+    "favs",
+    "sfx",
+    "summon",
+    "disconnect",
+    "undo",
+    "dedupe",
+    "remove",
+    "transfer",
+    "trivia",
+    "schedule",
+    "party",
+];
+
+// Commands that start new songs playing, which aren't taken in maintenance mode.
+fn starts_playback(name: &str, subcommand: Option<&str>) -> bool {
+    matches!(
+        (name, subcommand),
+        (
+            "play" | "playmany" | "Queue this" | "resume" | "replace" | "sfx" | "trivia",
+            _
+        ) | ("playlist", Some("load"))
+            | ("favs", Some("play"))
+    )
+}
 
 enum HandleCommandError {
     CreateError(crate::error::Error),
@@ -27,7 +138,7 @@ enum HandleCommandError {
 }
 
 enum QueuedSongsMetadata {
-    Single(mrvn_back_ytdl::SongMetadata),
+    Single(Box<mrvn_back_ytdl::SongMetadata>),
     Multiple(usize),
 }
 
@@ -35,15 +146,192 @@ pub struct Frontend {
     pub config: Arc<Config>,
     pub backend_brain: Brain,
     pub model: AppModel<Song>,
+    pub events: EventBus,
+    pub scripts: ScriptHost,
+    pub plugins: PluginRegistry,
+    pub stats: Stats,
+    pub prefs: Prefs,
+    pub playlists: Playlists,
+    pub guild_playlists: GuildPlaylists,
+    pub favorites: Favorites,
+    pub pets: Pets,
+    pub streaks: Streaks,
+    pub schedules: Schedules,
+    pub settings: Arc<Settings>,
+    pub blocklist: Blocklist,
+    pub music_bans: MusicBans,
+    pub soundboard: Soundboard,
+    pub positions: SavedPositions,
+    pub welcomes: Welcomes,
+    pub extraction_failures: ExtractionFailures,
+    pub command_usage: CommandUsage,
+    pub extractor_updater: Option<ExtractorUpdater>,
+    pub library: Option<LocalLibrary>,
+    pub handover: Option<Arc<Handover>>,
+    pub recovery: Option<Arc<PlaybackRecovery>>,
+    // New songs aren't started while this is on, so the bot can be restarted once they finish.
+    maintenance: AtomicBool,
+    cooldowns: Cooldowns,
+    entitlements: Entitlements,
+    cards: CardRenderer,
+    trivia: Trivia,
 }
 
 impl Frontend {
-    pub fn new(config: Arc<Config>, backend_brain: Brain, model: AppModel<Song>) -> Frontend {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        config: Arc<Config>,
+        backend_brain: Brain,
+        model: AppModel<Song>,
+        scripts: ScriptHost,
+        plugins: PluginRegistry,
+        stats: Stats,
+        prefs: Prefs,
+        playlists: Playlists,
+        guild_playlists: GuildPlaylists,
+        favorites: Favorites,
+        pets: Pets,
+        streaks: Streaks,
+        schedules: Schedules,
+        settings: Arc<Settings>,
+        blocklist: Blocklist,
+        music_bans: MusicBans,
+        soundboard: Soundboard,
+        positions: SavedPositions,
+        welcomes: Welcomes,
+        handover: Option<Arc<Handover>>,
+        recovery: Option<Arc<PlaybackRecovery>>,
+    ) -> Frontend {
+        let cooldowns = Cooldowns::new(config.cooldowns.clone());
+        let entitlements = Entitlements::new(&config);
+        let cards = CardRenderer::new(config.action_embed_color);
+        let extractor_updater = config.extractor_update.clone().map(ExtractorUpdater::new);
+        let library = config.library.as_ref().map(|library_config| {
+            LocalLibrary::new(&library_config.directory, library_config.search_by_default)
+        });
         Frontend {
             config,
             backend_brain,
             model,
+            events: EventBus::new(),
+            scripts,
+            plugins,
+            stats,
+            prefs,
+            playlists,
+            guild_playlists,
+            favorites,
+            pets,
+            streaks,
+            schedules,
+            settings,
+            blocklist,
+            music_bans,
+            soundboard,
+            positions,
+            welcomes,
+            extraction_failures: ExtractionFailures::new(EXTRACTION_FAILURE_HISTORY_LENGTH),
+            command_usage: CommandUsage::default(),
+            extractor_updater,
+            library,
+            handover,
+            recovery,
+            maintenance: AtomicBool::new(false),
+            cooldowns,
+            entitlements,
+            cards,
+            trivia: Trivia::default(),
+        }
+    }
+
+    /// Whether this process has handed over to a new one, and should leave everything to it.
+    pub fn is_handed_over(&self) -> bool {
+        self.handover
+            .as_ref()
+            .is_some_and(|handover| handover.is_handed_over())
+    }
+
+    pub fn is_in_maintenance(&self) -> bool {
+        self.maintenance.load(Ordering::SeqCst)
+    }
+
+    /// How many speakers are playing something in any guild.
+    pub async fn playing_speaker_count(&self) -> usize {
+        let mut count = 0;
+        for speaker in &self.backend_brain.speakers {
+            let guild_speaker_handles: Vec<_> = speaker.iter().collect();
+            for guild_speaker_handle in guild_speaker_handles {
+                if guild_speaker_handle.lock().await.is_active() {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    /// Posts the welcome message in the system channel of a guild the bot has just been added to,
+    /// unless it's been posted there before.
+    pub async fn handle_guild_joined(&self, ctx: &Context, guild: &Guild) {
+        if !self.config.welcome_new_guilds {
+            return;
+        }
+        let channel_id = match guild.system_channel_id {
+            Some(channel_id) => channel_id,
+            None => return,
+        };
+        if self.welcomes.is_welcomed(guild.id) {
+            return;
+        }
+
+        log::info!("Welcoming new guild {}", guild.id);
+        let message = Message::Action(ActionMessage::Welcome);
+        match send_channel_message(ctx, channel_id, &message, &self.config).await {
+            Ok(_) => self.welcomes.mark_welcomed(guild.id),
+            // It's posted next time the bot is added instead.
+            Err(why) => log::warn!(
+                "Unable to post welcome message in guild {}: {}",
+                guild.id,
+                why
+            ),
+        }
+    }
+
+    /// Sends the message to every guild with a song playing, in its message channel. Returns how
+    /// many guilds it was sent to.
+    pub async fn broadcast(&self, ctx: &Context, message: ActionMessage) -> usize {
+        let mut guild_count = 0;
+        for (guild_id, guild_model_handle) in self.model.guilds() {
+            let mut playback = guild_model_handle.lock_playback().await;
+            let message_channel = match playback.message_channel() {
+                Some(message_channel) => message_channel,
+                None => continue,
+            };
+            let mut is_playing = false;
+            for speaker in &self.backend_brain.speakers {
+                if speaker.get(guild_id).lock().await.is_active() {
+                    is_playing = true;
+                    break;
+                }
+            }
+            if !is_playing {
+                continue;
+            }
+
+            let send_res = send_messages(
+                &self.config,
+                ctx,
+                SendMessageDestination::Channel(message_channel),
+                playback.deref_mut(),
+                vec![Message::Action(message.clone())],
+            )
+            .await;
+            match send_res {
+                Ok(()) => guild_count += 1,
+                Err(why) => log::error!("Error while broadcasting in guild {}: {}", guild_id, why),
+            }
         }
+        log::info!("Broadcast a message to {} guilds", guild_count);
+        guild_count
     }
 
     pub async fn handle_command(
@@ -92,6 +380,410 @@ impl Frontend {
         }
     }
 
+    /// Reposts the last action message once enough other messages have been posted after it, so
+    /// it doesn't scroll out of view.
+    pub async fn handle_channel_message(
+        &self,
+        ctx: &Context,
+        message: &serenity::model::channel::Message,
+    ) {
+        if let Some(guild_id) = message.guild_id {
+            if !message.author.bot
+                && self.trivia.guess(
+                    guild_id,
+                    message.channel_id,
+                    message.author.id,
+                    &message.content,
+                )
+            {
+                log::trace!("Trivia round won by {}", message.author.id);
+            }
+        }
+
+        let (repost_after, guild_id) =
+            match (self.config.repost_action_message_after, message.guild_id) {
+                (Some(repost_after), Some(guild_id)) => (repost_after, guild_id),
+                _ => return,
+            };
+
+        let guild_model_handle = self.model.get(guild_id);
+        let mut playback = guild_model_handle.lock_playback().await;
+        let action_message = match playback.last_action_message() {
+            Some(action_message)
+                if action_message.channel_id == message.channel_id
+                    && action_message.message_id != message.id =>
+            {
+                action_message
+            }
+            _ => return,
+        };
+        if playback.count_message_after_action() < repost_after {
+            return;
+        }
+
+        log::trace!("Action message has scrolled away, reposting it");
+        if let Err(why) = self
+            .repost_action_message(ctx, playback.deref_mut(), action_message)
+            .await
+        {
+            log::error!("Error while reposting action message: {}", why);
+        }
+    }
+
+    async fn repost_action_message(
+        &self,
+        ctx: &Context,
+        playback: &mut PlaybackState,
+        action_message: GuildActionMessage,
+    ) -> Result<(), crate::error::Error> {
+        let old_message = match action_message
+            .channel_id
+            .message(&ctx.http, action_message.message_id)
+            .await
+        {
+            Ok(old_message) => old_message,
+            Err(why) => {
+                // The message was probably deleted, so stop trying to repost it.
+                playback.set_last_action_message(None);
+                return Err(crate::error::Error::Serenity(why));
+            }
+        };
+        let maybe_embed = old_message.embeds.into_iter().next();
+        let new_message = action_message
+            .channel_id
+            .send_message(&ctx.http, |create_message| {
+                // Action messages can be configured to be plain text instead of an embed.
+                if !old_message.content.is_empty() {
+                    create_message.content(&old_message.content);
+                }
+                if let Some(embed) = maybe_embed {
+                    create_message.embed(|create_embed| {
+                        *create_embed = embed.into();
+                        create_embed
+                    });
+                }
+                create_message
+            })
+            .await
+            .map_err(crate::error::Error::Serenity)?;
+        playback.set_last_action_message(Some(GuildActionMessage {
+            channel_id: new_message.channel_id,
+            message_id: new_message.id,
+        }));
+
+        action_message
+            .channel_id
+            .delete_message(&ctx.http, action_message.message_id)
+            .await
+            .map_err(crate::error::Error::Serenity)
+    }
+
+    pub async fn handle_component(
+        self: &Arc<Self>,
+        ctx: &Context,
+        interaction: &message_component::MessageComponentInteraction,
+    ) {
+        let why = match self.handle_component_fallable(ctx, interaction).await {
+            Ok(_) => return,
+            Err(why) => why,
+        };
+
+        log::error!("Error while handling component: {}", why);
+        let send_error_res = interaction
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(interactions::InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|data| {
+                        data.flags(
+                            interactions::InteractionApplicationCommandCallbackDataFlags::EPHEMERAL,
+                        )
+                        .create_embed(|embed| {
+                            embed
+                                .description(self.config.get_raw_message("action.unknown_error"))
+                                .color(self.config.response_embed_color)
+                        })
+                    })
+            })
+            .await;
+        if let Err(why) = send_error_res {
+            log::error!("Error while sending error response: {}", why);
+        }
+    }
+
+    async fn handle_component_fallable(
+        self: &Arc<Self>,
+        ctx: &Context,
+        interaction: &message_component::MessageComponentInteraction,
+    ) -> Result<(), crate::error::Error> {
+        match ComponentId::parse(&interaction.data.custom_id) {
+            Some(ComponentId::Vote(vote_button)) => {
+                self.handle_vote_component(ctx, interaction, vote_button)
+                    .await
+            }
+            Some(ComponentId::Page { list, number }) => {
+                self.handle_page_component(ctx, interaction, list, number)
+                    .await
+            }
+            Some(ComponentId::Favorite { voice_channel_id }) => {
+                self.handle_favorite_component(ctx, interaction, voice_channel_id)
+                    .await
+            }
+            _ => {
+                log::warn!("Received unknown component {}", interaction.data.custom_id);
+                Ok(())
+            }
+        }
+    }
+
+    async fn handle_vote_component(
+        self: &Arc<Self>,
+        ctx: &Context,
+        interaction: &message_component::MessageComponentInteraction,
+        vote_button: VoteButton,
+    ) -> Result<(), crate::error::Error> {
+        let guild_id = interaction.guild_id.ok_or(crate::error::Error::NoGuild)?;
+        let user_id = interaction.user.id;
+        let guild_model_handle = self.model.get(guild_id);
+        let mut playback = guild_model_handle.lock_playback().await;
+
+        let messages = if self.music_bans.is_banned(guild_id, user_id)? {
+            log::debug!("Refusing vote button from banned user");
+            vec![Message::Response(ResponseMessage::MusicBannedError)]
+        } else {
+            self.handle_vote_button(ctx, user_id, guild_id, playback.deref_mut(), vote_button)
+                .await?
+        };
+
+        let messages = self
+            .add_now_playing_cards(ctx, guild_id, self.apply_user_prefs(messages))
+            .await;
+        let user_prefs = self.prefs.get(user_id);
+        send_messages(
+            &self.config.localized(user_prefs.language.as_deref()),
+            ctx,
+            SendMessageDestination::Component(interaction),
+            playback.deref_mut(),
+            messages,
+        )
+        .await
+    }
+
+    async fn handle_favorite_component(
+        self: &Arc<Self>,
+        ctx: &Context,
+        interaction: &message_component::MessageComponentInteraction,
+        voice_channel_id: ChannelId,
+    ) -> Result<(), crate::error::Error> {
+        let guild_id = interaction.guild_id.ok_or(crate::error::Error::NoGuild)?;
+        let user_id = interaction.user.id;
+        log::debug!("Received favorite button");
+        let message = self
+            .favorite_playing_song(user_id, guild_id, voice_channel_id)
+            .await;
+
+        let guild_model_handle = self.model.get(guild_id);
+        let mut playback = guild_model_handle.lock_playback().await;
+        let user_prefs = self.prefs.get(user_id);
+        send_messages(
+            &self.config.localized(user_prefs.language.as_deref()),
+            ctx,
+            SendMessageDestination::Component(interaction),
+            playback.deref_mut(),
+            vec![Message::Response(message)],
+        )
+        .await
+    }
+
+    async fn handle_page_component(
+        self: &Arc<Self>,
+        ctx: &Context,
+        interaction: &message_component::MessageComponentInteraction,
+        list: PagedList,
+        number: usize,
+    ) -> Result<(), crate::error::Error> {
+        let guild_id = interaction.guild_id.ok_or(crate::error::Error::NoGuild)?;
+        log::debug!("Turning {:?} to page {}", list, number);
+        let guild_model_handle = self.model.get(guild_id);
+        let message = {
+            let guild_model = guild_model_handle.lock_queues().await;
+            self.paged_list_message(guild_id, &guild_model, list, number)?
+        };
+
+        let mut playback = guild_model_handle.lock_playback().await;
+        let user_prefs = self.prefs.get(interaction.user.id);
+        send_messages(
+            &self.config.localized(user_prefs.language.as_deref()),
+            ctx,
+            SendMessageDestination::Component(interaction),
+            playback.deref_mut(),
+            vec![Message::Response(message)],
+        )
+        .await
+    }
+
+    /// Looks up a page of a list that's shown a page at a time, both when it's first shown and
+    /// when its buttons are pressed.
+    fn paged_list_message(
+        &self,
+        guild_id: GuildId,
+        guild_model: &GuildModel<Song>,
+        list: PagedList,
+        number: usize,
+    ) -> Result<ResponseMessage, crate::error::Error> {
+        match list {
+            PagedList::UserQueue { user_id } => {
+                let page = Page::new(number, guild_model.user_entries(user_id).count());
+                Ok(ResponseMessage::UserQueue {
+                    user_id,
+                    page,
+                    songs: page.items(
+                        guild_model
+                            .user_entries(user_id)
+                            .map(|song| song.metadata.clone()),
+                    ),
+                })
+            }
+            PagedList::Playlists { user_id } => {
+                let playlists = self.playlists.list(user_id);
+                let page = Page::new(number, playlists.len());
+                Ok(ResponseMessage::Playlists {
+                    user_id,
+                    page,
+                    playlists: page.items(playlists),
+                })
+            }
+            PagedList::Favorites { user_id } => {
+                let favorites = self.favorites.list(user_id);
+                let page = Page::new(number, favorites.len());
+                Ok(ResponseMessage::Favorites {
+                    user_id,
+                    page,
+                    songs: page.items(favorites),
+                })
+            }
+            PagedList::GuildPlaylists => {
+                let playlists = self.guild_playlists.list(guild_id)?;
+                let page = Page::new(number, playlists.len());
+                Ok(ResponseMessage::GuildPlaylists {
+                    page,
+                    playlists: page.items(playlists),
+                })
+            }
+            PagedList::Leaderboard { period } => {
+                let entries = self.stats.leaderboard(guild_id, period)?;
+                if entries.is_empty() {
+                    return Ok(ResponseMessage::NoStats);
+                }
+                let page = Page::new(number, entries.len());
+                Ok(ResponseMessage::Leaderboard {
+                    period,
+                    page,
+                    entries: page.items(entries),
+                })
+            }
+            PagedList::Help { max_permission } => {
+                let commands = crate::commands::help_commands(self, max_permission);
+                let page = Page::new(number, commands.len());
+                Ok(ResponseMessage::Help {
+                    max_permission,
+                    page,
+                    commands: page.items(commands),
+                })
+            }
+        }
+    }
+
+    /// Searches for each term and queues what's found in the order the terms were given, then
+    /// lists the terms nothing could be queued for.
+    async fn handle_play_many(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model_handle: &GuildHandle<Song>,
+        terms: Vec<String>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let play_config = self.guild_play_config(guild_id).await;
+        let delegate_future = ModelDelegate::new(ctx, guild_id);
+        let songs_future = stream::iter(terms.clone())
+            .map(|term| {
+                let play_config = &play_config;
+                async move {
+                    let songs_res = Song::load(&term, user_id, play_config).await;
+                    if let Err(why) = &songs_res {
+                        log::warn!("Unable to load \"{}\" from playmany: {}", term, why);
+                        self.extraction_failures.record(guild_id, &term, why);
+                    }
+                    songs_res
+                }
+            })
+            .buffered(PLAYLIST_LOAD_CONCURRENCY)
+            .collect::<Vec<_>>();
+        let (delegate, songs_results) = futures::join!(delegate_future, songs_future);
+
+        let mut songs = Vec::new();
+        let mut failed_terms = Vec::new();
+        for (index, (term, songs_res)) in terms.into_iter().zip(songs_results).enumerate() {
+            match songs_res {
+                Ok(term_songs) if !term_songs.is_empty() => songs.extend(term_songs),
+                _ => failed_terms.push((index + 1, term)),
+            }
+        }
+
+        let mut messages = self
+            .queue_songs(ctx, user_id, guild_id, guild_model_handle, delegate?, songs)
+            .await?;
+        if !failed_terms.is_empty() {
+            messages.push(Message::Response(ResponseMessage::PlayManyFailed {
+                failed_terms,
+            }));
+        }
+        Ok(messages)
+    }
+
+    /// Votes the same way as the command that posted the button, as long as the user is in the
+    /// voice channel the vote is for.
+    async fn handle_vote_button(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        playback: &mut PlaybackState,
+        vote_button: VoteButton,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let voice_channel_id = vote_button.voice_channel_id();
+        if delegate.get_user_voice_channel(user_id) != Some(voice_channel_id) {
+            return Ok(vec![Message::Response(
+                ResponseMessage::VoteNotInChannelError { voice_channel_id },
+            )]);
+        }
+
+        match vote_button {
+            VoteButton::Skip { song_id, .. } => {
+                log::debug!("Received skip vote button");
+                let is_same_song = {
+                    let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+                    let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+                    matches!(
+                        guild_speakers_ref.find_active_in_channel(voice_channel_id),
+                        Some((_, active_metadata)) if truncate_song_id(&active_metadata.id) == song_id
+                    )
+                };
+                if !is_same_song {
+                    return Ok(vec![Message::Response(ResponseMessage::VoteSongEndedError)]);
+                }
+                self.handle_skip_command(ctx, user_id, guild_id, playback)
+                    .await
+            }
+            VoteButton::Stop { .. } => {
+                log::debug!("Received stop vote button");
+                self.handle_stop_command(ctx, user_id, guild_id, playback)
+                    .await
+            }
+        }
+    }
+
     async fn handle_command_fallable(
         self: &Arc<Self>,
         ctx: &Context,
@@ -105,54 +797,134 @@ impl Frontend {
         // This signal is used to cancel sending a "loading..." message when we finish executing
         // the command.
         let (tx, rx) = tokio::sync::oneshot::channel();
+        let working_config = &self.config.working_message;
         let send_deferred_message_future = async {
             let show_deferred_message = futures::select!(
                 _ = rx.fuse() => false,
-                _ = tokio::time::sleep(Duration::from_millis(SEND_WORKING_TIMEOUT_MS)).fuse() => true,
+                _ = tokio::time::sleep(Duration::from_millis(working_config.delay_ms)).fuse() => true,
             );
-            if show_deferred_message {
-                if let Err(why) = command
-                    .create_interaction_response(&ctx.http, |response| {
-                        response.kind(
+            if !show_deferred_message {
+                return;
+            }
+
+            // Slow searches show what's being searched for, so it's clear the bot is doing
+            // something. The message is edited the same way as a deferred one once the command
+            // finishes.
+            let maybe_query = CommandArgs::parse(&command.data)
+                .optional::<&str>("term")
+                .ok()
+                .flatten()
+                .filter(|_| working_config.show_query);
+            let response_res = match maybe_query {
+                Some(query) => {
+                    let user_prefs = self.prefs.get(command.user.id);
+                    let config = self.config.localized(user_prefs.language.as_deref());
+                    let message = Message::Response(ResponseMessage::Searching {
+                        query: query.to_string(),
+                    });
+                    command
+                        .create_interaction_response(&ctx.http, |response| {
+                            response
+                                .kind(
+                                    interactions::InteractionResponseType::ChannelMessageWithSource,
+                                )
+                                .interaction_response_data(|data| {
+                                    match message.plain_text(&config) {
+                                        Some(content) => data.content(content),
+                                        None => data.create_embed(|embed| {
+                                            message.create_embed(embed, &config)
+                                        }),
+                                    }
+                                })
+                        })
+                        .await
+                }
+                None => {
+                    command
+                        .create_interaction_response(&ctx.http, |response| {
+                            response.kind(
                             interactions::InteractionResponseType::DeferredChannelMessageWithSource,
                         )
-                    })
-                    .await
-                {
-                    log::error!("Error while sending deferred message: {}", why);
+                        })
+                        .await
                 }
+            };
+            if let Err(why) = response_res {
+                log::error!("Error while sending deferred message: {}", why);
             }
         };
 
         let send_future = async {
-            // Ensure we have the guild locked for the duration of the command.
+            let received_time = Instant::now();
             let guild_model_handle = self.model.get(guild_id);
-            let mut guild_model = guild_model_handle.lock().await;
-            guild_model.set_message_channel(Some(message_channel_id));
 
-            // Execute the command
-            let messages_res = self
-                .handle_guild_command(ctx, command, guild_id, guild_model.deref_mut())
-                .await;
+            // Execute the command, unless it's in the wrong channel or the user is running it
+            // too often
+            let maybe_wrong_channel_message = self.check_command_channel(command, guild_id);
+            let messages_res = match (
+                maybe_wrong_channel_message,
+                self.cooldowns.check(command.user.id, &command.data.name),
+            ) {
+                (Some(message), _) => Ok(vec![message]),
+                (None, Ok(())) => {
+                    {
+                        let mut playback = guild_model_handle.lock_playback().await;
+                        self.update_message_channel(
+                            ctx,
+                            guild_id,
+                            playback.deref_mut(),
+                            message_channel_id,
+                        )
+                        .await;
+                    }
+                    self.handle_guild_command(ctx, command, guild_id, &guild_model_handle)
+                        .await
+                }
+                (None, Err(retry_after)) => {
+                    log::debug!("User is on cooldown for {}", command.data.name);
+                    Ok(vec![Message::Response(ResponseMessage::CooldownError {
+                        retry_after_secs: retry_after.as_secs() + 1,
+                    })])
+                }
+            };
+
+            let is_error = messages_res
+                .as_ref()
+                .map_or(true, |messages| messages.iter().any(Message::is_error));
+            self.command_usage.record(
+                guild_id,
+                &command.data.name,
+                received_time.elapsed(),
+                is_error,
+            );
 
             // If the timeout has finished, rx will be closed so this send call will return an
             // error. We can use this to know that a response has been created, and we need to edit
             // it from now on.
             let has_sent_deferred = tx.send(()).is_err();
-            let messages = messages_res.map_err(if has_sent_deferred {
+            let mut messages = messages_res.map_err(if has_sent_deferred {
                 HandleCommandError::EditError
             } else {
                 HandleCommandError::CreateError
             })?;
+            let mut playback = guild_model_handle.lock_playback().await;
+            messages.extend(
+                self.dropped_entries_messages(guild_model_handle.lock_queues().await.deref_mut()),
+            );
+            let messages = self
+                .add_now_playing_cards(ctx, guild_id, self.apply_user_prefs(messages))
+                .await;
 
+            // Responses are shown in the language the user prefers.
+            let user_prefs = self.prefs.get(command.user.id);
             let send_res = send_messages(
-                &self.config,
+                &self.config.localized(user_prefs.language.as_deref()),
                 ctx,
                 SendMessageDestination::Interaction {
                     interaction: command,
                     is_edit: has_sent_deferred,
                 },
-                guild_model.deref_mut(),
+                playback.deref_mut(),
                 messages,
             )
             .await;
@@ -167,56 +939,256 @@ impl Frontend {
         send_res
     }
 
+    /// Updates are posted wherever commands were last used, unless they're locked to a channel.
+    async fn update_message_channel(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        playback: &mut PlaybackState,
+        command_channel_id: ChannelId,
+    ) {
+        let settings = self.settings.get(guild_id);
+        if let Some(locked_channel_id) = settings.locked_message_channel {
+            playback.set_message_channel(Some(locked_channel_id));
+            return;
+        }
+
+        let old_channel_id = playback.message_channel();
+        playback.set_message_channel(Some(command_channel_id));
+        match old_channel_id {
+            Some(old_channel_id)
+                if old_channel_id != command_channel_id
+                    && settings.notify_message_channel_moves =>
+            {
+                log::trace!("Message channel has moved, sending a notice in the old one");
+                let send_res = send_messages(
+                    &self.config,
+                    ctx,
+                    SendMessageDestination::Channel(old_channel_id),
+                    playback,
+                    vec![Message::Response(ResponseMessage::MessageChannelMoved {
+                        channel_id: command_channel_id,
+                    })],
+                )
+                .await;
+                if let Err(why) = send_res {
+                    log::error!("Error while sending message channel notice: {}", why);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Why the user can't use the command, if they're banned from music, it would start songs
+    /// during maintenance, or it needs a premium feature the guild doesn't have.
+    async fn refuse_command(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        args: &CommandArgs<'_>,
+    ) -> Result<Option<Message>, crate::error::Error> {
+        if MUSIC_COMMANDS.contains(&args.name) && self.music_bans.is_banned(guild_id, user_id)? {
+            log::debug!("Refusing {} from banned user", args.name);
+            return Ok(Some(Message::Response(ResponseMessage::MusicBannedError)));
+        }
+        if self.is_in_maintenance() && starts_playback(args.name, args.subcommand) {
+            log::debug!("Refusing {} during maintenance", args.full_name());
+            return Ok(Some(Message::Response(ResponseMessage::MaintenanceError)));
+        }
+        if let Some(feature) = PremiumFeature::for_command(args)? {
+            if !self.entitlements.allows(guild_id, feature).await {
+                log::debug!(
+                    "Refusing {} in guild without {:?}",
+                    args.full_name(),
+                    feature
+                );
+                return Ok(Some(Message::Response(ResponseMessage::NotEntitledError {
+                    command: args.full_name(),
+                })));
+            }
+        }
+        Ok(None)
+    }
+
     async fn handle_guild_command(
         self: &Arc<Self>,
         ctx: &Context,
         command: &interactions::application_command::ApplicationCommandInteraction,
         guild_id: GuildId,
-        guild_model: &mut GuildModel<Song>,
+        guild_model_handle: &GuildHandle<Song>,
     ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
         let user_id = command.user.id;
-        match command.data.name.as_str() {
-            "play" => {
-                let term = match command
-                    .data
-                    .options
-                    .get(0)
-                    .and_then(|val| val.resolved.as_ref())
-                {
-                    Some(
-                        application_command::ApplicationCommandInteractionDataOptionValue::String(
-                            val,
-                        ),
-                    ) => val.clone(),
-                    _ => "".to_string(),
-                };
+        let args = CommandArgs::parse(&command.data);
+        if let Some(message) = self.refuse_command(guild_id, user_id, &args).await? {
+            return Ok(vec![message]);
+        }
+
+        if let Some(plugin) = self.plugins.get(&command.data.name) {
+            log::debug!("Received plugin command {}", command.data.name);
+            let (mut playback, mut guild_model) = guild_model_handle.lock().await;
+            return plugin
+                .handle(PluginContext {
+                    ctx,
+                    command,
+                    frontend: self,
+                    guild_id,
+                    user_id,
+                    playback: playback.deref_mut(),
+                    guild_model: guild_model.deref_mut(),
+                })
+                .await;
+        }
 
+        // Searching for songs can take a few seconds, and nothing else can happen in the guild
+        // while it's locked. Commands that search lock it themselves once they've found what to
+        // queue, and only hold the queues while adding to them. Everything else has both the
+        // playback state and the queues locked for the whole command.
+        match (args.name, args.subcommand) {
+            ("play", _) => {
+                let term = args.required::<&str>("term")?;
+                let resume = args.optional::<bool>("resume")?.unwrap_or(false);
+                let start = args.optional::<&str>("start")?;
+                let end = args.optional::<&str>("end")?;
                 log::debug!("Received play \"{}\"", term);
-                self.handle_queue_play_command(ctx, user_id, guild_id, guild_model, &term)
-                    .await
+
+                let options = match PlayOptions::parse(resume, start, end) {
+                    Some(options) => options,
+                    None => return Ok(vec![Message::Response(ResponseMessage::InvalidTrimError)]),
+                };
+                return self
+                    .handle_queue_play_command(
+                        ctx,
+                        user_id,
+                        guild_id,
+                        guild_model_handle,
+                        term,
+                        options,
+                    )
+                    .await;
             }
-            "resume" => {
-                log::debug!("Received resume");
-                self.handle_unpause_command(ctx, user_id, guild_id, guild_model)
+            ("playmany", _) => {
+                let terms: Vec<_> = args
+                    .required::<&str>("songs")?
+                    .split(PLAY_MANY_SEPARATOR)
+                    .map(str::trim)
+                    .filter(|term| !term.is_empty())
+                    .take(PLAY_MANY_MAX_TERMS)
+                    .map(str::to_string)
+                    .collect();
+                log::debug!("Received playmany with {} terms", terms.len());
+                return self
+                    .handle_play_many(ctx, user_id, guild_id, guild_model_handle, terms)
+                    .await;
+            }
+            ("Queue this", _) => {
+                log::debug!("Received queue this");
+                return self
+                    .handle_queue_message_command(
+                        ctx,
+                        command,
+                        user_id,
+                        guild_id,
+                        guild_model_handle,
+                    )
+                    .await;
+            }
+            ("replace", _) => {
+                let term = args.required::<&str>("term")?;
+                log::debug!("Received replace \"{}\"", term);
+                return self
+                    .handle_replace_command(ctx, user_id, guild_id, guild_model_handle, term)
+                    .await;
+            }
+            ("playlist", Some("load")) => {
+                log::debug!("Received {}", args.full_name());
+                return self
+                    .handle_playlist_load_command(
+                        ctx,
+                        command,
+                        &args,
+                        user_id,
+                        guild_id,
+                        guild_model_handle,
+                    )
+                    .await;
+            }
+            ("playlist", Some("add")) => {
+                log::debug!("Received {}", args.full_name());
+                return self
+                    .handle_playlist_add_command(command, &args, user_id, guild_id)
+                    .await;
+            }
+            ("favs", Some("play")) => {
+                log::debug!("Received {}", args.full_name());
+                let favorites = self.favorites.list(user_id);
+                if favorites.is_empty() {
+                    return Ok(vec![Message::Response(ResponseMessage::NoFavoritesError)]);
+                }
+                return self
+                    .queue_playlist_songs(ctx, user_id, guild_id, guild_model_handle, favorites)
+                    .await;
+            }
+            ("library", Some("rescan")) => {
+                log::debug!("Received {}", args.full_name());
+                if !is_owner(ctx, user_id).await? {
+                    return Ok(vec![Message::Response(ResponseMessage::MissingOwnerError)]);
+                }
+
+                // Scans can take a while, so they happen without holding up the guild.
+                let library = self
+                    .library
+                    .as_ref()
+                    .ok_or_else(|| crate::error::Error::UnknownCommand(args.full_name()))?;
+                let count = library
+                    .rescan()
                     .await
+                    .map_err(crate::error::Error::Backend)?;
+                return Ok(vec![Message::Response(ResponseMessage::LibraryRescanned {
+                    count,
+                })]);
             }
-            "replace" => {
-                let term = match command
-                    .data
-                    .options
-                    .get(0)
-                    .and_then(|val| val.resolved.as_ref())
-                {
-                    Some(
-                        application_command::ApplicationCommandInteractionDataOptionValue::String(
-                            val,
-                        ),
-                    ) => val.clone(),
-                    _ => "".to_string(),
-                };
+            ("broadcast", _) => {
+                log::debug!("Received broadcast");
+                if !is_owner(ctx, user_id).await? {
+                    return Ok(vec![Message::Response(ResponseMessage::MissingOwnerError)]);
+                }
 
-                log::debug!("Received replace \"{}\"", term);
-                self.handle_replace_command(ctx, user_id, guild_id, guild_model, &term)
+                // Every guild that's playing is locked in turn, including this one.
+                let message = args.required::<&str>("message")?.to_string();
+                let guild_count = self
+                    .broadcast(ctx, ActionMessage::Broadcast { message })
+                    .await;
+                return Ok(vec![Message::Response(ResponseMessage::BroadcastSent {
+                    guild_count,
+                })]);
+            }
+            ("playlist", Some("import")) => {
+                log::debug!("Received {}", args.full_name());
+                return self
+                    .handle_playlist_import_command(command, &args, user_id, guild_id)
+                    .await;
+            }
+            (command_name, _) if self.scripts.has_command(command_name) => {
+                log::debug!("Received script command {}", command_name);
+                return self
+                    .handle_script_command(ctx, &args, user_id, guild_id, guild_model_handle)
+                    .await;
+            }
+            _ => {}
+        }
+
+        let (mut playback, mut guild_model) = guild_model_handle.lock().await;
+        let playback = playback.deref_mut();
+        let guild_model = guild_model.deref_mut();
+        match args.name {
+            "What are they listening to?" => {
+                log::debug!("Received what are they listening to");
+                self.handle_listening_user_command(ctx, command, guild_id, playback, guild_model)
+                    .await
+            }
+            "resume" => {
+                log::debug!("Received resume");
+                self.handle_unpause_command(ctx, user_id, guild_id, playback, guild_model)
                     .await
             }
             "pause" => {
@@ -224,22 +1196,353 @@ impl Frontend {
                 self.handle_pause_command(ctx, user_id, guild_id).await
             }
             "skip" => {
-                log::debug!("Received skip");
-                self.handle_skip_command(ctx, user_id, guild_id, guild_model)
-                    .await
+                let force = args.optional::<bool>("force")?.unwrap_or(false);
+                log::debug!("Received skip (force: {})", force);
+                if force {
+                    self.handle_force_skip_command(ctx, command, user_id, guild_id)
+                        .await
+                } else {
+                    self.handle_skip_command(ctx, user_id, guild_id, playback)
+                        .await
+                }
             }
             "stop" => {
                 log::debug!("Received stop");
-                self.handle_stop_command(ctx, user_id, guild_id, guild_model)
+                self.handle_stop_command(ctx, user_id, guild_id, playback)
                     .await
             }
-            "highfive" => {
-                log::debug!("Received highfive");
-                match guild_model.secret_add_streak(user_id) {
-                    SecretStreakStatus::Success => {
-                        Ok(vec![Message::Response(ResponseMessage::ImageEmbed {
-                            image_url: self
-                                .config
+            "transfer" => {
+                let target_channel_id = match args.optional::<ChannelId>("channel")? {
+                    Some(channel_id) => channel_id,
+                    None => return Ok(Vec::new()),
+                };
+                log::debug!("Received transfer to {}", target_channel_id);
+                self.handle_transfer_command(ctx, user_id, guild_id, playback, target_channel_id)
+                    .await
+            }
+            "summon" => {
+                log::debug!("Received summon");
+                self.handle_summon_command(ctx, user_id, guild_id).await
+            }
+            "disconnect" => {
+                log::debug!("Received disconnect");
+                self.handle_disconnect_command(ctx, user_id, guild_id, playback)
+                    .await
+            }
+            "undo" => {
+                log::debug!("Received undo");
+                let message = match guild_model.undo_user_change(user_id) {
+                    Some(kind) => {
+                        self.events.publish(Event::QueueChanged {
+                            guild_id,
+                            user_id,
+                            by_script: false,
+                        });
+                        ResponseMessage::Undone { kind }
+                    }
+                    None => ResponseMessage::NothingToUndoError,
+                };
+                Ok(vec![Message::Response(message)])
+            }
+            "dedupe" => {
+                log::debug!("Received dedupe");
+                self.handle_dedupe_command(ctx, user_id, guild_id, playback, guild_model)
+                    .await
+            }
+            "queue" => {
+                self.handle_queue_command(ctx, &args, user_id, guild_id, playback, guild_model)
+                    .await
+            }
+            "nowplaying" => {
+                log::debug!("Received nowplaying");
+                self.handle_now_playing_command(ctx, &args, user_id, guild_id)
+                    .await
+            }
+            "songinfo" => {
+                log::debug!("Received songinfo");
+                self.handle_song_info_command(ctx, &args, user_id, guild_id, playback, guild_model)
+                    .await
+            }
+            "voteinfo" => {
+                log::debug!("Received voteinfo");
+                self.handle_vote_info_command(ctx, user_id, guild_id, playback)
+                    .await
+            }
+            "fav" => {
+                log::debug!("Received fav");
+                self.handle_fav_command(ctx, user_id, guild_id).await
+            }
+            "favs" => self.handle_favs_command(&args, user_id, guild_id, guild_model),
+            "playlist" => {
+                self.handle_playlist_command(command, &args, user_id, guild_id, guild_model)
+                    .await
+            }
+            "stats" => {
+                let maybe_user_id = args.optional::<UserId>("user")?;
+                log::debug!("Received stats");
+                match self
+                    .stats
+                    .summary(guild_id, maybe_user_id, STATS_TOP_COUNT)?
+                {
+                    Some(summary) => Ok(vec![Message::Response(ResponseMessage::Stats {
+                        user_id: maybe_user_id,
+                        summary,
+                    })]),
+                    None => Ok(vec![Message::Response(ResponseMessage::NoStats)]),
+                }
+            }
+            "history" => self.handle_history_command(command, &args, guild_id),
+            "achievements" => {
+                let target_user_id = args.optional::<UserId>("user")?.unwrap_or(user_id);
+                log::debug!("Received achievements");
+                Ok(vec![Message::Response(ResponseMessage::Achievements {
+                    user_id: target_user_id,
+                    unlocked: self.stats.achievements(guild_id, target_user_id)?,
+                })])
+            }
+            "top" => {
+                let maybe_user_id = args.optional::<UserId>("user")?;
+                log::debug!("Received top");
+                match self.stats.summary(guild_id, maybe_user_id, TOP_COUNT)? {
+                    Some(summary) => Ok(vec![Message::Response(ResponseMessage::Top {
+                        user_id: maybe_user_id,
+                        summary,
+                    })]),
+                    None => Ok(vec![Message::Response(ResponseMessage::NoStats)]),
+                }
+            }
+            "leaderboard" => {
+                let period = args
+                    .optional::<&str>("period")?
+                    .and_then(LeaderboardPeriod::from_name)
+                    .unwrap_or(LeaderboardPeriod::AllTime);
+                let page = args.optional::<i64>("page")?.unwrap_or(1).max(1) as usize;
+
+                log::debug!("Received leaderboard {:?} page {}", period, page);
+                Ok(vec![Message::Response(self.paged_list_message(
+                    guild_id,
+                    guild_model,
+                    PagedList::Leaderboard { period },
+                    page,
+                )?)])
+            }
+            "prefs" => {
+                let dm_notifications = args.optional::<bool>("dm_notifications")?;
+                let mention = args.optional::<bool>("mention")?;
+                let language = args.optional::<&str>("language")?;
+                let mut language_error = None;
+                let user_prefs = self.prefs.update(user_id, |user_prefs| {
+                    if let Some(val) = dm_notifications {
+                        user_prefs.dm_notifications = val;
+                    }
+                    if let Some(val) = mention {
+                        user_prefs.announce_mention = val;
+                    }
+                    match language {
+                        Some("default") => user_prefs.language = None,
+                        Some(val) if self.config.languages.contains_key(val) => {
+                            user_prefs.language = Some(val.to_string())
+                        }
+                        Some(val) => language_error = Some(val.to_string()),
+                        None => {}
+                    }
+                });
+
+                log::debug!("Received prefs");
+                match language_error {
+                    Some(language) => Ok(vec![Message::Response(
+                        ResponseMessage::UnknownLanguageError { language },
+                    )]),
+                    None => Ok(vec![Message::Response(ResponseMessage::Prefs {
+                        dm_notifications: user_prefs.dm_notifications,
+                        announce_mention: user_prefs.announce_mention,
+                        language: user_prefs.language,
+                    })]),
+                }
+            }
+            "settings" => {
+                self.handle_settings_command(ctx, command, &args, user_id, guild_id, guild_model)
+            }
+            "blocklist" => self.handle_blocklist_command(command, &args, guild_id),
+            "remove" => {
+                self.handle_remove_command(
+                    ctx,
+                    command,
+                    &args,
+                    user_id,
+                    guild_id,
+                    playback,
+                    guild_model,
+                )
+                .await
+            }
+            "party" => {
+                self.handle_party_command(ctx, command, &args, user_id, guild_id, playback)
+                    .await
+            }
+            "musicban" | "musicunban" => {
+                let target_user_id = match args.optional::<UserId>("user")? {
+                    Some(target_user_id) => target_user_id,
+                    None => return Ok(Vec::new()),
+                };
+
+                log::debug!("Received {} {}", args.name, target_user_id);
+                if !can_manage_guild(command) {
+                    return Ok(vec![Message::Response(
+                        ResponseMessage::MissingPermissionError,
+                    )]);
+                }
+
+                let message = if args.name == "musicban" {
+                    if self.music_bans.ban(guild_id, target_user_id)? {
+                        ResponseMessage::MusicBanned {
+                            user_id: target_user_id,
+                        }
+                    } else {
+                        ResponseMessage::AlreadyMusicBannedError {
+                            user_id: target_user_id,
+                        }
+                    }
+                } else if self.music_bans.unban(guild_id, target_user_id)? {
+                    ResponseMessage::MusicUnbanned {
+                        user_id: target_user_id,
+                    }
+                } else {
+                    ResponseMessage::NotMusicBannedError {
+                        user_id: target_user_id,
+                    }
+                };
+                Ok(vec![Message::Response(message)])
+            }
+            "help" => {
+                let is_all = args.optional::<bool>("all")?.unwrap_or(false);
+                log::debug!("Received help, all: {}", is_all);
+                let max_permission = if is_all || is_owner(ctx, user_id).await? {
+                    CommandPermission::Owner
+                } else if can_manage_guild(command) {
+                    CommandPermission::ManageServer
+                } else {
+                    CommandPermission::Everyone
+                };
+                Ok(vec![Message::Response(self.paged_list_message(
+                    guild_id,
+                    guild_model,
+                    PagedList::Help { max_permission },
+                    1,
+                )?)])
+            }
+            "sync" => {
+                let is_global = args.optional::<bool>("global")?.unwrap_or(false);
+                log::debug!("Received sync, global: {}", is_global);
+                if !is_owner(ctx, user_id).await? {
+                    return Ok(vec![Message::Response(ResponseMessage::MissingOwnerError)]);
+                }
+
+                let maybe_guild_id = if is_global { None } else { Some(guild_id) };
+                crate::commands::register_commands(&ctx.http, maybe_guild_id, self)
+                    .await
+                    .map_err(crate::error::Error::Serenity)?;
+                Ok(vec![Message::Response(ResponseMessage::Synced {
+                    is_global,
+                })])
+            }
+            "usage" => {
+                log::debug!("Received usage");
+                if !is_owner(ctx, user_id).await? {
+                    return Ok(vec![Message::Response(ResponseMessage::MissingOwnerError)]);
+                }
+
+                Ok(vec![Message::Response(ResponseMessage::CommandUsage {
+                    busiest_guilds: self.command_usage.busiest_guilds(USAGE_LIST_LENGTH),
+                    slowest_commands: self.command_usage.slowest_commands(USAGE_LIST_LENGTH),
+                })])
+            }
+            "debug" => {
+                log::debug!("Received {}", args.full_name());
+                if !is_owner(ctx, user_id).await? {
+                    return Ok(vec![Message::Response(ResponseMessage::MissingOwnerError)]);
+                }
+
+                match args.subcommand {
+                    Some("status") => Ok(vec![Message::Response(ResponseMessage::DebugStatus {
+                        maintenance: self.is_in_maintenance(),
+                        playing_count: self.playing_speaker_count().await,
+                    })]),
+                    Some("errors") => Ok(vec![Message::Response(
+                        ResponseMessage::ExtractionFailures {
+                            failures: self.extraction_failures.recent(),
+                        },
+                    )]),
+                    Some("extractor") => {
+                        Ok(vec![Message::Response(ResponseMessage::ExtractorVersion {
+                            version: crate::extractor_update::installed_version(
+                                &self.config.ytdl.name,
+                            )
+                            .await,
+                        })])
+                    }
+                    _ => Ok(Vec::new()),
+                }
+            }
+            "maintenance" => {
+                log::debug!("Received {}", args.full_name());
+                if !is_owner(ctx, user_id).await? {
+                    return Ok(vec![Message::Response(ResponseMessage::MissingOwnerError)]);
+                }
+
+                let is_on = args.subcommand == Some("on");
+                let was_on = self.maintenance.swap(is_on, Ordering::SeqCst);
+                if is_on && !was_on {
+                    log::info!("Maintenance mode is on, parking speakers as their songs finish");
+                    tokio::task::spawn(crate::cleanup_loop::park_speakers(self.clone()));
+
+                    // This guild is locked until the command is finished, so the notice is sent
+                    // after it.
+                    let frontend = self.clone();
+                    let ctx = ctx.clone();
+                    tokio::task::spawn(async move {
+                        frontend
+                            .broadcast(&ctx, ActionMessage::MaintenanceNotice)
+                            .await
+                    });
+                } else if !is_on && was_on {
+                    log::info!("Maintenance mode is off");
+                }
+                Ok(vec![Message::Response(ResponseMessage::MaintenanceSet {
+                    is_on,
+                })])
+            }
+            "update-extractor" => {
+                log::debug!("Received update-extractor");
+                if !is_owner(ctx, user_id).await? {
+                    return Ok(vec![Message::Response(ResponseMessage::MissingOwnerError)]);
+                }
+
+                let updater = self
+                    .extractor_updater
+                    .as_ref()
+                    .ok_or_else(|| crate::error::Error::UnknownCommand(args.full_name()))?;
+                let message = match updater.update().await? {
+                    UpdateStatus::UpToDate { version } => {
+                        ResponseMessage::ExtractorUpToDate { version }
+                    }
+                    UpdateStatus::Updated {
+                        old_version,
+                        new_version,
+                    } => ResponseMessage::ExtractorUpdated {
+                        old_version,
+                        new_version,
+                    },
+                };
+                Ok(vec![Message::Response(message)])
+            }
+            "highfive" => {
+                log::debug!("Received highfive");
+                match self.streaks.add(guild_id, user_id)? {
+                    StreakStatus::Success => {
+                        Ok(vec![Message::Response(ResponseMessage::ImageEmbed {
+                            image_url: self
+                                .config
                                 .secret_highfive
                                 .as_ref()
                                 .ok_or(crate::error::Error::UnknownCommand("highfive".to_string()))?
@@ -247,19 +1550,84 @@ impl Frontend {
                                 .clone(),
                         })])
                     }
-                    SecretStreakStatus::Wait => {
-                        Ok(vec![Message::Response(ResponseMessage::StreakWait)])
-                    }
+                    StreakStatus::Wait => Ok(vec![Message::Response(ResponseMessage::StreakWait)]),
                 }
             }
             "streak" => {
-                log::debug!("Received streak");
-                match guild_model.secret_get_streak(user_id) {
-                    0 => Ok(vec![Message::Response(ResponseMessage::NoStreak)]),
-                    streak_length => Ok(vec![Message::Response(ResponseMessage::Streak {
-                        streak_length,
-                    })]),
+                log::debug!("Received {}", args.full_name());
+                let message = match args.subcommand {
+                    Some("leaderboard") => ResponseMessage::StreakLeaderboard {
+                        entries: self.streaks.top(guild_id, TOP_COUNT)?,
+                    },
+                    _ => match self.streaks.get(guild_id, user_id)? {
+                        0 => ResponseMessage::NoStreak,
+                        streak_length => ResponseMessage::Streak { streak_length },
+                    },
+                };
+                Ok(vec![Message::Response(message)])
+            }
+            "pet" => {
+                log::debug!("Received pet");
+                let pets_config = self
+                    .config
+                    .pets
+                    .as_ref()
+                    .ok_or(crate::error::Error::UnknownCommand("pet".to_string()))?;
+                let pet = match self
+                    .pets
+                    .find(pets_config, user_id, &mut rand::thread_rng())
+                {
+                    Some(pet) => pet,
+                    None => return Ok(vec![Message::Response(ResponseMessage::NoPetsError)]),
+                };
+
+                let mut messages = vec![Message::Response(ResponseMessage::PetFound {
+                    name: pet.name,
+                    image_url: pet.image_url,
+                    is_shiny: pet.is_shiny,
+                    is_new: pet.is_new,
+                })];
+                if pet.is_shiny {
+                    let unlocked_user_ids =
+                        self.stats
+                            .unlock(guild_id, &[user_id], Achievement::FirstShiny)?;
+                    messages.extend(unlocked_user_ids.into_iter().map(|user_id| {
+                        Message::Response(ResponseMessage::AchievementUnlocked {
+                            user_id,
+                            achievement: Achievement::FirstShiny,
+                        })
+                    }));
                 }
+                Ok(messages)
+            }
+            "pets" => {
+                log::debug!("Received pets");
+                let pets_config = self
+                    .config
+                    .pets
+                    .as_ref()
+                    .ok_or(crate::error::Error::UnknownCommand("pets".to_string()))?;
+                let collection = self.pets.collection(user_id);
+                Ok(vec![Message::Response(ResponseMessage::PetCollection {
+                    total_pets: pets_config.pets.len(),
+                    pets: collection.pets.into_iter().collect(),
+                    shiny_name: pets_config.shiny.as_ref().map(|shiny| shiny.name.clone()),
+                    shinies: collection.shinies,
+                })])
+            }
+            "sfx" => {
+                self.handle_sfx_command(ctx, command, &args, user_id, guild_id)
+                    .await
+            }
+            "trivia" => {
+                self.handle_trivia_command(ctx, command, &args, user_id, guild_id)
+                    .await
+            }
+            "schedule" => self.handle_schedule_command(command, &args, user_id, guild_id),
+            command_name if self.config.greets.contains_key(command_name) => {
+                log::debug!("Received greet {}", command_name);
+                self.handle_greet_command(ctx, user_id, guild_id, command_name)
+                    .await
             }
             command_name => Err(crate::error::Error::UnknownCommand(
                 command_name.to_string(),
@@ -267,163 +1635,3349 @@ impl Frontend {
         }
     }
 
-    async fn handle_queue_play_command(
+    async fn handle_script_command(
         self: &Arc<Self>,
         ctx: &Context,
+        args: &CommandArgs<'_>,
         user_id: UserId,
         guild_id: GuildId,
-        guild_model: &mut GuildModel<Song>,
-        term: &str,
+        guild_model_handle: &GuildHandle<Song>,
     ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
-        let play_config = self.config.get_play_config();
-
-        let delegate_future = ModelDelegate::new(ctx, guild_id);
-        let song_future =
-            Song::load(term, user_id, &play_config).map_err(crate::error::Error::Backend);
-
-        let (delegate, songs) = match futures::try_join!(delegate_future, song_future) {
-            Ok(data) => data,
-            Err(crate::error::Error::Backend(mrvn_back_ytdl::Error::UnsupportedUrl)) => {
-                return Ok(vec![Message::Response(
-                    ResponseMessage::UnsupportedSiteError,
-                )]);
-            }
-            Err(why) => return Err(why),
+        let input = args
+            .optional::<&str>("input")?
+            .unwrap_or_default()
+            .to_string();
+        let script_ctx = {
+            let guild_model = guild_model_handle.lock_queues().await;
+            script_context(guild_id, Some(user_id), input, &guild_model)
         };
-        if songs.is_empty() {
-            return Ok(vec![Message::Response(
-                ResponseMessage::NoMatchingSongsError,
-            )]);
+        match self.scripts.run_command(args.name, script_ctx) {
+            Some(actions_res) => {
+                self.run_script_actions(ctx, user_id, guild_id, guild_model_handle, actions_res?)
+                    .await
+            }
+            None => Err(crate::error::Error::UnknownCommand(args.name.to_string())),
         }
+    }
 
-        let metadata = if songs.len() == 1 {
-            let song_metadata = &songs[0].metadata;
-            log::trace!(
-                "Resolved song query as {} (\"{}\")",
-                song_metadata.url,
-                song_metadata.title
-            );
-            QueuedSongsMetadata::Single(song_metadata.clone())
-        } else {
-            log::trace!("Resolved song query as {} songs", songs.len());
-            QueuedSongsMetadata::Multiple(songs.len())
+    async fn handle_queue_command(
+        &self,
+        ctx: &Context,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+        playback: &PlaybackState,
+        guild_model: &mut GuildModel<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        log::debug!("Received {}", args.full_name());
+        let message = match args.subcommand {
+            Some("channel") => {
+                let delegate = ModelDelegate::new(ctx, guild_id).await?;
+                let channel_id = match args
+                    .optional::<ChannelId>("channel")?
+                    .or_else(|| delegate.get_user_voice_channel(user_id))
+                {
+                    Some(channel_id) => channel_id,
+                    None => {
+                        return Ok(vec![Message::Response(
+                            ResponseMessage::NotInVoiceChannelError,
+                        )])
+                    }
+                };
+                ResponseMessage::ChannelQueue {
+                    voice_channel_id: channel_id,
+                    total_count: guild_model.queue_len(playback, &delegate, channel_id),
+                    songs: guild_model
+                        .upcoming_channel_entries(
+                            playback,
+                            &delegate,
+                            channel_id,
+                            CHANNEL_QUEUE_PREVIEW_LENGTH,
+                        )
+                        .into_iter()
+                        .map(|song| song.metadata.clone())
+                        .collect(),
+                }
+            }
+            Some("list") => {
+                self.paged_list_message(guild_id, guild_model, PagedList::UserQueue { user_id }, 1)?
+            }
+            Some("clear") => {
+                let count = guild_model.clear_user_entries(user_id);
+                if count > 0 {
+                    self.audit(ctx, guild_id, user_id, AuditAction::QueueCleared { count });
+                }
+                ResponseMessage::QueueCleared { count }
+            }
+            Some("shuffle") => {
+                match guild_model.shuffle_user_entries(user_id, &mut rand::thread_rng()) {
+                    0 => ResponseMessage::QueueEmptyError,
+                    count => ResponseMessage::QueueShuffled { count },
+                }
+            }
+            Some("remove") => {
+                // Positions start at 1, the same as in /queue list.
+                let position = args.required::<i64>("position")?;
+                let maybe_song = if position > 0 {
+                    guild_model.remove_user_entry(user_id, position as usize - 1)
+                } else {
+                    None
+                };
+                match maybe_song {
+                    Some(song) => ResponseMessage::QueueRemoved {
+                        song_title: song.metadata.title,
+                        song_url: song.metadata.url,
+                    },
+                    None => ResponseMessage::QueuePositionError { position },
+                }
+            }
+            _ => return Ok(Vec::new()),
         };
 
-        guild_model.push_entries(user_id, songs);
+        if !matches!(args.subcommand, Some("list") | Some("channel")) {
+            self.events.publish(Event::QueueChanged {
+                guild_id,
+                user_id,
+                by_script: false,
+            });
+        }
+        Ok(vec![Message::Response(message)])
+    }
 
-        // From this point on the user needs to be in a channel, otherwise the songs will only stay
-        // queued.
-        let channel_id = match delegate.get_user_voice_channel(user_id) {
-            Some(channel) => channel,
+    async fn handle_now_playing_command(
+        &self,
+        ctx: &Context,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let channel_id = match args.optional::<ChannelId>("channel")? {
+            Some(channel_id) => channel_id,
             None => {
-                log::trace!("User is not in any voice channel, song will remain queued");
-                return Ok(vec![Message::Response(match metadata {
-                    QueuedSongsMetadata::Single(song_metadata) => ResponseMessage::Queued {
-                        song_title: song_metadata.title,
-                        song_url: song_metadata.url,
-                    },
-                    QueuedSongsMetadata::Multiple(count) => {
-                        ResponseMessage::QueuedMultiple { count }
+                let delegate = ModelDelegate::new(ctx, guild_id).await?;
+                match delegate.get_user_voice_channel(user_id) {
+                    Some(channel_id) => channel_id,
+                    None => {
+                        return Ok(vec![Message::Response(
+                            ResponseMessage::NotInVoiceChannelError,
+                        )])
                     }
-                })]);
+                }
             }
         };
 
-        // Find a speaker that will be able to play in this channel. We do this before checking if
-        // we actually need to play anything so the song can stay in the queue if a speaker isn't
-        // found.
         let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
         let mut guild_speakers_ref = guild_speakers_handle.lock().await;
-        let guild_speaker = match guild_speakers_ref.find_to_play_in_channel(channel_id) {
-            Some(speaker) => speaker,
+        let message = match guild_speakers_ref.find_active_in_channel(channel_id) {
+            Some((guild_speaker, active_metadata)) => ResponseMessage::NowPlaying {
+                song_title: active_metadata.title,
+                song_url: active_metadata.url,
+                voice_channel_id: channel_id,
+                user_id: active_metadata.user_id,
+                is_paused: guild_speaker.pause_reason().is_some(),
+            },
+            None => ResponseMessage::NothingIsPlayingError {
+                voice_channel_id: channel_id,
+            },
+        };
+        Ok(vec![Message::Response(message)])
+    }
+
+    /// Details of the song playing in the user's voice channel, or one coming up in it.
+    async fn handle_song_info_command(
+        &self,
+        ctx: &Context,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+        playback: &PlaybackState,
+        guild_model: &GuildModel<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel_id) => channel_id,
             None => {
-                log::trace!(
-                    "No speakers are available to handle playback, song will remain queued"
-                );
-                return Ok(vec![Message::Response(match metadata {
-                    QueuedSongsMetadata::Single(song_metadata) => {
-                        ResponseMessage::QueuedNoSpeakers {
-                            song_title: song_metadata.title,
-                            song_url: song_metadata.url,
-                        }
-                    }
-                    QueuedSongsMetadata::Multiple(count) => {
-                        ResponseMessage::QueuedMultipleNoSpeakers { count }
-                    }
-                })]);
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NotInVoiceChannelError,
+                )])
             }
         };
 
-        // Play a song if the model indicates one isn't playing.
-        let next_song = match guild_model.next_channel_entry(&delegate, channel_id) {
-            NextEntry::Entry(song) => song,
-            NextEntry::AlreadyPlaying | NextEntry::NoneAvailable => {
-                log::trace!("Channel is already playing, song will remain queued");
-                return Ok(vec![Message::Response(match metadata {
-                    QueuedSongsMetadata::Single(song_metadata) => ResponseMessage::Queued {
-                        song_title: song_metadata.title,
-                        song_url: song_metadata.url,
+        let message = match args.optional::<i64>("position")? {
+            // Positions start at 1, the same as in /queue channel.
+            Some(position) => {
+                let maybe_metadata = if position > 0 {
+                    guild_model
+                        .upcoming_channel_entries(
+                            playback,
+                            &delegate,
+                            channel_id,
+                            position as usize,
+                        )
+                        .get(position as usize - 1)
+                        .map(|song| song.metadata.clone())
+                } else {
+                    None
+                };
+                match maybe_metadata {
+                    Some(metadata) => ResponseMessage::SongInfo {
+                        metadata: Box::new(metadata),
                     },
-                    QueuedSongsMetadata::Multiple(count) => {
-                        ResponseMessage::QueuedMultiple { count }
-                    }
-                })]);
+                    None => ResponseMessage::SongInfoPositionError {
+                        voice_channel_id: channel_id,
+                        position,
+                    },
+                }
+            }
+            None => {
+                let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+                let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+                match guild_speakers_ref.find_active_in_channel(channel_id) {
+                    Some((_, metadata)) => ResponseMessage::SongInfo {
+                        metadata: Box::new(metadata),
+                    },
+                    None => ResponseMessage::NothingIsPlayingError {
+                        voice_channel_id: channel_id,
+                    },
+                }
             }
         };
+        Ok(vec![Message::Response(message)])
+    }
 
-        let next_metadata = next_song.metadata.clone();
-        self.play_to_speaker(ctx, guild_model, guild_speaker, channel_id, next_song)
-            .await?;
+    /// What's playing where the user the command was used on is, or what they have queued if
+    /// they aren't listening to anything.
+    async fn handle_listening_user_command(
+        &self,
+        ctx: &Context,
+        command: &application_command::ApplicationCommandInteraction,
+        guild_id: GuildId,
+        playback: &PlaybackState,
+        guild_model: &GuildModel<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        // User commands only ever resolve the user they were used on.
+        let target_user_id = match command.data.resolved.users.keys().next() {
+            Some(user_id) => *user_id,
+            None => return Ok(Vec::new()),
+        };
 
-        // We could be in one of three states:
-        //  - One song was queued, and we're now playing that song. We only show a "playing"
-        //    message.
-        //  - Multiple songs were queued, and we're playing the first one. We show a "queued"
-        //    message and a "playing" message.
-        //    todo: maybe we should combine these in this case
-        // - We queued one or more songs and started a different song, which can happen if there
-        //   were other songs waiting but we weren't playing at the time.
-        match metadata {
-            QueuedSongsMetadata::Single(song_metadata) => {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let maybe_listening = match delegate.get_user_voice_channel(target_user_id) {
+            Some(channel_id) => {
+                let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+                let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+                guild_speakers_ref
+                    .find_active_in_channel(channel_id)
+                    .map(|(_, active_metadata)| (channel_id, active_metadata))
+            }
+            None => None,
+        };
+
+        let message = match maybe_listening {
+            Some((channel_id, active_metadata)) => ResponseMessage::UserListening {
+                user_id: target_user_id,
+                song_title: active_metadata.title,
+                song_url: active_metadata.url,
+                voice_channel_id: channel_id,
+                songs_before_next: guild_model.estimate_user_position(
+                    playback,
+                    &delegate,
+                    channel_id,
+                    target_user_id,
+                    USER_POSITION_LOOKAHEAD,
+                ),
+            },
+            None => ResponseMessage::UserNotListening {
+                user_id: target_user_id,
+                queued_count: guild_model.user_entries(target_user_id).count(),
+            },
+        };
+        Ok(vec![Message::Response(message)])
+    }
+
+    async fn handle_vote_info_command(
+        &self,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        playback: &PlaybackState,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel) => channel,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NotInVoiceChannelError,
+                )])
+            }
+        };
+
+        let message = match playback.vote_info(&delegate, channel_id) {
+            Some(vote_info) => ResponseMessage::VoteInfo {
+                voice_channel_id: channel_id,
+                skip_voters: vote_info.skip_voters,
+                skip_votes_needed: vote_info.skip_votes_needed,
+                stop_voters: vote_info.stop_voters,
+                stop_votes_needed: vote_info.stop_votes_needed,
+            },
+            None => ResponseMessage::NothingIsPlayingError {
+                voice_channel_id: channel_id,
+            },
+        };
+        Ok(vec![Message::Response(message)])
+    }
+
+    async fn handle_playlist_command(
+        self: &Arc<Self>,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model: &mut GuildModel<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        log::debug!("Received {}", args.full_name());
+        if args.optional::<bool>("guild")? == Some(true)
+            || matches!(
+                args.subcommand,
+                Some("move" | "remove" | "permission" | "log")
+            )
+        {
+            return self.handle_guild_playlist_command(
+                command,
+                args,
+                user_id,
+                guild_id,
+                guild_model,
+            );
+        }
+        if args.subcommand == Some("list") {
+            return Ok(vec![Message::Response(self.paged_list_message(
+                guild_id,
+                guild_model,
+                PagedList::Playlists { user_id },
+                1,
+            )?)]);
+        }
+
+        let name = args.required::<&str>("name")?.trim();
+        match args.subcommand {
+            Some("save") => {
+                let songs = queued_playlist_songs(guild_model, user_id);
+                if songs.is_empty() {
+                    return Ok(vec![Message::Response(ResponseMessage::QueueEmptyError)]);
+                }
+
+                let count = songs.len();
+                self.playlists.save(user_id, name.to_string(), songs);
+                Ok(vec![Message::Response(ResponseMessage::PlaylistSaved {
+                    name: name.to_string(),
+                    count,
+                })])
+            }
+            Some("delete") => {
+                if self.playlists.delete(user_id, name) {
+                    Ok(vec![Message::Response(ResponseMessage::PlaylistDeleted {
+                        name: name.to_string(),
+                    })])
+                } else {
+                    Ok(vec![Message::Response(
+                        ResponseMessage::PlaylistNotFoundError {
+                            name: name.to_string(),
+                        },
+                    )])
+                }
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn handle_guild_playlist_command(
+        &self,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model: &GuildModel<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        if args.subcommand == Some("list") {
+            return Ok(vec![Message::Response(self.paged_list_message(
+                guild_id,
+                guild_model,
+                PagedList::GuildPlaylists,
+                1,
+            )?)]);
+        }
+
+        let name = args.required::<&str>("name")?.trim();
+        if args.subcommand == Some("save") {
+            if self.guild_playlist_access(command, guild_id, user_id, None)
+                < PlaylistAccess::Contributors
+            {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::MissingPermissionError,
+                )]);
+            }
+            let songs = queued_playlist_songs(guild_model, user_id);
+            if songs.is_empty() {
+                return Ok(vec![Message::Response(ResponseMessage::QueueEmptyError)]);
+            }
+
+            let count = songs.len();
+            let message =
+                if self
+                    .guild_playlists
+                    .create(guild_id, user_id, name.to_string(), songs)?
+                {
+                    ResponseMessage::PlaylistSaved {
+                        name: name.to_string(),
+                        count,
+                    }
+                } else {
+                    ResponseMessage::GuildPlaylistExistsError {
+                        name: name.to_string(),
+                    }
+                };
+            return Ok(vec![Message::Response(message)]);
+        }
+
+        let playlist = match self.guild_playlists.get(guild_id, name)? {
+            Some(playlist) => playlist,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::GuildPlaylistNotFoundError {
+                        name: name.to_string(),
+                    },
+                )])
+            }
+        };
+        let access = self.guild_playlist_access(command, guild_id, user_id, Some(&playlist));
+        let action = match args.subcommand {
+            Some("move") => PlaylistAction::Reorder,
+            Some("remove" | "delete") => PlaylistAction::Delete,
+            _ => PlaylistAction::View,
+        };
+        // Only managers can change who's allowed to do what.
+        let required_access = match args.subcommand {
+            Some("permission") => PlaylistAccess::Managers,
+            _ => playlist.permissions.get(action),
+        };
+        if access < required_access {
+            return Ok(vec![Message::Response(
+                ResponseMessage::MissingPermissionError,
+            )]);
+        }
+
+        let message = match args.subcommand {
+            Some("delete") => {
+                self.guild_playlists.delete(guild_id, name)?;
+                ResponseMessage::PlaylistDeleted {
+                    name: name.to_string(),
+                }
+            }
+            Some("move") => {
+                let from = args.required::<i64>("from")?;
+                let to = args.required::<i64>("to")?;
+                let moved_song = match (usize::try_from(from - 1), usize::try_from(to - 1)) {
+                    (Ok(from_index), Ok(to_index)) => self
+                        .guild_playlists
+                        .move_song(guild_id, user_id, name, from_index, to_index)?,
+                    _ => None,
+                };
+                match moved_song {
+                    Some(song) => ResponseMessage::PlaylistSongMoved {
+                        name: name.to_string(),
+                        song_title: song.title,
+                        position: to,
+                    },
+                    None => ResponseMessage::PlaylistPositionError {
+                        name: name.to_string(),
+                        count: playlist.songs.len(),
+                    },
+                }
+            }
+            Some("remove") => {
+                let position = args.required::<i64>("position")?;
+                let removed_song = match usize::try_from(position - 1) {
+                    Ok(index) => self
+                        .guild_playlists
+                        .remove_song(guild_id, user_id, name, index)?,
+                    Err(_) => None,
+                };
+                match removed_song {
+                    Some(song) => ResponseMessage::PlaylistSongRemoved {
+                        name: name.to_string(),
+                        song_title: song.title,
+                    },
+                    None => ResponseMessage::PlaylistPositionError {
+                        name: name.to_string(),
+                        count: playlist.songs.len(),
+                    },
+                }
+            }
+            Some("permission") => {
+                let maybe_action = PlaylistAction::from_name(args.required::<&str>("action")?);
+                let maybe_access = PlaylistAccess::from_name(args.required::<&str>("who")?);
+                match (maybe_action, maybe_access) {
+                    (Some(action), Some(access)) => {
+                        self.guild_playlists
+                            .set_permission(guild_id, name, action, access)?;
+                        ResponseMessage::PlaylistPermissionSet {
+                            name: name.to_string(),
+                            action,
+                            access,
+                        }
+                    }
+                    _ => return Ok(Vec::new()),
+                }
+            }
+            Some("log") => ResponseMessage::PlaylistLog {
+                name: name.to_string(),
+                contributions: playlist
+                    .contributions
+                    .iter()
+                    .rev()
+                    .take(PLAYLIST_LOG_LENGTH)
+                    .cloned()
+                    .collect(),
+            },
+            _ => return Ok(Vec::new()),
+        };
+        Ok(vec![Message::Response(message)])
+    }
+
+    /// How much the user can do to a server playlist, or to one that doesn't exist yet.
+    fn guild_playlist_access(
+        &self,
+        command: &application_command::ApplicationCommandInteraction,
+        guild_id: GuildId,
+        user_id: UserId,
+        maybe_playlist: Option<&GuildPlaylist>,
+    ) -> PlaylistAccess {
+        let is_creator = maybe_playlist.is_some_and(|playlist| playlist.created_by == user_id);
+        if is_creator || can_manage_guild(command) {
+            return PlaylistAccess::Managers;
+        }
+        match (self.settings.get(guild_id).playlist_role, &command.member) {
+            (Some(role_id), Some(member)) if member.roles.contains(&role_id) => {
+                PlaylistAccess::Contributors
+            }
+            _ => PlaylistAccess::Everyone,
+        }
+    }
+
+    async fn handle_playlist_load_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model_handle: &GuildHandle<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let name = args.required::<&str>("name")?.trim();
+        let maybe_playlist_songs = if args.optional::<bool>("guild")? == Some(true) {
+            match self.guild_playlists.get(guild_id, name)? {
+                Some(playlist) => {
+                    let access =
+                        self.guild_playlist_access(command, guild_id, user_id, Some(&playlist));
+                    if access < playlist.permissions.get(PlaylistAction::View) {
+                        return Ok(vec![Message::Response(
+                            ResponseMessage::MissingPermissionError,
+                        )]);
+                    }
+                    Some(playlist.songs)
+                }
+                None => {
+                    return Ok(vec![Message::Response(
+                        ResponseMessage::GuildPlaylistNotFoundError {
+                            name: name.to_string(),
+                        },
+                    )])
+                }
+            }
+        } else {
+            self.playlists.get(user_id, name)
+        };
+        let playlist_songs = match maybe_playlist_songs {
+            Some(playlist_songs) => playlist_songs,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::PlaylistNotFoundError {
+                        name: name.to_string(),
+                    },
+                )])
+            }
+        };
+
+        self.queue_playlist_songs(ctx, user_id, guild_id, guild_model_handle, playlist_songs)
+            .await
+    }
+
+    /// Loads songs saved in a playlist, or anywhere else songs are saved, and queues them.
+    async fn queue_playlist_songs(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model_handle: &GuildHandle<Song>,
+        playlist_songs: Vec<PlaylistSong>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let delegate_future = ModelDelegate::new(ctx, guild_id);
+        let songs_future = self.load_playlist_songs(guild_id, user_id, playlist_songs);
+        let (delegate, songs) = futures::join!(delegate_future, songs_future);
+
+        self.queue_songs(ctx, user_id, guild_id, guild_model_handle, delegate?, songs)
+            .await
+    }
+
+    async fn handle_fav_command(
+        &self,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let message = match delegate.get_user_voice_channel(user_id) {
+            Some(channel_id) => {
+                self.favorite_playing_song(user_id, guild_id, channel_id)
+                    .await
+            }
+            None => ResponseMessage::NotInVoiceChannelError,
+        };
+        Ok(vec![Message::Response(message)])
+    }
+
+    /// Adds the song playing in the channel to the user's favorites.
+    async fn favorite_playing_song(
+        &self,
+        user_id: UserId,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    ) -> ResponseMessage {
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+        let metadata = match guild_speakers_ref.find_active_in_channel(channel_id) {
+            Some((_, active_metadata)) => active_metadata,
+            None => {
+                return ResponseMessage::NothingIsPlayingError {
+                    voice_channel_id: channel_id,
+                }
+            }
+        };
+
+        let song = PlaylistSong {
+            title: metadata.title.clone(),
+            url: metadata.url.clone(),
+        };
+        if self.favorites.add(user_id, song) {
+            ResponseMessage::FavoriteAdded {
+                song_title: metadata.title,
+                song_url: metadata.url,
+            }
+        } else {
+            ResponseMessage::FavoriteAlreadyAddedError {
+                song_title: metadata.title,
+            }
+        }
+    }
+
+    fn handle_favs_command(
+        &self,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model: &GuildModel<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        log::debug!("Received {}", args.full_name());
+        let message = match args.subcommand {
+            Some("list") => {
+                self.paged_list_message(guild_id, guild_model, PagedList::Favorites { user_id }, 1)?
+            }
+            Some("remove") => {
+                let position = args.required::<i64>("position")?;
+                let removed_song = match usize::try_from(position - 1) {
+                    Ok(index) => self.favorites.remove(user_id, index),
+                    Err(_) => None,
+                };
+                match removed_song {
+                    Some(song) => ResponseMessage::FavoriteRemoved {
+                        song_title: song.title,
+                    },
+                    None => ResponseMessage::FavoritePositionError { position },
+                }
+            }
+            _ => return Ok(Vec::new()),
+        };
+        Ok(vec![Message::Response(message)])
+    }
+
+    /// Adds songs to a server playlist. Loading them can take a while, so this doesn't hold the
+    /// guild model.
+    async fn handle_playlist_add_command(
+        self: &Arc<Self>,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let name = args.required::<&str>("name")?.trim();
+        let term = args.required::<&str>("song")?;
+        let playlist = match self.guild_playlists.get(guild_id, name)? {
+            Some(playlist) => playlist,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::GuildPlaylistNotFoundError {
+                        name: name.to_string(),
+                    },
+                )])
+            }
+        };
+        let access = self.guild_playlist_access(command, guild_id, user_id, Some(&playlist));
+        if access < playlist.permissions.get(PlaylistAction::Add) {
+            return Ok(vec![Message::Response(
+                ResponseMessage::MissingPermissionError,
+            )]);
+        }
+
+        let songs = match Song::load(term, user_id, &self.guild_play_config(guild_id).await).await {
+            Ok(songs) => songs,
+            Err(why) => return self.load_error_response(guild_id, term, why),
+        };
+        let songs = match self.blocklist.filter(guild_id, songs)? {
+            (songs, Some(rule)) if songs.is_empty() => {
+                return Ok(vec![Message::Response(ResponseMessage::Blocked { rule })]);
+            }
+            (songs, _) => songs,
+        };
+        if songs.is_empty() {
+            return Ok(vec![Message::Response(
+                ResponseMessage::NoMatchingSongsError,
+            )]);
+        }
+
+        let count = songs.len();
+        let playlist_songs = songs
+            .iter()
+            .map(|song| PlaylistSong {
+                title: song.metadata.title.clone(),
+                url: song.metadata.url.clone(),
+            })
+            .collect();
+        let message = if self
+            .guild_playlists
+            .add(guild_id, user_id, name, playlist_songs)?
+        {
+            ResponseMessage::PlaylistSongsAdded {
+                name: name.to_string(),
+                count,
+            }
+        } else {
+            // It was deleted while the songs were loading.
+            ResponseMessage::GuildPlaylistNotFoundError {
+                name: name.to_string(),
+            }
+        };
+        Ok(vec![Message::Response(message)])
+    }
+
+    /// Saves the songs in a playlist from another site as a playlist, without loading the songs
+    /// themselves until it's played.
+    async fn handle_playlist_import_command(
+        self: &Arc<Self>,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let name = args.required::<&str>("name")?.trim();
+        let url = args.required::<&str>("url")?.trim();
+        let is_guild = args.optional::<bool>("guild")? == Some(true);
+        if is_guild
+            && self.guild_playlist_access(command, guild_id, user_id, None)
+                < PlaylistAccess::Contributors
+        {
+            return Ok(vec![Message::Response(
+                ResponseMessage::MissingPermissionError,
+            )]);
+        }
+
+        let listing = match Song::list_playlist(url, &self.play_config()).await {
+            Ok(listing) => listing,
+            Err(why) => return self.load_error_response(guild_id, url, why),
+        };
+        if listing.entries.is_empty() {
+            return Ok(vec![Message::Response(
+                ResponseMessage::NoMatchingSongsError,
+            )]);
+        }
+
+        let count = listing.entries.len();
+        let songs = listing
+            .entries
+            .into_iter()
+            .map(|entry| PlaylistSong {
+                title: entry.title,
+                url: entry.url,
+            })
+            .collect();
+        if is_guild {
+            if !self
+                .guild_playlists
+                .create(guild_id, user_id, name.to_string(), songs)?
+            {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::GuildPlaylistExistsError {
+                        name: name.to_string(),
+                    },
+                )]);
+            }
+        } else {
+            self.playlists.save(user_id, name.to_string(), songs);
+        }
+        Ok(vec![Message::Response(ResponseMessage::PlaylistImported {
+            name: name.to_string(),
+            count,
+            failures: listing.failures,
+        })])
+    }
+
+    /// Loads a playlist's songs for the user, leaving out any that can't be loaded anymore.
+    async fn load_playlist_songs(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        playlist_songs: Vec<PlaylistSong>,
+    ) -> Vec<Song> {
+        let play_config = self.play_config();
+        stream::iter(playlist_songs)
+            .map(|playlist_song| {
+                let play_config = &play_config;
+                async move {
+                    let song_res = Song::fetch_one(&playlist_song.url, user_id, play_config).await;
+                    if let Err(why) = &song_res {
+                        log::warn!(
+                            "Unable to load {} from playlist: {}",
+                            playlist_song.url,
+                            why
+                        );
+                        self.extraction_failures
+                            .record(guild_id, &playlist_song.url, why);
+                    }
+                    song_res.ok()
+                }
+            })
+            .buffered(PLAYLIST_LOAD_CONCURRENCY)
+            .filter_map(future::ready)
+            .collect()
+            .await
+    }
+
+    async fn handle_greet_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        name: &str,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let greet = &self.config.greets[name];
+        let mut messages = Vec::new();
+        if let Some(content) = &greet.message {
+            messages.push(Message::Response(ResponseMessage::Custom {
+                content: content.clone(),
+            }));
+        }
+
+        if let Some(clip) = &greet.clip {
+            let clip_message = self
+                .play_clip_for_user(ctx, user_id, guild_id, clip, 1.)
+                .await?;
+
+            // The greet's own message is enough to show it worked.
+            if clip_message.is_error() || messages.is_empty() {
+                messages.push(Message::Response(clip_message));
+            }
+        }
+        Ok(messages)
+    }
+
+    async fn handle_sfx_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        log::debug!("Received {}", args.full_name());
+        if args.subcommand == Some("list") {
+            return Ok(vec![Message::Response(ResponseMessage::SoundEffects {
+                names: self.soundboard.names(guild_id)?,
+            })]);
+        }
+
+        let name = args.required::<&str>("name")?.trim();
+        let message = match args.subcommand {
+            Some("play") => match self.soundboard.get(guild_id, name)? {
+                Some(effect) => {
+                    self.play_clip_for_user(
+                        ctx,
+                        user_id,
+                        guild_id,
+                        &effect.url,
+                        effect.volume as f32 / 100.,
+                    )
+                    .await?
+                }
+                None => ResponseMessage::SoundEffectNotFoundError {
+                    name: name.to_string(),
+                },
+            },
+            Some("add") | Some("remove") if !can_manage_guild(command) => {
+                ResponseMessage::MissingPermissionError
+            }
+            Some("add") => {
+                let url = args.required::<&str>("url")?;
+                let volume = args
+                    .optional::<i64>("volume")?
+                    .unwrap_or(100)
+                    .clamp(0, MAX_SFX_VOLUME) as u32;
+                match SoundEffect::new(url, volume) {
+                    Some(effect) => {
+                        self.soundboard.add(guild_id, name.to_string(), effect)?;
+                        ResponseMessage::SoundEffectAdded {
+                            name: name.to_string(),
+                        }
+                    }
+                    None => ResponseMessage::InvalidSoundEffectUrlError,
+                }
+            }
+            Some("remove") => {
+                if self.soundboard.remove(guild_id, name)? {
+                    ResponseMessage::SoundEffectRemoved {
+                        name: name.to_string(),
+                    }
+                } else {
+                    ResponseMessage::SoundEffectNotFoundError {
+                        name: name.to_string(),
+                    }
+                }
+            }
+            _ => return Ok(Vec::new()),
+        };
+        Ok(vec![Message::Response(message)])
+    }
+
+    /// Plays a clip in the user's voice channel, over anything that's already playing there.
+    async fn play_clip_for_user(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        source: &str,
+        volume: f32,
+    ) -> Result<ResponseMessage, crate::error::Error> {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel_id) => channel_id,
+            None => {
+                log::trace!("User is not in any voice channel, clip won't be played");
+                return Ok(ResponseMessage::NotInVoiceChannelError);
+            }
+        };
+
+        // Clips play alongside whatever is already playing, so any speaker that isn't busy in
+        // another channel will do.
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+        let guild_speaker = match guild_speakers_ref.find_to_play_in_channel(channel_id).await {
+            Ok(speaker) => speaker,
+            Err(FindSpeakerError::ChannelNotAllowed) => {
+                log::trace!("Speakers aren't allowed in the channel, clip won't be played");
+                return Ok(ResponseMessage::ChannelNotAllowedError {
+                    voice_channel_id: channel_id,
+                });
+            }
+            Err(FindSpeakerError::NoneAvailable) => {
+                log::trace!("No speakers are available to play the clip");
+                return Ok(ResponseMessage::ClipNoSpeakersError {
+                    voice_channel_id: channel_id,
+                });
+            }
+        };
+
+        guild_speaker
+            .play_clip(channel_id, source, volume, &self.play_config())
+            .await
+            .map_err(crate::error::Error::Backend)?;
+        Ok(ResponseMessage::ClipPlayed {
+            voice_channel_id: channel_id,
+        })
+    }
+
+    async fn handle_trivia_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        log::debug!("Received {}", args.full_name());
+        if args.subcommand == Some("stop") {
+            let message = match self.trivia.get(guild_id) {
+                Some(game) if game.host_user_id != user_id && !can_manage_guild(command) => {
+                    ResponseMessage::MissingPermissionError
+                }
+                Some(game) => {
+                    game.stop();
+                    ResponseMessage::TriviaStopped
+                }
+                None => ResponseMessage::TriviaNotRunningError,
+            };
+            return Ok(vec![Message::Response(message)]);
+        }
+
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let voice_channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel_id) => channel_id,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NotInVoiceChannelError,
+                )])
+            }
+        };
+
+        // Excerpts would be drowned out by the song.
+        let is_music_playing = self
+            .backend_brain
+            .guild_speakers(guild_id)
+            .lock()
+            .await
+            .find_active_in_channel(voice_channel_id)
+            .is_some();
+        if is_music_playing {
+            return Ok(vec![Message::Response(
+                ResponseMessage::TriviaMusicPlayingError { voice_channel_id },
+            )]);
+        }
+
+        let urls = match args.optional::<&str>("playlist")? {
+            Some(name) => match self.playlists.get(user_id, name.trim()) {
+                Some(playlist_songs) => playlist_songs.into_iter().map(|song| song.url).collect(),
+                None => {
+                    return Ok(vec![Message::Response(
+                        ResponseMessage::PlaylistNotFoundError {
+                            name: name.trim().to_string(),
+                        },
+                    )])
+                }
+            },
+            None => self.stats.played_tracks(guild_id)?,
+        };
+        if urls.is_empty() {
+            return Ok(vec![Message::Response(ResponseMessage::TriviaNoSongsError)]);
+        }
+
+        let rounds = args
+            .optional::<i64>("rounds")?
+            .unwrap_or(DEFAULT_TRIVIA_ROUNDS)
+            .clamp(1, MAX_TRIVIA_ROUNDS) as usize;
+        let game = TriviaGame::new(user_id, command.channel_id, voice_channel_id, rounds);
+        let game = match self.trivia.start(guild_id, game) {
+            Some(game) => game,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::TriviaAlreadyRunningError,
+                )])
+            }
+        };
+        tokio::task::spawn(
+            self.clone()
+                .run_trivia_game(ctx.clone(), guild_id, game, urls),
+        );
+        Ok(vec![Message::Response(ResponseMessage::TriviaStarted {
+            voice_channel_id,
+            rounds,
+        })])
+    }
+
+    /// Plays each round of a trivia game, then shows everyone's scores.
+    async fn run_trivia_game(
+        self: Arc<Self>,
+        ctx: Context,
+        guild_id: GuildId,
+        game: Arc<TriviaGame>,
+        mut urls: Vec<String>,
+    ) {
+        urls.shuffle(&mut rand::thread_rng());
+        let mut urls = urls.into_iter();
+
+        let mut round_number = 0;
+        while round_number < game.rounds && !game.is_stopped() {
+            let song = match self.load_trivia_song(&mut urls, game.host_user_id).await {
+                Some(song) => song,
+                None => break,
+            };
+            round_number += 1;
+
+            match self
+                .play_trivia_excerpt(guild_id, game.voice_channel_id, &song)
+                .await
+            {
+                Ok(None) => {}
+                Ok(Some(message)) => {
+                    self.send_trivia_message(&ctx, guild_id, game.text_channel_id, message)
+                        .await;
+                    break;
+                }
+                Err(why) => {
+                    log::error!("Error while playing trivia excerpt: {}", why);
+                    break;
+                }
+            }
+            game.start_round(&song.metadata.title);
+            self.send_trivia_message(
+                &ctx,
+                guild_id,
+                game.text_channel_id,
+                ResponseMessage::TriviaRound {
+                    round_number,
+                    rounds: game.rounds,
+                },
+            )
+            .await;
+
+            let message = match game.wait_for_winner(TRIVIA_ROUND_LENGTH).await {
+                Some((user_id, time_taken)) => ResponseMessage::TriviaRoundWon {
+                    user_id,
+                    time_taken,
+                    song_title: song.metadata.title.clone(),
+                    song_url: song.metadata.url.clone(),
+                },
+                None => ResponseMessage::TriviaRoundMissed {
+                    song_title: song.metadata.title.clone(),
+                    song_url: song.metadata.url.clone(),
+                },
+            };
+            self.send_trivia_message(&ctx, guild_id, game.text_channel_id, message)
+                .await;
+
+            if round_number < game.rounds && !game.is_stopped() {
+                tokio::time::sleep(TRIVIA_ROUND_BREAK).await;
+            }
+        }
+
+        self.trivia.end(guild_id);
+        self.send_trivia_message(
+            &ctx,
+            guild_id,
+            game.text_channel_id,
+            ResponseMessage::TriviaFinished {
+                scores: game.scores(),
+            },
+        )
+        .await;
+    }
+
+    /// Loads the next song that can still be played. Songs from the play history may have been
+    /// taken down since, those are skipped.
+    async fn load_trivia_song(
+        &self,
+        urls: &mut impl Iterator<Item = String>,
+        user_id: UserId,
+    ) -> Option<Song> {
+        for url in urls {
+            match Song::fetch_one(&url, user_id, &self.play_config()).await {
+                Ok(mut song) => {
+                    let start = crate::trivia::excerpt_start(
+                        song.metadata.duration,
+                        &mut rand::thread_rng(),
+                    );
+                    song.set_start_position(start);
+                    return Some(song);
+                }
+                Err(why) => log::warn!("Skipping trivia song {}: {}", url, why),
+            }
+        }
+        None
+    }
+
+    /// Returns a message to show instead if there's no speaker that can play the excerpt.
+    async fn play_trivia_excerpt(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        song: &Song,
+    ) -> Result<Option<ResponseMessage>, crate::error::Error> {
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+        let guild_speaker = match guild_speakers_ref.find_to_play_in_channel(channel_id).await {
+            Ok(speaker) => speaker,
+            Err(FindSpeakerError::ChannelNotAllowed) => {
+                return Ok(Some(ResponseMessage::ChannelNotAllowedError {
+                    voice_channel_id: channel_id,
+                }))
+            }
+            Err(FindSpeakerError::NoneAvailable) => {
+                return Ok(Some(ResponseMessage::ClipNoSpeakersError {
+                    voice_channel_id: channel_id,
+                }))
+            }
+        };
+        guild_speaker
+            .play_excerpt(
+                channel_id,
+                song,
+                crate::trivia::EXCERPT_LENGTH,
+                &self.play_config(),
+            )
+            .await
+            .map_err(crate::error::Error::Backend)?;
+        Ok(None)
+    }
+
+    async fn send_trivia_message(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        message: ResponseMessage,
+    ) {
+        let guild_model_handle = self.model.get(guild_id);
+        let mut playback = guild_model_handle.lock_playback().await;
+        let send_res = send_messages(
+            &self.config,
+            ctx,
+            SendMessageDestination::Channel(channel_id),
+            playback.deref_mut(),
+            vec![Message::Response(message)],
+        )
+        .await;
+        if let Err(why) = send_res {
+            log::error!("Error while sending trivia message: {}", why);
+        }
+    }
+
+    fn handle_schedule_command(
+        &self,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        log::debug!("Received {}", args.full_name());
+        let message = match args.subcommand {
+            Some("list") => {
+                let schedules = self
+                    .schedules
+                    .list(guild_id)?
+                    .into_iter()
+                    .map(|schedule| {
+                        let next_run = self.schedules.next_run(&schedule.cron);
+                        (schedule, next_run)
+                    })
+                    .collect();
+                ResponseMessage::Schedules { schedules }
+            }
+            Some("add") if !can_manage_guild(command) => ResponseMessage::MissingPermissionError,
+            Some("add") => {
+                let cron = args.required::<&str>("cron")?.trim();
+                let voice_channel_id = args.required::<ChannelId>("channel")?;
+                let source = ScheduleSource::parse(args.required::<&str>("play")?.trim());
+                // Expressions that can't be parsed and ones for dates that don't exist are both
+                // turned away.
+                let next_run = match self.schedules.next_run(cron) {
+                    Some(next_run) => next_run,
+                    None => return Ok(vec![Message::Response(ResponseMessage::InvalidCronError)]),
+                };
+                if let ScheduleSource::Playlist(name) = &source {
+                    if self.playlists.get(user_id, name).is_none() {
+                        return Ok(vec![Message::Response(
+                            ResponseMessage::PlaylistNotFoundError { name: name.clone() },
+                        )]);
+                    }
+                }
+
+                let add_res = self.schedules.add(
+                    guild_id,
+                    cron.to_string(),
+                    voice_channel_id,
+                    command.channel_id,
+                    user_id,
+                    source,
+                )?;
+                match add_res {
+                    Some(id) => ResponseMessage::ScheduleAdded {
+                        id,
+                        voice_channel_id,
+                        next_run,
+                    },
+                    None => ResponseMessage::TooManySchedulesError,
+                }
+            }
+            Some("remove") => {
+                let id = args.required::<i64>("id")?.max(0) as u64;
+                match self.schedules.get(guild_id, id)? {
+                    // People can remove their own schedules even if they can't add new ones.
+                    Some(schedule) if schedule.user_id != user_id && !can_manage_guild(command) => {
+                        ResponseMessage::MissingPermissionError
+                    }
+                    Some(_) => {
+                        self.schedules.remove(guild_id, id)?;
+                        ResponseMessage::ScheduleRemoved { id }
+                    }
+                    None => ResponseMessage::ScheduleNotFoundError { id },
+                }
+            }
+            _ => return Ok(Vec::new()),
+        };
+        Ok(vec![Message::Response(message)])
+    }
+
+    /// Starts scheduled playback when it's due. Schedules that were due while the bot wasn't
+    /// running are skipped.
+    pub async fn run_schedules(self: Arc<Self>, ctx: Context) {
+        let mut last_checked = self.schedules.now();
+        let mut interval = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            // The new process runs schedules once it has taken over.
+            if self.is_handed_over() {
+                return;
+            }
+
+            let now = self.schedules.now();
+            while last_checked < now {
+                last_checked += chrono::Duration::minutes(1);
+                for guild_id in ctx.cache.guilds().await {
+                    match self.schedules.due(guild_id, last_checked) {
+                        Ok(due_schedules) => {
+                            for schedule in due_schedules {
+                                if self.is_in_maintenance() {
+                                    log::info!(
+                                        "Skipping schedule {} during maintenance",
+                                        schedule.id
+                                    );
+                                    continue;
+                                }
+                                tokio::task::spawn(self.clone().run_schedule(
+                                    ctx.clone(),
+                                    guild_id,
+                                    schedule,
+                                ));
+                            }
+                        }
+                        Err(why) => log::error!("Error while loading schedules: {}", why),
+                    }
+                }
+            }
+        }
+    }
+
+    async fn run_schedule(self: Arc<Self>, ctx: Context, guild_id: GuildId, schedule: Schedule) {
+        log::debug!("Running schedule {} in guild {}", schedule.id, guild_id);
+        if !self
+            .entitlements
+            .allows(guild_id, PremiumFeature::Schedules)
+            .await
+        {
+            log::debug!("Skipping schedule in guild that can't use them");
+            return;
+        }
+        match self.music_bans.is_banned(guild_id, schedule.user_id) {
+            Ok(false) => {}
+            Ok(true) => {
+                log::debug!("Skipping schedule from banned user");
+                return;
+            }
+            Err(why) => {
+                log::error!("Error while running schedule: {}", why);
+                return;
+            }
+        }
+
+        // Songs are loaded before locking the guild, like with commands.
+        let songs = match &schedule.source {
+            ScheduleSource::Playlist(name) => match self.playlists.get(schedule.user_id, name) {
+                Some(playlist_songs) => {
+                    self.load_playlist_songs(guild_id, schedule.user_id, playlist_songs)
+                        .await
+                }
+                None => Vec::new(),
+            },
+            ScheduleSource::Url(url) => {
+                match Song::load(
+                    url,
+                    schedule.user_id,
+                    &self.guild_play_config(guild_id).await,
+                )
+                .await
+                {
+                    Ok(songs) => songs,
+                    Err(why) => {
+                        log::warn!("Unable to load {} for schedule: {}", url, why);
+                        self.extraction_failures.record(guild_id, url, &why);
+                        Vec::new()
+                    }
+                }
+            }
+        };
+
+        let guild_model_handle = self.model.get(guild_id);
+        let (mut playback, mut guild_model) = guild_model_handle.lock().await;
+        self.update_message_channel(
+            &ctx,
+            guild_id,
+            playback.deref_mut(),
+            schedule.text_channel_id,
+        )
+        .await;
+        let messages = match self
+            .start_schedule(
+                &ctx,
+                guild_id,
+                playback.deref_mut(),
+                guild_model.deref_mut(),
+                &schedule,
+                songs,
+            )
+            .await
+        {
+            Ok(messages) => messages,
+            Err(why) => {
+                log::error!("Error while running schedule: {}", why);
+                return;
+            }
+        };
+        drop(guild_model);
+
+        let message_channel = playback
+            .message_channel()
+            .unwrap_or(schedule.text_channel_id);
+        let send_res = send_messages(
+            &self.config,
+            &ctx,
+            SendMessageDestination::Channel(message_channel),
+            playback.deref_mut(),
+            messages,
+        )
+        .await;
+        if let Err(why) = send_res {
+            log::error!("Error while sending schedule messages: {}", why);
+        }
+    }
+
+    /// Queues a schedule's songs for whoever made it and starts playing them in its channel,
+    /// unless the channel is already in use.
+    async fn start_schedule(
+        self: &Arc<Self>,
+        ctx: &Context,
+        guild_id: GuildId,
+        playback: &mut PlaybackState,
+        guild_model: &mut GuildModel<Song>,
+        schedule: &Schedule,
+        songs: Vec<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let channel_id = schedule.voice_channel_id;
+        let (songs, _) = self.blocklist.filter(guild_id, songs)?;
+        if songs.is_empty() {
+            return Ok(vec![Message::Response(
+                ResponseMessage::ScheduleNoSongsError { id: schedule.id },
+            )]);
+        }
+
+        let is_trivia_running = self
+            .trivia
+            .get(guild_id)
+            .is_some_and(|game| game.voice_channel_id == channel_id);
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+        if is_trivia_running
+            || guild_speakers_ref
+                .find_active_in_channel(channel_id)
+                .is_some()
+        {
+            log::trace!("Channel is already in use, schedule won't be played");
+            return Ok(vec![Message::Response(
+                ResponseMessage::ScheduleChannelInUseError {
+                    id: schedule.id,
+                    voice_channel_id: channel_id,
+                },
+            )]);
+        }
+        let guild_speaker = match guild_speakers_ref.find_to_play_in_channel(channel_id).await {
+            Ok(speaker) => speaker,
+            Err(FindSpeakerError::ChannelNotAllowed) => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::ChannelNotAllowedError {
+                        voice_channel_id: channel_id,
+                    },
+                )])
+            }
+            Err(FindSpeakerError::NoneAvailable) => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::ScheduleNoSpeakersError {
+                        id: schedule.id,
+                        voice_channel_id: channel_id,
+                    },
+                )])
+            }
+        };
+
+        // Whoever made the schedule doesn't need to be there for their songs to play.
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let push_result = guild_model.push_entries_bulk(schedule.user_id, songs);
+        if push_result.rejected > 0 {
+            log::warn!(
+                "Schedule {} in guild {} left out {} songs past the queue limit",
+                schedule.id,
+                guild_id,
+                push_result.rejected
+            );
+        }
+        playback.set_channel_host(channel_id, schedule.user_id);
+        self.events.publish(Event::QueueChanged {
+            guild_id,
+            user_id: schedule.user_id,
+            by_script: false,
+        });
+        let next_song = match guild_model.next_channel_entry(playback, &delegate, channel_id) {
+            NextEntry::Entry(song) => song,
+            NextEntry::AlreadyPlaying | NextEntry::NoneAvailable => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::ScheduleChannelInUseError {
+                        id: schedule.id,
+                        voice_channel_id: channel_id,
+                    },
+                )])
+            }
+        };
+
+        let next_metadata = next_song.metadata.clone();
+        self.play_to_speaker(
+            ctx,
+            playback,
+            guild_model.features(),
+            guild_speaker,
+            channel_id,
+            next_song,
+        )
+        .await?;
+        Ok(vec![
+            Message::Response(ResponseMessage::ScheduleStarted {
+                id: schedule.id,
+                voice_channel_id: channel_id,
+            }),
+            Message::Action(ActionMessage::Playing {
+                song_title: next_metadata.title,
+                song_url: next_metadata.url,
+                voice_channel_id: channel_id,
+                user_id: next_metadata.user_id,
+            }),
+        ])
+    }
+
+    fn handle_settings_command(
+        &self,
+        ctx: &Context,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model: &mut GuildModel<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        log::debug!("Received {}", args.full_name());
+
+        let previous_audit_channel = self.settings.get(guild_id).audit_channel;
+        let settings = if args.subcommand == Some("show") {
+            self.settings.get(guild_id)
+        } else {
+            if !can_manage_guild(command) {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::MissingPermissionError,
+                )]);
+            }
+            let strategy = args.optional::<&str>("strategy")?;
+            let drop_absent = args.optional::<bool>("drop_absent")?;
+            let enabled = args.optional::<bool>("enabled")?;
+            let channel = args.optional::<ChannelId>("channel")?;
+            let role = args.optional::<RoleId>("role")?;
+            self.settings
+                .update(guild_id, |settings| match (args.group, args.subcommand) {
+                    (None, Some("queue")) => {
+                        if let Some(queue_strategy) = strategy.and_then(QueueStrategy::from_name) {
+                            settings.queue_strategy = queue_strategy;
+                        }
+                        if let Some(val) = drop_absent {
+                            settings.drop_absent_entries = val;
+                        }
+                    }
+                    (None, Some("follow")) => {
+                        if let Some(val) = enabled {
+                            settings.follow_requester = val;
+                        }
+                    }
+                    (Some("announce-channel"), subcommand) => match (subcommand, channel) {
+                        (Some("lock"), Some(channel_id)) => {
+                            settings.locked_message_channel = Some(channel_id)
+                        }
+                        (Some("unlock"), _) => settings.locked_message_channel = None,
+                        (Some("notify"), _) => {
+                            if let Some(val) = enabled {
+                                settings.notify_message_channel_moves = val;
+                            }
+                        }
+                        _ => {}
+                    },
+                    (None, Some("session-threads")) => {
+                        if let Some(val) = enabled {
+                            settings.session_threads = val;
+                        }
+                    }
+                    (None, Some("announcements")) => {
+                        if let Some(val) = enabled {
+                            settings.announce_songs = val;
+                        }
+                    }
+                    (None, Some("music-mode")) => {
+                        if let Some(val) = enabled {
+                            settings.music_mode = val;
+                        }
+                    }
+                    (None, Some("gapless")) => {
+                        if let Some(val) = enabled {
+                            settings.gapless = val;
+                        }
+                    }
+                    (None, Some("now-playing-cards")) => {
+                        if let Some(val) = enabled {
+                            settings.now_playing_cards = val;
+                        }
+                    }
+                    (Some("voice-channels"), subcommand) => match (subcommand, channel) {
+                        (Some("block"), Some(channel_id))
+                            if !settings.blocked_voice_channels.contains(&channel_id) =>
+                        {
+                            settings.blocked_voice_channels.push(channel_id);
+                        }
+                        (Some("unblock"), Some(channel_id)) => settings
+                            .blocked_voice_channels
+                            .retain(|existing_channel_id| *existing_channel_id != channel_id),
+                        (Some("clear"), _) => settings.blocked_voice_channels.clear(),
+                        _ => {}
+                    },
+                    (Some("command-channels"), subcommand) => match (subcommand, channel) {
+                        (Some("add"), Some(channel_id))
+                            if !settings.command_channels.contains(&channel_id) =>
+                        {
+                            settings.command_channels.push(channel_id);
+                        }
+                        (Some("remove"), Some(channel_id)) => settings
+                            .command_channels
+                            .retain(|existing_channel_id| *existing_channel_id != channel_id),
+                        (Some("clear"), _) => settings.command_channels.clear(),
+                        _ => {}
+                    },
+                    (Some("audit-channel"), subcommand) => match (subcommand, channel) {
+                        (Some("set"), Some(channel_id)) => {
+                            settings.audit_channel = Some(channel_id)
+                        }
+                        (Some("clear"), _) => settings.audit_channel = None,
+                        _ => {}
+                    },
+                    (Some("playlist-role"), subcommand) => match (subcommand, role) {
+                        (Some("set"), Some(role_id)) => settings.playlist_role = Some(role_id),
+                        (Some("clear"), _) => settings.playlist_role = None,
+                        _ => {}
+                    },
+                    _ => {}
+                })?
+        };
+
+        if args.subcommand != Some("show") {
+            // Turning the audit channel off is recorded in the channel it was in.
+            if let Some(audit_channel) = settings.audit_channel.or(previous_audit_channel) {
+                post_audit_entry(
+                    ctx.http.clone(),
+                    audit_channel,
+                    AuditEntry::new(
+                        user_id,
+                        AuditAction::SettingsChanged {
+                            command: args.full_name(),
+                        },
+                    ),
+                );
+            }
+        }
+        settings.apply(guild_model);
+
+        Ok(vec![Message::Response(ResponseMessage::Settings {
+            settings,
+        })])
+    }
+
+    /// The config for loading songs, which includes the local library if there is one.
+    fn play_config(&self) -> PlayConfig<'_> {
+        let mut play_config = self.config.get_play_config();
+        play_config.library = self.library.as_ref();
+        play_config
+    }
+
+    /// The config for loading songs in the guild, which searches in music mode if it's on and the
+    /// guild can use it.
+    async fn guild_play_config(&self, guild_id: GuildId) -> PlayConfig<'_> {
+        let mut play_config = self.play_config();
+        if self.settings.get(guild_id).features().music_mode
+            && self
+                .entitlements
+                .allows(guild_id, PremiumFeature::MusicMode)
+                .await
+        {
+            play_config.music_mode_search_prefix = Some(&self.config.music_mode_search_prefix);
+        }
+        play_config
+    }
+
+    /// Responds to the reasons songs can't be loaded that users should know about, instead of with
+    /// the generic error message.
+    fn load_error_response(
+        &self,
+        guild_id: GuildId,
+        term: &str,
+        why: mrvn_back_ytdl::Error,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        self.extraction_failures.record(guild_id, term, &why);
+        let message = match why.classification() {
+            mrvn_back_ytdl::Error::UnsupportedUrl => ResponseMessage::UnsupportedSiteError,
+            mrvn_back_ytdl::Error::NetworkTimeout => ResponseMessage::NetworkTimeoutError,
+            mrvn_back_ytdl::Error::VideoUnavailable => ResponseMessage::VideoUnavailableError,
+            mrvn_back_ytdl::Error::AgeRestricted => ResponseMessage::AgeRestrictedError,
+            mrvn_back_ytdl::Error::GeoBlocked => ResponseMessage::GeoBlockedError,
+            mrvn_back_ytdl::Error::PrivateVideo => ResponseMessage::PrivateVideoError,
+            mrvn_back_ytdl::Error::LiveNotSupported => ResponseMessage::LiveNotSupportedError,
+            mrvn_back_ytdl::Error::ExtractorOutdated => ResponseMessage::ExtractorOutdatedError,
+            _ => return Err(crate::error::Error::Backend(why)),
+        };
+        Ok(vec![Message::Response(message)])
+    }
+
+    /// Records an action in the guild's audit channel, if it has one.
+    fn audit(&self, ctx: &Context, guild_id: GuildId, user_id: UserId, action: AuditAction) {
+        if let Some(audit_channel) = self.settings.get(guild_id).audit_channel {
+            post_audit_entry(
+                ctx.http.clone(),
+                audit_channel,
+                AuditEntry::new(user_id, action),
+            );
+        }
+    }
+
+    /// Music commands can be limited to some text channels, this points people to them when a
+    /// command is run somewhere else.
+    fn check_command_channel(
+        &self,
+        command: &application_command::ApplicationCommandInteraction,
+        guild_id: GuildId,
+    ) -> Option<Message> {
+        if !MUSIC_COMMANDS.contains(&command.data.name.as_str()) {
+            return None;
+        }
+
+        let command_channels = self.settings.get(guild_id).command_channels;
+        if command_channels.is_empty() || command_channels.contains(&command.channel_id) {
+            None
+        } else {
+            log::debug!("Refusing {} outside of command channels", command.data.name);
+            Some(Message::Response(ResponseMessage::WrongChannelError {
+                channel_ids: command_channels,
+            }))
+        }
+    }
+
+    fn handle_blocklist_command(
+        &self,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
+        guild_id: GuildId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        log::debug!("Received {}", args.full_name());
+
+        if args.subcommand == Some("list") {
+            return Ok(vec![Message::Response(ResponseMessage::Blocklist {
+                rules: self.blocklist.rules(guild_id)?,
+            })]);
+        }
+
+        if !can_manage_guild(command) {
+            return Ok(vec![Message::Response(
+                ResponseMessage::MissingPermissionError,
+            )]);
+        }
+
+        let kind = args.required::<&str>("kind")?;
+        let value = args.required::<&str>("value")?;
+        let rule = match BlockRule::new(kind, value) {
+            Some(rule) => rule,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::InvalidBlockRuleError,
+                )])
+            }
+        };
+
+        match args.subcommand {
+            Some("add") => {
+                if self.blocklist.add(guild_id, rule.clone())? {
+                    Ok(vec![Message::Response(ResponseMessage::BlocklistAdded {
+                        rule,
+                    })])
+                } else {
+                    Ok(vec![Message::Response(
+                        ResponseMessage::BlocklistAlreadyAddedError { rule },
+                    )])
+                }
+            }
+            Some("remove") => {
+                if self.blocklist.remove(guild_id, &rule)? {
+                    Ok(vec![Message::Response(ResponseMessage::BlocklistRemoved {
+                        rule,
+                    })])
+                } else {
+                    Ok(vec![Message::Response(
+                        ResponseMessage::BlocklistNotFoundError { rule },
+                    )])
+                }
+            }
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Summarizes songs the model dropped because the people that queued them left.
+    fn dropped_entries_messages(&self, guild_model: &mut GuildModel<Song>) -> Vec<Message> {
+        let dropped_entries = guild_model.take_dropped_entries();
+        if dropped_entries.is_empty() {
+            return Vec::new();
+        }
+
+        vec![Message::Response(ResponseMessage::DroppedAbsentEntries {
+            count: dropped_entries.iter().map(|dropped| dropped.count).sum(),
+            user_ids: dropped_entries
+                .iter()
+                .map(|dropped| dropped.user_id)
+                .collect(),
+        })]
+    }
+
+    /// Leaves out mentions from announcements for users that don't want them.
+    fn apply_user_prefs(&self, messages: Vec<Message>) -> Vec<Message> {
+        messages
+            .into_iter()
+            .map(|message| match message {
+                Message::Action(ActionMessage::Playing {
+                    song_title,
+                    song_url,
+                    voice_channel_id,
+                    user_id,
+                }) if !self.prefs.get(user_id).announce_mention => {
+                    Message::Action(ActionMessage::PlayingNoMention {
+                        song_title,
+                        song_url,
+                        voice_channel_id,
+                    })
+                }
+                message => message,
+            })
+            .collect()
+    }
+
+    /// Gives messages about songs starting to play a card showing the song, if the guild has
+    /// cards turned on and can use them. If a card can't be drawn, the message is sent without one.
+    async fn add_now_playing_cards(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        messages: Vec<Message>,
+    ) -> Vec<Message> {
+        if !self.settings.get(guild_id).features().now_playing_cards
+            || !self
+                .entitlements
+                .allows(guild_id, PremiumFeature::NowPlayingCards)
+                .await
+        {
+            return messages;
+        }
+
+        let mut carded_messages = Vec::with_capacity(messages.len());
+        for message in messages {
+            let (action, voice_channel_id) = match message {
+                Message::Action(
+                    action @ (ActionMessage::Playing {
+                        voice_channel_id, ..
+                    }
+                    | ActionMessage::PlayingNoMention {
+                        voice_channel_id, ..
+                    }),
+                ) => (action, voice_channel_id),
+                message => {
+                    carded_messages.push(message);
+                    continue;
+                }
+            };
+
+            let action = match self.now_playing_card(ctx, guild_id, voice_channel_id).await {
+                Ok(Some(card)) => ActionMessage::WithCard {
+                    message: Box::new(action),
+                    card,
+                },
+                Ok(None) => action,
+                Err(why) => {
+                    log::warn!("Error while drawing now playing card: {}", why);
+                    action
+                }
+            };
+            carded_messages.push(Message::Action(action));
+        }
+        carded_messages
+    }
+
+    async fn now_playing_card(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+    ) -> Result<Option<NowPlayingCard>, crate::error::Error> {
+        let (metadata, position) = {
+            let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+            let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+            match guild_speakers_ref.find_active_in_channel(channel_id) {
+                Some((guild_speaker, metadata)) => {
+                    let position = guild_speaker.active_position().await.unwrap_or_default();
+                    (metadata, position)
+                }
+                None => return Ok(None),
+            }
+        };
+
+        let user = metadata
+            .user_id
+            .to_user(ctx)
+            .await
+            .map_err(crate::error::Error::Serenity)?;
+        let avatar_url = user
+            .static_avatar_url()
+            .unwrap_or_else(|| user.default_avatar_url());
+        self.cards
+            .render(&metadata, &avatar_url, position)
+            .await
+            .map(Some)
+    }
+
+    fn track_started(
+        self: &Arc<Self>,
+        ctx: &Context,
+        guild_id: GuildId,
+        features: GuildFeatures,
+        channel_id: ChannelId,
+        metadata: &SongMetadata,
+    ) {
+        self.stats.track_started(guild_id, channel_id);
+        self.events.publish(Event::TrackStarted {
+            guild_id,
+            channel_id,
+            song_title: metadata.title.clone(),
+            song_url: metadata.url.clone(),
+            user_id: metadata.user_id,
+        });
+
+        if self.prefs.get(metadata.user_id).dm_notifications {
+            tokio::task::spawn(self.clone().send_playing_dm(
+                ctx.clone(),
+                channel_id,
+                metadata.clone(),
+            ));
+        }
+
+        if features.gapless && metadata.collection.is_some() {
+            tokio::task::spawn(self.clone().prime_next_song(
+                ctx.clone(),
+                guild_id,
+                channel_id,
+                metadata.clone(),
+            ));
+        }
+    }
+
+    /// Waits until the song is nearly over, then starts loading the next one if it's from the
+    /// same album or playlist, so it can play as soon as the song ends. Gives up if something
+    /// else starts playing in the meantime.
+    async fn prime_next_song(
+        self: Arc<Self>,
+        ctx: Context,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        metadata: SongMetadata,
+    ) {
+        if !self
+            .entitlements
+            .allows(guild_id, PremiumFeature::Gapless)
+            .await
+        {
+            return;
+        }
+        let duration = match metadata.duration {
+            Some(duration) => duration,
+            None => return,
+        };
+
+        // The song could be paused or skipped around in, so how long is left is checked again
+        // after each wait.
+        loop {
+            let position = {
+                let speakers_handle = self.backend_brain.guild_speakers(guild_id);
+                let mut speakers_ref = speakers_handle.lock().await;
+                match speakers_ref.find_active_in_channel(channel_id) {
+                    Some((guild_speaker, active_metadata))
+                        if active_metadata.url == metadata.url
+                            && active_metadata.queued_at == metadata.queued_at =>
+                    {
+                        guild_speaker.active_position().await.unwrap_or_default()
+                    }
+                    _ => return,
+                }
+            };
+            let remaining = duration.saturating_sub(position);
+            if remaining <= GAPLESS_PRIME_LEAD {
+                break;
+            }
+            tokio::time::sleep(remaining - GAPLESS_PRIME_LEAD).await;
+        }
+
+        let delegate = match ModelDelegate::new(&ctx, guild_id).await {
+            Ok(delegate) => delegate,
+            Err(why) => {
+                log::warn!("Unable to look up the next song to prime: {}", why);
+                return;
+            }
+        };
+        let next_song = {
+            let guild_model_handle = self.model.get(guild_id);
+            let (playback, guild_model) = guild_model_handle.lock().await;
+            match guild_model.peek_next_channel_entry(&playback, &delegate, channel_id) {
+                Some(song) if song.metadata.collection == metadata.collection => song.clone(),
+                _ => return,
+            }
+        };
+
+        log::trace!("Priming \"{}\" to play gaplessly", next_song.metadata.title);
+        let primed_song = match PrimedSong::new(&next_song, &self.play_config()).await {
+            Ok(primed_song) => primed_song,
+            Err(why) => {
+                log::warn!("Error while priming the next song: {}", why);
+                return;
+            }
+        };
+        let speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut speakers_ref = speakers_handle.lock().await;
+        if let Some(guild_speaker) = speakers_ref.find_in_channel(channel_id) {
+            guild_speaker.prime(primed_song);
+        }
+    }
+
+    /// What's read out in voice before the song plays, if the guild has announcements turned on
+    /// and can use them, and text to speech is set up.
+    async fn song_announcement(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        features: GuildFeatures,
+        metadata: &SongMetadata,
+    ) -> Option<String> {
+        if self.config.tts.is_none()
+            || !features.announcements
+            || !self
+                .entitlements
+                .allows(guild_id, PremiumFeature::Announcements)
+                .await
+        {
+            return None;
+        }
+
+        let user_name = match guild_id.member(ctx, metadata.user_id).await {
+            Ok(member) => member.display_name().into_owned(),
+            Err(why) => {
+                log::warn!("Unable to find who queued the song to announce: {}", why);
+                return None;
+            }
+        };
+        Some(self.config.get_message(
+            "tts.now_playing",
+            &[("song_title", &metadata.title), ("user_name", &user_name)],
+        ))
+    }
+
+    async fn send_playing_dm(
+        self: Arc<Self>,
+        ctx: Context,
+        channel_id: ChannelId,
+        metadata: SongMetadata,
+    ) {
+        log::trace!(
+            "Notifying user {} that their song is playing",
+            metadata.user_id
+        );
+        let user_prefs = self.prefs.get(metadata.user_id);
+        let config = self.config.localized(user_prefs.language.as_deref());
+        let channel_id_string = channel_id.0.to_string();
+        let content = config.get_message(
+            "dm.playing",
+            &[
+                ("song_title", &metadata.title),
+                ("song_url", &metadata.url),
+                ("voice_channel_id", &channel_id_string),
+            ],
+        );
+
+        let send_res = async {
+            let dm_channel = metadata.user_id.create_dm_channel(&ctx.http).await?;
+            dm_channel
+                .send_message(&ctx.http, |create_message| {
+                    create_message
+                        .embed(|embed| embed.description(content).color(config.action_embed_color))
+                })
+                .await
+        }
+        .await;
+        if let Err(why) = send_res {
+            log::error!("Error while sending playing notification: {}", why);
+        }
+    }
+
+    async fn run_script_actions(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model_handle: &GuildHandle<Song>,
+        actions: Vec<ScriptAction>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let mut messages = Vec::new();
+        for action in actions {
+            match action {
+                ScriptAction::Enqueue { term } => {
+                    log::trace!("Script is queueing \"{}\"", term);
+                    let play_messages = self
+                        .handle_queue_play_command(
+                            ctx,
+                            user_id,
+                            guild_id,
+                            guild_model_handle,
+                            &term,
+                            PlayOptions::default(),
+                        )
+                        .await?;
+                    messages.extend(play_messages);
+                }
+                ScriptAction::SendMessage { content } => {
+                    messages.push(Message::Response(ResponseMessage::Custom { content }));
+                }
+            }
+        }
+
+        // Commands always need a response, even if the script didn't do anything visible.
+        if messages.is_empty() {
+            messages.push(Message::Response(ResponseMessage::ScriptFinished));
+        }
+        Ok(messages)
+    }
+
+    pub async fn run_event_scripts(self: Arc<Self>, http: Arc<Http>) {
+        let mut receiver = self.events.subscribe();
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Event scripts fell behind, skipped {} events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+
+            if matches!(
+                event,
+                Event::QueueChanged {
+                    by_script: true,
+                    ..
+                }
+            ) {
+                continue;
+            }
+
+            let guild_id = event.guild_id();
+            let maybe_user_id = event.user_id();
+            let script_ctx = {
+                let guild_model_handle = self.model.get(guild_id);
+                let guild_model = guild_model_handle.lock_queues().await;
+                script_context(guild_id, maybe_user_id, String::new(), &guild_model)
+            };
+            let actions = self.scripts.run_event(&event, script_ctx);
+
+            // Loading songs can take a while, so it happens in the background instead of holding
+            // up the next events' scripts.
+            if !actions.is_empty() {
+                tokio::task::spawn(self.clone().run_event_script_actions(
+                    http.clone(),
+                    guild_id,
+                    maybe_user_id,
+                    actions,
+                ));
+            }
+        }
+    }
+
+    /// Carries out the actions scripts asked for in response to an event, in order.
+    async fn run_event_script_actions(
+        self: Arc<Self>,
+        http: Arc<Http>,
+        guild_id: GuildId,
+        maybe_user_id: Option<UserId>,
+        actions: Vec<ScriptAction>,
+    ) {
+        let guild_model_handle = self.model.get(guild_id);
+
+        // The guild is only locked once songs have been found, like with commands.
+        for action in actions {
+            match (action, maybe_user_id) {
+                (ScriptAction::Enqueue { term }, Some(user_id)) => {
+                    // The songs are queued on behalf of the user the event is about, so they're
+                    // held to the same limits as if the user had used /play.
+                    let refuse_res = if self.cooldowns.check(user_id, "play").is_err() {
+                        log::debug!("Refusing play from script for user on cooldown");
+                        Ok(true)
+                    } else {
+                        self.refuse_command(guild_id, user_id, &CommandArgs::named("play"))
+                            .await
+                            .map(|refusal| refusal.is_some())
+                    };
+                    match refuse_res {
+                        Ok(false) => {}
+                        Ok(true) => continue,
+                        Err(why) => {
+                            log::error!("Error while checking queueing from script: {}", why);
+                            continue;
+                        }
+                    }
+
+                    // There's no command to respond to here, so the songs are only queued.
+                    let load_res =
+                        Song::load(&term, user_id, &self.guild_play_config(guild_id).await)
+                            .await
+                            .map_err(crate::error::Error::Backend)
+                            .and_then(|songs| self.blocklist.filter(guild_id, songs));
+                    match load_res {
+                        Ok((songs, _)) => {
+                            let push_result = guild_model_handle
+                                .lock_queues()
+                                .await
+                                .push_entries_bulk(user_id, songs);
+                            if push_result.rejected > 0 {
+                                log::warn!(
+                                    "Script left out {} songs past the queue limit",
+                                    push_result.rejected
+                                );
+                            }
+                            self.events.publish(Event::QueueChanged {
+                                guild_id,
+                                user_id,
+                                by_script: true,
+                            });
+                        }
+                        Err(why) => log::error!("Error while queueing from script: {}", why),
+                    }
+                }
+                (ScriptAction::Enqueue { .. }, None) => {
+                    log::warn!("Scripts can only queue songs from events with a user");
+                }
+                (ScriptAction::SendMessage { content }, _) => {
+                    let maybe_message_channel =
+                        guild_model_handle.lock_playback().await.message_channel();
+                    if let Some(message_channel) = maybe_message_channel {
+                        let message = Message::Response(ResponseMessage::Custom { content });
+                        let send_res = message_channel
+                            .send_message(&http, |create_message| {
+                                match message.plain_text(&self.config) {
+                                    Some(content) => create_message.content(content),
+                                    None => create_message
+                                        .embed(|embed| message.create_embed(embed, &self.config)),
+                                }
+                            })
+                            .await;
+                        if let Err(why) = send_res {
+                            log::error!("Error while sending script message: {}", why);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    async fn handle_queue_play_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model_handle: &GuildHandle<Song>,
+        term: &str,
+        options: PlayOptions,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let play_config = self.guild_play_config(guild_id).await;
+
+        let delegate_future = ModelDelegate::new(ctx, guild_id);
+        let song_future =
+            Song::load(term, user_id, &play_config).map_err(crate::error::Error::Backend);
+
+        let (delegate, mut songs) = match futures::try_join!(delegate_future, song_future) {
+            Ok(data) => data,
+            Err(crate::error::Error::Backend(why)) => {
+                return self.load_error_response(guild_id, term, why)
+            }
+            Err(why) => return Err(why),
+        };
+
+        // If playback of the song stopped partway through last time, either pick up from there
+        // or let the user know they can.
+        let mut resume_hint = None;
+        if let (Some(resume_config), [song]) = (&self.config.resume_positions, songs.as_mut_slice())
+        {
+            let saved_position =
+                self.positions
+                    .get(guild_id, &song.metadata.url, resume_config.max_age())?;
+            if let Some(position) = saved_position {
+                if options.resume {
+                    log::trace!("Resuming song from {}s", position.as_secs());
+                    song.set_start_position(position);
+                } else {
+                    resume_hint = Some(ResponseMessage::ResumeAvailable {
+                        song_title: song.metadata.title.clone(),
+                        song_url: song.metadata.url.clone(),
+                        position_secs: position.as_secs(),
+                    });
+                }
+            }
+        }
+
+        // Trimming applies to every song, so a whole album can have its intros cut.
+        for song in &mut songs {
+            if let Some(start) = options.start {
+                song.set_start_position(start);
+            }
+            if let Some(end) = options.end {
+                song.set_end_position(end);
+            }
+        }
+
+        let mut messages = self
+            .queue_songs(ctx, user_id, guild_id, guild_model_handle, delegate, songs)
+            .await?;
+        messages.extend(resume_hint.map(Message::Response));
+        Ok(messages)
+    }
+
+    /// Queues the first link in the message the command was used on that leads to a supported
+    /// site, checking the message's text before its attachments.
+    async fn handle_queue_message_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        command: &application_command::ApplicationCommandInteraction,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model_handle: &GuildHandle<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        // Message commands only ever resolve the message they were used on.
+        let links = match command.data.resolved.messages.values().next() {
+            Some(message) => message_links(message),
+            None => Vec::new(),
+        };
+        if links.is_empty() {
+            return Ok(vec![Message::Response(ResponseMessage::NoLinkError)]);
+        }
+
+        let play_config = self.play_config();
+        for link in &links {
+            match Song::load(link, user_id, &play_config).await {
+                Ok(songs) if !songs.is_empty() => {
+                    log::trace!("Queueing {} from message", link);
+                    let delegate = ModelDelegate::new(ctx, guild_id).await?;
+                    return self
+                        .queue_songs(ctx, user_id, guild_id, guild_model_handle, delegate, songs)
+                        .await;
+                }
+                // Sites youtube-dl can't handle don't give any songs.
+                Ok(_) => {
+                    log::trace!("Skipping unsupported link {} in message", link);
+                }
+                Err(why)
+                    if matches!(why.classification(), mrvn_back_ytdl::Error::UnsupportedUrl) =>
+                {
+                    log::trace!("Skipping unsupported link {} in message", link);
+                    self.extraction_failures.record(guild_id, link, &why);
+                }
+                Err(why) => return self.load_error_response(guild_id, link, why),
+            }
+        }
+        Ok(vec![Message::Response(
+            ResponseMessage::UnsupportedSiteError,
+        )])
+    }
+
+    /// Where the song the user queued last is in the channel's queue, and roughly how long until
+    /// it plays based on what's left of the current song and the durations of the ones before it.
+    async fn estimate_queued_eta(
+        &self,
+        playback: &PlaybackState,
+        guild_model: &GuildModel<Song>,
+        delegate: &ModelDelegate,
+        guild_speaker: &GuildSpeakerRef<'_>,
+        channel_id: ChannelId,
+        user_id: UserId,
+    ) -> Option<QueueEta> {
+        let entry_index = guild_model.user_entries(user_id).count().checked_sub(1)?;
+        let songs_before = guild_model.upcoming_entries_before(
+            playback,
+            delegate,
+            channel_id,
+            user_id,
+            entry_index,
+            USER_POSITION_LOOKAHEAD,
+        )?;
+
+        let current_remaining = match (
+            guild_speaker
+                .active_metadata()
+                .and_then(|metadata| metadata.duration),
+            guild_speaker.active_position().await,
+        ) {
+            (Some(duration), Some(position)) => Some(duration.saturating_sub(position)),
+            _ => None,
+        };
+        let wait: Option<Duration> = songs_before
+            .iter()
+            .map(|song| song.metadata.duration)
+            .chain(std::iter::once(current_remaining))
+            .sum();
+
+        Some(QueueEta {
+            position: songs_before.len() + 1,
+            wait_secs: wait.map(|wait| wait.as_secs()),
+        })
+    }
+
+    /// Queues songs for the user and starts playing if their channel isn't already.
+    async fn queue_songs(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model_handle: &GuildHandle<Song>,
+        delegate: ModelDelegate,
+        songs: Vec<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let songs = match self.blocklist.filter(guild_id, songs)? {
+            (songs, Some(rule)) if songs.is_empty() => {
+                return Ok(vec![Message::Response(ResponseMessage::Blocked { rule })]);
+            }
+            (songs, _) => songs,
+        };
+        if songs.is_empty() {
+            return Ok(vec![Message::Response(
+                ResponseMessage::NoMatchingSongsError,
+            )]);
+        }
+
+        log::trace!("Resolved song query as {} songs", songs.len());
+        let first_metadata = songs[0].metadata.clone();
+        let push_result = guild_model_handle
+            .lock_queues()
+            .await
+            .push_entries_bulk(user_id, songs);
+        // Songs past the queue limit are all reported together in one message.
+        let limit_message = push_result.limit.map(|limit| {
+            Message::Response(ResponseMessage::QueueLimitReached {
+                queued: push_result.accepted,
+                rejected: push_result.rejected,
+                limit,
+            })
+        });
+        let metadata = match push_result.accepted {
+            0 => return Ok(limit_message.into_iter().collect()),
+            1 => QueuedSongsMetadata::Single(Box::new(first_metadata)),
+            count => QueuedSongsMetadata::Multiple(count),
+        };
+
+        self.events.publish(Event::QueueChanged {
+            guild_id,
+            user_id,
+            by_script: false,
+        });
+        self.audit(
+            ctx,
+            guild_id,
+            user_id,
+            match &metadata {
+                QueuedSongsMetadata::Single(song_metadata) => AuditAction::QueuedSong {
+                    song_title: song_metadata.title.clone(),
+                    song_url: song_metadata.url.clone(),
+                },
+                QueuedSongsMetadata::Multiple(count) => AuditAction::QueuedSongs { count: *count },
+            },
+        );
+
+        let mut messages = self
+            .play_queued_songs(
+                ctx,
+                user_id,
+                guild_id,
+                guild_model_handle,
+                delegate,
+                metadata,
+            )
+            .await?;
+        messages.extend(limit_message);
+        Ok(messages)
+    }
+
+    /// Starts playing in the user's channel after their songs were queued, if it isn't already.
+    async fn play_queued_songs(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model_handle: &GuildHandle<Song>,
+        delegate: ModelDelegate,
+        metadata: QueuedSongsMetadata,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        // From this point on the user needs to be in a channel, otherwise the songs will only stay
+        // queued.
+        let channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel) => channel,
+            None => {
+                log::trace!("User is not in any voice channel, song will remain queued");
+                return Ok(vec![Message::Response(match metadata {
+                    QueuedSongsMetadata::Single(song_metadata) => ResponseMessage::Queued {
+                        song_title: song_metadata.title,
+                        song_url: song_metadata.url,
+                        eta: None,
+                    },
+                    QueuedSongsMetadata::Multiple(count) => {
+                        ResponseMessage::QueuedMultiple { count }
+                    }
+                })]);
+            }
+        };
+
+        // Play a song if the model indicates one isn't playing. The song can stay in the queue if
+        // a speaker isn't found, and the queues aren't locked while one is, since it can mean
+        // joining the channel.
+        let mut playback = guild_model_handle.lock_playback().await;
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut speakers = BotSpeakers {
+            frontend: self,
+            ctx,
+            speakers: guild_speakers_handle.lock().await,
+        };
+        let start_status = speaker::start_next(
+            &mut speakers,
+            &mut playback,
+            guild_model_handle,
+            &delegate,
+            channel_id,
+        )
+        .await?;
+        let next_metadata = match start_status {
+            StartStatus::Started(next_metadata) => *next_metadata,
+            StartStatus::NoSpeaker(FindSpeakerError::ChannelNotAllowed) => {
+                log::trace!("Speakers aren't allowed in the channel, song will remain queued");
+                return Ok(vec![Message::Response(
+                    ResponseMessage::ChannelNotAllowedError {
+                        voice_channel_id: channel_id,
+                    },
+                )]);
+            }
+            StartStatus::NoSpeaker(FindSpeakerError::NoneAvailable) => {
+                log::trace!(
+                    "No speakers are available to handle playback, song will remain queued"
+                );
+                return Ok(vec![Message::Response(match metadata {
+                    QueuedSongsMetadata::Single(song_metadata) => {
+                        ResponseMessage::QueuedNoSpeakers {
+                            song_title: song_metadata.title,
+                            song_url: song_metadata.url,
+                        }
+                    }
+                    QueuedSongsMetadata::Multiple(count) => {
+                        ResponseMessage::QueuedMultipleNoSpeakers { count }
+                    }
+                })]);
+            }
+            StartStatus::AlreadyPlaying | StartStatus::NothingQueued => {
+                log::trace!("Channel is already playing, song will remain queued");
+                return Ok(vec![Message::Response(match metadata {
+                    QueuedSongsMetadata::Single(song_metadata) => {
+                        let guild_model = guild_model_handle.lock_queues().await;
+                        let eta = match speakers.speakers.find_in_channel(channel_id) {
+                            Some(guild_speaker) => {
+                                self.estimate_queued_eta(
+                                    &playback,
+                                    &guild_model,
+                                    &delegate,
+                                    guild_speaker,
+                                    channel_id,
+                                    user_id,
+                                )
+                                .await
+                            }
+                            None => None,
+                        };
+                        ResponseMessage::Queued {
+                            song_title: song_metadata.title,
+                            song_url: song_metadata.url,
+                            eta,
+                        }
+                    }
+                    QueuedSongsMetadata::Multiple(count) => {
+                        ResponseMessage::QueuedMultiple { count }
+                    }
+                })]);
+            }
+        };
+
+        // We could be in one of three states:
+        //  - One song was queued, and we're now playing that song. We only show a "playing"
+        //    message.
+        //  - Multiple songs were queued, and we're playing the first one. We show a "queued"
+        //    message and a "playing" message.
+        //    todo: maybe we should combine these in this case
+        // - We queued one or more songs and started a different song, which can happen if there
+        //   were other songs waiting but we weren't playing at the time.
+        match metadata {
+            QueuedSongsMetadata::Single(song_metadata) => {
                 if next_metadata.url == song_metadata.url {
                     Ok(vec![Message::Action(ActionMessage::PlayingResponse {
                         song_title: song_metadata.title,
                         song_url: song_metadata.url,
                         voice_channel_id: channel_id,
-                    })])
-                } else {
-                    Ok(vec![
-                        Message::Response(ResponseMessage::Queued {
-                            song_title: song_metadata.title,
-                            song_url: song_metadata.url,
-                        }),
-                        Message::Action(ActionMessage::Playing {
-                            song_title: next_metadata.title,
-                            song_url: next_metadata.url,
-                            voice_channel_id: channel_id,
-                            user_id: next_metadata.user_id,
-                        }),
-                    ])
-                }
+                    })])
+                } else {
+                    Ok(vec![
+                        Message::Response(ResponseMessage::Queued {
+                            song_title: song_metadata.title,
+                            song_url: song_metadata.url,
+                            eta: None,
+                        }),
+                        Message::Action(ActionMessage::Playing {
+                            song_title: next_metadata.title,
+                            song_url: next_metadata.url,
+                            voice_channel_id: channel_id,
+                            user_id: next_metadata.user_id,
+                        }),
+                    ])
+                }
+            }
+            QueuedSongsMetadata::Multiple(count) => Ok(vec![
+                Message::Response(ResponseMessage::QueuedMultiple { count }),
+                Message::Action(ActionMessage::Playing {
+                    song_title: next_metadata.title,
+                    song_url: next_metadata.url,
+                    voice_channel_id: channel_id,
+                    user_id: next_metadata.user_id,
+                }),
+            ]),
+        }
+    }
+
+    async fn handle_unpause_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        playback: &mut PlaybackState,
+        guild_model: &mut GuildModel<Song>,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel) => channel,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NotInVoiceChannelError,
+                )])
+            }
+        };
+
+        // See if there's currently a speaker in this channel to unpause.
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+        if let Some((guild_speaker, active_metadata)) =
+            guild_speakers_ref.find_active_in_channel(channel_id)
+        {
+            return match guild_speaker.pause_reason() {
+                Some(PauseReason::Requested) => {
+                    log::trace!(
+                        "Found a paused speaker in the user's voice channel, starting playback"
+                    );
+                    guild_speaker
+                        .unpause()
+                        .map_err(crate::error::Error::Backend)?;
+                    Ok(vec![Message::Action(ActionMessage::Playing {
+                        song_title: active_metadata.title.clone(),
+                        song_url: active_metadata.url.clone(),
+                        voice_channel_id: channel_id,
+                        user_id: active_metadata.user_id,
+                    })])
+                }
+                Some(PauseReason::Muted) => {
+                    log::trace!("Found a muted speaker in the user's voice channel, playback will resume when it's unmuted");
+                    Ok(vec![Message::Response(
+                        ResponseMessage::SpeakerMutedError {
+                            voice_channel_id: channel_id,
+                        },
+                    )])
+                }
+                None => {
+                    log::trace!(
+                        "Found an unpaused speaker in the user's voice channel, playback will continue"
+                    );
+                    Ok(vec![Message::Response(
+                        ResponseMessage::AlreadyPlayingError {
+                            voice_channel_id: channel_id,
+                        },
+                    )])
+                }
+            };
+        };
+
+        // Otherwise, try starting to play in this channel.
+        let guild_speaker = match guild_speakers_ref.find_to_play_in_channel(channel_id).await {
+            Ok(speaker) => speaker,
+            Err(FindSpeakerError::ChannelNotAllowed) => {
+                log::trace!("Speakers aren't allowed in the channel, nothing will be played");
+                return Ok(vec![Message::Response(
+                    ResponseMessage::ChannelNotAllowedError {
+                        voice_channel_id: channel_id,
+                    },
+                )]);
+            }
+            Err(FindSpeakerError::NoneAvailable) => {
+                log::trace!("No speakers are available to handle playback, nothing will be played");
+                return Ok(vec![Message::Action(ActionMessage::NoSpeakersError {
+                    voice_channel_id: channel_id,
+                })]);
+            }
+        };
+        let next_song = match guild_model.next_channel_entry(playback, &delegate, channel_id) {
+            NextEntry::Entry(song) => song,
+            NextEntry::AlreadyPlaying | NextEntry::NoneAvailable => {
+                log::trace!(
+                    "No songs are available to play back in the channel, nothing will be played"
+                );
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NothingIsQueuedError {
+                        voice_channel_id: channel_id,
+                    },
+                )]);
+            }
+        };
+
+        let next_metadata = next_song.metadata.clone();
+        self.play_to_speaker(
+            ctx,
+            playback,
+            guild_model.features(),
+            guild_speaker,
+            channel_id,
+            next_song,
+        )
+        .await?;
+
+        Ok(vec![Message::Action(ActionMessage::Playing {
+            song_title: next_metadata.title,
+            song_url: next_metadata.url,
+            voice_channel_id: channel_id,
+            user_id: next_metadata.user_id,
+        })])
+    }
+
+    async fn handle_replace_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        guild_model_handle: &GuildHandle<Song>,
+        term: &str,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let play_config = self.guild_play_config(guild_id).await;
+
+        let delegate_future = ModelDelegate::new(ctx, guild_id);
+        let song_future =
+            Song::load(term, user_id, &play_config).map_err(crate::error::Error::Backend);
+
+        let (delegate, songs) = match futures::try_join!(delegate_future, song_future) {
+            Ok(data) => data,
+            Err(crate::error::Error::Backend(why)) => {
+                return self.load_error_response(guild_id, term, why)
+            }
+            Err(why) => return Err(why),
+        };
+        let songs = match self.blocklist.filter(guild_id, songs)? {
+            (songs, Some(rule)) if songs.is_empty() => {
+                return Ok(vec![Message::Response(ResponseMessage::Blocked { rule })]);
+            }
+            (songs, _) => songs,
+        };
+
+        if songs.len() == 1 {
+            let song_metadata = &songs[0].metadata;
+            log::trace!(
+                "Resolved song query as {} (\"{}\")",
+                song_metadata.url,
+                song_metadata.title
+            );
+        } else {
+            log::trace!("Resolved song query as {} songs", songs.len());
+        }
+
+        let mut songs_iter = songs.into_iter();
+        let song = match songs_iter.next() {
+            Some(song) => song,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NoMatchingSongsError,
+                )])
+            }
+        };
+
+        let (mut playback, mut guild_model) = guild_model_handle.lock().await;
+        let playback = playback.deref_mut();
+        let guild_model = guild_model.deref_mut();
+        let song_metadata = song.metadata.clone();
+        let maybe_channel_id = delegate.get_user_voice_channel(user_id);
+        let replace_status = guild_model.replace_entry(playback, user_id, maybe_channel_id, song);
+        let push_result = guild_model.push_entries_bulk(user_id, songs_iter);
+        if push_result.rejected > 0 {
+            log::trace!(
+                "Left out {} songs past the queue limit when replacing",
+                push_result.rejected
+            );
+        }
+        self.events.publish(Event::QueueChanged {
+            guild_id,
+            user_id,
+            by_script: false,
+        });
+
+        let channel_id = match replace_status {
+            // If the song was queued, no playback changes are needed so we send a status message
+            // and leave it there. But if the model indicated we're replacing the current song,
+            // we need to start playing the next song.
+            ReplaceStatus::Queued => {
+                log::trace!("No songs in queue to replace, song will be queued");
+                return Ok(vec![Message::Response(ResponseMessage::Queued {
+                    song_title: song_metadata.title,
+                    song_url: song_metadata.url,
+                    eta: None,
+                })]);
+            }
+            ReplaceStatus::ReplacedInQueue(old_song) => {
+                log::trace!("Latest song in the users queue will be replaced");
+                return Ok(vec![Message::Response(ResponseMessage::Replaced {
+                    old_song_title: old_song.metadata.title,
+                    old_song_url: old_song.metadata.url,
+                    new_song_title: song_metadata.title,
+                    new_song_url: song_metadata.url,
+                })]);
+            }
+            ReplaceStatus::ReplacedCurrent(channel_id) => channel_id,
+        };
+
+        log::trace!("Only song queued by user is currently playing, it will be skipped");
+
+        // We're replacing an already-playing song, so if there's no speaker for this channel
+        // something has gone very wrong :(
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+        let (guild_speaker, playing_metadata) = guild_speakers_ref
+            .find_active_in_channel(channel_id)
+            .ok_or(crate::error::Error::ModelPlayingSpeakerNotDesync)?;
+
+        // Play a song if the model indicates one isn't playing.
+        let next_song =
+            match guild_model.next_channel_entry_finished(playback, &delegate, channel_id) {
+                Some(song) => song,
+                None => {
+                    log::trace!("New song is no longer accessible in queue, nothing will play");
+                    return Ok(vec![Message::Response(
+                        ResponseMessage::NothingIsQueuedError {
+                            voice_channel_id: channel_id,
+                        },
+                    )]);
+                }
+            };
+
+        let next_metadata = next_song.metadata.clone();
+        self.play_to_speaker(
+            ctx,
+            playback,
+            guild_model.features(),
+            guild_speaker,
+            channel_id,
+            next_song,
+        )
+        .await?;
+
+        // We could be in one of two states:
+        //  - The song that's now playing is the one we just queued, in which case we only show a
+        //    "playing" message.
+        //  - We queued a song and started a different song, which can happen if there were other
+        //    songs waiting but we weren't playing at the time. In this case we show a "queued"
+        //    message and a "playing" message.
+        if next_metadata.url == song_metadata.url {
+            Ok(vec![Message::Action(ActionMessage::PlayingResponse {
+                song_title: song_metadata.title,
+                song_url: song_metadata.url,
+                voice_channel_id: channel_id,
+            })])
+        } else {
+            Ok(vec![
+                Message::Response(ResponseMessage::ReplaceSkipped {
+                    new_song_title: song_metadata.title,
+                    new_song_url: song_metadata.url,
+                    old_song_title: playing_metadata.title,
+                    old_song_url: playing_metadata.url,
+                    voice_channel_id: channel_id,
+                }),
+                Message::Action(ActionMessage::Playing {
+                    song_title: next_metadata.title,
+                    song_url: next_metadata.url,
+                    voice_channel_id: channel_id,
+                    user_id: next_metadata.user_id,
+                }),
+            ])
+        }
+    }
+
+    async fn handle_pause_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel) => channel,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NotInVoiceChannelError,
+                )])
+            }
+        };
+
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+        match guild_speakers_ref.find_active_in_channel(channel_id) {
+            Some((guild_speaker, active_metadata)) => {
+                if guild_speaker.is_paused() {
+                    log::trace!("Found a paused speaker in the user's voice channel, playback will remain paused");
+                    Ok(vec![Message::Response(
+                        ResponseMessage::NothingIsPlayingError {
+                            voice_channel_id: channel_id,
+                        },
+                    )])
+                } else {
+                    log::trace!("Found an unpaused speaker in the user's voice channel, playback will be paused");
+                    guild_speaker
+                        .pause()
+                        .map_err(crate::error::Error::Backend)?;
+                    Ok(vec![Message::Response(ResponseMessage::Paused {
+                        song_title: active_metadata.title.clone(),
+                        song_url: active_metadata.url.clone(),
+                        voice_channel_id: channel_id,
+                        user_id: active_metadata.user_id,
+                    })])
+                }
+            }
+            _ => {
+                log::trace!(
+                    "No speakers are in the user's voice channel, playback will not change"
+                );
+                Ok(vec![Message::Response(
+                    ResponseMessage::NothingIsPlayingError {
+                        voice_channel_id: channel_id,
+                    },
+                )])
+            }
+        }
+    }
+
+    async fn handle_skip_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        playback: &mut PlaybackState,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel) => channel,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NotInVoiceChannelError,
+                )])
+            }
+        };
+
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut speakers = BotSpeakers {
+            frontend: self,
+            ctx,
+            speakers: guild_speakers_handle.lock().await,
+        };
+        let (skip_status, maybe_active_metadata) = speaker::vote(
+            &mut speakers,
+            playback,
+            &delegate,
+            VoteType::Skip,
+            channel_id,
+            user_id,
+        )?;
+
+        match (skip_status, maybe_active_metadata) {
+            (VoteStatus::Success, Some(active_metadata)) => {
+                log::trace!("Skip command passed preconditions, stopped current playback");
+                self.events.publish(Event::VotePassed {
+                    guild_id,
+                    channel_id,
+                    vote_type: "skip",
+                });
+                self.audit(
+                    ctx,
+                    guild_id,
+                    user_id,
+                    AuditAction::VoteSkipped {
+                        song_title: active_metadata.title.clone(),
+                        song_url: active_metadata.url.clone(),
+                    },
+                );
+                Ok(vec![Message::Response(ResponseMessage::Skipped {
+                    song_title: active_metadata.title,
+                    song_url: active_metadata.url,
+                    voice_channel_id: channel_id,
+                    user_id: active_metadata.user_id,
+                })])
+            }
+            (VoteStatus::AlreadyVoted, Some(active_metadata)) => {
+                log::trace!("User attempting to skip has already voted, not stopping playback");
+                Ok(vec![Message::Response(
+                    ResponseMessage::SkipAlreadyVotedError {
+                        song_title: active_metadata.title,
+                        song_url: active_metadata.url,
+                        voice_channel_id: channel_id,
+                    },
+                )])
+            }
+            (VoteStatus::NeedsMoreVotes(count), Some(active_metadata)) => {
+                log::trace!(
+                    "Skip vote has been counted but more are needed, not stopping playback"
+                );
+                self.events.publish(Event::VoteProgress {
+                    guild_id,
+                    channel_id,
+                    vote_type: "skip",
+                    votes_needed: count,
+                });
+                Ok(vec![Message::Response(
+                    ResponseMessage::SkipMoreVotesNeeded {
+                        song_id: active_metadata.id,
+                        song_title: active_metadata.title,
+                        song_url: active_metadata.url,
+                        voice_channel_id: channel_id,
+                        count,
+                    },
+                )])
+            }
+            (VoteStatus::NothingPlaying, _) => {
+                log::trace!(
+                    "Nothing is playing in the user's voice channel, not stopping playback"
+                );
+                Ok(vec![Message::Response(
+                    ResponseMessage::NothingIsPlayingError {
+                        voice_channel_id: channel_id,
+                    },
+                )])
+            }
+            (_, None) => Err(crate::error::Error::ModelPlayingSpeakerNotDesync),
+        }
+    }
+
+    /// Skips without a vote, for admins to deal with songs that shouldn't keep playing.
+    async fn handle_force_skip_command(
+        &self,
+        ctx: &Context,
+        command: &application_command::ApplicationCommandInteraction,
+        user_id: UserId,
+        guild_id: GuildId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        if !can_manage_guild(command) {
+            return Ok(vec![Message::Response(
+                ResponseMessage::MissingPermissionError,
+            )]);
+        }
+
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel) => channel,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NotInVoiceChannelError,
+                )])
+            }
+        };
+
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+        let (guild_speaker, active_metadata) =
+            match guild_speakers_ref.find_active_in_channel(channel_id) {
+                Some(active) => active,
+                None => {
+                    return Ok(vec![Message::Response(
+                        ResponseMessage::NothingIsPlayingError {
+                            voice_channel_id: channel_id,
+                        },
+                    )])
+                }
+            };
+
+        log::trace!("Force skip command passed preconditions, stopping current playback");
+        guild_speaker.stop().map_err(crate::error::Error::Backend)?;
+        self.audit(
+            ctx,
+            guild_id,
+            user_id,
+            AuditAction::ForceSkipped {
+                song_title: active_metadata.title.clone(),
+                song_url: active_metadata.url.clone(),
+            },
+        );
+        Ok(vec![Message::Response(ResponseMessage::Skipped {
+            song_title: active_metadata.title,
+            song_url: active_metadata.url,
+            voice_channel_id: channel_id,
+            user_id: active_metadata.user_id,
+        })])
+    }
+
+    async fn handle_stop_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        playback: &mut PlaybackState,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel) => channel,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NotInVoiceChannelError,
+                )])
+            }
+        };
+
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut speakers = BotSpeakers {
+            frontend: self,
+            ctx,
+            speakers: guild_speakers_handle.lock().await,
+        };
+        let (stop_status, maybe_active_metadata) = speaker::vote(
+            &mut speakers,
+            playback,
+            &delegate,
+            VoteType::Stop,
+            channel_id,
+            user_id,
+        )?;
+
+        match stop_status {
+            VoteStatus::Success => match maybe_active_metadata {
+                Some(active_metadata) => {
+                    log::trace!("Stop command passed preconditions, stopped playback");
+                    self.events.publish(Event::VotePassed {
+                        guild_id,
+                        channel_id,
+                        vote_type: "stop",
+                    });
+                    Ok(vec![Message::Response(ResponseMessage::Stopped {
+                        song_title: active_metadata.title.clone(),
+                        song_url: active_metadata.url.clone(),
+                        voice_channel_id: channel_id,
+                        user_id: active_metadata.user_id,
+                    })])
+                }
+                None => Err(crate::error::Error::ModelPlayingSpeakerNotDesync),
+            },
+            VoteStatus::AlreadyVoted => {
+                log::trace!("User attempting to stop has already voted, not stopping playback");
+                Ok(vec![Message::Response(
+                    ResponseMessage::StopAlreadyVotedError {
+                        voice_channel_id: channel_id,
+                    },
+                )])
+            }
+            VoteStatus::NeedsMoreVotes(count) => {
+                log::trace!(
+                    "Stop vote has been counted but more are needed, not stopping playback"
+                );
+                self.events.publish(Event::VoteProgress {
+                    guild_id,
+                    channel_id,
+                    vote_type: "stop",
+                    votes_needed: count,
+                });
+                Ok(vec![Message::Response(
+                    ResponseMessage::StopMoreVotesNeeded {
+                        voice_channel_id: channel_id,
+                        count,
+                    },
+                )])
+            }
+            VoteStatus::NothingPlaying => {
+                log::trace!(
+                    "Nothing is playing in the user's voice channel, not stopping playback"
+                );
+                Ok(vec![Message::Response(
+                    ResponseMessage::NothingIsPlayingError {
+                        voice_channel_id: channel_id,
+                    },
+                )])
+            }
+        }
+    }
+
+    async fn handle_transfer_command(
+        self: &Arc<Self>,
+        ctx: &Context,
+        user_id: UserId,
+        guild_id: GuildId,
+        playback: &mut PlaybackState,
+        target_channel_id: ChannelId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel) => channel,
+            None => {
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NotInVoiceChannelError,
+                )])
+            }
+        };
+        if target_channel_id == channel_id {
+            return Ok(vec![Message::Response(
+                ResponseMessage::TransferSameChannelError {
+                    voice_channel_id: channel_id,
+                },
+            )]);
+        }
+        if !self
+            .settings
+            .get(guild_id)
+            .is_voice_channel_allowed(target_channel_id)
+        {
+            return Ok(vec![Message::Response(
+                ResponseMessage::ChannelNotAllowedError {
+                    voice_channel_id: target_channel_id,
+                },
+            )]);
+        }
+
+        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
+        let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+        if guild_speakers_ref
+            .find_active_in_channel(target_channel_id)
+            .is_some()
+        {
+            log::trace!("A speaker is already playing in the target channel, not transferring");
+            return Ok(vec![Message::Response(
+                ResponseMessage::AlreadyPlayingError {
+                    voice_channel_id: target_channel_id,
+                },
+            )]);
+        }
+        let (guild_speaker, active_metadata) = match guild_speakers_ref
+            .find_active_in_channel(channel_id)
+        {
+            Some(found) => found,
+            None => {
+                log::trace!("Nothing is playing in the user's voice channel, nothing to transfer");
+                return Ok(vec![Message::Response(
+                    ResponseMessage::NothingIsPlayingError {
+                        voice_channel_id: channel_id,
+                    },
+                )]);
+            }
+        };
+
+        // Taking the music away from everyone else needs the same votes as stopping it.
+        match playback.vote_for_skip(&delegate, VoteType::Stop, channel_id, user_id) {
+            VoteStatus::Success | VoteStatus::NothingPlaying => {}
+            VoteStatus::AlreadyVoted => {
+                log::trace!("User attempting to transfer has already voted, not transferring");
+                return Ok(vec![Message::Response(
+                    ResponseMessage::StopAlreadyVotedError {
+                        voice_channel_id: channel_id,
+                    },
+                )]);
+            }
+            VoteStatus::NeedsMoreVotes(count) => {
+                log::trace!("Stop vote has been counted but more are needed, not transferring");
+                self.events.publish(Event::VoteProgress {
+                    guild_id,
+                    channel_id,
+                    vote_type: "stop",
+                    votes_needed: count,
+                });
+                return Ok(vec![Message::Response(
+                    ResponseMessage::StopMoreVotesNeeded {
+                        voice_channel_id: channel_id,
+                        count,
+                    },
+                )]);
             }
-            QueuedSongsMetadata::Multiple(count) => Ok(vec![
-                Message::Response(ResponseMessage::QueuedMultiple { count }),
-                Message::Action(ActionMessage::Playing {
-                    song_title: next_metadata.title,
-                    song_url: next_metadata.url,
-                    voice_channel_id: channel_id,
-                    user_id: next_metadata.user_id,
-                }),
-            ]),
         }
+
+        if !playback.move_channel(channel_id, target_channel_id) {
+            return Ok(vec![Message::Response(
+                ResponseMessage::AlreadyPlayingError {
+                    voice_channel_id: target_channel_id,
+                },
+            )]);
+        }
+        log::trace!("Transfer command passed preconditions, moving playback");
+        if let Err(why) = guild_speaker.move_to(target_channel_id).await {
+            playback.move_channel(target_channel_id, channel_id);
+            return Err(crate::error::Error::Backend(why));
+        }
+
+        Ok(vec![Message::Response(ResponseMessage::Transferred {
+            song_title: active_metadata.title,
+            song_url: active_metadata.url,
+            from_voice_channel_id: channel_id,
+            to_voice_channel_id: target_channel_id,
+        })])
     }
 
-    async fn handle_unpause_command(
+    async fn handle_summon_command(
         self: &Arc<Self>,
         ctx: &Context,
         user_id: UserId,
         guild_id: GuildId,
-        guild_model: &mut GuildModel<Song>,
     ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
         let delegate = ModelDelegate::new(ctx, guild_id).await?;
         let channel_id = match delegate.get_user_voice_channel(user_id) {
@@ -435,267 +4989,257 @@ impl Frontend {
             }
         };
 
-        // See if there's currently a speaker in this channel to unpause.
         let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
         let mut guild_speakers_ref = guild_speakers_handle.lock().await;
-        if let Some((guild_speaker, active_metadata)) =
-            guild_speakers_ref.find_active_in_channel(channel_id)
-        {
-            return if guild_speaker.is_paused() {
-                log::trace!(
-                    "Found a paused speaker in the user's voice channel, starting playback"
-                );
+        match guild_speakers_ref.find_to_play_in_channel(channel_id).await {
+            Ok(guild_speaker) => {
+                log::trace!("Found a speaker to bring into the user's voice channel");
                 guild_speaker
-                    .unpause()
+                    .summon(channel_id)
+                    .await
                     .map_err(crate::error::Error::Backend)?;
-                Ok(vec![Message::Action(ActionMessage::Playing {
-                    song_title: active_metadata.title.clone(),
-                    song_url: active_metadata.url.clone(),
+                Ok(vec![Message::Response(ResponseMessage::Summoned {
                     voice_channel_id: channel_id,
-                    user_id: active_metadata.user_id,
                 })])
-            } else {
-                log::trace!(
-                    "Found an unpaused speaker in the user's voice channel, playback will continue"
-                );
+            }
+            Err(FindSpeakerError::ChannelNotAllowed) => {
+                log::trace!("Speakers aren't allowed in the user's voice channel");
                 Ok(vec![Message::Response(
-                    ResponseMessage::AlreadyPlayingError {
+                    ResponseMessage::ChannelNotAllowedError {
                         voice_channel_id: channel_id,
                     },
                 )])
-            };
-        };
-
-        // Otherwise, try starting to play in this channel.
-        let guild_speaker = match guild_speakers_ref.find_to_play_in_channel(channel_id) {
-            Some(speaker) => speaker,
-            None => {
-                log::trace!("No speakers are available to handle playback, nothing will be played");
-                return Ok(vec![Message::Action(ActionMessage::NoSpeakersError {
-                    voice_channel_id: channel_id,
-                })]);
             }
-        };
-        let next_song = match guild_model.next_channel_entry(&delegate, channel_id) {
-            NextEntry::Entry(song) => song,
-            NextEntry::AlreadyPlaying | NextEntry::NoneAvailable => {
-                log::trace!(
-                    "No songs are available to play back in the channel, nothing will be played"
-                );
-                return Ok(vec![Message::Response(
-                    ResponseMessage::NothingIsQueuedError {
+            Err(FindSpeakerError::NoneAvailable) => {
+                log::trace!("No speakers are available to bring into the user's voice channel");
+                Ok(vec![Message::Response(
+                    ResponseMessage::SummonNoSpeakersError {
                         voice_channel_id: channel_id,
                     },
-                )]);
+                )])
             }
-        };
-
-        let next_metadata = next_song.metadata.clone();
-        self.play_to_speaker(ctx, guild_model, guild_speaker, channel_id, next_song)
-            .await?;
-
-        Ok(vec![Message::Action(ActionMessage::Playing {
-            song_title: next_metadata.title,
-            song_url: next_metadata.url,
-            voice_channel_id: channel_id,
-            user_id: next_metadata.user_id,
-        })])
+        }
     }
 
-    async fn handle_replace_command(
+    async fn handle_disconnect_command(
         self: &Arc<Self>,
         ctx: &Context,
         user_id: UserId,
         guild_id: GuildId,
-        guild_model: &mut GuildModel<Song>,
-        term: &str,
+        playback: &mut PlaybackState,
     ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
-        let play_config = self.config.get_play_config();
-
-        let delegate_future = ModelDelegate::new(ctx, guild_id);
-        let song_future =
-            Song::load(term, user_id, &play_config).map_err(crate::error::Error::Backend);
-
-        let (delegate, songs) = match futures::try_join!(delegate_future, song_future) {
-            Ok(data) => data,
-            Err(crate::error::Error::Backend(mrvn_back_ytdl::Error::UnsupportedUrl)) => {
-                return Ok(vec![Message::Response(
-                    ResponseMessage::UnsupportedSiteError,
-                )]);
-            }
-            Err(why) => return Err(why),
-        };
-
-        if songs.len() == 1 {
-            let song_metadata = &songs[0].metadata;
-            log::trace!(
-                "Resolved song query as {} (\"{}\")",
-                song_metadata.url,
-                song_metadata.title
-            );
-        } else {
-            log::trace!("Resolved song query as {} songs", songs.len());
-        }
-
-        let mut songs_iter = songs.into_iter();
-        let song = match songs_iter.next() {
-            Some(song) => song,
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let channel_id = match delegate.get_user_voice_channel(user_id) {
+            Some(channel) => channel,
             None => {
                 return Ok(vec![Message::Response(
-                    ResponseMessage::NoMatchingSongsError,
+                    ResponseMessage::NotInVoiceChannelError,
                 )])
             }
         };
 
-        let song_metadata = song.metadata.clone();
-        let maybe_channel_id = delegate.get_user_voice_channel(user_id);
-        let replace_status = guild_model.replace_entry(user_id, maybe_channel_id, song);
-        guild_model.push_entries(user_id, songs_iter);
-
-        let channel_id = match replace_status {
-            // If the song was queued, no playback changes are needed so we send a status message
-            // and leave it there. But if the model indicated we're replacing the current song,
-            // we need to start playing the next song.
-            ReplaceStatus::Queued => {
-                log::trace!("No songs in queue to replace, song will be queued");
-                return Ok(vec![Message::Response(ResponseMessage::Queued {
-                    song_title: song_metadata.title,
-                    song_url: song_metadata.url,
-                })]);
-            }
-            ReplaceStatus::ReplacedInQueue(old_song) => {
-                log::trace!("Latest song in the users queue will be replaced");
-                return Ok(vec![Message::Response(ResponseMessage::Replaced {
-                    old_song_title: old_song.metadata.title,
-                    old_song_url: old_song.metadata.url,
-                    new_song_title: song_metadata.title,
-                    new_song_url: song_metadata.url,
-                })]);
-            }
-            ReplaceStatus::ReplacedCurrent(channel_id) => channel_id,
-        };
-
-        log::trace!("Only song queued by user is currently playing, it will be skipped");
-
-        // We're replacing an already-playing song, so if there's no speaker for this channel
-        // something has gone very wrong :(
         let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
         let mut guild_speakers_ref = guild_speakers_handle.lock().await;
-        let (guild_speaker, playing_metadata) = guild_speakers_ref
-            .find_active_in_channel(channel_id)
-            .ok_or(crate::error::Error::ModelPlayingSpeakerNotDesync)?;
-
-        // Play a song if the model indicates one isn't playing.
-        let next_song = match guild_model.next_channel_entry_finished(&delegate, channel_id) {
-            Some(song) => song,
+        let guild_speaker = match guild_speakers_ref.find_in_channel(channel_id) {
+            Some(guild_speaker) => guild_speaker,
             None => {
-                log::trace!("New song is no longer accessible in queue, nothing will play");
+                log::trace!("No speakers are in the user's voice channel, nothing to disconnect");
                 return Ok(vec![Message::Response(
-                    ResponseMessage::NothingIsQueuedError {
+                    ResponseMessage::NotConnectedError {
                         voice_channel_id: channel_id,
                     },
                 )]);
             }
         };
 
-        let next_metadata = next_song.metadata.clone();
-        self.play_to_speaker(ctx, guild_model, guild_speaker, channel_id, next_song)
-            .await?;
-
-        // We could be in one of two states:
-        //  - The song that's now playing is the one we just queued, in which case we only show a
-        //    "playing" message.
-        //  - We queued a song and started a different song, which can happen if there were other
-        //    songs waiting but we weren't playing at the time. In this case we show a "queued"
-        //    message and a "playing" message.
-        if next_metadata.url == song_metadata.url {
-            Ok(vec![Message::Action(ActionMessage::PlayingResponse {
-                song_title: song_metadata.title,
-                song_url: song_metadata.url,
-                voice_channel_id: channel_id,
-            })])
-        } else {
-            Ok(vec![
-                Message::Response(ResponseMessage::ReplaceSkipped {
-                    new_song_title: song_metadata.title,
-                    new_song_url: song_metadata.url,
-                    old_song_title: playing_metadata.title,
-                    old_song_url: playing_metadata.url,
-                    voice_channel_id: channel_id,
-                }),
-                Message::Action(ActionMessage::Playing {
-                    song_title: next_metadata.title,
-                    song_url: next_metadata.url,
-                    voice_channel_id: channel_id,
-                    user_id: next_metadata.user_id,
-                }),
-            ])
+        // Leaving cuts off whatever is playing, so it needs the same votes as stopping.
+        if guild_speaker.is_active() {
+            match playback.vote_for_skip(&delegate, VoteType::Stop, channel_id, user_id) {
+                VoteStatus::Success => {
+                    self.events.publish(Event::VotePassed {
+                        guild_id,
+                        channel_id,
+                        vote_type: "stop",
+                    });
+                }
+                VoteStatus::AlreadyVoted => {
+                    log::trace!(
+                        "User attempting to disconnect has already voted, staying in the channel"
+                    );
+                    return Ok(vec![Message::Response(
+                        ResponseMessage::StopAlreadyVotedError {
+                            voice_channel_id: channel_id,
+                        },
+                    )]);
+                }
+                VoteStatus::NeedsMoreVotes(count) => {
+                    log::trace!(
+                        "Stop vote has been counted but more are needed, staying in the channel"
+                    );
+                    self.events.publish(Event::VoteProgress {
+                        guild_id,
+                        channel_id,
+                        vote_type: "stop",
+                        votes_needed: count,
+                    });
+                    return Ok(vec![Message::Response(
+                        ResponseMessage::StopMoreVotesNeeded {
+                            voice_channel_id: channel_id,
+                            count,
+                        },
+                    )]);
+                }
+                VoteStatus::NothingPlaying => {}
+            }
         }
+
+        log::trace!("Disconnect command passed preconditions, leaving the channel");
+        playback.set_channel_stopped(channel_id);
+        guild_speaker
+            .disconnect()
+            .await
+            .map_err(crate::error::Error::Backend)?;
+        Ok(vec![Message::Response(ResponseMessage::Disconnected {
+            voice_channel_id: channel_id,
+        })])
     }
 
-    async fn handle_pause_command(
-        self: &Arc<Self>,
+    /// Bulk removal from other people's queues, for admins to clean up after someone.
+    #[allow(clippy::too_many_arguments)]
+    async fn handle_remove_command(
+        &self,
         ctx: &Context,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
         user_id: UserId,
         guild_id: GuildId,
+        playback: &mut PlaybackState,
+        guild_model: &mut GuildModel<Song>,
     ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
-        let delegate = ModelDelegate::new(ctx, guild_id).await?;
-        let channel_id = match delegate.get_user_voice_channel(user_id) {
-            Some(channel) => channel,
-            None => {
-                return Ok(vec![Message::Response(
-                    ResponseMessage::NotInVoiceChannelError,
-                )])
-            }
-        };
+        log::debug!("Received {}", args.full_name());
+        let message = match args.subcommand {
+            Some("range") => {
+                let delegate = ModelDelegate::new(ctx, guild_id).await?;
+                let channel_id = match delegate.get_user_voice_channel(user_id) {
+                    Some(channel_id) => channel_id,
+                    None => {
+                        return Ok(vec![Message::Response(
+                            ResponseMessage::NotInVoiceChannelError,
+                        )])
+                    }
+                };
+                // Party hosts can look after their own channel's queue.
+                let is_party_host = playback
+                    .party(channel_id)
+                    .is_some_and(|party| party.host_user_id == user_id);
+                if !can_manage_guild(command) && !is_party_host {
+                    return Ok(vec![Message::Response(
+                        ResponseMessage::MissingPermissionError,
+                    )]);
+                }
 
-        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
-        let mut guild_speakers_ref = guild_speakers_handle.lock().await;
-        match guild_speakers_ref.find_active_in_channel(channel_id) {
-            Some((guild_speaker, active_metadata)) => {
-                if guild_speaker.is_paused() {
-                    log::trace!("Found a paused speaker in the user's voice channel, playback will remain paused");
-                    Ok(vec![Message::Response(
-                        ResponseMessage::NothingIsPlayingError {
-                            voice_channel_id: channel_id,
-                        },
-                    )])
+                // Positions start at 1, the same as in /queue channel.
+                let from = args.required::<i64>("from")?;
+                let to = args.required::<i64>("to")?;
+                let count = if from > 0 && to >= from {
+                    guild_model.remove_channel_entries(
+                        playback,
+                        &delegate,
+                        channel_id,
+                        from as usize - 1,
+                        to as usize - 1,
+                    )
                 } else {
-                    log::trace!("Found an unpaused speaker in the user's voice channel, playback will be paused");
-                    guild_speaker
-                        .pause()
-                        .map_err(crate::error::Error::Backend)?;
-                    Ok(vec![Message::Response(ResponseMessage::Paused {
-                        song_title: active_metadata.title.clone(),
-                        song_url: active_metadata.url.clone(),
+                    0
+                };
+                if count == 0 {
+                    ResponseMessage::RemoveRangeError {
                         voice_channel_id: channel_id,
-                        user_id: active_metadata.user_id,
-                    })])
+                        from,
+                        to,
+                    }
+                } else {
+                    ResponseMessage::ChannelEntriesRemoved {
+                        voice_channel_id: channel_id,
+                        count,
+                    }
                 }
             }
-            _ => {
-                log::trace!(
-                    "No speakers are in the user's voice channel, playback will not change"
-                );
-                Ok(vec![Message::Response(
-                    ResponseMessage::NothingIsPlayingError {
-                        voice_channel_id: channel_id,
-                    },
-                )])
+            Some("user") => {
+                let target_user_id = match args.optional::<UserId>("member")? {
+                    Some(target_user_id) => target_user_id,
+                    None => return Ok(Vec::new()),
+                };
+                if !can_manage_guild(command) {
+                    return Ok(vec![Message::Response(
+                        ResponseMessage::MissingPermissionError,
+                    )]);
+                }
+                let count = guild_model.clear_user_entries(target_user_id);
+                // It's up to admins whether the songs come back, not the user.
+                guild_model.forget_user_changes(target_user_id);
+                ResponseMessage::UserEntriesRemoved {
+                    user_id: target_user_id,
+                    count,
+                }
             }
+            _ => return Ok(Vec::new()),
+        };
+
+        self.events.publish(Event::QueueChanged {
+            guild_id,
+            user_id,
+            by_script: false,
+        });
+        Ok(vec![Message::Response(message)])
+    }
+
+    fn handle_history_command(
+        &self,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
+        guild_id: GuildId,
+    ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        log::debug!("Received {}", args.full_name());
+        if !can_manage_guild(command) {
+            return Ok(vec![Message::Response(
+                ResponseMessage::MissingPermissionError,
+            )]);
+        }
+
+        let format = args
+            .optional::<&str>("format")?
+            .and_then(HistoryFormat::from_name)
+            .unwrap_or(HistoryFormat::Csv);
+        let mut data = Vec::new();
+        let count = self.stats.export_history(guild_id, format, &mut data)?;
+        if count == 0 {
+            return Ok(vec![Message::Response(ResponseMessage::NoHistoryError)]);
         }
+
+        // The file can't be attached to the command's response, so it follows in its own message.
+        Ok(vec![
+            Message::Response(ResponseMessage::HistoryExported { count }),
+            Message::Response(ResponseMessage::HistoryExport {
+                file_name: format!("history.{}", format.extension()),
+                data,
+            }),
+        ])
     }
 
-    async fn handle_skip_command(
-        self: &Arc<Self>,
+    async fn handle_party_command(
+        &self,
         ctx: &Context,
+        command: &application_command::ApplicationCommandInteraction,
+        args: &CommandArgs<'_>,
         user_id: UserId,
         guild_id: GuildId,
-        guild_model: &mut GuildModel<Song>,
+        playback: &mut PlaybackState,
     ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
+        log::debug!("Received {}", args.full_name());
         let delegate = ModelDelegate::new(ctx, guild_id).await?;
         let channel_id = match delegate.get_user_voice_channel(user_id) {
-            Some(channel) => channel,
+            Some(channel_id) => channel_id,
             None => {
                 return Ok(vec![Message::Response(
                     ResponseMessage::NotInVoiceChannelError,
@@ -703,65 +5247,85 @@ impl Frontend {
             }
         };
 
-        let skip_status = guild_model.vote_for_skip(&delegate, VoteType::Skip, channel_id, user_id);
-
-        let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
-        let mut guild_speakers_ref = guild_speakers_handle.lock().await;
-        let maybe_guild_speaker = guild_speakers_ref.find_active_in_channel(channel_id);
-
-        match (skip_status, maybe_guild_speaker) {
-            (VoteStatus::Success, Some((guild_speaker, active_metadata))) => {
-                log::trace!("Skip command passed preconditions, stopping current playback");
-                guild_speaker.stop().map_err(crate::error::Error::Backend)?;
-                Ok(vec![Message::Response(ResponseMessage::Skipped {
-                    song_title: active_metadata.title,
-                    song_url: active_metadata.url,
+        let message = match args.subcommand {
+            Some("start") => match playback.start_party(channel_id, user_id) {
+                Ok(()) => ResponseMessage::PartyStarted {
+                    guild_id,
+                    host_user_id: user_id,
                     voice_channel_id: channel_id,
-                    user_id: active_metadata.user_id,
-                })])
-            }
-            (VoteStatus::AlreadyVoted, Some((_, active_metadata))) => {
-                log::trace!("User attempting to skip has already voted, not stopping playback");
-                Ok(vec![Message::Response(
-                    ResponseMessage::SkipAlreadyVotedError {
-                        song_title: active_metadata.title,
-                        song_url: active_metadata.url,
-                        voice_channel_id: channel_id,
-                    },
-                )])
-            }
-            (VoteStatus::NeedsMoreVotes(count), Some((_, active_metadata))) => {
-                log::trace!(
-                    "Skip vote has been counted but more are needed, not stopping playback"
-                );
-                Ok(vec![Message::Response(
-                    ResponseMessage::SkipMoreVotesNeeded {
-                        song_title: active_metadata.title,
-                        song_url: active_metadata.url,
-                        voice_channel_id: channel_id,
-                        count,
-                    },
-                )])
-            }
-            (VoteStatus::NothingPlaying, _) => {
-                log::trace!(
-                    "Nothing is playing in the user's voice channel, not stopping playback"
-                );
-                Ok(vec![Message::Response(
-                    ResponseMessage::NothingIsPlayingError {
+                },
+                Err(party) => ResponseMessage::PartyAlreadyStartedError {
+                    host_user_id: party.host_user_id,
+                    voice_channel_id: channel_id,
+                },
+            },
+            Some("end") => match playback.party(channel_id) {
+                Some(party) if party.host_user_id != user_id && !can_manage_guild(command) => {
+                    ResponseMessage::MissingPermissionError
+                }
+                Some(_) => {
+                    playback.end_party(channel_id);
+                    ResponseMessage::PartyEnded {
                         voice_channel_id: channel_id,
-                    },
-                )])
-            }
-            (_, None) => Err(crate::error::Error::ModelPlayingSpeakerNotDesync),
+                    }
+                }
+                None => ResponseMessage::NoPartyError {
+                    voice_channel_id: channel_id,
+                },
+            },
+            _ => return Ok(Vec::new()),
+        };
+        Ok(vec![Message::Response(message)])
+    }
+
+    /// Ends a party once its host leaves the party's channel.
+    pub async fn handle_voice_state_update(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        old_channel_id: Option<ChannelId>,
+        new_state: &VoiceState,
+    ) {
+        let old_channel_id = match old_channel_id {
+            Some(old_channel_id) if new_state.channel_id != Some(old_channel_id) => old_channel_id,
+            _ => return,
+        };
+
+        let guild_model_handle = self.model.get(guild_id);
+        let mut playback = guild_model_handle.lock_playback().await;
+        match playback.party(old_channel_id) {
+            Some(party) if party.host_user_id == new_state.user_id => {}
+            _ => return,
+        }
+        log::trace!("Party host has left the channel, ending the party");
+        playback.end_party(old_channel_id);
+
+        let message_channel = match playback.message_channel() {
+            Some(message_channel) => message_channel,
+            None => return,
+        };
+        let send_res = send_messages(
+            &self.config,
+            ctx,
+            SendMessageDestination::Channel(message_channel),
+            playback.deref_mut(),
+            vec![Message::Response(ResponseMessage::PartyHostLeft {
+                host_user_id: new_state.user_id,
+                voice_channel_id: old_channel_id,
+            })],
+        )
+        .await;
+        if let Err(why) = send_res {
+            log::error!("Error while sending party message: {}", why);
         }
     }
 
-    async fn handle_stop_command(
+    async fn handle_dedupe_command(
         self: &Arc<Self>,
         ctx: &Context,
         user_id: UserId,
         guild_id: GuildId,
+        playback: &mut PlaybackState,
         guild_model: &mut GuildModel<Song>,
     ) -> Result<Vec<crate::message::Message>, crate::error::Error> {
         let delegate = ModelDelegate::new(ctx, guild_id).await?;
@@ -774,79 +5338,178 @@ impl Frontend {
             }
         };
 
-        match guild_model.vote_for_skip(&delegate, VoteType::Stop, channel_id, user_id) {
+        // This changes everyone's queues, so it needs the same votes as stopping while something
+        // is playing.
+        match playback.vote_for_skip(&delegate, VoteType::Stop, channel_id, user_id) {
             VoteStatus::Success => {
-                let guild_speakers_handle = self.backend_brain.guild_speakers(guild_id);
-                let mut guild_speakers_ref = guild_speakers_handle.lock().await;
-                let maybe_guild_speaker = guild_speakers_ref.find_active_in_channel(channel_id);
-                match maybe_guild_speaker {
-                    Some((guild_speaker, active_metadata)) => {
-                        log::trace!("Stop command passed preconditions, stopping playback");
-                        guild_model.set_channel_stopped(channel_id);
-                        guild_speaker.stop().map_err(crate::error::Error::Backend)?;
-                        Ok(vec![Message::Response(ResponseMessage::Stopped {
-                            song_title: active_metadata.title.clone(),
-                            song_url: active_metadata.url.clone(),
-                            voice_channel_id: channel_id,
-                            user_id: active_metadata.user_id,
-                        })])
-                    }
-                    None => Err(crate::error::Error::ModelPlayingSpeakerNotDesync),
-                }
+                self.events.publish(Event::VotePassed {
+                    guild_id,
+                    channel_id,
+                    vote_type: "stop",
+                });
             }
             VoteStatus::AlreadyVoted => {
-                log::trace!("User attempting to stop has already voted, not stopping playback");
-                Ok(vec![Message::Response(
+                log::trace!("User attempting to dedupe has already voted, leaving the queue");
+                return Ok(vec![Message::Response(
                     ResponseMessage::StopAlreadyVotedError {
                         voice_channel_id: channel_id,
                     },
-                )])
+                )]);
             }
             VoteStatus::NeedsMoreVotes(count) => {
-                log::trace!(
-                    "Stop vote has been counted but more are needed, not stopping playback"
-                );
-                Ok(vec![Message::Response(
+                log::trace!("Stop vote has been counted but more are needed, leaving the queue");
+                self.events.publish(Event::VoteProgress {
+                    guild_id,
+                    channel_id,
+                    vote_type: "stop",
+                    votes_needed: count,
+                });
+                return Ok(vec![Message::Response(
                     ResponseMessage::StopMoreVotesNeeded {
                         voice_channel_id: channel_id,
                         count,
                     },
-                )])
+                )]);
             }
-            VoteStatus::NothingPlaying => {
-                log::trace!(
-                    "Nothing is playing in the user's voice channel, not stopping playback"
-                );
-                Ok(vec![Message::Response(
-                    ResponseMessage::NothingIsPlayingError {
-                        voice_channel_id: channel_id,
-                    },
-                )])
+            VoteStatus::NothingPlaying => {}
+        }
+
+        log::trace!("Dedupe command passed preconditions, removing duplicate songs");
+        let count = guild_model.dedupe_entries(playback, &delegate, channel_id, |song| {
+            song.metadata.url.clone()
+        });
+        if count > 0 {
+            self.events.publish(Event::QueueChanged {
+                guild_id,
+                user_id,
+                by_script: false,
+            });
+        }
+        Ok(vec![Message::Response(ResponseMessage::Deduped {
+            voice_channel_id: channel_id,
+            count,
+        })])
+    }
+
+    /// Remembers where a long song stopped, or forgets it once the song has been played through.
+    fn update_saved_position(
+        &self,
+        guild_id: GuildId,
+        metadata: &SongMetadata,
+        ended_position: Option<Duration>,
+    ) -> Result<(), crate::error::Error> {
+        let resume_config = match &self.config.resume_positions {
+            Some(resume_config) => resume_config,
+            None => return Ok(()),
+        };
+        let (duration, position) = match (metadata.duration, ended_position) {
+            (Some(duration), Some(position))
+                if duration.as_secs() >= resume_config.min_duration_secs =>
+            {
+                (duration, position)
             }
+            _ => return Ok(()),
+        };
+
+        let margin = Duration::from_secs(RESUME_POSITION_MARGIN_SECS);
+        if position > margin && position + margin < duration {
+            self.positions.save(
+                guild_id,
+                metadata.url.clone(),
+                position,
+                resume_config.max_age(),
+            )
+        } else {
+            self.positions.forget(guild_id, &metadata.url)
         }
     }
 
     async fn handle_playback_ended(
         self: Arc<Self>,
         ctx: Context,
-        started_channel_id: ChannelId,
+        mut started_channel_id: ChannelId,
         ended_handle: GuildSpeakerEndedHandle,
     ) {
+        // The new process has the queues now, and picks up the song itself.
+        if self.is_handed_over() {
+            log::trace!("Playback has ended after handing over, leaving it to the new process");
+            return;
+        }
         log::trace!("Playback has ended, preparing to play the next available song");
 
         let guild_model_handle = self.model.get(ended_handle.guild_id());
-        let mut guild_model = guild_model_handle.lock().await;
-        let maybe_message_channel = guild_model.message_channel();
+        let mut playback = guild_model_handle.lock_playback().await;
+        let maybe_message_channel = playback.message_channel();
 
         let (state, speaker_ended_ref) = ended_handle.lock().await;
+        // The song was transferred to another channel while it was playing.
+        if let Some(playing_channel_id) = state.playing_channel_id {
+            started_channel_id = playing_channel_id;
+        }
+        let mut achievement_messages = Vec::new();
+        let mut failure_message = None;
+        if let Some(ended_metadata) = &state.ended_metadata {
+            let failed = state.end_reason == EndReason::Errored;
+            if failed {
+                log::warn!(
+                    "Playback of \"{}\" broke off before the end",
+                    ended_metadata.title
+                );
+                failure_message = Some(Message::Response(ResponseMessage::PlaybackFailedError {
+                    song_title: ended_metadata.title.clone(),
+                    song_url: ended_metadata.url.clone(),
+                    voice_channel_id: started_channel_id,
+                    user_id: ended_metadata.user_id,
+                }));
+            }
+            self.events.publish(Event::TrackEnded {
+                guild_id: ended_handle.guild_id(),
+                channel_id: started_channel_id,
+                song_title: ended_metadata.title.clone(),
+                song_url: ended_metadata.url.clone(),
+                user_id: ended_metadata.user_id,
+            });
+            achievement_messages = self
+                .record_play(
+                    &ctx,
+                    ended_handle.guild_id(),
+                    started_channel_id,
+                    ended_metadata,
+                    failed,
+                )
+                .await;
+            if let Err(why) = self.update_saved_position(
+                ended_handle.guild_id(),
+                ended_metadata,
+                state.ended_position,
+            ) {
+                log::error!("Error while saving playback position: {}", why);
+            }
+        }
         let messages = match state.channel_id {
+            // The song was allowed to finish, but nothing else starts until maintenance is over.
+            // The queues are kept for afterwards.
+            Some(_) if self.is_in_maintenance() => {
+                playback.set_channel_stopped(started_channel_id);
+                speaker_ended_ref.stop();
+                Ok(vec![Message::Response(
+                    ResponseMessage::MaintenanceStopped {
+                        voice_channel_id: started_channel_id,
+                    },
+                )])
+            }
             Some(channel_id) => {
                 self.continue_channel_playback(
                     &ctx,
                     ended_handle.guild_id(),
-                    guild_model.deref_mut(),
+                    playback.deref_mut(),
+                    &guild_model_handle,
                     started_channel_id,
                     channel_id,
+                    state
+                        .ended_metadata
+                        .as_ref()
+                        .map(|metadata| metadata.user_id),
                     speaker_ended_ref,
                 )
                 .await
@@ -854,7 +5517,7 @@ impl Frontend {
             None => {
                 // The speaker that played a song is no longer in a voice channel. Interpret
                 // this as a forced stop command, instead of just trying to play the next song.
-                guild_model.set_channel_stopped(started_channel_id);
+                playback.set_channel_stopped(started_channel_id);
                 speaker_ended_ref.stop();
                 match state.ended_metadata {
                     Some(active_metadata) => {
@@ -872,11 +5535,26 @@ impl Frontend {
 
         let send_result = match (messages, maybe_message_channel) {
             (Ok(messages), Some(message_channel)) => {
+                // The failure is about the song before whatever plays next, so it comes first.
+                let mut messages: Vec<_> = failure_message.into_iter().chain(messages).collect();
+                messages.extend(
+                    self.dropped_entries_messages(
+                        guild_model_handle.lock_queues().await.deref_mut(),
+                    ),
+                );
+                messages.extend(achievement_messages);
+                let messages = self
+                    .add_now_playing_cards(
+                        &ctx,
+                        ended_handle.guild_id(),
+                        self.apply_user_prefs(messages),
+                    )
+                    .await;
                 send_messages(
                     &self.config,
                     &ctx,
                     SendMessageDestination::Channel(message_channel),
-                    guild_model.deref_mut(),
+                    playback.deref_mut(),
                     messages,
                 )
                 .await
@@ -887,7 +5565,7 @@ impl Frontend {
                     &self.config,
                     &ctx,
                     SendMessageDestination::Channel(message_channel),
-                    guild_model.deref_mut(),
+                    playback.deref_mut(),
                     vec![Message::Action(ActionMessage::UnknownError)],
                 )
                 .await
@@ -899,47 +5577,207 @@ impl Frontend {
         if let Err(why) = send_result {
             log::error!("Error while continuing playback: {}", why);
         }
+
+        if !playback.is_playing() {
+            self.end_session_thread(&ctx, playback.deref_mut()).await;
+        }
+    }
+
+    /// Counts the song in the guild's stats, returning messages for any achievements that
+    /// unlocked.
+    async fn record_play(
+        &self,
+        ctx: &Context,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        metadata: &SongMetadata,
+        failed: bool,
+    ) -> Vec<Message> {
+        let mut unlocked = Vec::new();
+        match self
+            .stats
+            .record_play(guild_id, channel_id, metadata, failed)
+        {
+            Ok(Some(achievement)) => unlocked.push((metadata.user_id, achievement)),
+            Ok(None) => {}
+            Err(why) => log::error!("Error while recording stats: {}", why),
+        }
+
+        // Everyone still listening this far into a session has survived it.
+        if self.stats.is_long_session(guild_id, channel_id) {
+            let unlock_res = ModelDelegate::new(ctx, guild_id)
+                .await
+                .and_then(|delegate| {
+                    self.stats.unlock(
+                        guild_id,
+                        &delegate.users_in_voice_channel(channel_id),
+                        Achievement::LongSession,
+                    )
+                });
+            match unlock_res {
+                Ok(user_ids) => unlocked.extend(
+                    user_ids
+                        .into_iter()
+                        .map(|user_id| (user_id, Achievement::LongSession)),
+                ),
+                Err(why) => log::error!("Error while unlocking achievements: {}", why),
+            }
+        }
+
+        unlocked
+            .into_iter()
+            .map(|(user_id, achievement)| {
+                Message::Response(ResponseMessage::AchievementUnlocked {
+                    user_id,
+                    achievement,
+                })
+            })
+            .collect()
+    }
+
+    /// Creates a thread off the message channel for this session's updates, if the guild has
+    /// session threads turned on and there isn't one already.
+    async fn start_session_thread(
+        &self,
+        ctx: &Context,
+        playback: &mut PlaybackState,
+        features: GuildFeatures,
+    ) {
+        if playback.session_thread().is_some() || !features.session_threads {
+            return;
+        }
+        let message_channel = match playback.message_channel() {
+            Some(message_channel) => message_channel,
+            None => return,
+        };
+
+        let timezone: chrono_tz::Tz = self
+            .config
+            .leaderboard
+            .timezone
+            .parse()
+            .unwrap_or(chrono_tz::UTC);
+        let weekday_string = chrono::Utc::now()
+            .with_timezone(&timezone)
+            .format("%A")
+            .to_string();
+        let thread_name = self
+            .config
+            .get_message("session.thread_name", &[("weekday", &weekday_string)]);
+
+        let thread_res = async {
+            let starter_message = message_channel
+                .send_message(&ctx.http, |create_message| {
+                    create_message.content(self.config.get_raw_message("session.thread_starter"))
+                })
+                .await?;
+            message_channel
+                .create_public_thread(&ctx.http, starter_message.id, |thread| {
+                    thread.name(thread_name)
+                })
+                .await
+        }
+        .await;
+        match thread_res {
+            Ok(thread) => {
+                log::trace!("Started session thread {}", thread.id);
+                playback.set_session_thread(Some(thread.id));
+            }
+            Err(why) => log::error!("Error while creating session thread: {}", why),
+        }
     }
 
+    async fn end_session_thread(&self, ctx: &Context, playback: &mut PlaybackState) {
+        let thread_id = match playback.session_thread() {
+            Some(thread_id) => thread_id,
+            None => return,
+        };
+        playback.set_session_thread(None);
+
+        log::trace!(
+            "Playback has stopped, archiving session thread {}",
+            thread_id
+        );
+        if let Err(why) = thread_id
+            .edit_thread(&ctx.http, |thread| thread.archived(true))
+            .await
+        {
+            log::error!("Error while archiving session thread: {}", why);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn continue_channel_playback(
         self: &Arc<Self>,
         ctx: &Context,
         guild_id: GuildId,
-        guild_model: &mut GuildModel<Song>,
+        playback: &mut PlaybackState,
+        guild_model_handle: &GuildHandle<Song>,
         started_channel_id: ChannelId,
         current_channel_id: ChannelId,
+        ended_user_id: Option<UserId>,
         mut speaker_ended_ref: GuildSpeakerEndedRef<'_>,
     ) -> Result<Vec<Message>, crate::error::Error> {
+        let delegate = ModelDelegate::new(ctx, guild_id).await?;
+        let features = guild_model_handle.lock_queues().await.features();
+
         // If the speaker has moved channels, simply indicate the original channel as stopped and
         // do not play anything in the new channel. This ensures we follow the behavior of not
-        // playing songs until the user instructs the bot to.
+        // playing songs until the user instructs the bot to. The exception is when the guild has
+        // opted to follow the requester of the last song, and they've moved along with it.
         if started_channel_id != current_channel_id {
-            log::trace!("Speaker has switched channel, not playing any more songs.");
-            guild_model.set_channel_stopped(started_channel_id);
-            speaker_ended_ref.stop();
-            return Ok(Vec::new());
+            let is_following = features.follow_requester
+                && match ended_user_id {
+                    Some(user_id) => {
+                        delegate.get_user_voice_channel(user_id) == Some(current_channel_id)
+                    }
+                    None => false,
+                }
+                && playback.move_channel(started_channel_id, current_channel_id);
+
+            if is_following {
+                log::trace!("Speaker has followed the requester to a new channel, continuing playback there.");
+            } else {
+                log::trace!("Speaker has switched channel, not playing any more songs.");
+                playback.set_channel_stopped(started_channel_id);
+                speaker_ended_ref.stop();
+                return Ok(Vec::new());
+            }
         }
 
         // Don't play anything more if the channel was stopped.
-        if guild_model.is_channel_stopped(current_channel_id) {
+        if playback.is_channel_stopped(current_channel_id) {
             log::trace!("Channel has been stopped, not playing any more songs.");
             speaker_ended_ref.stop();
             return Ok(Vec::new());
         }
 
-        let delegate = ModelDelegate::new(ctx, guild_id).await?;
-
-        // Playing a song can fail - keep trying to play until we succeed or run out of songs
-        while let Some(song) =
-            guild_model.next_channel_entry_finished(&delegate, current_channel_id)
-        {
+        // Playing a song can fail - keep trying to play until we succeed or run out of songs. The
+        // queues are only locked while each song is picked, not while it starts playing.
+        loop {
+            let next_entry = guild_model_handle
+                .lock_queues()
+                .await
+                .next_channel_entry_finished(playback, &delegate, current_channel_id);
+            let song = match next_entry {
+                Some(song) => song,
+                None => break,
+            };
             let next_metadata = song.metadata.clone();
             log::trace!("Playing \"{}\" to speaker", next_metadata.title);
 
+            // Announcing a song that's playing gaplessly would put the gap back.
+            let announcement = if speaker_ended_ref.is_primed(&song) {
+                None
+            } else {
+                self.song_announcement(ctx, guild_id, features, &next_metadata)
+                    .await
+            };
             let play_res = speaker_ended_ref
                 .play(
                     song,
-                    &self.config.get_play_config(),
+                    announcement.as_deref(),
+                    &self.play_config(),
                     EndedDelegate {
                         frontend: self.clone(),
                         ctx: ctx.clone(),
@@ -950,12 +5788,13 @@ impl Frontend {
 
             match play_res {
                 Ok(_) => {
+                    self.track_started(ctx, guild_id, features, current_channel_id, &next_metadata);
                     return Ok(vec![Message::Action(ActionMessage::Playing {
                         song_title: next_metadata.title,
                         song_url: next_metadata.url,
                         voice_channel_id: current_channel_id,
                         user_id: next_metadata.user_id,
-                    })])
+                    })]);
                 }
                 Err((new_ref, why)) => {
                     log::error!("Error while continuing playback: {}", why);
@@ -966,6 +5805,10 @@ impl Frontend {
 
         log::trace!("No songs are available to play in the channel, nothing will be played");
         speaker_ended_ref.stop();
+        self.events.publish(Event::QueueEmptied {
+            guild_id,
+            channel_id: current_channel_id,
+        });
         Ok(vec![Message::Action(ActionMessage::Finished {
             voice_channel_id: current_channel_id,
         })])
@@ -974,17 +5817,23 @@ impl Frontend {
     async fn play_to_speaker(
         self: &Arc<Self>,
         ctx: &Context,
-        guild_model: &mut GuildModel<Song>,
+        playback: &mut PlaybackState,
+        features: GuildFeatures,
         guild_speaker: &mut GuildSpeakerRef<'_>,
         channel_id: ChannelId,
         song: Song,
     ) -> Result<(), crate::error::Error> {
         log::trace!("Playing \"{}\" to speaker", song.metadata.title);
+        let metadata = song.metadata.clone();
+        let announcement = self
+            .song_announcement(ctx, guild_speaker.guild_id(), features, &metadata)
+            .await;
         let play_res = guild_speaker
             .play(
                 channel_id,
                 song,
-                &self.config.get_play_config(),
+                announcement.as_deref(),
+                &self.play_config(),
                 EndedDelegate {
                     frontend: self.clone(),
                     ctx: ctx.clone(),
@@ -994,13 +5843,144 @@ impl Frontend {
             .await;
 
         match play_res {
-            Ok(()) => Ok(()),
+            Ok(()) => {
+                self.start_session_thread(ctx, playback, features).await;
+                self.track_started(
+                    ctx,
+                    guild_speaker.guild_id(),
+                    features,
+                    channel_id,
+                    &metadata,
+                );
+                Ok(())
+            }
             Err(why) => {
-                guild_model.set_channel_stopped(channel_id);
+                playback.set_channel_stopped(channel_id);
                 Err(crate::error::Error::Backend(why))
             }
         }
     }
+
+    /// Plays a song another process was playing, on the same speaker and from the song's start
+    /// position. It isn't announced or counted again, since it already was when it started.
+    pub async fn resume_playback(
+        self: &Arc<Self>,
+        ctx: &Context,
+        guild_id: GuildId,
+        speaker_index: usize,
+        channel_id: ChannelId,
+        song: Song,
+        is_paused: bool,
+    ) -> Result<(), crate::error::Error> {
+        let speaker = self
+            .backend_brain
+            .speakers
+            .get(speaker_index)
+            .ok_or(crate::error::Error::UnknownSpeaker(speaker_index))?;
+        let guild_model_handle = self.model.get(guild_id);
+        let mut playback = guild_model_handle.lock_playback().await;
+        let guild_speaker_handle = speaker.get(guild_id);
+        let mut guild_speaker = guild_speaker_handle.lock().await;
+
+        log::trace!("Resuming \"{}\" on speaker", song.metadata.title);
+        let user_id = song.metadata.user_id;
+        guild_speaker
+            .play(
+                channel_id,
+                song,
+                None,
+                &self.play_config(),
+                EndedDelegate {
+                    frontend: self.clone(),
+                    ctx: ctx.clone(),
+                    started_channel_id: channel_id,
+                },
+            )
+            .await
+            .map_err(crate::error::Error::Backend)?;
+        playback.set_channel_playing(channel_id, user_id);
+        if is_paused {
+            guild_speaker
+                .pause()
+                .map_err(crate::error::Error::Backend)?;
+        }
+        Ok(())
+    }
+}
+
+fn script_context(
+    guild_id: GuildId,
+    maybe_user_id: Option<UserId>,
+    input: String,
+    guild_model: &GuildModel<Song>,
+) -> ScriptContext {
+    let queue = match maybe_user_id {
+        Some(user_id) => guild_model
+            .user_entries(user_id)
+            .map(|song| song.metadata.title.clone())
+            .collect(),
+        None => Vec::new(),
+    };
+    ScriptContext::new(guild_id, maybe_user_id, input, queue)
+}
+
+/// A guild's speakers while they're locked, with what's needed to start songs on them.
+struct BotSpeakers<'a> {
+    frontend: &'a Arc<Frontend>,
+    ctx: &'a Context,
+    speakers: BrainSpeakersRef<'a>,
+}
+
+#[serenity::async_trait]
+impl<'a> GuildSpeakers for BotSpeakers<'a> {
+    fn active_in_channel(&mut self, channel_id: ChannelId) -> Option<SongMetadata> {
+        self.speakers
+            .find_active_in_channel(channel_id)
+            .map(|(_, active_metadata)| active_metadata)
+    }
+
+    fn stop_in_channel(&mut self, channel_id: ChannelId) -> Result<(), crate::error::Error> {
+        match self.speakers.find_active_in_channel(channel_id) {
+            Some((guild_speaker, _)) => guild_speaker.stop().map_err(crate::error::Error::Backend),
+            None => Ok(()),
+        }
+    }
+
+    async fn find_to_play_in_channel(
+        &mut self,
+        channel_id: ChannelId,
+    ) -> Result<(), FindSpeakerError> {
+        self.speakers
+            .find_to_play_in_channel(channel_id)
+            .await
+            .map(|_| ())
+    }
+
+    async fn play_in_channel(
+        &mut self,
+        playback: &mut PlaybackState,
+        features: GuildFeatures,
+        channel_id: ChannelId,
+        song: Song,
+    ) -> Result<(), crate::error::Error> {
+        // Nothing else can take a speaker while they're locked, so this is the same one
+        // `find_to_play_in_channel` found.
+        let guild_speaker = self
+            .speakers
+            .find_to_play_in_channel(channel_id)
+            .await
+            .map_err(|_| crate::error::Error::ModelPlayingSpeakerNotDesync)?;
+        self.frontend
+            .play_to_speaker(
+                self.ctx,
+                playback,
+                features,
+                guild_speaker,
+                channel_id,
+                song,
+            )
+            .await
+    }
 }
 
 struct EndedDelegate {
@@ -1018,3 +5998,112 @@ impl EndedHandler for EndedDelegate {
         ));
     }
 }
+
+/// Reads a point in a song written like `1:02:03`, `2:30` or `90`. Returns `None` if it isn't
+/// written like that.
+fn parse_timestamp(timestamp: &str) -> Option<Duration> {
+    let mut secs: u64 = 0;
+    let mut part_count = 0;
+    for part in timestamp.trim().split(':') {
+        let value: u64 = part.parse().ok()?;
+        // Only the first part can go past 59, e.g. `90` or `90:00`.
+        if part_count > 0 && value >= 60 {
+            return None;
+        }
+        secs = secs.checked_mul(60)?.checked_add(value)?;
+        part_count += 1;
+    }
+    if part_count > 3 {
+        return None;
+    }
+    Some(Duration::from_secs(secs))
+}
+
+/// How `/play` should play what it queues.
+#[derive(Default)]
+struct PlayOptions {
+    /// Pick up from where the song stopped last time.
+    resume: bool,
+    start: Option<Duration>,
+    end: Option<Duration>,
+}
+
+impl PlayOptions {
+    /// Returns `None` if the start or end can't be read, or the song would end before it starts.
+    fn parse(resume: bool, start: Option<&str>, end: Option<&str>) -> Option<Self> {
+        let start = match start {
+            Some(start) => Some(parse_timestamp(start)?),
+            None => None,
+        };
+        let end = match end {
+            Some(end) => Some(parse_timestamp(end)?),
+            None => None,
+        };
+        match (start, end) {
+            (Some(start), Some(end)) if end <= start => None,
+            (_, Some(end)) if end.is_zero() => None,
+            _ => Some(PlayOptions { resume, start, end }),
+        }
+    }
+}
+
+/// Web links in a message's text followed by its attachments, in the order they appear.
+fn message_links(message: &serenity::model::channel::Message) -> Vec<String> {
+    let content_links = message
+        .content
+        .split_whitespace()
+        // Discord lets links be wrapped in angle brackets to hide their embeds.
+        .map(|word| word.trim_start_matches('<').trim_end_matches('>'))
+        .filter(|word| {
+            matches!(url::Url::parse(word), Ok(url) if matches!(url.scheme(), "http" | "https"))
+        })
+        .map(str::to_string);
+    let attachment_links = message
+        .attachments
+        .iter()
+        .map(|attachment| attachment.url.clone());
+    content_links.chain(attachment_links).collect()
+}
+
+/// Audit entries are posted in the background, so a slow or missing channel never holds up the
+/// response.
+fn post_audit_entry(http: Arc<Http>, channel_id: ChannelId, entry: AuditEntry) {
+    tokio::spawn(async move {
+        if let Err(why) = entry.post(&http, channel_id).await {
+            log::warn!("Unable to post to audit channel {}: {}", channel_id, why);
+        }
+    });
+}
+
+/// The songs in the user's queue, as they're saved in playlists.
+fn queued_playlist_songs(guild_model: &GuildModel<Song>, user_id: UserId) -> Vec<PlaylistSong> {
+    guild_model
+        .user_entries(user_id)
+        .map(|song| PlaylistSong {
+            title: song.metadata.title.clone(),
+            url: song.metadata.url.clone(),
+        })
+        .collect()
+}
+
+fn can_manage_guild(command: &application_command::ApplicationCommandInteraction) -> bool {
+    command
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.manage_guild())
+}
+
+/// Whether the user owns the command bot's application, or is on the team that does.
+async fn is_owner(ctx: &Context, user_id: UserId) -> Result<bool, crate::error::Error> {
+    let app_info = ctx
+        .http
+        .get_current_application_info()
+        .await
+        .map_err(crate::error::Error::Serenity)?;
+    let is_team_member = match app_info.team {
+        Some(team) => team.members.iter().any(|member| member.user.id == user_id),
+        None => false,
+    };
+    Ok(app_info.owner.id == user_id || is_team_member)
+}
@@ -0,0 +1,312 @@
+use crate::frontend::Frontend;
+use crate::persistence::Persistence;
+use crate::queue_store::SavedUserQueue;
+use mrvn_back_ytdl::{PauseReason, Song};
+use mrvn_model::{AppModel, SavedQueue};
+use serenity::{model::prelude::*, prelude::*};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::Notify;
+
+// Sent by the new process once it's playing everything the old one was.
+const RESUMED_LINE: &str = "resumed";
+
+// How long the old process waits for the new one to pick up playback before carrying on itself.
+const RESUME_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SpeakerState {
+    speaker_index: usize,
+    channel_id: ChannelId,
+    // Starts from where playback had got to.
+    song: Song,
+    is_paused: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct GuildState {
+    guild_id: GuildId,
+    message_channel: Option<ChannelId>,
+    session_thread: Option<ChannelId>,
+    queues: Vec<SavedUserQueue>,
+    speakers: Vec<SpeakerState>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct HandoverState {
+    guilds: Vec<GuildState>,
+}
+
+// What's left to do after taking the old process's state, once the clients have connected.
+struct ReceivedHandover {
+    speakers: Vec<(GuildId, SpeakerState)>,
+    received_time: Instant,
+    stream: UnixStream,
+}
+
+/// Lets a new process take over from a running one without stopping what's playing. The new
+/// process connects to the old one's socket and is sent its queues and what each speaker is
+/// playing. Once the new process is playing those songs itself, the old one exits.
+pub struct Handover {
+    socket_path: String,
+    voice_client_count: usize,
+    persistence: Arc<dyn Persistence>,
+    // Set in the old process once it's sent its state, so it stops changing it.
+    is_handed_over: AtomicBool,
+    is_taking_over: AtomicBool,
+    received: Mutex<Option<ReceivedHandover>>,
+    taken_over: Notify,
+    ready_voice_clients: std::sync::Mutex<HashSet<usize>>,
+    voice_clients_ready: Notify,
+}
+
+impl Handover {
+    pub fn new(
+        socket_path: String,
+        voice_client_count: usize,
+        persistence: Arc<dyn Persistence>,
+    ) -> Self {
+        Handover {
+            socket_path,
+            voice_client_count,
+            persistence,
+            is_handed_over: AtomicBool::new(false),
+            is_taking_over: AtomicBool::new(false),
+            received: Mutex::new(None),
+            taken_over: Notify::new(),
+            ready_voice_clients: std::sync::Mutex::new(HashSet::new()),
+            voice_clients_ready: Notify::new(),
+        }
+    }
+
+    /// Whether this process has handed over to a new one, and should leave everything to it.
+    pub fn is_handed_over(&self) -> bool {
+        self.is_handed_over.load(Ordering::SeqCst)
+    }
+
+    /// Whether this process took over from a running one, which carries on what it was playing.
+    pub fn is_taking_over(&self) -> bool {
+        self.is_taking_over.load(Ordering::SeqCst)
+    }
+
+    /// Takes over from the process listening on the socket, if there is one. Queues are restored
+    /// straight away, songs are picked up by `resume` once the clients have connected.
+    pub async fn receive(&self, model: &AppModel<Song>) -> Result<(), crate::error::Error> {
+        let stream = match UnixStream::connect(&self.socket_path).await {
+            Ok(stream) => stream,
+            Err(why)
+                if why.kind() == std::io::ErrorKind::NotFound
+                    || why.kind() == std::io::ErrorKind::ConnectionRefused =>
+            {
+                log::info!("No running process to take over from");
+                return Ok(());
+            }
+            Err(why) => return Err(crate::error::Error::Io(why)),
+        };
+
+        log::info!("Taking over from the running process");
+        let mut stream = BufReader::new(stream);
+        let mut line = String::new();
+        stream
+            .read_line(&mut line)
+            .await
+            .map_err(crate::error::Error::Io)?;
+        let state: HandoverState =
+            serde_json::from_str(&line).map_err(crate::error::Error::Json)?;
+        let received_time = Instant::now();
+
+        let mut speakers = Vec::new();
+        for guild_state in state.guilds {
+            let guild_model_handle = model.get(guild_state.guild_id);
+            let (mut playback, mut guild_model) = guild_model_handle.lock().await;
+            playback.set_message_channel(guild_state.message_channel);
+            playback.set_session_thread(guild_state.session_thread);
+            guild_model.restore_queues(
+                guild_state
+                    .queues
+                    .into_iter()
+                    .map(SavedQueue::from)
+                    .collect(),
+            );
+            speakers.extend(
+                guild_state
+                    .speakers
+                    .into_iter()
+                    .map(|speaker_state| (guild_state.guild_id, speaker_state)),
+            );
+        }
+
+        log::info!("Received state, {} songs will be resumed", speakers.len());
+        self.is_taking_over.store(true, Ordering::SeqCst);
+        *self.received.lock().await = Some(ReceivedHandover {
+            speakers,
+            received_time,
+            stream: stream.into_inner(),
+        });
+        Ok(())
+    }
+
+    /// Counts a voice client as connected. Songs are only picked up once they all are.
+    pub fn voice_client_ready(&self, client_index: usize) {
+        let mut ready_voice_clients = self.ready_voice_clients.lock().unwrap();
+        ready_voice_clients.insert(client_index);
+        if ready_voice_clients.len() >= self.voice_client_count {
+            self.voice_clients_ready.notify_one();
+        }
+    }
+
+    /// Plays the songs the old process was playing once the voice clients have connected, then
+    /// lets it exit. This needs the command client's context, so it's run once that's connected.
+    pub async fn resume(&self, ctx: &Context, frontend: &Arc<Frontend>) {
+        let received = match self.received.lock().await.take() {
+            Some(received) => received,
+            None => return,
+        };
+        self.voice_clients_ready.notified().await;
+
+        // The old process kept playing while this one was connecting.
+        let elapsed = received.received_time.elapsed();
+        for (guild_id, mut speaker_state) in received.speakers {
+            if !speaker_state.is_paused {
+                let position = speaker_state.song.start_position() + elapsed;
+                speaker_state.song.set_start_position(position);
+            }
+            let resume_res = frontend
+                .resume_playback(
+                    ctx,
+                    guild_id,
+                    speaker_state.speaker_index,
+                    speaker_state.channel_id,
+                    speaker_state.song,
+                    speaker_state.is_paused,
+                )
+                .await;
+            if let Err(why) = resume_res {
+                log::error!(
+                    "Error while resuming playback in guild {}: {}",
+                    guild_id,
+                    why
+                );
+            }
+        }
+
+        let mut stream = received.stream;
+        let line = format!("{}\n", RESUMED_LINE);
+        if let Err(why) = stream.write_all(line.as_bytes()).await {
+            log::error!("Error while telling the old process to exit: {}", why);
+        }
+        log::info!("Finished taking over from the old process");
+        self.taken_over.notify_one();
+    }
+
+    /// Waits for new processes to take over from this one. If this process is taking over itself,
+    /// it only starts listening once that's finished.
+    pub async fn listen(&self, frontend: &Frontend) {
+        if self.is_taking_over.load(Ordering::SeqCst) {
+            self.taken_over.notified().await;
+        }
+
+        // The old process's socket file is still there, and would stop this one binding.
+        if let Err(why) = std::fs::remove_file(&self.socket_path) {
+            if why.kind() != std::io::ErrorKind::NotFound {
+                log::error!("Error while removing old handover socket: {}", why);
+            }
+        }
+        let listener = match UnixListener::bind(&self.socket_path) {
+            Ok(listener) => listener,
+            Err(why) => {
+                log::error!("Error while binding handover socket: {}", why);
+                return;
+            }
+        };
+        log::info!("Listening for handovers on {}", self.socket_path);
+
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => self.hand_over(frontend, stream).await,
+                Err(why) => log::error!("Error while accepting handover: {}", why),
+            }
+        }
+    }
+
+    async fn hand_over(&self, frontend: &Frontend, stream: UnixStream) {
+        log::info!("A new process is taking over");
+        self.is_handed_over.store(true, Ordering::SeqCst);
+
+        let hand_over_res = async {
+            // The new process loads what's been saved as it needs it.
+            tokio::task::block_in_place(|| self.persistence.flush());
+            let mut json = serde_json::to_string(&capture_state(frontend).await)
+                .map_err(crate::error::Error::Json)?;
+            json.push('\n');
+            let mut stream = BufReader::new(stream);
+            stream
+                .get_mut()
+                .write_all(json.as_bytes())
+                .await
+                .map_err(crate::error::Error::Io)?;
+
+            let mut line = String::new();
+            match tokio::time::timeout(RESUME_TIMEOUT, stream.read_line(&mut line)).await {
+                Ok(read_res) => read_res.map_err(crate::error::Error::Io)?,
+                Err(_) => return Ok(false),
+            };
+            Ok::<_, crate::error::Error>(line.trim() == RESUMED_LINE)
+        }
+        .await;
+
+        match hand_over_res {
+            Ok(true) => {
+                log::info!("The new process has taken over, exiting");
+                tokio::task::block_in_place(|| self.persistence.flush());
+                std::process::exit(0);
+            }
+            Ok(false) => log::warn!("The new process didn't take over, carrying on"),
+            Err(why) => log::error!("Error while handing over, carrying on: {}", why),
+        }
+        self.is_handed_over.store(false, Ordering::SeqCst);
+    }
+}
+
+async fn capture_state(frontend: &Frontend) -> HandoverState {
+    let mut guilds = Vec::new();
+    for (guild_id, guild_model_handle) in frontend.model.guilds() {
+        let (playback, guild_model) = guild_model_handle.lock().await;
+
+        let mut speakers = Vec::new();
+        for (speaker_index, speaker) in frontend.backend_brain.speakers.iter().enumerate() {
+            let guild_speaker_handle = speaker.get(guild_id);
+            let guild_speaker = guild_speaker_handle.lock().await;
+            let (channel_id, song) = match (
+                guild_speaker.current_channel(),
+                guild_speaker.resumable_song().await,
+            ) {
+                (Some(channel_id), Some(song)) => (channel_id, song),
+                _ => continue,
+            };
+            speakers.push(SpeakerState {
+                speaker_index,
+                channel_id,
+                song,
+                is_paused: guild_speaker.pause_reason() == Some(PauseReason::Requested),
+            });
+        }
+
+        guilds.push(GuildState {
+            guild_id,
+            message_channel: playback.message_channel(),
+            session_thread: playback.session_thread(),
+            queues: guild_model
+                .saved_queues()
+                .into_iter()
+                .map(SavedUserQueue::from)
+                .collect(),
+            speakers,
+        });
+    }
+    HandoverState { guilds }
+}
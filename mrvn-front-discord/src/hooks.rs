@@ -0,0 +1,153 @@
+use crate::config::{WebhookConfig, WebhookKind};
+use crate::event_bus::Event;
+use futures::prelude::*;
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+// Every matching hook is sent to before the next event, so a slow one can't hold up the rest for
+// long.
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Hook {
+    // Where the hook is in the config, which is how it's identified in logs since its URL can
+    // have a token in it.
+    index: usize,
+    config: WebhookConfig,
+}
+
+impl Hook {
+    fn matches(&self, event: &Event) -> bool {
+        self.config.events.is_empty() || self.config.events.iter().any(|name| name == event.name())
+    }
+
+    async fn send(&self, client: &reqwest::Client, event: &Event) -> Result<(), reqwest::Error> {
+        let payload = match self.config.kind {
+            WebhookKind::Discord => match discord_content(event) {
+                // Mentions are formatted so they display nicely, but shouldn't ping anyone.
+                Some(content) => json!({
+                    "content": content,
+                    "allowed_mentions": { "parse": [] },
+                }),
+                None => return Ok(()),
+            },
+            WebhookKind::Http => json!(event),
+        };
+
+        client
+            .post(&self.config.url)
+            .json(&payload)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+fn discord_content(event: &Event) -> Option<String> {
+    match event {
+        Event::TrackStarted {
+            channel_id,
+            song_title,
+            song_url,
+            user_id,
+            ..
+        } => Some(format!(
+            "Started playing [{}](<{}>) in <#{}> (added by <@{}>)",
+            song_title, song_url, channel_id, user_id
+        )),
+        Event::TrackEnded {
+            channel_id,
+            song_title,
+            song_url,
+            ..
+        } => Some(format!(
+            "Finished playing [{}](<{}>) in <#{}>",
+            song_title, song_url, channel_id
+        )),
+        Event::VotePassed {
+            channel_id,
+            vote_type,
+            ..
+        } => Some(format!("Vote to {} passed in <#{}>", vote_type, channel_id)),
+        Event::QueueEmptied { channel_id, .. } => {
+            Some(format!("Nothing left to play in <#{}>", channel_id))
+        }
+        Event::QueueChanged { .. } | Event::VoteProgress { .. } => None,
+    }
+}
+
+// reqwest puts the URL in its errors, and a webhook's URL can have a token in it. The locked
+// reqwest is older than `Error::without_url`.
+fn without_url(why: &reqwest::Error) -> String {
+    let message = why.to_string();
+    match why.url() {
+        Some(url) => message.replace(&format!(" for url ({})", url.as_str()), ""),
+        None => message,
+    }
+}
+
+/// Sends events to the configured outgoing webhooks.
+#[derive(Default)]
+pub struct HookRegistry {
+    hooks: Vec<Hook>,
+}
+
+impl HookRegistry {
+    pub fn from_config(configs: &[WebhookConfig]) -> Self {
+        let mut registry = HookRegistry::default();
+        for config in configs {
+            registry.register(config.clone());
+        }
+        registry
+    }
+
+    pub fn register(&mut self, config: WebhookConfig) {
+        self.hooks.push(Hook {
+            index: self.hooks.len(),
+            config,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    pub async fn run(self, mut receiver: broadcast::Receiver<Event>) {
+        let client = match reqwest::Client::builder().timeout(SEND_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(why) => {
+                log::error!("Unable to create webhook client: {}", why);
+                return;
+            }
+        };
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Webhooks fell behind, skipped {} events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+
+            let sends = self
+                .hooks
+                .iter()
+                .filter(|hook| hook.matches(&event))
+                .map(|hook| {
+                    hook.send(&client, &event).map(move |res| {
+                        if let Err(why) = res {
+                            log::error!(
+                                "Error while sending webhook {}: {}",
+                                hook.index,
+                                without_url(&why)
+                            );
+                        }
+                    })
+                });
+            future::join_all(sends).await;
+        }
+    }
+}
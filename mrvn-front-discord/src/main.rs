@@ -2,16 +2,55 @@ use futures::prelude::*;
 use mrvn_back_ytdl::SpeakerInit;
 use serenity::{model::prelude::*, prelude::*};
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal;
 
+mod audit;
+mod bans;
+mod blocklist;
 mod cleanup_loop;
+mod cluster;
+mod command_args;
 mod command_handler;
+mod command_usage;
 mod commands;
 mod config;
+mod cooldowns;
+mod entitlements;
 mod error;
+mod event_bus;
+mod extraction_failures;
+mod extractor_update;
+mod favorites;
 mod frontend;
+mod handover;
+mod hooks;
 mod message;
 mod model_delegate;
+mod now_playing_card;
+mod persistence;
+mod pets;
+mod playlists;
+mod plugin;
+mod positions;
+mod prefs;
+mod presence;
+mod queue_store;
+mod recovery;
+mod schedules;
+mod scripting;
+mod settings;
+mod soundboard;
+mod speaker;
+mod stats;
+mod streaks;
+mod template;
+mod track_log;
+mod trivia;
 mod voice_handler;
+mod voice_status;
+mod web;
+mod welcome;
 
 #[tokio::main]
 async fn main() {
@@ -32,16 +71,118 @@ async fn main() {
     let config_file = std::fs::File::open(config_file_path).expect("Unable to open config file");
     let config: Arc<config::Config> =
         Arc::new(serde_json::from_reader(config_file).expect("Unable to read config file"));
+    if let Err(why) = config.check_messages() {
+        eprintln!("Invalid config: {}", why);
+        std::process::exit(1);
+    }
+
+    let cluster_config = config.cluster.clone().map(|cluster_config| {
+        let shard_counts = std::iter::once(config.command_bot.shard_count).chain(
+            config
+                .voice_bots
+                .iter()
+                .map(|bot_config| bot_config.shard_count),
+        );
+        for shard_count in shard_counts {
+            // Every process has to agree on which shard a guild is in.
+            let shard_count =
+                shard_count.expect("shard_count must be set for each bot in a cluster");
+            assert!(
+                shard_count >= cluster_config.node_count,
+                "Each bot needs at least as many shards as there are processes in the cluster"
+            );
+        }
+
+        log::info!(
+            "Running as {} ({} of {} in the cluster)",
+            cluster_config.node_id,
+            cluster_config.node_index + 1,
+            cluster_config.node_count
+        );
+        cluster_config
+    });
+    let cluster = match cluster_config {
+        Some(cluster_config) => Some(Arc::new(
+            cluster::Cluster::connect(cluster_config)
+                .await
+                .expect("Unable to connect to Redis"),
+        )),
+        None => None,
+    };
+
+    let persistence: Arc<dyn persistence::Persistence> = match (&cluster, &config.data_directory) {
+        (Some(cluster), _) => {
+            log::info!("Saving data to Redis");
+            Arc::new(persistence::RedisPersistence::new(cluster.redis()))
+        }
+        (None, Some(directory)) => {
+            log::info!("Saving data to {}", directory);
+            Arc::new(
+                persistence::FilePersistence::new(directory)
+                    .expect("Unable to create data directory"),
+            )
+        }
+        (None, None) => {
+            log::warn!("No data directory is configured, data will be lost on restart");
+            Arc::new(persistence::MemoryPersistence)
+        }
+    };
+
+    // Leases already let another process in a cluster pick up where one left off.
+    let handover = config.handover_socket.clone().map(|socket_path| {
+        assert!(
+            cluster.is_none(),
+            "handover_socket can't be used when running as a cluster"
+        );
+        Arc::new(handover::Handover::new(
+            socket_path,
+            config.voice_bots.len(),
+            persistence.clone(),
+        ))
+    });
+
+    // A handover carries on what was playing by itself, this is for when the process stopped
+    // without one.
+    let recovery = config.recover_playback.then(|| {
+        assert!(
+            cluster.is_none(),
+            "recover_playback can't be used when running as a cluster"
+        );
+        Arc::new(recovery::PlaybackRecovery::load(
+            persistence.clone(),
+            config.voice_bots.len(),
+        ))
+    });
 
     let mut backend_brain = mrvn_back_ytdl::Brain::new();
-    let model = mrvn_model::AppModel::new(mrvn_model::AppModelConfig {
+    if let Some(cluster) = &cluster {
+        let cluster = cluster.clone();
+        backend_brain.set_speaker_filter(move |guild_id, speaker_index| {
+            let cluster = cluster.clone();
+            Box::pin(async move {
+                match cluster.acquire_lease(guild_id, speaker_index).await {
+                    Ok(is_acquired) => is_acquired,
+                    Err(why) => {
+                        log::error!("Error while leasing speaker {}: {}", speaker_index, why);
+                        false
+                    }
+                }
+            })
+        });
+    }
+
+    let mut model = mrvn_model::AppModel::new(mrvn_model::AppModelConfig {
         skip_votes_required: config.skip_votes_required,
         stop_votes_required: config.stop_votes_required,
-
-        secret_highfive_timezone: match &config.secret_highfive {
-            Some(secret) => secret.timezone.parse().expect("Unable to parse timezone"),
-            None => chrono_tz::Etc::UTC,
-        },
+        vote_expiry: config.vote_expiry_secs.map(Duration::from_secs),
+        undo_window: config.undo_window(),
+        role_priorities: config
+            .role_priorities
+            .iter()
+            .map(|(role_id, priority)| (RoleId(*role_id), *priority))
+            .collect(),
+        max_user_entries: config.max_user_queue_length,
+        max_guild_entries: config.max_guild_queue_length,
     });
 
     log::info!("Starting {} voice clients", config.voice_bots.len());
@@ -51,6 +192,8 @@ async fn main() {
                 .application_id(bot_config.application_id)
                 .event_handler(voice_handler::VoiceHandler {
                     client_index: index,
+                    handover: handover.clone(),
+                    recovery: recovery.clone(),
                 })
                 .register_speaker(&mut backend_brain)
         },
@@ -58,10 +201,110 @@ async fn main() {
     .await
     .expect("Unable to create voice client");
 
+    if config.voice_channel_status {
+        tokio::spawn(
+            voice_status::VoiceStatus::new(config.clone())
+                .run(backend_brain.event_stream("Voice status")),
+        );
+    }
+
+    let scripts = scripting::ScriptHost::load(&config.scripts).expect("Unable to load scripts");
+
+    let stats = stats::Stats::new(
+        persistence.clone(),
+        stats::ResetSchedule {
+            timezone: config
+                .leaderboard
+                .timezone
+                .parse()
+                .expect("Unable to parse leaderboard timezone"),
+            week_start: config
+                .leaderboard
+                .week_start
+                .parse()
+                .expect("Unable to parse leaderboard week start"),
+        },
+    );
+
+    let settings = Arc::new(settings::Settings::new(persistence.clone()));
+    let queue_store = config
+        .persist_queues
+        .then(|| Arc::new(queue_store::QueueStore::new(persistence.clone())));
+    {
+        let settings = settings.clone();
+        let queue_store = queue_store.clone();
+        model.set_guild_initializer(move |guild_id, guild_model| {
+            settings.get(guild_id).apply(guild_model);
+            if let Some(queue_store) = &queue_store {
+                queue_store.restore(guild_id, guild_model);
+            }
+        });
+    }
+
+    // This has to happen before the clients connect, so the old process stops handling commands
+    // before this one starts.
+    if let Some(handover) = &handover {
+        if let Err(why) = handover.receive(&model).await {
+            log::error!("Error while taking over from the running process: {}", why);
+        }
+    }
+
+    {
+        let settings = settings.clone();
+        backend_brain.set_channel_filter(move |guild_id, channel_id| {
+            settings.get(guild_id).is_voice_channel_allowed(channel_id)
+        });
+    }
+
+    let prefs = prefs::Prefs::load(persistence.clone()).expect("Unable to load user preferences");
+    let playlists =
+        playlists::Playlists::load(persistence.clone()).expect("Unable to load playlists");
+    let favorites =
+        favorites::Favorites::load(persistence.clone()).expect("Unable to load favorites");
+    let pets = pets::Pets::load(persistence.clone()).expect("Unable to load pets");
+    let streaks = streaks::Streaks::new(
+        persistence.clone(),
+        match &config.secret_highfive {
+            Some(secret) => secret.timezone.parse().expect("Unable to parse timezone"),
+            None => chrono_tz::Etc::UTC,
+        },
+    );
+
+    // Schedules run in the same timezone as the leaderboard.
+    let schedules = schedules::Schedules::new(
+        persistence.clone(),
+        config
+            .leaderboard
+            .timezone
+            .parse()
+            .expect("Unable to parse leaderboard timezone"),
+    );
+
+    // Commands added by forks can be registered here, see plugin::CommandPlugin.
+    let plugins = plugin::PluginRegistry::default();
+
     let frontend = Arc::new(crate::frontend::Frontend::new(
         config.clone(),
         backend_brain,
         model,
+        scripts,
+        plugins,
+        stats,
+        prefs,
+        playlists,
+        playlists::GuildPlaylists::new(persistence.clone()),
+        favorites,
+        pets,
+        streaks,
+        schedules,
+        settings,
+        blocklist::Blocklist::new(persistence.clone()),
+        bans::MusicBans::new(persistence.clone()),
+        soundboard::Soundboard::new(persistence.clone()),
+        positions::SavedPositions::new(persistence.clone()),
+        welcome::Welcomes::load(persistence.clone()).expect("Unable to load welcomed guilds"),
+        handover.clone(),
+        recovery.clone(),
     ));
     let mut command_client = Client::builder(&config.command_bot.token)
         .application_id(config.command_bot.application_id)
@@ -71,20 +314,171 @@ async fn main() {
     commands::register_commands(
         &command_client.cache_and_http.http,
         config.command_bot.guild_id.map(GuildId),
-        &config,
+        &frontend,
     )
     .await
     .expect("Unable to register commands");
     log::info!("Finished registering application commands");
 
+    if !config.scripts.is_empty() {
+        tokio::spawn(
+            frontend
+                .clone()
+                .run_event_scripts(command_client.cache_and_http.http.clone()),
+        );
+    }
+
+    let hook_registry = hooks::HookRegistry::from_config(&config.webhooks);
+    if !hook_registry.is_empty() {
+        log::info!("Sending events to {} webhooks", config.webhooks.len());
+        tokio::spawn(hook_registry.run(frontend.events.subscribe()));
+    }
+
+    {
+        let persistence = persistence.clone();
+        tokio::spawn(async move {
+            wait_for_shutdown().await;
+            log::info!("Shutting down");
+            tokio::task::block_in_place(|| persistence.flush());
+            std::process::exit(0);
+        });
+    }
+
+    if config.track_log.is_some() {
+        let track_log = track_log::TrackLog::new(
+            frontend.clone(),
+            command_client.cache_and_http.http.clone(),
+            persistence,
+        );
+        tokio::spawn(track_log.run(frontend.events.subscribe()));
+    }
+
+    if let Some(check_interval) = frontend
+        .extractor_updater
+        .as_ref()
+        .and_then(|updater| updater.check_interval())
+    {
+        let frontend = frontend.clone();
+        tokio::spawn(async move {
+            if let Some(updater) = &frontend.extractor_updater {
+                updater.run(check_interval).await;
+            }
+        });
+    }
+
+    if let Some(library_config) = &config.library {
+        let frontend = frontend.clone();
+        let rescan_interval = library_config.rescan_interval_secs.map(Duration::from_secs);
+        tokio::spawn(async move {
+            if let Some(library) = &frontend.library {
+                library.run(rescan_interval).await;
+            }
+        });
+    }
+
+    if let Some(queue_store) = queue_store {
+        let frontend = frontend.clone();
+        tokio::spawn(async move { queue_store.run(&frontend.model).await });
+    }
+
+    if let Some(cluster) = cluster.clone() {
+        let frontend = frontend.clone();
+        tokio::spawn(async move {
+            cluster
+                .run_lease_renewal(&frontend.backend_brain.speakers)
+                .await
+        });
+    }
+
+    if let Some(recovery) = recovery {
+        let frontend = frontend.clone();
+        tokio::spawn(async move { recovery.run(&frontend).await });
+    }
+
+    if let Some(handover) = handover {
+        let frontend = frontend.clone();
+        tokio::spawn(async move { handover.listen(&frontend).await });
+    }
+
+    if config.show_presence {
+        let presence_manager =
+            presence::PresenceManager::new(config.clone(), command_client.shard_manager.clone());
+        tokio::spawn(presence_manager.run(frontend.backend_brain.event_stream("Presence")));
+    }
+
+    let web_future = {
+        let frontend = frontend.clone();
+        let cache = command_client.cache_and_http.cache.clone();
+        async move {
+            if let Some(web_config) = &frontend.config.web {
+                let bind_address = web_config
+                    .bind_address
+                    .parse()
+                    .expect("Unable to parse web bind address");
+                let internal_bind_address =
+                    web_config.internal_bind_address.as_ref().map(|address| {
+                        address
+                            .parse()
+                            .expect("Unable to parse internal web bind address")
+                    });
+                let serve_res =
+                    web::serve(frontend.clone(), cache, bind_address, internal_bind_address).await;
+                if let Err(why) = serve_res {
+                    log::error!("Error while serving web endpoints: {}", why);
+                }
+            }
+            Ok(())
+        }
+    };
+
     let cleanup_loop_future =
         cleanup_loop::cleanup_loop(frontend, command_client.cache_and_http.cache.clone())
             .map(|_| Ok(()));
 
     futures::try_join!(
-        command_client.start(),
-        future::try_join_all(voice_clients.iter_mut().map(|client| client.start())),
+        start_client(
+            &mut command_client,
+            config.command_bot.shard_count,
+            cluster.as_deref()
+        ),
+        future::try_join_all(voice_clients.iter_mut().zip(&config.voice_bots).map(
+            |(client, bot_config)| start_client(client, bot_config.shard_count, cluster.as_deref())
+        )),
         cleanup_loop_future,
+        web_future,
     )
     .expect("Error while running client");
 }
+
+// Resolves once the process is asked to stop, with Ctrl-C or SIGTERM.
+async fn wait_for_shutdown() {
+    let mut terminate = signal::unix::signal(signal::unix::SignalKind::terminate())
+        .expect("Unable to listen for SIGTERM");
+    tokio::select! {
+        _ = signal::ctrl_c() => {}
+        _ = terminate.recv() => {}
+    }
+}
+
+/// Bots in more than 2,500 servers have to be split into shards, which Discord can pick the number
+/// of. In a cluster, each process only connects its own share of them.
+async fn start_client(
+    client: &mut Client,
+    shard_count: Option<u64>,
+    cluster: Option<&cluster::Cluster>,
+) -> serenity::Result<()> {
+    match (shard_count, cluster) {
+        (Some(shard_count), Some(cluster)) => {
+            let shard_range = cluster.shard_range(shard_count);
+            log::info!(
+                "Starting shards {} to {} of {}",
+                shard_range[0],
+                shard_range[1],
+                shard_count
+            );
+            client.start_shard_range(shard_range, shard_count).await
+        }
+        (Some(shard_count), None) => client.start_shards(shard_count).await,
+        (None, _) => client.start_autosharded().await,
+    }
+}
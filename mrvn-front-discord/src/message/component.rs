@@ -0,0 +1,171 @@
+use crate::commands::CommandPermission;
+use crate::message::PagedList;
+use crate::stats::LeaderboardPeriod;
+use serenity::model::prelude::*;
+
+// Separates the parts of a custom ID.
+const SEPARATOR: char = ':';
+
+/// What a button or select menu is for, which is how interactions with it are routed. The
+/// custom ID starts with a namespace for the kind of component, followed by any state it needs,
+/// so components keep working after a restart without anything being stored for them. Discord
+/// limits custom IDs to 100 characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ComponentId {
+    Vote(VoteButton),
+    /// Adds the song playing in a voice channel to the favorites of whoever pressed it.
+    Favorite {
+        voice_channel_id: ChannelId,
+    },
+    /// Turns a paged list to another page.
+    Page {
+        list: PagedList,
+        number: usize,
+    },
+}
+
+impl ComponentId {
+    pub fn custom_id(&self) -> String {
+        match self {
+            ComponentId::Vote(VoteButton::Skip {
+                voice_channel_id,
+                song_id,
+            }) => join(&["vote_skip", &voice_channel_id.0.to_string(), song_id]),
+            ComponentId::Vote(VoteButton::Stop { voice_channel_id }) => {
+                join(&["vote_stop", &voice_channel_id.0.to_string()])
+            }
+            ComponentId::Favorite { voice_channel_id } => {
+                join(&["fav", &voice_channel_id.0.to_string()])
+            }
+            ComponentId::Page { list, number } => {
+                let number_string = number.to_string();
+                match list {
+                    PagedList::UserQueue { user_id } => {
+                        join(&["page", "queue", &user_id.0.to_string(), &number_string])
+                    }
+                    PagedList::Playlists { user_id } => {
+                        join(&["page", "playlists", &user_id.0.to_string(), &number_string])
+                    }
+                    PagedList::GuildPlaylists => join(&["page", "guild_playlists", &number_string]),
+                    PagedList::Favorites { user_id } => {
+                        join(&["page", "favorites", &user_id.0.to_string(), &number_string])
+                    }
+                    PagedList::Leaderboard { period } => {
+                        join(&["page", "leaderboard", period.name(), &number_string])
+                    }
+                    PagedList::Help { max_permission } => {
+                        join(&["page", "help", max_permission.name(), &number_string])
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn parse(custom_id: &str) -> Option<Self> {
+        let mut parts = Parts {
+            remaining: Some(custom_id),
+        };
+        let component_id = match parts.next()? {
+            "vote_skip" => ComponentId::Vote(VoteButton::Skip {
+                voice_channel_id: parts.next_id()?,
+                // Song IDs can have the separator in them, so this is the rest of the ID.
+                song_id: parts.rest()?.to_string(),
+            }),
+            "vote_stop" => ComponentId::Vote(VoteButton::Stop {
+                voice_channel_id: parts.next_id()?,
+            }),
+            "fav" => ComponentId::Favorite {
+                voice_channel_id: parts.next_id()?,
+            },
+            "page" => {
+                let list = match parts.next()? {
+                    "queue" => PagedList::UserQueue {
+                        user_id: parts.next_id()?,
+                    },
+                    "playlists" => PagedList::Playlists {
+                        user_id: parts.next_id()?,
+                    },
+                    "guild_playlists" => PagedList::GuildPlaylists,
+                    "favorites" => PagedList::Favorites {
+                        user_id: parts.next_id()?,
+                    },
+                    "leaderboard" => PagedList::Leaderboard {
+                        period: LeaderboardPeriod::from_name(parts.next()?)?,
+                    },
+                    "help" => PagedList::Help {
+                        max_permission: CommandPermission::from_name(parts.next()?)?,
+                    },
+                    _ => return None,
+                };
+                ComponentId::Page {
+                    list,
+                    number: parts.next()?.parse().ok()?,
+                }
+            }
+            _ => return None,
+        };
+
+        // Anything left over means the ID isn't one of ours after all.
+        match parts.rest() {
+            Some(_) => None,
+            None => Some(component_id),
+        }
+    }
+}
+
+fn join(parts: &[&str]) -> String {
+    parts.join(&SEPARATOR.to_string())
+}
+
+// Reads a custom ID's parts in order.
+struct Parts<'a> {
+    remaining: Option<&'a str>,
+}
+
+impl<'a> Parts<'a> {
+    fn next(&mut self) -> Option<&'a str> {
+        let remaining = self.remaining?;
+        match remaining.split_once(SEPARATOR) {
+            Some((part, rest)) => {
+                self.remaining = Some(rest);
+                Some(part)
+            }
+            None => {
+                self.remaining = None;
+                Some(remaining)
+            }
+        }
+    }
+
+    fn next_id<Id: From<u64>>(&mut self) -> Option<Id> {
+        self.next()?.parse().ok().map(Id::from)
+    }
+
+    fn rest(&mut self) -> Option<&'a str> {
+        self.remaining.take()
+    }
+}
+
+/// A button that adds the user's vote to a skip or stop vote in a voice channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VoteButton {
+    Skip {
+        voice_channel_id: ChannelId,
+        // Skip votes are for a specific song, so the button stops working once it's finished.
+        song_id: String,
+    },
+    Stop {
+        voice_channel_id: ChannelId,
+    },
+}
+
+impl VoteButton {
+    pub fn voice_channel_id(&self) -> ChannelId {
+        match self {
+            VoteButton::Skip {
+                voice_channel_id, ..
+            }
+            | VoteButton::Stop { voice_channel_id } => *voice_channel_id,
+        }
+    }
+}
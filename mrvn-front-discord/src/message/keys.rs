@@ -0,0 +1,529 @@
+/// Every message key the bot looks up, with the placeholders it fills in for it. Achievement
+/// names and descriptions are looked up by the achievement's name, so they're left out.
+pub const MESSAGE_KEYS: &[(&str, &[&str])] = &[
+    ("action.broadcast", &["message"]),
+    ("action.finished", &["voice_channel_id"]),
+    ("action.maintenance_notice", &[]),
+    ("action.no_speakers_error", &["voice_channel_id"]),
+    (
+        "action.playing",
+        &["song_title", "song_url", "voice_channel_id", "user_id"],
+    ),
+    (
+        "action.playing_no_mention",
+        &["song_title", "song_url", "voice_channel_id"],
+    ),
+    (
+        "action.playing_response",
+        &["song_title", "song_url", "voice_channel_id"],
+    ),
+    (
+        "action.resumed_after_restart",
+        &["song_title", "song_url", "voice_channel_id"],
+    ),
+    ("action.unknown_error", &[]),
+    ("action.welcome", &[]),
+    ("button.favorite", &[]),
+    ("button.join_party", &[]),
+    ("button.next_page", &[]),
+    ("button.previous_page", &[]),
+    ("button.vote", &[]),
+    (
+        "dm.playing",
+        &["song_title", "song_url", "voice_channel_id"],
+    ),
+    ("presence.listening", &["song_title"]),
+    ("presence.playing_in.plural", &["count"]),
+    ("presence.playing_in.singular", &[]),
+    (
+        "response.achievement_unlocked",
+        &["user_id", "name", "description"],
+    ),
+    ("response.achievements", &["user_id", "count", "total"]),
+    ("response.achievements.locked", &["name", "description"]),
+    ("response.achievements.unlocked", &["name", "description"]),
+    ("response.age_restricted_error", &[]),
+    ("response.already_playing_error", &["voice_channel_id"]),
+    ("response.blocked", &["rule"]),
+    ("response.blocklist", &[]),
+    ("response.blocklist.added", &["rule"]),
+    ("response.blocklist.already_added_error", &["rule"]),
+    ("response.blocklist.domain", &["value"]),
+    ("response.blocklist.empty", &[]),
+    ("response.blocklist.invalid_rule_error", &[]),
+    ("response.blocklist.keyword", &["value"]),
+    ("response.blocklist.not_found_error", &["rule"]),
+    ("response.blocklist.removed", &["rule"]),
+    ("response.blocklist.video", &["value"]),
+    ("response.broadcast_sent", &["guild_count"]),
+    ("response.channel_not_allowed_error", &["voice_channel_id"]),
+    ("response.clip_no_speakers_error", &["voice_channel_id"]),
+    ("response.clip_played", &["voice_channel_id"]),
+    ("response.cooldown_error", &["retry_after_secs"]),
+    ("response.debug_status", &["maintenance", "playing_count"]),
+    ("response.deduped", &["voice_channel_id", "count"]),
+    ("response.disconnected", &["voice_channel_id"]),
+    ("response.dropped_absent_entries", &["count", "users"]),
+    ("response.extraction_failures", &["count"]),
+    ("response.extraction_failures.empty", &[]),
+    (
+        "response.extraction_failures.entry",
+        &["time", "guild_id", "cause", "exit_code", "stderr"],
+    ),
+    ("response.extraction_failures.no_exit_code", &[]),
+    ("response.extractor_outdated_error", &[]),
+    ("response.extractor_up_to_date", &["version"]),
+    (
+        "response.extractor_updated",
+        &["old_version", "new_version"],
+    ),
+    ("response.extractor_updated.unknown_version", &[]),
+    ("response.extractor_version", &["version"]),
+    ("response.extractor_version.unknown", &[]),
+    ("response.favorites", &[]),
+    ("response.favorites.added", &["song_title", "song_url"]),
+    ("response.favorites.already_added_error", &["song_title"]),
+    ("response.favorites.empty", &[]),
+    (
+        "response.favorites.line",
+        &["position", "song_title", "song_url"],
+    ),
+    ("response.favorites.none_error", &[]),
+    ("response.favorites.position_error", &["position"]),
+    ("response.favorites.removed", &["song_title"]),
+    ("response.geo_blocked_error", &[]),
+    ("response.guild_playlist.added", &["name", "count"]),
+    ("response.guild_playlist.contributors", &[]),
+    ("response.guild_playlist.everyone", &[]),
+    ("response.guild_playlist.exists_error", &["name"]),
+    ("response.guild_playlist.list", &[]),
+    ("response.guild_playlist.list_empty", &[]),
+    ("response.guild_playlist.log", &["name"]),
+    (
+        "response.guild_playlist.log.added",
+        &["user_id", "time", "song_title"],
+    ),
+    (
+        "response.guild_playlist.log.added_multiple",
+        &["user_id", "time", "count"],
+    ),
+    (
+        "response.guild_playlist.log.created",
+        &["user_id", "time", "count"],
+    ),
+    (
+        "response.guild_playlist.log.moved",
+        &["user_id", "time", "song_title", "position"],
+    ),
+    (
+        "response.guild_playlist.log.removed",
+        &["user_id", "time", "song_title"],
+    ),
+    ("response.guild_playlist.log_empty", &["name"]),
+    ("response.guild_playlist.managers", &[]),
+    (
+        "response.guild_playlist.moved",
+        &["name", "song_title", "position"],
+    ),
+    ("response.guild_playlist.not_found_error", &["name"]),
+    (
+        "response.guild_playlist.permission_set",
+        &["name", "action", "who"],
+    ),
+    ("response.guild_playlist.position_error", &["name", "count"]),
+    ("response.guild_playlist.removed", &["name", "song_title"]),
+    (
+        "response.guild_queue_limit_reached",
+        &["queued", "rejected", "max"],
+    ),
+    ("response.help", &[]),
+    (
+        "response.help.line",
+        &["usage", "description", "permission"],
+    ),
+    ("response.help.manage_server", &[]),
+    ("response.help.message_command", &["name", "permission"]),
+    ("response.help.owner", &[]),
+    ("response.help.user_command", &["name", "permission"]),
+    ("response.history.export", &[]),
+    ("response.history.exported", &["count"]),
+    ("response.history.none_error", &[]),
+    ("response.invalid_trim_error", &[]),
+    ("response.leaderboard.all_time", &[]),
+    (
+        "response.leaderboard.line",
+        &["position", "user_id", "plays"],
+    ),
+    ("response.leaderboard.month", &[]),
+    ("response.leaderboard.week", &[]),
+    ("response.library_rescanned", &["count"]),
+    ("response.live_not_supported_error", &[]),
+    ("response.maintenance.error", &[]),
+    ("response.maintenance.off", &[]),
+    ("response.maintenance.on", &[]),
+    ("response.maintenance.stopped", &["voice_channel_id"]),
+    ("response.message_channel_moved", &["channel_id"]),
+    ("response.missing_owner_error", &[]),
+    ("response.missing_permission_error", &[]),
+    ("response.musicban.already_banned_error", &["user_id"]),
+    ("response.musicban.banned", &["user_id"]),
+    ("response.musicban.not_banned_error", &["user_id"]),
+    ("response.musicban.refused", &[]),
+    ("response.musicban.unbanned", &["user_id"]),
+    ("response.network_timeout_error", &[]),
+    ("response.no_link_error", &[]),
+    ("response.no_matching_songs_error", &[]),
+    ("response.no_stats", &[]),
+    ("response.no_streak", &[]),
+    ("response.not_connected_error", &["voice_channel_id"]),
+    ("response.not_entitled_error", &["command"]),
+    ("response.not_in_voice_channel_error", &[]),
+    ("response.nothing_is_playing_error", &["voice_channel_id"]),
+    ("response.nothing_is_queued_error", &["voice_channel_id"]),
+    ("response.nothing_to_undo_error", &[]),
+    (
+        "response.now_playing",
+        &["song_title", "song_url", "voice_channel_id", "user_id"],
+    ),
+    (
+        "response.now_playing.paused",
+        &["song_title", "song_url", "voice_channel_id", "user_id"],
+    ),
+    ("response.page", &["page", "page_count"]),
+    (
+        "response.party.already_started_error",
+        &["host_user_id", "voice_channel_id"],
+    ),
+    ("response.party.ended", &["voice_channel_id"]),
+    (
+        "response.party.host_left",
+        &["host_user_id", "voice_channel_id"],
+    ),
+    ("response.party.no_party_error", &["voice_channel_id"]),
+    (
+        "response.party.started",
+        &["host_user_id", "voice_channel_id"],
+    ),
+    ("response.party.started_title", &[]),
+    (
+        "response.paused",
+        &["song_title", "song_url", "voice_channel_id", "user_id"],
+    ),
+    ("response.pet.found", &["name"]),
+    ("response.pet.found_new", &["name"]),
+    ("response.pet.found_shiny", &["name"]),
+    ("response.pet.none_error", &[]),
+    ("response.pets", &["found", "total"]),
+    ("response.pets.empty", &[]),
+    ("response.pets.line", &["name", "count"]),
+    ("response.pets.shiny", &["name", "count"]),
+    ("response.play_many_failed", &[]),
+    ("response.play_many_failed.term", &["position", "term"]),
+    (
+        "response.playback_failed_error",
+        &["song_title", "song_url", "voice_channel_id", "user_id"],
+    ),
+    ("response.playlist.deleted", &["name"]),
+    ("response.playlist.imported", &["name", "count"]),
+    ("response.playlist.imported.failure", &["failure"]),
+    ("response.playlist.imported.failures", &["count"]),
+    ("response.playlist.imported.more_failures", &["count"]),
+    ("response.playlist.line", &["name", "count"]),
+    ("response.playlist.list", &[]),
+    ("response.playlist.list_empty", &[]),
+    ("response.playlist.not_found_error", &["name"]),
+    ("response.playlist.saved", &["name", "count"]),
+    (
+        "response.prefs",
+        &["dm_notifications", "mention", "language"],
+    ),
+    ("response.prefs.default_language", &[]),
+    ("response.prefs.disabled", &[]),
+    ("response.prefs.enabled", &[]),
+    ("response.prefs.unknown_language", &["language"]),
+    ("response.private_video_error", &[]),
+    ("response.queue", &[]),
+    ("response.queue.channel", &["voice_channel_id", "count"]),
+    ("response.queue.channel_empty", &["voice_channel_id"]),
+    (
+        "response.queue.channel_line",
+        &["position", "song_title", "song_url", "user_id"],
+    ),
+    ("response.queue.cleared", &["count"]),
+    ("response.queue.empty", &[]),
+    ("response.queue.empty_error", &[]),
+    (
+        "response.queue.line",
+        &["position", "song_title", "song_url"],
+    ),
+    ("response.queue.position_error", &["position"]),
+    ("response.queue.removed", &["song_title", "song_url"]),
+    ("response.queue.shuffled", &["count"]),
+    ("response.queued", &["song_title", "song_url"]),
+    (
+        "response.queued.eta",
+        &["song_title", "song_url", "position", "wait"],
+    ),
+    (
+        "response.queued.position",
+        &["song_title", "song_url", "position"],
+    ),
+    ("response.queued_multiple", &["count"]),
+    ("response.queued_multiple_no_speakers", &["count"]),
+    ("response.queued_no_speakers", &["song_title", "song_url"]),
+    ("response.remove.range", &["voice_channel_id", "count"]),
+    (
+        "response.remove.range_error",
+        &["voice_channel_id", "from", "to"],
+    ),
+    ("response.remove.user", &["user_id", "count"]),
+    (
+        "response.replace_skipped",
+        &[
+            "new_song_title",
+            "new_song_url",
+            "old_song_title",
+            "old_song_url",
+            "voice_channel_id",
+        ],
+    ),
+    (
+        "response.replaced",
+        &[
+            "old_song_title",
+            "old_song_url",
+            "new_song_title",
+            "new_song_url",
+        ],
+    ),
+    (
+        "response.resume_available",
+        &["song_title", "song_url", "position"],
+    ),
+    (
+        "response.schedule.added",
+        &["id", "voice_channel_id", "next_run"],
+    ),
+    (
+        "response.schedule.channel_in_use_error",
+        &["id", "voice_channel_id"],
+    ),
+    ("response.schedule.invalid_cron_error", &[]),
+    ("response.schedule.no_songs_error", &["id"]),
+    (
+        "response.schedule.no_speakers_error",
+        &["id", "voice_channel_id"],
+    ),
+    ("response.schedule.not_found_error", &["id"]),
+    ("response.schedule.removed", &["id"]),
+    ("response.schedule.started", &["id", "voice_channel_id"]),
+    ("response.schedule.too_many_error", &[]),
+    ("response.schedules", &[]),
+    ("response.schedules.empty", &[]),
+    (
+        "response.schedules.line",
+        &[
+            "id",
+            "cron",
+            "voice_channel_id",
+            "user_id",
+            "source",
+            "next_run",
+        ],
+    ),
+    ("response.schedules.never", &[]),
+    ("response.schedules.playlist", &["name"]),
+    ("response.schedules.url", &["url"]),
+    ("response.script_finished", &[]),
+    ("response.searching", &["query"]),
+    (
+        "response.settings",
+        &[
+            "queue_strategy",
+            "drop_absent",
+            "command_channels",
+            "blocked_voice_channels",
+            "follow_requester",
+            "announce_songs",
+            "message_channel",
+            "notify_message_channel_moves",
+            "session_threads",
+            "now_playing_cards",
+            "audit_channel",
+            "playlist_role",
+            "music_mode",
+            "gapless",
+        ],
+    ),
+    ("response.settings.any_channel", &[]),
+    ("response.settings.disabled", &[]),
+    ("response.settings.enabled", &[]),
+    ("response.settings.fifo", &[]),
+    ("response.settings.latest_channel", &[]),
+    ("response.settings.no_channels", &[]),
+    ("response.settings.no_role", &[]),
+    ("response.settings.round_robin", &[]),
+    ("response.sfx.added", &["name"]),
+    ("response.sfx.invalid_url_error", &[]),
+    ("response.sfx.list", &["names"]),
+    ("response.sfx.list_empty", &[]),
+    ("response.sfx.not_found_error", &["name"]),
+    ("response.sfx.removed", &["name"]),
+    (
+        "response.skip_already_voted_error",
+        &["song_title", "song_url", "voice_channel_id"],
+    ),
+    (
+        "response.skip_more_votes_needed.plural",
+        &["song_title", "song_url", "voice_channel_id", "count"],
+    ),
+    (
+        "response.skip_more_votes_needed.singular",
+        &["song_title", "song_url", "voice_channel_id"],
+    ),
+    (
+        "response.skipped",
+        &["song_title", "song_url", "voice_channel_id", "user_id"],
+    ),
+    ("response.song_info", &["song_title", "song_url", "user_id"]),
+    ("response.song_info.duration", &[]),
+    (
+        "response.song_info.position_error",
+        &["voice_channel_id", "position"],
+    ),
+    ("response.song_info.queued_at", &[]),
+    ("response.song_info.site", &[]),
+    ("response.song_info.uploaded", &[]),
+    ("response.song_info.uploader", &[]),
+    ("response.song_info.views", &[]),
+    ("response.speaker_muted_error", &["voice_channel_id"]),
+    (
+        "response.stats.guild",
+        &["songs_played", "songs_failed", "listening_time"],
+    ),
+    (
+        "response.stats.user",
+        &["user_id", "songs_played", "listening_time"],
+    ),
+    ("response.stop_already_voted_error", &["voice_channel_id"]),
+    (
+        "response.stop_more_votes_needed.plural",
+        &["voice_channel_id", "count"],
+    ),
+    (
+        "response.stop_more_votes_needed.singular",
+        &["voice_channel_id"],
+    ),
+    (
+        "response.stopped",
+        &["song_title", "song_url", "voice_channel_id", "user_id"],
+    ),
+    ("response.streak", &["streak_length"]),
+    ("response.streak_leaderboard", &[]),
+    ("response.streak_leaderboard.empty", &[]),
+    (
+        "response.streak_leaderboard.line",
+        &["position", "user_id", "streak_length"],
+    ),
+    ("response.streak_wait", &[]),
+    ("response.summon_no_speakers_error", &["voice_channel_id"]),
+    ("response.summoned", &["voice_channel_id"]),
+    ("response.synced.global", &[]),
+    ("response.synced.guild", &[]),
+    ("response.top.artist_line", &["position", "artist", "plays"]),
+    ("response.top.artists_title", &[]),
+    ("response.top.guild", &[]),
+    (
+        "response.top.track_line",
+        &["position", "song_title", "song_url", "plays"],
+    ),
+    ("response.top.tracks_title", &[]),
+    ("response.top.user", &["user_id"]),
+    (
+        "response.transfer_same_channel_error",
+        &["voice_channel_id"],
+    ),
+    (
+        "response.transferred",
+        &[
+            "song_title",
+            "song_url",
+            "from_voice_channel_id",
+            "to_voice_channel_id",
+        ],
+    ),
+    ("response.trivia.already_running_error", &[]),
+    ("response.trivia.finished", &[]),
+    ("response.trivia.finished_no_scores", &[]),
+    ("response.trivia.music_playing_error", &["voice_channel_id"]),
+    ("response.trivia.no_songs_error", &[]),
+    ("response.trivia.not_running_error", &[]),
+    ("response.trivia.round", &["round_number", "rounds"]),
+    ("response.trivia.round_missed", &["song_title", "song_url"]),
+    (
+        "response.trivia.round_won",
+        &["user_id", "time_taken", "song_title", "song_url"],
+    ),
+    (
+        "response.trivia.score_line",
+        &["position", "user_id", "score"],
+    ),
+    ("response.trivia.started", &["voice_channel_id", "rounds"]),
+    ("response.trivia.stopped", &[]),
+    ("response.undone.clear", &[]),
+    ("response.undone.move", &[]),
+    ("response.undone.queue", &[]),
+    ("response.undone.remove", &[]),
+    ("response.undone.replace", &[]),
+    ("response.unsupported_site_error", &[]),
+    ("response.usage", &["guilds", "commands"]),
+    (
+        "response.usage.command",
+        &["command", "average_ms", "max_ms", "count", "error_percent"],
+    ),
+    ("response.usage.empty", &[]),
+    ("response.usage.guild", &["guild_id", "count"]),
+    (
+        "response.user_listening",
+        &["user_id", "song_title", "song_url", "voice_channel_id"],
+    ),
+    ("response.user_listening.songs_before_next", &["count"]),
+    ("response.user_listening.up_next", &[]),
+    ("response.user_not_listening", &["user_id", "count"]),
+    ("response.user_not_listening.queued", &["user_id", "count"]),
+    (
+        "response.user_queue_limit_reached",
+        &["queued", "rejected", "max"],
+    ),
+    ("response.video_unavailable_error", &[]),
+    (
+        "response.vote_info",
+        &[
+            "voice_channel_id",
+            "skip_voters",
+            "skip_votes_needed",
+            "stop_voters",
+            "stop_votes_needed",
+        ],
+    ),
+    ("response.vote_info.no_voters", &[]),
+    ("response.vote_not_in_channel_error", &["voice_channel_id"]),
+    ("response.vote_song_ended_error", &[]),
+    ("response.wrong_channel_error", &["channels"]),
+    ("session.thread_name", &["weekday"]),
+    ("session.thread_starter", &[]),
+    (
+        "track_log.entry",
+        &[
+            "timestamp",
+            "song_title",
+            "song_url",
+            "voice_channel_id",
+            "user_id",
+        ],
+    ),
+    ("track_log.thread_starter", &[]),
+    ("tts.now_playing", &["song_title", "user_name"]),
+    ("voice_status.playing", &["song_title", "artist"]),
+    ("voice_status.playing_no_artist", &["song_title"]),
+];
@@ -1,7 +1,27 @@
+use crate::blocklist::BlockRule;
+use crate::command_usage::CommandStats;
+use crate::commands::{CommandPermission, HelpCommand, HelpCommandKind};
+use crate::extraction_failures::ExtractionFailure;
+use crate::now_playing_card::NowPlayingCard;
+use crate::playlists::{
+    Contribution, ContributionKind, PlaylistAccess, PlaylistAction, PlaylistSong,
+};
+use crate::schedules::{Schedule, ScheduleSource};
+use crate::settings::{GuildSettings, QueueStrategy};
+use crate::stats::{Achievement, LeaderboardEntry, LeaderboardPeriod, StatsSummary};
+use mrvn_back_ytdl::SongMetadata;
+use mrvn_model::{QueueChangeKind, QueueLimit};
 use serenity::model::prelude::*;
+use std::time::Duration;
 
+mod component;
+mod keys;
+mod pagination;
 mod send_message;
 
+pub use self::component::*;
+pub use self::keys::*;
+pub use self::pagination::*;
 pub use self::send_message::*;
 
 #[derive(Debug, Clone)]
@@ -18,6 +38,111 @@ impl Message {
         }
     }
 
+    pub fn card(&self) -> Option<&NowPlayingCard> {
+        match self {
+            Message::Action(ActionMessage::WithCard { card, .. }) => Some(card),
+            _ => None,
+        }
+    }
+
+    /// A file attached to the message, as its name and contents.
+    pub fn file(&self) -> Option<(&str, &[u8])> {
+        match self {
+            Message::Response(ResponseMessage::HistoryExport { file_name, data }) => {
+                Some((file_name, data))
+            }
+            _ => None,
+        }
+    }
+
+    /// Ephemeral messages are only shown to the user that ran the command. This only applies when
+    /// the message is the first response to a command.
+    pub fn is_ephemeral(&self) -> bool {
+        match self {
+            Message::Action(_) => false,
+            Message::Response(response) => response.is_ephemeral(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        match self {
+            Message::Action(action) => action.is_error(),
+            Message::Response(response) => response.is_error(),
+        }
+    }
+
+    /// Messages asking for more votes have a button that votes the same way.
+    pub fn vote_button(&self) -> Option<VoteButton> {
+        match self {
+            Message::Response(ResponseMessage::SkipMoreVotesNeeded {
+                song_id,
+                voice_channel_id,
+                ..
+            }) => Some(VoteButton::Skip {
+                voice_channel_id: *voice_channel_id,
+                song_id: truncate_song_id(song_id).to_string(),
+            }),
+            Message::Response(ResponseMessage::StopMoreVotesNeeded {
+                voice_channel_id, ..
+            }) => Some(VoteButton::Stop {
+                voice_channel_id: *voice_channel_id,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Messages about a song starting have a button that adds it to your favorites. It favorites
+    /// whatever is playing in the channel when it's pressed, which is the same song until the
+    /// message is replaced by the next one.
+    pub fn favorite_button(&self) -> Option<ChannelId> {
+        match self {
+            Message::Action(action) => action.playing_channel(),
+            Message::Response(_) => None,
+        }
+    }
+
+    /// Party invites link to the party's voice channel.
+    pub fn party_link(&self) -> Option<String> {
+        match self {
+            Message::Response(ResponseMessage::PartyStarted {
+                guild_id,
+                voice_channel_id,
+                ..
+            }) => Some(format!(
+                "https://discord.com/channels/{}/{}",
+                guild_id.0, voice_channel_id.0
+            )),
+            _ => None,
+        }
+    }
+
+    /// Messages showing a page of a long list have buttons to turn to the other pages.
+    pub fn paged_list(&self) -> Option<(PagedList, Page)> {
+        match self {
+            Message::Response(response) => response.paged_list(),
+            Message::Action(_) => None,
+        }
+    }
+
+    pub fn message_key(&self) -> &'static str {
+        match self {
+            Message::Action(action) => action.message_key(),
+            Message::Response(response) => response.message_key(),
+        }
+    }
+
+    /// The text to send instead of an embed, if the message has been configured to be sent as
+    /// plain text.
+    pub fn plain_text(&self, config: &crate::config::Config) -> Option<String> {
+        match config.embed_style(self.message_key()) {
+            Some(style) if style.plain_text => Some(match self {
+                Message::Action(action) => action.to_string(config),
+                Message::Response(response) => response.to_string(config),
+            }),
+            _ => None,
+        }
+    }
+
     pub fn create_embed<'e>(
         &self,
         embed: &'e mut serenity::builder::CreateEmbed,
@@ -26,10 +151,44 @@ impl Message {
         match self {
             Message::Action(action) => action.create_embed(embed, config),
             Message::Response(response) => response.create_embed(embed, config),
+        };
+
+        // Anything configured for the message replaces what it would normally have.
+        if let Some(style) = config.embed_style(self.message_key()) {
+            if let Some(color) = style.color {
+                embed.color(color);
+            }
+            if let Some(title) = &style.title {
+                embed.title(title);
+            }
+            if let Some(footer) = &style.footer {
+                embed.footer(|create_footer| create_footer.text(footer));
+            }
         }
+        embed
     }
 }
 
+// Discord limits custom IDs to 100 characters, which the channel ID and prefix take some of.
+const MAX_BUTTON_SONG_ID_LENGTH: usize = 64;
+
+/// The part of a song ID that's kept in a skip vote button.
+pub fn truncate_song_id(song_id: &str) -> &str {
+    match song_id.char_indices().nth(MAX_BUTTON_SONG_ID_LENGTH) {
+        Some((index, _)) => &song_id[..index],
+        None => song_id,
+    }
+}
+
+/// Roughly when a queued song will play in the channel the user is in.
+#[derive(Debug, Clone)]
+pub struct QueueEta {
+    /// Starts at 1 for the song after the one that's playing.
+    pub position: usize,
+    /// Unknown if any song before it doesn't have a duration, like livestreams.
+    pub wait_secs: Option<u64>,
+}
+
 /// Action messages have the possibility of being sent not directly as a response to a command
 /// invocation. Only one action message is kept around in a guild at a time, old ones are deleted
 /// when new ones are sent.
@@ -41,30 +200,68 @@ pub enum ActionMessage {
         voice_channel_id: ChannelId,
         user_id: UserId,
     },
+    PlayingNoMention {
+        song_title: String,
+        song_url: String,
+        voice_channel_id: ChannelId,
+    },
     PlayingResponse {
         song_title: String,
         song_url: String,
         voice_channel_id: ChannelId,
     },
+    /// A song starting to play, shown with a generated card. The card can only be attached when
+    /// the message is sent as a regular message, otherwise it's shown the same as `message`.
+    WithCard {
+        message: Box<ActionMessage>,
+        card: NowPlayingCard,
+    },
     Finished {
         voice_channel_id: ChannelId,
     },
+    /// A song that was playing when the bot stopped, started again from about where it was.
+    ResumedAfterRestart {
+        song_title: String,
+        song_url: String,
+        voice_channel_id: ChannelId,
+    },
     NoSpeakersError {
         voice_channel_id: ChannelId,
     },
+    /// Sent by the bot's owner to every guild with a song playing.
+    Broadcast {
+        message: String,
+    },
+    /// Sent to every guild with a song playing when maintenance mode is turned on.
+    MaintenanceNotice,
+    /// Posted in a guild's system channel when the bot is added to it.
+    Welcome,
     UnknownError,
 }
 
 /// Response messages are always sent directly as a response to a command invocation.
 #[derive(Debug, Clone)]
 pub enum ResponseMessage {
+    Searching {
+        query: String,
+    },
     Queued {
         song_title: String,
         song_url: String,
+        eta: Option<QueueEta>,
     },
     QueuedMultiple {
         count: usize,
     },
+    QueueLimitReached {
+        queued: usize,
+        rejected: usize,
+        limit: QueueLimit,
+    },
+    PlayManyFailed {
+        // Positions start at 1, paired with the term that was there.
+        failed_terms: Vec<(usize, String)>,
+    },
     QueuedNoSpeakers {
         song_title: String,
         song_url: String,
@@ -72,6 +269,11 @@ pub enum ResponseMessage {
     QueuedMultipleNoSpeakers {
         count: usize,
     },
+    ResumeAvailable {
+        song_title: String,
+        song_url: String,
+        position_secs: u64,
+    },
     Replaced {
         old_song_title: String,
         old_song_url: String,
@@ -98,6 +300,7 @@ pub enum ResponseMessage {
         user_id: UserId,
     },
     SkipMoreVotesNeeded {
+        song_id: String,
         song_title: String,
         song_url: String,
         voice_channel_id: ChannelId,
@@ -109,6 +312,13 @@ pub enum ResponseMessage {
         voice_channel_id: ChannelId,
         user_id: UserId,
     },
+    /// The song broke off before the end, usually because its stream failed.
+    PlaybackFailedError {
+        song_title: String,
+        song_url: String,
+        voice_channel_id: ChannelId,
+        user_id: UserId,
+    },
     StopMoreVotesNeeded {
         voice_channel_id: ChannelId,
         count: usize,
@@ -116,9 +326,363 @@ pub enum ResponseMessage {
     ImageEmbed {
         image_url: String,
     },
+    Custom {
+        content: String,
+    },
+    ScriptFinished,
+    ClipPlayed {
+        voice_channel_id: ChannelId,
+    },
+    Summoned {
+        voice_channel_id: ChannelId,
+    },
+    Transferred {
+        song_title: String,
+        song_url: String,
+        from_voice_channel_id: ChannelId,
+        to_voice_channel_id: ChannelId,
+    },
+    TransferSameChannelError {
+        voice_channel_id: ChannelId,
+    },
+    NowPlaying {
+        song_title: String,
+        song_url: String,
+        voice_channel_id: ChannelId,
+        user_id: UserId,
+        is_paused: bool,
+    },
+    ChannelQueue {
+        voice_channel_id: ChannelId,
+        // Only the first few songs are listed, out of this many.
+        total_count: usize,
+        songs: Vec<SongMetadata>,
+    },
+    SongInfo {
+        metadata: Box<SongMetadata>,
+    },
+    SongInfoPositionError {
+        voice_channel_id: ChannelId,
+        position: i64,
+    },
+    UserListening {
+        user_id: UserId,
+        song_title: String,
+        song_url: String,
+        voice_channel_id: ChannelId,
+        // How many songs will play before the user's next one, if they have one coming up.
+        songs_before_next: Option<usize>,
+    },
+    UserNotListening {
+        user_id: UserId,
+        queued_count: usize,
+    },
+    VoteInfo {
+        voice_channel_id: ChannelId,
+        skip_voters: Vec<UserId>,
+        skip_votes_needed: usize,
+        stop_voters: Vec<UserId>,
+        stop_votes_needed: usize,
+    },
+    ChannelNotAllowedError {
+        voice_channel_id: ChannelId,
+    },
+    SpeakerMutedError {
+        voice_channel_id: ChannelId,
+    },
+    SummonNoSpeakersError {
+        voice_channel_id: ChannelId,
+    },
+    Disconnected {
+        voice_channel_id: ChannelId,
+    },
+    Deduped {
+        voice_channel_id: ChannelId,
+        count: usize,
+    },
+    Undone {
+        kind: QueueChangeKind,
+    },
+    NothingToUndoError,
+    ChannelEntriesRemoved {
+        voice_channel_id: ChannelId,
+        count: usize,
+    },
+    UserEntriesRemoved {
+        user_id: UserId,
+        count: usize,
+    },
+    RemoveRangeError {
+        voice_channel_id: ChannelId,
+        from: i64,
+        to: i64,
+    },
+    NotConnectedError {
+        voice_channel_id: ChannelId,
+    },
+    ClipNoSpeakersError {
+        voice_channel_id: ChannelId,
+    },
+    SoundEffects {
+        names: Vec<String>,
+    },
+    SoundEffectAdded {
+        name: String,
+    },
+    SoundEffectRemoved {
+        name: String,
+    },
+    SoundEffectNotFoundError {
+        name: String,
+    },
+    InvalidSoundEffectUrlError,
+    Stats {
+        user_id: Option<UserId>,
+        summary: StatsSummary,
+    },
+    Top {
+        user_id: Option<UserId>,
+        summary: StatsSummary,
+    },
+    Achievements {
+        user_id: UserId,
+        unlocked: Vec<Achievement>,
+    },
+    AchievementUnlocked {
+        user_id: UserId,
+        achievement: Achievement,
+    },
+    Leaderboard {
+        period: LeaderboardPeriod,
+        page: Page,
+        // Only the entries on this page.
+        entries: Vec<LeaderboardEntry>,
+    },
+    NoStats,
+    HistoryExported {
+        count: usize,
+    },
+    HistoryExport {
+        file_name: String,
+        data: Vec<u8>,
+    },
+    NoHistoryError,
+    Prefs {
+        dm_notifications: bool,
+        announce_mention: bool,
+        language: Option<String>,
+    },
+    UnknownLanguageError {
+        language: String,
+    },
+    Settings {
+        settings: GuildSettings,
+    },
+    MessageChannelMoved {
+        channel_id: ChannelId,
+    },
+    DroppedAbsentEntries {
+        count: usize,
+        user_ids: Vec<UserId>,
+    },
+    Blocklist {
+        rules: Vec<BlockRule>,
+    },
+    BlocklistAdded {
+        rule: BlockRule,
+    },
+    BlocklistRemoved {
+        rule: BlockRule,
+    },
+    BlocklistAlreadyAddedError {
+        rule: BlockRule,
+    },
+    BlocklistNotFoundError {
+        rule: BlockRule,
+    },
+    InvalidBlockRuleError,
+    UserQueue {
+        user_id: UserId,
+        page: Page,
+        // Only the songs on this page.
+        songs: Vec<SongMetadata>,
+    },
+    QueueCleared {
+        count: usize,
+    },
+    QueueShuffled {
+        count: usize,
+    },
+    QueueRemoved {
+        song_title: String,
+        song_url: String,
+    },
+    QueueEmptyError,
+    QueuePositionError {
+        position: i64,
+    },
+    Playlists {
+        user_id: UserId,
+        page: Page,
+        // Only the playlists on this page.
+        playlists: Vec<(String, usize)>,
+    },
+    PlaylistSaved {
+        name: String,
+        count: usize,
+    },
+    PlaylistDeleted {
+        name: String,
+    },
+    PlaylistNotFoundError {
+        name: String,
+    },
+    GuildPlaylists {
+        page: Page,
+        // Only the playlists on this page.
+        playlists: Vec<(String, usize)>,
+    },
+    PlaylistSongsAdded {
+        name: String,
+        count: usize,
+    },
+    PlaylistSongMoved {
+        name: String,
+        song_title: String,
+        position: i64,
+    },
+    PlaylistSongRemoved {
+        name: String,
+        song_title: String,
+    },
+    PlaylistPermissionSet {
+        name: String,
+        action: PlaylistAction,
+        access: PlaylistAccess,
+    },
+    PlaylistLog {
+        name: String,
+        /// Newest first.
+        contributions: Vec<Contribution>,
+    },
+    Favorites {
+        user_id: UserId,
+        page: Page,
+        // Only the songs on this page.
+        songs: Vec<PlaylistSong>,
+    },
+    FavoriteAdded {
+        song_title: String,
+        song_url: String,
+    },
+    FavoriteRemoved {
+        song_title: String,
+    },
+    FavoriteAlreadyAddedError {
+        song_title: String,
+    },
+    FavoritePositionError {
+        position: i64,
+    },
+    NoFavoritesError,
+    PlaylistImported {
+        name: String,
+        count: usize,
+        /// Why each song that couldn't be imported was left out.
+        failures: Vec<String>,
+    },
+    GuildPlaylistNotFoundError {
+        name: String,
+    },
+    GuildPlaylistExistsError {
+        name: String,
+    },
+    PlaylistPositionError {
+        name: String,
+        count: usize,
+    },
+    MusicBanned {
+        user_id: UserId,
+    },
+    MusicUnbanned {
+        user_id: UserId,
+    },
+    AlreadyMusicBannedError {
+        user_id: UserId,
+    },
+    NotMusicBannedError {
+        user_id: UserId,
+    },
+    MusicBannedError,
+    CooldownError {
+        retry_after_secs: u64,
+    },
+    NotEntitledError {
+        command: String,
+    },
+    WrongChannelError {
+        channel_ids: Vec<ChannelId>,
+    },
+    Blocked {
+        rule: BlockRule,
+    },
+    Help {
+        max_permission: CommandPermission,
+        page: Page,
+        // Only the commands on this page.
+        commands: Vec<HelpCommand>,
+    },
+    Synced {
+        is_global: bool,
+    },
+    MaintenanceSet {
+        is_on: bool,
+    },
+    MaintenanceError,
+    BroadcastSent {
+        guild_count: usize,
+    },
+    MaintenanceStopped {
+        voice_channel_id: ChannelId,
+    },
+    DebugStatus {
+        maintenance: bool,
+        playing_count: usize,
+    },
+    ExtractionFailures {
+        failures: Vec<ExtractionFailure>,
+    },
+    CommandUsage {
+        busiest_guilds: Vec<(GuildId, u64)>,
+        slowest_commands: Vec<(String, CommandStats)>,
+    },
+    ExtractorVersion {
+        version: Option<String>,
+    },
+    ExtractorUpToDate {
+        version: String,
+    },
+    ExtractorUpdated {
+        old_version: Option<String>,
+        new_version: String,
+    },
+    LibraryRescanned {
+        count: usize,
+    },
+    MissingPermissionError,
+    MissingOwnerError,
     NoMatchingSongsError,
     NotInVoiceChannelError,
     UnsupportedSiteError,
+    NetworkTimeoutError,
+    VideoUnavailableError,
+    AgeRestrictedError,
+    GeoBlockedError,
+    PrivateVideoError,
+    LiveNotSupportedError,
+    ExtractorOutdatedError,
+    NoLinkError,
+    InvalidTrimError,
     SkipAlreadyVotedError {
         song_title: String,
         song_url: String,
@@ -127,6 +691,10 @@ pub enum ResponseMessage {
     StopAlreadyVotedError {
         voice_channel_id: ChannelId,
     },
+    VoteNotInChannelError {
+        voice_channel_id: ChannelId,
+    },
+    VoteSongEndedError,
     NothingIsQueuedError {
         voice_channel_id: ChannelId,
     },
@@ -142,44 +710,182 @@ pub enum ResponseMessage {
         streak_length: u64,
     },
     NoStreak,
-}
+    StreakLeaderboard {
+        /// Each user with an unbroken streak and its length, longest first.
+        entries: Vec<(UserId, u64)>,
+    },
 
-impl ActionMessage {
-    pub fn to_string(&self, config: &crate::config::Config) -> String {
-        match self {
-            ActionMessage::Playing {
-                song_title,
-                song_url,
-                voice_channel_id,
-                user_id,
-            } => {
-                let channel_id_string = voice_channel_id.0.to_string();
-                let user_id_string = user_id.0.to_string();
-                config.get_message(
-                    "action.playing",
-                    &[
-                        ("song_title", song_title),
-                        ("song_url", song_url),
-                        ("voice_channel_id", &channel_id_string),
-                        ("user_id", &user_id_string),
-                    ],
-                )
-            }
-            ActionMessage::PlayingResponse {
-                song_title,
-                song_url,
-                voice_channel_id,
-            } => {
-                let channel_id_string = voice_channel_id.0.to_string();
-                config.get_message(
-                    "action.playing_response",
-                    &[
-                        ("song_title", song_title),
+    PetFound {
+        name: String,
+        image_url: String,
+        is_shiny: bool,
+        /// Whether it's the first time the user has found this pet.
+        is_new: bool,
+    },
+    PetCollection {
+        /// Each pet the user has found, and how many times.
+        pets: Vec<(String, u64)>,
+        shiny_name: Option<String>,
+        shinies: u64,
+        /// How many different pets there are to find, not counting the shiny.
+        total_pets: usize,
+    },
+    NoPetsError,
+
+    TriviaStarted {
+        voice_channel_id: ChannelId,
+        rounds: usize,
+    },
+    TriviaRound {
+        round_number: usize,
+        rounds: usize,
+    },
+    TriviaRoundWon {
+        user_id: UserId,
+        time_taken: Duration,
+        song_title: String,
+        song_url: String,
+    },
+    TriviaRoundMissed {
+        song_title: String,
+        song_url: String,
+    },
+    TriviaFinished {
+        /// Highest score first.
+        scores: Vec<(UserId, u32)>,
+    },
+    TriviaStopped,
+    TriviaAlreadyRunningError,
+    TriviaNotRunningError,
+    TriviaNoSongsError,
+    TriviaMusicPlayingError {
+        voice_channel_id: ChannelId,
+    },
+
+    Schedules {
+        /// Each schedule with when it next runs, as a Unix timestamp.
+        schedules: Vec<(Schedule, Option<i64>)>,
+    },
+    ScheduleAdded {
+        id: u64,
+        voice_channel_id: ChannelId,
+        /// Unix timestamp.
+        next_run: i64,
+    },
+    ScheduleRemoved {
+        id: u64,
+    },
+    ScheduleStarted {
+        id: u64,
+        voice_channel_id: ChannelId,
+    },
+    InvalidCronError,
+    TooManySchedulesError,
+    ScheduleNotFoundError {
+        id: u64,
+    },
+    ScheduleNoSongsError {
+        id: u64,
+    },
+    ScheduleChannelInUseError {
+        id: u64,
+        voice_channel_id: ChannelId,
+    },
+    ScheduleNoSpeakersError {
+        id: u64,
+        voice_channel_id: ChannelId,
+    },
+
+    PartyStarted {
+        guild_id: GuildId,
+        host_user_id: UserId,
+        voice_channel_id: ChannelId,
+    },
+    PartyEnded {
+        voice_channel_id: ChannelId,
+    },
+    PartyHostLeft {
+        host_user_id: UserId,
+        voice_channel_id: ChannelId,
+    },
+    PartyAlreadyStartedError {
+        host_user_id: UserId,
+        voice_channel_id: ChannelId,
+    },
+    NoPartyError {
+        voice_channel_id: ChannelId,
+    },
+}
+
+impl ActionMessage {
+    /// The voice channel a song has started playing in, if that's what this message is about.
+    pub fn playing_channel(&self) -> Option<ChannelId> {
+        match self {
+            ActionMessage::Playing {
+                voice_channel_id, ..
+            }
+            | ActionMessage::PlayingNoMention {
+                voice_channel_id, ..
+            }
+            | ActionMessage::PlayingResponse {
+                voice_channel_id, ..
+            } => Some(*voice_channel_id),
+            ActionMessage::WithCard { message, .. } => message.playing_channel(),
+            _ => None,
+        }
+    }
+
+    pub fn to_string(&self, config: &crate::config::Config) -> String {
+        match self {
+            ActionMessage::Playing {
+                song_title,
+                song_url,
+                voice_channel_id,
+                user_id,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                let user_id_string = user_id.0.to_string();
+                config.get_message(
+                    "action.playing",
+                    &[
+                        ("song_title", song_title),
+                        ("song_url", song_url),
+                        ("voice_channel_id", &channel_id_string),
+                        ("user_id", &user_id_string),
+                    ],
+                )
+            }
+            ActionMessage::PlayingNoMention {
+                song_title,
+                song_url,
+                voice_channel_id,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "action.playing_no_mention",
+                    &[
+                        ("song_title", song_title),
+                        ("song_url", song_url),
+                        ("voice_channel_id", &channel_id_string),
+                    ],
+                )
+            }
+            ActionMessage::PlayingResponse {
+                song_title,
+                song_url,
+                voice_channel_id,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "action.playing_response",
+                    &[
+                        ("song_title", song_title),
                         ("song_url", song_url),
                         ("voice_channel_id", &channel_id_string),
                     ],
                 )
             }
+            ActionMessage::WithCard { message, .. } => message.to_string(config),
             ActionMessage::Finished { voice_channel_id } => {
                 let channel_id_string = voice_channel_id.0.to_string();
                 config.get_message(
@@ -187,6 +893,21 @@ impl ActionMessage {
                     &[("voice_channel_id", &channel_id_string)],
                 )
             }
+            ActionMessage::ResumedAfterRestart {
+                song_title,
+                song_url,
+                voice_channel_id,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "action.resumed_after_restart",
+                    &[
+                        ("song_title", song_title),
+                        ("song_url", song_url),
+                        ("voice_channel_id", &channel_id_string),
+                    ],
+                )
+            }
             ActionMessage::NoSpeakersError { voice_channel_id } => {
                 let channel_id_string = voice_channel_id.0.to_string();
                 config.get_message(
@@ -194,6 +915,13 @@ impl ActionMessage {
                     &[("voice_channel_id", &channel_id_string)],
                 )
             }
+            ActionMessage::Broadcast { message } => {
+                config.get_message("action.broadcast", &[("message", message)])
+            }
+            ActionMessage::MaintenanceNotice => config
+                .get_raw_message("action.maintenance_notice")
+                .to_string(),
+            ActionMessage::Welcome => config.get_raw_message("action.welcome").to_string(),
             ActionMessage::UnknownError => {
                 config.get_raw_message("action.unknown_error").to_string()
             }
@@ -203,12 +931,34 @@ impl ActionMessage {
     pub fn is_error(&self) -> bool {
         match self {
             ActionMessage::Playing { .. }
+            | ActionMessage::PlayingNoMention { .. }
             | ActionMessage::PlayingResponse { .. }
-            | ActionMessage::Finished { .. } => false,
+            | ActionMessage::WithCard { .. }
+            | ActionMessage::Finished { .. }
+            | ActionMessage::ResumedAfterRestart { .. }
+            | ActionMessage::Broadcast { .. }
+            | ActionMessage::MaintenanceNotice
+            | ActionMessage::Welcome => false,
             ActionMessage::NoSpeakersError { .. } | ActionMessage::UnknownError => true,
         }
     }
 
+    pub fn message_key(&self) -> &'static str {
+        match self {
+            ActionMessage::Playing { .. } => "action.playing",
+            ActionMessage::PlayingNoMention { .. } => "action.playing_no_mention",
+            ActionMessage::PlayingResponse { .. } => "action.playing_response",
+            ActionMessage::WithCard { message, .. } => message.message_key(),
+            ActionMessage::Finished { .. } => "action.finished",
+            ActionMessage::ResumedAfterRestart { .. } => "action.resumed_after_restart",
+            ActionMessage::NoSpeakersError { .. } => "action.no_speakers_error",
+            ActionMessage::Broadcast { .. } => "action.broadcast",
+            ActionMessage::MaintenanceNotice => "action.maintenance_notice",
+            ActionMessage::Welcome => "action.welcome",
+            ActionMessage::UnknownError => "action.unknown_error",
+        }
+    }
+
     pub fn create_embed<'e>(
         &self,
         embed: &'e mut serenity::builder::CreateEmbed,
@@ -227,17 +977,82 @@ impl ActionMessage {
 impl ResponseMessage {
     pub fn to_string(&self, config: &crate::config::Config) -> String {
         match self {
+            ResponseMessage::Searching { query } => {
+                config.get_message("response.searching", &[("query", query)])
+            }
             ResponseMessage::Queued {
                 song_title,
                 song_url,
-            } => config.get_message(
-                "response.queued",
-                &[("song_title", song_title), ("song_url", song_url)],
-            ),
+                eta,
+            } => {
+                let position_string = eta.as_ref().map(|eta| eta.position.to_string());
+                let wait_string = eta
+                    .as_ref()
+                    .and_then(|eta| eta.wait_secs)
+                    .map(format_duration);
+                match (&position_string, &wait_string) {
+                    (Some(position_string), Some(wait_string)) => config.get_message(
+                        "response.queued.eta",
+                        &[
+                            ("song_title", song_title),
+                            ("song_url", song_url),
+                            ("position", position_string),
+                            ("wait", wait_string),
+                        ],
+                    ),
+                    (Some(position_string), None) => config.get_message(
+                        "response.queued.position",
+                        &[
+                            ("song_title", song_title),
+                            ("song_url", song_url),
+                            ("position", position_string),
+                        ],
+                    ),
+                    _ => config.get_message(
+                        "response.queued",
+                        &[("song_title", song_title), ("song_url", song_url)],
+                    ),
+                }
+            }
             ResponseMessage::QueuedMultiple { count } => {
                 let count_string = count.to_string();
                 config.get_message("response.queued_multiple", &[("count", &count_string)])
             }
+            ResponseMessage::QueueLimitReached {
+                queued,
+                rejected,
+                limit,
+            } => {
+                let (key, max) = match limit {
+                    QueueLimit::User(max) => ("response.user_queue_limit_reached", max),
+                    QueueLimit::Guild(max) => ("response.guild_queue_limit_reached", max),
+                };
+                let queued_string = queued.to_string();
+                let rejected_string = rejected.to_string();
+                let max_string = max.to_string();
+                config.get_message(
+                    key,
+                    &[
+                        ("queued", &queued_string),
+                        ("rejected", &rejected_string),
+                        ("max", &max_string),
+                    ],
+                )
+            }
+            ResponseMessage::PlayManyFailed { failed_terms } => std::iter::once(
+                config
+                    .get_raw_message("response.play_many_failed")
+                    .to_string(),
+            )
+            .chain(failed_terms.iter().map(|(position, term)| {
+                let position_string = position.to_string();
+                config.get_message(
+                    "response.play_many_failed.term",
+                    &[("position", &position_string), ("term", term)],
+                )
+            }))
+            .collect::<Vec<_>>()
+            .join("\n"),
             ResponseMessage::QueuedNoSpeakers {
                 song_title,
                 song_url,
@@ -252,6 +1067,18 @@ impl ResponseMessage {
                     &[("count", &count_string)],
                 )
             }
+            ResponseMessage::ResumeAvailable {
+                song_title,
+                song_url,
+                position_secs,
+            } => config.get_message(
+                "response.resume_available",
+                &[
+                    ("song_title", song_title),
+                    ("song_url", song_url),
+                    ("position", &format_duration(*position_secs)),
+                ],
+            ),
             ResponseMessage::Replaced {
                 old_song_title,
                 old_song_url,
@@ -326,6 +1153,7 @@ impl ResponseMessage {
                 song_url,
                 voice_channel_id,
                 count,
+                ..
             } => {
                 let channel_id_string = voice_channel_id.0.to_string();
                 if *count == 1 {
@@ -368,6 +1196,24 @@ impl ResponseMessage {
                     ],
                 )
             }
+            ResponseMessage::PlaybackFailedError {
+                song_title,
+                song_url,
+                voice_channel_id,
+                user_id,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                let user_id_string = user_id.0.to_string();
+                config.get_message(
+                    "response.playback_failed_error",
+                    &[
+                        ("song_title", song_title),
+                        ("song_url", song_url),
+                        ("voice_channel_id", &channel_id_string),
+                        ("user_id", &user_id_string),
+                    ],
+                )
+            }
             ResponseMessage::StopMoreVotesNeeded {
                 voice_channel_id,
                 count,
@@ -398,6 +1244,33 @@ impl ResponseMessage {
             ResponseMessage::UnsupportedSiteError => config
                 .get_raw_message("response.unsupported_site_error")
                 .to_string(),
+            ResponseMessage::NetworkTimeoutError => config
+                .get_raw_message("response.network_timeout_error")
+                .to_string(),
+            ResponseMessage::VideoUnavailableError => config
+                .get_raw_message("response.video_unavailable_error")
+                .to_string(),
+            ResponseMessage::AgeRestrictedError => config
+                .get_raw_message("response.age_restricted_error")
+                .to_string(),
+            ResponseMessage::GeoBlockedError => config
+                .get_raw_message("response.geo_blocked_error")
+                .to_string(),
+            ResponseMessage::PrivateVideoError => config
+                .get_raw_message("response.private_video_error")
+                .to_string(),
+            ResponseMessage::LiveNotSupportedError => config
+                .get_raw_message("response.live_not_supported_error")
+                .to_string(),
+            ResponseMessage::ExtractorOutdatedError => config
+                .get_raw_message("response.extractor_outdated_error")
+                .to_string(),
+            ResponseMessage::NoLinkError => {
+                config.get_raw_message("response.no_link_error").to_string()
+            }
+            ResponseMessage::InvalidTrimError => config
+                .get_raw_message("response.invalid_trim_error")
+                .to_string(),
             ResponseMessage::SkipAlreadyVotedError {
                 song_title,
                 song_url,
@@ -420,6 +1293,16 @@ impl ResponseMessage {
                     &[("voice_channel_id", &channel_id_string)],
                 )
             }
+            ResponseMessage::VoteNotInChannelError { voice_channel_id } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.vote_not_in_channel_error",
+                    &[("voice_channel_id", &channel_id_string)],
+                )
+            }
+            ResponseMessage::VoteSongEndedError => config
+                .get_raw_message("response.vote_song_ended_error")
+                .to_string(),
             ResponseMessage::NothingIsQueuedError { voice_channel_id } => {
                 let channel_id_string = voice_channel_id.0.to_string();
                 config.get_message(
@@ -442,62 +1325,2254 @@ impl ResponseMessage {
                 )
             }
             ResponseMessage::ImageEmbed { image_url } => image_url.clone(),
-
-            ResponseMessage::StreakWait => {
-                config.get_raw_message("response.streak_wait").to_string()
+            ResponseMessage::Custom { content } => content.clone(),
+            ResponseMessage::ScriptFinished => config
+                .get_raw_message("response.script_finished")
+                .to_string(),
+            ResponseMessage::ClipPlayed { voice_channel_id } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.clip_played",
+                    &[("voice_channel_id", &channel_id_string)],
+                )
             }
-            ResponseMessage::Streak { streak_length } => {
-                let streak_length_string = streak_length.to_string();
+            ResponseMessage::ClipNoSpeakersError { voice_channel_id } => {
+                let channel_id_string = voice_channel_id.0.to_string();
                 config.get_message(
-                    "response.streak",
-                    &[("streak_length", &streak_length_string)],
+                    "response.clip_no_speakers_error",
+                    &[("voice_channel_id", &channel_id_string)],
                 )
             }
-            ResponseMessage::NoStreak => config.get_raw_message("response.no_streak").to_string(),
-        }
-    }
-
-    pub fn is_error(&self) -> bool {
-        match self {
-            ResponseMessage::Queued { .. }
-            | ResponseMessage::QueuedMultiple { .. }
-            | ResponseMessage::QueuedNoSpeakers { .. }
-            | ResponseMessage::QueuedMultipleNoSpeakers { .. }
-            | ResponseMessage::Replaced { .. }
-            | ResponseMessage::ReplaceSkipped { .. }
-            | ResponseMessage::Paused { .. }
-            | ResponseMessage::Skipped { .. }
-            | ResponseMessage::SkipMoreVotesNeeded { .. }
-            | ResponseMessage::Stopped { .. }
-            | ResponseMessage::StopMoreVotesNeeded { .. }
-            | ResponseMessage::ImageEmbed { .. }
-            | ResponseMessage::StreakWait
-            | ResponseMessage::Streak { .. }
-            | ResponseMessage::NoStreak => false,
-            ResponseMessage::NoMatchingSongsError
-            | ResponseMessage::NotInVoiceChannelError
-            | ResponseMessage::UnsupportedSiteError
-            | ResponseMessage::SkipAlreadyVotedError { .. }
-            | ResponseMessage::StopAlreadyVotedError { .. }
-            | ResponseMessage::NothingIsQueuedError { .. }
-            | ResponseMessage::NothingIsPlayingError { .. }
-            | ResponseMessage::AlreadyPlayingError { .. } => true,
-        }
-    }
-
-    pub fn create_embed<'e>(
-        &self,
-        embed: &'e mut serenity::builder::CreateEmbed,
-        config: &crate::config::Config,
-    ) -> &'e mut serenity::builder::CreateEmbed {
-        embed.color(if self.is_error() {
-            config.error_embed_color
-        } else {
-            config.response_embed_color
-        });
-        match self {
-            ResponseMessage::ImageEmbed { image_url } => embed.image(image_url),
-            _ => embed.description(self.to_string(config)),
-        }
+            ResponseMessage::SpeakerMutedError { voice_channel_id } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.speaker_muted_error",
+                    &[("voice_channel_id", &channel_id_string)],
+                )
+            }
+            ResponseMessage::ChannelNotAllowedError { voice_channel_id } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.channel_not_allowed_error",
+                    &[("voice_channel_id", &channel_id_string)],
+                )
+            }
+            ResponseMessage::NowPlaying {
+                song_title,
+                song_url,
+                voice_channel_id,
+                user_id,
+                is_paused,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                let user_id_string = user_id.0.to_string();
+                config.get_message(
+                    if *is_paused {
+                        "response.now_playing.paused"
+                    } else {
+                        "response.now_playing"
+                    },
+                    &[
+                        ("song_title", song_title),
+                        ("song_url", song_url),
+                        ("voice_channel_id", &channel_id_string),
+                        ("user_id", &user_id_string),
+                    ],
+                )
+            }
+            ResponseMessage::UserListening {
+                user_id,
+                song_title,
+                song_url,
+                voice_channel_id,
+                songs_before_next,
+            } => {
+                let user_id_string = user_id.0.to_string();
+                let channel_id_string = voice_channel_id.0.to_string();
+                let listening = config.get_message(
+                    "response.user_listening",
+                    &[
+                        ("user_id", &user_id_string),
+                        ("song_title", song_title),
+                        ("song_url", song_url),
+                        ("voice_channel_id", &channel_id_string),
+                    ],
+                );
+                match songs_before_next {
+                    Some(0) => format!(
+                        "{}\n{}",
+                        listening,
+                        config.get_raw_message("response.user_listening.up_next")
+                    ),
+                    Some(count) => {
+                        let count_string = count.to_string();
+                        format!(
+                            "{}\n{}",
+                            listening,
+                            config.get_message(
+                                "response.user_listening.songs_before_next",
+                                &[("count", &count_string)],
+                            )
+                        )
+                    }
+                    None => listening,
+                }
+            }
+            ResponseMessage::UserNotListening {
+                user_id,
+                queued_count,
+            } => {
+                let user_id_string = user_id.0.to_string();
+                let count_string = queued_count.to_string();
+                config.get_message(
+                    if *queued_count == 0 {
+                        "response.user_not_listening"
+                    } else {
+                        "response.user_not_listening.queued"
+                    },
+                    &[("user_id", &user_id_string), ("count", &count_string)],
+                )
+            }
+            ResponseMessage::VoteInfo {
+                voice_channel_id,
+                skip_voters,
+                skip_votes_needed,
+                stop_voters,
+                stop_votes_needed,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                let skip_votes_needed_string = skip_votes_needed.to_string();
+                let stop_votes_needed_string = stop_votes_needed.to_string();
+                config.get_message(
+                    "response.vote_info",
+                    &[
+                        ("voice_channel_id", &channel_id_string),
+                        ("skip_voters", &format_voters(skip_voters, config)),
+                        ("skip_votes_needed", &skip_votes_needed_string),
+                        ("stop_voters", &format_voters(stop_voters, config)),
+                        ("stop_votes_needed", &stop_votes_needed_string),
+                    ],
+                )
+            }
+            ResponseMessage::SongInfo { metadata } => {
+                let user_id_string = metadata.user_id.0.to_string();
+                config.get_message(
+                    "response.song_info",
+                    &[
+                        ("song_title", &metadata.title),
+                        ("song_url", &metadata.url),
+                        ("user_id", &user_id_string),
+                    ],
+                )
+            }
+            ResponseMessage::SongInfoPositionError {
+                voice_channel_id,
+                position,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                let position_string = position.to_string();
+                config.get_message(
+                    "response.song_info.position_error",
+                    &[
+                        ("voice_channel_id", &channel_id_string),
+                        ("position", &position_string),
+                    ],
+                )
+            }
+            ResponseMessage::ChannelQueue {
+                voice_channel_id,
+                total_count,
+                songs,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                let count_string = total_count.to_string();
+                if songs.is_empty() {
+                    config.get_message(
+                        "response.queue.channel_empty",
+                        &[("voice_channel_id", &channel_id_string)],
+                    )
+                } else {
+                    std::iter::once(config.get_message(
+                        "response.queue.channel",
+                        &[
+                            ("voice_channel_id", &channel_id_string),
+                            ("count", &count_string),
+                        ],
+                    ))
+                    .chain(songs.iter().enumerate().map(|(index, song)| {
+                        let position_string = (index + 1).to_string();
+                        let user_id_string = song.user_id.0.to_string();
+                        config.get_message(
+                            "response.queue.channel_line",
+                            &[
+                                ("position", &position_string),
+                                ("song_title", &song.title),
+                                ("song_url", &song.url),
+                                ("user_id", &user_id_string),
+                            ],
+                        )
+                    }))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                }
+            }
+            ResponseMessage::Transferred {
+                song_title,
+                song_url,
+                from_voice_channel_id,
+                to_voice_channel_id,
+            } => {
+                let from_channel_id_string = from_voice_channel_id.0.to_string();
+                let to_channel_id_string = to_voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.transferred",
+                    &[
+                        ("song_title", song_title),
+                        ("song_url", song_url),
+                        ("from_voice_channel_id", &from_channel_id_string),
+                        ("to_voice_channel_id", &to_channel_id_string),
+                    ],
+                )
+            }
+            ResponseMessage::TransferSameChannelError { voice_channel_id } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.transfer_same_channel_error",
+                    &[("voice_channel_id", &channel_id_string)],
+                )
+            }
+            ResponseMessage::Summoned { voice_channel_id } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.summoned",
+                    &[("voice_channel_id", &channel_id_string)],
+                )
+            }
+            ResponseMessage::SummonNoSpeakersError { voice_channel_id } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.summon_no_speakers_error",
+                    &[("voice_channel_id", &channel_id_string)],
+                )
+            }
+            ResponseMessage::Deduped {
+                voice_channel_id,
+                count,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                let count_string = count.to_string();
+                config.get_message(
+                    "response.deduped",
+                    &[
+                        ("voice_channel_id", &channel_id_string),
+                        ("count", &count_string),
+                    ],
+                )
+            }
+            ResponseMessage::Undone { kind } => config
+                .get_raw_message(match kind {
+                    QueueChangeKind::Queue => "response.undone.queue",
+                    QueueChangeKind::Remove => "response.undone.remove",
+                    QueueChangeKind::Move => "response.undone.move",
+                    QueueChangeKind::Replace => "response.undone.replace",
+                    QueueChangeKind::Clear => "response.undone.clear",
+                })
+                .to_string(),
+            ResponseMessage::NothingToUndoError => config
+                .get_raw_message("response.nothing_to_undo_error")
+                .to_string(),
+            ResponseMessage::ChannelEntriesRemoved {
+                voice_channel_id,
+                count,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                let count_string = count.to_string();
+                config.get_message(
+                    "response.remove.range",
+                    &[
+                        ("voice_channel_id", &channel_id_string),
+                        ("count", &count_string),
+                    ],
+                )
+            }
+            ResponseMessage::UserEntriesRemoved { user_id, count } => {
+                let user_id_string = user_id.0.to_string();
+                let count_string = count.to_string();
+                config.get_message(
+                    "response.remove.user",
+                    &[("user_id", &user_id_string), ("count", &count_string)],
+                )
+            }
+            ResponseMessage::RemoveRangeError {
+                voice_channel_id,
+                from,
+                to,
+            } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                let from_string = from.to_string();
+                let to_string = to.to_string();
+                config.get_message(
+                    "response.remove.range_error",
+                    &[
+                        ("voice_channel_id", &channel_id_string),
+                        ("from", &from_string),
+                        ("to", &to_string),
+                    ],
+                )
+            }
+            ResponseMessage::Disconnected { voice_channel_id } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.disconnected",
+                    &[("voice_channel_id", &channel_id_string)],
+                )
+            }
+            ResponseMessage::NotConnectedError { voice_channel_id } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.not_connected_error",
+                    &[("voice_channel_id", &channel_id_string)],
+                )
+            }
+            ResponseMessage::SoundEffects { names } => {
+                if names.is_empty() {
+                    config
+                        .get_raw_message("response.sfx.list_empty")
+                        .to_string()
+                } else {
+                    let names_string = names
+                        .iter()
+                        .map(|name| format!("`{}`", name))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    config.get_message("response.sfx.list", &[("names", &names_string)])
+                }
+            }
+            ResponseMessage::SoundEffectAdded { name } => {
+                config.get_message("response.sfx.added", &[("name", name)])
+            }
+            ResponseMessage::SoundEffectRemoved { name } => {
+                config.get_message("response.sfx.removed", &[("name", name)])
+            }
+            ResponseMessage::SoundEffectNotFoundError { name } => {
+                config.get_message("response.sfx.not_found_error", &[("name", name)])
+            }
+            ResponseMessage::InvalidSoundEffectUrlError => config
+                .get_raw_message("response.sfx.invalid_url_error")
+                .to_string(),
+            ResponseMessage::Stats { user_id, summary } => {
+                let songs_played_string = summary.counts.songs_played.to_string();
+                let songs_failed_string = summary.counts.songs_failed.to_string();
+                let listening_time_string = format_duration(summary.counts.listening_secs);
+                match user_id {
+                    Some(user_id) => {
+                        let user_id_string = user_id.0.to_string();
+                        config.get_message(
+                            "response.stats.user",
+                            &[
+                                ("user_id", &user_id_string),
+                                ("songs_played", &songs_played_string),
+                                ("listening_time", &listening_time_string),
+                            ],
+                        )
+                    }
+                    None => config.get_message(
+                        "response.stats.guild",
+                        &[
+                            ("songs_played", &songs_played_string),
+                            ("songs_failed", &songs_failed_string),
+                            ("listening_time", &listening_time_string),
+                        ],
+                    ),
+                }
+            }
+            ResponseMessage::Top { user_id, .. } => match user_id {
+                Some(user_id) => {
+                    let user_id_string = user_id.0.to_string();
+                    config.get_message("response.top.user", &[("user_id", &user_id_string)])
+                }
+                None => config.get_raw_message("response.top.guild").to_string(),
+            },
+            ResponseMessage::Achievements { user_id, unlocked } => {
+                // The shiny pet can't be found unless there's one configured.
+                let available: Vec<_> = Achievement::ALL
+                    .iter()
+                    .copied()
+                    .filter(|achievement| {
+                        *achievement != Achievement::FirstShiny
+                            || config
+                                .pets
+                                .as_ref()
+                                .is_some_and(|pets| pets.shiny.is_some())
+                            || unlocked.contains(achievement)
+                    })
+                    .collect();
+                let user_id_string = user_id.0.to_string();
+                let count_string = unlocked.len().to_string();
+                let total_string = available.len().to_string();
+                let header = config.get_message(
+                    "response.achievements",
+                    &[
+                        ("user_id", &user_id_string),
+                        ("count", &count_string),
+                        ("total", &total_string),
+                    ],
+                );
+                let lines = available.into_iter().map(|achievement| {
+                    let (name, description) = achievement_text(config, achievement);
+                    config.get_message(
+                        if unlocked.contains(&achievement) {
+                            "response.achievements.unlocked"
+                        } else {
+                            "response.achievements.locked"
+                        },
+                        &[("name", &name), ("description", &description)],
+                    )
+                });
+                std::iter::once(header)
+                    .chain(lines)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            ResponseMessage::AchievementUnlocked {
+                user_id,
+                achievement,
+            } => {
+                let user_id_string = user_id.0.to_string();
+                let (name, description) = achievement_text(config, *achievement);
+                config.get_message(
+                    "response.achievement_unlocked",
+                    &[
+                        ("user_id", &user_id_string),
+                        ("name", &name),
+                        ("description", &description),
+                    ],
+                )
+            }
+            ResponseMessage::Leaderboard {
+                period,
+                page,
+                entries,
+            } => {
+                let title = config.get_raw_message(match period {
+                    LeaderboardPeriod::Week => "response.leaderboard.week",
+                    LeaderboardPeriod::Month => "response.leaderboard.month",
+                    LeaderboardPeriod::AllTime => "response.leaderboard.all_time",
+                });
+                let lines = entries.iter().enumerate().map(|(index, entry)| {
+                    let position_string = (page.first_index() + index + 1).to_string();
+                    let user_id_string = entry.user_id.0.to_string();
+                    let plays_string = entry.plays.to_string();
+                    config.get_message(
+                        "response.leaderboard.line",
+                        &[
+                            ("position", &position_string),
+                            ("user_id", &user_id_string),
+                            ("plays", &plays_string),
+                        ],
+                    )
+                });
+                std::iter::once(title.to_string())
+                    .chain(lines)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            ResponseMessage::NoStats => config.get_raw_message("response.no_stats").to_string(),
+            ResponseMessage::HistoryExported { count } => {
+                let count_string = count.to_string();
+                config.get_message("response.history.exported", &[("count", &count_string)])
+            }
+            ResponseMessage::HistoryExport { .. } => config
+                .get_raw_message("response.history.export")
+                .to_string(),
+            ResponseMessage::NoHistoryError => config
+                .get_raw_message("response.history.none_error")
+                .to_string(),
+            ResponseMessage::Prefs {
+                dm_notifications,
+                announce_mention,
+                language,
+            } => {
+                let enabled_string = |enabled: bool| {
+                    config.get_raw_message(if enabled {
+                        "response.prefs.enabled"
+                    } else {
+                        "response.prefs.disabled"
+                    })
+                };
+                config.get_message(
+                    "response.prefs",
+                    &[
+                        ("dm_notifications", enabled_string(*dm_notifications)),
+                        ("mention", enabled_string(*announce_mention)),
+                        (
+                            "language",
+                            language.as_deref().unwrap_or_else(|| {
+                                config.get_raw_message("response.prefs.default_language")
+                            }),
+                        ),
+                    ],
+                )
+            }
+            ResponseMessage::Settings { settings } => config.get_message(
+                "response.settings",
+                &[
+                    (
+                        "queue_strategy",
+                        config.get_raw_message(match settings.queue_strategy {
+                            QueueStrategy::RoundRobin => "response.settings.round_robin",
+                            QueueStrategy::Fifo => "response.settings.fifo",
+                        }),
+                    ),
+                    (
+                        "drop_absent",
+                        config.get_raw_message(if settings.drop_absent_entries {
+                            "response.settings.enabled"
+                        } else {
+                            "response.settings.disabled"
+                        }),
+                    ),
+                    (
+                        "command_channels",
+                        &if settings.command_channels.is_empty() {
+                            config
+                                .get_raw_message("response.settings.any_channel")
+                                .to_string()
+                        } else {
+                            format_channel_mentions(&settings.command_channels)
+                        },
+                    ),
+                    (
+                        "blocked_voice_channels",
+                        &if settings.blocked_voice_channels.is_empty() {
+                            config
+                                .get_raw_message("response.settings.no_channels")
+                                .to_string()
+                        } else {
+                            format_channel_mentions(&settings.blocked_voice_channels)
+                        },
+                    ),
+                    (
+                        "follow_requester",
+                        config.get_raw_message(if settings.follow_requester {
+                            "response.settings.enabled"
+                        } else {
+                            "response.settings.disabled"
+                        }),
+                    ),
+                    (
+                        "announce_songs",
+                        config.get_raw_message(if settings.announce_songs {
+                            "response.settings.enabled"
+                        } else {
+                            "response.settings.disabled"
+                        }),
+                    ),
+                    (
+                        "message_channel",
+                        &match settings.locked_message_channel {
+                            Some(channel_id) => format!("<#{}>", channel_id.0),
+                            None => config
+                                .get_raw_message("response.settings.latest_channel")
+                                .to_string(),
+                        },
+                    ),
+                    (
+                        "notify_message_channel_moves",
+                        config.get_raw_message(if settings.notify_message_channel_moves {
+                            "response.settings.enabled"
+                        } else {
+                            "response.settings.disabled"
+                        }),
+                    ),
+                    (
+                        "session_threads",
+                        config.get_raw_message(if settings.session_threads {
+                            "response.settings.enabled"
+                        } else {
+                            "response.settings.disabled"
+                        }),
+                    ),
+                    (
+                        "now_playing_cards",
+                        config.get_raw_message(if settings.now_playing_cards {
+                            "response.settings.enabled"
+                        } else {
+                            "response.settings.disabled"
+                        }),
+                    ),
+                    (
+                        "audit_channel",
+                        &match settings.audit_channel {
+                            Some(channel_id) => format!("<#{}>", channel_id.0),
+                            None => config
+                                .get_raw_message("response.settings.disabled")
+                                .to_string(),
+                        },
+                    ),
+                    (
+                        "playlist_role",
+                        &match settings.playlist_role {
+                            Some(role_id) => format!("<@&{}>", role_id.0),
+                            None => config
+                                .get_raw_message("response.settings.no_role")
+                                .to_string(),
+                        },
+                    ),
+                    (
+                        "music_mode",
+                        config.get_raw_message(if settings.music_mode {
+                            "response.settings.enabled"
+                        } else {
+                            "response.settings.disabled"
+                        }),
+                    ),
+                    (
+                        "gapless",
+                        config.get_raw_message(if settings.gapless {
+                            "response.settings.enabled"
+                        } else {
+                            "response.settings.disabled"
+                        }),
+                    ),
+                ],
+            ),
+            ResponseMessage::MessageChannelMoved { channel_id } => {
+                let channel_id_string = channel_id.0.to_string();
+                config.get_message(
+                    "response.message_channel_moved",
+                    &[("channel_id", &channel_id_string)],
+                )
+            }
+            ResponseMessage::DroppedAbsentEntries { count, user_ids } => {
+                let count_string = count.to_string();
+                let users_string = user_ids
+                    .iter()
+                    .map(|user_id| format!("<@{}>", user_id.0))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                config.get_message(
+                    "response.dropped_absent_entries",
+                    &[("count", &count_string), ("users", &users_string)],
+                )
+            }
+            ResponseMessage::Blocklist { rules } => {
+                if rules.is_empty() {
+                    config
+                        .get_raw_message("response.blocklist.empty")
+                        .to_string()
+                } else {
+                    std::iter::once(config.get_raw_message("response.blocklist").to_string())
+                        .chain(rules.iter().map(|rule| format_block_rule(rule, config)))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+            }
+            ResponseMessage::BlocklistAdded { rule } => config.get_message(
+                "response.blocklist.added",
+                &[("rule", &format_block_rule(rule, config))],
+            ),
+            ResponseMessage::BlocklistRemoved { rule } => config.get_message(
+                "response.blocklist.removed",
+                &[("rule", &format_block_rule(rule, config))],
+            ),
+            ResponseMessage::BlocklistAlreadyAddedError { rule } => config.get_message(
+                "response.blocklist.already_added_error",
+                &[("rule", &format_block_rule(rule, config))],
+            ),
+            ResponseMessage::BlocklistNotFoundError { rule } => config.get_message(
+                "response.blocklist.not_found_error",
+                &[("rule", &format_block_rule(rule, config))],
+            ),
+            ResponseMessage::InvalidBlockRuleError => config
+                .get_raw_message("response.blocklist.invalid_rule_error")
+                .to_string(),
+            ResponseMessage::UserQueue { page, songs, .. } => {
+                if songs.is_empty() {
+                    config.get_raw_message("response.queue.empty").to_string()
+                } else {
+                    std::iter::once(config.get_raw_message("response.queue").to_string())
+                        .chain(songs.iter().enumerate().map(|(index, song)| {
+                            // Positions are in the whole queue, so they match /queue remove.
+                            let position_string = (page.first_index() + index + 1).to_string();
+                            config.get_message(
+                                "response.queue.line",
+                                &[
+                                    ("position", &position_string),
+                                    ("song_title", &song.title),
+                                    ("song_url", &song.url),
+                                ],
+                            )
+                        }))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+            }
+            ResponseMessage::QueueCleared { count } => {
+                let count_string = count.to_string();
+                config.get_message("response.queue.cleared", &[("count", &count_string)])
+            }
+            ResponseMessage::QueueShuffled { count } => {
+                let count_string = count.to_string();
+                config.get_message("response.queue.shuffled", &[("count", &count_string)])
+            }
+            ResponseMessage::QueueRemoved {
+                song_title,
+                song_url,
+            } => config.get_message(
+                "response.queue.removed",
+                &[("song_title", song_title), ("song_url", song_url)],
+            ),
+            ResponseMessage::QueueEmptyError => config
+                .get_raw_message("response.queue.empty_error")
+                .to_string(),
+            ResponseMessage::QueuePositionError { position } => {
+                let position_string = position.to_string();
+                config.get_message(
+                    "response.queue.position_error",
+                    &[("position", &position_string)],
+                )
+            }
+            ResponseMessage::Playlists { playlists, .. } => {
+                if playlists.is_empty() {
+                    config
+                        .get_raw_message("response.playlist.list_empty")
+                        .to_string()
+                } else {
+                    std::iter::once(config.get_raw_message("response.playlist.list").to_string())
+                        .chain(playlists.iter().map(|(name, count)| {
+                            let count_string = count.to_string();
+                            config.get_message(
+                                "response.playlist.line",
+                                &[("name", name), ("count", &count_string)],
+                            )
+                        }))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+            }
+            ResponseMessage::PlaylistSaved { name, count } => {
+                let count_string = count.to_string();
+                config.get_message(
+                    "response.playlist.saved",
+                    &[("name", name), ("count", &count_string)],
+                )
+            }
+            ResponseMessage::PlaylistDeleted { name } => {
+                config.get_message("response.playlist.deleted", &[("name", name)])
+            }
+            ResponseMessage::PlaylistNotFoundError { name } => {
+                config.get_message("response.playlist.not_found_error", &[("name", name)])
+            }
+            ResponseMessage::GuildPlaylists { playlists, .. } => {
+                if playlists.is_empty() {
+                    config
+                        .get_raw_message("response.guild_playlist.list_empty")
+                        .to_string()
+                } else {
+                    std::iter::once(
+                        config
+                            .get_raw_message("response.guild_playlist.list")
+                            .to_string(),
+                    )
+                    .chain(playlists.iter().map(|(name, count)| {
+                        let count_string = count.to_string();
+                        config.get_message(
+                            "response.playlist.line",
+                            &[("name", name), ("count", &count_string)],
+                        )
+                    }))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                }
+            }
+            ResponseMessage::PlaylistSongsAdded { name, count } => {
+                let count_string = count.to_string();
+                config.get_message(
+                    "response.guild_playlist.added",
+                    &[("name", name), ("count", &count_string)],
+                )
+            }
+            ResponseMessage::PlaylistSongMoved {
+                name,
+                song_title,
+                position,
+            } => {
+                let position_string = position.to_string();
+                config.get_message(
+                    "response.guild_playlist.moved",
+                    &[
+                        ("name", name),
+                        ("song_title", song_title),
+                        ("position", &position_string),
+                    ],
+                )
+            }
+            ResponseMessage::PlaylistSongRemoved { name, song_title } => config.get_message(
+                "response.guild_playlist.removed",
+                &[("name", name), ("song_title", song_title)],
+            ),
+            ResponseMessage::PlaylistPermissionSet {
+                name,
+                action,
+                access,
+            } => config.get_message(
+                "response.guild_playlist.permission_set",
+                &[
+                    ("name", name),
+                    ("action", action.name()),
+                    ("who", playlist_access_text(config, *access)),
+                ],
+            ),
+            ResponseMessage::PlaylistLog {
+                name,
+                contributions,
+            } => {
+                if contributions.is_empty() {
+                    config.get_message("response.guild_playlist.log_empty", &[("name", name)])
+                } else {
+                    std::iter::once(
+                        config.get_message("response.guild_playlist.log", &[("name", name)]),
+                    )
+                    .chain(
+                        contributions
+                            .iter()
+                            .map(|contribution| format_contribution(contribution, config)),
+                    )
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                }
+            }
+            ResponseMessage::Favorites { page, songs, .. } => {
+                if songs.is_empty() {
+                    config
+                        .get_raw_message("response.favorites.empty")
+                        .to_string()
+                } else {
+                    std::iter::once(config.get_raw_message("response.favorites").to_string())
+                        .chain(songs.iter().enumerate().map(|(index, song)| {
+                            // Positions are in the whole list, so they match /favs remove.
+                            let position_string = (page.first_index() + index + 1).to_string();
+                            config.get_message(
+                                "response.favorites.line",
+                                &[
+                                    ("position", &position_string),
+                                    ("song_title", &song.title),
+                                    ("song_url", &song.url),
+                                ],
+                            )
+                        }))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                }
+            }
+            ResponseMessage::FavoriteAdded {
+                song_title,
+                song_url,
+            } => config.get_message(
+                "response.favorites.added",
+                &[("song_title", song_title), ("song_url", song_url)],
+            ),
+            ResponseMessage::FavoriteRemoved { song_title } => {
+                config.get_message("response.favorites.removed", &[("song_title", song_title)])
+            }
+            ResponseMessage::FavoriteAlreadyAddedError { song_title } => config.get_message(
+                "response.favorites.already_added_error",
+                &[("song_title", song_title)],
+            ),
+            ResponseMessage::FavoritePositionError { position } => {
+                let position_string = position.to_string();
+                config.get_message(
+                    "response.favorites.position_error",
+                    &[("position", &position_string)],
+                )
+            }
+            ResponseMessage::NoFavoritesError => config
+                .get_raw_message("response.favorites.none_error")
+                .to_string(),
+            ResponseMessage::PlaylistImported {
+                name,
+                count,
+                failures,
+            } => {
+                let count_string = count.to_string();
+                let mut lines = vec![config.get_message(
+                    "response.playlist.imported",
+                    &[("name", name), ("count", &count_string)],
+                )];
+                if !failures.is_empty() {
+                    let failure_count_string = failures.len().to_string();
+                    lines.push(config.get_message(
+                        "response.playlist.imported.failures",
+                        &[("count", &failure_count_string)],
+                    ));
+                    lines.extend(
+                        failures
+                            .iter()
+                            .take(MAX_IMPORT_FAILURES_SHOWN)
+                            .map(|failure| {
+                                config.get_message(
+                                    "response.playlist.imported.failure",
+                                    &[(
+                                        "failure",
+                                        &truncate_chars(failure, MAX_IMPORT_FAILURE_LENGTH),
+                                    )],
+                                )
+                            }),
+                    );
+                    if failures.len() > MAX_IMPORT_FAILURES_SHOWN {
+                        let more_string = (failures.len() - MAX_IMPORT_FAILURES_SHOWN).to_string();
+                        lines.push(config.get_message(
+                            "response.playlist.imported.more_failures",
+                            &[("count", &more_string)],
+                        ));
+                    }
+                }
+                lines.join("\n")
+            }
+            ResponseMessage::GuildPlaylistNotFoundError { name } => {
+                config.get_message("response.guild_playlist.not_found_error", &[("name", name)])
+            }
+            ResponseMessage::GuildPlaylistExistsError { name } => {
+                config.get_message("response.guild_playlist.exists_error", &[("name", name)])
+            }
+            ResponseMessage::PlaylistPositionError { name, count } => {
+                let count_string = count.to_string();
+                config.get_message(
+                    "response.guild_playlist.position_error",
+                    &[("name", name), ("count", &count_string)],
+                )
+            }
+            ResponseMessage::MusicBanned { user_id } => {
+                let user_id_string = user_id.0.to_string();
+                config.get_message("response.musicban.banned", &[("user_id", &user_id_string)])
+            }
+            ResponseMessage::MusicUnbanned { user_id } => {
+                let user_id_string = user_id.0.to_string();
+                config.get_message(
+                    "response.musicban.unbanned",
+                    &[("user_id", &user_id_string)],
+                )
+            }
+            ResponseMessage::AlreadyMusicBannedError { user_id } => {
+                let user_id_string = user_id.0.to_string();
+                config.get_message(
+                    "response.musicban.already_banned_error",
+                    &[("user_id", &user_id_string)],
+                )
+            }
+            ResponseMessage::NotMusicBannedError { user_id } => {
+                let user_id_string = user_id.0.to_string();
+                config.get_message(
+                    "response.musicban.not_banned_error",
+                    &[("user_id", &user_id_string)],
+                )
+            }
+            ResponseMessage::CooldownError { retry_after_secs } => {
+                let retry_after_string = retry_after_secs.to_string();
+                config.get_message(
+                    "response.cooldown_error",
+                    &[("retry_after_secs", &retry_after_string)],
+                )
+            }
+            ResponseMessage::NotEntitledError { command } => {
+                config.get_message("response.not_entitled_error", &[("command", command)])
+            }
+            ResponseMessage::WrongChannelError { channel_ids } => config.get_message(
+                "response.wrong_channel_error",
+                &[("channels", &format_channel_mentions(channel_ids))],
+            ),
+            ResponseMessage::MusicBannedError => config
+                .get_raw_message("response.musicban.refused")
+                .to_string(),
+            ResponseMessage::Blocked { rule } => config.get_message(
+                "response.blocked",
+                &[("rule", &format_block_rule(rule, config))],
+            ),
+            ResponseMessage::ExtractorVersion { version } => match version {
+                Some(version) => {
+                    config.get_message("response.extractor_version", &[("version", version)])
+                }
+                None => config
+                    .get_raw_message("response.extractor_version.unknown")
+                    .to_string(),
+            },
+            ResponseMessage::ExtractorUpToDate { version } => {
+                config.get_message("response.extractor_up_to_date", &[("version", version)])
+            }
+            ResponseMessage::ExtractorUpdated {
+                old_version,
+                new_version,
+            } => config.get_message(
+                "response.extractor_updated",
+                &[
+                    (
+                        "old_version",
+                        old_version.as_deref().unwrap_or_else(|| {
+                            config.get_raw_message("response.extractor_updated.unknown_version")
+                        }),
+                    ),
+                    ("new_version", new_version),
+                ],
+            ),
+            ResponseMessage::LibraryRescanned { count } => {
+                let count_string = count.to_string();
+                config.get_message("response.library_rescanned", &[("count", &count_string)])
+            }
+            ResponseMessage::ExtractionFailures { failures } => {
+                if failures.is_empty() {
+                    config
+                        .get_raw_message("response.extraction_failures.empty")
+                        .to_string()
+                } else {
+                    let count_string = failures.len().to_string();
+                    config.get_message("response.extraction_failures", &[("count", &count_string)])
+                }
+            }
+            ResponseMessage::CommandUsage {
+                busiest_guilds,
+                slowest_commands,
+            } => {
+                if slowest_commands.is_empty() {
+                    return config.get_raw_message("response.usage.empty").to_string();
+                }
+                let guilds_string = busiest_guilds
+                    .iter()
+                    .map(|(guild_id, count)| {
+                        let guild_id_string = guild_id.0.to_string();
+                        let count_string = count.to_string();
+                        config.get_message(
+                            "response.usage.guild",
+                            &[("guild_id", &guild_id_string), ("count", &count_string)],
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let commands_string = slowest_commands
+                    .iter()
+                    .map(|(name, stats)| {
+                        let average_ms_string = stats.average_time().as_millis().to_string();
+                        let max_ms_string = stats.max_time.as_millis().to_string();
+                        let count_string = stats.count.to_string();
+                        let error_percent_string =
+                            (stats.error_count * 100 / stats.count.max(1)).to_string();
+                        config.get_message(
+                            "response.usage.command",
+                            &[
+                                ("command", name),
+                                ("average_ms", &average_ms_string),
+                                ("max_ms", &max_ms_string),
+                                ("count", &count_string),
+                                ("error_percent", &error_percent_string),
+                            ],
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                config.get_message(
+                    "response.usage",
+                    &[("guilds", &guilds_string), ("commands", &commands_string)],
+                )
+            }
+            ResponseMessage::Help { commands, .. } => {
+                std::iter::once(config.get_raw_message("response.help").to_string())
+                    .chain(commands.iter().map(|help_command| {
+                        let permission = match help_command.permission {
+                            CommandPermission::Everyone => "",
+                            CommandPermission::ManageServer => {
+                                config.get_raw_message("response.help.manage_server")
+                            }
+                            CommandPermission::Owner => {
+                                config.get_raw_message("response.help.owner")
+                            }
+                        };
+                        match help_command.kind {
+                            HelpCommandKind::Slash => config.get_message(
+                                "response.help.line",
+                                &[
+                                    ("usage", &help_command.usage),
+                                    ("description", &help_command.description),
+                                    ("permission", permission),
+                                ],
+                            ),
+                            HelpCommandKind::User => config.get_message(
+                                "response.help.user_command",
+                                &[("name", &help_command.usage), ("permission", permission)],
+                            ),
+                            HelpCommandKind::Message => config.get_message(
+                                "response.help.message_command",
+                                &[("name", &help_command.usage), ("permission", permission)],
+                            ),
+                        }
+                    }))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            ResponseMessage::Synced { is_global } => config
+                .get_raw_message(if *is_global {
+                    "response.synced.global"
+                } else {
+                    "response.synced.guild"
+                })
+                .to_string(),
+            ResponseMessage::MaintenanceSet { is_on } => config
+                .get_raw_message(if *is_on {
+                    "response.maintenance.on"
+                } else {
+                    "response.maintenance.off"
+                })
+                .to_string(),
+            ResponseMessage::MaintenanceError => config
+                .get_raw_message("response.maintenance.error")
+                .to_string(),
+            ResponseMessage::BroadcastSent { guild_count } => {
+                let guild_count_string = guild_count.to_string();
+                config.get_message(
+                    "response.broadcast_sent",
+                    &[("guild_count", &guild_count_string)],
+                )
+            }
+            ResponseMessage::MaintenanceStopped { voice_channel_id } => {
+                let channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.maintenance.stopped",
+                    &[("voice_channel_id", &channel_id_string)],
+                )
+            }
+            ResponseMessage::DebugStatus {
+                maintenance,
+                playing_count,
+            } => {
+                let playing_count_string = playing_count.to_string();
+                config.get_message(
+                    "response.debug_status",
+                    &[
+                        (
+                            "maintenance",
+                            config.get_raw_message(if *maintenance {
+                                "response.settings.enabled"
+                            } else {
+                                "response.settings.disabled"
+                            }),
+                        ),
+                        ("playing_count", &playing_count_string),
+                    ],
+                )
+            }
+            ResponseMessage::MissingPermissionError => config
+                .get_raw_message("response.missing_permission_error")
+                .to_string(),
+            ResponseMessage::MissingOwnerError => config
+                .get_raw_message("response.missing_owner_error")
+                .to_string(),
+            ResponseMessage::UnknownLanguageError { language } => {
+                config.get_message("response.prefs.unknown_language", &[("language", language)])
+            }
+
+            ResponseMessage::StreakWait => {
+                config.get_raw_message("response.streak_wait").to_string()
+            }
+            ResponseMessage::Streak { streak_length } => {
+                let streak_length_string = streak_length.to_string();
+                config.get_message(
+                    "response.streak",
+                    &[("streak_length", &streak_length_string)],
+                )
+            }
+            ResponseMessage::NoStreak => config.get_raw_message("response.no_streak").to_string(),
+            ResponseMessage::StreakLeaderboard { entries } => {
+                if entries.is_empty() {
+                    return config
+                        .get_raw_message("response.streak_leaderboard.empty")
+                        .to_string();
+                }
+                std::iter::once(
+                    config
+                        .get_raw_message("response.streak_leaderboard")
+                        .to_string(),
+                )
+                .chain(
+                    entries
+                        .iter()
+                        .enumerate()
+                        .map(|(index, (user_id, streak_length))| {
+                            let position_string = (index + 1).to_string();
+                            let user_id_string = user_id.0.to_string();
+                            let streak_length_string = streak_length.to_string();
+                            config.get_message(
+                                "response.streak_leaderboard.line",
+                                &[
+                                    ("position", &position_string),
+                                    ("user_id", &user_id_string),
+                                    ("streak_length", &streak_length_string),
+                                ],
+                            )
+                        }),
+                )
+                .collect::<Vec<_>>()
+                .join("\n")
+            }
+            ResponseMessage::PetFound {
+                name,
+                is_shiny,
+                is_new,
+                ..
+            } => {
+                let key = match (is_shiny, is_new) {
+                    (true, _) => "response.pet.found_shiny",
+                    (false, true) => "response.pet.found_new",
+                    (false, false) => "response.pet.found",
+                };
+                config.get_message(key, &[("name", name)])
+            }
+            ResponseMessage::PetCollection {
+                pets,
+                shiny_name,
+                shinies,
+                total_pets,
+            } => {
+                if pets.is_empty() && *shinies == 0 {
+                    return config.get_raw_message("response.pets.empty").to_string();
+                }
+                let found_string = pets.len().to_string();
+                let total_string = total_pets.to_string();
+                let header = config.get_message(
+                    "response.pets",
+                    &[("found", &found_string), ("total", &total_string)],
+                );
+                let lines = pets.iter().map(|(name, count)| {
+                    let count_string = count.to_string();
+                    config.get_message(
+                        "response.pets.line",
+                        &[("name", name), ("count", &count_string)],
+                    )
+                });
+                let shiny_line = match (shiny_name, shinies) {
+                    (Some(shiny_name), shinies) if *shinies > 0 => {
+                        let count_string = shinies.to_string();
+                        Some(config.get_message(
+                            "response.pets.shiny",
+                            &[("name", shiny_name), ("count", &count_string)],
+                        ))
+                    }
+                    _ => None,
+                };
+                std::iter::once(header)
+                    .chain(lines)
+                    .chain(shiny_line)
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            ResponseMessage::NoPetsError => config
+                .get_raw_message("response.pet.none_error")
+                .to_string(),
+            ResponseMessage::TriviaStarted {
+                voice_channel_id,
+                rounds,
+            } => {
+                let voice_channel_id_string = voice_channel_id.0.to_string();
+                let rounds_string = rounds.to_string();
+                config.get_message(
+                    "response.trivia.started",
+                    &[
+                        ("voice_channel_id", &voice_channel_id_string),
+                        ("rounds", &rounds_string),
+                    ],
+                )
+            }
+            ResponseMessage::TriviaRound {
+                round_number,
+                rounds,
+            } => {
+                let round_number_string = round_number.to_string();
+                let rounds_string = rounds.to_string();
+                config.get_message(
+                    "response.trivia.round",
+                    &[
+                        ("round_number", &round_number_string),
+                        ("rounds", &rounds_string),
+                    ],
+                )
+            }
+            ResponseMessage::TriviaRoundWon {
+                user_id,
+                time_taken,
+                song_title,
+                song_url,
+            } => {
+                let user_id_string = user_id.0.to_string();
+                let time_taken_string = format!("{:.1}", time_taken.as_secs_f32());
+                config.get_message(
+                    "response.trivia.round_won",
+                    &[
+                        ("user_id", &user_id_string),
+                        ("time_taken", &time_taken_string),
+                        ("song_title", song_title),
+                        ("song_url", song_url),
+                    ],
+                )
+            }
+            ResponseMessage::TriviaRoundMissed {
+                song_title,
+                song_url,
+            } => config.get_message(
+                "response.trivia.round_missed",
+                &[("song_title", song_title), ("song_url", song_url)],
+            ),
+            ResponseMessage::TriviaFinished { scores } => {
+                if scores.is_empty() {
+                    return config
+                        .get_raw_message("response.trivia.finished_no_scores")
+                        .to_string();
+                }
+                std::iter::once(
+                    config
+                        .get_raw_message("response.trivia.finished")
+                        .to_string(),
+                )
+                .chain(scores.iter().enumerate().map(|(index, (user_id, score))| {
+                    let position_string = (index + 1).to_string();
+                    let user_id_string = user_id.0.to_string();
+                    let score_string = score.to_string();
+                    config.get_message(
+                        "response.trivia.score_line",
+                        &[
+                            ("position", &position_string),
+                            ("user_id", &user_id_string),
+                            ("score", &score_string),
+                        ],
+                    )
+                }))
+                .collect::<Vec<_>>()
+                .join("\n")
+            }
+            ResponseMessage::TriviaStopped => config
+                .get_raw_message("response.trivia.stopped")
+                .to_string(),
+            ResponseMessage::TriviaAlreadyRunningError => config
+                .get_raw_message("response.trivia.already_running_error")
+                .to_string(),
+            ResponseMessage::TriviaNotRunningError => config
+                .get_raw_message("response.trivia.not_running_error")
+                .to_string(),
+            ResponseMessage::TriviaNoSongsError => config
+                .get_raw_message("response.trivia.no_songs_error")
+                .to_string(),
+            ResponseMessage::TriviaMusicPlayingError { voice_channel_id } => {
+                let voice_channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.trivia.music_playing_error",
+                    &[("voice_channel_id", &voice_channel_id_string)],
+                )
+            }
+            ResponseMessage::Schedules { schedules } => {
+                if schedules.is_empty() {
+                    return config
+                        .get_raw_message("response.schedules.empty")
+                        .to_string();
+                }
+                std::iter::once(config.get_raw_message("response.schedules").to_string())
+                    .chain(schedules.iter().map(|(schedule, next_run)| {
+                        let id_string = schedule.id.to_string();
+                        let voice_channel_id_string = schedule.voice_channel_id.0.to_string();
+                        let user_id_string = schedule.user_id.0.to_string();
+                        let source_string = match &schedule.source {
+                            ScheduleSource::Playlist(name) => {
+                                config.get_message("response.schedules.playlist", &[("name", name)])
+                            }
+                            ScheduleSource::Url(url) => {
+                                config.get_message("response.schedules.url", &[("url", url)])
+                            }
+                        };
+                        let next_run_string = match next_run {
+                            Some(next_run) => format!("<t:{}:R>", next_run),
+                            None => config
+                                .get_raw_message("response.schedules.never")
+                                .to_string(),
+                        };
+                        config.get_message(
+                            "response.schedules.line",
+                            &[
+                                ("id", &id_string),
+                                ("cron", &schedule.cron),
+                                ("voice_channel_id", &voice_channel_id_string),
+                                ("user_id", &user_id_string),
+                                ("source", &source_string),
+                                ("next_run", &next_run_string),
+                            ],
+                        )
+                    }))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            ResponseMessage::ScheduleAdded {
+                id,
+                voice_channel_id,
+                next_run,
+            } => {
+                let id_string = id.to_string();
+                let voice_channel_id_string = voice_channel_id.0.to_string();
+                let next_run_string = format!("<t:{}:R>", next_run);
+                config.get_message(
+                    "response.schedule.added",
+                    &[
+                        ("id", &id_string),
+                        ("voice_channel_id", &voice_channel_id_string),
+                        ("next_run", &next_run_string),
+                    ],
+                )
+            }
+            ResponseMessage::ScheduleRemoved { id } => {
+                let id_string = id.to_string();
+                config.get_message("response.schedule.removed", &[("id", &id_string)])
+            }
+            ResponseMessage::ScheduleStarted {
+                id,
+                voice_channel_id,
+            } => {
+                let id_string = id.to_string();
+                let voice_channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.schedule.started",
+                    &[
+                        ("id", &id_string),
+                        ("voice_channel_id", &voice_channel_id_string),
+                    ],
+                )
+            }
+            ResponseMessage::InvalidCronError => config
+                .get_raw_message("response.schedule.invalid_cron_error")
+                .to_string(),
+            ResponseMessage::TooManySchedulesError => config
+                .get_raw_message("response.schedule.too_many_error")
+                .to_string(),
+            ResponseMessage::ScheduleNotFoundError { id } => {
+                let id_string = id.to_string();
+                config.get_message("response.schedule.not_found_error", &[("id", &id_string)])
+            }
+            ResponseMessage::ScheduleNoSongsError { id } => {
+                let id_string = id.to_string();
+                config.get_message("response.schedule.no_songs_error", &[("id", &id_string)])
+            }
+            ResponseMessage::ScheduleChannelInUseError {
+                id,
+                voice_channel_id,
+            } => {
+                let id_string = id.to_string();
+                let voice_channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.schedule.channel_in_use_error",
+                    &[
+                        ("id", &id_string),
+                        ("voice_channel_id", &voice_channel_id_string),
+                    ],
+                )
+            }
+            ResponseMessage::ScheduleNoSpeakersError {
+                id,
+                voice_channel_id,
+            } => {
+                let id_string = id.to_string();
+                let voice_channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.schedule.no_speakers_error",
+                    &[
+                        ("id", &id_string),
+                        ("voice_channel_id", &voice_channel_id_string),
+                    ],
+                )
+            }
+            ResponseMessage::PartyStarted {
+                host_user_id,
+                voice_channel_id,
+                ..
+            } => {
+                let host_user_id_string = host_user_id.0.to_string();
+                let voice_channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.party.started",
+                    &[
+                        ("host_user_id", &host_user_id_string),
+                        ("voice_channel_id", &voice_channel_id_string),
+                    ],
+                )
+            }
+            ResponseMessage::PartyEnded { voice_channel_id } => {
+                let voice_channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.party.ended",
+                    &[("voice_channel_id", &voice_channel_id_string)],
+                )
+            }
+            ResponseMessage::PartyHostLeft {
+                host_user_id,
+                voice_channel_id,
+            } => {
+                let host_user_id_string = host_user_id.0.to_string();
+                let voice_channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.party.host_left",
+                    &[
+                        ("host_user_id", &host_user_id_string),
+                        ("voice_channel_id", &voice_channel_id_string),
+                    ],
+                )
+            }
+            ResponseMessage::PartyAlreadyStartedError {
+                host_user_id,
+                voice_channel_id,
+            } => {
+                let host_user_id_string = host_user_id.0.to_string();
+                let voice_channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.party.already_started_error",
+                    &[
+                        ("host_user_id", &host_user_id_string),
+                        ("voice_channel_id", &voice_channel_id_string),
+                    ],
+                )
+            }
+            ResponseMessage::NoPartyError { voice_channel_id } => {
+                let voice_channel_id_string = voice_channel_id.0.to_string();
+                config.get_message(
+                    "response.party.no_party_error",
+                    &[("voice_channel_id", &voice_channel_id_string)],
+                )
+            }
+        }
+    }
+
+    /// The key of the message string this is built from, which its appearance can be configured
+    /// by. Messages with more than one form, like singular and plural, use the start they share.
+    pub fn message_key(&self) -> &'static str {
+        match self {
+            ResponseMessage::Searching { .. } => "response.searching",
+            ResponseMessage::Queued { .. } => "response.queued",
+            ResponseMessage::QueuedMultiple { .. } => "response.queued_multiple",
+            ResponseMessage::QueueLimitReached {
+                limit: QueueLimit::User(_),
+                ..
+            } => "response.user_queue_limit_reached",
+            ResponseMessage::QueueLimitReached {
+                limit: QueueLimit::Guild(_),
+                ..
+            } => "response.guild_queue_limit_reached",
+            ResponseMessage::PlayManyFailed { .. } => "response.play_many_failed",
+            ResponseMessage::QueuedNoSpeakers { .. } => "response.queued_no_speakers",
+            ResponseMessage::QueuedMultipleNoSpeakers { .. } => {
+                "response.queued_multiple_no_speakers"
+            }
+            ResponseMessage::ResumeAvailable { .. } => "response.resume_available",
+            ResponseMessage::Replaced { .. } => "response.replaced",
+            ResponseMessage::ReplaceSkipped { .. } => "response.replace_skipped",
+            ResponseMessage::Paused { .. } => "response.paused",
+            ResponseMessage::Skipped { .. } => "response.skipped",
+            ResponseMessage::SkipMoreVotesNeeded { .. } => "response.skip_more_votes_needed",
+            ResponseMessage::Stopped { .. } => "response.stopped",
+            ResponseMessage::PlaybackFailedError { .. } => "response.playback_failed_error",
+            ResponseMessage::StopMoreVotesNeeded { .. } => "response.stop_more_votes_needed",
+            ResponseMessage::NoMatchingSongsError => "response.no_matching_songs_error",
+            ResponseMessage::NotInVoiceChannelError => "response.not_in_voice_channel_error",
+            ResponseMessage::UnsupportedSiteError => "response.unsupported_site_error",
+            ResponseMessage::NetworkTimeoutError => "response.network_timeout_error",
+            ResponseMessage::VideoUnavailableError => "response.video_unavailable_error",
+            ResponseMessage::AgeRestrictedError => "response.age_restricted_error",
+            ResponseMessage::GeoBlockedError => "response.geo_blocked_error",
+            ResponseMessage::PrivateVideoError => "response.private_video_error",
+            ResponseMessage::LiveNotSupportedError => "response.live_not_supported_error",
+            ResponseMessage::ExtractorOutdatedError => "response.extractor_outdated_error",
+            ResponseMessage::NoLinkError => "response.no_link_error",
+            ResponseMessage::InvalidTrimError => "response.invalid_trim_error",
+            ResponseMessage::SkipAlreadyVotedError { .. } => "response.skip_already_voted_error",
+            ResponseMessage::StopAlreadyVotedError { .. } => "response.stop_already_voted_error",
+            ResponseMessage::VoteNotInChannelError { .. } => "response.vote_not_in_channel_error",
+            ResponseMessage::VoteSongEndedError => "response.vote_song_ended_error",
+            ResponseMessage::NothingIsQueuedError { .. } => "response.nothing_is_queued_error",
+            ResponseMessage::NothingIsPlayingError { .. } => "response.nothing_is_playing_error",
+            ResponseMessage::AlreadyPlayingError { .. } => "response.already_playing_error",
+            ResponseMessage::ImageEmbed { .. } => "response.image_embed",
+            ResponseMessage::Custom { .. } => "response.custom",
+            ResponseMessage::ScriptFinished => "response.script_finished",
+            ResponseMessage::ClipPlayed { .. } => "response.clip_played",
+            ResponseMessage::ClipNoSpeakersError { .. } => "response.clip_no_speakers_error",
+            ResponseMessage::SpeakerMutedError { .. } => "response.speaker_muted_error",
+            ResponseMessage::ChannelNotAllowedError { .. } => "response.channel_not_allowed_error",
+            ResponseMessage::NowPlaying { .. } => "response.now_playing",
+            ResponseMessage::UserListening { .. } => "response.user_listening",
+            ResponseMessage::UserNotListening { .. } => "response.user_not_listening",
+            ResponseMessage::VoteInfo { .. } => "response.vote_info",
+            ResponseMessage::SongInfo { .. } => "response.song_info",
+            ResponseMessage::SongInfoPositionError { .. } => "response.song_info.position_error",
+            ResponseMessage::ChannelQueue { .. } => "response.queue.channel",
+            ResponseMessage::Transferred { .. } => "response.transferred",
+            ResponseMessage::TransferSameChannelError { .. } => {
+                "response.transfer_same_channel_error"
+            }
+            ResponseMessage::Summoned { .. } => "response.summoned",
+            ResponseMessage::SummonNoSpeakersError { .. } => "response.summon_no_speakers_error",
+            ResponseMessage::Deduped { .. } => "response.deduped",
+            ResponseMessage::Undone { .. } => "response.undone",
+            ResponseMessage::NothingToUndoError => "response.nothing_to_undo_error",
+            ResponseMessage::ChannelEntriesRemoved { .. } => "response.remove.range",
+            ResponseMessage::UserEntriesRemoved { .. } => "response.remove.user",
+            ResponseMessage::RemoveRangeError { .. } => "response.remove.range_error",
+            ResponseMessage::Disconnected { .. } => "response.disconnected",
+            ResponseMessage::NotConnectedError { .. } => "response.not_connected_error",
+            ResponseMessage::SoundEffects { .. } => "response.sfx.list",
+            ResponseMessage::SoundEffectAdded { .. } => "response.sfx.added",
+            ResponseMessage::SoundEffectRemoved { .. } => "response.sfx.removed",
+            ResponseMessage::SoundEffectNotFoundError { .. } => "response.sfx.not_found_error",
+            ResponseMessage::InvalidSoundEffectUrlError => "response.sfx.invalid_url_error",
+            ResponseMessage::Stats { .. } => "response.stats",
+            ResponseMessage::Top { .. } => "response.top",
+            ResponseMessage::Achievements { .. } => "response.achievements",
+            ResponseMessage::AchievementUnlocked { .. } => "response.achievement_unlocked",
+            ResponseMessage::Leaderboard { .. } => "response.leaderboard",
+            ResponseMessage::NoStats => "response.no_stats",
+            ResponseMessage::HistoryExported { .. } => "response.history.exported",
+            ResponseMessage::HistoryExport { .. } => "response.history.export",
+            ResponseMessage::NoHistoryError => "response.history.none_error",
+            ResponseMessage::Prefs { .. } => "response.prefs",
+            ResponseMessage::Settings { .. } => "response.settings",
+            ResponseMessage::MessageChannelMoved { .. } => "response.message_channel_moved",
+            ResponseMessage::DroppedAbsentEntries { .. } => "response.dropped_absent_entries",
+            ResponseMessage::Blocklist { .. } => "response.blocklist",
+            ResponseMessage::BlocklistAdded { .. } => "response.blocklist.added",
+            ResponseMessage::BlocklistRemoved { .. } => "response.blocklist.removed",
+            ResponseMessage::BlocklistAlreadyAddedError { .. } => {
+                "response.blocklist.already_added_error"
+            }
+            ResponseMessage::BlocklistNotFoundError { .. } => "response.blocklist.not_found_error",
+            ResponseMessage::InvalidBlockRuleError => "response.blocklist.invalid_rule_error",
+            ResponseMessage::UserQueue { .. } => "response.queue",
+            ResponseMessage::QueueCleared { .. } => "response.queue.cleared",
+            ResponseMessage::QueueShuffled { .. } => "response.queue.shuffled",
+            ResponseMessage::QueueRemoved { .. } => "response.queue.removed",
+            ResponseMessage::QueueEmptyError => "response.queue.empty_error",
+            ResponseMessage::QueuePositionError { .. } => "response.queue.position_error",
+            ResponseMessage::Playlists { .. } => "response.playlist.list",
+            ResponseMessage::PlaylistSaved { .. } => "response.playlist.saved",
+            ResponseMessage::PlaylistDeleted { .. } => "response.playlist.deleted",
+            ResponseMessage::PlaylistNotFoundError { .. } => "response.playlist.not_found_error",
+            ResponseMessage::GuildPlaylists { .. } => "response.guild_playlist.list",
+            ResponseMessage::PlaylistSongsAdded { .. } => "response.guild_playlist.added",
+            ResponseMessage::PlaylistSongMoved { .. } => "response.guild_playlist.moved",
+            ResponseMessage::PlaylistSongRemoved { .. } => "response.guild_playlist.removed",
+            ResponseMessage::PlaylistPermissionSet { .. } => {
+                "response.guild_playlist.permission_set"
+            }
+            ResponseMessage::PlaylistLog { .. } => "response.guild_playlist.log",
+            ResponseMessage::Favorites { .. } => "response.favorites",
+            ResponseMessage::FavoriteAdded { .. } => "response.favorites.added",
+            ResponseMessage::FavoriteRemoved { .. } => "response.favorites.removed",
+            ResponseMessage::FavoriteAlreadyAddedError { .. } => {
+                "response.favorites.already_added_error"
+            }
+            ResponseMessage::FavoritePositionError { .. } => "response.favorites.position_error",
+            ResponseMessage::NoFavoritesError => "response.favorites.none_error",
+            ResponseMessage::PlaylistImported { .. } => "response.playlist.imported",
+            ResponseMessage::GuildPlaylistNotFoundError { .. } => {
+                "response.guild_playlist.not_found_error"
+            }
+            ResponseMessage::GuildPlaylistExistsError { .. } => {
+                "response.guild_playlist.exists_error"
+            }
+            ResponseMessage::PlaylistPositionError { .. } => {
+                "response.guild_playlist.position_error"
+            }
+            ResponseMessage::MusicBanned { .. } => "response.musicban.banned",
+            ResponseMessage::MusicUnbanned { .. } => "response.musicban.unbanned",
+            ResponseMessage::AlreadyMusicBannedError { .. } => {
+                "response.musicban.already_banned_error"
+            }
+            ResponseMessage::NotMusicBannedError { .. } => "response.musicban.not_banned_error",
+            ResponseMessage::CooldownError { .. } => "response.cooldown_error",
+            ResponseMessage::NotEntitledError { .. } => "response.not_entitled_error",
+            ResponseMessage::WrongChannelError { .. } => "response.wrong_channel_error",
+            ResponseMessage::MusicBannedError => "response.musicban.refused",
+            ResponseMessage::Blocked { .. } => "response.blocked",
+            ResponseMessage::ExtractorVersion { .. } => "response.extractor_version",
+            ResponseMessage::ExtractorUpToDate { .. } => "response.extractor_up_to_date",
+            ResponseMessage::LibraryRescanned { .. } => "response.library_rescanned",
+            ResponseMessage::ExtractorUpdated { .. } => "response.extractor_updated",
+            ResponseMessage::ExtractionFailures { .. } => "response.extraction_failures",
+            ResponseMessage::Help { .. } => "response.help",
+            ResponseMessage::Synced { .. } => "response.synced",
+            ResponseMessage::MaintenanceSet { .. } => "response.maintenance",
+            ResponseMessage::MaintenanceError => "response.maintenance.error",
+            ResponseMessage::MaintenanceStopped { .. } => "response.maintenance.stopped",
+            ResponseMessage::DebugStatus { .. } => "response.debug_status",
+            ResponseMessage::BroadcastSent { .. } => "response.broadcast_sent",
+            ResponseMessage::CommandUsage { .. } => "response.usage",
+            ResponseMessage::MissingPermissionError => "response.missing_permission_error",
+            ResponseMessage::MissingOwnerError => "response.missing_owner_error",
+            ResponseMessage::UnknownLanguageError { .. } => "response.prefs.unknown_language",
+            ResponseMessage::StreakWait => "response.streak_wait",
+            ResponseMessage::Streak { .. } => "response.streak",
+            ResponseMessage::NoStreak => "response.no_streak",
+            ResponseMessage::StreakLeaderboard { .. } => "response.streak_leaderboard",
+            ResponseMessage::PetFound { .. } => "response.pet.found",
+            ResponseMessage::PetCollection { .. } => "response.pets",
+            ResponseMessage::NoPetsError => "response.pet.none_error",
+            ResponseMessage::TriviaStarted { .. } => "response.trivia.started",
+            ResponseMessage::TriviaRound { .. } => "response.trivia.round",
+            ResponseMessage::TriviaRoundWon { .. } => "response.trivia.round_won",
+            ResponseMessage::TriviaRoundMissed { .. } => "response.trivia.round_missed",
+            ResponseMessage::TriviaFinished { .. } => "response.trivia.finished",
+            ResponseMessage::TriviaStopped => "response.trivia.stopped",
+            ResponseMessage::TriviaAlreadyRunningError => "response.trivia.already_running_error",
+            ResponseMessage::TriviaNotRunningError => "response.trivia.not_running_error",
+            ResponseMessage::TriviaNoSongsError => "response.trivia.no_songs_error",
+            ResponseMessage::TriviaMusicPlayingError { .. } => {
+                "response.trivia.music_playing_error"
+            }
+            ResponseMessage::Schedules { .. } => "response.schedules",
+            ResponseMessage::ScheduleAdded { .. } => "response.schedule.added",
+            ResponseMessage::ScheduleRemoved { .. } => "response.schedule.removed",
+            ResponseMessage::ScheduleStarted { .. } => "response.schedule.started",
+            ResponseMessage::InvalidCronError => "response.schedule.invalid_cron_error",
+            ResponseMessage::TooManySchedulesError => "response.schedule.too_many_error",
+            ResponseMessage::ScheduleNotFoundError { .. } => "response.schedule.not_found_error",
+            ResponseMessage::ScheduleNoSongsError { .. } => "response.schedule.no_songs_error",
+            ResponseMessage::ScheduleChannelInUseError { .. } => {
+                "response.schedule.channel_in_use_error"
+            }
+            ResponseMessage::ScheduleNoSpeakersError { .. } => {
+                "response.schedule.no_speakers_error"
+            }
+            ResponseMessage::PartyStarted { .. } => "response.party.started",
+            ResponseMessage::PartyEnded { .. } => "response.party.ended",
+            ResponseMessage::PartyHostLeft { .. } => "response.party.host_left",
+            ResponseMessage::PartyAlreadyStartedError { .. } => {
+                "response.party.already_started_error"
+            }
+            ResponseMessage::NoPartyError { .. } => "response.party.no_party_error",
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        match self {
+            // It's only an error if none of the songs could be queued.
+            ResponseMessage::QueueLimitReached { queued, .. } => *queued == 0,
+            ResponseMessage::Searching { .. }
+            | ResponseMessage::Queued { .. }
+            | ResponseMessage::QueuedMultiple { .. }
+            | ResponseMessage::PlayManyFailed { .. }
+            | ResponseMessage::QueuedNoSpeakers { .. }
+            | ResponseMessage::QueuedMultipleNoSpeakers { .. }
+            | ResponseMessage::ResumeAvailable { .. }
+            | ResponseMessage::Replaced { .. }
+            | ResponseMessage::ReplaceSkipped { .. }
+            | ResponseMessage::Paused { .. }
+            | ResponseMessage::Skipped { .. }
+            | ResponseMessage::SkipMoreVotesNeeded { .. }
+            | ResponseMessage::Stopped { .. }
+            | ResponseMessage::StopMoreVotesNeeded { .. }
+            | ResponseMessage::ImageEmbed { .. }
+            | ResponseMessage::Custom { .. }
+            | ResponseMessage::ScriptFinished
+            | ResponseMessage::ClipPlayed { .. }
+            | ResponseMessage::Summoned { .. }
+            | ResponseMessage::Deduped { .. }
+            | ResponseMessage::Undone { .. }
+            | ResponseMessage::ChannelEntriesRemoved { .. }
+            | ResponseMessage::UserEntriesRemoved { .. }
+            | ResponseMessage::Transferred { .. }
+            | ResponseMessage::NowPlaying { .. }
+            | ResponseMessage::SongInfo { .. }
+            | ResponseMessage::UserListening { .. }
+            | ResponseMessage::UserNotListening { .. }
+            | ResponseMessage::VoteInfo { .. }
+            | ResponseMessage::ChannelQueue { .. }
+            | ResponseMessage::Disconnected { .. }
+            | ResponseMessage::SoundEffects { .. }
+            | ResponseMessage::SoundEffectAdded { .. }
+            | ResponseMessage::SoundEffectRemoved { .. }
+            | ResponseMessage::Stats { .. }
+            | ResponseMessage::Top { .. }
+            | ResponseMessage::Achievements { .. }
+            | ResponseMessage::AchievementUnlocked { .. }
+            | ResponseMessage::Leaderboard { .. }
+            | ResponseMessage::NoStats
+            | ResponseMessage::HistoryExported { .. }
+            | ResponseMessage::HistoryExport { .. }
+            | ResponseMessage::Prefs { .. }
+            | ResponseMessage::Settings { .. }
+            | ResponseMessage::MessageChannelMoved { .. }
+            | ResponseMessage::DroppedAbsentEntries { .. }
+            | ResponseMessage::Blocklist { .. }
+            | ResponseMessage::BlocklistAdded { .. }
+            | ResponseMessage::BlocklistRemoved { .. }
+            | ResponseMessage::UserQueue { .. }
+            | ResponseMessage::QueueCleared { .. }
+            | ResponseMessage::QueueShuffled { .. }
+            | ResponseMessage::QueueRemoved { .. }
+            | ResponseMessage::Playlists { .. }
+            | ResponseMessage::PlaylistSaved { .. }
+            | ResponseMessage::PlaylistDeleted { .. }
+            | ResponseMessage::GuildPlaylists { .. }
+            | ResponseMessage::PlaylistSongsAdded { .. }
+            | ResponseMessage::PlaylistSongMoved { .. }
+            | ResponseMessage::PlaylistSongRemoved { .. }
+            | ResponseMessage::PlaylistPermissionSet { .. }
+            | ResponseMessage::PlaylistLog { .. }
+            | ResponseMessage::PlaylistImported { .. }
+            | ResponseMessage::Favorites { .. }
+            | ResponseMessage::FavoriteAdded { .. }
+            | ResponseMessage::FavoriteRemoved { .. }
+            | ResponseMessage::MusicBanned { .. }
+            | ResponseMessage::MusicUnbanned { .. }
+            | ResponseMessage::Help { .. }
+            | ResponseMessage::Synced { .. }
+            | ResponseMessage::MaintenanceSet { .. }
+            | ResponseMessage::BroadcastSent { .. }
+            | ResponseMessage::MaintenanceStopped { .. }
+            | ResponseMessage::DebugStatus { .. }
+            | ResponseMessage::ExtractionFailures { .. }
+            | ResponseMessage::CommandUsage { .. }
+            | ResponseMessage::ExtractorVersion { .. }
+            | ResponseMessage::ExtractorUpToDate { .. }
+            | ResponseMessage::LibraryRescanned { .. }
+            | ResponseMessage::ExtractorUpdated { .. }
+            | ResponseMessage::StreakWait
+            | ResponseMessage::Streak { .. }
+            | ResponseMessage::NoStreak
+            | ResponseMessage::StreakLeaderboard { .. }
+            | ResponseMessage::PetFound { .. }
+            | ResponseMessage::PetCollection { .. }
+            | ResponseMessage::TriviaStarted { .. }
+            | ResponseMessage::TriviaRound { .. }
+            | ResponseMessage::TriviaRoundWon { .. }
+            | ResponseMessage::TriviaRoundMissed { .. }
+            | ResponseMessage::TriviaFinished { .. }
+            | ResponseMessage::TriviaStopped
+            | ResponseMessage::Schedules { .. }
+            | ResponseMessage::ScheduleAdded { .. }
+            | ResponseMessage::ScheduleRemoved { .. }
+            | ResponseMessage::ScheduleStarted { .. }
+            | ResponseMessage::PartyStarted { .. }
+            | ResponseMessage::PartyEnded { .. }
+            | ResponseMessage::PartyHostLeft { .. } => false,
+            ResponseMessage::NoMatchingSongsError
+            | ResponseMessage::NotInVoiceChannelError
+            | ResponseMessage::UnsupportedSiteError
+            | ResponseMessage::NetworkTimeoutError
+            | ResponseMessage::VideoUnavailableError
+            | ResponseMessage::AgeRestrictedError
+            | ResponseMessage::GeoBlockedError
+            | ResponseMessage::PrivateVideoError
+            | ResponseMessage::LiveNotSupportedError
+            | ResponseMessage::ExtractorOutdatedError
+            | ResponseMessage::NoLinkError
+            | ResponseMessage::PlaybackFailedError { .. }
+            | ResponseMessage::InvalidTrimError
+            | ResponseMessage::UnknownLanguageError { .. }
+            | ResponseMessage::NoPetsError
+            | ResponseMessage::TriviaAlreadyRunningError
+            | ResponseMessage::TriviaNotRunningError
+            | ResponseMessage::TriviaNoSongsError
+            | ResponseMessage::TriviaMusicPlayingError { .. }
+            | ResponseMessage::InvalidCronError
+            | ResponseMessage::TooManySchedulesError
+            | ResponseMessage::ScheduleNotFoundError { .. }
+            | ResponseMessage::ScheduleNoSongsError { .. }
+            | ResponseMessage::ScheduleChannelInUseError { .. }
+            | ResponseMessage::ScheduleNoSpeakersError { .. }
+            | ResponseMessage::PartyAlreadyStartedError { .. }
+            | ResponseMessage::NoPartyError { .. }
+            | ResponseMessage::MissingPermissionError
+            | ResponseMessage::MissingOwnerError
+            | ResponseMessage::BlocklistAlreadyAddedError { .. }
+            | ResponseMessage::BlocklistNotFoundError { .. }
+            | ResponseMessage::InvalidBlockRuleError
+            | ResponseMessage::QueueEmptyError
+            | ResponseMessage::ClipNoSpeakersError { .. }
+            | ResponseMessage::SummonNoSpeakersError { .. }
+            | ResponseMessage::NotConnectedError { .. }
+            | ResponseMessage::SpeakerMutedError { .. }
+            | ResponseMessage::ChannelNotAllowedError { .. }
+            | ResponseMessage::TransferSameChannelError { .. }
+            | ResponseMessage::SoundEffectNotFoundError { .. }
+            | ResponseMessage::InvalidSoundEffectUrlError
+            | ResponseMessage::QueuePositionError { .. }
+            | ResponseMessage::SongInfoPositionError { .. }
+            | ResponseMessage::RemoveRangeError { .. }
+            | ResponseMessage::NothingToUndoError
+            | ResponseMessage::PlaylistNotFoundError { .. }
+            | ResponseMessage::GuildPlaylistNotFoundError { .. }
+            | ResponseMessage::GuildPlaylistExistsError { .. }
+            | ResponseMessage::PlaylistPositionError { .. }
+            | ResponseMessage::FavoriteAlreadyAddedError { .. }
+            | ResponseMessage::FavoritePositionError { .. }
+            | ResponseMessage::NoFavoritesError
+            | ResponseMessage::NoHistoryError
+            | ResponseMessage::AlreadyMusicBannedError { .. }
+            | ResponseMessage::NotMusicBannedError { .. }
+            | ResponseMessage::MusicBannedError
+            | ResponseMessage::MaintenanceError
+            | ResponseMessage::CooldownError { .. }
+            | ResponseMessage::NotEntitledError { .. }
+            | ResponseMessage::WrongChannelError { .. }
+            | ResponseMessage::Blocked { .. }
+            | ResponseMessage::SkipAlreadyVotedError { .. }
+            | ResponseMessage::StopAlreadyVotedError { .. }
+            | ResponseMessage::VoteNotInChannelError { .. }
+            | ResponseMessage::VoteSongEndedError
+            | ResponseMessage::NothingIsQueuedError { .. }
+            | ResponseMessage::NothingIsPlayingError { .. }
+            | ResponseMessage::AlreadyPlayingError { .. } => true,
+        }
+    }
+
+    pub fn is_ephemeral(&self) -> bool {
+        matches!(
+            self,
+            ResponseMessage::MusicBannedError
+                | ResponseMessage::CooldownError { .. }
+                | ResponseMessage::NotEntitledError { .. }
+                | ResponseMessage::WrongChannelError { .. }
+                | ResponseMessage::ExtractionFailures { .. }
+                | ResponseMessage::CommandUsage { .. }
+                | ResponseMessage::DebugStatus { .. }
+                | ResponseMessage::BroadcastSent { .. }
+                | ResponseMessage::FavoriteAdded { .. }
+                | ResponseMessage::FavoriteAlreadyAddedError { .. }
+        )
+    }
+
+    pub fn paged_list(&self) -> Option<(PagedList, Page)> {
+        let (list, page) = match self {
+            ResponseMessage::UserQueue { user_id, page, .. } => {
+                (PagedList::UserQueue { user_id: *user_id }, *page)
+            }
+            ResponseMessage::Playlists { user_id, page, .. } => {
+                (PagedList::Playlists { user_id: *user_id }, *page)
+            }
+            ResponseMessage::GuildPlaylists { page, .. } => (PagedList::GuildPlaylists, *page),
+            ResponseMessage::Favorites { user_id, page, .. } => {
+                (PagedList::Favorites { user_id: *user_id }, *page)
+            }
+            ResponseMessage::Leaderboard { period, page, .. } => {
+                (PagedList::Leaderboard { period: *period }, *page)
+            }
+            ResponseMessage::Help {
+                max_permission,
+                page,
+                ..
+            } => (
+                PagedList::Help {
+                    max_permission: *max_permission,
+                },
+                *page,
+            ),
+            _ => return None,
+        };
+        // Lists that fit on one page don't need any buttons.
+        if page.count > 1 {
+            Some((list, page))
+        } else {
+            None
+        }
+    }
+
+    pub fn create_embed<'e>(
+        &self,
+        embed: &'e mut serenity::builder::CreateEmbed,
+        config: &crate::config::Config,
+    ) -> &'e mut serenity::builder::CreateEmbed {
+        embed.color(if self.is_error() {
+            config.error_embed_color
+        } else {
+            config.response_embed_color
+        });
+        match self {
+            ResponseMessage::ImageEmbed { image_url } => embed.image(image_url),
+            ResponseMessage::PetFound { image_url, .. } => {
+                embed.description(self.to_string(config)).image(image_url)
+            }
+            ResponseMessage::PartyStarted { .. } => embed
+                .title(config.get_raw_message("response.party.started_title"))
+                .description(self.to_string(config)),
+            ResponseMessage::SongInfo { metadata } => {
+                embed.description(self.to_string(config));
+                let show_thumbnail = config
+                    .embed_style(self.message_key())
+                    .map_or(true, |style| style.thumbnail);
+                if let (Some(thumbnail), true) = (&metadata.thumbnail, show_thumbnail) {
+                    embed.thumbnail(thumbnail);
+                }
+
+                let queued_at_secs = metadata
+                    .queued_at
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                let fields = [
+                    ("response.song_info.uploader", metadata.uploader.clone()),
+                    (
+                        "response.song_info.duration",
+                        metadata
+                            .duration
+                            .map(|duration| format_duration(duration.as_secs())),
+                    ),
+                    (
+                        "response.song_info.views",
+                        metadata.view_count.map(|views| views.to_string()),
+                    ),
+                    (
+                        "response.song_info.uploaded",
+                        metadata.upload_date.as_deref().and_then(format_upload_date),
+                    ),
+                    ("response.song_info.site", metadata.site.clone()),
+                    // Discord shows timestamps in the reader's own timezone.
+                    (
+                        "response.song_info.queued_at",
+                        Some(format!("<t:{}:R>", queued_at_secs)),
+                    ),
+                ];
+                for (title_key, maybe_value) in fields {
+                    if let Some(value) = maybe_value {
+                        embed.field(config.get_raw_message(title_key), value, true);
+                    }
+                }
+                embed
+            }
+            ResponseMessage::Stats { summary, .. } | ResponseMessage::Top { summary, .. } => {
+                embed.description(self.to_string(config));
+                if !summary.top_tracks.is_empty() {
+                    let lines = summary.top_tracks.iter().enumerate().map(|(index, track)| {
+                        let position_string = (index + 1).to_string();
+                        let plays_string = track.plays.to_string();
+                        config.get_message(
+                            "response.top.track_line",
+                            &[
+                                ("position", &position_string),
+                                ("song_title", &track.song_title),
+                                ("song_url", &track.song_url),
+                                ("plays", &plays_string),
+                            ],
+                        )
+                    });
+                    embed.field(
+                        config.get_raw_message("response.top.tracks_title"),
+                        join_field_lines(lines),
+                        false,
+                    );
+                }
+                if !summary.top_artists.is_empty() {
+                    let lines = summary
+                        .top_artists
+                        .iter()
+                        .enumerate()
+                        .map(|(index, artist)| {
+                            let position_string = (index + 1).to_string();
+                            let plays_string = artist.plays.to_string();
+                            config.get_message(
+                                "response.top.artist_line",
+                                &[
+                                    ("position", &position_string),
+                                    ("artist", &artist.artist),
+                                    ("plays", &plays_string),
+                                ],
+                            )
+                        });
+                    embed.field(
+                        config.get_raw_message("response.top.artists_title"),
+                        join_field_lines(lines),
+                        false,
+                    );
+                }
+                embed
+            }
+            ResponseMessage::ExtractionFailures { failures } => {
+                embed.description(self.to_string(config));
+                for failure in failures {
+                    let time_string = failure.time.to_string();
+                    let guild_id_string = failure.guild_id.0.to_string();
+                    let exit_code_string = match failure.exit_code {
+                        Some(exit_code) => exit_code.to_string(),
+                        None => config
+                            .get_raw_message("response.extraction_failures.no_exit_code")
+                            .to_string(),
+                    };
+                    // Backticks would end the code block the output is shown in.
+                    let stderr_string = truncate_chars(&failure.stderr, MAX_STDERR_PREVIEW_LENGTH)
+                        .replace('`', "'");
+                    embed.field(
+                        truncate_chars(&failure.term, MAX_EMBED_FIELD_NAME_LENGTH),
+                        config.get_message(
+                            "response.extraction_failures.entry",
+                            &[
+                                ("time", &time_string),
+                                ("guild_id", &guild_id_string),
+                                ("cause", &failure.cause),
+                                ("exit_code", &exit_code_string),
+                                ("stderr", &stderr_string),
+                            ],
+                        ),
+                        false,
+                    );
+                }
+                embed
+            }
+            ResponseMessage::Leaderboard { page, .. }
+            | ResponseMessage::UserQueue { page, .. }
+            | ResponseMessage::Playlists { page, .. }
+                if page.count > 1 =>
+            {
+                let page_string = page.number.to_string();
+                let page_count_string = page.count.to_string();
+                let footer_text = config.get_message(
+                    "response.page",
+                    &[("page", &page_string), ("page_count", &page_count_string)],
+                );
+                embed
+                    .description(self.to_string(config))
+                    .footer(|footer| footer.text(footer_text))
+            }
+            _ => embed.description(self.to_string(config)),
+        }
+    }
+}
+
+const MAX_EMBED_FIELD_LENGTH: usize = 1024;
+const MAX_EMBED_FIELD_NAME_LENGTH: usize = 256;
+// Keeps a full page of extraction failures under Discord's limit for the size of an embed.
+const MAX_STDERR_PREVIEW_LENGTH: usize = 300;
+// Long playlists can have a lot of failures, only the first few fit in the message.
+const MAX_IMPORT_FAILURES_SHOWN: usize = 10;
+const MAX_IMPORT_FAILURE_LENGTH: usize = 150;
+
+/// Cuts text off at a number of characters, marking that it was cut off.
+fn truncate_chars(text: &str, max_length: usize) -> String {
+    if text.chars().count() <= max_length {
+        return text.to_string();
+    }
+    let mut truncated = text
+        .chars()
+        .take(max_length.saturating_sub(1))
+        .collect::<String>();
+    truncated.push('…');
+    truncated
+}
+
+/// Joins lines into an embed field value, leaving out any lines that won't fit.
+fn join_field_lines(lines: impl Iterator<Item = String>) -> String {
+    let mut value = String::new();
+    for line in lines {
+        if value.len() + line.len() + 1 > MAX_EMBED_FIELD_LENGTH {
+            break;
+        }
+        if !value.is_empty() {
+            value.push('\n');
+        }
+        value.push_str(&line);
+    }
+    value
+}
+
+fn format_channel_mentions(channel_ids: &[ChannelId]) -> String {
+    channel_ids
+        .iter()
+        .map(|channel_id| format!("<#{}>", channel_id.0))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Turns youtube-dl's YYYYMMDD dates into YYYY-MM-DD.
+fn format_upload_date(upload_date: &str) -> Option<String> {
+    if upload_date.len() != 8 || !upload_date.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    Some(format!(
+        "{}-{}-{}",
+        &upload_date[..4],
+        &upload_date[4..6],
+        &upload_date[6..]
+    ))
+}
+
+fn format_voters(user_ids: &[UserId], config: &crate::config::Config) -> String {
+    if user_ids.is_empty() {
+        return config
+            .get_raw_message("response.vote_info.no_voters")
+            .to_string();
+    }
+    user_ids
+        .iter()
+        .map(|user_id| format!("<@{}>", user_id.0))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// An achievement's name and description, from the messages config.
+fn achievement_text(config: &crate::config::Config, achievement: Achievement) -> (String, String) {
+    let name_key = format!("achievement.{}", achievement.name());
+    let description_key = format!("achievement.{}.description", achievement.name());
+    (
+        config.get_raw_message(&name_key).to_string(),
+        config.get_raw_message(&description_key).to_string(),
+    )
+}
+
+fn playlist_access_text(config: &crate::config::Config, access: PlaylistAccess) -> &str {
+    config.get_raw_message(match access {
+        PlaylistAccess::Everyone => "response.guild_playlist.everyone",
+        PlaylistAccess::Contributors => "response.guild_playlist.contributors",
+        PlaylistAccess::Managers => "response.guild_playlist.managers",
+    })
+}
+
+fn format_contribution(contribution: &Contribution, config: &crate::config::Config) -> String {
+    let user_id_string = contribution.user_id.0.to_string();
+    let timestamp_string = format!("<t:{}:R>", contribution.timestamp);
+    let (key, extra): (_, Vec<(&str, String)>) = match &contribution.kind {
+        ContributionKind::Created { count } => (
+            "response.guild_playlist.log.created",
+            vec![("count", count.to_string())],
+        ),
+        ContributionKind::Added { title } => (
+            "response.guild_playlist.log.added",
+            vec![("song_title", title.clone())],
+        ),
+        ContributionKind::AddedMultiple { count } => (
+            "response.guild_playlist.log.added_multiple",
+            vec![("count", count.to_string())],
+        ),
+        ContributionKind::Moved { title, to } => (
+            "response.guild_playlist.log.moved",
+            vec![("song_title", title.clone()), ("position", to.to_string())],
+        ),
+        ContributionKind::Removed { title } => (
+            "response.guild_playlist.log.removed",
+            vec![("song_title", title.clone())],
+        ),
+    };
+    let mut substitutions = vec![
+        ("user_id", user_id_string.as_str()),
+        ("time", timestamp_string.as_str()),
+    ];
+    substitutions.extend(extra.iter().map(|(name, value)| (*name, value.as_str())));
+    config.get_message(key, &substitutions)
+}
+
+fn format_block_rule(rule: &BlockRule, config: &crate::config::Config) -> String {
+    let (message_key, value) = match rule {
+        BlockRule::Domain(domain) => ("response.blocklist.domain", domain),
+        BlockRule::VideoId(id) => ("response.blocklist.video", id),
+        BlockRule::Keyword(keyword) => ("response.blocklist.keyword", keyword),
+    };
+    config.get_message(message_key, &[("value", value)])
+}
+
+pub fn format_duration(total_secs: u64) -> String {
+    let hours = total_secs / 3600;
+    let minutes = (total_secs % 3600) / 60;
+    if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else if minutes > 0 {
+        format!("{}m", minutes)
+    } else {
+        format!("{}s", total_secs)
     }
 }
@@ -0,0 +1,78 @@
+use crate::commands::CommandPermission;
+use crate::stats::LeaderboardPeriod;
+use serenity::model::prelude::*;
+use std::time::Duration;
+
+/// How many items are shown on each page of a long list.
+pub const PAGE_SIZE: usize = 10;
+
+/// How long the buttons on a paged list keep working. Interaction responses can only be edited
+/// for 15 minutes, so they're disabled a little before then.
+pub const PAGE_BUTTON_TIMEOUT: Duration = Duration::from_secs(14 * 60);
+
+/// A list that's shown a page at a time, with buttons to turn between the pages. Everything needed
+/// to show another page is kept in the buttons, so the list is looked up again when one's pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PagedList {
+    UserQueue { user_id: UserId },
+    Playlists { user_id: UserId },
+    GuildPlaylists,
+    Favorites { user_id: UserId },
+    Leaderboard { period: LeaderboardPeriod },
+    Help { max_permission: CommandPermission },
+}
+
+/// Which page of a list is being shown. Page numbers start at 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Page {
+    pub number: usize,
+    pub count: usize,
+}
+
+impl Page {
+    /// The page with this number in a list of `item_count` items. Numbers past the end are moved
+    /// onto the last page, since the list can get shorter while someone is looking through it.
+    pub fn new(number: usize, item_count: usize) -> Self {
+        let count = item_count.saturating_sub(1) / PAGE_SIZE + 1;
+        Page {
+            number: number.clamp(1, count),
+            count,
+        }
+    }
+
+    /// The index in the whole list of the first item on this page.
+    pub fn first_index(&self) -> usize {
+        (self.number - 1) * PAGE_SIZE
+    }
+
+    /// Takes the items on this page out of the whole list.
+    pub fn items<T>(&self, items: impl IntoIterator<Item = T>) -> Vec<T> {
+        items
+            .into_iter()
+            .skip(self.first_index())
+            .take(PAGE_SIZE)
+            .collect()
+    }
+
+    pub fn has_previous(&self) -> bool {
+        self.number > 1
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.number < self.count
+    }
+
+    pub fn previous(&self) -> Page {
+        Page {
+            number: self.number.saturating_sub(1).max(1),
+            count: self.count,
+        }
+    }
+
+    pub fn next(&self) -> Page {
+        Page {
+            number: (self.number + 1).min(self.count),
+            count: self.count,
+        }
+    }
+}
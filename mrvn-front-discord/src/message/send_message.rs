@@ -1,15 +1,54 @@
 use crate::config::Config;
-use crate::message::Message;
+use crate::message::{ComponentId, Message, Page, PagedList, PAGE_BUTTON_TIMEOUT};
+use crate::now_playing_card::{NowPlayingCard, CARD_FILENAME};
 use futures::prelude::*;
-use mrvn_back_ytdl::Song;
-use mrvn_model::{GuildActionMessage, GuildModel};
-use serenity::model::prelude::ChannelId;
+use mrvn_model::{GuildActionMessage, PlaybackState};
+use serenity::builder::{CreateComponents, CreateInteractionResponseFollowup};
+use serenity::http::AttachmentType;
+use serenity::model::prelude::{ChannelId, InteractionId};
 use serenity::{
     client::Context,
     model::interactions::{
-        application_command::ApplicationCommandInteraction, InteractionResponseType,
+        application_command::ApplicationCommandInteraction,
+        message_component::{ButtonStyle, MessageComponentInteraction},
+        InteractionApplicationCommandCallbackDataFlags, InteractionResponseType,
     },
 };
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Interaction responses can only be created or edited for this long after the command was run.
+const INTERACTION_TOKEN_LIFETIME_SECS: i64 = 15 * 60;
+// The error code Discord uses when an interaction token has expired.
+const INVALID_WEBHOOK_TOKEN_CODE: isize = 50027;
+
+static EXPIRED_INTERACTION_RESPONSES: AtomicU64 = AtomicU64::new(0);
+
+/// How many responses have been posted as regular messages because the command's interaction
+/// token had expired, usually after a very slow playlist load.
+pub fn expired_interaction_responses() -> u64 {
+    EXPIRED_INTERACTION_RESPONSES.load(Ordering::Relaxed)
+}
+
+fn record_expired_interaction() {
+    log::warn!("Interaction token has expired, sending the response as a regular message");
+    EXPIRED_INTERACTION_RESPONSES.fetch_add(1, Ordering::Relaxed);
+}
+
+fn is_interaction_expired(interaction_id: InteractionId) -> bool {
+    let age = chrono::Utc::now() - interaction_id.created_at();
+    age.num_seconds() >= INTERACTION_TOKEN_LIFETIME_SECS
+}
+
+fn is_expired_token_error(why: &serenity::Error) -> bool {
+    match why {
+        serenity::Error::Http(http_error) => matches!(
+            http_error.as_ref(),
+            serenity::http::HttpError::UnsuccessfulRequest(response)
+                if response.error.code == INVALID_WEBHOOK_TOKEN_CODE
+        ),
+        _ => false,
+    }
+}
 
 #[derive(Clone, Copy)]
 pub enum SendMessageDestination<'interaction> {
@@ -18,18 +57,211 @@ pub enum SendMessageDestination<'interaction> {
         interaction: &'interaction ApplicationCommandInteraction,
         is_edit: bool,
     },
+    /// A button was pressed. The message the button is on is edited to show the first message,
+    /// unless it's an error or ephemeral, which is only shown to the user that pressed it.
+    Component(&'interaction MessageComponentInteraction),
+}
+
+fn create_components<'c>(
+    components: &'c mut CreateComponents,
+    message: &Message,
+    config: &Config,
+) -> &'c mut CreateComponents {
+    if let Some(vote_button) = message.vote_button() {
+        components.create_action_row(|row| {
+            row.create_button(|button| {
+                button
+                    .style(ButtonStyle::Primary)
+                    .label(config.get_raw_message("button.vote"))
+                    .custom_id(ComponentId::Vote(vote_button).custom_id())
+            })
+        });
+    }
+    if let Some(voice_channel_id) = message.favorite_button() {
+        components.create_action_row(|row| {
+            row.create_button(|button| {
+                button
+                    .style(ButtonStyle::Secondary)
+                    .label(config.get_raw_message("button.favorite"))
+                    .custom_id(ComponentId::Favorite { voice_channel_id }.custom_id())
+            })
+        });
+    }
+    if let Some(party_link) = message.party_link() {
+        components.create_action_row(|row| {
+            row.create_button(|button| {
+                button
+                    .style(ButtonStyle::Link)
+                    .label(config.get_raw_message("button.join_party"))
+                    .url(party_link)
+            })
+        });
+    }
+    if let Some((list, page)) = message.paged_list() {
+        create_page_buttons(components, list, page, config, true);
+    }
+    components
+}
+
+fn create_page_buttons<'c>(
+    components: &'c mut CreateComponents,
+    list: PagedList,
+    page: Page,
+    config: &Config,
+    is_enabled: bool,
+) -> &'c mut CreateComponents {
+    components.create_action_row(|row| {
+        row.create_button(|button| {
+            button
+                .style(ButtonStyle::Secondary)
+                .label(config.get_raw_message("button.previous_page"))
+                .custom_id(
+                    ComponentId::Page {
+                        list,
+                        number: page.previous().number,
+                    }
+                    .custom_id(),
+                )
+                .disabled(!is_enabled || !page.has_previous())
+        })
+        .create_button(|button| {
+            button
+                .style(ButtonStyle::Secondary)
+                .label(config.get_raw_message("button.next_page"))
+                .custom_id(
+                    ComponentId::Page {
+                        list,
+                        number: page.next().number,
+                    }
+                    .custom_id(),
+                )
+                .disabled(!is_enabled || !page.has_next())
+        })
+    })
+}
+
+// Page buttons stop working once the command's interaction token expires, so they're disabled
+// before then instead of failing when they're pressed. Turning pages edits the same message, so
+// this covers every page it ends up showing.
+fn disable_page_buttons_later(
+    ctx: &Context,
+    interaction: &ApplicationCommandInteraction,
+    message: &Message,
+    config: &Config,
+) {
+    let (list, page) = match message.paged_list() {
+        Some(paged_list) => paged_list,
+        None => return,
+    };
+    let ctx = ctx.clone();
+    let interaction = interaction.clone();
+    let config = config.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(PAGE_BUTTON_TIMEOUT).await;
+        let edit_res = interaction
+            .edit_original_interaction_response(&ctx.http, |response| {
+                response.components(|components| {
+                    create_page_buttons(components, list, page, &config, false)
+                })
+            })
+            .await;
+        if let Err(why) = edit_res {
+            log::error!("Error while disabling page buttons: {}", why);
+        }
+    });
+}
+
+pub async fn send_channel_message(
+    ctx: &Context,
+    channel_id: ChannelId,
+    message: &Message,
+    config: &Config,
+) -> serenity::Result<serenity::model::channel::Message> {
+    channel_id
+        .send_message(&ctx.http, |create_message| {
+            match message.plain_text(config) {
+                Some(content) => create_message.content(content),
+                None => create_message.embed(|embed| {
+                    message.create_embed(embed, config);
+                    if message.card().is_some() {
+                        embed.image(NowPlayingCard::attachment_url());
+                    }
+                    embed
+                }),
+            }
+            .components(|components| create_components(components, message, config));
+            if let Some(card) = message.card() {
+                create_message.add_file(AttachmentType::Bytes {
+                    data: card.png().to_vec().into(),
+                    filename: CARD_FILENAME.to_string(),
+                });
+            }
+            if let Some((file_name, data)) = message.file() {
+                create_message.add_file(AttachmentType::Bytes {
+                    data: data.to_vec().into(),
+                    filename: file_name.to_string(),
+                });
+            }
+            create_message
+        })
+        .await
+}
+
+// Followups work the same for every kind of interaction, so they're sent using the token.
+async fn send_followup_message(
+    ctx: &Context,
+    interaction_token: &str,
+    channel_id: ChannelId,
+    message: &Message,
+    config: &Config,
+) -> serenity::Result<serenity::model::channel::Message> {
+    let mut followup = CreateInteractionResponseFollowup::default();
+    if message.is_ephemeral() {
+        followup.flags(InteractionApplicationCommandCallbackDataFlags::EPHEMERAL);
+    }
+    match message.plain_text(config) {
+        Some(content) => followup.content(content),
+        None => followup.create_embed(|embed| message.create_embed(embed, config)),
+    }
+    .components(|components| create_components(components, message, config));
+    let followup_map = serde_json::Value::Object(serenity::utils::hashmap_to_json_map(followup.0));
+
+    match ctx
+        .http
+        .create_followup_message(interaction_token, &followup_map)
+        .await
+    {
+        // The token can also expire while the earlier messages are being sent.
+        Err(why) if is_expired_token_error(&why) => {
+            record_expired_interaction();
+            send_channel_message(ctx, channel_id, message, config).await
+        }
+        res => res,
+    }
 }
 
 pub async fn send_messages(
     config: &Config,
     ctx: &Context,
     destination: SendMessageDestination<'_>,
-    guild_model: &mut GuildModel<Song>,
+    playback: &mut PlaybackState,
     mut messages: Vec<Message>,
 ) -> Result<(), crate::error::Error> {
+    // Once the interaction has expired, everything is sent as regular messages.
+    let destination = match destination {
+        SendMessageDestination::Interaction { interaction, .. }
+            if is_interaction_expired(interaction.id) =>
+        {
+            record_expired_interaction();
+            SendMessageDestination::Channel(interaction.channel_id)
+        }
+        destination => destination,
+    };
+
     let message_channel_id = match destination {
         SendMessageDestination::Channel(channel) => channel,
         SendMessageDestination::Interaction { interaction, .. } => interaction.channel_id,
+        SendMessageDestination::Component(interaction) => interaction.channel_id,
     };
 
     // Action messages are special: we only keep the latest one around. This also means out of
@@ -49,49 +281,166 @@ pub async fn send_messages(
     // Send the first message as an interaction response, if our destination is an interaction.
     let maybe_first_message = match destination {
         SendMessageDestination::Channel(_) => None,
-        SendMessageDestination::Interaction { .. } => messages_iter.next(),
+        SendMessageDestination::Interaction { .. } | SendMessageDestination::Component(_) => {
+            messages_iter.next()
+        }
     };
     let first_message_future = async {
-        if let (
-            SendMessageDestination::Interaction {
-                interaction,
-                is_edit,
-            },
-            Some(first_message),
-        ) = (destination, maybe_first_message)
-        {
-            if is_edit {
-                interaction
+        match (destination, maybe_first_message) {
+            (
+                SendMessageDestination::Interaction {
+                    interaction,
+                    is_edit: true,
+                },
+                Some(first_message),
+            ) => {
+                let edit_res = interaction
                     .edit_original_interaction_response(&ctx.http, |response| {
-                        response.create_embed(|embed| first_message.create_embed(embed, config))
+                        match first_message.plain_text(config) {
+                            Some(content) => {
+                                // Clears the embed the working message might have had.
+                                response
+                                    .0
+                                    .insert("embeds", serde_json::Value::Array(Vec::new()));
+                                response.content(content)
+                            }
+                            None => response
+                                .content("")
+                                .create_embed(|embed| first_message.create_embed(embed, config)),
+                        }
+                        .components(|components| {
+                            create_components(components, &first_message, config)
+                        })
                     })
-                    .await
-                    .map_err(crate::error::Error::Serenity)?;
-            } else {
+                    .await;
+                match edit_res {
+                    Ok(_) => disable_page_buttons_later(ctx, interaction, &first_message, config),
+                    // The token can still expire between checking it and editing the response.
+                    Err(why) if is_expired_token_error(&why) => {
+                        record_expired_interaction();
+                        send_channel_message(ctx, interaction.channel_id, &first_message, config)
+                            .await
+                            .map_err(crate::error::Error::Serenity)?;
+                    }
+                    Err(why) => return Err(crate::error::Error::Serenity(why)),
+                }
+            }
+            (
+                SendMessageDestination::Interaction {
+                    interaction,
+                    is_edit: false,
+                },
+                Some(first_message),
+            ) => {
                 interaction
                     .create_interaction_response(&ctx.http, |response| {
                         response
                             .kind(InteractionResponseType::ChannelMessageWithSource)
                             .interaction_response_data(|data| {
-                                data.create_embed(|embed| first_message.create_embed(embed, config))
+                                if first_message.is_ephemeral() {
+                                    data.flags(
+                                        InteractionApplicationCommandCallbackDataFlags::EPHEMERAL,
+                                    );
+                                }
+                                match first_message.plain_text(config) {
+                                    Some(content) => data.content(content),
+                                    None => data.create_embed(|embed| {
+                                        first_message.create_embed(embed, config)
+                                    }),
+                                }
+                                .components(|components| {
+                                    create_components(components, &first_message, config)
+                                })
                             })
                     })
                     .await
                     .map_err(crate::error::Error::Serenity)?;
+                disable_page_buttons_later(ctx, interaction, &first_message, config);
+            }
+            (SendMessageDestination::Component(interaction), Some(first_message)) => {
+                interaction
+                    .create_interaction_response(&ctx.http, |response| {
+                        if first_message.is_error() || first_message.is_ephemeral() {
+                            response
+                                .kind(InteractionResponseType::ChannelMessageWithSource)
+                                .interaction_response_data(|data| {
+                                    data.flags(
+                                        InteractionApplicationCommandCallbackDataFlags::EPHEMERAL,
+                                    );
+                                    match first_message.plain_text(config) {
+                                        Some(content) => data.content(content),
+                                        None => data.create_embed(|embed| {
+                                            first_message.create_embed(embed, config)
+                                        }),
+                                    }
+                                })
+                        } else {
+                            // Replacing the components removes the button once the vote is over.
+                            response
+                                .kind(InteractionResponseType::UpdateMessage)
+                                .interaction_response_data(|data| {
+                                    match first_message.plain_text(config) {
+                                        Some(content) => {
+                                            // Clears the embed of the message being replaced.
+                                            data.0.insert(
+                                                "embeds",
+                                                serde_json::Value::Array(Vec::new()),
+                                            );
+                                            data.content(content)
+                                        }
+                                        None => data.content("").create_embed(|embed| {
+                                            first_message.create_embed(embed, config)
+                                        }),
+                                    }
+                                    .components(|components| {
+                                        create_components(components, &first_message, config)
+                                    })
+                                })
+                        }
+                    })
+                    .await
+                    .map_err(crate::error::Error::Serenity)?;
             }
+            _ => {}
         }
         Ok(())
     };
 
-    // Send each remaining message as a regular message. If the message is the possible one
-    // action message, keep track of its ID so we can record it later.
+    // Action messages go to the session thread while there is one.
+    let action_channel_id = playback.session_thread().unwrap_or(message_channel_id);
+
+    // Send each remaining message as a followup to the interaction, or as a regular message for
+    // other destinations. Action messages going to the session thread are always regular
+    // messages, and so are messages with a card or file, since followups can't have files attached
+    // here.
+    // If the message is the possible one action message, keep track of its ID so we can record
+    // it later.
     let remaining_messages_future = future::try_join_all(messages_iter.map(|message| async move {
-        let channel_message = message_channel_id
-            .send_message(&ctx.http, |create_message| {
-                create_message.embed(|embed| message.create_embed(embed, config))
-            })
-            .await
-            .map_err(crate::error::Error::Serenity)?;
+        let channel_message = match destination {
+            SendMessageDestination::Interaction { interaction, .. }
+                if (!message.is_action() || action_channel_id == message_channel_id)
+                    && message.card().is_none()
+                    && message.file().is_none() =>
+            {
+                send_followup_message(
+                    ctx,
+                    &interaction.token,
+                    interaction.channel_id,
+                    &message,
+                    config,
+                )
+                .await
+            }
+            _ => {
+                let channel_id = if message.is_action() {
+                    action_channel_id
+                } else {
+                    message_channel_id
+                };
+                send_channel_message(ctx, channel_id, &message, config).await
+            }
+        }
+        .map_err(crate::error::Error::Serenity)?;
 
         if message.is_action() {
             Ok(Some(channel_message))
@@ -100,9 +449,15 @@ pub async fn send_messages(
         }
     }));
 
+    // Followup messages can only be sent once the interaction has been responded to.
+    let send_all_messages_future = async {
+        first_message_future.await?;
+        remaining_messages_future.await
+    };
+
     // Delete the guild's latest action message from before this operation, if this operation
     // sent an action message.
-    let old_action_message = guild_model.last_action_message();
+    let old_action_message = playback.last_action_message();
     let delete_old_action_message_future = async {
         if maybe_last_action_message_index.is_some() {
             if let Some(old_action_message) = old_action_message {
@@ -117,11 +472,8 @@ pub async fn send_messages(
     };
 
     // Execute all the message sending!
-    let (_, remaining_messages, _) = futures::try_join!(
-        first_message_future,
-        remaining_messages_future,
-        delete_old_action_message_future
-    )?;
+    let (remaining_messages, _) =
+        futures::try_join!(send_all_messages_future, delete_old_action_message_future)?;
 
     // Set the guild's last action message to the message we sent, if there was one.
     // If we were expecting an action message but there isn't one collected after sending,
@@ -131,7 +483,7 @@ pub async fn send_messages(
         let maybe_sent_message = remaining_messages
             .iter()
             .find_map(|maybe_message| maybe_message.as_ref());
-        guild_model.set_last_action_message(maybe_sent_message.map(|sent_message| {
+        playback.set_last_action_message(maybe_sent_message.map(|sent_message| {
             GuildActionMessage {
                 channel_id: sent_message.channel_id,
                 message_id: sent_message.id,
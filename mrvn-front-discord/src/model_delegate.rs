@@ -1,4 +1,5 @@
 use mrvn_model::AppModelDelegate;
+use serenity::cache::Cache;
 use serenity::{model::prelude::*, prelude::*};
 
 pub struct ModelDelegate {
@@ -10,8 +11,14 @@ impl ModelDelegate {
         ctx: &Context,
         guild_id: GuildId,
     ) -> Result<ModelDelegate, crate::error::Error> {
-        let guild = ctx
-            .cache
+        ModelDelegate::from_cache(&ctx.cache, guild_id).await
+    }
+
+    pub async fn from_cache(
+        cache: &Cache,
+        guild_id: GuildId,
+    ) -> Result<ModelDelegate, crate::error::Error> {
+        let guild = cache
             .guild(guild_id)
             .await
             .ok_or(crate::error::Error::UnknownGuild(guild_id))?;
@@ -24,10 +31,39 @@ impl ModelDelegate {
             .get(&user_id)
             .and_then(|state| state.channel_id)
     }
+
+    /// Everyone in the voice channel apart from bots.
+    pub fn users_in_voice_channel(&self, channel_id: ChannelId) -> Vec<UserId> {
+        self.guild
+            .voice_states
+            .values()
+            .filter(|state| state.channel_id == Some(channel_id))
+            .map(|state| state.user_id)
+            .filter(|user_id| {
+                !self
+                    .guild
+                    .members
+                    .get(user_id)
+                    .is_some_and(|member| member.user.bot)
+            })
+            .collect()
+    }
 }
 
 impl AppModelDelegate for ModelDelegate {
     fn is_user_in_voice_channel(&self, user_id: UserId, channel_id: ChannelId) -> bool {
         self.get_user_voice_channel(user_id) == Some(channel_id)
     }
+
+    fn is_user_in_any_voice_channel(&self, user_id: UserId) -> bool {
+        self.get_user_voice_channel(user_id).is_some()
+    }
+
+    fn user_roles(&self, user_id: UserId) -> Vec<RoleId> {
+        self.guild
+            .members
+            .get(&user_id)
+            .map(|member| member.roles.clone())
+            .unwrap_or_default()
+    }
 }
@@ -0,0 +1,186 @@
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageOutputFormat, Rgba, RgbaImage};
+use mrvn_back_ytdl::SongMetadata;
+use std::io::Cursor;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// What the card is called when it's attached to a message.
+pub const CARD_FILENAME: &str = "now_playing.png";
+
+const CARD_WIDTH: u32 = 640;
+const CARD_HEIGHT: u32 = 200;
+const PADDING: u32 = 20;
+const ART_SIZE: u32 = CARD_HEIGHT - PADDING * 2;
+const AVATAR_SIZE: u32 = 56;
+const PROGRESS_BAR_HEIGHT: u32 = 10;
+
+const BACKGROUND_COLOR: Rgba<u8> = Rgba([43, 45, 49, 255]);
+// Behind the progress bar, and in place of art for songs that don't have any.
+const TRACK_COLOR: Rgba<u8> = Rgba([79, 84, 92, 255]);
+
+// Thumbnails and avatars that take longer than this leave the song with a plain message.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// A generated image showing the song that's playing, attached to the message announcing it.
+#[derive(Clone)]
+pub struct NowPlayingCard {
+    png: Arc<[u8]>,
+}
+
+impl NowPlayingCard {
+    pub fn png(&self) -> &[u8] {
+        &self.png
+    }
+
+    pub fn attachment_url() -> String {
+        format!("attachment://{}", CARD_FILENAME)
+    }
+}
+
+impl std::fmt::Debug for NowPlayingCard {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "NowPlayingCard({} bytes)", self.png.len())
+    }
+}
+
+/// Draws now-playing cards with the song's thumbnail, the avatar of whoever queued it and how far
+/// through it playback is. Everything is drawn in-process, so there's nothing else to install.
+pub struct CardRenderer {
+    client: reqwest::Client,
+    accent_color: Rgba<u8>,
+}
+
+impl CardRenderer {
+    pub fn new(accent_color: u32) -> Self {
+        CardRenderer {
+            client: reqwest::Client::new(),
+            accent_color: Rgba([
+                (accent_color >> 16) as u8,
+                (accent_color >> 8) as u8,
+                accent_color as u8,
+                255,
+            ]),
+        }
+    }
+
+    pub async fn render(
+        &self,
+        metadata: &SongMetadata,
+        avatar_url: &str,
+        position: Duration,
+    ) -> Result<NowPlayingCard, crate::error::Error> {
+        let art = match &metadata.thumbnail {
+            Some(thumbnail_url) => Some(self.fetch_image(thumbnail_url).await?),
+            None => None,
+        };
+        let avatar = self.fetch_image(avatar_url).await?;
+        let progress = match metadata.duration {
+            Some(duration) if !duration.is_zero() => {
+                (position.as_secs_f32() / duration.as_secs_f32()).clamp(0., 1.)
+            }
+            // Livestreams don't have an end to show progress towards.
+            _ => 0.,
+        };
+
+        // Resizing the art takes long enough that it shouldn't hold up other tasks.
+        let png = tokio::task::block_in_place(|| {
+            self.draw(art.as_ref(), &avatar, progress)
+                .map_err(crate::error::Error::Image)
+        })?;
+        Ok(NowPlayingCard { png: png.into() })
+    }
+
+    async fn fetch_image(&self, url: &str) -> Result<DynamicImage, crate::error::Error> {
+        let bytes = self
+            .client
+            .get(url)
+            .timeout(FETCH_TIMEOUT)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(crate::error::Error::Request)?
+            .bytes()
+            .await
+            .map_err(crate::error::Error::Request)?;
+        image::load_from_memory(&bytes).map_err(crate::error::Error::Image)
+    }
+
+    fn draw(
+        &self,
+        art: Option<&DynamicImage>,
+        avatar: &DynamicImage,
+        progress: f32,
+    ) -> image::ImageResult<Vec<u8>> {
+        let mut card = RgbaImage::from_pixel(CARD_WIDTH, CARD_HEIGHT, BACKGROUND_COLOR);
+
+        // Thumbnails are usually wider than they are tall, so they're cropped to a square.
+        match art {
+            Some(art) => {
+                let art = art.resize_to_fill(ART_SIZE, ART_SIZE, FilterType::Triangle);
+                image::imageops::overlay(
+                    &mut card,
+                    &art.to_rgba8(),
+                    PADDING as i64,
+                    PADDING as i64,
+                );
+            }
+            None => fill_rect(&mut card, PADDING, PADDING, ART_SIZE, ART_SIZE, TRACK_COLOR),
+        }
+
+        let mut avatar = avatar
+            .resize_to_fill(AVATAR_SIZE, AVATAR_SIZE, FilterType::Triangle)
+            .to_rgba8();
+        cut_circle(&mut avatar);
+        image::imageops::overlay(
+            &mut card,
+            &avatar,
+            (CARD_WIDTH - PADDING - AVATAR_SIZE) as i64,
+            PADDING as i64,
+        );
+
+        let bar_x = PADDING * 2 + ART_SIZE;
+        let bar_y = CARD_HEIGHT - PADDING - PROGRESS_BAR_HEIGHT;
+        let bar_width = CARD_WIDTH - PADDING - bar_x;
+        fill_rect(
+            &mut card,
+            bar_x,
+            bar_y,
+            bar_width,
+            PROGRESS_BAR_HEIGHT,
+            TRACK_COLOR,
+        );
+        fill_rect(
+            &mut card,
+            bar_x,
+            bar_y,
+            (bar_width as f32 * progress) as u32,
+            PROGRESS_BAR_HEIGHT,
+            self.accent_color,
+        );
+
+        let mut png = Cursor::new(Vec::new());
+        DynamicImage::ImageRgba8(card).write_to(&mut png, ImageOutputFormat::Png)?;
+        Ok(png.into_inner())
+    }
+}
+
+fn fill_rect(image: &mut RgbaImage, x: u32, y: u32, width: u32, height: u32, color: Rgba<u8>) {
+    for pixel_y in y..y + height {
+        for pixel_x in x..x + width {
+            image.put_pixel(pixel_x, pixel_y, color);
+        }
+    }
+}
+
+// Makes everything outside the largest circle that fits in the image transparent.
+fn cut_circle(image: &mut RgbaImage) {
+    let radius = image.width().min(image.height()) as f32 / 2.;
+    for (x, y, pixel) in image.enumerate_pixels_mut() {
+        let dx = x as f32 + 0.5 - radius;
+        let dy = y as f32 + 0.5 - radius;
+        if dx * dx + dy * dy > radius * radius {
+            pixel[3] = 0;
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use crate::cluster::RedisConnection;
+use dashmap::DashMap;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serenity::model::prelude::*;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+const REDIS_KEY_PREFIX: &str = "mrvn:data";
+// How many times an update is tried again when another process changes the value first.
+const MAX_UPDATE_ATTEMPTS: usize = 5;
+// How long to wait before trying to write a file again after it couldn't be written.
+const WRITE_RETRY_DELAY: Duration = Duration::from_secs(5);
+// How many times a file is tried before its value is given up on.
+const MAX_WRITE_ATTEMPTS: usize = 3;
+
+lazy_static::lazy_static! {
+    // Saves the value only if what's stored is still what the update started from.
+    static ref COMPARE_AND_SET_SCRIPT: redis::Script = redis::Script::new(
+        r"
+        local current = redis.call('GET', KEYS[1])
+        if (current == false and ARGV[1] == '0') or (ARGV[1] == '1' and current == ARGV[2]) then
+            redis.call('SET', KEYS[1], ARGV[3])
+            return 1
+        end
+        return 0
+        ",
+    );
+}
+
+/// Somewhere to keep state that should survive restarts. Values are stored as JSON strings under
+/// a key.
+pub trait Persistence: Send + Sync {
+    fn load(&self, key: &str) -> Result<Option<String>, crate::error::Error>;
+    fn save(&self, key: &str, value: &str) -> Result<(), crate::error::Error>;
+
+    /// Saves the value only if what's stored is still `expected`, or nothing if that's `None`.
+    /// Returns `false` if another process has changed it since. Persistence that isn't shared
+    /// always saves.
+    fn compare_and_save(
+        &self,
+        key: &str,
+        _expected: Option<&str>,
+        value: &str,
+    ) -> Result<bool, crate::error::Error> {
+        self.save(key, value).map(|()| true)
+    }
+
+    /// Whether other processes can change what's stored, so values have to be loaded again each
+    /// time they're used instead of kept in memory.
+    fn is_shared(&self) -> bool {
+        false
+    }
+
+    /// Blocks until everything that's been saved is stored, so it isn't lost when the process
+    /// exits.
+    fn flush(&self) {}
+}
+
+/// Stores each key as a JSON file in a directory. Files are written on a background thread, so
+/// saving doesn't hold up the async code that changed the value.
+pub struct FilePersistence {
+    directory: PathBuf,
+    writes: Arc<PendingWrites>,
+}
+
+// Values that have been saved but not written yet. They stay here until they've been written, so
+// loading them in the meantime doesn't find an older file. If a key is saved again before it's
+// written, only the latest value is written.
+#[derive(Default)]
+struct PendingWrites {
+    values: Mutex<HashMap<PathBuf, String>>,
+    added: Condvar,
+    // Notified whenever there's nothing left to write.
+    written: Condvar,
+}
+
+impl FilePersistence {
+    pub fn new(directory: impl Into<PathBuf>) -> Result<Self, crate::error::Error> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory).map_err(crate::error::Error::Io)?;
+
+        let writes = Arc::new(PendingWrites::default());
+        let thread_writes = writes.clone();
+        std::thread::Builder::new()
+            .name("file-persistence".to_string())
+            .spawn(move || write_files(&thread_writes))
+            .map_err(crate::error::Error::Io)?;
+        Ok(FilePersistence { directory, writes })
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(format!("{}.json", key))
+    }
+}
+
+fn write_files(writes: &PendingWrites) {
+    // Files that couldn't be written, with how many times they've been tried and when to try them
+    // again. Other files are written in the meantime, so one that keeps failing doesn't hold them
+    // up.
+    let mut failures: HashMap<PathBuf, (usize, Instant)> = HashMap::new();
+    loop {
+        let batch = {
+            let mut values = writes.values.lock().unwrap();
+            loop {
+                let now = Instant::now();
+                let batch: Vec<_> = values
+                    .iter()
+                    .filter(|(path, _)| match failures.get(*path) {
+                        Some((_, retry_time)) => now >= *retry_time,
+                        None => true,
+                    })
+                    .map(|(path, value)| (path.clone(), value.clone()))
+                    .collect();
+                if !batch.is_empty() {
+                    break batch;
+                }
+
+                let next_retry_time = values
+                    .keys()
+                    .filter_map(|path| failures.get(path))
+                    .map(|(_, retry_time)| *retry_time)
+                    .min();
+                values = match next_retry_time {
+                    Some(retry_time) => {
+                        let timeout = retry_time.saturating_duration_since(now);
+                        writes.added.wait_timeout(values, timeout).unwrap().0
+                    }
+                    None => writes.added.wait(values).unwrap(),
+                };
+            }
+        };
+
+        let mut finished = Vec::new();
+        for (path, value) in batch {
+            match write_file(&path, &value) {
+                Ok(()) => {
+                    failures.remove(&path);
+                    finished.push((path, value));
+                }
+                Err(why) => {
+                    let attempts = failures.get(&path).map_or(0, |(attempts, _)| *attempts) + 1;
+                    if attempts < MAX_WRITE_ATTEMPTS {
+                        log::error!(
+                            "Error while writing {}, trying again: {}",
+                            path.display(),
+                            why
+                        );
+                        failures.insert(path, (attempts, Instant::now() + WRITE_RETRY_DELAY));
+                    } else {
+                        log::error!(
+                            "Gave up writing {} after {} attempts: {}",
+                            path.display(),
+                            attempts,
+                            why
+                        );
+                        failures.remove(&path);
+                        finished.push((path, value));
+                    }
+                }
+            }
+        }
+
+        // Values saved again while they were being written still need writing.
+        let mut values = writes.values.lock().unwrap();
+        for (path, value) in finished {
+            if values.get(&path) == Some(&value) {
+                values.remove(&path);
+            }
+        }
+        if values.is_empty() {
+            writes.written.notify_all();
+        }
+    }
+}
+
+// Writes to a temporary file first so a crash can't leave a half-written file behind.
+fn write_file(path: &Path, value: &str) -> std::io::Result<()> {
+    let temp_path = path.with_extension("json.tmp");
+    std::fs::write(&temp_path, value)?;
+    std::fs::rename(&temp_path, path)
+}
+
+impl Persistence for FilePersistence {
+    fn load(&self, key: &str) -> Result<Option<String>, crate::error::Error> {
+        let path = self.path(key);
+        if let Some(value) = self.writes.values.lock().unwrap().get(&path) {
+            return Ok(Some(value.clone()));
+        }
+
+        match std::fs::read_to_string(path) {
+            Ok(value) => Ok(Some(value)),
+            Err(why) if why.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(why) => Err(crate::error::Error::Io(why)),
+        }
+    }
+
+    fn save(&self, key: &str, value: &str) -> Result<(), crate::error::Error> {
+        self.writes
+            .values
+            .lock()
+            .unwrap()
+            .insert(self.path(key), value.to_string());
+        self.writes.added.notify_one();
+        Ok(())
+    }
+
+    fn flush(&self) {
+        let mut values = self.writes.values.lock().unwrap();
+        while !values.is_empty() {
+            values = self.writes.written.wait(values).unwrap();
+        }
+    }
+}
+
+/// Stores each key as a string in Redis, so every process in a cluster sees the same data.
+pub struct RedisPersistence {
+    redis: Arc<RedisConnection>,
+}
+
+impl RedisPersistence {
+    pub fn new(redis: Arc<RedisConnection>) -> Self {
+        RedisPersistence { redis }
+    }
+
+    fn key(key: &str) -> String {
+        format!("{}:{}", REDIS_KEY_PREFIX, key)
+    }
+}
+
+// Values are loaded and saved from synchronous code all over the bot, so the worker thread is
+// handed over to the runtime's other tasks while it waits for Redis.
+fn block_on<T>(future: impl std::future::Future<Output = T>) -> T {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
+
+impl Persistence for RedisPersistence {
+    fn load(&self, key: &str) -> Result<Option<String>, crate::error::Error> {
+        block_on(self.redis.query(redis::cmd("GET").arg(Self::key(key))))
+    }
+
+    fn save(&self, key: &str, value: &str) -> Result<(), crate::error::Error> {
+        block_on(
+            self.redis
+                .query(redis::cmd("SET").arg(Self::key(key)).arg(value)),
+        )
+    }
+
+    fn compare_and_save(
+        &self,
+        key: &str,
+        expected: Option<&str>,
+        value: &str,
+    ) -> Result<bool, crate::error::Error> {
+        let mut invocation = COMPARE_AND_SET_SCRIPT.prepare_invoke();
+        invocation
+            .key(Self::key(key))
+            .arg(if expected.is_some() { "1" } else { "0" })
+            .arg(expected.unwrap_or_default())
+            .arg(value);
+        block_on(self.redis.invoke(&invocation))
+    }
+
+    fn is_shared(&self) -> bool {
+        true
+    }
+}
+
+/// Used when no data directory is configured. Nothing is saved, so state only lasts until the bot
+/// restarts.
+pub struct MemoryPersistence;
+
+impl Persistence for MemoryPersistence {
+    fn load(&self, _key: &str) -> Result<Option<String>, crate::error::Error> {
+        Ok(None)
+    }
+
+    fn save(&self, _key: &str, _value: &str) -> Result<(), crate::error::Error> {
+        Ok(())
+    }
+}
+
+/// A value that's saved every time it's updated. When other processes share the persistence, an
+/// update is run again on the latest value if another process saved it first.
+pub struct Persisted<T> {
+    key: String,
+    persistence: Arc<dyn Persistence>,
+    value: Mutex<T>,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> Persisted<T> {
+    pub fn load(
+        persistence: Arc<dyn Persistence>,
+        key: String,
+    ) -> Result<Self, crate::error::Error> {
+        let value = match persistence.load(&key)? {
+            Some(json) => serde_json::from_str(&json).map_err(crate::error::Error::Json)?,
+            None => T::default(),
+        };
+        Ok(Persisted {
+            key,
+            persistence,
+            value: Mutex::new(value),
+        })
+    }
+
+    pub fn read<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        let mut value = self.value.lock().unwrap();
+        self.refresh(&mut value);
+        f(&value)
+    }
+
+    pub fn update<R>(&self, mut f: impl FnMut(&mut T) -> R) -> R {
+        let mut value = self.value.lock().unwrap();
+        let mut attempt = 1;
+        loop {
+            let loaded_json = self.refresh(&mut value);
+            let res = f(&mut value);
+
+            let save_res = serde_json::to_string(&*value)
+                .map_err(crate::error::Error::Json)
+                .and_then(|json| {
+                    self.persistence
+                        .compare_and_save(&self.key, loaded_json.as_deref(), &json)
+                });
+            match save_res {
+                Ok(true) => return res,
+                Ok(false) if attempt < MAX_UPDATE_ATTEMPTS => {
+                    log::debug!(
+                        "{} was changed by another process, updating again",
+                        self.key
+                    );
+                    attempt += 1;
+                }
+                Ok(false) => {
+                    log::error!(
+                        "Gave up saving {} after it kept being changed by other processes",
+                        self.key
+                    );
+                    return res;
+                }
+                Err(why) => {
+                    log::error!("Error while saving {}: {}", self.key, why);
+                    return res;
+                }
+            }
+        }
+    }
+
+    // Loads the latest value if other processes can change it, returning the JSON it was loaded
+    // from so an update can tell if it's changed again before it's saved.
+    fn refresh(&self, value: &mut T) -> Option<String> {
+        if !self.persistence.is_shared() {
+            return None;
+        }
+
+        let load_res = self.persistence.load(&self.key).and_then(|json| {
+            let loaded_value = match &json {
+                Some(json) => serde_json::from_str(json).map_err(crate::error::Error::Json)?,
+                None => T::default(),
+            };
+            Ok((json, loaded_value))
+        });
+        match load_res {
+            Ok((json, loaded_value)) => {
+                *value = loaded_value;
+                json
+            }
+            // Carry on with what was loaded last time rather than failing whatever needed it.
+            Err(why) => {
+                log::error!("Error while loading {}: {}", self.key, why);
+                None
+            }
+        }
+    }
+}
+
+/// Persisted values kept separately for each guild, loaded the first time they're needed.
+pub struct GuildStore<T> {
+    name: &'static str,
+    persistence: Arc<dyn Persistence>,
+    guilds: DashMap<GuildId, Arc<Persisted<T>>>,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> GuildStore<T> {
+    pub fn new(name: &'static str, persistence: Arc<dyn Persistence>) -> Self {
+        GuildStore {
+            name,
+            persistence,
+            guilds: DashMap::new(),
+        }
+    }
+
+    pub fn get(&self, guild_id: GuildId) -> Result<Arc<Persisted<T>>, crate::error::Error> {
+        if let Some(persisted) = self.guilds.get(&guild_id) {
+            return Ok(persisted.value().clone());
+        }
+
+        let persisted = Persisted::load(
+            self.persistence.clone(),
+            format!("{}-{}", self.name, guild_id),
+        )?;
+        Ok(self
+            .guilds
+            .entry(guild_id)
+            .or_insert_with(|| Arc::new(persisted))
+            .value()
+            .clone())
+    }
+}
@@ -0,0 +1,83 @@
+use crate::config::PetsConfig;
+use crate::persistence::{Persisted, Persistence};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serenity::model::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct PetCollection {
+    /// How many times the user has found each pet, by name.
+    pub pets: BTreeMap<String, u64>,
+    pub shinies: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FoundPet {
+    pub name: String,
+    pub image_url: String,
+    pub is_shiny: bool,
+    /// Whether this is the first time the user has found it.
+    pub is_new: bool,
+}
+
+/// The pets each user has found with `/pet`, which follow them between guilds.
+pub struct Pets {
+    // Keyed by user ID.
+    users: Persisted<HashMap<u64, PetCollection>>,
+}
+
+impl Pets {
+    pub fn load(persistence: Arc<dyn Persistence>) -> Result<Self, crate::error::Error> {
+        Ok(Pets {
+            users: Persisted::load(persistence, "pets".to_string())?,
+        })
+    }
+
+    /// Picks a pet, more often the ones with higher weights, and adds it to the user's collection.
+    /// There's no pet to find if none are configured with a weight above zero.
+    pub fn find<R: Rng + ?Sized>(
+        &self,
+        config: &PetsConfig,
+        user_id: UserId,
+        rng: &mut R,
+    ) -> Option<FoundPet> {
+        let is_shiny = match &config.shiny {
+            Some(shiny) => rng.gen_ratio(1, shiny.odds.max(1)),
+            None => false,
+        };
+        let (name, image_url) = match (&config.shiny, is_shiny) {
+            (Some(shiny), true) => (&shiny.name, &shiny.image_url),
+            _ => {
+                let weights = WeightedIndex::new(config.pets.iter().map(|pet| pet.weight)).ok()?;
+                let pet = &config.pets[weights.sample(rng)];
+                (&pet.name, &pet.image_url)
+            }
+        };
+
+        let is_new = self.users.update(|users| {
+            let collection = users.entry(user_id.0).or_default();
+            if is_shiny {
+                collection.shinies += 1;
+                collection.shinies == 1
+            } else {
+                let count = collection.pets.entry(name.clone()).or_default();
+                *count += 1;
+                *count == 1
+            }
+        });
+        Some(FoundPet {
+            name: name.clone(),
+            image_url: image_url.clone(),
+            is_shiny,
+            is_new,
+        })
+    }
+
+    pub fn collection(&self, user_id: UserId) -> PetCollection {
+        self.users
+            .read(|users| users.get(&user_id.0).cloned().unwrap_or_default())
+    }
+}
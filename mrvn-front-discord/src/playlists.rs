@@ -0,0 +1,380 @@
+use crate::persistence::{GuildStore, Persisted, Persistence};
+use serenity::model::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PlaylistSong {
+    pub title: String,
+    pub url: String,
+}
+
+/// Named lists of songs that users have saved from their queue, which follow them between guilds.
+pub struct Playlists {
+    // Keyed by user ID, then playlist name.
+    users: Persisted<HashMap<u64, BTreeMap<String, Vec<PlaylistSong>>>>,
+}
+
+impl Playlists {
+    pub fn load(persistence: Arc<dyn Persistence>) -> Result<Self, crate::error::Error> {
+        Ok(Playlists {
+            users: Persisted::load(persistence, "playlists".to_string())?,
+        })
+    }
+
+    /// The user's playlists by name, with how many songs are in each.
+    pub fn list(&self, user_id: UserId) -> Vec<(String, usize)> {
+        self.users.read(|users| match users.get(&user_id.0) {
+            Some(playlists) => playlists
+                .iter()
+                .map(|(name, songs)| (name.clone(), songs.len()))
+                .collect(),
+            None => Vec::new(),
+        })
+    }
+
+    pub fn get(&self, user_id: UserId, name: &str) -> Option<Vec<PlaylistSong>> {
+        self.users.read(|users| {
+            users
+                .get(&user_id.0)
+                .and_then(|playlists| playlists.get(name))
+                .cloned()
+        })
+    }
+
+    /// Replaces any playlist the user already has with the same name.
+    pub fn save(&self, user_id: UserId, name: String, songs: Vec<PlaylistSong>) {
+        self.users.update(|users| {
+            users
+                .entry(user_id.0)
+                .or_default()
+                .insert(name.clone(), songs.clone());
+        })
+    }
+
+    /// Returns `false` if the user didn't have a playlist with the name.
+    pub fn delete(&self, user_id: UserId, name: &str) -> bool {
+        self.users.update(|users| match users.get_mut(&user_id.0) {
+            Some(playlists) => playlists.remove(name).is_some(),
+            None => false,
+        })
+    }
+}
+
+// Only the latest changes to each server playlist are kept in its log.
+const MAX_CONTRIBUTIONS: usize = 50;
+
+/// Something that can be done to a server playlist, which each playlist can limit to some members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistAction {
+    /// Loading the playlist or looking at its log.
+    View,
+    Add,
+    Reorder,
+    /// Removing songs or deleting the whole playlist.
+    Delete,
+}
+
+impl PlaylistAction {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "view" => Some(PlaylistAction::View),
+            "add" => Some(PlaylistAction::Add),
+            "reorder" => Some(PlaylistAction::Reorder),
+            "delete" => Some(PlaylistAction::Delete),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PlaylistAction::View => "view",
+            PlaylistAction::Add => "add",
+            PlaylistAction::Reorder => "reorder",
+            PlaylistAction::Delete => "delete",
+        }
+    }
+}
+
+/// Who can do something to a server playlist. Each level includes the ones before it.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaylistAccess {
+    Everyone,
+    /// Members with the server's playlist role.
+    Contributors,
+    /// Whoever made the playlist and members with the Manage Server permission.
+    Managers,
+}
+
+impl PlaylistAccess {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "everyone" => Some(PlaylistAccess::Everyone),
+            "contributors" => Some(PlaylistAccess::Contributors),
+            "managers" => Some(PlaylistAccess::Managers),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct PlaylistPermissions {
+    pub view: PlaylistAccess,
+    pub add: PlaylistAccess,
+    pub reorder: PlaylistAccess,
+    pub delete: PlaylistAccess,
+}
+
+impl Default for PlaylistPermissions {
+    fn default() -> Self {
+        PlaylistPermissions {
+            view: PlaylistAccess::Everyone,
+            add: PlaylistAccess::Contributors,
+            reorder: PlaylistAccess::Managers,
+            delete: PlaylistAccess::Managers,
+        }
+    }
+}
+
+impl PlaylistPermissions {
+    pub fn get(&self, action: PlaylistAction) -> PlaylistAccess {
+        match action {
+            PlaylistAction::View => self.view,
+            PlaylistAction::Add => self.add,
+            PlaylistAction::Reorder => self.reorder,
+            PlaylistAction::Delete => self.delete,
+        }
+    }
+
+    fn set(&mut self, action: PlaylistAction, access: PlaylistAccess) {
+        match action {
+            PlaylistAction::View => self.view = access,
+            PlaylistAction::Add => self.add = access,
+            PlaylistAction::Reorder => self.reorder = access,
+            PlaylistAction::Delete => self.delete = access,
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum ContributionKind {
+    Created { count: usize },
+    Added { title: String },
+    AddedMultiple { count: usize },
+    Moved { title: String, to: usize },
+    Removed { title: String },
+}
+
+/// A change someone made to a server playlist.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Contribution {
+    pub user_id: UserId,
+    /// Unix timestamp.
+    pub timestamp: i64,
+    pub kind: ContributionKind,
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct GuildPlaylist {
+    pub created_by: UserId,
+    pub songs: Vec<PlaylistSong>,
+    #[serde(default)]
+    pub permissions: PlaylistPermissions,
+    /// Oldest first.
+    #[serde(default)]
+    pub contributions: Vec<Contribution>,
+}
+
+impl GuildPlaylist {
+    fn record(&mut self, user_id: UserId, kind: ContributionKind) {
+        self.contributions.push(Contribution {
+            user_id,
+            timestamp: chrono::Utc::now().timestamp(),
+            kind,
+        });
+        let overflow = self.contributions.len().saturating_sub(MAX_CONTRIBUTIONS);
+        self.contributions.drain(..overflow);
+    }
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct GuildPlaylistSet {
+    playlists: BTreeMap<String, GuildPlaylist>,
+}
+
+/// Playlists shared by everyone in a guild, which members can be allowed to add to and change.
+pub struct GuildPlaylists {
+    store: GuildStore<GuildPlaylistSet>,
+}
+
+impl GuildPlaylists {
+    pub fn new(persistence: Arc<dyn Persistence>) -> Self {
+        GuildPlaylists {
+            store: GuildStore::new("guild_playlists", persistence),
+        }
+    }
+
+    /// The guild's playlists by name, with how many songs are in each.
+    pub fn list(&self, guild_id: GuildId) -> Result<Vec<(String, usize)>, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.read(|playlist_set| {
+            playlist_set
+                .playlists
+                .iter()
+                .map(|(name, playlist)| (name.clone(), playlist.songs.len()))
+                .collect()
+        }))
+    }
+
+    pub fn get(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+    ) -> Result<Option<GuildPlaylist>, crate::error::Error> {
+        Ok(self
+            .store
+            .get(guild_id)?
+            .read(|playlist_set| playlist_set.playlists.get(name).cloned()))
+    }
+
+    /// Returns `false` if the guild already has a playlist with the name.
+    pub fn create(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        name: String,
+        songs: Vec<PlaylistSong>,
+    ) -> Result<bool, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.update(|playlist_set| {
+            if playlist_set.playlists.contains_key(&name) {
+                return false;
+            }
+
+            let mut playlist = GuildPlaylist {
+                created_by: user_id,
+                songs: songs.clone(),
+                permissions: PlaylistPermissions::default(),
+                contributions: Vec::new(),
+            };
+            playlist.record(
+                user_id,
+                ContributionKind::Created {
+                    count: playlist.songs.len(),
+                },
+            );
+            playlist_set.playlists.insert(name.clone(), playlist);
+            true
+        }))
+    }
+
+    /// Adds songs to the end of the playlist. Returns `false` if there's no playlist with the
+    /// name.
+    pub fn add(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        name: &str,
+        songs: Vec<PlaylistSong>,
+    ) -> Result<bool, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.update(|playlist_set| {
+            let playlist = match playlist_set.playlists.get_mut(name) {
+                Some(playlist) => playlist,
+                None => return false,
+            };
+
+            let kind = match songs.as_slice() {
+                [song] => ContributionKind::Added {
+                    title: song.title.clone(),
+                },
+                _ => ContributionKind::AddedMultiple { count: songs.len() },
+            };
+            playlist.songs.extend(songs.iter().cloned());
+            playlist.record(user_id, kind);
+            true
+        }))
+    }
+
+    /// Moves the song at one index to another, returning the song. Returns `None` if there's no
+    /// playlist with the name or either index is out of range.
+    pub fn move_song(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        name: &str,
+        from: usize,
+        to: usize,
+    ) -> Result<Option<PlaylistSong>, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.update(|playlist_set| {
+            let playlist = playlist_set.playlists.get_mut(name)?;
+            if from >= playlist.songs.len() || to >= playlist.songs.len() {
+                return None;
+            }
+
+            let song = playlist.songs.remove(from);
+            playlist.songs.insert(to, song.clone());
+            playlist.record(
+                user_id,
+                ContributionKind::Moved {
+                    title: song.title.clone(),
+                    to: to + 1,
+                },
+            );
+            Some(song)
+        }))
+    }
+
+    /// Returns the removed song, or `None` if there's no playlist with the name or the index is
+    /// out of range.
+    pub fn remove_song(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        name: &str,
+        index: usize,
+    ) -> Result<Option<PlaylistSong>, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.update(|playlist_set| {
+            let playlist = playlist_set.playlists.get_mut(name)?;
+            if index >= playlist.songs.len() {
+                return None;
+            }
+
+            let song = playlist.songs.remove(index);
+            playlist.record(
+                user_id,
+                ContributionKind::Removed {
+                    title: song.title.clone(),
+                },
+            );
+            Some(song)
+        }))
+    }
+
+    /// Returns `false` if there's no playlist with the name.
+    pub fn set_permission(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        action: PlaylistAction,
+        access: PlaylistAccess,
+    ) -> Result<bool, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.update(|playlist_set| {
+            match playlist_set.playlists.get_mut(name) {
+                Some(playlist) => {
+                    playlist.permissions.set(action, access);
+                    true
+                }
+                None => false,
+            }
+        }))
+    }
+
+    /// Returns `false` if the guild didn't have a playlist with the name.
+    pub fn delete(&self, guild_id: GuildId, name: &str) -> Result<bool, crate::error::Error> {
+        Ok(self
+            .store
+            .get(guild_id)?
+            .update(|playlist_set| playlist_set.playlists.remove(name).is_some()))
+    }
+}
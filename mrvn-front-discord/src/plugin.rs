@@ -0,0 +1,63 @@
+use crate::frontend::Frontend;
+use crate::message::Message;
+use mrvn_back_ytdl::Song;
+use mrvn_model::{GuildModel, PlaybackState};
+use serenity::builder::CreateApplicationCommand;
+use serenity::model::interactions::application_command::ApplicationCommandInteraction;
+use serenity::{model::prelude::*, prelude::*};
+use std::sync::Arc;
+
+/// Everything a plugin command gets access to while handling an invocation. The guild's playback
+/// state and queues are both locked for the duration of the command.
+// No plugins ship with the bot itself, so nothing reads these here.
+#[allow(dead_code)]
+pub struct PluginContext<'a> {
+    pub ctx: &'a Context,
+    pub command: &'a ApplicationCommandInteraction,
+    pub frontend: &'a Arc<Frontend>,
+    pub guild_id: GuildId,
+    pub user_id: UserId,
+    pub playback: &'a mut PlaybackState,
+    pub guild_model: &'a mut GuildModel<Song>,
+}
+
+/// A slash command that lives outside of the built-in command set. Plugins are registered with
+/// Discord alongside the built-in commands, and take priority over them when dispatching.
+#[serenity::async_trait]
+pub trait CommandPlugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn register<'c>(
+        &self,
+        command: &'c mut CreateApplicationCommand,
+    ) -> &'c mut CreateApplicationCommand;
+
+    async fn handle(
+        &self,
+        plugin_ctx: PluginContext<'_>,
+    ) -> Result<Vec<Message>, crate::error::Error>;
+}
+
+#[derive(Default)]
+pub struct PluginRegistry {
+    plugins: Vec<Box<dyn CommandPlugin>>,
+}
+
+impl PluginRegistry {
+    #[allow(dead_code)]
+    pub fn register(&mut self, plugin: impl CommandPlugin + 'static) {
+        log::debug!("Registering plugin command {}", plugin.name());
+        self.plugins.push(Box::new(plugin));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn CommandPlugin> {
+        self.plugins
+            .iter()
+            .find(|plugin| plugin.name() == name)
+            .map(|plugin| plugin.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn CommandPlugin> {
+        self.plugins.iter().map(|plugin| plugin.as_ref())
+    }
+}
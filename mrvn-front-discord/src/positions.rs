@@ -0,0 +1,92 @@
+use crate::persistence::{GuildStore, Persistence};
+use serenity::model::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedPosition {
+    position_secs: u64,
+    // Unix timestamp.
+    saved_at: u64,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct GuildPositions {
+    // Keyed by song URL.
+    songs: HashMap<String, SavedPosition>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Where playback of long songs stopped in each guild, so they can be resumed from there when
+/// they're played again.
+pub struct SavedPositions {
+    store: GuildStore<GuildPositions>,
+}
+
+impl SavedPositions {
+    pub fn new(persistence: Arc<dyn Persistence>) -> Self {
+        SavedPositions {
+            store: GuildStore::new("positions", persistence),
+        }
+    }
+
+    /// Positions older than `max_age` are treated as if they weren't saved.
+    pub fn get(
+        &self,
+        guild_id: GuildId,
+        url: &str,
+        max_age: Duration,
+    ) -> Result<Option<Duration>, crate::error::Error> {
+        let min_saved_at = now_secs().saturating_sub(max_age.as_secs());
+        Ok(self.store.get(guild_id)?.read(|positions| {
+            positions
+                .songs
+                .get(url)
+                .filter(|saved| saved.saved_at >= min_saved_at)
+                .map(|saved| Duration::from_secs(saved.position_secs))
+        }))
+    }
+
+    /// Also drops positions older than `max_age`, so the file doesn't grow forever.
+    pub fn save(
+        &self,
+        guild_id: GuildId,
+        url: String,
+        position: Duration,
+        max_age: Duration,
+    ) -> Result<(), crate::error::Error> {
+        let now = now_secs();
+        let min_saved_at = now.saturating_sub(max_age.as_secs());
+        self.store.get(guild_id)?.update(|positions| {
+            positions
+                .songs
+                .retain(|_, saved| saved.saved_at >= min_saved_at);
+            positions.songs.insert(
+                url.clone(),
+                SavedPosition {
+                    position_secs: position.as_secs(),
+                    saved_at: now,
+                },
+            );
+        });
+        Ok(())
+    }
+
+    pub fn forget(&self, guild_id: GuildId, url: &str) -> Result<(), crate::error::Error> {
+        let positions = self.store.get(guild_id)?;
+        // Avoid saving the file when nothing changed, which is the case for most songs.
+        if positions.read(|positions| positions.songs.contains_key(url)) {
+            positions.update(|positions| {
+                positions.songs.remove(url);
+            });
+        }
+        Ok(())
+    }
+}
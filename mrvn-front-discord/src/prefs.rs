@@ -0,0 +1,52 @@
+use crate::persistence::{Persisted, Persistence};
+use serenity::model::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct UserPrefs {
+    /// Send a direct message when one of the user's songs starts playing.
+    pub dm_notifications: bool,
+    /// Mention the user when their songs are announced.
+    pub announce_mention: bool,
+    /// Language used for responses to the user's commands, from the languages in the config.
+    pub language: Option<String>,
+}
+
+impl Default for UserPrefs {
+    fn default() -> Self {
+        UserPrefs {
+            dm_notifications: false,
+            announce_mention: true,
+            language: None,
+        }
+    }
+}
+
+/// Preferences that follow a user between guilds.
+pub struct Prefs {
+    // Keyed by user ID.
+    users: Persisted<HashMap<u64, UserPrefs>>,
+}
+
+impl Prefs {
+    pub fn load(persistence: Arc<dyn Persistence>) -> Result<Self, crate::error::Error> {
+        Ok(Prefs {
+            users: Persisted::load(persistence, "prefs".to_string())?,
+        })
+    }
+
+    pub fn get(&self, user_id: UserId) -> UserPrefs {
+        self.users
+            .read(|users| users.get(&user_id.0).cloned().unwrap_or_default())
+    }
+
+    pub fn update(&self, user_id: UserId, mut f: impl FnMut(&mut UserPrefs)) -> UserPrefs {
+        self.users.update(|users| {
+            let prefs = users.entry(user_id.0).or_default();
+            f(prefs);
+            prefs.clone()
+        })
+    }
+}
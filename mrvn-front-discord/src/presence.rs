@@ -0,0 +1,108 @@
+use crate::config::Config;
+use futures::prelude::*;
+use mrvn_back_ytdl::{SongMetadata, SpeakerEvent};
+use serenity::client::bridge::gateway::ShardManager;
+use serenity::model::prelude::*;
+use serenity::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PresenceState {
+    Idle,
+    Listening(String),
+    PlayingIn(usize),
+}
+
+/// Keeps the command bot's activity up to date with what the speakers are playing.
+pub struct PresenceManager {
+    config: Arc<Config>,
+    shard_manager: Arc<Mutex<ShardManager>>,
+    playing: HashMap<(usize, GuildId), SongMetadata>,
+    state: PresenceState,
+}
+
+impl PresenceManager {
+    pub fn new(config: Arc<Config>, shard_manager: Arc<Mutex<ShardManager>>) -> Self {
+        PresenceManager {
+            config,
+            shard_manager,
+            playing: HashMap::new(),
+            state: PresenceState::Idle,
+        }
+    }
+
+    pub async fn run(mut self, events: impl Stream<Item = SpeakerEvent>) {
+        futures::pin_mut!(events);
+        while let Some(event) = events.next().await {
+            match event {
+                SpeakerEvent::Started {
+                    speaker_index,
+                    guild_id,
+                    metadata,
+                    ..
+                } => {
+                    self.playing.insert((speaker_index, guild_id), metadata);
+                }
+                SpeakerEvent::Stopped {
+                    speaker_index,
+                    guild_id,
+                    ..
+                } => {
+                    self.playing.remove(&(speaker_index, guild_id));
+                }
+                _ => continue,
+            }
+
+            let new_state = self.current_state();
+            if new_state != self.state {
+                self.state = new_state;
+                self.update_activity().await;
+            }
+        }
+    }
+
+    fn current_state(&self) -> PresenceState {
+        let mut playing_iter = self.playing.values();
+        match (playing_iter.next(), playing_iter.next()) {
+            (None, _) => PresenceState::Idle,
+            (Some(metadata), None) => PresenceState::Listening(metadata.title.clone()),
+            _ => {
+                let guild_count = self
+                    .playing
+                    .keys()
+                    .map(|(_, guild_id)| *guild_id)
+                    .collect::<HashSet<_>>()
+                    .len();
+                PresenceState::PlayingIn(guild_count)
+            }
+        }
+    }
+
+    async fn update_activity(&self) {
+        let activity = match &self.state {
+            PresenceState::Idle => None,
+            PresenceState::Listening(song_title) => Some(Activity::listening(
+                self.config
+                    .get_message("presence.listening", &[("song_title", song_title)]),
+            )),
+            PresenceState::PlayingIn(1) => Some(Activity::playing(
+                self.config.get_raw_message("presence.playing_in.singular"),
+            )),
+            PresenceState::PlayingIn(count) => {
+                let count_string = count.to_string();
+                Some(Activity::playing(self.config.get_message(
+                    "presence.playing_in.plural",
+                    &[("count", &count_string)],
+                )))
+            }
+        };
+
+        log::trace!("Updating presence to {:?}", self.state);
+        let shard_manager = self.shard_manager.lock().await;
+        let runners = shard_manager.runners.lock().await;
+        for runner in runners.values() {
+            runner.runner_tx.set_activity(activity.clone());
+        }
+    }
+}
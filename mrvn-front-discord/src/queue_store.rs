@@ -0,0 +1,139 @@
+use crate::persistence::Persistence;
+use dashmap::DashMap;
+use mrvn_back_ytdl::Song;
+use mrvn_model::{AppModel, GuildModel, SavedQueue};
+use serenity::model::prelude::*;
+use std::sync::Arc;
+use std::time::Duration;
+
+// How long a change to a queue can go unsaved.
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(serde::Serialize, serde::Deserialize)]
+pub struct SavedUserQueue {
+    user_id: UserId,
+    songs: Vec<Song>,
+    requests: Vec<u64>,
+}
+
+impl From<SavedQueue<Song>> for SavedUserQueue {
+    fn from(saved_queue: SavedQueue<Song>) -> Self {
+        SavedUserQueue {
+            user_id: saved_queue.user_id,
+            songs: saved_queue.entries,
+            requests: saved_queue.requests,
+        }
+    }
+}
+
+impl From<SavedUserQueue> for SavedQueue<Song> {
+    fn from(saved_user_queue: SavedUserQueue) -> Self {
+        SavedQueue {
+            user_id: saved_user_queue.user_id,
+            entries: saved_user_queue.songs,
+            requests: saved_user_queue.requests,
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedGuildQueues {
+    queues: Vec<SavedUserQueue>,
+}
+
+/// Saves each guild's queues after they change, so songs are still queued after a restart, or on
+/// another process that takes over the guild.
+pub struct QueueStore {
+    persistence: Arc<dyn Persistence>,
+    // The queue version last saved or restored in each guild.
+    saved_versions: DashMap<GuildId, u64>,
+}
+
+impl QueueStore {
+    pub fn new(persistence: Arc<dyn Persistence>) -> Self {
+        QueueStore {
+            persistence,
+            saved_versions: DashMap::new(),
+        }
+    }
+
+    fn key(guild_id: GuildId) -> String {
+        format!("queues-{}", guild_id)
+    }
+
+    /// Puts back the queues last saved for the guild, as its model is created.
+    pub fn restore(&self, guild_id: GuildId, guild_model: &mut GuildModel<Song>) {
+        let load_res = self
+            .persistence
+            .load(&Self::key(guild_id))
+            .and_then(|json| match json {
+                Some(json) => serde_json::from_str::<SavedGuildQueues>(&json)
+                    .map(Some)
+                    .map_err(crate::error::Error::Json),
+                None => Ok(None),
+            });
+        let saved_guild_queues = match load_res {
+            Ok(Some(saved_guild_queues)) => saved_guild_queues,
+            Ok(None) => return,
+            Err(why) => {
+                log::error!("Error while loading queues in guild {}: {}", guild_id, why);
+                return;
+            }
+        };
+
+        let saved_queues: Vec<SavedQueue<Song>> = saved_guild_queues
+            .queues
+            .into_iter()
+            .map(SavedQueue::from)
+            .collect();
+        let song_count: usize = saved_queues.iter().map(|queue| queue.entries.len()).sum();
+        guild_model.restore_queues(saved_queues);
+        self.saved_versions
+            .insert(guild_id, guild_model.queue_version());
+        if song_count > 0 {
+            log::info!("Restored {} queued songs in guild {}", song_count, guild_id);
+        }
+    }
+
+    pub async fn run(&self, model: &AppModel<Song>) {
+        let mut interval = tokio::time::interval(SAVE_INTERVAL);
+        loop {
+            interval.tick().await;
+            self.save_changed(model).await;
+        }
+    }
+
+    async fn save_changed(&self, model: &AppModel<Song>) {
+        for (guild_id, guild_model_handle) in model.guilds() {
+            let guild_model = guild_model_handle.lock_queues().await;
+            let queue_version = guild_model.queue_version();
+            let saved_version = self
+                .saved_versions
+                .get(&guild_id)
+                .map_or(0, |saved_version| *saved_version);
+            if queue_version == saved_version {
+                continue;
+            }
+
+            let saved_guild_queues = SavedGuildQueues {
+                queues: guild_model
+                    .saved_queues()
+                    .into_iter()
+                    .map(SavedUserQueue::from)
+                    .collect(),
+            };
+            // Don't hold up commands in the guild while saving.
+            drop(guild_model);
+
+            let save_res = serde_json::to_string(&saved_guild_queues)
+                .map_err(crate::error::Error::Json)
+                .and_then(|json| self.persistence.save(&Self::key(guild_id), &json));
+            match save_res {
+                Ok(()) => {
+                    self.saved_versions.insert(guild_id, queue_version);
+                }
+                Err(why) => log::error!("Error while saving queues in guild {}: {}", guild_id, why),
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use crate::frontend::Frontend;
+use crate::message::{send_messages, ActionMessage, Message, SendMessageDestination};
+use crate::persistence::Persistence;
+use mrvn_back_ytdl::{PauseReason, Song};
+use serenity::{model::prelude::*, prelude::*};
+use std::collections::HashSet;
+use std::ops::DerefMut;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Notify;
+
+// How far behind the saved position of a song can be, so how much is played again after a crash.
+const SAVE_INTERVAL: Duration = Duration::from_secs(10);
+
+const PLAYBACK_KEY: &str = "playback";
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedPlayback {
+    guild_id: GuildId,
+    speaker_index: usize,
+    channel_id: ChannelId,
+    // Where to say the song has been resumed.
+    message_channel: Option<ChannelId>,
+    // Starts from where playback had got to when it was saved.
+    song: Song,
+    is_paused: bool,
+}
+
+/// Keeps track of what each speaker is playing, so if the process stops without handing over,
+/// the next one can rejoin the channels and carry on the songs from about where they were.
+pub struct PlaybackRecovery {
+    persistence: Arc<dyn Persistence>,
+    voice_client_count: usize,
+    interrupted: std::sync::Mutex<Vec<SavedPlayback>>,
+    // Whether there was anything to resume when the process started.
+    was_interrupted: bool,
+    has_resumed: AtomicBool,
+    resumed: Notify,
+    ready_voice_clients: std::sync::Mutex<HashSet<usize>>,
+    voice_clients_ready: Notify,
+}
+
+impl PlaybackRecovery {
+    /// Reads what was playing when the last process stopped. It's played again by `resume` once
+    /// the clients have connected.
+    pub fn load(persistence: Arc<dyn Persistence>, voice_client_count: usize) -> Self {
+        let load_res = persistence.load(PLAYBACK_KEY).and_then(|json| match json {
+            Some(json) => serde_json::from_str(&json).map_err(crate::error::Error::Json),
+            None => Ok(Vec::new()),
+        });
+        let interrupted: Vec<SavedPlayback> = load_res.unwrap_or_else(|why| {
+            log::error!("Error while loading interrupted playback: {}", why);
+            Vec::new()
+        });
+        if !interrupted.is_empty() {
+            log::info!(
+                "{} songs were interrupted and will be resumed",
+                interrupted.len()
+            );
+        }
+
+        PlaybackRecovery {
+            persistence,
+            voice_client_count,
+            was_interrupted: !interrupted.is_empty(),
+            has_resumed: AtomicBool::new(interrupted.is_empty()),
+            interrupted: std::sync::Mutex::new(interrupted),
+            resumed: Notify::new(),
+            ready_voice_clients: std::sync::Mutex::new(HashSet::new()),
+            voice_clients_ready: Notify::new(),
+        }
+    }
+
+    /// Counts a voice client as connected. Songs are only resumed once they all are.
+    pub fn voice_client_ready(&self, client_index: usize) {
+        let mut ready_voice_clients = self.ready_voice_clients.lock().unwrap();
+        ready_voice_clients.insert(client_index);
+        if ready_voice_clients.len() >= self.voice_client_count {
+            self.voice_clients_ready.notify_one();
+        }
+    }
+
+    /// Plays the songs that were interrupted once the voice clients have connected, and says so in
+    /// each guild's message channel. Nothing is resumed if a handover is already carrying on what
+    /// the old process was playing.
+    pub async fn resume(&self, ctx: &Context, frontend: &Arc<Frontend>) {
+        // The command client can connect more than once.
+        if self.has_resumed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let interrupted = std::mem::take(&mut *self.interrupted.lock().unwrap());
+        let is_taking_over = frontend
+            .handover
+            .as_ref()
+            .is_some_and(|handover| handover.is_taking_over());
+        if is_taking_over {
+            log::info!("Taking over from a running process, interrupted songs won't be resumed");
+            self.resumed.notify_one();
+            return;
+        }
+        self.voice_clients_ready.notified().await;
+
+        for saved_playback in interrupted {
+            let guild_id = saved_playback.guild_id;
+            let song_title = saved_playback.song.metadata.title.clone();
+            let song_url = saved_playback.song.metadata.url.clone();
+            let resume_res = frontend
+                .resume_playback(
+                    ctx,
+                    guild_id,
+                    saved_playback.speaker_index,
+                    saved_playback.channel_id,
+                    saved_playback.song,
+                    saved_playback.is_paused,
+                )
+                .await;
+            if let Err(why) = resume_res {
+                log::error!(
+                    "Error while resuming interrupted playback in guild {}: {}",
+                    guild_id,
+                    why
+                );
+                continue;
+            }
+
+            let guild_model_handle = frontend.model.get(guild_id);
+            let mut playback = guild_model_handle.lock_playback().await;
+            if playback.message_channel().is_none() {
+                playback.set_message_channel(saved_playback.message_channel);
+            }
+            let message_channel = match playback.message_channel() {
+                Some(message_channel) => message_channel,
+                None => continue,
+            };
+            let send_res = send_messages(
+                &frontend.config,
+                ctx,
+                SendMessageDestination::Channel(message_channel),
+                playback.deref_mut(),
+                vec![Message::Action(ActionMessage::ResumedAfterRestart {
+                    song_title,
+                    song_url,
+                    voice_channel_id: saved_playback.channel_id,
+                })],
+            )
+            .await;
+            if let Err(why) = send_res {
+                log::error!("Error while sending resumed message: {}", why);
+            }
+        }
+
+        log::info!("Finished resuming interrupted playback");
+        self.resumed.notify_one();
+    }
+
+    /// Saves what's playing every few seconds. This only starts once anything interrupted has
+    /// been resumed, so it isn't overwritten before then.
+    pub async fn run(&self, frontend: &Frontend) {
+        if self.was_interrupted {
+            self.resumed.notified().await;
+        }
+
+        let mut last_saved = None;
+        let mut interval = tokio::time::interval(SAVE_INTERVAL);
+        loop {
+            interval.tick().await;
+            // The new process is saving what's playing now.
+            if frontend.is_handed_over() {
+                continue;
+            }
+
+            let json = match serde_json::to_string(&capture_playback(frontend).await) {
+                Ok(json) => json,
+                Err(why) => {
+                    log::error!("Error while saving playback: {}", why);
+                    continue;
+                }
+            };
+            if last_saved.as_ref() == Some(&json) {
+                continue;
+            }
+            match self.persistence.save(PLAYBACK_KEY, &json) {
+                Ok(()) => last_saved = Some(json),
+                Err(why) => log::error!("Error while saving playback: {}", why),
+            }
+        }
+    }
+}
+
+async fn capture_playback(frontend: &Frontend) -> Vec<SavedPlayback> {
+    let mut playback = Vec::new();
+    for (guild_id, guild_model_handle) in frontend.model.guilds() {
+        let guild_playback = guild_model_handle.lock_playback().await;
+        for (speaker_index, speaker) in frontend.backend_brain.speakers.iter().enumerate() {
+            let guild_speaker_handle = speaker.get(guild_id);
+            let guild_speaker = guild_speaker_handle.lock().await;
+            let (channel_id, song) = match (
+                guild_speaker.current_channel(),
+                guild_speaker.resumable_song().await,
+            ) {
+                (Some(channel_id), Some(song)) => (channel_id, song),
+                _ => continue,
+            };
+            playback.push(SavedPlayback {
+                guild_id,
+                speaker_index,
+                channel_id,
+                message_channel: guild_playback.message_channel(),
+                song,
+                is_paused: guild_speaker.pause_reason() == Some(PauseReason::Requested),
+            });
+        }
+    }
+    playback
+}
@@ -0,0 +1,287 @@
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
+
+const MONTH_NAMES: &[&str] = &[
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const DAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+// Leap days only come around every four years, so that's as far ahead as a match can be.
+const MAX_DAYS_AHEAD: i64 = 4 * 366;
+
+/// A cron expression with the usual five fields: minute, hour, day of the month, month and day of
+/// the week. Fields can be `*`, numbers, ranges like `1-5`, steps like `*/15` and lists of any of
+/// those, and months and days can also be given by the first three letters of their names.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    // Each field is a bitset of the values it matches.
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    // Like other crons, when both days are limited either one matching is enough.
+    is_day_of_month_any: bool,
+    is_day_of_week_any: bool,
+}
+
+impl CronSchedule {
+    /// Returns `None` if the expression isn't valid.
+    pub fn parse(expression: &str) -> Option<Self> {
+        let fields: Vec<_> = expression.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return None;
+        };
+
+        let days_of_week_bits = parse_field(days_of_week, 0, 7, DAY_NAMES)?;
+        Some(CronSchedule {
+            minutes: parse_field(minutes, 0, 59, &[])?,
+            hours: parse_field(hours, 0, 23, &[])?,
+            days_of_month: parse_field(days_of_month, 1, 31, &[])?,
+            months: parse_field(months, 1, 12, MONTH_NAMES)?,
+            // Sunday can be 0 or 7.
+            days_of_week: (days_of_week_bits | days_of_week_bits >> 7) & 0x7f,
+            is_day_of_month_any: days_of_month.starts_with('*'),
+            is_day_of_week_any: days_of_week.starts_with('*'),
+        })
+    }
+
+    /// Whether the schedule runs at the start of the minute.
+    pub fn matches(&self, time: NaiveDateTime) -> bool {
+        self.matches_date(time)
+            && has_bit(self.hours, time.hour())
+            && has_bit(self.minutes, time.minute())
+    }
+
+    /// The first minute after `time` that the schedule runs, if it ever does. Schedules for
+    /// dates that don't exist, like the 31st of February, never run.
+    pub fn next_after(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        let start = time.date().and_hms_opt(time.hour(), time.minute(), 0)? + Duration::minutes(1);
+        let mut day = start.date();
+        for _ in 0..MAX_DAYS_AHEAD {
+            let day_start = day.and_hms_opt(0, 0, 0)?;
+            if self.matches_date(day_start) {
+                let first_minute = if day == start.date() {
+                    start
+                } else {
+                    day_start
+                };
+                let mut minute = first_minute;
+                while minute.date() == day {
+                    if self.matches(minute) {
+                        return Some(minute);
+                    }
+                    minute += Duration::minutes(1);
+                }
+            }
+            day = day.succ_opt()?;
+        }
+        None
+    }
+
+    fn matches_date(&self, time: NaiveDateTime) -> bool {
+        if !has_bit(self.months, time.month()) {
+            return false;
+        }
+
+        let day_of_month = has_bit(self.days_of_month, time.day());
+        let day_of_week = has_bit(self.days_of_week, time.weekday().num_days_from_sunday());
+        // A field like `*/2` still limits the days, it just doesn't count as limited here.
+        if self.is_day_of_month_any || self.is_day_of_week_any {
+            day_of_month && day_of_week
+        } else {
+            day_of_month || day_of_week
+        }
+    }
+}
+
+fn has_bit(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Option<u64> {
+    let mut bits = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse().ok()?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return None;
+        }
+
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (
+                parse_value(start, min, names)?,
+                parse_value(end, min, names)?,
+            ),
+            // A single value with a step, like `5/15`, runs from there to the end.
+            None if part.contains('/') => (parse_value(range, min, names)?, max),
+            None => {
+                let value = parse_value(range, min, names)?;
+                (value, value)
+            }
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+
+        for value in (start..=end).step_by(step) {
+            bits |= 1 << value;
+        }
+    }
+    Some(bits)
+}
+
+fn parse_value(value: &str, min: u32, names: &[&str]) -> Option<u32> {
+    let value = value.to_lowercase();
+    match names.iter().position(|name| *name == value) {
+        Some(index) => Some(min + index as u32),
+        None => value.parse().ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn time(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(year, month, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn minutes(expression: &str) -> Vec<u32> {
+        let schedule = CronSchedule::parse(expression).unwrap();
+        (0..60)
+            .filter(|minute| has_bit(schedule.minutes, *minute))
+            .collect()
+    }
+
+    #[test]
+    fn rejects_values_out_of_range() {
+        for expression in [
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * 32 * *",
+            "* * * 0 *",
+            "* * * 13 *",
+            "* * * * 8",
+            "0-60 * * * *",
+        ] {
+            assert_eq!(CronSchedule::parse(expression), None, "{}", expression);
+        }
+    }
+
+    #[test]
+    fn accepts_values_at_the_ends_of_ranges() {
+        assert!(CronSchedule::parse("0,59 0,23 1,31 1,12 0,7").is_some());
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expression in [
+            "",
+            "* * * *",
+            "* * * * * *",
+            "5-1 * * * *",
+            "*/0 * * * *",
+            "a * * * *",
+            "1, * * * *",
+            "* * * foo *",
+        ] {
+            assert_eq!(CronSchedule::parse(expression), None, "{:?}", expression);
+        }
+    }
+
+    #[test]
+    fn parses_steps() {
+        assert_eq!(minutes("*/15 * * * *"), [0, 15, 30, 45]);
+        assert_eq!(minutes("5/20 * * * *"), [5, 25, 45]);
+        assert_eq!(minutes("1-10/3 * * * *"), [1, 4, 7, 10]);
+        assert_eq!(minutes("0-5/10,30 * * * *"), [0, 30]);
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(
+            CronSchedule::parse("0 9 * Jan-Mar mon-FRI"),
+            CronSchedule::parse("0 9 * 1-3 1-5")
+        );
+    }
+
+    #[test]
+    fn sunday_is_zero_or_seven() {
+        assert_eq!(
+            CronSchedule::parse("0 0 * * 7"),
+            CronSchedule::parse("0 0 * * 0")
+        );
+        assert_eq!(
+            CronSchedule::parse("0 0 * * 5-7"),
+            CronSchedule::parse("0 0 * * 0,5,6")
+        );
+    }
+
+    #[test]
+    fn finds_next_run() {
+        let schedule = CronSchedule::parse("*/15 * * * *").unwrap();
+        assert_eq!(
+            schedule.next_after(time(2024, 6, 1, 10, 7)),
+            Some(time(2024, 6, 1, 10, 15))
+        );
+        // Strictly after, even at the start of a matching minute.
+        assert_eq!(
+            schedule.next_after(time(2024, 6, 1, 10, 15)),
+            Some(time(2024, 6, 1, 10, 30))
+        );
+        assert_eq!(
+            schedule.next_after(time(2024, 12, 31, 23, 50)),
+            Some(time(2025, 1, 1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn finds_next_weekday() {
+        // The 1st of June 2024 is a Saturday.
+        let schedule = CronSchedule::parse("0 12 * * mon-fri").unwrap();
+        assert_eq!(
+            schedule.next_after(time(2024, 6, 1, 13, 0)),
+            Some(time(2024, 6, 3, 12, 0))
+        );
+    }
+
+    #[test]
+    fn finds_leap_days() {
+        let schedule = CronSchedule::parse("0 0 29 2 *").unwrap();
+        assert_eq!(
+            schedule.next_after(time(2025, 3, 1, 0, 0)),
+            Some(time(2028, 2, 29, 0, 0))
+        );
+    }
+
+    #[test]
+    fn missing_dates_never_run() {
+        let schedule = CronSchedule::parse("0 0 31 2 *").unwrap();
+        assert_eq!(schedule.next_after(time(2024, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn either_day_field_matches_when_both_are_limited() {
+        let schedule = CronSchedule::parse("0 0 13 * fri").unwrap();
+        // Thursday the 13th, Friday the 7th and Saturday the 8th.
+        assert!(schedule.matches(time(2024, 6, 13, 0, 0)));
+        assert!(schedule.matches(time(2024, 6, 7, 0, 0)));
+        assert!(!schedule.matches(time(2024, 6, 8, 0, 0)));
+    }
+
+    #[test]
+    fn stepped_day_fields_are_still_limited() {
+        // `*/2` limits the days even though it starts with a star, so only odd days match.
+        let schedule = CronSchedule::parse("0 0 */2 * *").unwrap();
+        assert!(schedule.matches(time(2024, 6, 1, 0, 0)));
+        assert!(!schedule.matches(time(2024, 6, 2, 0, 0)));
+    }
+}
@@ -0,0 +1,166 @@
+mod cron;
+
+pub use self::cron::CronSchedule;
+
+use crate::persistence::{GuildStore, Persistence};
+use chrono::{NaiveDateTime, TimeZone, Timelike, Utc};
+use serenity::model::prelude::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// How many schedules each guild can have.
+pub const MAX_SCHEDULES: usize = 25;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub enum ScheduleSource {
+    /// One of the creator's playlists, by name. Changes to the playlist are picked up the next
+    /// time it plays.
+    Playlist(String),
+    Url(String),
+}
+
+impl ScheduleSource {
+    /// Web links are played directly, anything else is the name of a playlist.
+    pub fn parse(source: &str) -> Self {
+        match url::Url::parse(source) {
+            Ok(parsed_url) if matches!(parsed_url.scheme(), "http" | "https") => {
+                ScheduleSource::Url(source.to_string())
+            }
+            _ => ScheduleSource::Playlist(source.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Schedule {
+    pub id: u64,
+    pub cron: String,
+    pub voice_channel_id: ChannelId,
+    /// Where the schedule was made, which is treated like the channel a command was used in when
+    /// it runs.
+    pub text_channel_id: ChannelId,
+    /// Songs are queued for whoever made the schedule.
+    pub user_id: UserId,
+    pub source: ScheduleSource,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct GuildSchedules {
+    next_id: u64,
+    schedules: Vec<Schedule>,
+}
+
+/// Times that music automatically starts playing in a voice channel, set up with `/schedule`.
+pub struct Schedules {
+    store: GuildStore<GuildSchedules>,
+    timezone: chrono_tz::Tz,
+    is_running: AtomicBool,
+}
+
+impl Schedules {
+    pub fn new(persistence: Arc<dyn Persistence>, timezone: chrono_tz::Tz) -> Self {
+        Schedules {
+            store: GuildStore::new("schedules", persistence),
+            timezone,
+            is_running: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns `false` if the scheduler was already started, since the command client can connect
+    /// more than once.
+    pub fn start_running(&self) -> bool {
+        !self.is_running.swap(true, Ordering::SeqCst)
+    }
+
+    /// Returns the new schedule's ID, or `None` if the guild already has as many schedules as it
+    /// can.
+    pub fn add(
+        &self,
+        guild_id: GuildId,
+        cron: String,
+        voice_channel_id: ChannelId,
+        text_channel_id: ChannelId,
+        user_id: UserId,
+        source: ScheduleSource,
+    ) -> Result<Option<u64>, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.update(|guild_schedules| {
+            if guild_schedules.schedules.len() >= MAX_SCHEDULES {
+                return None;
+            }
+
+            guild_schedules.next_id += 1;
+            let id = guild_schedules.next_id;
+            guild_schedules.schedules.push(Schedule {
+                id,
+                cron: cron.clone(),
+                voice_channel_id,
+                text_channel_id,
+                user_id,
+                source: source.clone(),
+            });
+            Some(id)
+        }))
+    }
+
+    pub fn get(&self, guild_id: GuildId, id: u64) -> Result<Option<Schedule>, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.read(|guild_schedules| {
+            guild_schedules
+                .schedules
+                .iter()
+                .find(|schedule| schedule.id == id)
+                .cloned()
+        }))
+    }
+
+    /// Returns `false` if there wasn't a schedule with the ID.
+    pub fn remove(&self, guild_id: GuildId, id: u64) -> Result<bool, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.update(|guild_schedules| {
+            let count = guild_schedules.schedules.len();
+            guild_schedules
+                .schedules
+                .retain(|schedule| schedule.id != id);
+            guild_schedules.schedules.len() != count
+        }))
+    }
+
+    pub fn list(&self, guild_id: GuildId) -> Result<Vec<Schedule>, crate::error::Error> {
+        Ok(self
+            .store
+            .get(guild_id)?
+            .read(|guild_schedules| guild_schedules.schedules.clone()))
+    }
+
+    /// The guild's schedules that run at the start of the minute.
+    pub fn due(
+        &self,
+        guild_id: GuildId,
+        minute: NaiveDateTime,
+    ) -> Result<Vec<Schedule>, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.read(|guild_schedules| {
+            guild_schedules
+                .schedules
+                .iter()
+                .filter(|schedule| {
+                    CronSchedule::parse(&schedule.cron)
+                        .is_some_and(|cron_schedule| cron_schedule.matches(minute))
+                })
+                .cloned()
+                .collect()
+        }))
+    }
+
+    /// When a schedule with the cron expression next runs, as a Unix timestamp.
+    pub fn next_run(&self, cron: &str) -> Option<i64> {
+        let next_minute = CronSchedule::parse(cron)?.next_after(self.now())?;
+        let next_time = self.timezone.from_local_datetime(&next_minute).earliest()?;
+        Some(next_time.timestamp())
+    }
+
+    /// The start of the current minute, in the schedule timezone.
+    pub fn now(&self) -> NaiveDateTime {
+        let now = Utc::now().with_timezone(&self.timezone).naive_local();
+        now.with_second(0)
+            .and_then(|now| now.with_nanosecond(0))
+            .unwrap_or(now)
+    }
+}
@@ -0,0 +1,221 @@
+use crate::event_bus::Event;
+use rhai::{Array, Dynamic, Engine, Scope, AST};
+use serenity::model::prelude::*;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// Scripts are run synchronously on the async runtime, so keep them short.
+const MAX_OPERATIONS: u64 = 100_000;
+
+const EVENT_HANDLER_NAME: &str = "on_event";
+const COMMAND_HANDLER_PREFIX: &str = "command_";
+
+/// Something a script asked to do. Scripts can't touch the bot directly, instead these are
+/// collected while the script runs and carried out by the frontend afterwards.
+#[derive(Debug, Clone)]
+pub enum ScriptAction {
+    Enqueue { term: String },
+    SendMessage { content: String },
+}
+
+struct ScriptContextData {
+    guild_id: GuildId,
+    user_id: Option<UserId>,
+    input: String,
+    queue: Vec<String>,
+    actions: Vec<ScriptAction>,
+}
+
+/// The `ctx` value passed to script functions.
+#[derive(Clone)]
+pub struct ScriptContext {
+    data: Arc<Mutex<ScriptContextData>>,
+}
+
+impl ScriptContext {
+    pub fn new(
+        guild_id: GuildId,
+        user_id: Option<UserId>,
+        input: String,
+        queue: Vec<String>,
+    ) -> Self {
+        ScriptContext {
+            data: Arc::new(Mutex::new(ScriptContextData {
+                guild_id,
+                user_id,
+                input,
+                queue,
+                actions: Vec::new(),
+            })),
+        }
+    }
+
+    fn take_actions(&self) -> Vec<ScriptAction> {
+        std::mem::take(&mut self.data.lock().unwrap().actions)
+    }
+
+    fn push_action(&mut self, action: ScriptAction) {
+        self.data.lock().unwrap().actions.push(action);
+    }
+
+    fn get_guild_id(&mut self) -> String {
+        self.data.lock().unwrap().guild_id.0.to_string()
+    }
+
+    fn get_user_id(&mut self) -> Dynamic {
+        match self.data.lock().unwrap().user_id {
+            Some(user_id) => user_id.0.to_string().into(),
+            None => Dynamic::UNIT,
+        }
+    }
+
+    fn get_input(&mut self) -> String {
+        self.data.lock().unwrap().input.clone()
+    }
+
+    fn get_queue(&mut self) -> Array {
+        self.data
+            .lock()
+            .unwrap()
+            .queue
+            .iter()
+            .map(|title| title.clone().into())
+            .collect()
+    }
+}
+
+struct Script {
+    path: PathBuf,
+    ast: AST,
+}
+
+impl Script {
+    fn has_function(&self, name: &str) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|function| function.name == name)
+    }
+}
+
+pub struct ScriptHost {
+    engine: Engine,
+    scripts: Vec<Script>,
+}
+
+impl ScriptHost {
+    pub fn load(paths: &[String]) -> Result<Self, crate::error::Error> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine
+            .register_type_with_name::<ScriptContext>("Context")
+            .register_get("guild_id", ScriptContext::get_guild_id)
+            .register_get("user_id", ScriptContext::get_user_id)
+            .register_get("input", ScriptContext::get_input)
+            .register_get("queue", ScriptContext::get_queue)
+            .register_fn("enqueue", |ctx: &mut ScriptContext, term: &str| {
+                ctx.push_action(ScriptAction::Enqueue {
+                    term: term.to_string(),
+                })
+            })
+            .register_fn("send_message", |ctx: &mut ScriptContext, content: &str| {
+                ctx.push_action(ScriptAction::SendMessage {
+                    content: content.to_string(),
+                })
+            });
+
+        let scripts = paths
+            .iter()
+            .map(|path| {
+                log::info!("Loading script {}", path);
+                let path = Path::new(path).to_path_buf();
+                let ast = engine
+                    .compile_file(path.clone())
+                    .map_err(|why| crate::error::Error::Script(why.to_string()))?;
+                Ok(Script { path, ast })
+            })
+            .collect::<Result<Vec<_>, crate::error::Error>>()?;
+
+        Ok(ScriptHost { engine, scripts })
+    }
+
+    /// Names of custom commands defined by scripts, through functions called `command_<name>`.
+    pub fn command_names(&self) -> Vec<String> {
+        self.scripts
+            .iter()
+            .flat_map(|script| script.ast.iter_functions())
+            .filter_map(|function| function.name.strip_prefix(COMMAND_HANDLER_PREFIX))
+            .map(|name| name.to_string())
+            .collect()
+    }
+
+    pub fn has_command(&self, name: &str) -> bool {
+        let function_name = format!("{}{}", COMMAND_HANDLER_PREFIX, name);
+        self.scripts
+            .iter()
+            .any(|script| script.has_function(&function_name))
+    }
+
+    pub fn run_command(
+        &self,
+        name: &str,
+        ctx: ScriptContext,
+    ) -> Option<Result<Vec<ScriptAction>, crate::error::Error>> {
+        let function_name = format!("{}{}", COMMAND_HANDLER_PREFIX, name);
+        let script = self
+            .scripts
+            .iter()
+            .find(|script| script.has_function(&function_name))?;
+        Some(
+            self.call(script, &function_name, ctx.clone(), Dynamic::UNIT)
+                .map(|_| ctx.take_actions()),
+        )
+    }
+
+    pub fn run_event(&self, event: &Event, ctx: ScriptContext) -> Vec<ScriptAction> {
+        let event_value = match rhai::serde::to_dynamic(event) {
+            Ok(value) => value,
+            Err(why) => {
+                log::error!("Error while passing event to scripts: {}", why);
+                return Vec::new();
+            }
+        };
+
+        for script in &self.scripts {
+            if !script.has_function(EVENT_HANDLER_NAME) {
+                continue;
+            }
+
+            if let Err(why) =
+                self.call(script, EVENT_HANDLER_NAME, ctx.clone(), event_value.clone())
+            {
+                log::error!("{}", why);
+            }
+        }
+        ctx.take_actions()
+    }
+
+    fn call(
+        &self,
+        script: &Script,
+        function_name: &str,
+        ctx: ScriptContext,
+        arg: Dynamic,
+    ) -> Result<(), crate::error::Error> {
+        let mut scope = Scope::new();
+        let res = if arg.is_unit() {
+            self.engine
+                .call_fn::<Dynamic>(&mut scope, &script.ast, function_name, (ctx,))
+        } else {
+            self.engine
+                .call_fn::<Dynamic>(&mut scope, &script.ast, function_name, (ctx, arg))
+        };
+        res.map(|_| ()).map_err(|why| {
+            crate::error::Error::Script(format!(
+                "{} in {}: {}",
+                function_name,
+                script.path.display(),
+                why
+            ))
+        })
+    }
+}
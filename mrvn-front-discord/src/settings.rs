@@ -0,0 +1,124 @@
+use crate::persistence::{GuildStore, Persistence};
+use mrvn_back_ytdl::Song;
+use mrvn_model::{GuildFeatures, GuildModel, SchedulingStrategy};
+use serenity::model::prelude::*;
+use std::sync::Arc;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueStrategy {
+    #[default]
+    RoundRobin,
+    Fifo,
+}
+
+impl QueueStrategy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "round_robin" => Some(QueueStrategy::RoundRobin),
+            "fifo" => Some(QueueStrategy::Fifo),
+            _ => None,
+        }
+    }
+
+    pub fn scheduling_strategy(self) -> SchedulingStrategy {
+        match self {
+            QueueStrategy::RoundRobin => SchedulingStrategy::RoundRobin,
+            QueueStrategy::Fifo => SchedulingStrategy::Fifo,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct GuildSettings {
+    pub queue_strategy: QueueStrategy,
+    /// Drop songs queued by people who have left voice instead of keeping them for later.
+    pub drop_absent_entries: bool,
+    /// Text channels music commands can be used in. Commands work anywhere if this is empty.
+    pub command_channels: Vec<ChannelId>,
+    /// Voice channels the bots won't join, like AFK or staff channels.
+    pub blocked_voice_channels: Vec<ChannelId>,
+    /// Read out each song and who queued it in voice before it plays.
+    pub announce_songs: bool,
+    /// Keep playing if someone moves the bot into the voice channel the song's requester moved
+    /// to, instead of stopping.
+    pub follow_requester: bool,
+    /// Post songs and other updates here, instead of wherever commands were last used.
+    pub locked_message_channel: Option<ChannelId>,
+    /// Let people know in the old channel when commands from a different one move where updates
+    /// are posted.
+    pub notify_message_channel_moves: bool,
+    /// Start a thread for updates each time playback starts, and archive it once everything has
+    /// stopped.
+    pub session_threads: bool,
+    /// Show songs starting to play as a generated image, instead of only text.
+    pub now_playing_cards: bool,
+    /// Post queueing, skips, clears and settings changes here, with who did them.
+    pub audit_channel: Option<ChannelId>,
+    /// Members with this role are contributors to server playlists.
+    pub playlist_role: Option<RoleId>,
+    /// Prefer official audio over lyric videos and live performances when searching.
+    pub music_mode: bool,
+    /// Start the next song the moment the last one ends when they're from the same album or
+    /// playlist, instead of after loading it.
+    pub gapless: bool,
+}
+
+impl GuildSettings {
+    pub fn is_voice_channel_allowed(&self, channel_id: ChannelId) -> bool {
+        !self.blocked_voice_channels.contains(&channel_id)
+    }
+
+    pub fn features(&self) -> GuildFeatures {
+        GuildFeatures {
+            announcements: self.announce_songs,
+            follow_requester: self.follow_requester,
+            session_threads: self.session_threads,
+            now_playing_cards: self.now_playing_cards,
+            music_mode: self.music_mode,
+            gapless: self.gapless,
+        }
+    }
+
+    /// Copies settings that the model needs to know about into it.
+    pub fn apply(&self, guild_model: &mut GuildModel<Song>) {
+        guild_model.set_scheduling_strategy(self.queue_strategy.scheduling_strategy());
+        guild_model.set_drop_absent_entries(self.drop_absent_entries);
+        guild_model.set_features(self.features());
+    }
+}
+
+/// Settings that guild admins can change with `/settings`.
+pub struct Settings {
+    store: GuildStore<GuildSettings>,
+}
+
+impl Settings {
+    pub fn new(persistence: Arc<dyn Persistence>) -> Self {
+        Settings {
+            store: GuildStore::new("settings", persistence),
+        }
+    }
+
+    pub fn get(&self, guild_id: GuildId) -> GuildSettings {
+        match self.store.get(guild_id) {
+            Ok(settings) => settings.read(|settings| settings.clone()),
+            Err(why) => {
+                log::error!("Unable to load settings, using defaults: {}", why);
+                GuildSettings::default()
+            }
+        }
+    }
+
+    pub fn update(
+        &self,
+        guild_id: GuildId,
+        mut f: impl FnMut(&mut GuildSettings),
+    ) -> Result<GuildSettings, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.update(|settings| {
+            f(settings);
+            settings.clone()
+        }))
+    }
+}
@@ -0,0 +1,84 @@
+use crate::persistence::{GuildStore, Persistence};
+use serenity::model::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SoundEffect {
+    /// A direct link to the audio file.
+    pub url: String,
+    /// Percentage of the clip's own volume it's played at.
+    pub volume: u32,
+}
+
+impl SoundEffect {
+    /// Only web links are allowed, so people can't point effects at files on the host.
+    pub fn new(url: &str, volume: u32) -> Option<Self> {
+        match url::Url::parse(url) {
+            Ok(parsed_url) if matches!(parsed_url.scheme(), "http" | "https") => {
+                Some(SoundEffect {
+                    url: url.to_string(),
+                    volume,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct GuildSoundboard {
+    effects: BTreeMap<String, SoundEffect>,
+}
+
+/// Sound effects that guild admins have added, which anyone can play in voice with `/sfx`.
+pub struct Soundboard {
+    store: GuildStore<GuildSoundboard>,
+}
+
+impl Soundboard {
+    pub fn new(persistence: Arc<dyn Persistence>) -> Self {
+        Soundboard {
+            store: GuildStore::new("soundboard", persistence),
+        }
+    }
+
+    pub fn names(&self, guild_id: GuildId) -> Result<Vec<String>, crate::error::Error> {
+        Ok(self
+            .store
+            .get(guild_id)?
+            .read(|soundboard| soundboard.effects.keys().cloned().collect()))
+    }
+
+    pub fn get(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+    ) -> Result<Option<SoundEffect>, crate::error::Error> {
+        Ok(self
+            .store
+            .get(guild_id)?
+            .read(|soundboard| soundboard.effects.get(name).cloned()))
+    }
+
+    /// Replaces any effect that already has the name.
+    pub fn add(
+        &self,
+        guild_id: GuildId,
+        name: String,
+        effect: SoundEffect,
+    ) -> Result<(), crate::error::Error> {
+        self.store.get(guild_id)?.update(|soundboard| {
+            soundboard.effects.insert(name.clone(), effect.clone());
+        });
+        Ok(())
+    }
+
+    /// Returns `false` if there wasn't an effect with the name.
+    pub fn remove(&self, guild_id: GuildId, name: &str) -> Result<bool, crate::error::Error> {
+        Ok(self
+            .store
+            .get(guild_id)?
+            .update(|soundboard| soundboard.effects.remove(name).is_some()))
+    }
+}
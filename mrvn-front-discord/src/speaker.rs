@@ -0,0 +1,452 @@
+use mrvn_back_ytdl::{FindSpeakerError, Song, SongMetadata};
+use mrvn_model::{
+    AppModelDelegate, GuildFeatures, GuildHandle, NextEntry, PlaybackState, VoteStatus, VoteType,
+};
+use serenity::model::prelude::*;
+
+/// What commands need from a guild's speakers while they're locked. The bot plays through its
+/// voice connections, and tests use the model's `FakeSpeaker` instead.
+#[serenity::async_trait]
+pub trait GuildSpeakers: Send {
+    /// What's playing in the channel, if a speaker is playing there.
+    fn active_in_channel(&mut self, channel_id: ChannelId) -> Option<SongMetadata>;
+
+    /// Stops what's playing in the channel. The song ends like any other, so the next one starts
+    /// unless the channel was stopped.
+    fn stop_in_channel(&mut self, channel_id: ChannelId) -> Result<(), crate::error::Error>;
+
+    /// Checks that a speaker can play in the channel, either because it's already there or
+    /// because it's free to join.
+    async fn find_to_play_in_channel(
+        &mut self,
+        channel_id: ChannelId,
+    ) -> Result<(), FindSpeakerError>;
+
+    /// Plays the song on the speaker `find_to_play_in_channel` found. If it can't be played, the
+    /// channel is stopped.
+    async fn play_in_channel(
+        &mut self,
+        playback: &mut PlaybackState,
+        features: GuildFeatures,
+        channel_id: ChannelId,
+        song: Song,
+    ) -> Result<(), crate::error::Error>;
+}
+
+/// What happened when starting the next song in a channel.
+pub enum StartStatus {
+    Started(Box<SongMetadata>),
+    AlreadyPlaying,
+    NothingQueued,
+    NoSpeaker(FindSpeakerError),
+}
+
+/// Counts the user's vote to skip or stop what's playing in their channel, and stops the speaker
+/// once the vote passes. Returns how the vote went along with what's playing.
+pub fn vote<Delegate: AppModelDelegate>(
+    speakers: &mut impl GuildSpeakers,
+    playback: &mut PlaybackState,
+    delegate: &Delegate,
+    vote_type: VoteType,
+    channel_id: ChannelId,
+    user_id: UserId,
+) -> Result<(VoteStatus, Option<SongMetadata>), crate::error::Error> {
+    let is_stop = matches!(vote_type, VoteType::Stop);
+    let status = playback.vote_for_skip(delegate, vote_type, channel_id, user_id);
+    let maybe_active_metadata = speakers.active_in_channel(channel_id);
+    if let (VoteStatus::Success, Some(_)) = (&status, &maybe_active_metadata) {
+        if is_stop {
+            playback.set_channel_stopped(channel_id);
+        }
+        speakers.stop_in_channel(channel_id)?;
+    }
+    Ok((status, maybe_active_metadata))
+}
+
+/// Starts the next song in the channel if nothing is playing there. A speaker is found before
+/// taking anything from the queues, so songs stay queued when there isn't one.
+pub async fn start_next<Delegate: AppModelDelegate + Sync>(
+    speakers: &mut impl GuildSpeakers,
+    playback: &mut PlaybackState,
+    guild_model_handle: &GuildHandle<Song>,
+    delegate: &Delegate,
+    channel_id: ChannelId,
+) -> Result<StartStatus, crate::error::Error> {
+    if let Err(why) = speakers.find_to_play_in_channel(channel_id).await {
+        return Ok(StartStatus::NoSpeaker(why));
+    }
+
+    // The queues are only held while the song is picked, not while it starts.
+    let (next_entry, features) = {
+        let mut guild_model = guild_model_handle.lock_queues().await;
+        let next_entry = guild_model.next_channel_entry(playback, delegate, channel_id);
+        (next_entry, guild_model.features())
+    };
+    let song = match next_entry {
+        NextEntry::Entry(song) => song,
+        NextEntry::AlreadyPlaying => return Ok(StartStatus::AlreadyPlaying),
+        NextEntry::NoneAvailable => return Ok(StartStatus::NothingQueued),
+    };
+
+    let metadata = song.metadata.clone();
+    speakers
+        .play_in_channel(playback, features, channel_id, song)
+        .await?;
+    Ok(StartStatus::Started(Box::new(metadata)))
+}
+
+#[cfg(test)]
+#[serenity::async_trait]
+impl GuildSpeakers for mrvn_model::FakeSpeaker<Song> {
+    fn active_in_channel(&mut self, channel_id: ChannelId) -> Option<SongMetadata> {
+        self.playing(channel_id).map(|song| song.metadata.clone())
+    }
+
+    fn stop_in_channel(&mut self, channel_id: ChannelId) -> Result<(), crate::error::Error> {
+        self.end(channel_id);
+        Ok(())
+    }
+
+    async fn find_to_play_in_channel(
+        &mut self,
+        _channel_id: ChannelId,
+    ) -> Result<(), FindSpeakerError> {
+        Ok(())
+    }
+
+    async fn play_in_channel(
+        &mut self,
+        _playback: &mut PlaybackState,
+        _features: GuildFeatures,
+        channel_id: ChannelId,
+        song: Song,
+    ) -> Result<(), crate::error::Error> {
+        self.start(channel_id, song);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mrvn_model::{
+        AppModelConfig, BulkPushResult, FakeSpeaker, GuildModel, MockChannelStateDelegate,
+        QueueChangeKind,
+    };
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    const CHANNEL: ChannelId = ChannelId(1);
+    const ALICE: UserId = UserId(1);
+    const BOB: UserId = UserId(2);
+    const CAROL: UserId = UserId(3);
+
+    /// A command a user sends, with any searching already done.
+    enum TestCommand {
+        Play(Vec<Song>),
+        Skip,
+        Stop,
+        Remove(usize),
+        Clear,
+        Undo,
+    }
+
+    /// What a command did, in place of the messages the bot would respond with.
+    enum TestResponse {
+        Queued {
+            result: BulkPushResult,
+            start: Option<StartStatus>,
+        },
+        Vote(VoteStatus),
+        /// The removed song's ID.
+        Removed(Option<String>),
+        Cleared(usize),
+        Undone(Option<QueueChangeKind>),
+        NotInVoiceChannel,
+    }
+
+    /// A guild with its voice channels and a fake speaker, which handles commands through the
+    /// same calls the frontend's handlers make.
+    struct TestGuild {
+        handle: GuildHandle<Song>,
+        voice: MockChannelStateDelegate,
+        speaker: FakeSpeaker<Song>,
+    }
+
+    impl TestGuild {
+        fn new() -> Self {
+            let config = AppModelConfig {
+                skip_votes_required: 2,
+                stop_votes_required: 2,
+                vote_expiry: None,
+                undo_window: Duration::from_secs(60),
+                role_priorities: HashMap::new(),
+                max_user_entries: None,
+                max_guild_entries: None,
+            };
+            TestGuild {
+                handle: GuildHandle::new(
+                    PlaybackState::new(config.clone()),
+                    GuildModel::new(config),
+                ),
+                voice: MockChannelStateDelegate::new(),
+                speaker: FakeSpeaker::new(),
+            }
+        }
+
+        async fn handle_guild_command(
+            &mut self,
+            user_id: UserId,
+            command: TestCommand,
+        ) -> TestResponse {
+            let maybe_channel_id = self.voice.user_channel(user_id);
+            match (command, maybe_channel_id) {
+                (TestCommand::Play(songs), maybe_channel_id) => {
+                    let result = self
+                        .handle
+                        .lock_queues()
+                        .await
+                        .push_entries_bulk(user_id, songs);
+                    let start = match maybe_channel_id {
+                        Some(channel_id) => {
+                            let mut playback = self.handle.lock_playback().await;
+                            Some(
+                                start_next(
+                                    &mut self.speaker,
+                                    &mut playback,
+                                    &self.handle,
+                                    &self.voice,
+                                    channel_id,
+                                )
+                                .await
+                                .unwrap(),
+                            )
+                        }
+                        None => None,
+                    };
+                    TestResponse::Queued { result, start }
+                }
+                (TestCommand::Skip | TestCommand::Stop, None) => TestResponse::NotInVoiceChannel,
+                (command @ (TestCommand::Skip | TestCommand::Stop), Some(channel_id)) => {
+                    let vote_type = match command {
+                        TestCommand::Skip => VoteType::Skip,
+                        _ => VoteType::Stop,
+                    };
+                    let mut playback = self.handle.lock_playback().await;
+                    let (status, _) = vote(
+                        &mut self.speaker,
+                        &mut playback,
+                        &self.voice,
+                        vote_type,
+                        channel_id,
+                        user_id,
+                    )
+                    .unwrap();
+                    TestResponse::Vote(status)
+                }
+                (TestCommand::Remove(index), _) => TestResponse::Removed(
+                    self.handle
+                        .lock_queues()
+                        .await
+                        .remove_user_entry(user_id, index)
+                        .map(|song| song.metadata.id),
+                ),
+                (TestCommand::Clear, _) => TestResponse::Cleared(
+                    self.handle.lock_queues().await.clear_user_entries(user_id),
+                ),
+                (TestCommand::Undo, _) => {
+                    TestResponse::Undone(self.handle.lock_queues().await.undo_user_change(user_id))
+                }
+            }
+        }
+
+        /// Ends what's playing in the channel, or what was just stopped, and starts the next
+        /// song the way the speaker's ended handler does.
+        async fn finish(&mut self, channel_id: ChannelId) -> Option<String> {
+            self.speaker.end(channel_id);
+            let (mut playback, mut guild_model) = self.handle.lock().await;
+            if playback.is_channel_stopped(channel_id) {
+                return None;
+            }
+            let song =
+                guild_model.next_channel_entry_finished(&mut playback, &self.voice, channel_id)?;
+            let id = song.metadata.id.clone();
+            self.speaker.start(channel_id, song);
+            Some(id)
+        }
+
+        fn playing(&self, channel_id: ChannelId) -> Option<&str> {
+            self.speaker
+                .playing(channel_id)
+                .map(|song| song.metadata.id.as_str())
+        }
+    }
+
+    fn song(id: &str) -> Song {
+        serde_json::from_value(serde_json::json!({
+            "metadata": {
+                "id": id,
+                "title": id,
+                "url": format!("https://example.com/{}", id),
+                "user_id": 0,
+                "queued_at": { "secs_since_epoch": 0, "nanos_since_epoch": 0 },
+            },
+            "download_url": format!("https://example.com/{}.opus", id),
+            "http_headers": [],
+            "start_position": null,
+        }))
+        .unwrap()
+    }
+
+    fn songs(ids: &[&str]) -> Vec<Song> {
+        ids.iter().map(|id| song(id)).collect()
+    }
+
+    fn started(response: TestResponse) -> Option<String> {
+        match response {
+            TestResponse::Queued {
+                start: Some(StartStatus::Started(metadata)),
+                ..
+            } => Some(metadata.id),
+            TestResponse::Queued { .. } => None,
+            _ => panic!("songs weren't queued"),
+        }
+    }
+
+    #[tokio::test]
+    async fn play_starts_in_users_channel() {
+        let mut guild = TestGuild::new();
+        guild.voice.join(ALICE, CHANNEL);
+        guild.voice.join(BOB, CHANNEL);
+
+        let response = guild
+            .handle_guild_command(ALICE, TestCommand::Play(songs(&["a1", "a2"])))
+            .await;
+        assert_eq!(started(response).as_deref(), Some("a1"));
+        let response = guild
+            .handle_guild_command(BOB, TestCommand::Play(songs(&["b1"])))
+            .await;
+        assert_eq!(started(response), None);
+
+        while guild.finish(CHANNEL).await.is_some() {}
+        let played: Vec<_> = guild
+            .speaker
+            .played()
+            .iter()
+            .map(|(_, song)| song.metadata.id.as_str())
+            .collect();
+        assert_eq!(played, vec!["a1", "b1", "a2"]);
+    }
+
+    #[tokio::test]
+    async fn play_outside_voice_stays_queued() {
+        let mut guild = TestGuild::new();
+        let response = guild
+            .handle_guild_command(ALICE, TestCommand::Play(songs(&["a1"])))
+            .await;
+        assert!(matches!(
+            response,
+            TestResponse::Queued {
+                result: BulkPushResult { accepted: 1, .. },
+                start: None,
+            }
+        ));
+        assert!(matches!(
+            guild.handle_guild_command(ALICE, TestCommand::Skip).await,
+            TestResponse::NotInVoiceChannel
+        ));
+
+        guild.voice.join(ALICE, CHANNEL);
+        let response = guild
+            .handle_guild_command(ALICE, TestCommand::Play(songs(&["a2"])))
+            .await;
+        assert_eq!(started(response).as_deref(), Some("a1"));
+    }
+
+    #[tokio::test]
+    async fn skip_needs_enough_votes() {
+        let mut guild = TestGuild::new();
+        for user_id in [ALICE, BOB, CAROL] {
+            guild.voice.join(user_id, CHANNEL);
+        }
+        guild
+            .handle_guild_command(ALICE, TestCommand::Play(songs(&["a1", "a2"])))
+            .await;
+
+        assert!(matches!(
+            guild.handle_guild_command(BOB, TestCommand::Skip).await,
+            TestResponse::Vote(VoteStatus::NeedsMoreVotes(1))
+        ));
+        assert!(matches!(
+            guild.handle_guild_command(BOB, TestCommand::Skip).await,
+            TestResponse::Vote(VoteStatus::AlreadyVoted)
+        ));
+        assert_eq!(guild.playing(CHANNEL), Some("a1"));
+        assert!(matches!(
+            guild.handle_guild_command(CAROL, TestCommand::Skip).await,
+            TestResponse::Vote(VoteStatus::Success)
+        ));
+        assert_eq!(guild.playing(CHANNEL), None);
+
+        guild.finish(CHANNEL).await;
+        assert_eq!(guild.playing(CHANNEL), Some("a2"));
+    }
+
+    #[tokio::test]
+    async fn stop_keeps_queue() {
+        let mut guild = TestGuild::new();
+        for user_id in [ALICE, BOB] {
+            guild.voice.join(user_id, CHANNEL);
+        }
+        guild
+            .handle_guild_command(ALICE, TestCommand::Play(songs(&["a1", "a2"])))
+            .await;
+        guild.handle_guild_command(ALICE, TestCommand::Stop).await;
+        guild.handle_guild_command(BOB, TestCommand::Stop).await;
+        assert_eq!(guild.finish(CHANNEL).await, None);
+        assert_eq!(guild.playing(CHANNEL), None);
+        assert!(guild
+            .handle
+            .lock_playback()
+            .await
+            .is_channel_stopped(CHANNEL));
+
+        let response = guild
+            .handle_guild_command(BOB, TestCommand::Play(songs(&["b1"])))
+            .await;
+        assert_eq!(started(response).as_deref(), Some("a2"));
+    }
+
+    #[tokio::test]
+    async fn undo_reverses_remove_and_clear() {
+        let mut guild = TestGuild::new();
+        guild
+            .handle_guild_command(ALICE, TestCommand::Play(songs(&["a1", "a2", "a3"])))
+            .await;
+
+        let response = guild
+            .handle_guild_command(ALICE, TestCommand::Remove(1))
+            .await;
+        assert!(matches!(response, TestResponse::Removed(Some(id)) if id == "a2"));
+        assert!(matches!(
+            guild.handle_guild_command(ALICE, TestCommand::Clear).await,
+            TestResponse::Cleared(2)
+        ));
+        assert!(matches!(
+            guild.handle_guild_command(ALICE, TestCommand::Undo).await,
+            TestResponse::Undone(Some(QueueChangeKind::Clear))
+        ));
+        assert!(matches!(
+            guild.handle_guild_command(ALICE, TestCommand::Undo).await,
+            TestResponse::Undone(Some(QueueChangeKind::Remove))
+        ));
+        let ids: Vec<_> = guild
+            .handle
+            .lock_queues()
+            .await
+            .user_entries(ALICE)
+            .map(|song| song.metadata.id.clone())
+            .collect();
+        assert_eq!(ids, vec!["a1", "a2", "a3"]);
+    }
+}
@@ -0,0 +1,768 @@
+use crate::persistence::{GuildStore, Persisted, Persistence};
+use chrono::{Datelike, NaiveDate, TimeZone, Utc, Weekday};
+use dashmap::DashMap;
+use mrvn_back_ytdl::SongMetadata;
+use serenity::model::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+use std::io::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const HUNDRED_SONGS_COUNT: u64 = 100;
+const LONG_SESSION_LENGTH: Duration = Duration::from_secs(10 * 60 * 60);
+
+// Playback that starts again within this long of the last song ending carries on the same session.
+const SESSION_GAP: Duration = Duration::from_secs(5 * 60);
+
+// Every play is kept for `/history export` until there are about this many, then the oldest are
+// dropped.
+const MAX_HISTORY_LENGTH: usize = 10_000;
+// Plays are stored in chunks of this many under their own keys, which are reused in a ring, so
+// recording a play only saves the latest chunk instead of the whole history.
+const HISTORY_CHUNK_LENGTH: usize = 500;
+const HISTORY_CHUNK_COUNT: u64 = (MAX_HISTORY_LENGTH / HISTORY_CHUNK_LENGTH) as u64;
+
+// Each listener's play counts are kept for this many tracks and artists. Past that, the least
+// played are forgotten down to `PRUNED_COUNTS_LENGTH` so it doesn't happen on every play.
+const MAX_COUNTS_LENGTH: usize = 1000;
+const PRUNED_COUNTS_LENGTH: usize = MAX_COUNTS_LENGTH * 3 / 4;
+
+const CSV_HEADER: &str = "played_at,user_id,song_title,song_url,listening_secs,duration_secs";
+
+#[derive(Debug, Default, Clone, Copy, serde::Serialize, serde::Deserialize)]
+pub struct PlayCounts {
+    pub songs_played: u64,
+    pub listening_secs: u64,
+    /// Songs that broke off before the end, which are also counted as played.
+    #[serde(default)]
+    pub songs_failed: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaderboardPeriod {
+    Week,
+    Month,
+    AllTime,
+}
+
+impl LeaderboardPeriod {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "week" => Some(LeaderboardPeriod::Week),
+            "month" => Some(LeaderboardPeriod::Month),
+            "all_time" => Some(LeaderboardPeriod::AllTime),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LeaderboardPeriod::Week => "week",
+            LeaderboardPeriod::Month => "month",
+            LeaderboardPeriod::AllTime => "all_time",
+        }
+    }
+}
+
+/// Milestones that users unlock in each guild, shown with `/achievements`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum Achievement {
+    HundredSongs,
+    LongSession,
+    FirstShiny,
+}
+
+impl Achievement {
+    pub const ALL: &'static [Achievement] = &[
+        Achievement::HundredSongs,
+        Achievement::LongSession,
+        Achievement::FirstShiny,
+    ];
+
+    /// Used to find the achievement's name and description in the messages config.
+    pub fn name(self) -> &'static str {
+        match self {
+            Achievement::HundredSongs => "hundred_songs",
+            Achievement::LongSession => "long_session",
+            Achievement::FirstShiny => "first_shiny",
+        }
+    }
+}
+
+/// When the weekly and monthly leaderboards start over.
+#[derive(Debug, Clone, Copy)]
+pub struct ResetSchedule {
+    pub timezone: chrono_tz::Tz,
+    pub week_start: Weekday,
+}
+
+impl ResetSchedule {
+    fn current_period_starts(&self) -> PeriodStarts {
+        let today = Utc::now()
+            .with_timezone(&self.timezone)
+            .naive_local()
+            .date();
+        let days_into_week = (7 + today.weekday().num_days_from_monday()
+            - self.week_start.num_days_from_monday())
+            % 7;
+        PeriodStarts {
+            week: today - chrono::Duration::days(days_into_week as i64),
+            month: today.with_day(1).unwrap(),
+        }
+    }
+}
+
+struct PeriodStarts {
+    week: NaiveDate,
+    month: NaiveDate,
+}
+
+/// Plays counted since the start of a leaderboard period.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct PeriodPlays {
+    start: String,
+    plays: u64,
+}
+
+impl PeriodPlays {
+    fn record(&mut self, start: NaiveDate) {
+        let start_string = start.to_string();
+        if self.start != start_string {
+            self.start = start_string;
+            self.plays = 0;
+        }
+        self.plays += 1;
+    }
+
+    fn plays_since(&self, start: NaiveDate) -> u64 {
+        if self.start == start.to_string() {
+            self.plays
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct ListenerStats {
+    counts: PlayCounts,
+    track_plays: HashMap<String, u64>,
+    artist_plays: HashMap<String, u64>,
+    #[serde(default)]
+    week_plays: PeriodPlays,
+    #[serde(default)]
+    month_plays: PeriodPlays,
+    #[serde(default)]
+    achievements: BTreeSet<Achievement>,
+}
+
+impl ListenerStats {
+    fn record(
+        &mut self,
+        metadata: &SongMetadata,
+        listening_secs: u64,
+        failed: bool,
+        starts: &PeriodStarts,
+    ) {
+        self.counts.songs_played += 1;
+        self.counts.listening_secs += listening_secs;
+        if failed {
+            self.counts.songs_failed += 1;
+        }
+        self.week_plays.record(starts.week);
+        self.month_plays.record(starts.month);
+        *self.track_plays.entry(metadata.url.clone()).or_default() += 1;
+        if let Some(artist) = &metadata.artist {
+            *self.artist_plays.entry(artist.clone()).or_default() += 1;
+        }
+        prune_counts(&mut self.track_plays, &metadata.url);
+        if let Some(artist) = &metadata.artist {
+            prune_counts(&mut self.artist_plays, artist);
+        }
+    }
+}
+
+// Forgets the least played keys once there are too many. The one that was just played is kept
+// over others played as often, so it gets a chance to catch up.
+fn prune_counts(counts: &mut HashMap<String, u64>, played_key: &str) {
+    if counts.len() <= MAX_COUNTS_LENGTH {
+        return;
+    }
+    let mut sorted: Vec<_> = counts.drain().collect();
+    sorted.sort_by(|(a_key, a_count), (b_key, b_count)| {
+        b_count
+            .cmp(a_count)
+            .then_with(|| (b_key == played_key).cmp(&(a_key == played_key)))
+    });
+    sorted.truncate(PRUNED_COUNTS_LENGTH);
+    counts.extend(sorted);
+}
+
+/// A song that was played, for the guild's play history.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct PlayRecord {
+    /// Unix timestamp of when the song started.
+    started_at: i64,
+    user_id: u64,
+    song_title: String,
+    song_url: String,
+    listening_secs: u64,
+    duration_secs: Option<u64>,
+}
+
+impl PlayRecord {
+    fn started_at_string(&self) -> String {
+        Utc.timestamp(self.started_at, 0).to_rfc3339()
+    }
+
+    fn write_csv(&self, writer: &mut impl Write) -> std::io::Result<()> {
+        writeln!(
+            writer,
+            "{},{},{},{},{},{}",
+            self.started_at_string(),
+            self.user_id,
+            escape_csv(&self.song_title),
+            escape_csv(&self.song_url),
+            self.listening_secs,
+            self.duration_secs
+                .map(|secs| secs.to_string())
+                .unwrap_or_default()
+        )
+    }
+
+    fn write_json(&self, writer: &mut impl Write) -> serde_json::Result<()> {
+        serde_json::to_writer(
+            writer,
+            &serde_json::json!({
+                "played_at": self.started_at_string(),
+                "user_id": self.user_id.to_string(),
+                "song_title": self.song_title,
+                "song_url": self.song_url,
+                "listening_secs": self.listening_secs,
+                "duration_secs": self.duration_secs,
+            }),
+        )
+    }
+}
+
+// Fields with anything that would break up the row are quoted, with quotes doubled.
+fn escape_csv(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryFormat {
+    Csv,
+    Json,
+}
+
+impl HistoryFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(HistoryFormat::Csv),
+            "json" => Some(HistoryFormat::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            HistoryFormat::Csv => "csv",
+            HistoryFormat::Json => "json",
+        }
+    }
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+pub struct GuildStats {
+    guild: ListenerStats,
+    // Keyed by user ID. Songs are credited to the user that queued them.
+    users: HashMap<u64, ListenerStats>,
+    // Track URLs to the title they were last played with, for tracks the guild's counts still have.
+    track_titles: HashMap<String, String>,
+    // How many plays have been recorded in the history, including ones that have been dropped.
+    #[serde(default)]
+    history_length: u64,
+}
+
+// Some of a guild's play history, stored under a key for its place in the ring.
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct HistoryChunk {
+    // Counts up through the whole history, so a chunk left over from the last time around the
+    // ring can be told apart.
+    number: u64,
+    // Oldest first.
+    records: Vec<PlayRecord>,
+}
+
+#[derive(Debug, Clone)]
+pub struct TopTrack {
+    pub song_title: String,
+    pub song_url: String,
+    pub plays: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TopArtist {
+    pub artist: String,
+    pub plays: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct LeaderboardEntry {
+    pub user_id: UserId,
+    pub plays: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatsSummary {
+    pub counts: PlayCounts,
+    pub top_tracks: Vec<TopTrack>,
+    pub top_artists: Vec<TopArtist>,
+}
+
+fn top_counts(counts: &HashMap<String, u64>, limit: usize) -> Vec<(&String, u64)> {
+    let mut sorted: Vec<_> = counts.iter().map(|(key, count)| (key, *count)).collect();
+    sorted.sort_by(|(a_key, a_count), (b_key, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_key.cmp(b_key))
+    });
+    sorted.truncate(limit);
+    sorted
+}
+
+// Songs played one after another in a channel.
+struct Session {
+    started_at: Instant,
+    last_ended_at: Option<Instant>,
+}
+
+pub struct Stats {
+    persistence: Arc<dyn Persistence>,
+    store: GuildStore<GuildStats>,
+    // Keyed by guild and place in the ring, loaded the first time they're needed.
+    history_chunks: DashMap<(GuildId, u64), Arc<Persisted<HistoryChunk>>>,
+    reset_schedule: ResetSchedule,
+    started_at: DashMap<(GuildId, ChannelId), Instant>,
+    sessions: DashMap<(GuildId, ChannelId), Session>,
+}
+
+impl Stats {
+    pub fn new(persistence: Arc<dyn Persistence>, reset_schedule: ResetSchedule) -> Self {
+        Stats {
+            store: GuildStore::new("stats", persistence.clone()),
+            persistence,
+            history_chunks: DashMap::new(),
+            reset_schedule,
+            started_at: DashMap::new(),
+            sessions: DashMap::new(),
+        }
+    }
+
+    pub fn track_started(&self, guild_id: GuildId, channel_id: ChannelId) {
+        let now = Instant::now();
+        self.started_at.insert((guild_id, channel_id), now);
+
+        let mut session = self
+            .sessions
+            .entry((guild_id, channel_id))
+            .or_insert(Session {
+                started_at: now,
+                last_ended_at: None,
+            });
+        let is_new_session = match session.last_ended_at {
+            Some(last_ended_at) => last_ended_at.elapsed() > SESSION_GAP,
+            None => false,
+        };
+        if is_new_session {
+            session.started_at = now;
+        }
+    }
+
+    /// Whether songs have been playing in the channel, with no long breaks, for long enough that
+    /// everyone listening unlocks `Achievement::LongSession`.
+    pub fn is_long_session(&self, guild_id: GuildId, channel_id: ChannelId) -> bool {
+        self.sessions
+            .get(&(guild_id, channel_id))
+            .is_some_and(|session| session.started_at.elapsed() >= LONG_SESSION_LENGTH)
+    }
+
+    /// Returns the achievement the user that queued the song unlocked by it being played, if
+    /// they unlocked one. `failed` is for songs that broke off before the end.
+    pub fn record_play(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        metadata: &SongMetadata,
+        failed: bool,
+    ) -> Result<Option<Achievement>, crate::error::Error> {
+        let listening_secs = self
+            .started_at
+            .remove(&(guild_id, channel_id))
+            .map(|(_, started_at)| started_at.elapsed().as_secs())
+            .unwrap_or(0);
+        if let Some(mut session) = self.sessions.get_mut(&(guild_id, channel_id)) {
+            session.last_ended_at = Some(Instant::now());
+        }
+        let starts = self.reset_schedule.current_period_starts();
+
+        let (history_index, achievement) = self.store.get(guild_id)?.update(|stats| {
+            stats
+                .guild
+                .record(metadata, listening_secs, failed, &starts);
+            stats
+                .track_titles
+                .insert(metadata.url.clone(), metadata.title.clone());
+            if stats.track_titles.len() > stats.guild.track_plays.len() {
+                let track_plays = &stats.guild.track_plays;
+                stats
+                    .track_titles
+                    .retain(|url, _| track_plays.contains_key(url));
+            }
+            let history_index = stats.history_length;
+            stats.history_length += 1;
+
+            let user_stats = stats.users.entry(metadata.user_id.0).or_default();
+            user_stats.record(metadata, listening_secs, failed, &starts);
+            let achievement = if user_stats.counts.songs_played >= HUNDRED_SONGS_COUNT
+                && user_stats.achievements.insert(Achievement::HundredSongs)
+            {
+                Some(Achievement::HundredSongs)
+            } else {
+                None
+            };
+            (history_index, achievement)
+        });
+
+        let record = PlayRecord {
+            started_at: Utc::now().timestamp() - listening_secs as i64,
+            user_id: metadata.user_id.0,
+            song_title: metadata.title.clone(),
+            song_url: metadata.url.clone(),
+            listening_secs,
+            duration_secs: metadata.duration.map(|duration| duration.as_secs()),
+        };
+        let chunk_number = history_index / HISTORY_CHUNK_LENGTH as u64;
+        self.history_chunk(guild_id, chunk_number)?.update(|chunk| {
+            if chunk.number != chunk_number {
+                chunk.number = chunk_number;
+                chunk.records.clear();
+            }
+            chunk.records.push(record.clone());
+        });
+        Ok(achievement)
+    }
+
+    fn history_chunk(
+        &self,
+        guild_id: GuildId,
+        chunk_number: u64,
+    ) -> Result<Arc<Persisted<HistoryChunk>>, crate::error::Error> {
+        let place = chunk_number % HISTORY_CHUNK_COUNT;
+        if let Some(persisted) = self.history_chunks.get(&(guild_id, place)) {
+            return Ok(persisted.value().clone());
+        }
+
+        let persisted = Persisted::load(
+            self.persistence.clone(),
+            format!("history-{}-{}", guild_id, place),
+        )?;
+        Ok(self
+            .history_chunks
+            .entry((guild_id, place))
+            .or_insert_with(|| Arc::new(persisted))
+            .value()
+            .clone())
+    }
+
+    // Every play in the guild's history that hasn't been dropped, oldest first.
+    fn history(&self, guild_id: GuildId) -> Result<Vec<PlayRecord>, crate::error::Error> {
+        let history_length = self.store.get(guild_id)?.read(|stats| stats.history_length);
+        let chunk_end =
+            (history_length + HISTORY_CHUNK_LENGTH as u64 - 1) / HISTORY_CHUNK_LENGTH as u64;
+        let chunk_start = chunk_end.saturating_sub(HISTORY_CHUNK_COUNT);
+
+        let mut records = Vec::new();
+        for chunk_number in chunk_start..chunk_end {
+            self.history_chunk(guild_id, chunk_number)?.read(|chunk| {
+                if chunk.number == chunk_number {
+                    records.extend(chunk.records.iter().cloned());
+                }
+            });
+        }
+        Ok(records)
+    }
+
+    /// Returns the users that hadn't already unlocked the achievement.
+    pub fn unlock(
+        &self,
+        guild_id: GuildId,
+        user_ids: &[UserId],
+        achievement: Achievement,
+    ) -> Result<Vec<UserId>, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.update(|stats| {
+            user_ids
+                .iter()
+                .copied()
+                .filter(|user_id| {
+                    stats
+                        .users
+                        .entry(user_id.0)
+                        .or_default()
+                        .achievements
+                        .insert(achievement)
+                })
+                .collect()
+        }))
+    }
+
+    pub fn achievements(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> Result<Vec<Achievement>, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.read(|stats| {
+            stats
+                .users
+                .get(&user_id.0)
+                .map(|user_stats| user_stats.achievements.iter().copied().collect())
+                .unwrap_or_default()
+        }))
+    }
+
+    /// Stats for the whole guild, or for songs queued by one user. Returns `None` if nothing has
+    /// been played yet.
+    pub fn summary(
+        &self,
+        guild_id: GuildId,
+        maybe_user_id: Option<UserId>,
+        limit: usize,
+    ) -> Result<Option<StatsSummary>, crate::error::Error> {
+        Ok(self.store.get(guild_id)?.read(|stats| {
+            let listener_stats = match maybe_user_id {
+                Some(user_id) => stats.users.get(&user_id.0)?,
+                None => &stats.guild,
+            };
+            if listener_stats.counts.songs_played == 0 {
+                return None;
+            }
+
+            let top_tracks = top_counts(&listener_stats.track_plays, limit)
+                .into_iter()
+                .map(|(url, plays)| TopTrack {
+                    song_title: stats.track_titles.get(url).unwrap_or(url).clone(),
+                    song_url: url.clone(),
+                    plays,
+                })
+                .collect();
+            let top_artists = top_counts(&listener_stats.artist_plays, limit)
+                .into_iter()
+                .map(|(artist, plays)| TopArtist {
+                    artist: artist.clone(),
+                    plays,
+                })
+                .collect();
+
+            Some(StatsSummary {
+                counts: listener_stats.counts,
+                top_tracks,
+                top_artists,
+            })
+        }))
+    }
+
+    /// Writes out the guild's play history, oldest first, returning how many plays were in it.
+    pub fn export_history(
+        &self,
+        guild_id: GuildId,
+        format: HistoryFormat,
+        writer: &mut impl Write,
+    ) -> Result<usize, crate::error::Error> {
+        let history = self.history(guild_id)?;
+        match format {
+            HistoryFormat::Csv => {
+                writeln!(writer, "{}", CSV_HEADER).map_err(crate::error::Error::Io)?;
+                for record in &history {
+                    record.write_csv(writer).map_err(crate::error::Error::Io)?;
+                }
+            }
+            HistoryFormat::Json => {
+                writer.write_all(b"[").map_err(crate::error::Error::Io)?;
+                for (index, record) in history.iter().enumerate() {
+                    if index > 0 {
+                        writer.write_all(b",\n").map_err(crate::error::Error::Io)?;
+                    }
+                    record
+                        .write_json(writer)
+                        .map_err(crate::error::Error::Json)?;
+                }
+                writer.write_all(b"]\n").map_err(crate::error::Error::Io)?;
+            }
+        }
+        Ok(history.len())
+    }
+
+    /// The URLs of songs that have been played in the guild, leaving out ones that haven't been
+    /// played much if there have been a lot.
+    pub fn played_tracks(&self, guild_id: GuildId) -> Result<Vec<String>, crate::error::Error> {
+        Ok(self
+            .store
+            .get(guild_id)?
+            .read(|stats| stats.track_titles.keys().cloned().collect()))
+    }
+
+    /// Users ordered by how many of their songs have been played in the period.
+    pub fn leaderboard(
+        &self,
+        guild_id: GuildId,
+        period: LeaderboardPeriod,
+    ) -> Result<Vec<LeaderboardEntry>, crate::error::Error> {
+        let starts = self.reset_schedule.current_period_starts();
+        let mut entries: Vec<_> = self.store.get(guild_id)?.read(|stats| {
+            stats
+                .users
+                .iter()
+                .map(|(user_id, listener_stats)| LeaderboardEntry {
+                    user_id: UserId(*user_id),
+                    plays: match period {
+                        LeaderboardPeriod::Week => {
+                            listener_stats.week_plays.plays_since(starts.week)
+                        }
+                        LeaderboardPeriod::Month => {
+                            listener_stats.month_plays.plays_since(starts.month)
+                        }
+                        LeaderboardPeriod::AllTime => listener_stats.counts.songs_played,
+                    },
+                })
+                .filter(|entry| entry.plays > 0)
+                .collect()
+        });
+        entries.sort_by(|a, b| {
+            b.plays
+                .cmp(&a.plays)
+                .then_with(|| a.user_id.cmp(&b.user_id))
+        });
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::MemoryPersistence;
+    use std::time::SystemTime;
+
+    fn record(song_title: &str, song_url: &str) -> PlayRecord {
+        PlayRecord {
+            started_at: 0,
+            user_id: 1,
+            song_title: song_title.to_string(),
+            song_url: song_url.to_string(),
+            listening_secs: 30,
+            duration_secs: None,
+        }
+    }
+
+    fn csv_row(record: &PlayRecord) -> String {
+        let mut row = Vec::new();
+        record.write_csv(&mut row).unwrap();
+        String::from_utf8(row).unwrap()
+    }
+
+    #[test]
+    fn leaves_plain_fields_unquoted() {
+        assert_eq!(escape_csv("Tiny Dancer"), "Tiny Dancer");
+        assert_eq!(escape_csv(""), "");
+    }
+
+    #[test]
+    fn quotes_fields_with_separators() {
+        assert_eq!(escape_csv("Hello, World"), "\"Hello, World\"");
+        assert_eq!(escape_csv("two\nlines"), "\"two\nlines\"");
+        assert_eq!(escape_csv("two\r\nlines"), "\"two\r\nlines\"");
+    }
+
+    #[test]
+    fn doubles_quotes() {
+        assert_eq!(escape_csv("The \"Best\" Song"), "\"The \"\"Best\"\" Song\"");
+    }
+
+    #[test]
+    fn writes_csv_rows() {
+        assert_eq!(
+            csv_row(&record("Song, \"Live\"", "https://example.org/?a=1,2")),
+            "1970-01-01T00:00:00+00:00,1,\"Song, \"\"Live\"\"\",\"https://example.org/?a=1,2\",30,\n"
+        );
+
+        let mut with_duration = record("Song", "https://example.org/");
+        with_duration.duration_secs = Some(200);
+        assert_eq!(
+            csv_row(&with_duration),
+            "1970-01-01T00:00:00+00:00,1,Song,https://example.org/,30,200\n"
+        );
+    }
+
+    #[test]
+    fn exports_history_as_csv() {
+        let stats = Stats::new(
+            Arc::new(MemoryPersistence),
+            ResetSchedule {
+                timezone: chrono_tz::UTC,
+                week_start: Weekday::Mon,
+            },
+        );
+        let guild_id = GuildId(1);
+        let channel_id = ChannelId(2);
+        for title in ["First", "Second, with a comma"] {
+            let metadata = SongMetadata {
+                id: "id".to_string(),
+                title: title.to_string(),
+                artist: None,
+                uploader: None,
+                url: "https://example.org/".to_string(),
+                duration: Some(Duration::from_secs(200)),
+                site: None,
+                view_count: None,
+                upload_date: None,
+                thumbnail: None,
+                collection: None,
+                track_gain: None,
+                user_id: UserId(3),
+                queued_at: SystemTime::now(),
+            };
+            stats
+                .record_play(guild_id, channel_id, &metadata, false)
+                .unwrap();
+        }
+
+        let mut csv = Vec::new();
+        let count = stats
+            .export_history(guild_id, HistoryFormat::Csv, &mut csv)
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0], CSV_HEADER);
+        assert!(lines[1].ends_with(",3,First,https://example.org/,0,200"));
+        assert!(lines[2].ends_with(",3,\"Second, with a comma\",https://example.org/,0,200"));
+    }
+
+    #[test]
+    fn prunes_least_played_counts() {
+        let mut counts: HashMap<String, u64> = (0..=MAX_COUNTS_LENGTH)
+            .map(|index| (index.to_string(), if index < 10 { 2 } else { 1 }))
+            .collect();
+        prune_counts(&mut counts, "999");
+        assert_eq!(counts.len(), PRUNED_COUNTS_LENGTH);
+        assert!((0..10).all(|index| counts.contains_key(&index.to_string())));
+        assert!(counts.contains_key("999"));
+    }
+}
@@ -0,0 +1,102 @@
+use crate::persistence::{GuildStore, Persistence};
+use chrono::{NaiveDate, Utc};
+use serenity::model::prelude::*;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+pub enum StreakStatus {
+    Success,
+    Wait,
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct Streak {
+    // The last day the user high-fived, in the streak timezone.
+    last_day: String,
+    streak_days: u64,
+}
+
+impl Streak {
+    // Streaks are broken once a whole day goes by without a high-five.
+    fn current_length(&self, today: NaiveDate) -> u64 {
+        match self.last_day.parse::<NaiveDate>() {
+            Ok(last_day) if last_day >= today.pred() => self.streak_days,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct GuildStreaks {
+    // Keyed by user ID.
+    users: HashMap<u64, Streak>,
+}
+
+/// How many days in a row each user has used `/highfive` in each guild.
+pub struct Streaks {
+    store: GuildStore<GuildStreaks>,
+    timezone: chrono_tz::Tz,
+}
+
+impl Streaks {
+    pub fn new(persistence: Arc<dyn Persistence>, timezone: chrono_tz::Tz) -> Self {
+        Streaks {
+            store: GuildStore::new("streaks", persistence),
+            timezone,
+        }
+    }
+
+    pub fn add(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+    ) -> Result<StreakStatus, crate::error::Error> {
+        let today = self.today();
+        Ok(self.store.get(guild_id)?.update(|streaks| {
+            let streak = streaks.users.entry(user_id.0).or_default();
+            if streak.last_day.parse::<NaiveDate>().ok() == Some(today) {
+                return StreakStatus::Wait;
+            }
+            streak.streak_days = streak.current_length(today) + 1;
+            streak.last_day = today.to_string();
+            StreakStatus::Success
+        }))
+    }
+
+    pub fn get(&self, guild_id: GuildId, user_id: UserId) -> Result<u64, crate::error::Error> {
+        let today = self.today();
+        Ok(self.store.get(guild_id)?.read(|streaks| {
+            streaks
+                .users
+                .get(&user_id.0)
+                .map_or(0, |streak| streak.current_length(today))
+        }))
+    }
+
+    /// The longest unbroken streaks in the guild, longest first.
+    pub fn top(
+        &self,
+        guild_id: GuildId,
+        count: usize,
+    ) -> Result<Vec<(UserId, u64)>, crate::error::Error> {
+        let today = self.today();
+        let mut top: Vec<_> = self.store.get(guild_id)?.read(|streaks| {
+            streaks
+                .users
+                .iter()
+                .map(|(user_id, streak)| (UserId(*user_id), streak.current_length(today)))
+                .filter(|(_, streak_length)| *streak_length > 0)
+                .collect()
+        });
+        top.sort_by(|(_, a), (_, b)| b.cmp(a));
+        top.truncate(count);
+        Ok(top)
+    }
+
+    fn today(&self) -> NaiveDate {
+        Utc::now()
+            .with_timezone(&self.timezone)
+            .naive_local()
+            .date()
+    }
+}
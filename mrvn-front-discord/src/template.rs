@@ -0,0 +1,61 @@
+lazy_static::lazy_static! {
+    static ref SUBSTITUTE_REGEX: regex::Regex = regex::Regex::new(r"\{(\w+)\}").unwrap();
+}
+
+/// Fills in the `{name}` placeholders in a message template. Placeholders without a substitution
+/// are left empty, and anything else in braces is kept as it is.
+pub fn fill_template(template: &str, substitutions: &[(&str, &str)]) -> String {
+    SUBSTITUTE_REGEX
+        .replace_all(template, |caps: &regex::Captures| {
+            let substitute_name = &caps[1];
+            substitutions
+                .iter()
+                .find(|(key, _)| *key == substitute_name)
+                .map(|(_, value)| *value)
+                .unwrap_or("")
+        })
+        .into_owned()
+}
+
+/// The names of the placeholders in a message template, in the order they appear.
+pub fn template_placeholders(template: &str) -> impl Iterator<Item = &str> {
+    SUBSTITUTE_REGEX
+        .captures_iter(template)
+        .filter_map(|caps| caps.get(1))
+        .map(|name| name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fills_placeholders() {
+        assert_eq!(
+            fill_template(
+                "{user} queued {song}",
+                &[("song", "Tiny Dancer"), ("user", "Mira")]
+            ),
+            "Mira queued Tiny Dancer"
+        );
+    }
+
+    #[test]
+    fn leaves_missing_placeholders_empty() {
+        assert_eq!(fill_template("Now playing {song}", &[]), "Now playing ");
+    }
+
+    #[test]
+    fn keeps_other_braces() {
+        assert_eq!(
+            fill_template("{not a placeholder} {song-title} {}", &[("song", "x")]),
+            "{not a placeholder} {song-title} {}"
+        );
+    }
+
+    #[test]
+    fn lists_placeholders_in_order() {
+        let placeholders: Vec<_> = template_placeholders("{b} {a} {b} {not valid}").collect();
+        assert_eq!(placeholders, ["b", "a", "b"]);
+    }
+}
@@ -0,0 +1,182 @@
+use crate::event_bus::Event;
+use crate::frontend::Frontend;
+use crate::persistence::{GuildStore, Persistence};
+use serde_json::json;
+use serenity::http::Http;
+use serenity::model::prelude::*;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+
+const DEFAULT_THREAD_NAME: &str = "Track log";
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+struct TrackLogState {
+    thread_id: Option<ChannelId>,
+}
+
+/// Posts every track that starts playing to a webhook, or to a thread off each guild's message
+/// channel, so there's a history of what was played that sticks around.
+pub struct TrackLog {
+    frontend: Arc<Frontend>,
+    http: Arc<Http>,
+    client: reqwest::Client,
+    guild_states: GuildStore<TrackLogState>,
+}
+
+impl TrackLog {
+    pub fn new(
+        frontend: Arc<Frontend>,
+        http: Arc<Http>,
+        persistence: Arc<dyn Persistence>,
+    ) -> Self {
+        TrackLog {
+            frontend,
+            http,
+            client: reqwest::Client::new(),
+            guild_states: GuildStore::new("track-log", persistence),
+        }
+    }
+
+    pub async fn run(self, mut receiver: broadcast::Receiver<Event>) {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!("Track log fell behind, skipped {} events", skipped);
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+
+            if let Event::TrackStarted {
+                guild_id,
+                channel_id,
+                song_title,
+                song_url,
+                user_id,
+            } = event
+            {
+                let timestamp_string = chrono::Utc::now().timestamp().to_string();
+                let channel_id_string = channel_id.0.to_string();
+                let user_id_string = user_id.0.to_string();
+                let content = self.frontend.config.get_message(
+                    "track_log.entry",
+                    &[
+                        ("timestamp", &timestamp_string),
+                        ("song_title", &song_title),
+                        ("song_url", &song_url),
+                        ("voice_channel_id", &channel_id_string),
+                        ("user_id", &user_id_string),
+                    ],
+                );
+
+                if let Err(why) = self.log_track(guild_id, &content).await {
+                    log::error!("Error while logging track: {}", why);
+                }
+            }
+        }
+    }
+
+    async fn log_track(&self, guild_id: GuildId, content: &str) -> Result<(), crate::error::Error> {
+        let track_log_config = match &self.frontend.config.track_log {
+            Some(config) => config,
+            None => return Ok(()),
+        };
+
+        if let Some(webhook_url) = &track_log_config.webhook_url {
+            self.client
+                .post(webhook_url)
+                .json(&json!({
+                    "content": content,
+                    "allowed_mentions": { "parse": [] },
+                }))
+                .send()
+                .await
+                .and_then(|response| response.error_for_status())
+                .map_err(crate::error::Error::Request)?;
+            return Ok(());
+        }
+
+        // Tracks are logged in the session's thread while there is one.
+        let session_thread = {
+            let guild_model_handle = self.frontend.model.get(guild_id);
+            let playback = guild_model_handle.lock_playback().await;
+            playback.session_thread()
+        };
+        if let Some(session_thread) = session_thread {
+            return send_log_message(&self.http, session_thread, content)
+                .await
+                .map_err(crate::error::Error::Serenity);
+        }
+
+        let state = self.guild_states.get(guild_id)?;
+        if let Some(thread_id) = state.read(|state| state.thread_id) {
+            match send_log_message(&self.http, thread_id, content).await {
+                Ok(()) => return Ok(()),
+                Err(why) => {
+                    // The thread was probably deleted, so make a new one.
+                    log::warn!(
+                        "Unable to post to track log thread, creating a new one: {}",
+                        why
+                    );
+                    state.update(|state| state.thread_id = None);
+                }
+            }
+        }
+
+        let message_channel = {
+            let guild_model_handle = self.frontend.model.get(guild_id);
+            let playback = guild_model_handle.lock_playback().await;
+            playback.message_channel()
+        };
+        let message_channel = match message_channel {
+            Some(channel) => channel,
+            None => {
+                log::trace!("Guild has no message channel yet, not logging track");
+                return Ok(());
+            }
+        };
+
+        let thread_name = track_log_config
+            .thread_name
+            .as_deref()
+            .unwrap_or(DEFAULT_THREAD_NAME);
+        let starter_message = message_channel
+            .send_message(&self.http, |create_message| {
+                create_message.content(
+                    self.frontend
+                        .config
+                        .get_raw_message("track_log.thread_starter"),
+                )
+            })
+            .await
+            .map_err(crate::error::Error::Serenity)?;
+        let thread = message_channel
+            .create_public_thread(&self.http, starter_message.id, |thread| {
+                thread.name(thread_name)
+            })
+            .await
+            .map_err(crate::error::Error::Serenity)?;
+        state.update(|state| state.thread_id = Some(thread.id));
+
+        send_log_message(&self.http, thread.id, content)
+            .await
+            .map_err(crate::error::Error::Serenity)
+    }
+}
+
+async fn send_log_message(
+    http: &Http,
+    channel_id: ChannelId,
+    content: &str,
+) -> serenity::Result<()> {
+    channel_id
+        .send_message(http, |create_message| {
+            create_message
+                .content(content)
+                .allowed_mentions(|mentions| mentions.empty_parse())
+        })
+        .await
+        .map(|_| ())
+}
@@ -0,0 +1,168 @@
+// Guesses can be this far from the title, per character of the title, and still count. Enough to
+// forgive a typo or two without letting a different song's title through.
+const MAX_TYPOS_PER_CHAR: f32 = 0.2;
+
+// Anything in brackets or after one of these is usually extra detail nobody would guess.
+const TITLE_EXTRAS: &[&str] = &[" feat.", " ft.", " featuring ", " - official", " | "];
+
+/// A song title, cut down to the part people are expected to guess.
+#[derive(Debug, Clone)]
+pub struct Answer {
+    normalized: String,
+}
+
+impl Answer {
+    pub fn new(title: &str) -> Self {
+        let mut title = strip_brackets(&title.to_lowercase());
+        for extra in TITLE_EXTRAS {
+            if let Some(index) = title.find(extra) {
+                title.truncate(index);
+            }
+        }
+
+        // Video titles are often "Artist - Title", only the title needs guessing.
+        let title = match title.split_once(" - ") {
+            Some((_, song_title)) if !normalize(song_title).is_empty() => song_title,
+            _ => &title,
+        };
+        Answer {
+            normalized: normalize(title),
+        }
+    }
+
+    /// Whether a chat message names the song. Case, punctuation and small typos don't matter, and
+    /// the title can be part of a longer message.
+    pub fn is_matched_by(&self, guess: &str) -> bool {
+        let guess = normalize(guess);
+        if self.normalized.is_empty() || guess.is_empty() {
+            return false;
+        }
+        // Padded so only whole words match, a title like "Go" shouldn't be found in "good".
+        if format!(" {} ", guess).contains(&format!(" {} ", self.normalized)) {
+            return true;
+        }
+
+        let max_typos = (self.normalized.chars().count() as f32 * MAX_TYPOS_PER_CHAR) as usize;
+        edit_distance(&guess, &self.normalized) <= max_typos
+    }
+}
+
+fn strip_brackets(text: &str) -> String {
+    let mut depth = 0usize;
+    text.chars()
+        .filter(|c| match c {
+            '(' | '[' | '{' => {
+                depth += 1;
+                false
+            }
+            ')' | ']' | '}' => {
+                depth = depth.saturating_sub(1);
+                false
+            }
+            _ => depth == 0,
+        })
+        .collect()
+}
+
+// Lowercase letters and digits, with single spaces between words.
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// How many characters have to be added, removed or changed to turn one string into the other.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous_row: Vec<usize> = (0..=b.len()).collect();
+    for (a_index, a_char) in a.chars().enumerate() {
+        let mut row = vec![a_index + 1; b.len() + 1];
+        for (b_index, b_char) in b.iter().enumerate() {
+            let change_cost = if a_char == *b_char { 0 } else { 1 };
+            row[b_index + 1] = (previous_row[b_index] + change_cost)
+                .min(previous_row[b_index + 1] + 1)
+                .min(row[b_index] + 1);
+        }
+        previous_row = row;
+    }
+    previous_row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_case_and_punctuation() {
+        assert_eq!(normalize("  Don't STOP me -- now!! "), "don t stop me now");
+        assert_eq!(normalize("?!"), "");
+    }
+
+    #[test]
+    fn strips_brackets() {
+        assert_eq!(strip_brackets("song (live [2019]) edit"), "song  edit");
+        assert_eq!(strip_brackets("unbalanced) song"), "unbalanced song");
+    }
+
+    #[test]
+    fn keeps_only_the_song_title() {
+        let answer = Answer::new("Rick Astley - Never Gonna Give You Up (Official Video)");
+        assert_eq!(answer.normalized, "never gonna give you up");
+
+        assert_eq!(
+            Answer::new("Song Name feat. Someone").normalized,
+            "song name"
+        );
+        assert_eq!(
+            Answer::new("Song Name | Live at Wembley").normalized,
+            "song name"
+        );
+    }
+
+    #[test]
+    fn keeps_the_artist_without_a_title() {
+        assert_eq!(Answer::new("AC/DC - (Live)").normalized, "ac dc");
+    }
+
+    #[test]
+    fn matches_within_messages() {
+        let answer = Answer::new("Never Gonna Give You Up");
+        assert!(answer.is_matched_by("NEVER GONNA GIVE YOU UP!!!"));
+        assert!(answer.is_matched_by("i think it's never gonna give you up?"));
+        assert!(!answer.is_matched_by("give you up"));
+    }
+
+    #[test]
+    fn forgives_small_typos() {
+        let answer = Answer::new("Never Gonna Give You Up");
+        assert!(answer.is_matched_by("never gona giv you up"));
+        assert!(!answer.is_matched_by("together forever"));
+        assert!(Answer::new("Don't Stop Me Now").is_matched_by("dont stop me now"));
+    }
+
+    #[test]
+    fn only_matches_whole_words() {
+        let answer = Answer::new("Go");
+        assert!(answer.is_matched_by("go"));
+        assert!(answer.is_matched_by("is it go?"));
+        assert!(!answer.is_matched_by("good"));
+    }
+
+    #[test]
+    fn empty_titles_never_match() {
+        let answer = Answer::new("(Official Video)");
+        assert!(!answer.is_matched_by(""));
+        assert!(!answer.is_matched_by("official video"));
+    }
+
+    #[test]
+    fn counts_edits() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("same", "same"), 0);
+    }
+}
@@ -0,0 +1,186 @@
+mod answer;
+
+pub use self::answer::Answer;
+
+use dashmap::DashMap;
+use rand::Rng;
+use serenity::model::prelude::*;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// How much of each song is played.
+pub const EXCERPT_LENGTH: Duration = Duration::from_secs(15);
+
+struct Round {
+    answer: Answer,
+    started_at: Instant,
+    winner: Option<(UserId, Duration)>,
+}
+
+/// A game of guess-the-song. Excerpts play in a voice channel, and the first person to name each
+/// song in the text channel the game was started from scores a point.
+pub struct TriviaGame {
+    pub host_user_id: UserId,
+    pub text_channel_id: ChannelId,
+    pub voice_channel_id: ChannelId,
+    pub rounds: usize,
+    round: Mutex<Option<Round>>,
+    scores: Mutex<HashMap<UserId, u32>>,
+    // Woken when someone wins the round or the game is stopped.
+    round_ended: Notify,
+    is_stopped: AtomicBool,
+}
+
+impl TriviaGame {
+    pub fn new(
+        host_user_id: UserId,
+        text_channel_id: ChannelId,
+        voice_channel_id: ChannelId,
+        rounds: usize,
+    ) -> Self {
+        TriviaGame {
+            host_user_id,
+            text_channel_id,
+            voice_channel_id,
+            rounds,
+            round: Mutex::new(None),
+            scores: Mutex::new(HashMap::new()),
+            round_ended: Notify::new(),
+            is_stopped: AtomicBool::new(false),
+        }
+    }
+
+    pub fn start_round(&self, song_title: &str) {
+        *self.round.lock().unwrap() = Some(Round {
+            answer: Answer::new(song_title),
+            started_at: Instant::now(),
+            winner: None,
+        });
+    }
+
+    /// Returns `true` if the guess won the round.
+    pub fn guess(&self, user_id: UserId, guess: &str) -> bool {
+        let mut maybe_round = self.round.lock().unwrap();
+        let round = match maybe_round.as_mut() {
+            Some(round) if round.winner.is_none() => round,
+            _ => return false,
+        };
+        if !round.answer.is_matched_by(guess) {
+            return false;
+        }
+
+        round.winner = Some((user_id, round.started_at.elapsed()));
+        *self.scores.lock().unwrap().entry(user_id).or_default() += 1;
+        self.round_ended.notify_one();
+        true
+    }
+
+    /// Waits for someone to win the round, returning who won and how long they took. Returns
+    /// `None` if nobody got it in time or the game was stopped.
+    pub async fn wait_for_winner(&self, timeout: Duration) -> Option<(UserId, Duration)> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            if let Some(round) = self.round.lock().unwrap().as_ref() {
+                if round.winner.is_some() {
+                    break;
+                }
+            }
+            if self.is_stopped() {
+                break;
+            }
+            if tokio::time::timeout_at(deadline, self.round_ended.notified())
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+        self.round
+            .lock()
+            .unwrap()
+            .take()
+            .and_then(|round| round.winner)
+    }
+
+    pub fn stop(&self) {
+        self.is_stopped.store(true, Ordering::SeqCst);
+        self.round_ended.notify_one();
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.is_stopped.load(Ordering::SeqCst)
+    }
+
+    /// Everyone that's scored, highest score first.
+    pub fn scores(&self) -> Vec<(UserId, u32)> {
+        let mut scores: Vec<_> = self
+            .scores
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(user_id, score)| (*user_id, *score))
+            .collect();
+        scores.sort_by(|(a_user_id, a_score), (b_user_id, b_score)| {
+            b_score.cmp(a_score).then_with(|| a_user_id.cmp(b_user_id))
+        });
+        scores
+    }
+}
+
+/// The trivia games being played, at most one in each guild.
+#[derive(Default)]
+pub struct Trivia {
+    games: DashMap<GuildId, Arc<TriviaGame>>,
+}
+
+impl Trivia {
+    /// Returns `None` if there's already a game in the guild.
+    pub fn start(&self, guild_id: GuildId, game: TriviaGame) -> Option<Arc<TriviaGame>> {
+        match self.games.entry(guild_id) {
+            dashmap::mapref::entry::Entry::Occupied(_) => None,
+            dashmap::mapref::entry::Entry::Vacant(entry) => {
+                let game = Arc::new(game);
+                entry.insert(game.clone());
+                Some(game)
+            }
+        }
+    }
+
+    pub fn get(&self, guild_id: GuildId) -> Option<Arc<TriviaGame>> {
+        self.games.get(&guild_id).map(|game| game.value().clone())
+    }
+
+    pub fn end(&self, guild_id: GuildId) {
+        self.games.remove(&guild_id);
+    }
+
+    /// Checks a chat message against the guild's game, if it's being played in the channel the
+    /// message was sent in. Returns `true` if the message won the round.
+    pub fn guess(
+        &self,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        user_id: UserId,
+        guess: &str,
+    ) -> bool {
+        match self.get(guild_id) {
+            Some(game) if game.text_channel_id == channel_id => game.guess(user_id, guess),
+            _ => false,
+        }
+    }
+}
+
+/// Where to start the excerpt of a song. Intros are often quiet or the same between songs, so the
+/// start is skipped when there's enough song to pick from.
+pub fn excerpt_start<R: Rng + ?Sized>(duration: Option<Duration>, rng: &mut R) -> Duration {
+    let duration = match duration {
+        Some(duration) if duration > EXCERPT_LENGTH * 2 => duration,
+        _ => return Duration::ZERO,
+    };
+    let earliest = duration / 10;
+    let latest = duration - EXCERPT_LENGTH;
+    Duration::from_secs(rng.gen_range(earliest.as_secs()..=latest.as_secs()))
+}
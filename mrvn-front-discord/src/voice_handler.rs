@@ -1,16 +1,53 @@
+use crate::handover::Handover;
+use crate::recovery::PlaybackRecovery;
+use mrvn_back_ytdl::SpeakerKey;
 use serenity::{model::prelude::*, prelude::*};
+use std::sync::Arc;
 
 pub struct VoiceHandler {
     pub client_index: usize,
+    pub handover: Option<Arc<Handover>>,
+    pub recovery: Option<Arc<PlaybackRecovery>>,
 }
 
 #[serenity::async_trait]
 impl EventHandler for VoiceHandler {
     async fn ready(&self, _ctx: Context, ready: Ready) {
         log::info!(
-            "Voice client {} is connected as {}",
+            "Voice client {} is connected as {} on shard {:?}",
             self.client_index,
-            ready.user.name
+            ready.user.name,
+            ready.shard
         );
+        if let Some(handover) = &self.handover {
+            handover.voice_client_ready(self.client_index);
+        }
+        if let Some(recovery) = &self.recovery {
+            recovery.voice_client_ready(self.client_index);
+        }
+    }
+
+    async fn voice_state_update(
+        &self,
+        ctx: Context,
+        guild_id: Option<GuildId>,
+        _old: Option<VoiceState>,
+        new: VoiceState,
+    ) {
+        // Only the voice bot's own voice state matters here.
+        if new.user_id != ctx.cache.current_user_id().await {
+            return;
+        }
+        let guild_id = match guild_id.or(new.guild_id) {
+            Some(guild_id) => guild_id,
+            None => return,
+        };
+        let speaker = match ctx.data.read().await.get::<SpeakerKey>() {
+            Some(speaker) => speaker.clone(),
+            None => return,
+        };
+
+        let is_muted = new.channel_id.is_some() && (new.mute || new.suppress);
+        speaker.get(guild_id).lock().await.set_muted(is_muted);
     }
 }
@@ -0,0 +1,93 @@
+use crate::config::Config;
+use futures::prelude::*;
+use mrvn_back_ytdl::SpeakerEvent;
+use serde_json::json;
+use serenity::model::prelude::*;
+use std::sync::Arc;
+
+const DISCORD_API_URL: &str = "https://discord.com/api/v10";
+
+// Discord rejects statuses longer than this.
+const MAX_STATUS_LENGTH: usize = 500;
+
+/// Sets the status of voice channels to the song that's playing in them. The status can only be
+/// set by a bot that's connected to the channel, so requests are made with the playing speaker's
+/// token.
+pub struct VoiceStatus {
+    config: Arc<Config>,
+    client: reqwest::Client,
+}
+
+impl VoiceStatus {
+    pub fn new(config: Arc<Config>) -> Self {
+        VoiceStatus {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    pub async fn run(self, events: impl Stream<Item = SpeakerEvent>) {
+        futures::pin_mut!(events);
+        while let Some(event) = events.next().await {
+            let (speaker_index, channel_id, status) = match event {
+                SpeakerEvent::Started {
+                    speaker_index,
+                    channel_id,
+                    metadata,
+                    ..
+                } => {
+                    let status = match &metadata.artist {
+                        Some(artist) => self.config.get_message(
+                            "voice_status.playing",
+                            &[("song_title", &metadata.title), ("artist", artist)],
+                        ),
+                        None => self.config.get_message(
+                            "voice_status.playing_no_artist",
+                            &[("song_title", &metadata.title)],
+                        ),
+                    };
+                    (speaker_index, channel_id, status)
+                }
+                SpeakerEvent::Stopped {
+                    speaker_index,
+                    channel_id,
+                    ..
+                } => (speaker_index, channel_id, String::new()),
+                _ => continue,
+            };
+
+            if let Err(why) = self.set_status(speaker_index, channel_id, &status).await {
+                log::error!("Error while setting voice channel status: {}", why);
+            }
+        }
+    }
+
+    async fn set_status(
+        &self,
+        speaker_index: usize,
+        channel_id: ChannelId,
+        status: &str,
+    ) -> Result<(), reqwest::Error> {
+        let voice_bot = match self.config.voice_bots.get(speaker_index) {
+            Some(bot) => bot,
+            None => return Ok(()),
+        };
+        let status: String = status.chars().take(MAX_STATUS_LENGTH).collect();
+
+        log::trace!("Setting status of channel {} to \"{}\"", channel_id, status);
+        self.client
+            .put(format!(
+                "{}/channels/{}/voice-status",
+                DISCORD_API_URL, channel_id
+            ))
+            .header(
+                reqwest::header::AUTHORIZATION,
+                format!("Bot {}", voice_bot.token),
+            )
+            .json(&json!({ "status": status }))
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+use super::auth::Session;
+use super::WebState;
+use crate::event_bus::Event;
+use crate::model_delegate::ModelDelegate;
+use hyper::{Body, Method, Request, Response, StatusCode};
+use mrvn_back_ytdl::Song;
+use mrvn_model::{GuildModel, VoteStatus, VoteType};
+use serde_json::json;
+use serenity::model::prelude::*;
+
+pub async fn handle_api(
+    state: &WebState,
+    request: &Request<Body>,
+    segments: &[&str],
+) -> Response<Body> {
+    let session = match state.sessions.get(request) {
+        Some(session) => session,
+        None => return super::status_response(StatusCode::UNAUTHORIZED),
+    };
+
+    match (request.method(), segments) {
+        (&Method::GET, ["guilds"]) => list_guilds(state, &session).await,
+        (method, ["guilds", guild_id, rest @ ..]) => {
+            let guild_id = match guild_id.parse() {
+                Ok(id) => GuildId(id),
+                Err(_) => return super::status_response(StatusCode::BAD_REQUEST),
+            };
+
+            // Only allow access to guilds the user is a member of.
+            if !session.guild_ids.contains(&guild_id) {
+                return super::status_response(StatusCode::FORBIDDEN);
+            }
+
+            // Permissions mirror the in-Discord ones: everything applies to the user's own queue
+            // and the voice channel they're currently in.
+            let delegate = match ModelDelegate::from_cache(&state.cache, guild_id).await {
+                Ok(delegate) => delegate,
+                Err(_) => return super::status_response(StatusCode::NOT_FOUND),
+            };
+
+            match (method, rest) {
+                (&Method::GET, ["queue"]) => get_queue(state, &session, guild_id, &delegate).await,
+                (&Method::GET, ["events"]) => {
+                    super::event_stream::event_response(&state.frontend, Some(guild_id))
+                }
+                (&Method::POST, ["queue", "move"]) => {
+                    move_entry(state, &session, guild_id, request).await
+                }
+                (&Method::POST, ["pause"]) => {
+                    set_paused(state, &session, guild_id, &delegate, true).await
+                }
+                (&Method::POST, ["resume"]) => {
+                    set_paused(state, &session, guild_id, &delegate, false).await
+                }
+                (&Method::POST, ["skip"]) => skip(state, &session, guild_id, &delegate).await,
+                _ => super::status_response(StatusCode::NOT_FOUND),
+            }
+        }
+        _ => super::status_response(StatusCode::NOT_FOUND),
+    }
+}
+
+async fn list_guilds(state: &WebState, session: &Session) -> Response<Body> {
+    let mut guilds = Vec::new();
+    for guild_id in &session.guild_ids {
+        if let Some(guild_name) = state
+            .cache
+            .guild_field(*guild_id, |guild| guild.name.clone())
+            .await
+        {
+            guilds.push(json!({
+                "id": guild_id.0.to_string(),
+                "name": guild_name,
+            }));
+        }
+    }
+    super::json_response(json!({ "guilds": guilds }))
+}
+
+async fn get_queue(
+    state: &WebState,
+    session: &Session,
+    guild_id: GuildId,
+    delegate: &ModelDelegate,
+) -> Response<Body> {
+    let guild_snapshot = match state
+        .frontend
+        .model
+        .snapshot(guild_id, super::SNAPSHOT_TIMEOUT)
+        .await
+    {
+        Some(guild_snapshot) => guild_snapshot,
+        None => return super::status_response(StatusCode::SERVICE_UNAVAILABLE),
+    };
+
+    let voice_channel_id = delegate.get_user_voice_channel(session.user_id);
+    let playing = match voice_channel_id {
+        Some(channel_id) => {
+            let guild_speakers_handle = state.frontend.backend_brain.guild_speakers(guild_id);
+            let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+            guild_speakers_ref.find_active_in_channel(channel_id).map(
+                |(guild_speaker, metadata)| {
+                    json!({
+                        "title": metadata.title,
+                        "url": metadata.url,
+                        "user_id": metadata.user_id.0.to_string(),
+                        "paused": guild_speaker.is_paused(),
+                    })
+                },
+            )
+        }
+        None => None,
+    };
+
+    let queue: Vec<_> = guild_snapshot
+        .user_entries(session.user_id)
+        .iter()
+        .map(|song| {
+            json!({
+                "title": song.metadata.title,
+                "url": song.metadata.url,
+            })
+        })
+        .collect();
+
+    super::json_response(json!({
+        "voice_channel_id": voice_channel_id.map(|channel_id| channel_id.0.to_string()),
+        "playing": playing,
+        "queue": queue,
+        "version": guild_snapshot.user_queue_version(session.user_id),
+    }))
+}
+
+async fn move_entry(
+    state: &WebState,
+    session: &Session,
+    guild_id: GuildId,
+    request: &Request<Body>,
+) -> Response<Body> {
+    let (from_index, to_index) = match (
+        super::query_param(request, "from").and_then(|value| value.parse().ok()),
+        super::query_param(request, "to").and_then(|value| value.parse().ok()),
+    ) {
+        (Some(from_index), Some(to_index)) => (from_index, to_index),
+        _ => return super::status_response(StatusCode::BAD_REQUEST),
+    };
+    // With the version of the user's queue the move was made on, it's only made if their queue
+    // hasn't changed since, otherwise the indices could point at different songs.
+    let maybe_version = match super::query_param(request, "version") {
+        Some(value) => match value.parse() {
+            Ok(version) => Some(version),
+            Err(_) => return super::status_response(StatusCode::BAD_REQUEST),
+        },
+        None => None,
+    };
+
+    let guild_model_handle = state.frontend.model.get(guild_id);
+    let mut guild_model = guild_model_handle.lock_queues().await;
+    let move_song = |guild_model: &mut GuildModel<Song>| {
+        guild_model.move_user_entry(session.user_id, from_index, to_index)
+    };
+    let move_res = match maybe_version {
+        Some(version) => guild_model.change_if_user_version(session.user_id, version, move_song),
+        None => Ok(move_song(&mut guild_model)),
+    };
+    match move_res {
+        Ok(true) => {}
+        Ok(false) => return super::status_response(StatusCode::BAD_REQUEST),
+        Err(conflict) => {
+            let mut response = super::json_response(json!({
+                "version": conflict.current_version,
+            }));
+            *response.status_mut() = StatusCode::CONFLICT;
+            return response;
+        }
+    }
+
+    state.frontend.events.publish(Event::QueueChanged {
+        guild_id,
+        user_id: session.user_id,
+        by_script: false,
+    });
+    super::status_response(StatusCode::OK)
+}
+
+async fn set_paused(
+    state: &WebState,
+    session: &Session,
+    guild_id: GuildId,
+    delegate: &ModelDelegate,
+    paused: bool,
+) -> Response<Body> {
+    let channel_id = match delegate.get_user_voice_channel(session.user_id) {
+        Some(channel_id) => channel_id,
+        None => return super::status_response(StatusCode::CONFLICT),
+    };
+
+    let guild_speakers_handle = state.frontend.backend_brain.guild_speakers(guild_id);
+    let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+    let guild_speaker = match guild_speakers_ref.find_active_in_channel(channel_id) {
+        Some((guild_speaker, _)) => guild_speaker,
+        None => return super::status_response(StatusCode::CONFLICT),
+    };
+
+    let res = if paused {
+        guild_speaker.pause()
+    } else {
+        guild_speaker.unpause()
+    };
+    match res {
+        Ok(()) => super::status_response(StatusCode::OK),
+        Err(why) => {
+            log::error!("Error while changing playback from dashboard: {}", why);
+            super::status_response(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+async fn skip(
+    state: &WebState,
+    session: &Session,
+    guild_id: GuildId,
+    delegate: &ModelDelegate,
+) -> Response<Body> {
+    let channel_id = match delegate.get_user_voice_channel(session.user_id) {
+        Some(channel_id) => channel_id,
+        None => return super::status_response(StatusCode::CONFLICT),
+    };
+
+    let guild_model_handle = state.frontend.model.get(guild_id);
+    let mut playback = guild_model_handle.lock_playback().await;
+    match playback.vote_for_skip(delegate, VoteType::Skip, channel_id, session.user_id) {
+        VoteStatus::Success => {
+            let guild_speakers_handle = state.frontend.backend_brain.guild_speakers(guild_id);
+            let mut guild_speakers_ref = guild_speakers_handle.lock().await;
+            match guild_speakers_ref.find_active_in_channel(channel_id) {
+                Some((guild_speaker, _)) => match guild_speaker.stop() {
+                    Ok(()) => super::status_response(StatusCode::OK),
+                    Err(why) => {
+                        log::error!("Error while skipping from dashboard: {}", why);
+                        super::status_response(StatusCode::INTERNAL_SERVER_ERROR)
+                    }
+                },
+                None => super::status_response(StatusCode::CONFLICT),
+            }
+        }
+        VoteStatus::NeedsMoreVotes(count) => {
+            state.frontend.events.publish(Event::VoteProgress {
+                guild_id,
+                channel_id,
+                vote_type: "skip",
+                votes_needed: count,
+            });
+            super::json_response(json!({ "votes_needed": count }))
+        }
+        VoteStatus::AlreadyVoted | VoteStatus::NothingPlaying => {
+            super::status_response(StatusCode::CONFLICT)
+        }
+    }
+}
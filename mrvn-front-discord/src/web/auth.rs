@@ -0,0 +1,245 @@
+use super::WebState;
+use dashmap::DashMap;
+use hyper::header::{COOKIE, LOCATION, SET_COOKIE};
+use hyper::{Body, Request, Response, StatusCode};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serenity::model::prelude::*;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const DISCORD_AUTHORIZE_URL: &str = "https://discord.com/api/oauth2/authorize";
+const DISCORD_API_URL: &str = "https://discord.com/api/v9";
+const SESSION_COOKIE_NAME: &str = "mrvn_session";
+const TOKEN_LENGTH: usize = 32;
+// How long someone has to finish logging in on Discord's side.
+const STATE_TTL: Duration = Duration::from_secs(10 * 60);
+// Logins that haven't finished yet are capped, since anyone can start one.
+const MAX_PENDING_STATES: usize = 10_000;
+const SESSION_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A logged in dashboard user. The guild list is fetched from Discord when logging in, so the
+/// dashboard only shows guilds the user is actually a member of.
+pub struct Session {
+    pub user_id: UserId,
+    pub guild_ids: Vec<GuildId>,
+    created_at: Instant,
+}
+
+/// Logged in users by session token, and logins that have been started. Both expire, and expired
+/// ones are cleared out whenever a new one is added.
+#[derive(Default)]
+pub struct Sessions {
+    sessions: DashMap<String, Arc<Session>>,
+    // When each login was started.
+    pending_states: DashMap<String, Instant>,
+}
+
+impl Sessions {
+    pub fn get(&self, request: &Request<Body>) -> Option<Arc<Session>> {
+        let cookies = request.headers().get(COOKIE)?.to_str().ok()?;
+        let token = cookies.split(';').find_map(|cookie| {
+            let (name, value) = cookie.trim().split_once('=')?;
+            if name == SESSION_COOKIE_NAME {
+                Some(value)
+            } else {
+                None
+            }
+        })?;
+        let session = self.sessions.get(token)?.value().clone();
+        if session.created_at.elapsed() >= SESSION_TTL {
+            self.sessions.remove(token);
+            return None;
+        }
+        Some(session)
+    }
+
+    /// Returns `None` if too many logins have been started recently.
+    fn start_login(&self) -> Option<String> {
+        self.pending_states
+            .retain(|_, started_at| started_at.elapsed() < STATE_TTL);
+        if self.pending_states.len() >= MAX_PENDING_STATES {
+            return None;
+        }
+
+        let oauth_state = generate_token();
+        self.pending_states
+            .insert(oauth_state.clone(), Instant::now());
+        Some(oauth_state)
+    }
+
+    fn finish_login(&self, oauth_state: &str) -> bool {
+        matches!(
+            self.pending_states.remove(oauth_state),
+            Some((_, started_at)) if started_at.elapsed() < STATE_TTL
+        )
+    }
+
+    fn add(&self, session: Session) -> String {
+        self.sessions
+            .retain(|_, existing| existing.created_at.elapsed() < SESSION_TTL);
+        let token = generate_token();
+        self.sessions.insert(token.clone(), Arc::new(session));
+        token
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(serde::Deserialize)]
+struct CurrentUser {
+    id: UserId,
+}
+
+#[derive(serde::Deserialize)]
+struct CurrentUserGuild {
+    id: GuildId,
+}
+
+fn generate_token() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(TOKEN_LENGTH)
+        .map(char::from)
+        .collect()
+}
+
+pub fn handle_login(state: &WebState) -> Response<Body> {
+    let oauth_config = match &state.frontend.config.web {
+        Some(crate::config::WebConfig {
+            oauth: Some(oauth), ..
+        }) => oauth,
+        _ => return super::status_response(StatusCode::NOT_FOUND),
+    };
+
+    // The state parameter ensures the callback was started by us.
+    let oauth_state = match state.sessions.start_login() {
+        Some(oauth_state) => oauth_state,
+        None => return super::status_response(StatusCode::TOO_MANY_REQUESTS),
+    };
+
+    let client_id = state.frontend.config.command_bot.application_id.to_string();
+    let authorize_url = url::Url::parse_with_params(
+        DISCORD_AUTHORIZE_URL,
+        &[
+            ("client_id", client_id.as_str()),
+            ("redirect_uri", oauth_config.redirect_uri.as_str()),
+            ("response_type", "code"),
+            ("scope", "identify guilds"),
+            ("state", oauth_state.as_str()),
+        ],
+    )
+    .unwrap();
+
+    Response::builder()
+        .status(StatusCode::FOUND)
+        .header(LOCATION, authorize_url.as_str())
+        .body(Body::empty())
+        .unwrap()
+}
+
+pub async fn handle_callback(state: &WebState, request: &Request<Body>) -> Response<Body> {
+    let oauth_config = match &state.frontend.config.web {
+        Some(crate::config::WebConfig {
+            oauth: Some(oauth), ..
+        }) => oauth,
+        _ => return super::status_response(StatusCode::NOT_FOUND),
+    };
+
+    let (code, oauth_state) = match (
+        super::query_param(request, "code"),
+        super::query_param(request, "state"),
+    ) {
+        (Some(code), Some(oauth_state)) => (code, oauth_state),
+        _ => return super::status_response(StatusCode::BAD_REQUEST),
+    };
+    if !state.sessions.finish_login(&oauth_state) {
+        return super::status_response(StatusCode::BAD_REQUEST);
+    }
+
+    let client_id = state.frontend.config.command_bot.application_id.to_string();
+    let session = match create_session(
+        &client_id,
+        &oauth_config.client_secret,
+        &oauth_config.redirect_uri,
+        &code,
+    )
+    .await
+    {
+        Ok(session) => session,
+        Err(why) => {
+            log::error!("Error while logging in to dashboard: {}", why);
+            return super::status_response(StatusCode::BAD_GATEWAY);
+        }
+    };
+
+    log::debug!("User {} logged in to the dashboard", session.user_id);
+    let token = state.sessions.add(session);
+
+    Response::builder()
+        .status(StatusCode::FOUND)
+        .header(LOCATION, "/")
+        .header(
+            SET_COOKIE,
+            format!(
+                "{}={}; HttpOnly; Secure; Path=/; SameSite=Lax; Max-Age={}",
+                SESSION_COOKIE_NAME,
+                token,
+                SESSION_TTL.as_secs()
+            ),
+        )
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn create_session(
+    client_id: &str,
+    client_secret: &str,
+    redirect_uri: &str,
+    code: &str,
+) -> Result<Session, reqwest::Error> {
+    lazy_static::lazy_static! {
+        static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::builder().build().unwrap();
+    }
+
+    let token: TokenResponse = HTTP_CLIENT
+        .post(format!("{}/oauth2/token", DISCORD_API_URL))
+        .form(&[
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let user: CurrentUser = HTTP_CLIENT
+        .get(format!("{}/users/@me", DISCORD_API_URL))
+        .bearer_auth(&token.access_token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    let guilds: Vec<CurrentUserGuild> = HTTP_CLIENT
+        .get(format!("{}/users/@me/guilds", DISCORD_API_URL))
+        .bearer_auth(&token.access_token)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(Session {
+        user_id: user.id,
+        guild_ids: guilds.into_iter().map(|guild| guild.id).collect(),
+        created_at: Instant::now(),
+    })
+}
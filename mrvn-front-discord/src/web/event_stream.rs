@@ -0,0 +1,66 @@
+use crate::frontend::Frontend;
+use hyper::header::{CACHE_CONTROL, CONTENT_TYPE};
+use hyper::{Body, Request, Response, StatusCode};
+use serenity::model::prelude::*;
+use tokio::sync::broadcast::error::RecvError;
+
+/// Streams playback events to the client as server-sent events. The stream can be limited to a
+/// single guild with the `guild_id` query parameter.
+pub fn handle_events(frontend: &Frontend, request: &Request<Body>) -> Response<Body> {
+    let guild_filter = match super::query_param(request, "guild_id") {
+        Some(value) => match value.parse() {
+            Ok(id) => Some(GuildId(id)),
+            Err(_) => return super::status_response(StatusCode::BAD_REQUEST),
+        },
+        None => None,
+    };
+    event_response(frontend, guild_filter)
+}
+
+/// Streams events from one guild, or every guild if there's no filter.
+pub fn event_response(frontend: &Frontend, guild_filter: Option<GuildId>) -> Response<Body> {
+    let mut receiver = frontend.events.subscribe();
+    let (mut sender, body) = Body::channel();
+    tokio::spawn(async move {
+        loop {
+            let event = match receiver.recv().await {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    log::warn!(
+                        "Event stream client fell behind, skipped {} events",
+                        skipped
+                    );
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            };
+
+            if let Some(guild_id) = guild_filter {
+                if event.guild_id() != guild_id {
+                    continue;
+                }
+            }
+
+            let data = match serde_json::to_string(&event) {
+                Ok(data) => data,
+                Err(why) => {
+                    log::error!("Error while serializing event: {}", why);
+                    continue;
+                }
+            };
+
+            // An error here means the client has gone away.
+            let chunk = format!("event: {}\ndata: {}\n\n", event.name(), data);
+            if sender.send_data(chunk.into()).await.is_err() {
+                break;
+            }
+        }
+        log::trace!("Event stream client disconnected");
+    });
+
+    Response::builder()
+        .header(CONTENT_TYPE, "text/event-stream")
+        .header(CACHE_CONTROL, "no-cache")
+        .body(body)
+        .unwrap()
+}
@@ -0,0 +1,272 @@
+use crate::command_usage::CommandStats;
+use crate::frontend::Frontend;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Request, Response};
+use mrvn_back_ytdl::VoiceStats;
+use serde_json::json;
+use serenity::model::prelude::*;
+use std::fmt::Write;
+use std::time::Duration;
+
+// Shorter than the API's, since a scrape waits for the slowest guild.
+const LOCK_TIMEOUT: Duration = Duration::from_millis(100);
+
+// A metric's name, type and help text, with how to read its value from a speaker's stats.
+type VoiceMetric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&VoiceStats) -> Option<f64>,
+);
+// The same for a command's usage stats.
+type CommandMetric = (
+    &'static str,
+    &'static str,
+    &'static str,
+    fn(&CommandStats) -> f64,
+);
+
+struct GuildQueueStats {
+    guild_id: GuildId,
+    queued_songs: usize,
+    playing_channels: usize,
+}
+
+struct SpeakerVoiceStats {
+    speaker_index: usize,
+    guild_id: GuildId,
+    stats: VoiceStats,
+}
+
+/// Serves counters for keeping an eye on the bot as JSON, or in Prometheus' text format with
+/// `?format=prometheus`.
+pub async fn handle_metrics(frontend: &Frontend, request: &Request<Body>) -> Response<Body> {
+    let mut voice_stats = Vec::new();
+    for (speaker_index, speaker) in frontend.backend_brain.speakers.iter().enumerate() {
+        voice_stats.extend(
+            speaker
+                .voice_stats()
+                .await
+                .into_iter()
+                .map(|(guild_id, stats)| SpeakerVoiceStats {
+                    speaker_index,
+                    guild_id,
+                    stats,
+                }),
+        );
+    }
+
+    // Guilds busy with a command are left out rather than holding up the response. Idle guilds are
+    // too, so there isn't a series for every guild the bot has been used in.
+    let guilds = frontend.model.guilds();
+    let guild_stats_futures = guilds
+        .into_iter()
+        .map(|(guild_id, guild_model_handle)| async move {
+            let (playback, guild_model) =
+                tokio::time::timeout(LOCK_TIMEOUT, guild_model_handle.lock())
+                    .await
+                    .ok()?;
+            Some(GuildQueueStats {
+                guild_id,
+                queued_songs: guild_model.entry_count(),
+                playing_channels: playback.playing_channel_count(),
+            })
+        });
+    let queue_stats: Vec<_> = futures::future::join_all(guild_stats_futures)
+        .await
+        .into_iter()
+        .flatten()
+        .filter(|stats| stats.queued_songs > 0 || stats.playing_channels > 0)
+        .collect();
+
+    let command_stats = frontend.command_usage.commands();
+
+    if super::query_param(request, "format").as_deref() == Some("prometheus") {
+        return Response::builder()
+            .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+            .body(Body::from(prometheus_metrics(
+                &queue_stats,
+                &voice_stats,
+                &command_stats,
+            )))
+            .unwrap();
+    }
+
+    super::json_response(json!({
+        "expired_interaction_responses": crate::message::expired_interaction_responses(),
+        "guilds": queue_stats.iter().map(|guild_stats| json!({
+            "guild_id": guild_stats.guild_id,
+            "queued_songs": guild_stats.queued_songs,
+            "playing_channels": guild_stats.playing_channels,
+        })).collect::<Vec<_>>(),
+        "voice": voice_stats.iter().map(|speaker_stats| json!({
+            "speaker": speaker_stats.speaker_index,
+            "guild_id": speaker_stats.guild_id,
+            "connects": speaker_stats.stats.connects,
+            "reconnects": speaker_stats.stats.reconnects,
+            "disconnects": speaker_stats.stats.disconnects,
+            "packet_loss": speaker_stats.stats.packet_loss,
+            "packets_lost_total": speaker_stats.stats.packets_lost_total,
+            "jitter_ms": speaker_stats.stats.jitter_ms,
+        })).collect::<Vec<_>>(),
+        "commands": command_stats.iter().map(|(name, stats)| json!({
+            "command": name,
+            "count": stats.count,
+            "errors": stats.error_count,
+            "total_seconds": stats.total_time.as_secs_f64(),
+            "max_seconds": stats.max_time.as_secs_f64(),
+        })).collect::<Vec<_>>(),
+    }))
+}
+
+fn prometheus_metrics(
+    queue_stats: &[GuildQueueStats],
+    voice_stats: &[SpeakerVoiceStats],
+    command_stats: &[(String, CommandStats)],
+) -> String {
+    let mut output = String::new();
+    write_metric(
+        &mut output,
+        "mrvn_expired_interaction_responses_total",
+        "counter",
+        "Responses posted as regular messages because the interaction had expired.",
+        [(
+            String::new(),
+            crate::message::expired_interaction_responses() as f64,
+        )],
+    );
+
+    write_metric(
+        &mut output,
+        "mrvn_queued_songs",
+        "gauge",
+        "Songs waiting in the guild's queues.",
+        queue_stats.iter().map(|guild_stats| {
+            (
+                format!("{{guild_id=\"{}\"}}", guild_stats.guild_id),
+                guild_stats.queued_songs as f64,
+            )
+        }),
+    );
+    write_metric(
+        &mut output,
+        "mrvn_playing_channels",
+        "gauge",
+        "Voice channels in the guild with a song playing.",
+        queue_stats.iter().map(|guild_stats| {
+            (
+                format!("{{guild_id=\"{}\"}}", guild_stats.guild_id),
+                guild_stats.playing_channels as f64,
+            )
+        }),
+    );
+
+    let voice_metrics: [VoiceMetric; 6] = [
+        (
+            "mrvn_voice_connects_total",
+            "counter",
+            "Times the speaker connected to voice.",
+            |stats| Some(stats.connects as f64),
+        ),
+        (
+            "mrvn_voice_reconnects_total",
+            "counter",
+            "Times the speaker's voice connection dropped and was reconnected.",
+            |stats| Some(stats.reconnects as f64),
+        ),
+        (
+            "mrvn_voice_disconnects_total",
+            "counter",
+            "Times the speaker disconnected from voice.",
+            |stats| Some(stats.disconnects as f64),
+        ),
+        (
+            "mrvn_voice_packet_loss_ratio",
+            "gauge",
+            "Share of the speaker's packets lost in Discord's last report.",
+            |stats| stats.packet_loss,
+        ),
+        (
+            "mrvn_voice_packets_lost_total",
+            "counter",
+            "Packets from the speaker Discord has lost in total.",
+            |stats| stats.packets_lost_total.map(f64::from),
+        ),
+        (
+            "mrvn_voice_jitter_milliseconds",
+            "gauge",
+            "Jitter in the speaker's audio in Discord's last report.",
+            |stats| stats.jitter_ms,
+        ),
+    ];
+    for (name, kind, help, value) in voice_metrics {
+        write_metric(
+            &mut output,
+            name,
+            kind,
+            help,
+            voice_stats.iter().filter_map(|speaker_stats| {
+                let labels = format!(
+                    "{{speaker=\"{}\",guild_id=\"{}\"}}",
+                    speaker_stats.speaker_index, speaker_stats.guild_id
+                );
+                value(&speaker_stats.stats).map(|value| (labels, value))
+            }),
+        );
+    }
+
+    let command_metrics: [CommandMetric; 4] = [
+        (
+            "mrvn_commands_total",
+            "counter",
+            "Times the command was used.",
+            |stats| stats.count as f64,
+        ),
+        (
+            "mrvn_command_errors_total",
+            "counter",
+            "Times the command failed or was answered with an error.",
+            |stats| stats.error_count as f64,
+        ),
+        (
+            "mrvn_command_duration_seconds_total",
+            "counter",
+            "Time spent handling the command, summed over every use.",
+            |stats| stats.total_time.as_secs_f64(),
+        ),
+        (
+            "mrvn_command_duration_seconds_max",
+            "gauge",
+            "Longest the command has taken to handle.",
+            |stats| stats.max_time.as_secs_f64(),
+        ),
+    ];
+    for (name, kind, help, value) in command_metrics {
+        write_metric(
+            &mut output,
+            name,
+            kind,
+            help,
+            command_stats
+                .iter()
+                .map(|(command, stats)| (format!("{{command=\"{}\"}}", command), value(stats))),
+        );
+    }
+
+    output
+}
+
+fn write_metric(
+    output: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    samples: impl IntoIterator<Item = (String, f64)>,
+) {
+    // Writing to a string can't fail.
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        let _ = writeln!(output, "{}{} {}", name, labels, value);
+    }
+}
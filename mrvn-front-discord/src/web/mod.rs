@@ -0,0 +1,158 @@
+use crate::frontend::Frontend;
+use hyper::header::CONTENT_TYPE;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod api;
+mod auth;
+mod event_stream;
+mod metrics;
+
+const DASHBOARD_HTML: &str = include_str!("dashboard.html");
+
+// How long to wait on a guild that's busy with a command before giving up on reading its state.
+const SNAPSHOT_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct WebState {
+    pub frontend: Arc<Frontend>,
+    pub cache: Arc<serenity::cache::Cache>,
+    pub sessions: auth::Sessions,
+}
+
+/// Which endpoints a listener serves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Listener {
+    /// Playback events, metrics and health, which anyone who can reach them can read.
+    Internal,
+    /// The dashboard and logging in to it, which have to be reachable from users' browsers.
+    Dashboard,
+    /// Everything, when there's no dashboard to keep them apart from.
+    All,
+    /// The dashboard along with everything internal, when there's nowhere else to serve them.
+    Shared,
+}
+
+/// Serves everything on `bind_address` without the dashboard. With the dashboard on, only the
+/// dashboard is served there, and the rest is served on `internal_bind_address`. Without an
+/// internal address, everything stays on `bind_address` so nothing reading it breaks.
+pub async fn serve(
+    frontend: Arc<Frontend>,
+    cache: Arc<serenity::cache::Cache>,
+    bind_address: SocketAddr,
+    internal_bind_address: Option<SocketAddr>,
+) -> hyper::Result<()> {
+    // The dashboard is only served when OAuth is configured, since there's no other way to log in.
+    let dashboard_enabled = matches!(
+        &frontend.config.web,
+        Some(crate::config::WebConfig { oauth: Some(_), .. })
+    );
+    let state = Arc::new(WebState {
+        frontend,
+        cache,
+        sessions: auth::Sessions::default(),
+    });
+
+    if !dashboard_enabled {
+        return serve_listener(state, bind_address, Listener::All).await;
+    }
+    match internal_bind_address {
+        Some(internal_bind_address) => {
+            futures::try_join!(
+                serve_listener(state.clone(), bind_address, Listener::Dashboard),
+                serve_listener(state, internal_bind_address, Listener::Internal),
+            )?;
+            Ok(())
+        }
+        None => {
+            log::warn!(
+                "No internal_bind_address is set, so events, metrics and health are served \
+                 alongside the dashboard on {}",
+                bind_address
+            );
+            serve_listener(state, bind_address, Listener::Shared).await
+        }
+    }
+}
+
+async fn serve_listener(
+    state: Arc<WebState>,
+    bind_address: SocketAddr,
+    listener: Listener,
+) -> hyper::Result<()> {
+    let make_service = make_service_fn(move |_conn| {
+        let state = state.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                handle_request(state.clone(), request, listener)
+            }))
+        }
+    });
+
+    log::info!("Serving {:?} web endpoints on {}", listener, bind_address);
+    Server::bind(&bind_address).serve(make_service).await
+}
+
+async fn handle_request(
+    state: Arc<WebState>,
+    request: Request<Body>,
+    listener: Listener,
+) -> Result<Response<Body>, Infallible> {
+    log::trace!("Received {} {}", request.method(), request.uri());
+
+    let is_internal = listener != Listener::Dashboard;
+    let is_dashboard = matches!(listener, Listener::Dashboard | Listener::Shared);
+    let path = request.uri().path().to_string();
+    let segments: Vec<_> = path.trim_matches('/').split('/').collect();
+    let response = match (request.method(), segments.as_slice()) {
+        (&Method::GET, ["events"]) if is_internal => {
+            event_stream::handle_events(&state.frontend, &request)
+        }
+        (&Method::GET, ["metrics"]) if is_internal => {
+            metrics::handle_metrics(&state.frontend, &request).await
+        }
+        (&Method::GET, ["health"]) if is_internal => handle_health(&state.frontend).await,
+        (&Method::GET, [""]) if is_dashboard => Response::builder()
+            .header(CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(Body::from(DASHBOARD_HTML))
+            .unwrap(),
+        (&Method::GET, ["login"]) if is_dashboard => auth::handle_login(&state),
+        (&Method::GET, ["callback"]) if is_dashboard => {
+            auth::handle_callback(&state, &request).await
+        }
+        (_, ["api", rest @ ..]) if is_dashboard => api::handle_api(&state, &request, rest).await,
+        _ => status_response(StatusCode::NOT_FOUND),
+    };
+    Ok(response)
+}
+
+// Lets an upgrade wait until maintenance mode has parked every speaker before restarting.
+async fn handle_health(frontend: &Frontend) -> Response<Body> {
+    json_response(serde_json::json!({
+        "status": if frontend.is_in_maintenance() { "maintenance" } else { "ok" },
+        "playing_speakers": frontend.playing_speaker_count().await,
+    }))
+}
+
+fn query_param(request: &Request<Body>, name: &str) -> Option<String> {
+    let query = request.uri().query()?;
+    url::form_urlencoded::parse(query.as_bytes())
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.into_owned())
+}
+
+fn status_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::from(status.to_string()));
+    *response.status_mut() = status;
+    response
+}
+
+fn json_response(value: serde_json::Value) -> Response<Body> {
+    Response::builder()
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(value.to_string()))
+        .unwrap()
+}
@@ -0,0 +1,26 @@
+use crate::persistence::{Persisted, Persistence};
+use serenity::model::prelude::*;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Guilds the bot has posted its welcome message in, so it's only posted once per guild, even if
+/// the bot is removed and added back.
+pub struct Welcomes {
+    guilds: Persisted<HashSet<u64>>,
+}
+
+impl Welcomes {
+    pub fn load(persistence: Arc<dyn Persistence>) -> Result<Self, crate::error::Error> {
+        Ok(Welcomes {
+            guilds: Persisted::load(persistence, "welcomed_guilds".to_string())?,
+        })
+    }
+
+    pub fn is_welcomed(&self, guild_id: GuildId) -> bool {
+        self.guilds.read(|guilds| guilds.contains(&guild_id.0))
+    }
+
+    pub fn mark_welcomed(&self, guild_id: GuildId) {
+        self.guilds.update(|guilds| guilds.insert(guild_id.0));
+    }
+}
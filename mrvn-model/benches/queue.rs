@@ -0,0 +1,118 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use mrvn_model::{AppModelConfig, AppModelDelegate, GuildModel, PlaybackState};
+use serenity::model::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+const ENTRY_COUNT: u64 = 10_000;
+const PLAYLIST_USER_ID: UserId = UserId(1);
+const CHANNEL_ID: ChannelId = ChannelId(1);
+
+struct EveryoneListening;
+
+impl AppModelDelegate for EveryoneListening {
+    fn is_user_in_voice_channel(&self, _user_id: UserId, _channel_id: ChannelId) -> bool {
+        true
+    }
+
+    fn is_user_in_any_voice_channel(&self, _user_id: UserId) -> bool {
+        true
+    }
+
+    fn user_roles(&self, _user_id: UserId) -> Vec<RoleId> {
+        Vec::new()
+    }
+}
+
+fn config() -> AppModelConfig {
+    AppModelConfig {
+        skip_votes_required: 1,
+        stop_votes_required: 1,
+        vote_expiry: None,
+        undo_window: Duration::from_secs(60),
+        role_priorities: HashMap::new(),
+        max_user_entries: None,
+        max_guild_entries: None,
+    }
+}
+
+fn new_model() -> GuildModel<u64> {
+    GuildModel::new(config())
+}
+
+// One user has queued a big playlist, with a few other users queueing a song each.
+fn model_with_playlist() -> GuildModel<u64> {
+    let mut model = new_model();
+    model.push_entries(PLAYLIST_USER_ID, 0..ENTRY_COUNT);
+    for user_id in 2..10 {
+        model.push_entries(UserId(user_id), [ENTRY_COUNT + user_id]);
+    }
+    model
+}
+
+fn queue_benchmarks(c: &mut Criterion) {
+    c.bench_function("push 10k", |b| {
+        b.iter_batched(
+            new_model,
+            |mut model| {
+                model.push_entries(PLAYLIST_USER_ID, 0..ENTRY_COUNT);
+                model
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("next of 10k", |b| {
+        b.iter_batched(
+            || (PlaybackState::new(config()), model_with_playlist()),
+            |(mut playback, mut model)| {
+                black_box(model.next_channel_entry_finished(
+                    &mut playback,
+                    &EveryoneListening,
+                    CHANNEL_ID,
+                ));
+                (playback, model)
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("remove middle of 10k", |b| {
+        b.iter_batched(
+            model_with_playlist,
+            |mut model| {
+                let index = (ENTRY_COUNT / 2) as usize;
+                black_box(model.remove_user_entry(PLAYLIST_USER_ID, index));
+                model
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("move across 10k", |b| {
+        b.iter_batched(
+            model_with_playlist,
+            |mut model| {
+                let last_index = ENTRY_COUNT as usize - 1;
+                black_box(model.move_user_entry(PLAYLIST_USER_ID, last_index, 0));
+                model
+            },
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("shuffle 10k", |b| {
+        let mut rng = rand::thread_rng();
+        b.iter_batched(
+            model_with_playlist,
+            |mut model| {
+                black_box(model.shuffle_user_entries(PLAYLIST_USER_ID, &mut rng));
+                model
+            },
+            BatchSize::SmallInput,
+        )
+    });
+}
+
+criterion_group!(benches, queue_benchmarks);
+criterion_main!(benches);
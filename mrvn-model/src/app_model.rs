@@ -1,12 +1,56 @@
-use crate::{AppModelConfig, GuildModel};
+use crate::{AppModelConfig, GuildModel, GuildSnapshot, PlaybackState};
 use dashmap::DashMap;
 use serenity::model::prelude::*;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::{Mutex, MutexGuard};
+
+type GuildInitializer<QueueEntry> = Box<dyn Fn(GuildId, &mut GuildModel<QueueEntry>) + Send + Sync>;
+
+/// A guild's queues and playback state, each behind its own lock. Adding to a queue only needs
+/// the queues, so it doesn't wait on a command that's busy connecting to a channel or starting a
+/// song, which only holds the playback state for that long.
+///
+/// When both are needed the playback state is always locked first, which `lock` does. The queues
+/// shouldn't be held across anything slow.
+pub struct GuildHandle<QueueEntry> {
+    playback: Mutex<PlaybackState>,
+    queues: Mutex<GuildModel<QueueEntry>>,
+}
+
+impl<QueueEntry> GuildHandle<QueueEntry> {
+    pub fn new(playback: PlaybackState, queues: GuildModel<QueueEntry>) -> Self {
+        GuildHandle {
+            playback: Mutex::new(playback),
+            queues: Mutex::new(queues),
+        }
+    }
+
+    pub async fn lock_playback(&self) -> MutexGuard<'_, PlaybackState> {
+        self.playback.lock().await
+    }
+
+    pub async fn lock_queues(&self) -> MutexGuard<'_, GuildModel<QueueEntry>> {
+        self.queues.lock().await
+    }
+
+    /// Locks the playback state and then the queues.
+    pub async fn lock(
+        &self,
+    ) -> (
+        MutexGuard<'_, PlaybackState>,
+        MutexGuard<'_, GuildModel<QueueEntry>>,
+    ) {
+        let playback = self.playback.lock().await;
+        let queues = self.queues.lock().await;
+        (playback, queues)
+    }
+}
 
 pub struct AppModel<QueueEntry> {
     config: AppModelConfig,
-    guilds: DashMap<GuildId, Arc<Mutex<GuildModel<QueueEntry>>>>,
+    guilds: DashMap<GuildId, Arc<GuildHandle<QueueEntry>>>,
+    guild_initializer: Option<GuildInitializer<QueueEntry>>,
 }
 
 impl<QueueEntry> AppModel<QueueEntry> {
@@ -14,14 +58,70 @@ impl<QueueEntry> AppModel<QueueEntry> {
         AppModel {
             config,
             guilds: DashMap::new(),
+            guild_initializer: None,
         }
     }
 
-    pub fn get(&self, guild_id: GuildId) -> Arc<Mutex<GuildModel<QueueEntry>>> {
-        let handle = self
-            .guilds
+    /// Sets up guild models when they're first created, e.g. to apply settings saved for the
+    /// guild.
+    pub fn set_guild_initializer(
+        &mut self,
+        initializer: impl Fn(GuildId, &mut GuildModel<QueueEntry>) + Send + Sync + 'static,
+    ) {
+        self.guild_initializer = Some(Box::new(initializer));
+    }
+
+    /// Every guild that's been used since the bot started.
+    pub fn guilds(&self) -> Vec<(GuildId, Arc<GuildHandle<QueueEntry>>)> {
+        self.guilds
+            .iter()
+            .map(|guild| (*guild.key(), guild.value().clone()))
+            .collect()
+    }
+
+    /// The guild's model, creating it if it hasn't been used yet. Anything slow, like loading
+    /// songs, should be done before locking it.
+    pub fn get(&self, guild_id: GuildId) -> Arc<GuildHandle<QueueEntry>> {
+        if let Some(handle) = self.guilds.get(&guild_id) {
+            return handle.clone();
+        }
+
+        // The initializer can be slow (e.g. loading settings from disk), so it runs without
+        // holding the map's lock, which would block every other guild in the same shard of the
+        // map. If events from two shards race to create the same guild, the first one in wins.
+        let mut guild_model = GuildModel::new(self.config.clone());
+        if let Some(initializer) = &self.guild_initializer {
+            initializer(guild_id, &mut guild_model);
+        }
+        let playback = PlaybackState::new(self.config.clone());
+        self.guilds
             .entry(guild_id)
-            .or_insert_with(|| Arc::new(Mutex::new(GuildModel::new(self.config))));
-        handle.clone()
+            .or_insert_with(|| Arc::new(GuildHandle::new(playback, guild_model)))
+            .clone()
+    }
+
+    /// The guild's model, if it's been used.
+    pub fn get_existing(&self, guild_id: GuildId) -> Option<Arc<GuildHandle<QueueEntry>>> {
+        self.guilds.get(&guild_id).map(|handle| handle.clone())
+    }
+
+    /// Copies the guild's queues and playing state for diagnostics, waiting at most `timeout` for
+    /// whatever's using the guild to finish so a slow command can't hold up the caller too. `None`
+    /// if it didn't finish in time. Guilds that haven't been used get an empty snapshot, without
+    /// creating a model for them.
+    pub async fn snapshot(
+        &self,
+        guild_id: GuildId,
+        timeout: Duration,
+    ) -> Option<GuildSnapshot<QueueEntry>>
+    where
+        QueueEntry: Clone,
+    {
+        let handle = match self.get_existing(guild_id) {
+            Some(handle) => handle,
+            None => return Some(GuildSnapshot::default()),
+        };
+        let (playback, guild_model) = tokio::time::timeout(timeout, handle.lock()).await.ok()?;
+        Some(guild_model.snapshot(&playback))
     }
 }
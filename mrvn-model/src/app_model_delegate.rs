@@ -2,4 +2,6 @@ use serenity::model::prelude::*;
 
 pub trait AppModelDelegate {
     fn is_user_in_voice_channel(&self, user_id: UserId, channel_id: ChannelId) -> bool;
+    fn is_user_in_any_voice_channel(&self, user_id: UserId) -> bool;
+    fn user_roles(&self, user_id: UserId) -> Vec<RoleId>;
 }
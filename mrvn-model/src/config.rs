@@ -1,7 +1,20 @@
-#[derive(Debug, Clone, Copy)]
+use serenity::model::prelude::*;
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
 pub struct AppModelConfig {
     pub skip_votes_required: usize,
     pub stop_votes_required: usize,
-
-    pub secret_highfive_timezone: chrono_tz::Tz,
+    /// Skip and stop votes older than this no longer count.
+    pub vote_expiry: Option<Duration>,
+    /// How long users have to undo changes to their queue.
+    pub undo_window: Duration,
+    /// Songs from users with higher priority roles are played before everyone else's.
+    pub role_priorities: HashMap<RoleId, u32>,
+    /// How many entries each user can have queued at once, if there's a limit.
+    pub max_user_entries: Option<usize>,
+    /// How many entries can be queued in a guild at once across everyone's queues, if there's a
+    /// limit.
+    pub max_guild_entries: Option<usize>,
 }
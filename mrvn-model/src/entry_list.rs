@@ -0,0 +1,316 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use std::collections::VecDeque;
+use std::ops::Index;
+
+// Entries are kept in chunks of around this size, so inserting or removing in the middle of a
+// long queue only shifts the entries in one chunk instead of everything after it.
+const CHUNK_SIZE: usize = 64;
+
+/// A list of queued entries that stays cheap to change anywhere in it, even when a big playlist
+/// has put thousands of entries in one queue.
+pub struct EntryList<T> {
+    chunks: VecDeque<VecDeque<T>>,
+    len: usize,
+}
+
+impl<T> EntryList<T> {
+    pub fn new() -> Self {
+        EntryList {
+            chunks: VecDeque::new(),
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        let (chunk_index, entry_index) = self.locate(index)?;
+        self.chunks[chunk_index].get(entry_index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.chunks.iter().flatten()
+    }
+
+    pub fn push_back(&mut self, entry: T) {
+        match self.chunks.back_mut() {
+            Some(chunk) if chunk.len() < CHUNK_SIZE => chunk.push_back(entry),
+            _ => {
+                let mut chunk = VecDeque::with_capacity(CHUNK_SIZE);
+                chunk.push_back(entry);
+                self.chunks.push_back(chunk);
+            }
+        }
+        self.len += 1;
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let chunk = self.chunks.back_mut()?;
+        let entry = chunk.pop_back()?;
+        if chunk.is_empty() {
+            self.chunks.pop_back();
+        }
+        self.len -= 1;
+        Some(entry)
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let chunk = self.chunks.front_mut()?;
+        let entry = chunk.pop_front()?;
+        if chunk.is_empty() {
+            self.chunks.pop_front();
+        }
+        self.len -= 1;
+        Some(entry)
+    }
+
+    /// Inserts the entry so it ends up at `index`, panicking if that's past the end of the list
+    /// like `VecDeque::insert`.
+    pub fn insert(&mut self, index: usize, entry: T) {
+        assert!(index <= self.len, "index out of bounds");
+        if index == self.len {
+            self.push_back(entry);
+            return;
+        }
+
+        let (chunk_index, entry_index) = self.locate(index).unwrap();
+        let chunk = &mut self.chunks[chunk_index];
+        chunk.insert(entry_index, entry);
+        self.len += 1;
+
+        // Split chunks that have grown too big, so they stay cheap to insert into.
+        if chunk.len() > CHUNK_SIZE * 2 {
+            let tail = chunk.split_off(CHUNK_SIZE);
+            self.chunks.insert(chunk_index + 1, tail);
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<T> {
+        let (chunk_index, entry_index) = self.locate(index)?;
+        let entry = self.chunks[chunk_index].remove(entry_index)?;
+        self.len -= 1;
+
+        // Merge small chunks into their neighbours, so finding an index doesn't have to step
+        // through lots of nearly empty ones.
+        if self.chunks[chunk_index].is_empty() {
+            self.chunks.remove(chunk_index);
+        } else if chunk_index + 1 < self.chunks.len()
+            && self.chunks[chunk_index].len() + self.chunks[chunk_index + 1].len() <= CHUNK_SIZE
+        {
+            let mut next_chunk = self.chunks.remove(chunk_index + 1).unwrap();
+            self.chunks[chunk_index].append(&mut next_chunk);
+        } else if chunk_index > 0
+            && self.chunks[chunk_index - 1].len() + self.chunks[chunk_index].len() <= CHUNK_SIZE
+        {
+            let mut chunk = self.chunks.remove(chunk_index).unwrap();
+            self.chunks[chunk_index - 1].append(&mut chunk);
+        }
+        Some(entry)
+    }
+
+    pub fn shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        let mut entries: Vec<_> = std::mem::take(self).into_iter().collect();
+        entries.shuffle(rng);
+        *self = entries.into_iter().collect();
+    }
+
+    // The chunk an index is in, and where it is in that chunk.
+    fn locate(&self, mut index: usize) -> Option<(usize, usize)> {
+        if index >= self.len {
+            return None;
+        }
+        for (chunk_index, chunk) in self.chunks.iter().enumerate() {
+            if index < chunk.len() {
+                return Some((chunk_index, index));
+            }
+            index -= chunk.len();
+        }
+        None
+    }
+}
+
+impl<T> Default for EntryList<T> {
+    fn default() -> Self {
+        EntryList::new()
+    }
+}
+
+impl<T> Index<usize> for EntryList<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T> Extend<T> for EntryList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, entries: I) {
+        for entry in entries {
+            self.push_back(entry);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for EntryList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(entries: I) -> Self {
+        let mut list = EntryList::new();
+        list.extend(entries);
+        list
+    }
+}
+
+impl<T> IntoIterator for EntryList<T> {
+    type Item = T;
+    type IntoIter = std::iter::Flatten<std::collections::vec_deque::IntoIter<VecDeque<T>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks.into_iter().flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn chunk_lens<T>(list: &EntryList<T>) -> Vec<usize> {
+        list.chunks.iter().map(VecDeque::len).collect()
+    }
+
+    fn assert_matches(list: &EntryList<usize>, expected: &VecDeque<usize>) {
+        assert_eq!(list.len(), expected.len());
+        assert_eq!(chunk_lens(list).iter().sum::<usize>(), expected.len());
+        assert!(list.iter().eq(expected.iter()));
+        for (index, entry) in expected.iter().enumerate() {
+            assert_eq!(list.get(index), Some(entry));
+        }
+        assert_eq!(list.get(expected.len()), None);
+    }
+
+    #[test]
+    fn empty_list() {
+        let mut list = EntryList::<usize>::new();
+        assert!(list.is_empty());
+        assert_eq!(list.len(), 0);
+        assert_eq!(list.get(0), None);
+        assert_eq!(list.iter().next(), None);
+        assert_eq!(list.remove(0), None);
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        list.insert(0, 1);
+        assert_eq!(list.pop_back(), Some(1));
+        assert!(list.is_empty());
+        assert!(list.chunks.is_empty());
+    }
+
+    #[test]
+    fn push_back_fills_chunks() {
+        let list: EntryList<usize> = (0..CHUNK_SIZE * 3 + 1).collect();
+        assert_eq!(
+            chunk_lens(&list),
+            vec![CHUNK_SIZE, CHUNK_SIZE, CHUNK_SIZE, 1]
+        );
+        assert_matches(&list, &(0..CHUNK_SIZE * 3 + 1).collect());
+    }
+
+    #[test]
+    fn insert_and_remove_across_chunk_boundary() {
+        let mut list: EntryList<usize> = (0..CHUNK_SIZE * 2).collect();
+        let mut expected: VecDeque<usize> = (0..CHUNK_SIZE * 2).collect();
+
+        for index in [CHUNK_SIZE - 1, CHUNK_SIZE, CHUNK_SIZE + 1] {
+            list.insert(index, 1000 + index);
+            expected.insert(index, 1000 + index);
+            assert_matches(&list, &expected);
+        }
+        for index in [CHUNK_SIZE + 1, CHUNK_SIZE, CHUNK_SIZE - 1] {
+            assert_eq!(list.remove(index), expected.remove(index));
+            assert_matches(&list, &expected);
+        }
+    }
+
+    #[test]
+    fn insert_splits_full_chunk() {
+        let mut list: EntryList<usize> = (0..CHUNK_SIZE).collect();
+        let mut expected: VecDeque<usize> = (0..CHUNK_SIZE).collect();
+
+        for entry in 0..CHUNK_SIZE {
+            list.insert(0, 1000 + entry);
+            expected.push_front(1000 + entry);
+        }
+        assert_eq!(chunk_lens(&list), vec![CHUNK_SIZE * 2]);
+
+        list.insert(0, 2000);
+        expected.push_front(2000);
+        assert_eq!(chunk_lens(&list), vec![CHUNK_SIZE, CHUNK_SIZE + 1]);
+        assert_matches(&list, &expected);
+    }
+
+    #[test]
+    fn remove_merges_small_chunks() {
+        let mut list: EntryList<usize> = (0..CHUNK_SIZE + 10).collect();
+        let mut expected: VecDeque<usize> = (0..CHUNK_SIZE + 10).collect();
+        assert_eq!(chunk_lens(&list), vec![CHUNK_SIZE, 10]);
+
+        for _ in 0..9 {
+            assert_eq!(list.remove(0), expected.pop_front());
+        }
+        assert_eq!(chunk_lens(&list), vec![CHUNK_SIZE - 9, 10]);
+
+        assert_eq!(list.remove(0), expected.pop_front());
+        assert_eq!(chunk_lens(&list), vec![CHUNK_SIZE]);
+        assert_matches(&list, &expected);
+    }
+
+    #[test]
+    fn remove_drops_empty_chunk() {
+        let mut list: EntryList<usize> = (0..CHUNK_SIZE + 1).collect();
+        assert_eq!(list.remove(CHUNK_SIZE), Some(CHUNK_SIZE));
+        assert_eq!(chunk_lens(&list), vec![CHUNK_SIZE]);
+        assert_eq!(list.remove(CHUNK_SIZE), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of bounds")]
+    fn insert_past_end_panics() {
+        let mut list: EntryList<usize> = (0..3).collect();
+        list.insert(4, 0);
+    }
+
+    #[test]
+    fn matches_vec_deque() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut list = EntryList::new();
+        let mut expected = VecDeque::new();
+
+        for entry in 0..10_000 {
+            match rng.gen_range(0..5) {
+                0 | 1 => {
+                    let index = rng.gen_range(0..=expected.len());
+                    list.insert(index, entry);
+                    expected.insert(index, entry);
+                }
+                2 if !expected.is_empty() => {
+                    let index = rng.gen_range(0..expected.len());
+                    assert_eq!(list.remove(index), expected.remove(index));
+                }
+                3 => assert_eq!(list.pop_front(), expected.pop_front()),
+                _ => {
+                    list.push_back(entry);
+                    expected.push_back(entry);
+                }
+            }
+            assert_eq!(list.len(), expected.len());
+        }
+        assert_matches(&list, &expected);
+    }
+}
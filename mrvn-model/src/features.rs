@@ -0,0 +1,17 @@
+/// Optional behaviour that each guild can turn on or off. It's copied into the guild's model
+/// from its settings, so every check for a feature looks in the same place.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GuildFeatures {
+    /// Read out each song and who queued it before it plays.
+    pub announcements: bool,
+    /// Keep playing when the bot is moved to the channel the last song's requester moved to.
+    pub follow_requester: bool,
+    /// Post updates in a thread that's archived once playback stops.
+    pub session_threads: bool,
+    /// Show songs starting to play as a generated image.
+    pub now_playing_cards: bool,
+    /// Prefer official audio when searching.
+    pub music_mode: bool,
+    /// Prime the next song from the same album or playlist so it starts without a gap.
+    pub gapless: bool,
+}
@@ -1,30 +1,14 @@
-use crate::{AppModelConfig, AppModelDelegate};
-use chrono::{Date, TimeZone, Utc};
+use crate::entry_list::EntryList;
+use crate::playback_state::ChannelPlayingState;
+use crate::{
+    AppModelConfig, AppModelDelegate, ChannelSnapshot, GuildFeatures, PlaybackState, QueueSummary,
+    SchedulingStrategy,
+};
+use rand::Rng;
 use serenity::model::prelude::*;
-use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet, VecDeque};
-
-fn find_first_user_in_channel<'a, Entry: 'a, Delegate: AppModelDelegate>(
-    mut queues: impl Iterator<Item = &'a Queue<Entry>>,
-    delegate: &Delegate,
-    channel_id: ChannelId,
-) -> Option<UserId> {
-    queues
-        .find(|queue| delegate.is_user_in_voice_channel(queue.user_id, channel_id))
-        .map(|queue| queue.user_id)
-}
-
-pub enum VoteType {
-    Skip,
-    Stop,
-}
-
-pub enum VoteStatus {
-    Success,
-    AlreadyVoted,
-    NeedsMoreVotes(usize),
-    NothingPlaying,
-}
+use std::hash::Hash;
+use std::time::{Duration, Instant};
 
 pub enum ReplaceStatus<QueueEntry> {
     Queued,
@@ -38,121 +22,372 @@ pub enum NextEntry<QueueEntry> {
     Entry(QueueEntry),
 }
 
-pub enum SecretStreakStatus {
-    Success,
-    Wait,
+/// Entries that were removed from a user's queue because they weren't in a voice channel.
+pub struct DroppedEntries {
+    pub user_id: UserId,
+    pub count: usize,
 }
 
-struct Queue<Entry> {
-    user_id: UserId,
-    entries: VecDeque<Entry>,
+/// A queue length limit that stopped entries from being queued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueLimit {
+    /// The user already had this many entries queued.
+    User(usize),
+    /// Everyone's queues in the guild together already had this many entries.
+    Guild(usize),
+}
+
+/// How many entries `push_entries_bulk` queued, and how many it turned away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BulkPushResult {
+    pub accepted: usize,
+    pub rejected: usize,
+    /// Which limit was reached, if any entries were turned away.
+    pub limit: Option<QueueLimit>,
+}
+
+/// A change to a user's queue that can be undone with `undo_user_change`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueChangeKind {
+    Queue,
+    Remove,
+    Move,
+    Replace,
+    Clear,
+}
+
+/// A user's queue as it can be saved and restored later, e.g. across a restart.
+#[derive(Debug, Clone)]
+pub struct SavedQueue<QueueEntry> {
+    pub user_id: UserId,
+    pub entries: Vec<QueueEntry>,
+    /// When each position in the queue was requested, which decides whose turn is next.
+    pub requests: Vec<u64>,
+}
+
+/// A copy of a guild's queues and what's playing in its channels, which can be looked at without
+/// holding up commands in the guild.
+#[derive(Debug, Clone)]
+pub struct GuildSnapshot<QueueEntry> {
+    pub queues: Vec<SavedQueue<QueueEntry>>,
+    pub channels: Vec<ChannelSnapshot>,
+    pub scheduling_strategy: SchedulingStrategy,
+    pub queue_version: u64,
+    user_queue_versions: HashMap<UserId, u64>,
 }
 
-enum ChannelPlayingState {
-    NotPlaying,
-    Stopped,
-    Playing {
-        playing_user_id: UserId,
-        skip_votes: HashSet<UserId>,
-        stop_votes: HashSet<UserId>,
-    },
+impl<QueueEntry> GuildSnapshot<QueueEntry> {
+    pub fn user_entries(&self, user_id: UserId) -> &[QueueEntry] {
+        self.queues
+            .iter()
+            .find(|queue| queue.user_id == user_id)
+            .map_or(&[], |queue| &queue.entries)
+    }
+
+    /// The version of the user's queue, to pass to `change_if_user_version`.
+    pub fn user_queue_version(&self, user_id: UserId) -> u64 {
+        self.user_queue_versions
+            .get(&user_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn entry_count(&self) -> usize {
+        self.queues.iter().map(|queue| queue.entries.len()).sum()
+    }
 }
 
-impl ChannelPlayingState {
-    fn is_playing(&self) -> bool {
-        matches!(self, ChannelPlayingState::Playing { .. })
+impl<QueueEntry> Default for GuildSnapshot<QueueEntry> {
+    fn default() -> Self {
+        GuildSnapshot {
+            queues: Vec::new(),
+            channels: Vec::new(),
+            scheduling_strategy: SchedulingStrategy::default(),
+            queue_version: 0,
+            user_queue_versions: HashMap::new(),
+        }
     }
 }
 
-struct ChannelModel {
-    playing: ChannelPlayingState,
+/// A change was based on an older copy of a queue than the current one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionConflict {
+    /// The version to retry the change against, after looking at the queue again.
+    pub current_version: u64,
+}
+
+struct Queue<Entry> {
+    user_id: UserId,
+    entries: EntryList<Entry>,
+    // When each position in the queue was requested. These stay in order when entries are moved
+    // around, so reordering your own queue doesn't change when your turn comes up.
+    requests: VecDeque<u64>,
+    // The guild's queue version when this queue last changed.
+    version: u64,
 }
 
-struct SecretStreak {
-    last_time: Date<Utc>,
-    streak_days: u64,
+// Each change has what's needed to reverse it.
+enum QueueChange<Entry> {
+    Queued { count: usize },
+    Removed { index: usize, entry: Entry },
+    Moved { from_index: usize, to_index: usize },
+    Replaced { entry: Entry },
+    Cleared { entries: Vec<Entry> },
 }
 
-#[derive(Clone, Copy)]
-pub struct GuildActionMessage {
-    pub channel_id: ChannelId,
-    pub message_id: MessageId,
+struct JournalRecord<Entry> {
+    user_id: UserId,
+    change: QueueChange<Entry>,
+    time: Instant,
 }
 
+// Old changes expire after the undo window anyway, this just stops the journal growing in a busy
+// guild.
+const MAX_UNDO_JOURNAL_LENGTH: usize = 32;
+
+// How long someone who has left a channel has to come back before their queue is dropped, if
+// the guild drops absent users' queues. Dropping only happens when a song ends, so it can take
+// longer.
+const ABSENT_GRACE_PERIOD: Duration = Duration::from_secs(60);
+
+/// A guild's queues, and how it picks whose turn is next. Picking the next entry for a channel
+/// also needs the guild's `PlaybackState`, which is passed in.
 pub struct GuildModel<QueueEntry> {
     config: AppModelConfig,
-    message_channel: Option<ChannelId>,
-    last_action_message: Option<GuildActionMessage>,
     queues: Vec<Queue<QueueEntry>>,
-    channels: HashMap<ChannelId, ChannelModel>,
-
-    secret_streaks: HashMap<UserId, SecretStreak>,
+    scheduling_strategy: SchedulingStrategy,
+    features: GuildFeatures,
+    next_request: u64,
+    drop_absent_entries: bool,
+    dropped_entries: Vec<DroppedEntries>,
+    undo_journal: VecDeque<JournalRecord<QueueEntry>>,
+    // Goes up every time the queues change.
+    queue_version: u64,
 }
 
 impl<QueueEntry> GuildModel<QueueEntry> {
     pub fn new(config: AppModelConfig) -> Self {
         GuildModel {
             config,
-            message_channel: None,
-            last_action_message: None,
             queues: Vec::new(),
-            channels: HashMap::new(),
-
-            secret_streaks: HashMap::new(),
+            scheduling_strategy: SchedulingStrategy::default(),
+            features: GuildFeatures::default(),
+            next_request: 0,
+            drop_absent_entries: false,
+            dropped_entries: Vec::new(),
+            undo_journal: VecDeque::new(),
+            queue_version: 0,
         }
     }
 
-    pub fn message_channel(&self) -> Option<ChannelId> {
-        self.message_channel
+    pub fn scheduling_strategy(&self) -> SchedulingStrategy {
+        self.scheduling_strategy
     }
 
-    pub fn set_message_channel(&mut self, message_channel: Option<ChannelId>) {
-        self.message_channel = message_channel;
+    pub fn set_scheduling_strategy(&mut self, scheduling_strategy: SchedulingStrategy) {
+        self.scheduling_strategy = scheduling_strategy;
     }
 
-    pub fn last_action_message(&self) -> Option<GuildActionMessage> {
-        self.last_action_message
+    pub fn features(&self) -> GuildFeatures {
+        self.features
     }
 
-    pub fn set_last_action_message(&mut self, status_message: Option<GuildActionMessage>) {
-        self.last_action_message = status_message;
+    pub fn set_features(&mut self, features: GuildFeatures) {
+        self.features = features;
     }
 
-    pub fn is_channel_stopped(&self, channel_id: ChannelId) -> bool {
-        matches!(
-            self.get_channel_playing_state(channel_id),
-            Some(ChannelPlayingState::Stopped)
-        )
+    pub fn set_drop_absent_entries(&mut self, drop_absent_entries: bool) {
+        self.drop_absent_entries = drop_absent_entries;
+    }
+
+    /// Entries dropped since this was last called, if dropping entries from users who have left
+    /// voice is enabled.
+    pub fn take_dropped_entries(&mut self) -> Vec<DroppedEntries> {
+        std::mem::take(&mut self.dropped_entries)
+    }
+
+    /// How many entries are queued across everyone's queues.
+    pub fn entry_count(&self) -> usize {
+        self.queues.iter().map(|queue| queue.entries.len()).sum()
     }
 
-    pub fn set_channel_stopped(&mut self, channel_id: ChannelId) {
-        self.create_channel(channel_id).playing = ChannelPlayingState::Stopped;
+    /// Goes up every time the queues change, so they only need saving again once it has.
+    pub fn queue_version(&self) -> u64 {
+        self.queue_version
+    }
+
+    /// The version of the user's queue, which only changes when their queue does.
+    pub fn user_queue_version(&self, user_id: UserId) -> u64 {
+        self.queues
+            .iter()
+            .find(|queue| queue.user_id == user_id)
+            .map_or(0, |queue| queue.version)
+    }
+
+    /// Makes a change only if the user's queue is still at `expected_version`, like the one in a
+    /// snapshot, so a change based on an old copy of their queue can't clobber a newer one.
+    /// Changes to other users' queues don't get in the way.
+    pub fn change_if_user_version<T>(
+        &mut self,
+        user_id: UserId,
+        expected_version: u64,
+        change: impl FnOnce(&mut Self) -> T,
+    ) -> Result<T, VersionConflict> {
+        let current_version = self.user_queue_version(user_id);
+        if current_version != expected_version {
+            return Err(VersionConflict { current_version });
+        }
+        Ok(change(self))
+    }
+
+    pub fn saved_queues(&self) -> Vec<SavedQueue<QueueEntry>>
+    where
+        QueueEntry: Clone,
+    {
+        self.queues
+            .iter()
+            .map(|queue| SavedQueue {
+                user_id: queue.user_id,
+                entries: queue.entries.iter().cloned().collect(),
+                requests: queue.requests.iter().copied().collect(),
+            })
+            .collect()
+    }
+
+    /// Replaces the queues with ones saved earlier. The restored entries can't be undone.
+    pub fn restore_queues(&mut self, saved_queues: Vec<SavedQueue<QueueEntry>>) {
+        self.queue_version += 1;
+        let queue_version = self.queue_version;
+        self.queues = saved_queues
+            .into_iter()
+            .filter(|saved_queue| {
+                !saved_queue.entries.is_empty()
+                    && saved_queue.entries.len() == saved_queue.requests.len()
+            })
+            .map(|saved_queue| Queue {
+                user_id: saved_queue.user_id,
+                entries: saved_queue.entries.into_iter().collect(),
+                requests: saved_queue.requests.into(),
+                version: queue_version,
+            })
+            .collect();
+        self.next_request = self
+            .queues
+            .iter()
+            .filter_map(|queue| queue.requests.back())
+            .max()
+            .map_or(0, |request| request + 1);
+        self.undo_journal.clear();
+    }
+
+    pub fn snapshot(&self, playback: &PlaybackState) -> GuildSnapshot<QueueEntry>
+    where
+        QueueEntry: Clone,
+    {
+        GuildSnapshot {
+            queues: self.saved_queues(),
+            channels: playback.channel_snapshots(),
+            scheduling_strategy: self.scheduling_strategy,
+            queue_version: self.queue_version,
+            user_queue_versions: self
+                .queues
+                .iter()
+                .map(|queue| (queue.user_id, queue.version))
+                .collect(),
+        }
     }
 
     // User commands:
     pub fn push_entries(&mut self, user_id: UserId, entries: impl IntoIterator<Item = QueueEntry>) {
+        let mut next_request = self.next_request;
         let queue = self.create_user_queue(user_id);
         for entry in entries {
             queue.entries.push_back(entry);
+            queue.requests.push_back(next_request);
+            next_request += 1;
+        }
+        let count = (next_request - self.next_request) as usize;
+        self.next_request = next_request;
+        if count > 0 {
+            self.record_change(user_id, QueueChange::Queued { count });
+        }
+    }
+
+    /// Queues entries in order until the user's or the guild's queue length limit is reached,
+    /// and turns the rest away.
+    pub fn push_entries_bulk(
+        &mut self,
+        user_id: UserId,
+        entries: impl IntoIterator<Item = QueueEntry>,
+    ) -> BulkPushResult {
+        let user_room = self.config.max_user_entries.map(|max| {
+            let count = self.user_entries(user_id).count();
+            (max.saturating_sub(count), QueueLimit::User(max))
+        });
+        let guild_room = self.config.max_guild_entries.map(|max| {
+            (
+                max.saturating_sub(self.entry_count()),
+                QueueLimit::Guild(max),
+            )
+        });
+        let maybe_room = user_room
+            .into_iter()
+            .chain(guild_room)
+            .min_by_key(|(room, _)| *room);
+
+        let mut entries = entries.into_iter();
+        let accepted_entries: Vec<_> = match maybe_room {
+            Some((room, _)) => entries.by_ref().take(room).collect(),
+            None => entries.by_ref().collect(),
+        };
+        let accepted = accepted_entries.len();
+        self.push_entries(user_id, accepted_entries);
+        let rejected = entries.count();
+
+        BulkPushResult {
+            accepted,
+            rejected,
+            limit: maybe_room.filter(|_| rejected > 0).map(|(_, limit)| limit),
         }
     }
 
     pub fn replace_entry(
         &mut self,
+        playback: &PlaybackState,
         user_id: UserId,
         maybe_channel_id: Option<ChannelId>,
         entry: QueueEntry,
-    ) -> ReplaceStatus<QueueEntry> {
+    ) -> ReplaceStatus<QueueEntry>
+    where
+        QueueEntry: Clone,
+    {
+        let next_request = self.next_request;
         let queue = self.create_user_queue(user_id);
         let removed_entry = queue.entries.pop_back();
         queue.entries.push_back(entry);
 
+        // A replaced entry keeps its place, otherwise this is a new request.
+        if removed_entry.is_none() {
+            queue.requests.push_back(next_request);
+            self.next_request += 1;
+        }
+
         match removed_entry {
-            Some(entry) => ReplaceStatus::ReplacedInQueue(entry),
+            Some(entry) => {
+                self.record_change(
+                    user_id,
+                    QueueChange::Replaced {
+                        entry: entry.clone(),
+                    },
+                );
+                ReplaceStatus::ReplacedInQueue(entry)
+            }
             None => {
+                self.record_change(user_id, QueueChange::Queued { count: 1 });
+
                 // If the current channel is playing this user, the current song should be skipped.
                 if let Some(channel_id) = maybe_channel_id {
-                    let maybe_playing_user = self.get_channel_playing_user(channel_id);
+                    let maybe_playing_user = playback.get_channel_playing_user(channel_id);
                     if maybe_playing_user == Some(user_id) {
                         return ReplaceStatus::ReplacedCurrent(channel_id);
                     }
@@ -163,187 +398,610 @@ impl<QueueEntry> GuildModel<QueueEntry> {
         }
     }
 
-    pub fn secret_add_streak(&mut self, user_id: UserId) -> SecretStreakStatus {
-        let now_time = Utc::today();
-
-        match self.secret_streaks.entry(user_id) {
-            Entry::Occupied(mut o) => {
-                let streak = o.get_mut();
-
-                let last_day = self
-                    .config
-                    .secret_highfive_timezone
-                    .from_utc_date(&streak.last_time.naive_utc());
-                let now_day = self
-                    .config
-                    .secret_highfive_timezone
-                    .from_utc_date(&now_time.naive_utc());
-
-                if now_day == last_day {
-                    SecretStreakStatus::Wait
-                } else if now_day == last_day.succ() {
-                    streak.streak_days += 1;
-                    streak.last_time = now_time;
-                    SecretStreakStatus::Success
-                } else {
-                    streak.streak_days = 1;
-                    streak.last_time = now_time;
-                    SecretStreakStatus::Success
-                }
+    pub fn user_entries(&self, user_id: UserId) -> impl Iterator<Item = &QueueEntry> {
+        self.queues
+            .iter()
+            .filter(move |queue| queue.user_id == user_id)
+            .flat_map(|queue| queue.entries.iter())
+    }
+
+    pub fn move_user_entry(&mut self, user_id: UserId, from_index: usize, to_index: usize) -> bool {
+        let queue = match self.get_user_queue_mut(user_id) {
+            Some(queue) => queue,
+            None => return false,
+        };
+        if from_index >= queue.entries.len() || to_index >= queue.entries.len() {
+            return false;
+        }
+
+        let entry = queue.entries.remove(from_index).unwrap();
+        queue.entries.insert(to_index, entry);
+        self.record_change(
+            user_id,
+            QueueChange::Moved {
+                from_index,
+                to_index,
+            },
+        );
+        true
+    }
+
+    /// Shuffles the user's queued entries, returning how many there are. Like other reordering,
+    /// the user's turns stay where they were. Earlier changes can't be undone afterwards, since
+    /// their positions no longer mean anything.
+    pub fn shuffle_user_entries<R: Rng + ?Sized>(&mut self, user_id: UserId, rng: &mut R) -> usize {
+        let count = match self.get_user_queue_mut(user_id) {
+            Some(queue) => {
+                queue.entries.shuffle(rng);
+                queue.entries.len()
             }
-            Entry::Vacant(v) => {
-                v.insert(SecretStreak {
-                    last_time: now_time,
-                    streak_days: 1,
-                });
-                SecretStreakStatus::Success
+            None => return 0,
+        };
+        self.user_queue_changed(user_id);
+        self.forget_user_changes(user_id);
+        count
+    }
+
+    pub fn remove_user_entry(&mut self, user_id: UserId, index: usize) -> Option<QueueEntry>
+    where
+        QueueEntry: Clone,
+    {
+        let queue = self.get_user_queue_mut(user_id)?;
+        let entry = queue.entries.remove(index)?;
+
+        // The entries after this one move up a turn, so the user loses their last turn.
+        queue.requests.pop_back();
+        self.record_change(
+            user_id,
+            QueueChange::Removed {
+                index,
+                entry: entry.clone(),
+            },
+        );
+        Some(entry)
+    }
+
+    /// Removes entries queued by users in the channel that have the same key as an entry that was
+    /// requested before them, returning the number of entries that were removed.
+    pub fn dedupe_entries<Delegate: AppModelDelegate, Key: Eq + Hash>(
+        &mut self,
+        playback: &PlaybackState,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+        key: impl Fn(&QueueEntry) -> Key,
+    ) -> usize {
+        // Look at entries in the order they were requested, so the earliest of each is kept.
+        let mut requests: Vec<_> = self
+            .queues
+            .iter()
+            .enumerate()
+            .filter(|(_, queue)| playback.is_user_listening(delegate, queue.user_id, channel_id))
+            .flat_map(|(queue_index, queue)| {
+                queue
+                    .requests
+                    .iter()
+                    .enumerate()
+                    .map(move |(entry_index, request)| (*request, queue_index, entry_index))
+            })
+            .collect();
+        requests.sort_unstable();
+
+        let mut seen_keys = HashSet::new();
+        let duplicates = requests
+            .into_iter()
+            .filter(|(_, queue_index, entry_index)| {
+                !seen_keys.insert(key(&self.queues[*queue_index].entries[*entry_index]))
+            })
+            .map(|(_, queue_index, entry_index)| (queue_index, entry_index))
+            .collect();
+        self.remove_positions(duplicates)
+    }
+
+    /// Returns the number of entries that were removed.
+    pub fn clear_user_entries(&mut self, user_id: UserId) -> usize {
+        let entries: Vec<_> = match self.get_user_queue_mut(user_id) {
+            Some(queue) => {
+                queue.requests.clear();
+                std::mem::take(&mut queue.entries).into_iter().collect()
             }
+            None => return 0,
+        };
+        let count = entries.len();
+        if count > 0 {
+            self.record_change(user_id, QueueChange::Cleared { entries });
         }
+        count
     }
 
-    pub fn secret_get_streak(&self, user_id: UserId) -> u64 {
-        match self.secret_streaks.get(&user_id) {
-            Some(streak) => {
-                let now_time = Utc::today();
-
-                let last_day = self
-                    .config
-                    .secret_highfive_timezone
-                    .from_utc_date(&streak.last_time.naive_utc());
-                let now_day = self
-                    .config
-                    .secret_highfive_timezone
-                    .from_utc_date(&now_time.naive_utc());
-
-                if last_day < now_day.pred() {
-                    0
-                } else {
-                    streak.streak_days
+    /// Reverses the latest change the user made to their queue within the undo window. Entries
+    /// put back in the queue take a new turn, and entries that have played since the change
+    /// can't be brought back.
+    pub fn undo_user_change(&mut self, user_id: UserId) -> Option<QueueChangeKind> {
+        let undo_window = self.config.undo_window;
+        let record_index = self
+            .undo_journal
+            .iter()
+            .rposition(|record| record.user_id == user_id && record.time.elapsed() < undo_window)?;
+        let record = self.undo_journal.remove(record_index)?;
+
+        let mut next_request = self.next_request;
+        let queue = self.create_user_queue(user_id);
+        let kind = match record.change {
+            QueueChange::Queued { count } => {
+                for _ in 0..count.min(queue.entries.len()) {
+                    queue.entries.pop_back();
+                    queue.requests.pop_back();
                 }
+                QueueChangeKind::Queue
             }
-            None => 0,
+            QueueChange::Removed { index, entry } => {
+                queue.entries.insert(index.min(queue.entries.len()), entry);
+                queue.requests.push_back(next_request);
+                next_request += 1;
+                QueueChangeKind::Remove
+            }
+            QueueChange::Moved {
+                from_index,
+                to_index,
+            } => {
+                if from_index < queue.entries.len() && to_index < queue.entries.len() {
+                    let entry = queue.entries.remove(to_index).unwrap();
+                    queue.entries.insert(from_index, entry);
+                }
+                QueueChangeKind::Move
+            }
+            QueueChange::Replaced { entry } => {
+                if queue.entries.pop_back().is_none() {
+                    queue.requests.push_back(next_request);
+                    next_request += 1;
+                }
+                queue.entries.push_back(entry);
+                QueueChangeKind::Replace
+            }
+            QueueChange::Cleared { entries } => {
+                for entry in entries {
+                    queue.entries.push_back(entry);
+                    queue.requests.push_back(next_request);
+                    next_request += 1;
+                }
+                QueueChangeKind::Clear
+            }
+        };
+        self.next_request = next_request;
+        self.user_queue_changed(user_id);
+        Some(kind)
+    }
+
+    /// Stops the user undoing changes made so far, for when someone else changed their queue.
+    pub fn forget_user_changes(&mut self, user_id: UserId) {
+        self.undo_journal.retain(|record| record.user_id != user_id);
+    }
+
+    fn record_change(&mut self, user_id: UserId, change: QueueChange<QueueEntry>) {
+        self.user_queue_changed(user_id);
+        let undo_window = self.config.undo_window;
+        self.undo_journal
+            .retain(|record| record.time.elapsed() < undo_window);
+        if self.undo_journal.len() >= MAX_UNDO_JOURNAL_LENGTH {
+            self.undo_journal.pop_front();
         }
+        self.undo_journal.push_back(JournalRecord {
+            user_id,
+            change,
+            time: Instant::now(),
+        });
     }
 
     // Events:
     pub fn next_channel_entry_finished<Delegate: AppModelDelegate>(
         &mut self,
+        playback: &mut PlaybackState,
         delegate: &Delegate,
         channel_id: ChannelId,
     ) -> Option<QueueEntry> {
         let old_playing_state = std::mem::replace(
-            &mut self.create_channel(channel_id).playing,
+            &mut playback.create_channel(channel_id).playing,
             ChannelPlayingState::NotPlaying,
         );
 
-        // Round-robin to the next user
-        let next_user_id = match old_playing_state {
+        let last_user_id = match old_playing_state {
             ChannelPlayingState::Playing {
-                playing_user_id: user_id,
-                ..
-            } => {
-                let last_playing_queue_index = self
-                    .queues
-                    .iter_mut()
-                    .position(|queue| queue.user_id == user_id);
-                match last_playing_queue_index {
-                    Some(last_playing_index) => {
-                        // Search queues from after the last active one, back around to it again
-                        let queues_iter = self
-                            .queues
-                            .iter()
-                            .skip(last_playing_index + 1)
-                            .chain(self.queues.iter().take(last_playing_index + 1));
-                        find_first_user_in_channel(queues_iter, delegate, channel_id)
-                    }
-                    None => find_first_user_in_channel(self.queues.iter(), delegate, channel_id),
-                }
-            }
-            _ => find_first_user_in_channel(self.queues.iter(), delegate, channel_id),
-        }?;
+                playing_user_id, ..
+            } => Some(playing_user_id),
+            _ => None,
+        };
+        if self.drop_absent_entries {
+            self.drop_absent_queues(playback, delegate, channel_id);
+        }
+        let queues = &self.queues;
+        playback
+            .channel_hosts
+            .retain(|_, user_id| queues.iter().any(|queue| queue.user_id == *user_id));
+
+        let queue_summaries: Vec<_> = self
+            .queues
+            .iter()
+            .filter_map(|queue| {
+                Some(QueueSummary {
+                    user_id: queue.user_id,
+                    oldest_request: *queue.requests.front()?,
+                })
+            })
+            .collect();
+
+        // Only users in the highest priority tier that's in the channel get a turn.
+        let user_priorities: HashMap<_, _> = queue_summaries
+            .iter()
+            .filter(|queue| playback.is_user_listening(delegate, queue.user_id, channel_id))
+            .map(|queue| {
+                (
+                    queue.user_id,
+                    self.get_user_priority(delegate, queue.user_id),
+                )
+            })
+            .collect();
+        let top_priority = user_priorities.values().copied().max()?;
+        let next_user_id = self.scheduling_strategy.scheduler().next_user(
+            &queue_summaries,
+            last_user_id,
+            &|user_id| user_priorities.get(&user_id) == Some(&top_priority),
+        )?;
 
         let next_queue = self.get_user_queue_mut(next_user_id)?;
         let next_entry = next_queue.entries.pop_front()?;
+        next_queue.requests.pop_front();
 
         // Update channel state to indicate it's playing
-        self.create_channel(channel_id).playing = ChannelPlayingState::Playing {
-            playing_user_id: next_queue.user_id,
-            skip_votes: HashSet::new(),
-            stop_votes: HashSet::new(),
-        };
+        playback.set_channel_playing(channel_id, next_queue.user_id);
+        self.user_queue_changed(next_user_id);
 
         // Remove any empty queues and channels
         self.queues.retain(|queue| !queue.entries.is_empty());
-        self.channels
+        playback
+            .channels
             .retain(|_, channel| channel.playing.is_playing());
 
         Some(next_entry)
     }
 
+    /// Up to `limit` entries that will play next in the channel, in order, if nobody joins, leaves
+    /// or changes their queue in the meantime.
+    pub fn upcoming_channel_entries<Delegate: AppModelDelegate>(
+        &self,
+        playback: &PlaybackState,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+        limit: usize,
+    ) -> Vec<&QueueEntry> {
+        self.upcoming_channel_user_entries(playback, delegate, channel_id, limit)
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect()
+    }
+
+    /// The entry that will play next in the channel, without taking it out of its queue. Like
+    /// `upcoming_channel_entries` this can change before it plays.
+    pub fn peek_next_channel_entry<Delegate: AppModelDelegate>(
+        &self,
+        playback: &PlaybackState,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+    ) -> Option<&QueueEntry> {
+        self.upcoming_channel_entries(playback, delegate, channel_id, 1)
+            .into_iter()
+            .next()
+    }
+
+    /// Where the first of the user's entries that `is_entry` matches is in the channel's upcoming
+    /// entries, with 0 being next, looking at most `limit` entries ahead.
+    pub fn entry_position<Delegate: AppModelDelegate>(
+        &self,
+        playback: &PlaybackState,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+        user_id: UserId,
+        is_entry: impl Fn(&QueueEntry) -> bool,
+        limit: usize,
+    ) -> Option<usize> {
+        self.upcoming_channel_user_entries(playback, delegate, channel_id, limit)
+            .into_iter()
+            .position(|(entry_user_id, entry)| entry_user_id == user_id && is_entry(entry))
+    }
+
+    /// How many entries are waiting to play in the channel, across everyone listening there.
+    pub fn queue_len<Delegate: AppModelDelegate>(
+        &self,
+        playback: &PlaybackState,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+    ) -> usize {
+        self.queues
+            .iter()
+            .filter(|queue| playback.is_user_listening(delegate, queue.user_id, channel_id))
+            .map(|queue| queue.entries.len())
+            .sum()
+    }
+
+    /// How many entries will play in the channel before the user's next one, looking at most
+    /// `limit` entries ahead. Like `upcoming_channel_entries` this is only an estimate.
+    pub fn estimate_user_position<Delegate: AppModelDelegate>(
+        &self,
+        playback: &PlaybackState,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+        user_id: UserId,
+        limit: usize,
+    ) -> Option<usize> {
+        self.upcoming_entries_before(playback, delegate, channel_id, user_id, 0, limit)
+            .map(|entries| entries.len())
+    }
+
+    /// The entries that will play in the channel before the user's entry at `entry_index` in
+    /// their queue, looking at most `limit` entries ahead. Useful for estimating how long the
+    /// entry will take to come up.
+    pub fn upcoming_entries_before<Delegate: AppModelDelegate>(
+        &self,
+        playback: &PlaybackState,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+        user_id: UserId,
+        entry_index: usize,
+        limit: usize,
+    ) -> Option<Vec<&QueueEntry>> {
+        let upcoming_entries =
+            self.upcoming_channel_user_entries(playback, delegate, channel_id, limit);
+        let index = upcoming_entries
+            .iter()
+            .enumerate()
+            .filter(|(_, (entry_user_id, _))| *entry_user_id == user_id)
+            .nth(entry_index)
+            .map(|(index, _)| index)?;
+        Some(
+            upcoming_entries[..index]
+                .iter()
+                .map(|(_, entry)| *entry)
+                .collect(),
+        )
+    }
+
+    fn upcoming_channel_user_entries<Delegate: AppModelDelegate>(
+        &self,
+        playback: &PlaybackState,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+        limit: usize,
+    ) -> Vec<(UserId, &QueueEntry)> {
+        self.upcoming_channel_positions(playback, delegate, channel_id, limit)
+            .into_iter()
+            .map(|(queue_index, entry_index)| {
+                let queue = &self.queues[queue_index];
+                (queue.user_id, &queue.entries[entry_index])
+            })
+            .collect()
+    }
+
+    /// The queue and entry index of each upcoming entry in the channel.
+    fn upcoming_channel_positions<Delegate: AppModelDelegate>(
+        &self,
+        playback: &PlaybackState,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+        limit: usize,
+    ) -> Vec<(usize, usize)> {
+        let user_priorities: HashMap<_, _> = self
+            .queues
+            .iter()
+            .filter(|queue| playback.is_user_listening(delegate, queue.user_id, channel_id))
+            .map(|queue| {
+                (
+                    queue.user_id,
+                    self.get_user_priority(delegate, queue.user_id),
+                )
+            })
+            .collect();
+        let mut last_user_id = match playback.get_channel_playing_state(channel_id) {
+            Some(ChannelPlayingState::Playing {
+                playing_user_id, ..
+            }) => Some(*playing_user_id),
+            _ => None,
+        };
+
+        // Walk through the queues the same way next_channel_entry_finished would, keeping track
+        // of how far into each queue we've got instead of popping entries.
+        let mut queue_positions = vec![0; self.queues.len()];
+        let mut positions = Vec::new();
+        while positions.len() < limit {
+            let queue_summaries: Vec<_> = self
+                .queues
+                .iter()
+                .zip(&queue_positions)
+                .filter_map(|(queue, &position)| {
+                    Some(QueueSummary {
+                        user_id: queue.user_id,
+                        oldest_request: *queue.requests.get(position)?,
+                    })
+                })
+                .collect();
+
+            let top_priority = match queue_summaries
+                .iter()
+                .filter_map(|queue| user_priorities.get(&queue.user_id))
+                .max()
+            {
+                Some(priority) => *priority,
+                None => break,
+            };
+            let next_user_id = match self.scheduling_strategy.scheduler().next_user(
+                &queue_summaries,
+                last_user_id,
+                &|user_id| user_priorities.get(&user_id) == Some(&top_priority),
+            ) {
+                Some(user_id) => user_id,
+                None => break,
+            };
+
+            let queue_index = match self
+                .queues
+                .iter()
+                .position(|queue| queue.user_id == next_user_id)
+            {
+                Some(index) => index,
+                None => break,
+            };
+            let entry_index = queue_positions[queue_index];
+            if entry_index >= self.queues[queue_index].entries.len() {
+                break;
+            }
+            positions.push((queue_index, entry_index));
+            queue_positions[queue_index] += 1;
+            last_user_id = Some(next_user_id);
+        }
+        positions
+    }
+
+    /// Removes the upcoming entries in the channel from `from_index` to `to_index` inclusive, the
+    /// same entries `upcoming_channel_entries` lists there. Returns the number of entries that
+    /// were removed.
+    pub fn remove_channel_entries<Delegate: AppModelDelegate>(
+        &mut self,
+        playback: &PlaybackState,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+        from_index: usize,
+        to_index: usize,
+    ) -> usize {
+        let positions =
+            self.upcoming_channel_positions(playback, delegate, channel_id, to_index + 1);
+        let range_positions = positions.into_iter().skip(from_index).collect();
+        self.remove_positions(range_positions)
+    }
+
+    /// Removes entries by their queue and entry index. The user loses their last turn for each
+    /// one, like with `remove_user_entry`. Their earlier changes can't be undone afterwards, since
+    /// someone else has changed their queue.
+    fn remove_positions(&mut self, mut positions: Vec<(usize, usize)>) -> usize {
+        // Removing from the back of each queue first keeps the other indices valid.
+        positions.sort_unstable_by(|a, b| b.cmp(a));
+        let mut changed_user_ids = HashSet::new();
+        for (queue_index, entry_index) in &positions {
+            let queue = &mut self.queues[*queue_index];
+            queue.entries.remove(*entry_index);
+            queue.requests.pop_back();
+            changed_user_ids.insert(queue.user_id);
+        }
+        if positions.is_empty() {
+            return 0;
+        }
+
+        self.queue_version += 1;
+        for queue in &mut self.queues {
+            if changed_user_ids.contains(&queue.user_id) {
+                queue.version = self.queue_version;
+            }
+        }
+        for user_id in changed_user_ids {
+            self.forget_user_changes(user_id);
+        }
+        self.queues.retain(|queue| !queue.entries.is_empty());
+        positions.len()
+    }
+
     pub fn next_channel_entry<Delegate: AppModelDelegate>(
         &mut self,
+        playback: &mut PlaybackState,
         delegate: &Delegate,
         channel_id: ChannelId,
     ) -> NextEntry<QueueEntry> {
-        match self.get_channel_playing_state(channel_id) {
+        match playback.get_channel_playing_state(channel_id) {
             Some(ChannelPlayingState::Playing { .. }) => NextEntry::AlreadyPlaying,
-            _ => match self.next_channel_entry_finished(delegate, channel_id) {
+            _ => match self.next_channel_entry_finished(playback, delegate, channel_id) {
                 Some(entry) => NextEntry::Entry(entry),
                 None => NextEntry::NoneAvailable,
             },
         }
     }
 
-    pub fn vote_for_skip<Delegate: AppModelDelegate>(
+    /// Drops the queues of users who were listening in the channel, but have been out of every
+    /// channel for longer than the grace period. Users who queue songs before joining a channel
+    /// keep them.
+    fn drop_absent_queues<Delegate: AppModelDelegate>(
         &mut self,
+        playback: &mut PlaybackState,
         delegate: &Delegate,
-        vote_type: VoteType,
         channel_id: ChannelId,
-        user_id: UserId,
-    ) -> VoteStatus {
-        let votes_required = match vote_type {
-            VoteType::Skip => self.config.skip_votes_required,
-            VoteType::Stop => self.config.stop_votes_required,
+    ) {
+        let now = Instant::now();
+        let listening_user_ids: Vec<_> = self
+            .queues
+            .iter()
+            .map(|queue| queue.user_id)
+            .filter(|user_id| playback.is_user_listening(delegate, *user_id, channel_id))
+            .collect();
+        let queues = &self.queues;
+        let channel_hosts = &playback.channel_hosts;
+        let listeners = match playback.channels.get_mut(&channel_id) {
+            Some(channel) => &mut channel.listeners,
+            None => return,
         };
-        match self.get_channel_playing_state_mut(channel_id) {
-            Some(ChannelPlayingState::Playing {
-                playing_user_id,
-                skip_votes,
-                stop_votes,
-                ..
-            }) => {
-                let votes = match vote_type {
-                    VoteType::Skip => skip_votes,
-                    VoteType::Stop => stop_votes,
-                };
-
-                // We can skip immediately if this was the user who's currently playing
-                if user_id == *playing_user_id {
-                    return VoteStatus::Success;
-                }
+        for user_id in listening_user_ids {
+            listeners.insert(user_id, None);
+        }
+        listeners.retain(|user_id, _| queues.iter().any(|queue| queue.user_id == *user_id));
 
-                // We can skip immediately if the user who played this entry is not in the channel
-                // anymore.
-                if !delegate.is_user_in_voice_channel(*playing_user_id, channel_id) {
-                    return VoteStatus::Success;
-                }
+        let mut absent_user_ids = HashSet::new();
+        for (user_id, absent_since) in listeners.iter_mut() {
+            // Moving to another channel isn't leaving.
+            if delegate.is_user_in_any_voice_channel(*user_id)
+                || channel_hosts.values().any(|host_id| host_id == user_id)
+            {
+                *absent_since = None;
+                continue;
+            }
+            let absent_since = *absent_since.get_or_insert(now);
+            if now - absent_since >= ABSENT_GRACE_PERIOD {
+                absent_user_ids.insert(*user_id);
+            }
+        }
+        if absent_user_ids.is_empty() {
+            return;
+        }
+        listeners.retain(|user_id, _| !absent_user_ids.contains(user_id));
 
-                // Prevent voting if this user has already voted
-                if votes.contains(&user_id) {
-                    return VoteStatus::AlreadyVoted;
-                }
+        let dropped_entries = &mut self.dropped_entries;
+        self.queues.retain(|queue| {
+            if !absent_user_ids.contains(&queue.user_id) {
+                return true;
+            }
 
-                // We can succeed immediately if we will have the required number of votes
-                if votes.len() + 1 >= votes_required {
-                    return VoteStatus::Success;
-                }
+            dropped_entries.push(DroppedEntries {
+                user_id: queue.user_id,
+                count: queue.entries.len(),
+            });
+            false
+        });
+        self.queue_version += 1;
+        // Undoing would bring the dropped queues back.
+        for user_id in absent_user_ids {
+            self.forget_user_changes(user_id);
+        }
+    }
 
-                // Add the vote and indicate more votes are needed
-                votes.insert(user_id);
-                VoteStatus::NeedsMoreVotes(votes_required - votes.len())
-            }
-            _ => VoteStatus::NothingPlaying,
+    fn get_user_priority<Delegate: AppModelDelegate>(
+        &self,
+        delegate: &Delegate,
+        user_id: UserId,
+    ) -> u32 {
+        if self.config.role_priorities.is_empty() {
+            return 0;
         }
+
+        delegate
+            .user_roles(user_id)
+            .iter()
+            .filter_map(|role_id| self.config.role_priorities.get(role_id))
+            .copied()
+            .max()
+            .unwrap_or(0)
     }
 
     fn get_user_queue_mut(&mut self, user_id: UserId) -> Option<&mut Queue<QueueEntry>> {
@@ -352,6 +1010,15 @@ impl<QueueEntry> GuildModel<QueueEntry> {
             .find(|queue| queue.user_id == user_id)
     }
 
+    // Moves the queues on to a new version, which the user's queue is now at.
+    fn user_queue_changed(&mut self, user_id: UserId) {
+        self.queue_version += 1;
+        let queue_version = self.queue_version;
+        if let Some(queue) = self.get_user_queue_mut(user_id) {
+            queue.version = queue_version;
+        }
+    }
+
     fn create_user_queue(&mut self, user_id: UserId) -> &mut Queue<QueueEntry> {
         // For some reason we need to get the index then lookup instead of using .find() to work
         // around the borrow checker.
@@ -365,39 +1032,124 @@ impl<QueueEntry> GuildModel<QueueEntry> {
 
         self.queues.push(Queue {
             user_id,
-            entries: VecDeque::new(),
+            entries: EntryList::new(),
+            requests: VecDeque::new(),
+            version: self.queue_version,
         });
         self.queues.last_mut().unwrap()
     }
+}
 
-    fn create_channel(&mut self, channel_id: ChannelId) -> &mut ChannelModel {
-        self.channels.entry(channel_id).or_insert(ChannelModel {
-            playing: ChannelPlayingState::NotPlaying,
-        })
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockChannelStateDelegate;
+
+    const CHANNEL: ChannelId = ChannelId(1);
+    const ALICE: UserId = UserId(1);
+    const BOB: UserId = UserId(2);
+
+    fn config() -> AppModelConfig {
+        AppModelConfig {
+            skip_votes_required: 2,
+            stop_votes_required: 2,
+            vote_expiry: None,
+            undo_window: Duration::from_secs(60),
+            role_priorities: HashMap::new(),
+            max_user_entries: None,
+            max_guild_entries: None,
+        }
     }
 
-    fn get_channel_playing_state(&self, channel_id: ChannelId) -> Option<&ChannelPlayingState> {
-        self.channels
-            .get(&channel_id)
-            .map(|channel| &channel.playing)
+    fn model() -> (PlaybackState, GuildModel<u64>) {
+        (PlaybackState::new(config()), GuildModel::new(config()))
     }
 
-    fn get_channel_playing_state_mut(
-        &mut self,
-        channel_id: ChannelId,
-    ) -> Option<&mut ChannelPlayingState> {
-        self.channels
-            .get_mut(&channel_id)
-            .map(|channel| &mut channel.playing)
+    fn user_entries(model: &GuildModel<u64>, user_id: UserId) -> Vec<u64> {
+        model.user_entries(user_id).copied().collect()
     }
 
-    fn get_channel_playing_user(&self, channel_id: ChannelId) -> Option<UserId> {
-        match self.get_channel_playing_state(channel_id) {
-            Some(ChannelPlayingState::Playing {
-                playing_user_id: user_id,
-                ..
-            }) => Some(*user_id),
-            _ => None,
+    #[test]
+    fn drop_absent_keeps_recent_and_early_queues() {
+        let (mut playback, mut model) = model();
+        let mut voice = MockChannelStateDelegate::new();
+        model.set_drop_absent_entries(true);
+        voice.join(ALICE, CHANNEL);
+        model.push_entries_bulk(ALICE, vec![1, 2, 3]);
+        model.push_entries_bulk(BOB, vec![4]);
+        assert!(matches!(
+            model.next_channel_entry(&mut playback, &voice, CHANNEL),
+            NextEntry::Entry(1)
+        ));
+        assert_eq!(
+            model.next_channel_entry_finished(&mut playback, &voice, CHANNEL),
+            Some(2)
+        );
+
+        // Alice only just left, and Bob hasn't joined a channel yet.
+        voice.leave(ALICE);
+        assert_eq!(
+            model.next_channel_entry_finished(&mut playback, &voice, CHANNEL),
+            None
+        );
+        assert!(model.take_dropped_entries().is_empty());
+        assert_eq!(user_entries(&model, ALICE), vec![3]);
+        assert_eq!(user_entries(&model, BOB), vec![4]);
+    }
+
+    #[test]
+    fn drop_absent_forgets_dropped_changes() {
+        let (mut playback, mut model) = model();
+        let mut voice = MockChannelStateDelegate::new();
+        model.set_drop_absent_entries(true);
+        voice.join(ALICE, CHANNEL);
+        model.push_entries_bulk(ALICE, vec![1, 2, 3]);
+        model.next_channel_entry(&mut playback, &voice, CHANNEL);
+        assert_eq!(
+            model.next_channel_entry_finished(&mut playback, &voice, CHANNEL),
+            Some(2)
+        );
+        assert_eq!(model.remove_user_entry(ALICE, 0), Some(3));
+        model.push_entries_bulk(ALICE, vec![4]);
+
+        voice.leave(ALICE);
+        assert_eq!(
+            model.next_channel_entry_finished(&mut playback, &voice, CHANNEL),
+            None
+        );
+        // Pretend Alice left long enough ago for her queue to be dropped.
+        let listeners = &mut playback.channels.get_mut(&CHANNEL).unwrap().listeners;
+        for absent_since in listeners.values_mut() {
+            *absent_since = Some(Instant::now() - ABSENT_GRACE_PERIOD);
         }
+        assert_eq!(
+            model.next_channel_entry_finished(&mut playback, &voice, CHANNEL),
+            None
+        );
+
+        let dropped_entries = model.take_dropped_entries();
+        assert_eq!(dropped_entries.len(), 1);
+        assert_eq!(dropped_entries[0].user_id, ALICE);
+        assert_eq!(dropped_entries[0].count, 1);
+        assert_eq!(model.undo_user_change(ALICE), None);
+        assert!(user_entries(&model, ALICE).is_empty());
+    }
+
+    #[test]
+    fn user_versions_ignore_other_queues() {
+        let (playback, mut model) = model();
+        model.push_entries_bulk(ALICE, vec![1, 2]);
+        let alice_version = model.snapshot(&playback).user_queue_version(ALICE);
+
+        model.push_entries_bulk(BOB, vec![3]);
+        let move_res = model.change_if_user_version(ALICE, alice_version, |model| {
+            model.move_user_entry(ALICE, 1, 0)
+        });
+        assert_eq!(move_res, Ok(true));
+
+        let move_res = model.change_if_user_version(ALICE, alice_version, |model| {
+            model.move_user_entry(ALICE, 1, 0)
+        });
+        assert!(move_res.is_err());
     }
 }
@@ -1,9 +1,20 @@
 mod app_model;
 mod app_model_delegate;
 mod config;
+mod entry_list;
+mod features;
 mod guild_model;
+mod playback_state;
+mod scheduler;
+#[cfg(any(test, feature = "testing"))]
+mod testing;
 
 pub use self::app_model::*;
 pub use self::app_model_delegate::*;
 pub use self::config::*;
+pub use self::features::*;
 pub use self::guild_model::*;
+pub use self::playback_state::*;
+pub use self::scheduler::*;
+#[cfg(any(test, feature = "testing"))]
+pub use self::testing::*;
@@ -0,0 +1,436 @@
+use crate::{AppModelConfig, AppModelDelegate};
+use serenity::model::prelude::*;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+pub enum VoteType {
+    Skip,
+    Stop,
+}
+
+pub enum VoteStatus {
+    Success,
+    AlreadyVoted,
+    NeedsMoreVotes(usize),
+    NothingPlaying,
+}
+
+/// The people who have voted to skip or stop what's playing in a channel.
+pub struct VoteInfo {
+    pub skip_voters: Vec<UserId>,
+    pub skip_votes_needed: usize,
+    pub stop_voters: Vec<UserId>,
+    pub stop_votes_needed: usize,
+}
+
+/// A listening party in a voice channel. Until it ends, its host can skip and stop songs there
+/// without waiting for votes.
+#[derive(Debug, Clone, Copy)]
+pub struct Party {
+    pub host_user_id: UserId,
+    pub started_at: Instant,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChannelSnapshot {
+    pub channel_id: ChannelId,
+    /// Whose song is playing, if anything is.
+    pub playing_user_id: Option<UserId>,
+    pub is_stopped: bool,
+    pub skip_votes: usize,
+    pub stop_votes: usize,
+}
+
+#[derive(Clone, Copy)]
+pub struct GuildActionMessage {
+    pub channel_id: ChannelId,
+    pub message_id: MessageId,
+}
+
+pub(crate) enum ChannelPlayingState {
+    NotPlaying,
+    Stopped,
+    Playing {
+        playing_user_id: UserId,
+        // When each user voted.
+        skip_votes: HashMap<UserId, Instant>,
+        stop_votes: HashMap<UserId, Instant>,
+    },
+}
+
+impl ChannelPlayingState {
+    pub(crate) fn is_playing(&self) -> bool {
+        matches!(self, ChannelPlayingState::Playing { .. })
+    }
+}
+
+pub(crate) struct ChannelModel {
+    pub(crate) playing: ChannelPlayingState,
+    // Users with queues who have been seen listening in the channel, and since when they've been
+    // gone if they've left every channel.
+    pub(crate) listeners: HashMap<UserId, Option<Instant>>,
+}
+
+/// What's playing in a guild's voice channels, and where messages about it go. This has its own
+/// lock apart from the guild's queues, so queueing songs doesn't have to wait while a command is
+/// busy starting or stopping playback.
+pub struct PlaybackState {
+    config: AppModelConfig,
+    message_channel: Option<ChannelId>,
+    session_thread: Option<ChannelId>,
+    last_action_message: Option<GuildActionMessage>,
+    // Messages posted in the last action message's channel after it.
+    messages_since_action: usize,
+    pub(crate) channels: HashMap<ChannelId, ChannelModel>,
+    // Users whose queues play in a channel without them being in it, keyed by channel.
+    pub(crate) channel_hosts: HashMap<ChannelId, UserId>,
+    parties: HashMap<ChannelId, Party>,
+}
+
+impl PlaybackState {
+    pub fn new(config: AppModelConfig) -> Self {
+        PlaybackState {
+            config,
+            message_channel: None,
+            session_thread: None,
+            last_action_message: None,
+            messages_since_action: 0,
+            channels: HashMap::new(),
+            channel_hosts: HashMap::new(),
+            parties: HashMap::new(),
+        }
+    }
+
+    pub fn message_channel(&self) -> Option<ChannelId> {
+        self.message_channel
+    }
+
+    pub fn set_message_channel(&mut self, message_channel: Option<ChannelId>) {
+        self.message_channel = message_channel;
+    }
+
+    /// A thread that action messages are sent to instead of the message channel while songs are
+    /// playing.
+    pub fn session_thread(&self) -> Option<ChannelId> {
+        self.session_thread
+    }
+
+    pub fn set_session_thread(&mut self, session_thread: Option<ChannelId>) {
+        self.session_thread = session_thread;
+    }
+
+    pub fn last_action_message(&self) -> Option<GuildActionMessage> {
+        self.last_action_message
+    }
+
+    pub fn set_last_action_message(&mut self, status_message: Option<GuildActionMessage>) {
+        self.last_action_message = status_message;
+        self.messages_since_action = 0;
+    }
+
+    /// Counts a message posted in the same channel as the last action message, returning how
+    /// many have been posted since it.
+    pub fn count_message_after_action(&mut self) -> usize {
+        self.messages_since_action += 1;
+        self.messages_since_action
+    }
+
+    /// How many channels have something playing in them.
+    pub fn playing_channel_count(&self) -> usize {
+        self.channels
+            .values()
+            .filter(|channel| channel.playing.is_playing())
+            .count()
+    }
+
+    /// Whether anything is playing in any of the guild's channels.
+    pub fn is_playing(&self) -> bool {
+        self.channels
+            .values()
+            .any(|channel| channel.playing.is_playing())
+    }
+
+    pub fn is_channel_stopped(&self, channel_id: ChannelId) -> bool {
+        matches!(
+            self.get_channel_playing_state(channel_id),
+            Some(ChannelPlayingState::Stopped)
+        )
+    }
+
+    pub fn set_channel_stopped(&mut self, channel_id: ChannelId) {
+        self.create_channel(channel_id).playing = ChannelPlayingState::Stopped;
+        self.channel_hosts.remove(&channel_id);
+    }
+
+    /// Lets the user's queue play in the channel without them being in it, for playback that was
+    /// started on their behalf, like a schedule. This lasts until their queue runs out or the
+    /// channel is stopped.
+    pub fn set_channel_host(&mut self, channel_id: ChannelId, user_id: UserId) {
+        self.channel_hosts.insert(channel_id, user_id);
+    }
+
+    pub fn party(&self, channel_id: ChannelId) -> Option<Party> {
+        self.parties.get(&channel_id).copied()
+    }
+
+    /// Returns the party that's already going if there's one in the channel.
+    pub fn start_party(
+        &mut self,
+        channel_id: ChannelId,
+        host_user_id: UserId,
+    ) -> Result<(), Party> {
+        if let Some(party) = self.parties.get(&channel_id) {
+            return Err(*party);
+        }
+        self.parties.insert(
+            channel_id,
+            Party {
+                host_user_id,
+                started_at: Instant::now(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn end_party(&mut self, channel_id: ChannelId) -> Option<Party> {
+        self.parties.remove(&channel_id)
+    }
+
+    /// Marks the channel as playing a song the user queued, for when it was started without
+    /// going through the queue, like when picking up playback from another process.
+    pub fn set_channel_playing(&mut self, channel_id: ChannelId, playing_user_id: UserId) {
+        self.create_channel(channel_id).playing = ChannelPlayingState::Playing {
+            playing_user_id,
+            skip_votes: HashMap::new(),
+            stop_votes: HashMap::new(),
+        };
+    }
+
+    /// Carries a channel's playing state over to another channel, for when the speaker playing in
+    /// it has moved. Votes are left behind, since the people in the new channel might not be the
+    /// same. Returns `false` if something is already playing in the other channel.
+    pub fn move_channel(&mut self, from_channel_id: ChannelId, to_channel_id: ChannelId) -> bool {
+        if matches!(
+            self.get_channel_playing_state(to_channel_id),
+            Some(ChannelPlayingState::Playing { .. })
+        ) {
+            return false;
+        }
+
+        let mut channel = self
+            .channels
+            .remove(&from_channel_id)
+            .unwrap_or(ChannelModel {
+                playing: ChannelPlayingState::NotPlaying,
+                listeners: HashMap::new(),
+            });
+        if let ChannelPlayingState::Playing {
+            skip_votes,
+            stop_votes,
+            ..
+        } = &mut channel.playing
+        {
+            skip_votes.clear();
+            stop_votes.clear();
+        }
+        self.channels.insert(to_channel_id, channel);
+        true
+    }
+
+    pub fn vote_for_skip<Delegate: AppModelDelegate>(
+        &mut self,
+        delegate: &Delegate,
+        vote_type: VoteType,
+        channel_id: ChannelId,
+        user_id: UserId,
+    ) -> VoteStatus {
+        let votes_required = match vote_type {
+            VoteType::Skip => self.config.skip_votes_required,
+            VoteType::Stop => self.config.stop_votes_required,
+        };
+        let vote_expiry = self.config.vote_expiry;
+        let is_party_host = self
+            .parties
+            .get(&channel_id)
+            .is_some_and(|party| party.host_user_id == user_id);
+        match self.get_channel_playing_state_mut(channel_id) {
+            Some(ChannelPlayingState::Playing {
+                playing_user_id,
+                skip_votes,
+                stop_votes,
+                ..
+            }) => {
+                let votes = match vote_type {
+                    VoteType::Skip => skip_votes,
+                    VoteType::Stop => stop_votes,
+                };
+
+                // We can skip immediately if this was the user who's currently playing, or the
+                // host of a party in the channel
+                if user_id == *playing_user_id || is_party_host {
+                    return VoteStatus::Success;
+                }
+
+                // We can skip immediately if the user who played this entry is not in the channel
+                // anymore.
+                if !delegate.is_user_in_voice_channel(*playing_user_id, channel_id) {
+                    return VoteStatus::Success;
+                }
+
+                // Votes from people who have since left the channel, or that were made too long
+                // ago, no longer count.
+                let now = Instant::now();
+                votes.retain(|voter_id, voted_at| {
+                    is_vote_counted(delegate, channel_id, vote_expiry, now, *voter_id, *voted_at)
+                });
+
+                // Prevent voting if this user has already voted
+                if votes.contains_key(&user_id) {
+                    return VoteStatus::AlreadyVoted;
+                }
+
+                // We can succeed immediately if we will have the required number of votes
+                if votes.len() + 1 >= votes_required {
+                    return VoteStatus::Success;
+                }
+
+                // Add the vote and indicate more votes are needed
+                votes.insert(user_id, now);
+                VoteStatus::NeedsMoreVotes(votes_required - votes.len())
+            }
+            _ => VoteStatus::NothingPlaying,
+        }
+    }
+
+    /// Who has voted in the channel, or `None` if nothing is playing there.
+    pub fn vote_info<Delegate: AppModelDelegate>(
+        &self,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+    ) -> Option<VoteInfo> {
+        let (skip_votes, stop_votes) = match self.get_channel_playing_state(channel_id) {
+            Some(ChannelPlayingState::Playing {
+                skip_votes,
+                stop_votes,
+                ..
+            }) => (skip_votes, stop_votes),
+            _ => return None,
+        };
+
+        let now = Instant::now();
+        let counted_voters = |votes: &HashMap<UserId, Instant>| -> Vec<UserId> {
+            let mut voters: Vec<_> = votes
+                .iter()
+                .filter(|(voter_id, voted_at)| {
+                    is_vote_counted(
+                        delegate,
+                        channel_id,
+                        self.config.vote_expiry,
+                        now,
+                        **voter_id,
+                        **voted_at,
+                    )
+                })
+                .map(|(voter_id, _)| *voter_id)
+                .collect();
+            voters.sort();
+            voters
+        };
+        let skip_voters = counted_voters(skip_votes);
+        let stop_voters = counted_voters(stop_votes);
+        Some(VoteInfo {
+            skip_votes_needed: self
+                .config
+                .skip_votes_required
+                .saturating_sub(skip_voters.len()),
+            stop_votes_needed: self
+                .config
+                .stop_votes_required
+                .saturating_sub(stop_voters.len()),
+            skip_voters,
+            stop_voters,
+        })
+    }
+
+    pub(crate) fn channel_snapshots(&self) -> Vec<ChannelSnapshot> {
+        self.channels
+            .iter()
+            .map(|(channel_id, channel)| {
+                let (playing_user_id, skip_votes, stop_votes) = match &channel.playing {
+                    ChannelPlayingState::Playing {
+                        playing_user_id,
+                        skip_votes,
+                        stop_votes,
+                    } => (Some(*playing_user_id), skip_votes.len(), stop_votes.len()),
+                    _ => (None, 0, 0),
+                };
+                ChannelSnapshot {
+                    channel_id: *channel_id,
+                    playing_user_id,
+                    is_stopped: matches!(channel.playing, ChannelPlayingState::Stopped),
+                    skip_votes,
+                    stop_votes,
+                }
+            })
+            .collect()
+    }
+
+    // Whether the user's queue can play in the channel, because they're in it or host it.
+    pub(crate) fn is_user_listening<Delegate: AppModelDelegate>(
+        &self,
+        delegate: &Delegate,
+        user_id: UserId,
+        channel_id: ChannelId,
+    ) -> bool {
+        delegate.is_user_in_voice_channel(user_id, channel_id)
+            || self.channel_hosts.get(&channel_id) == Some(&user_id)
+    }
+
+    pub(crate) fn create_channel(&mut self, channel_id: ChannelId) -> &mut ChannelModel {
+        self.channels.entry(channel_id).or_insert(ChannelModel {
+            playing: ChannelPlayingState::NotPlaying,
+            listeners: HashMap::new(),
+        })
+    }
+
+    pub(crate) fn get_channel_playing_state(
+        &self,
+        channel_id: ChannelId,
+    ) -> Option<&ChannelPlayingState> {
+        self.channels
+            .get(&channel_id)
+            .map(|channel| &channel.playing)
+    }
+
+    fn get_channel_playing_state_mut(
+        &mut self,
+        channel_id: ChannelId,
+    ) -> Option<&mut ChannelPlayingState> {
+        self.channels
+            .get_mut(&channel_id)
+            .map(|channel| &mut channel.playing)
+    }
+
+    pub(crate) fn get_channel_playing_user(&self, channel_id: ChannelId) -> Option<UserId> {
+        match self.get_channel_playing_state(channel_id) {
+            Some(ChannelPlayingState::Playing {
+                playing_user_id: user_id,
+                ..
+            }) => Some(*user_id),
+            _ => None,
+        }
+    }
+}
+
+/// Votes from people who have left the channel, or that have expired, aren't counted.
+fn is_vote_counted<Delegate: AppModelDelegate>(
+    delegate: &Delegate,
+    channel_id: ChannelId,
+    vote_expiry: Option<Duration>,
+    now: Instant,
+    voter_id: UserId,
+    voted_at: Instant,
+) -> bool {
+    delegate.is_user_in_voice_channel(voter_id, channel_id)
+        && vote_expiry.map_or(true, |expiry| now - voted_at < expiry)
+}
@@ -0,0 +1,78 @@
+use serenity::model::prelude::*;
+
+/// A user's queue as seen by a scheduler.
+pub struct QueueSummary {
+    pub user_id: UserId,
+    /// When the oldest entry in the queue was requested, relative to other requests in the guild.
+    pub oldest_request: u64,
+}
+
+pub trait Scheduler {
+    /// Picks the user whose queue the next entry in a channel is taken from. `queues` are in the
+    /// order users started queueing, `last_user_id` is the user whose entry was playing before, and
+    /// only users that are `eligible` (in the channel) can be picked.
+    fn next_user(
+        &self,
+        queues: &[QueueSummary],
+        last_user_id: Option<UserId>,
+        eligible: &dyn Fn(UserId) -> bool,
+    ) -> Option<UserId>;
+}
+
+/// Takes turns between users, so everyone gets a song in before anyone gets a second one.
+pub struct RoundRobinScheduler;
+
+impl Scheduler for RoundRobinScheduler {
+    fn next_user(
+        &self,
+        queues: &[QueueSummary],
+        last_user_id: Option<UserId>,
+        eligible: &dyn Fn(UserId) -> bool,
+    ) -> Option<UserId> {
+        let last_playing_index = last_user_id
+            .and_then(|user_id| queues.iter().position(|queue| queue.user_id == user_id));
+
+        // Search queues from after the last active one, back around to it again
+        let start_index = last_playing_index.map_or(0, |index| index + 1);
+        queues
+            .iter()
+            .skip(start_index)
+            .chain(queues.iter().take(start_index))
+            .find(|queue| eligible(queue.user_id))
+            .map(|queue| queue.user_id)
+    }
+}
+
+/// Plays entries in the order they were requested, regardless of who requested them.
+pub struct FifoScheduler;
+
+impl Scheduler for FifoScheduler {
+    fn next_user(
+        &self,
+        queues: &[QueueSummary],
+        _last_user_id: Option<UserId>,
+        eligible: &dyn Fn(UserId) -> bool,
+    ) -> Option<UserId> {
+        queues
+            .iter()
+            .filter(|queue| eligible(queue.user_id))
+            .min_by_key(|queue| queue.oldest_request)
+            .map(|queue| queue.user_id)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SchedulingStrategy {
+    #[default]
+    RoundRobin,
+    Fifo,
+}
+
+impl SchedulingStrategy {
+    pub fn scheduler(self) -> &'static dyn Scheduler {
+        match self {
+            SchedulingStrategy::RoundRobin => &RoundRobinScheduler,
+            SchedulingStrategy::Fifo => &FifoScheduler,
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{AppModelDelegate, GuildModel, NextEntry, PlaybackState, VoteStatus, VoteType};
+use serenity::model::prelude::*;
+use std::collections::HashMap;
+
+/// Who's in which voice channel and what roles they have, kept in memory and changed as users
+/// join and leave, so the model can be used without a Discord cache.
+#[derive(Debug, Clone, Default)]
+pub struct MockChannelStateDelegate {
+    voice_channels: HashMap<UserId, ChannelId>,
+    user_roles: HashMap<UserId, Vec<RoleId>>,
+}
+
+impl MockChannelStateDelegate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the user into the channel, out of any they were in before.
+    pub fn join(&mut self, user_id: UserId, channel_id: ChannelId) {
+        self.voice_channels.insert(user_id, channel_id);
+    }
+
+    pub fn leave(&mut self, user_id: UserId) {
+        self.voice_channels.remove(&user_id);
+    }
+
+    pub fn user_channel(&self, user_id: UserId) -> Option<ChannelId> {
+        self.voice_channels.get(&user_id).copied()
+    }
+
+    pub fn set_roles(&mut self, user_id: UserId, roles: Vec<RoleId>) {
+        self.user_roles.insert(user_id, roles);
+    }
+
+    /// Everyone in the channel, in no particular order.
+    pub fn channel_users(&self, channel_id: ChannelId) -> Vec<UserId> {
+        self.voice_channels
+            .iter()
+            .filter(|(_, user_channel_id)| **user_channel_id == channel_id)
+            .map(|(user_id, _)| *user_id)
+            .collect()
+    }
+}
+
+impl AppModelDelegate for MockChannelStateDelegate {
+    fn is_user_in_voice_channel(&self, user_id: UserId, channel_id: ChannelId) -> bool {
+        self.voice_channels.get(&user_id) == Some(&channel_id)
+    }
+
+    fn is_user_in_any_voice_channel(&self, user_id: UserId) -> bool {
+        self.voice_channels.contains_key(&user_id)
+    }
+
+    fn user_roles(&self, user_id: UserId) -> Vec<RoleId> {
+        self.user_roles.get(&user_id).cloned().unwrap_or_default()
+    }
+}
+
+/// Plays entries from a guild's queue the way the bot's speakers do, without any audio. Each
+/// channel plays one entry at a time, until it's finished, skipped or stopped.
+#[derive(Debug)]
+pub struct FakeSpeaker<QueueEntry> {
+    playing: HashMap<ChannelId, QueueEntry>,
+    // Oldest first.
+    played: Vec<(ChannelId, QueueEntry)>,
+}
+
+impl<QueueEntry> Default for FakeSpeaker<QueueEntry> {
+    fn default() -> Self {
+        FakeSpeaker {
+            playing: HashMap::new(),
+            played: Vec::new(),
+        }
+    }
+}
+
+impl<QueueEntry: Clone> FakeSpeaker<QueueEntry> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// What's playing in the channel, if anything.
+    pub fn playing(&self, channel_id: ChannelId) -> Option<&QueueEntry> {
+        self.playing.get(&channel_id)
+    }
+
+    /// Every entry that's started playing, and the channel it played in.
+    pub fn played(&self) -> &[(ChannelId, QueueEntry)] {
+        &self.played
+    }
+
+    /// Starts the next entry in the channel, like `/play` does when nothing is playing there.
+    pub fn play<Delegate: AppModelDelegate>(
+        &mut self,
+        playback: &mut PlaybackState,
+        model: &mut GuildModel<QueueEntry>,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+    ) -> NextEntry<QueueEntry> {
+        let next_entry = model.next_channel_entry(playback, delegate, channel_id);
+        if let NextEntry::Entry(entry) = &next_entry {
+            self.start(channel_id, entry.clone());
+        }
+        next_entry
+    }
+
+    /// Ends what's playing in the channel and starts the next entry, if there is one. Returns
+    /// the new entry.
+    pub fn finish<Delegate: AppModelDelegate>(
+        &mut self,
+        playback: &mut PlaybackState,
+        model: &mut GuildModel<QueueEntry>,
+        delegate: &Delegate,
+        channel_id: ChannelId,
+    ) -> Option<QueueEntry> {
+        self.end(channel_id);
+        let entry = model.next_channel_entry_finished(playback, delegate, channel_id)?;
+        self.start(channel_id, entry.clone());
+        Some(entry)
+    }
+
+    /// Votes to skip or stop what's playing, and skips or stops it if the vote passes.
+    pub fn vote<Delegate: AppModelDelegate>(
+        &mut self,
+        playback: &mut PlaybackState,
+        model: &mut GuildModel<QueueEntry>,
+        delegate: &Delegate,
+        vote_type: VoteType,
+        channel_id: ChannelId,
+        user_id: UserId,
+    ) -> VoteStatus {
+        let is_skip = matches!(vote_type, VoteType::Skip);
+        let status = playback.vote_for_skip(delegate, vote_type, channel_id, user_id);
+        match status {
+            VoteStatus::Success if is_skip => {
+                self.finish(playback, model, delegate, channel_id);
+            }
+            VoteStatus::Success => self.stop(playback, channel_id),
+            _ => {}
+        }
+        status
+    }
+
+    /// Stops the channel until something is played in it again.
+    pub fn stop(&mut self, playback: &mut PlaybackState, channel_id: ChannelId) {
+        self.end(channel_id);
+        playback.set_channel_stopped(channel_id);
+    }
+
+    /// Starts playing the entry in the channel, in place of anything that was playing there.
+    pub fn start(&mut self, channel_id: ChannelId, entry: QueueEntry) {
+        self.playing.insert(channel_id, entry.clone());
+        self.played.push((channel_id, entry));
+    }
+
+    /// Ends what's playing in the channel without starting anything else. Returns the entry
+    /// that was playing.
+    pub fn end(&mut self, channel_id: ChannelId) -> Option<QueueEntry> {
+        self.playing.remove(&channel_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppModelConfig;
+    use std::time::Duration;
+
+    const CHANNEL: ChannelId = ChannelId(1);
+    const ALICE: UserId = UserId(1);
+    const BOB: UserId = UserId(2);
+    const CAROL: UserId = UserId(3);
+
+    fn config() -> AppModelConfig {
+        AppModelConfig {
+            skip_votes_required: 2,
+            stop_votes_required: 2,
+            vote_expiry: None,
+            undo_window: Duration::from_secs(60),
+            role_priorities: HashMap::new(),
+            max_user_entries: None,
+            max_guild_entries: None,
+        }
+    }
+
+    #[test]
+    fn finish_alternates_users() {
+        let mut playback = PlaybackState::new(config());
+        let mut model = GuildModel::new(config());
+        let mut voice = MockChannelStateDelegate::new();
+        let mut speaker = FakeSpeaker::new();
+        voice.join(ALICE, CHANNEL);
+        voice.join(BOB, CHANNEL);
+        model.push_entries_bulk(ALICE, vec![1, 2]);
+        model.push_entries_bulk(BOB, vec![3]);
+
+        assert!(matches!(
+            speaker.play(&mut playback, &mut model, &voice, CHANNEL),
+            NextEntry::Entry(1)
+        ));
+        while speaker
+            .finish(&mut playback, &mut model, &voice, CHANNEL)
+            .is_some()
+        {}
+        let played: Vec<_> = speaker.played().iter().map(|(_, entry)| *entry).collect();
+        assert_eq!(played, vec![1, 3, 2]);
+        assert_eq!(speaker.playing(CHANNEL), None);
+    }
+
+    #[test]
+    fn vote_skips_once_passed() {
+        let mut playback = PlaybackState::new(config());
+        let mut model = GuildModel::new(config());
+        let mut voice = MockChannelStateDelegate::new();
+        let mut speaker = FakeSpeaker::new();
+        for user_id in [ALICE, BOB, CAROL] {
+            voice.join(user_id, CHANNEL);
+        }
+        model.push_entries_bulk(ALICE, vec![1, 2]);
+        speaker.play(&mut playback, &mut model, &voice, CHANNEL);
+
+        let mut vote = |speaker: &mut FakeSpeaker<u64>, user_id| {
+            speaker.vote(
+                &mut playback,
+                &mut model,
+                &voice,
+                VoteType::Skip,
+                CHANNEL,
+                user_id,
+            )
+        };
+        assert!(matches!(
+            vote(&mut speaker, BOB),
+            VoteStatus::NeedsMoreVotes(1)
+        ));
+        assert_eq!(speaker.playing(CHANNEL), Some(&1));
+        assert!(matches!(vote(&mut speaker, CAROL), VoteStatus::Success));
+        assert_eq!(speaker.playing(CHANNEL), Some(&2));
+    }
+}
@@ -0,0 +1,258 @@
+use mrvn_model::{
+    AppModelConfig, AppModelDelegate, FakeSpeaker, GuildModel, MockChannelStateDelegate,
+    PlaybackState, VoteStatus, VoteType,
+};
+use rand::Rng;
+use serenity::model::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+const CHANNEL_COUNT: u64 = 3;
+// The most songs a user queues at once, like a short playlist.
+const MAX_BATCH_LENGTH: u64 = 5;
+const SKIP_VOTES_REQUIRED: usize = 3;
+const STOP_VOTES_REQUIRED: usize = 4;
+const MAX_USER_ENTRIES: usize = 50;
+const MAX_GUILD_ENTRIES: usize = 300;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Counts {
+    pub commands: u64,
+    pub queued: u64,
+    pub rejected: u64,
+    pub played: u64,
+    pub removed: u64,
+    pub votes: u64,
+}
+
+impl std::ops::AddAssign for Counts {
+    fn add_assign(&mut self, other: Self) {
+        self.commands += other.commands;
+        self.queued += other.queued;
+        self.rejected += other.rejected;
+        self.played += other.played;
+        self.removed += other.removed;
+        self.votes += other.votes;
+    }
+}
+
+/// A guild with its own users and voice channels, which are sent random commands. Entries are
+/// numbered in the order they're queued, so each one can be followed from being queued to being
+/// played or removed.
+pub struct SimGuild {
+    playback: PlaybackState,
+    model: GuildModel<u64>,
+    delegate: MockChannelStateDelegate,
+    speaker: FakeSpeaker<u64>,
+    user_count: u64,
+    next_entry: u64,
+    // Entries that have been queued but not played or removed yet.
+    waiting: HashSet<u64>,
+    // How many of the speaker's played entries have been checked.
+    checked_played: usize,
+    pub counts: Counts,
+}
+
+impl SimGuild {
+    pub fn new(user_count: u64) -> Self {
+        let config = AppModelConfig {
+            skip_votes_required: SKIP_VOTES_REQUIRED,
+            stop_votes_required: STOP_VOTES_REQUIRED,
+            // Votes expiring would depend on how fast the simulation runs.
+            vote_expiry: None,
+            undo_window: Duration::from_secs(60),
+            role_priorities: HashMap::new(),
+            max_user_entries: Some(MAX_USER_ENTRIES),
+            max_guild_entries: Some(MAX_GUILD_ENTRIES),
+        };
+        SimGuild {
+            playback: PlaybackState::new(config.clone()),
+            model: GuildModel::new(config),
+            delegate: MockChannelStateDelegate::new(),
+            speaker: FakeSpeaker::new(),
+            user_count,
+            next_entry: 0,
+            waiting: HashSet::new(),
+            checked_played: 0,
+            counts: Counts::default(),
+        }
+    }
+
+    /// Runs a random command for a random user, then checks nothing has gone wrong. Returns what
+    /// went wrong if something did.
+    pub fn step<R: Rng>(&mut self, rng: &mut R) -> Result<(), String> {
+        let user_id = UserId(rng.gen_range(1..=self.user_count));
+        let channel_id = ChannelId(rng.gen_range(1..=CHANNEL_COUNT));
+        self.counts.commands += 1;
+
+        match rng.gen_range(0..16) {
+            0..=2 => self.delegate.join(user_id, channel_id),
+            3 => self.delegate.leave(user_id),
+            4..=6 => {
+                let count = rng.gen_range(1..=MAX_BATCH_LENGTH);
+                let entries: Vec<_> = (self.next_entry..self.next_entry + count).collect();
+                self.next_entry += count;
+                let result = self
+                    .model
+                    .push_entries_bulk(user_id, entries.iter().copied());
+                self.waiting.extend(&entries[..result.accepted]);
+                self.counts.queued += result.accepted as u64;
+                self.counts.rejected += result.rejected as u64;
+            }
+            7 | 8 => {
+                self.speaker.play(
+                    &mut self.playback,
+                    &mut self.model,
+                    &self.delegate,
+                    channel_id,
+                );
+            }
+            9 | 10 => {
+                if self.speaker.playing(channel_id).is_some() {
+                    self.speaker.finish(
+                        &mut self.playback,
+                        &mut self.model,
+                        &self.delegate,
+                        channel_id,
+                    );
+                }
+            }
+            11 | 12 => {
+                // Only people in the channel can vote, like with the buttons.
+                if self.delegate.is_user_in_voice_channel(user_id, channel_id) {
+                    let vote_type = if rng.gen_bool(0.75) {
+                        VoteType::Skip
+                    } else {
+                        VoteType::Stop
+                    };
+                    let status = self.speaker.vote(
+                        &mut self.playback,
+                        &mut self.model,
+                        &self.delegate,
+                        vote_type,
+                        channel_id,
+                        user_id,
+                    );
+                    if !matches!(status, VoteStatus::NothingPlaying) {
+                        self.counts.votes += 1;
+                    }
+                }
+            }
+            13 => {
+                let count = self.model.user_entries(user_id).count();
+                if count > 0 {
+                    let index = rng.gen_range(0..count);
+                    match self.model.remove_user_entry(user_id, index) {
+                        Some(entry) => self.forget(entry)?,
+                        None => return Err(format!("entry {} of {} wasn't removed", index, count)),
+                    }
+                }
+            }
+            14 => {
+                let entries: Vec<_> = self.model.user_entries(user_id).copied().collect();
+                let count = self.model.clear_user_entries(user_id);
+                if count != entries.len() {
+                    return Err(format!(
+                        "{} entries were cleared out of {}",
+                        count,
+                        entries.len()
+                    ));
+                }
+                for entry in entries {
+                    self.forget(entry)?;
+                }
+            }
+            _ => {
+                self.model.shuffle_user_entries(user_id, rng);
+            }
+        }
+
+        self.check()
+    }
+
+    fn forget(&mut self, entry: u64) -> Result<(), String> {
+        if !self.waiting.remove(&entry) {
+            return Err(format!("entry {} was removed but wasn't waiting", entry));
+        }
+        self.counts.removed += 1;
+        Ok(())
+    }
+
+    fn check(&mut self) -> Result<(), String> {
+        // Every entry plays once at most, and only if it was queued.
+        let new_played = &self.speaker.played()[self.checked_played..];
+        self.checked_played += new_played.len();
+        for (channel_id, entry) in new_played {
+            if !self.waiting.remove(entry) {
+                return Err(format!(
+                    "entry {} played in {} but wasn't waiting",
+                    entry, channel_id
+                ));
+            }
+            self.counts.played += 1;
+        }
+
+        // Entries only leave the queues by being played or removed.
+        let snapshot = self.model.snapshot(&self.playback);
+        let queued: HashSet<_> = snapshot
+            .queues
+            .iter()
+            .flat_map(|queue| queue.entries.iter().copied())
+            .collect();
+        if queued.len() != snapshot.entry_count() {
+            return Err("an entry is queued more than once".to_string());
+        }
+        if queued != self.waiting {
+            let lost = self.waiting.difference(&queued).count();
+            let appeared = queued.difference(&self.waiting).count();
+            return Err(format!(
+                "{} entries were lost from the queues and {} appeared",
+                lost, appeared
+            ));
+        }
+
+        for channel_id in (1..=CHANNEL_COUNT).map(ChannelId) {
+            // Each channel has one thing playing at most, and the model agrees on what.
+            let is_model_playing = snapshot.channels.iter().any(|channel| {
+                channel.channel_id == channel_id && channel.playing_user_id.is_some()
+            });
+            let is_speaker_playing = self.speaker.playing(channel_id).is_some();
+            if is_model_playing != is_speaker_playing {
+                return Err(format!(
+                    "the model says {} is {}playing but the speaker doesn't",
+                    channel_id,
+                    if is_model_playing { "" } else { "not " }
+                ));
+            }
+
+            // A vote that's got enough votes has already skipped or stopped the song.
+            let vote_info = match self.playback.vote_info(&self.delegate, channel_id) {
+                Some(vote_info) => vote_info,
+                None => continue,
+            };
+            let member_count = self.delegate.channel_users(channel_id).len();
+            for (voters, votes_required) in [
+                (&vote_info.skip_voters, SKIP_VOTES_REQUIRED),
+                (&vote_info.stop_voters, STOP_VOTES_REQUIRED),
+            ] {
+                if voters.len() > member_count {
+                    return Err(format!(
+                        "{} votes counted in {} with {} members",
+                        voters.len(),
+                        channel_id,
+                        member_count
+                    ));
+                }
+                if voters.len() >= votes_required {
+                    return Err(format!(
+                        "{} votes counted in {} without the vote passing",
+                        voters.len(),
+                        channel_id
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+mod guild;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::Instant;
+
+const DEFAULT_GUILD_COUNT: u64 = 100;
+const DEFAULT_USER_COUNT: u64 = 20;
+const DEFAULT_STEP_COUNT: u64 = 1_000_000;
+
+/// Sends random commands to lots of guilds at once through the real queue model, with a fake
+/// speaker in place of the voice bots, checking after every command that nothing has gone wrong.
+/// The same seed always sends the same commands, so a failure can be run again.
+///
+/// `mrvn-sim [guilds] [users per guild] [steps] [seed]`
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let arg = |index: usize, name: &str, default: u64| -> u64 {
+        match args.get(index) {
+            Some(arg) => arg.parse().unwrap_or_else(|_| {
+                eprintln!("Unable to parse {} from \"{}\"", name, arg);
+                std::process::exit(2);
+            }),
+            None => default,
+        }
+    };
+    let guild_count = arg(0, "guild count", DEFAULT_GUILD_COUNT).max(1);
+    let user_count = arg(1, "user count", DEFAULT_USER_COUNT).max(1);
+    let step_count = arg(2, "step count", DEFAULT_STEP_COUNT);
+    let seed = arg(3, "seed", 0);
+
+    println!(
+        "Simulating {} guilds with {} users each for {} steps, seed {}",
+        guild_count, user_count, step_count, seed
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut guilds: Vec<_> = (0..guild_count)
+        .map(|_| guild::SimGuild::new(user_count))
+        .collect();
+
+    let start_time = Instant::now();
+    for step in 0..step_count {
+        let guild_index = rng.gen_range(0..guilds.len());
+        if let Err(why) = guilds[guild_index].step(&mut rng) {
+            eprintln!(
+                "Step {} in guild {} broke an invariant: {}",
+                step, guild_index, why
+            );
+            std::process::exit(1);
+        }
+    }
+    let elapsed = start_time.elapsed();
+
+    let mut counts = guild::Counts::default();
+    for guild in &guilds {
+        counts += guild.counts;
+    }
+    println!(
+        "Ran {} commands in {:.2}s, {:.0} per second",
+        counts.commands,
+        elapsed.as_secs_f64(),
+        counts.commands as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    );
+    println!(
+        "Queued {} songs ({} turned away by limits), played {}, removed {}, {} votes",
+        counts.queued, counts.rejected, counts.played, counts.removed, counts.votes
+    );
+}